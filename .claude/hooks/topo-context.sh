@@ -14,7 +14,7 @@ if [ -z "$PROMPT" ] || [ ${#PROMPT} -lt 15 ]; then
 fi
 
 # Run topo quick with fast preset for low latency
-RESULTS=$(topo quick "$PROMPT" --format compact --preset fast --top 10 2>/dev/null) || true
+RESULTS=$(topo quick --format compact --preset fast --top 10 -- "$PROMPT" 2>/dev/null) || true
 
 if [ -n "$RESULTS" ]; then
   jq -n --arg ctx "Topo file suggestions (ranked by relevance):
@@ -0,0 +1,215 @@
+use crate::Cli;
+use anyhow::Result;
+use serde::Serialize;
+use std::path::Path;
+use topo_core::Config;
+
+/// Which optional subsystems topo can use for a repository, so integrations
+/// can adapt instead of failing when one is missing.
+#[derive(Debug, Serialize)]
+pub struct Capabilities {
+    pub version: String,
+    pub capabilities: Vec<Capability>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Capability {
+    pub name: &'static str,
+    /// Whether this build and machine can provide it.
+    pub available: bool,
+    /// Whether queries against the repository use it right now.
+    pub active: bool,
+    pub detail: String,
+    /// What topo does instead when it isn't active.
+    pub fallback: &'static str,
+}
+
+pub fn run(cli: &Cli) -> Result<()> {
+    let capabilities = detect(&cli.repo_root()?)?;
+
+    match cli.effective_format() {
+        crate::OutputFormat::Json | crate::OutputFormat::Jsonl => {
+            println!("{}", serde_json::to_string_pretty(&capabilities)?);
+        }
+        _ => {
+            for capability in &capabilities.capabilities {
+                let state = match (capability.active, capability.available) {
+                    (true, _) => "active",
+                    (false, true) => "available",
+                    (false, false) => "missing",
+                };
+                println!(
+                    "{:<12} {:<10} {}",
+                    capability.name, state, capability.detail
+                );
+                if !capability.active {
+                    println!("{:<12} {:<10} fallback: {}", "", "", capability.fallback);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Probe every optional subsystem for the repository at `root`.
+pub fn detect(root: &Path) -> Result<Capabilities> {
+    let config = Config::load(root)?;
+    let index = topo_index::open_repo(root, "");
+
+    let deep_index = match &index {
+        Ok(Some(sharded)) => {
+            let corpus = sharded.corpus();
+            let mut extras = Vec::new();
+            if corpus.trigrams.is_some() {
+                extras.push("trigrams");
+            }
+            if corpus.chunk_postings {
+                extras.push("chunk postings");
+            }
+            if corpus.git_activity.is_some() {
+                extras.push("git activity");
+            }
+            let extras = if extras.is_empty() {
+                String::new()
+            } else {
+                format!(", with {}", extras.join(", "))
+            };
+            active(
+                "deep_index",
+                format!(
+                    "version {}, {} files{extras}",
+                    corpus.version, corpus.total_docs
+                ),
+            )
+        }
+        Ok(None) => inactive("deep_index", true, "not built; run `topo index`"),
+        Err(e) => inactive("deep_index", true, &e.to_string()),
+    }
+    .fallback("path and filename heuristics only");
+
+    let embeddings = match index
+        .as_ref()
+        .ok()
+        .and_then(Option::as_ref)
+        .and_then(|i| i.embeddings())
+    {
+        Some(segment) => active(
+            "embeddings",
+            format!("{}, {} dimensions", segment.model, segment.dimension),
+        ),
+        None => inactive("embeddings", true, "no vectors stored in the deep index"),
+    }
+    .fallback("lexical scoring only");
+
+    let git = match topo_vcs::detect(root) {
+        Some(vcs) => match vcs.branch() {
+            Ok(branch) => active(
+                "git",
+                format!(
+                    "{} backend, branch {}",
+                    vcs.name(),
+                    branch.as_deref().unwrap_or("(detached)")
+                ),
+            ),
+            Err(e) => inactive("git", false, &e.to_string()),
+        },
+        None => inactive("git", true, "not a git repository"),
+    }
+    .fallback("no recency, ownership, or branch-aware signals");
+
+    let daemon = match super::daemon::serving_pid(root) {
+        Some(pid) => active("daemon", format!("running (pid {pid})")),
+        None if cfg!(unix) => inactive("daemon", true, "not running; start `topo daemon`"),
+        None => inactive("daemon", false, "needs Unix domain sockets"),
+    }
+    .fallback("each command loads the index itself");
+
+    let tree_sitter = if topo_treesit::TREE_SITTER {
+        active(
+            "tree_sitter",
+            "grammars built in, used for indexing".to_string(),
+        )
+    } else {
+        inactive(
+            "tree_sitter",
+            false,
+            "not built in; install with `--features tree-sitter`",
+        )
+    }
+    .fallback("regex chunker");
+
+    let lsp = if config.lsp.servers.is_empty() {
+        inactive("lsp", true, "no [lsp] servers configured")
+    } else {
+        let servers: Vec<String> = config
+            .lsp
+            .servers
+            .iter()
+            .map(|(language, command)| {
+                let found = command.first().is_some_and(|program| on_path(program));
+                format!("{language} ({})", if found { "found" } else { "not found" })
+            })
+            .collect();
+        let available = servers.iter().any(|s| s.ends_with("(found)"));
+        // Servers are only started by `topo index --lsp`
+        inactive("lsp", available, &servers.join(", "))
+    }
+    .fallback("regex chunker");
+
+    let remote = match &config.remote.url {
+        Some(url) => active("remote", format!("{url} ({})", config.remote.key)),
+        None => inactive("remote", true, "no [remote] url configured"),
+    }
+    .fallback("indexes are built locally");
+
+    Ok(Capabilities {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        capabilities: vec![
+            deep_index,
+            tree_sitter,
+            embeddings,
+            git,
+            daemon,
+            lsp,
+            remote,
+        ],
+    })
+}
+
+fn active(name: &'static str, detail: String) -> Capability {
+    Capability {
+        name,
+        available: true,
+        active: true,
+        detail,
+        fallback: "",
+    }
+}
+
+fn inactive(name: &'static str, available: bool, detail: &str) -> Capability {
+    Capability {
+        name,
+        available,
+        active: false,
+        detail: detail.to_string(),
+        fallback: "",
+    }
+}
+
+impl Capability {
+    fn fallback(mut self, fallback: &'static str) -> Self {
+        self.fallback = fallback;
+        self
+    }
+}
+
+/// Whether `program` names an executable file, directly or on `PATH`.
+fn on_path(program: &str) -> bool {
+    let program = Path::new(program);
+    if program.components().count() > 1 {
+        return program.is_file();
+    }
+    std::env::var_os("PATH")
+        .is_some_and(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+}
@@ -0,0 +1,34 @@
+use crate::Cli;
+use anyhow::Result;
+
+/// Delete the saved deep indexes of the repository and its nested
+/// repositories, or with `dry_run` list what would be deleted. Usage
+/// statistics are kept.
+pub fn run(cli: &Cli, dry_run: bool) -> Result<()> {
+    let root = cli.repo_root()?;
+    let files = if dry_run {
+        topo_index::index_files(&root)?
+    } else {
+        topo_index::remove_indexes(&root)?
+    };
+    if cli.is_quiet() {
+        return Ok(());
+    }
+
+    let bytes: u64 = files.iter().map(|f| f.bytes).sum();
+    if dry_run {
+        for file in &files {
+            let path = file.path.strip_prefix(&root).unwrap_or(&file.path);
+            println!("  Would delete {} ({} bytes)", path.display(), file.bytes);
+        }
+        println!(
+            "Dry run: {} index files ({bytes} bytes) would be deleted; nothing was written.",
+            files.len()
+        );
+    } else if files.is_empty() {
+        println!("No index to clean in {}", root.display());
+    } else {
+        println!("Deleted {} index files ({bytes} bytes)", files.len());
+    }
+    Ok(())
+}
@@ -0,0 +1,578 @@
+use super::index::{self, IndexOptions};
+use super::query::{self, Federation, QueryArgs};
+use crate::progress::ProgressMode;
+use crate::resources::Meter;
+use crate::{Cli, Command, DaemonAction, OutputFormat};
+use anyhow::Result;
+use clap::{Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use topo_core::Config;
+use topo_scanner::{BundleBuilder, HashCache};
+
+/// Set to run `query` and `quick` in-process even when a daemon is
+/// serving the repository.
+pub const NO_DAEMON_VAR: &str = "TOPO_NO_DAEMON";
+
+/// How long a client waits for the daemon's answer, reindexing included,
+/// before running the command itself.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How long the daemon waits for a connected client's request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// One request per connection, as a line of JSON.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "request", rename_all = "snake_case")]
+enum Request {
+    /// Run a `topo query` or `topo quick` command line for the repository
+    /// at `root`, printing in `format`.
+    Run {
+        root: PathBuf,
+        args: Vec<String>,
+        format: String,
+    },
+    Status,
+    Stop,
+}
+
+/// The answer to a [`Request`], as a line of JSON.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "response", rename_all = "snake_case")]
+enum Response {
+    /// What the command prints on stdout.
+    Output {
+        stdout: String,
+    },
+    /// Not a command the daemon answers; the client runs it itself.
+    Declined {
+        reason: String,
+    },
+    /// The command failed; the client runs it itself to report why.
+    Error {
+        message: String,
+    },
+    Status(Status),
+    Stopping,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Status {
+    pid: u32,
+    root: PathBuf,
+    files: usize,
+    uptime_secs: u64,
+    queries: u64,
+    reindexes: u64,
+}
+
+pub fn run(cli: &Cli, action: Option<&DaemonAction>, interval: u64) -> Result<()> {
+    let root = cli.repo_root()?;
+    match action {
+        None => serve(cli, Duration::from_secs(interval.max(1))),
+        Some(DaemonAction::Status) => {
+            match send(&root, &Request::Status) {
+                Ok(Response::Status(status)) => {
+                    println!("Daemon running (pid {})", status.pid);
+                    println!("Root: {}", status.root.display());
+                    println!("Files: {}", status.files);
+                    println!("Uptime: {}s", status.uptime_secs);
+                    println!("Queries answered: {}", status.queries);
+                    println!("Reindexes: {}", status.reindexes);
+                }
+                _ => println!("No daemon is serving {}", root.display()),
+            }
+            Ok(())
+        }
+        Some(DaemonAction::Stop) => {
+            match send(&root, &Request::Stop) {
+                Ok(Response::Stopping) => println!("Stopped the daemon serving {}", root.display()),
+                _ => println!("No daemon is serving {}", root.display()),
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Have a running daemon answer the current `query` or `quick` command
+/// and print its output. False when no daemon is serving the repository,
+/// it declines or fails, or [`NO_DAEMON_VAR`] is set; the caller then runs
+/// the command itself.
+pub fn delegate(cli: &Cli) -> bool {
+    if std::env::var_os(NO_DAEMON_VAR).is_some() {
+        return false;
+    }
+    let Ok(root) = cli.repo_root() else {
+        return false;
+    };
+    if !topo_index::daemon_socket_path(&root).exists() {
+        return false;
+    }
+    let Ok(args) = std::env::args_os()
+        .skip(1)
+        .map(OsString::into_string)
+        .collect::<Result<Vec<_>, _>>()
+    else {
+        return false;
+    };
+    let (Ok(canonical), Some(format)) = (
+        root.canonicalize(),
+        cli.effective_format().to_possible_value(),
+    ) else {
+        return false;
+    };
+    let request = Request::Run {
+        root: canonical,
+        args,
+        format: format.get_name().to_string(),
+    };
+    match send(&root, &request) {
+        Ok(Response::Output { stdout }) => {
+            print!("{stdout}");
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Whether the daemon answers a command with `args`: those that open or
+/// build a deep index, except ones reading a file named relative to the
+/// client or rebuilding the index from scratch.
+fn serves(args: &QueryArgs, quick: bool) -> bool {
+    let uses_index = args.needs_index() || (quick && args.preset().needs_deep_index());
+    let reads_files = matches!(args.compare_retrieval, Some(Some(_)))
+        || args.query_file.is_some()
+        || args.query_from_diff.is_some();
+    uses_index && !reads_files && !(quick && args.preset().force_rebuild())
+}
+
+/// Index features a `quick` command builds if the index lacks them.
+#[derive(Debug, Clone, Copy, Default)]
+struct Features {
+    trigrams: bool,
+    chunks: bool,
+}
+
+/// A scan of the repository and its deep indexes, loaded once and kept
+/// until the files change.
+struct Warm {
+    config: Config,
+    fingerprints: Vec<String>,
+    /// The scan with its deep indexes open, for commands that use them.
+    indexed: Federation,
+    /// The same scan without them, so other commands answer exactly as
+    /// they do in-process.
+    plain: Federation,
+}
+
+impl Warm {
+    /// Bring the deep index up to date with `features`, then load it.
+    /// Only files changed since `hashes` last saw them are read to scan.
+    fn load(cli: &Cli, root: &Path, features: Features, hashes: &HashCache) -> Result<Self> {
+        let options = IndexOptions {
+            deep: true,
+            trigrams: features.trigrams,
+            chunks: features.chunks,
+            hashes: Some(hashes),
+            ..Default::default()
+        };
+        index::run(cli, &options)?;
+        let config = Config::load(root)?;
+        let repos = BundleBuilder::new(root)
+            .config(&config)
+            .hash_cache(hashes)
+            .build_federated()?;
+        let embedder = query::local_embedder(root, &config)?;
+        let indexed = Federation::from_repos(root, repos, &config, true)?.embedder(embedder);
+        Ok(Self {
+            config,
+            fingerprints: indexed.fingerprints(),
+            plain: indexed.without_indexes(),
+            indexed,
+        })
+    }
+
+    fn has(&self, features: Features) -> bool {
+        let index = self.indexed.root_index();
+        (!features.trigrams || index.is_some_and(|i| i.trigrams.is_some()))
+            && (!features.chunks || index.is_some_and(|i| i.chunk_postings))
+    }
+}
+
+struct Daemon {
+    root: PathBuf,
+    canonical: PathBuf,
+    /// Quiet options for the index builds behind each reload.
+    quiet: Cli,
+    verbose: bool,
+    started: Instant,
+    /// The latest load. Requests answer from a handle to it, so they don't
+    /// wait for each other, nor for a reload building its successor.
+    warm: Mutex<Arc<Warm>>,
+    /// Held while checking for changes and reloading, so that two checks
+    /// never build the index at once.
+    reloading: Mutex<()>,
+    /// Hashes of the files scanned so far, so checks for changes only read
+    /// files whose size or modification time moved.
+    hashes: HashCache,
+    queries: AtomicU64,
+    reindexes: AtomicU64,
+}
+
+impl Daemon {
+    fn warm(&self) -> Arc<Warm> {
+        Arc::clone(&lock(&self.warm))
+    }
+
+    /// Reload when the files, configuration, or wanted `features` changed
+    /// since the last load, and return the load that is current.
+    fn refresh(&self, features: Features) -> Result<Arc<Warm>> {
+        let _reloading = lock(&self.reloading);
+        let config = Config::load(&self.root)?;
+        let repos = BundleBuilder::new(&self.root)
+            .config(&config)
+            .hash_cache(&self.hashes)
+            .build_federated()?;
+        let warm = self.warm();
+        let unchanged = config.hash() == warm.config.hash()
+            && repos
+                .iter()
+                .map(|repo| &repo.bundle.fingerprint)
+                .eq(&warm.fingerprints)
+            && warm.has(features);
+        if unchanged {
+            return Ok(warm);
+        }
+        let warm = Arc::new(Warm::load(&self.quiet, &self.root, features, &self.hashes)?);
+        *lock(&self.warm) = Arc::clone(&warm);
+        self.reindexes.fetch_add(1, Ordering::Relaxed);
+        if self.verbose {
+            eprintln!(
+                "Files changed; index reloaded ({} files)",
+                warm.indexed.file_count()
+            );
+        }
+        Ok(warm)
+    }
+
+    fn respond(&self, request: Request) -> Response {
+        match request {
+            Request::Run { root, args, format } => self
+                .answer(&root, args, &format)
+                .unwrap_or_else(|e| Response::Error {
+                    message: format!("{e:#}"),
+                }),
+            Request::Status => Response::Status(Status {
+                pid: std::process::id(),
+                root: self.canonical.clone(),
+                files: self.warm().indexed.file_count(),
+                uptime_secs: self.started.elapsed().as_secs(),
+                queries: self.queries.load(Ordering::Relaxed),
+                reindexes: self.reindexes.load(Ordering::Relaxed),
+            }),
+            Request::Stop => Response::Stopping,
+        }
+    }
+
+    /// Run a `query` or `quick` command line against the warm scan.
+    fn answer(&self, root: &Path, args: Vec<String>, format: &str) -> Result<Response> {
+        let declined = |reason: &str| {
+            Ok(Response::Declined {
+                reason: reason.to_string(),
+            })
+        };
+        if root != self.canonical {
+            return declined("the daemon serves another repository");
+        }
+        let mut cli = Cli::try_parse_from(std::iter::once("topo".to_string()).chain(args))?;
+        cli.root = Some(self.root.clone());
+        cli.format = OutputFormat::from_str(format, true).map_err(anyhow::Error::msg)?;
+        cli.quiet = true;
+        cli.progress = ProgressMode::None;
+        query::default_preset(&mut cli)?;
+        let (args, quick) = match &cli.command {
+            Some(Command::Query(args)) => (args, false),
+            Some(Command::Quick(args)) => (args, true),
+            _ => return declined("only query and quick are answered"),
+        };
+        if !serves(args, quick) {
+            return declined("the command doesn't use the deep index");
+        }
+        if cli.is_verbose() && !query::reports_usage_inline(&cli) {
+            return declined("verbose reports go to the client's stderr");
+        }
+
+        let started = Instant::now();
+        let features = Features {
+            trigrams: quick && args.fuzzy,
+            chunks: quick && args.chunks.is_some(),
+        };
+        let mut meter = Meter::new(started);
+        let hashed = self.hashes.hashed_bytes();
+        let warm = self.refresh(features)?;
+        let federation = if args.needs_index() || warm.indexed.embeds() {
+            &warm.indexed
+        } else {
+            &warm.plain
+        };
+        // Checking for changes rehashed the files that changed
+        meter.phase("scan");
+        meter.hashed(self.hashes.hashed_bytes().saturating_sub(hashed));
+        let stdout = query::respond(&cli, args, &warm.config, federation, &mut meter)?;
+        self.queries.fetch_add(1, Ordering::Relaxed);
+        Ok(Response::Output { stdout })
+    }
+}
+
+/// Keep the deep index of the repository loaded, checking for changed
+/// files every `interval`, and answer requests on its socket until told to
+/// stop.
+#[cfg(unix)]
+fn serve(cli: &Cli, interval: Duration) -> Result<()> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::sync::atomic::AtomicBool;
+    use std::sync::mpsc;
+    use topo_core::TopoError;
+
+    let root = cli.repo_root()?;
+    if let Ok(Response::Status(status)) = send(&root, &Request::Status) {
+        anyhow::bail!(
+            "A daemon (pid {}) is already serving {}",
+            status.pid,
+            root.display()
+        );
+    }
+
+    let mut quiet = Cli::try_parse_from(["topo", "--quiet", "--progress", "none"])?;
+    quiet.root = Some(root.clone());
+    let hashes = HashCache::new();
+    let warm = Warm::load(cli, &root, Features::default(), &hashes)?;
+    let daemon = Daemon {
+        canonical: root.canonicalize().map_err(|e| TopoError::io(&root, e))?,
+        root,
+        quiet,
+        verbose: !cli.is_quiet(),
+        started: Instant::now(),
+        warm: Mutex::new(Arc::new(warm)),
+        reloading: Mutex::new(()),
+        hashes,
+        queries: AtomicU64::new(0),
+        reindexes: AtomicU64::new(0),
+    };
+
+    // A socket left behind by a daemon that didn't exit cleanly
+    let socket = topo_index::daemon_socket_path(&daemon.root);
+    let _ = std::fs::remove_file(&socket);
+    let listener = UnixListener::bind(&socket).map_err(|e| TopoError::io(&socket, e))?;
+    let _socket = RemoveOnDrop(&socket);
+    if daemon.verbose {
+        eprintln!("Serving {} on {}", daemon.root.display(), socket.display());
+    }
+
+    let (stop, stopped) = mpsc::channel::<()>();
+    let stopping = AtomicBool::new(false);
+    std::thread::scope(|scope| {
+        let (daemon, stopping, socket) = (&daemon, &stopping, &socket);
+        scope.spawn(move || {
+            while let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                if let Err(e) = daemon.refresh(Features::default())
+                    && daemon.verbose
+                {
+                    eprintln!("Daemon: reload failed: {e:#}");
+                }
+            }
+        });
+
+        // Each connection on its own thread, so a slow query or reload
+        // doesn't hold up the others
+        for stream in listener.incoming() {
+            if stopping.load(Ordering::SeqCst) {
+                break;
+            }
+            scope.spawn(move || {
+                let handled = stream.map_err(anyhow::Error::from).and_then(|mut stream| {
+                    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+                    let mut line = String::new();
+                    BufReader::new(&stream).read_line(&mut line)?;
+                    let request: Request = serde_json::from_str(&line)?;
+                    let stop = matches!(request, Request::Stop);
+                    let mut reply = serde_json::to_string(&daemon.respond(request))?;
+                    reply.push('\n');
+                    stream.write_all(reply.as_bytes())?;
+                    Ok(stop)
+                });
+                match handled {
+                    Ok(true) => {
+                        stopping.store(true, Ordering::SeqCst);
+                        // Wake the accept loop so it sees the flag
+                        let _ = UnixStream::connect(socket);
+                    }
+                    Ok(false) => {}
+                    Err(e) if daemon.verbose => eprintln!("Daemon: bad request: {e:#}"),
+                    Err(_) => {}
+                }
+            });
+        }
+        drop(stop);
+    });
+    if daemon.verbose {
+        eprintln!("Daemon stopped");
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn serve(_cli: &Cli, _interval: Duration) -> Result<()> {
+    anyhow::bail!("topo daemon needs Unix domain sockets, which this platform lacks")
+}
+
+/// Process id of the daemon serving `root`, if one answers.
+pub fn serving_pid(root: &Path) -> Option<u32> {
+    match send(root, &Request::Status) {
+        Ok(Response::Status(status)) => Some(status.pid),
+        _ => None,
+    }
+}
+
+/// Send `request` to the daemon serving `root` and wait for the answer.
+#[cfg(unix)]
+fn send(root: &Path, request: &Request) -> Result<Response> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = UnixStream::connect(topo_index::daemon_socket_path(root))?;
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    Ok(serde_json::from_str(&reply)?)
+}
+
+#[cfg(not(unix))]
+fn send(_root: &Path, _request: &Request) -> Result<Response> {
+    anyhow::bail!("topo daemon needs Unix domain sockets, which this platform lacks")
+}
+
+/// Removes the socket file when the daemon exits.
+#[cfg(unix)]
+struct RemoveOnDrop<'a>(&'a Path);
+
+#[cfg(unix)]
+impl Drop for RemoveOnDrop<'_> {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(self.0);
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query_args(argv: &[&str]) -> (QueryArgs, bool) {
+        let cli = Cli::try_parse_from(std::iter::once("topo").chain(argv.iter().copied())).unwrap();
+        match cli.command {
+            Some(Command::Query(args)) => (args, false),
+            Some(Command::Quick(args)) => (args, true),
+            _ => panic!("expected query or quick"),
+        }
+    }
+
+    #[test]
+    fn serves_only_commands_using_the_deep_index() {
+        let served = |argv: &[&str]| {
+            let (args, quick) = query_args(argv);
+            serves(&args, quick)
+        };
+        assert!(served(&["quick", "auth"]));
+        assert!(served(&["query", "auth", "--fuzzy"]));
+        assert!(served(&["query", "auth", "--preset", "deep"]));
+        assert!(!served(&["query", "auth"]));
+        assert!(!served(&["quick", "auth", "--preset", "fast"]));
+        assert!(!served(&["quick", "auth", "--preset", "thorough"]));
+        assert!(!served(&["query", "auth", "--compare-retrieval", "q.json"]));
+        assert!(served(&["query", "auth", "--compare-retrieval"]));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn answers_queries_from_the_loaded_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::create_dir(root.join("src")).unwrap();
+        std::fs::write(root.join("src/auth.rs"), "pub fn login() {}\n").unwrap();
+        std::fs::write(root.join("src/cart.rs"), "pub fn checkout() {}\n").unwrap();
+
+        let root_arg = root.to_string_lossy().into_owned();
+        let cli = Cli::try_parse_from(["topo", "--quiet", "--root", &root_arg]).unwrap();
+        std::thread::scope(|scope| {
+            let daemon = scope.spawn(|| run(&cli, None, 60));
+            let started = Instant::now();
+            while !matches!(send(&root, &Request::Status), Ok(Response::Status(_))) {
+                assert!(
+                    started.elapsed() < Duration::from_secs(30),
+                    "daemon never started"
+                );
+                std::thread::sleep(Duration::from_millis(20));
+            }
+
+            // A client that hasn't sent its request doesn't hold up others
+            let idle =
+                std::os::unix::net::UnixStream::connect(topo_index::daemon_socket_path(&root))
+                    .unwrap();
+            let asked = Instant::now();
+            assert!(matches!(
+                send(&root, &Request::Status),
+                Ok(Response::Status(_))
+            ));
+            assert!(asked.elapsed() < REQUEST_TIMEOUT);
+            drop(idle);
+
+            let run = |args: &[&str]| {
+                let request = Request::Run {
+                    root: root.clone(),
+                    args: args.iter().map(|a| a.to_string()).collect(),
+                    format: "json".to_string(),
+                };
+                send(&root, &request).unwrap()
+            };
+            let Response::Output { stdout } = run(&["quick", "login"]) else {
+                panic!("expected output");
+            };
+            let output: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+            assert_eq!(output["files"][0]["path"], "src/auth.rs");
+            assert!(matches!(
+                run(&["query", "login"]),
+                Response::Declined { .. }
+            ));
+
+            // Changed files are picked up before answering
+            std::fs::write(root.join("src/token.rs"), "pub fn refresh_token() {}\n").unwrap();
+            let Response::Output { stdout } = run(&["quick", "refresh token"]) else {
+                panic!("expected output");
+            };
+            assert!(stdout.contains("src/token.rs"));
+            let Ok(Response::Status(status)) = send(&root, &Request::Status) else {
+                panic!("expected status");
+            };
+            assert_eq!((status.files, status.queries, status.reindexes), (3, 2, 1));
+
+            assert!(matches!(
+                send(&root, &Request::Stop),
+                Ok(Response::Stopping)
+            ));
+            daemon.join().unwrap().unwrap();
+        });
+        assert!(!topo_index::daemon_socket_path(&root).exists());
+    }
+}
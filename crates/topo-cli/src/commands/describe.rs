@@ -1,18 +1,20 @@
 use crate::Cli;
 use anyhow::Result;
+use topo_core::Language;
 
 pub fn run(cli: &Cli) -> Result<()> {
+    let languages: Vec<&str> = Language::all()
+        .filter(|l| l.is_programming_language())
+        .map(|l| l.as_str())
+        .collect();
+
     let description = serde_json::json!({
         "name": "topo",
         "version": env!("CARGO_PKG_VERSION"),
         "replaces": "repo-context",
-        "commands": ["index", "query", "quick", "render", "explain", "inspect", "describe", "mcp", "init", "gain"],
-        "formats": ["jsonl", "json", "human", "compact"],
-        "languages": [
-            "rust", "go", "python", "javascript", "typescript",
-            "java", "ruby", "c", "cpp", "shell", "swift", "kotlin",
-            "scala", "haskell", "elixir", "lua", "php", "r"
-        ],
+        "commands": ["index", "query", "quick", "query-help", "render", "explain", "inspect", "stats", "describe", "capabilities", "mcp", "daemon", "setup", "init", "gain", "clean", "pin", "unpin", "feedback"],
+        "formats": ["jsonl", "json", "human", "compact", "html", "sarif"],
+        "languages": languages,
         "scoring": ["heuristic", "content", "hybrid"],
         "presets": ["fast", "balanced", "deep", "thorough"],
     });
@@ -21,11 +23,11 @@ pub fn run(cli: &Cli) -> Result<()> {
         crate::OutputFormat::Human => {
             println!("topo v{}", env!("CARGO_PKG_VERSION"));
             println!();
-            println!("Commands:  index, query, quick, render, explain, inspect, describe, mcp");
-            println!("Formats:   jsonl, json, human");
             println!(
-                "Languages: rust, go, python, javascript, typescript, java, ruby, c, cpp, shell, swift, kotlin, scala, haskell, elixir, lua, php, r"
+                "Commands:  index, query, quick, render, explain, inspect, stats, describe, capabilities, mcp, daemon"
             );
+            println!("Formats:   jsonl, json, human, compact, html, sarif");
+            println!("Languages: {}", languages.join(", "));
             println!("Scoring:   heuristic, content, hybrid");
             println!("Presets:   fast, balanced, deep, thorough");
         }
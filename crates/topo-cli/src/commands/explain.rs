@@ -1,11 +1,13 @@
 use crate::Cli;
 use crate::preset::Preset;
 use anyhow::Result;
+use topo_core::Config;
 use topo_scanner::BundleBuilder;
 
 pub fn run(cli: &Cli, task: &str, top: usize, preset: Preset) -> Result<()> {
     let root = cli.repo_root()?;
-    let bundle = BundleBuilder::new(&root).build()?;
+    let config = Config::load(&root)?;
+    let bundle = BundleBuilder::new(&root).config(&config).build()?;
 
     // Load deep index for PageRank when using structural signals
     let deep_index = if preset.use_structural_signals() {
@@ -14,7 +16,36 @@ pub fn run(cli: &Cli, task: &str, top: usize, preset: Preset) -> Result<()> {
         None
     };
 
-    let scored = super::query::score_files(task, &bundle.files, preset, deep_index.as_ref());
+    let options = super::query::ScoringOptions {
+        profile: topo_core::WeightProfile::load(&root)?,
+        ..super::query::ScoringOptions::from_config(&config)
+    };
+    let mut scored = super::query::score_files(
+        task,
+        &bundle.files,
+        preset,
+        deep_index.as_ref(),
+        None,
+        &options,
+    );
+    // Filters were applied in scoring; boosts look at the terms alone
+    let scoring = preset.scoring();
+    let terms = topo_score::ParsedQuery::parse(task).text;
+    if let Some(index) = &deep_index {
+        super::query::apply_exports(&terms, &mut scored, scoring.candidates, |path| {
+            index.files.get(path)
+        });
+    }
+    let stemming = deep_index
+        .as_ref()
+        .map_or(config.index.stemming, |index| index.stemmed);
+    super::query::apply_phrases(&terms, stemming, &mut scored, scoring.candidates, |path| {
+        deep_index.as_ref().and_then(|index| index.files.get(path))
+    });
+    if preset.use_structural_signals() {
+        super::query::apply_ownership(&root, &bundle.files, &mut scored, scoring.blame_candidates)?;
+    }
+    options.normalization.apply(&mut scored, scoring.candidates);
 
     let display_count = top.min(scored.len());
     let results = &scored[..display_count];
@@ -32,6 +63,13 @@ pub fn run(cli: &Cli, task: &str, top: usize, preset: Preset) -> Result<()> {
                             "heuristic": f.signals.heuristic,
                             "pagerank": f.signals.pagerank,
                             "git_recency": f.signals.git_recency,
+                            "ownership": f.signals.ownership,
+                            "exports": f.signals.exports,
+                            "phrase": f.signals.phrase,
+                            "centrality": f.signals.centrality,
+                            "penalty": f.signals.penalty,
+                            "raw": f.signals.raw,
+                            "duplicate_of": f.signals.duplicate_of,
                         },
                         "tokens": f.tokens,
                         "language": f.language.as_str(),
@@ -41,6 +79,15 @@ pub fn run(cli: &Cli, task: &str, top: usize, preset: Preset) -> Result<()> {
                 .collect();
             println!("{}", serde_json::to_string_pretty(&output)?);
         }
+        crate::OutputFormat::Html => {
+            let mut env = super::query::environment(preset, &config, &bundle, deep_index.as_ref());
+            env.degraded = super::query::degraded(preset, false, deep_index.as_ref());
+            let output = topo_render::HtmlWriter::new(task, preset.as_str())
+                .contents(super::query::read_contents(&root, results))
+                .environment(env)
+                .render(results, scored.len())?;
+            print!("{output}");
+        }
         _ => {
             println!("Score breakdown for query: \"{task}\"");
             println!("Showing top {display_count} of {} files\n", scored.len());
@@ -59,7 +106,7 @@ pub fn run(cli: &Cli, task: &str, top: usize, preset: Preset) -> Result<()> {
                     .unwrap_or_else(|| "-".to_string());
                 println!(
                     "{:<50} {:>8.4} {:>8.4} {:>8.4} {:>8} {:>8}",
-                    truncate(&f.path, 50),
+                    super::query::truncate_path(&f.path, 50),
                     f.score,
                     f.signals.bm25f,
                     f.signals.heuristic,
@@ -72,11 +119,3 @@ pub fn run(cli: &Cli, task: &str, top: usize, preset: Preset) -> Result<()> {
 
     Ok(())
 }
-
-fn truncate(s: &str, max: usize) -> String {
-    if s.len() <= max {
-        s.to_string()
-    } else {
-        format!("...{}", &s[s.len() - max + 3..])
-    }
-}
@@ -0,0 +1,148 @@
+use super::pin::repo_relative;
+use super::query::{ScoringOptions, score_files};
+use crate::preset::Preset;
+use crate::{Cli, OutputFormat};
+use anyhow::{Result, bail};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use topo_core::{Config, Judgment};
+use topo_scanner::BundleBuilder;
+use topo_score::{NDCG_DEPTH, TrainingQuery};
+
+/// Leading results of each judged query ranked when fitting, besides the
+/// judged files.
+const TRAINING_CANDIDATES: usize = 50;
+
+/// Record `good` and `bad` files for `task`, then fit the repository's
+/// weight profile to every judgment recorded so far.
+pub fn run(
+    cli: &Cli,
+    task: &str,
+    good: &[PathBuf],
+    bad: &[PathBuf],
+    preset: Option<Preset>,
+) -> Result<()> {
+    let root = cli.repo_root()?;
+    let config = Config::load(&root)?;
+    let preset = preset.unwrap_or_else(|| Preset::from_config(&config));
+    let judgment = Judgment {
+        query: task.to_string(),
+        good: judged(&root, good)?,
+        bad: judged(&root, bad)?,
+    };
+    judgment.record(&root)?;
+
+    // Judged queries are scored with the preset's weights, not fitted
+    // ones; the signals being weighed are the same either way
+    let judgments = Judgment::load_all(&root)?;
+    let bundle = BundleBuilder::new(&root).config(&config).build()?;
+    let deep_index = if preset.use_structural_signals() {
+        topo_index::load(&root)?
+    } else {
+        None
+    };
+    let scoring = ScoringOptions::from_config(&config);
+    let queries: Vec<TrainingQuery> = merge(&judgments)
+        .into_iter()
+        .map(|(query, (good, bad))| {
+            let scored = score_files(
+                &query,
+                &bundle.files,
+                preset,
+                deep_index.as_ref(),
+                None,
+                &scoring,
+            );
+            let files = scored
+                .into_iter()
+                .enumerate()
+                .filter(|(rank, file)| {
+                    *rank < TRAINING_CANDIDATES
+                        || good.contains(&file.path)
+                        || bad.contains(&file.path)
+                })
+                .map(|(_, file)| file)
+                .collect();
+            TrainingQuery { files, good, bad }
+        })
+        .collect();
+    let mut profile = topo_score::fit_weights(&queries);
+    profile.judgments = judgments.len();
+    profile.save(&root)?;
+
+    match cli.effective_format() {
+        OutputFormat::Json | OutputFormat::Jsonl => {
+            println!("{}", serde_json::to_string_pretty(&profile)?);
+        }
+        _ => {
+            println!(
+                "Recorded feedback for \"{task}\" ({} judgments of {} queries)",
+                judgments.len(),
+                queries.len()
+            );
+            println!(
+                "Fitted weights: BM25F {:.2}, heuristic {:.2}, PageRank {:.2} (NDCG@{NDCG_DEPTH} {:.3})",
+                profile.bm25f, profile.heuristic, profile.pagerank, profile.ndcg
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Repository-relative paths of the judged files, which must exist.
+fn judged(root: &Path, paths: &[PathBuf]) -> Result<Vec<String>> {
+    paths
+        .iter()
+        .map(|path| {
+            let rel = repo_relative(root, path)?;
+            if !root.join(&rel).is_file() {
+                bail!("{} is not a file in {}", path.display(), root.display());
+            }
+            Ok(rel)
+        })
+        .collect()
+}
+
+/// Good and bad files of each query judged, with later judgments of a file
+/// replacing earlier ones.
+fn merge(judgments: &[Judgment]) -> BTreeMap<String, (HashSet<String>, HashSet<String>)> {
+    let mut queries: BTreeMap<String, (HashSet<String>, HashSet<String>)> = BTreeMap::new();
+    for judgment in judgments {
+        let (good, bad) = queries.entry(judgment.query.clone()).or_default();
+        for path in &judgment.good {
+            bad.remove(path);
+            good.insert(path.clone());
+        }
+        for path in &judgment.bad {
+            good.remove(path);
+            bad.insert(path.clone());
+        }
+    }
+    queries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn later_judgments_replace_earlier_ones() {
+        let judgment = |query: &str, good: &[&str], bad: &[&str]| Judgment {
+            query: query.to_string(),
+            good: good.iter().map(|p| p.to_string()).collect(),
+            bad: bad.iter().map(|p| p.to_string()).collect(),
+        };
+        let merged = merge(&[
+            judgment("auth", &["a.rs", "b.rs"], &["c.rs"]),
+            judgment("db", &["d.rs"], &[]),
+            judgment("auth", &["c.rs"], &["b.rs"]),
+        ]);
+        assert_eq!(merged.len(), 2);
+        let (good, bad) = &merged["auth"];
+        assert_eq!(
+            good,
+            &HashSet::from(["a.rs".to_string(), "c.rs".to_string()])
+        );
+        assert_eq!(bad, &HashSet::from(["b.rs".to_string()]));
+    }
+}
@@ -3,6 +3,7 @@ use anyhow::Result;
 use std::collections::HashSet;
 use std::fs;
 use std::io::BufRead;
+use topo_index::UsageStats;
 
 /// Stats entry from `.topo/stats.jsonl`.
 #[derive(serde::Deserialize)]
@@ -21,8 +22,12 @@ struct StatsEntry {
 pub fn run(cli: &Cli) -> Result<()> {
     let root = cli.repo_root()?;
     let stats_path = root.join(".topo/stats.jsonl");
+    let usage = topo_index::load_usage(&root)?;
 
     if !stats_path.exists() {
+        if usage.queries > 0 || usage.index_runs > 0 {
+            return print_usage(cli, &usage);
+        }
         println!("No topo stats found.");
         println!();
         println!("Stats are collected automatically when Claude Code hooks are installed.");
@@ -79,6 +84,7 @@ pub fn run(cli: &Cli) -> Result<()> {
                 "files_suggested": total_files_suggested,
                 "files_opened": files_opened.len(),
                 "tokens_suggested": total_tokens_suggested,
+                "usage": usage_json(&usage),
             });
             println!("{}", serde_json::to_string_pretty(&output)?);
         }
@@ -93,12 +99,61 @@ pub fn run(cli: &Cli) -> Result<()> {
                 let avg = total_files_suggested as f64 / suggestion_events as f64;
                 println!("  Avg files/query:  {avg:.1}");
             }
+            println!();
+            print_usage_summary(&usage);
         }
     }
 
     Ok(())
 }
 
+/// Print local usage stats alone, when no hook events were recorded.
+fn print_usage(cli: &Cli, usage: &UsageStats) -> Result<()> {
+    match cli.effective_format() {
+        crate::OutputFormat::Json | crate::OutputFormat::Jsonl => {
+            let output = serde_json::json!({ "usage": usage_json(usage) });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+        _ => print_usage_summary(usage),
+    }
+    Ok(())
+}
+
+fn print_usage_summary(usage: &UsageStats) {
+    println!("Local usage (.topo/stats.json):");
+    println!("  Queries:          {}", usage.queries);
+    if let (Some(p50), Some(p95)) = (
+        usage.latency_percentile(50.0),
+        usage.latency_percentile(95.0),
+    ) {
+        println!("  Latency p50/p95:  {p50} ms / {p95} ms");
+    }
+    if let Some(rate) = usage.index_hit_rate() {
+        println!("  Index hit rate:   {:.1}%", rate * 100.0);
+    }
+    println!("  Index runs:       {}", usage.index_runs);
+    if let Some(rate) = usage.reuse_rate() {
+        println!("  Files reused:     {:.1}%", rate * 100.0);
+    }
+}
+
+fn usage_json(usage: &UsageStats) -> serde_json::Value {
+    serde_json::json!({
+        "queries": usage.queries,
+        "latency_ms": {
+            "p50": usage.latency_percentile(50.0),
+            "p90": usage.latency_percentile(90.0),
+            "p95": usage.latency_percentile(95.0),
+            "p99": usage.latency_percentile(99.0),
+        },
+        "index_hit_rate": usage.index_hit_rate(),
+        "index_runs": usage.index_runs,
+        "files_reused": usage.files_reused,
+        "files_reindexed": usage.files_reindexed,
+        "reuse_rate": usage.reuse_rate(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -121,6 +176,20 @@ mod tests {
         assert_eq!(entry.path, Some("src/main.rs".to_string()));
     }
 
+    #[test]
+    fn usage_json_reports_percentiles_and_rates() {
+        let mut usage = UsageStats::default();
+        usage.record_query(std::time::Duration::from_millis(20), Some(true));
+        usage.record_query(std::time::Duration::from_millis(80), Some(false));
+        usage.record_index(9, 1);
+        let json = usage_json(&usage);
+        assert_eq!(json["queries"], 2);
+        assert_eq!(json["latency_ms"]["p50"], 20);
+        assert_eq!(json["latency_ms"]["p99"], 80);
+        assert_eq!(json["index_hit_rate"], 0.5);
+        assert_eq!(json["reuse_rate"], 0.9);
+    }
+
     #[test]
     fn parses_session_start_entry() {
         let json = r#"{"timestamp":"2025-01-01T00:00:00Z","event":"session_start"}"#;
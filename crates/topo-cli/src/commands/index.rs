@@ -1,21 +1,99 @@
 use crate::Cli;
+use crate::progress::Progress;
 use anyhow::Result;
-use topo_index::IndexBuilder;
-use topo_scanner::BundleBuilder;
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+use topo_core::{
+    Config, DeepIndex, Embedder, FileInfo, Language, LspConfig, PruneReport, RemoteConfig,
+    TopoError, display_path,
+};
+use topo_index::{IndexBuilder, IndexIssue, RemoteBackend};
+use topo_scanner::{BundleBuilder, HashCache, RepoBundle};
+use topo_treesit::LspChunker;
 
-pub fn run(cli: &Cli, deep: bool, force: bool) -> Result<()> {
+/// What `topo index` should build, import, and export.
+#[derive(Default)]
+pub struct IndexOptions<'a> {
+    pub deep: bool,
+    pub force: bool,
+    pub trigrams: bool,
+    /// Record per-chunk term frequencies, see
+    /// [`IndexBuilder::chunk_postings`].
+    pub chunks: bool,
+    /// Count license headers in term statistics, see
+    /// [`IndexBuilder::strip_license_headers`].
+    pub keep_license_headers: bool,
+    /// Take chunks from the language servers under `[lsp]` in
+    /// `.topo.toml`, see [`LspChunker`].
+    pub lsp: bool,
+    pub export_cache: Option<&'a Path>,
+    pub import_cache: Option<&'a Path>,
+    /// Upload the deep index to the remote after indexing.
+    pub push: bool,
+    /// Seed the index from the remote before indexing, so the build only
+    /// covers local changes.
+    pub pull: bool,
+    /// Remote URL, overriding `[remote] url`.
+    pub remote: Option<&'a str>,
+    /// Key within the remote, overriding `[remote] key`.
+    pub remote_key: Option<&'a str>,
+    /// Drop entries for deleted files from existing deep indexes instead
+    /// of building.
+    pub prune: bool,
+    /// Check existing deep indexes instead of building.
+    pub verify: bool,
+    /// Rebuild the bad entries `verify` finds.
+    pub repair: bool,
+    /// Report what would be reindexed, written, and deleted without
+    /// writing anything.
+    pub dry_run: bool,
+    /// Model new and changed files are embedded with, instead of the
+    /// `embedding.model` of `.topo.toml`.
+    pub embedder: Option<&'a dyn Embedder>,
+    /// Hashes the scan reuses for files unchanged since they were
+    /// remembered, see [`BundleBuilder::hash_cache`].
+    pub hashes: Option<&'a HashCache>,
+}
+
+pub fn run(cli: &Cli, options: &IndexOptions) -> Result<()> {
+    let deep = options.deep;
     let root = cli.repo_root()?;
+    let config = Config::load(&root)?;
+    let remote = if options.push || options.pull {
+        Some(remote(options, &config.remote)?)
+    } else {
+        None
+    };
 
     if !cli.is_quiet() {
         eprintln!(
             "Indexing {} (mode: {})...",
             root.display(),
-            if deep { "deep" } else { "shallow" }
+            if options.prune {
+                "prune"
+            } else if options.verify {
+                "verify"
+            } else if deep {
+                "deep"
+            } else {
+                "shallow"
+            }
         );
     }
 
-    // Scan the repository
-    let bundle = BundleBuilder::new(&root).build()?;
+    let progress = cli.progress();
+
+    // Scan the repository and any nested repositories
+    progress.start("scan", None);
+    let mut scan = BundleBuilder::new(&root).config(&config);
+    if let Some(hashes) = options.hashes {
+        scan = scan.hash_cache(hashes);
+    }
+    let repos = scan.build_federated()?;
+    let bundle = &repos[0].bundle;
+    progress.finish(repos.iter().map(|r| r.bundle.file_count()).sum());
 
     if !cli.is_quiet() {
         eprintln!(
@@ -23,53 +101,655 @@ pub fn run(cli: &Cli, deep: bool, force: bool) -> Result<()> {
             bundle.file_count(),
             &bundle.fingerprint[..12]
         );
+        for repo in &repos[1..] {
+            eprintln!(
+                "Scanned {} files in nested repository {} (fingerprint: {})",
+                repo.bundle.file_count(),
+                repo.prefix,
+                &repo.bundle.fingerprint[..12]
+            );
+        }
     }
 
-    if deep {
-        // Load existing index (unless force rebuild)
-        let existing = if force {
-            None
-        } else {
-            topo_index::load(&root)?
+    if let Some(archive) = options.import_cache {
+        let report = topo_index::import_cache(&root, archive, &bundle.files, &bundle.fingerprint)?;
+        if !cli.is_quiet() {
+            eprintln!(
+                "Imported cache from {} (topo {}, {}/{} indexed files current{})",
+                archive.display(),
+                report.manifest.topo_version,
+                report.matching_files,
+                report.indexed_files,
+                if report.fingerprint_matches {
+                    ", fingerprint match"
+                } else {
+                    ""
+                }
+            );
+        }
+    }
+
+    if let Some((url, backend, key)) = remote.as_ref().filter(|_| options.pull) {
+        let report = topo_index::pull_cache(
+            &root,
+            backend.as_ref(),
+            key,
+            &bundle.files,
+            &bundle.fingerprint,
+        )?;
+        if !cli.is_quiet() {
+            match report {
+                Some(report) => eprintln!(
+                    "Pulled `{key}` from {url} (topo {}, {}/{} indexed files current)",
+                    report.manifest.topo_version, report.matching_files, report.indexed_files
+                ),
+                None => eprintln!("Nothing pushed as `{key}` to {url}; indexing from scratch"),
+            }
+        }
+    }
+
+    if options.verify {
+        let mut unresolved = 0;
+        for repo in &repos {
+            unresolved += verify_repo(cli, &root, repo, options, &config)?;
+        }
+        if unresolved > 0 {
+            let message = format!("the index has {unresolved} problems");
+            return Err(TopoError::index_stale(message).with_path(&root).into());
+        }
+    } else if options.prune {
+        for repo in &repos {
+            prune_repo(cli, &root, repo, options, &config)?;
+        }
+    } else if deep {
+        let local = match options.embedder {
+            None if !options.dry_run => super::query::local_embedder(&root, &config)?,
+            _ => None,
         };
+        let embedder = options.embedder.or(local.as_deref());
+        for repo in &repos {
+            index_repo(cli, &progress, &root, repo, options, &config, embedder)?;
+        }
+    }
 
-        // Build index, skipping unchanged files when existing index is available
-        let builder = IndexBuilder::new(&root);
-        let (index, reindexed) = builder.build(&bundle.files, existing.as_ref())?;
+    if let Some(archive) = options.export_cache {
+        let manifest = topo_index::export_cache(&root, archive, &bundle.fingerprint)?;
+        if !cli.is_quiet() {
+            eprintln!(
+                "Exported {} cache files to {}",
+                manifest.files.len(),
+                archive.display()
+            );
+        }
+    }
 
-        let is_incremental = existing.is_some();
-        let nothing_changed = is_incremental && reindexed == 0;
+    if let Some((url, backend, key)) = remote.as_ref().filter(|_| options.push) {
+        let manifest = topo_index::push_cache(&root, backend.as_ref(), key, &bundle.fingerprint)?;
+        if !cli.is_quiet() {
+            eprintln!(
+                "Pushed {} cache files to {url} as `{key}`",
+                manifest.files.len()
+            );
+        }
+    }
+
+    if !cli.is_quiet() {
+        if options.dry_run {
+            eprintln!("Dry run: nothing was written.");
+        } else {
+            eprintln!("Done.");
+        }
+    }
 
+    Ok(())
+}
+
+/// The remote URL, its backend, and the key to push or pull, from the
+/// flags or else `[remote]` in `.topo.toml`.
+fn remote(
+    options: &IndexOptions,
+    config: &RemoteConfig,
+) -> Result<(String, Box<dyn RemoteBackend>, String)> {
+    let Some(url) = options.remote.or(config.url.as_deref()) else {
+        return Err(TopoError::config(
+            "no remote to push to or pull from; pass --remote or set `url` under [remote]",
+        )
+        .into());
+    };
+    let key = options.remote_key.unwrap_or(&config.key);
+    if key.is_empty() || key.contains("..") {
+        return Err(TopoError::remote(url, format!("invalid key `{key}`")).into());
+    }
+    let backend = topo_index::remote_backend(url)?;
+    Ok((url.to_string(), backend, key.to_string()))
+}
+
+/// A chunker backed by the language servers under `[lsp]`, for files
+/// under `root`.
+fn lsp_chunker(root: &Path, config: &LspConfig) -> Result<LspChunker> {
+    if config.servers.is_empty() {
+        return Err(TopoError::config(
+            "--lsp needs a language server; add one under [lsp.servers], like `rust = [\"rust-analyzer\"]`",
+        )
+        .into());
+    }
+    let timeout = Duration::from_millis(config.timeout_ms);
+    Ok(config
+        .servers
+        .iter()
+        .filter_map(|(name, command)| Some((Language::from_name(name)?, command.clone())))
+        .fold(
+            LspChunker::new(root, timeout),
+            |chunker, (language, command)| chunker.server(language, command),
+        ))
+}
+
+/// Build and save the deep index of one repository, incrementally unless
+/// `--force` is set.
+fn index_repo(
+    cli: &Cli,
+    progress: &Progress,
+    root: &Path,
+    repo: &RepoBundle,
+    options: &IndexOptions,
+    config: &Config,
+    embedder: Option<&dyn Embedder>,
+) -> Result<()> {
+    let bundle = &repo.bundle;
+    let index_path = topo_index::repo_index_path(root, &repo.prefix);
+    let label = repo_label(&repo.prefix);
+
+    // Load the existing index for this branch (unless force rebuild),
+    // falling back to the last one built
+    let branch = current_branch(&bundle.root);
+    let existing = if options.force {
+        None
+    } else {
+        topo_index::load_repo(root, &repo.prefix, branch.as_deref())?
+    };
+
+    // Build index, skipping unchanged files when existing index is available
+    progress.start("index", Some(bundle.file_count()));
+    let report = |done: usize, _total: usize| progress.update(done);
+    let lsp = if options.lsp {
+        Some(lsp_chunker(&bundle.root, &config.lsp)?)
+    } else {
+        None
+    };
+    let sparse = sparse_paths(&bundle.root);
+    let mut builder = IndexBuilder::new(&bundle.root)
+        .sparse_checkout(&sparse)
+        .progress(&report)
+        .trigrams(options.trigrams)
+        .chunk_postings(options.chunks)
+        .git_activity(true)
+        .git_lookback_days(super::query::recency_model(config).lookback_days)
+        .fingerprint(&bundle.fingerprint)
+        .prune(config.index.prune)
+        .strip_license_headers(!options.keep_license_headers)
+        .lockfile_bodies(config.index.lockfile_bodies)
+        .comments(config.index.comments)
+        .stemming(config.index.stemming);
+    if let Some(lsp) = &lsp {
+        builder = builder.chunker(lsp);
+    }
+    if let Some(embedder) = embedder {
+        builder = builder.embedder(embedder, config.embedding.clone());
+    }
+    let (index, reindexed) = builder.build(&bundle.files, existing.as_ref())?;
+    progress.finish(bundle.file_count());
+    if let Some(lsp) = lsp
+        && !cli.is_quiet()
+    {
+        for (language, reason) in lsp.failures() {
+            eprintln!(
+                "Language server for {language} unavailable{label}, using the built-in chunker: {reason}"
+            );
+        }
+    }
+
+    let is_incremental = existing.is_some();
+    // Deletions and renames don't reindex anything but still change the index
+    let nothing_changed = existing.as_ref().is_some_and(|old| {
+        reindexed == 0
+            && old.stemmed == index.stemmed
+            && old.files.len() == index.files.len()
+            && old.files.keys().all(|path| index.files.contains_key(path))
+            && old.tombstones == index.tombstones
+            && old.trigrams.is_some() == index.trigrams.is_some()
+            && old.chunk_postings == index.chunk_postings
+            && old.git_activity == index.git_activity
+            && old.embeddings == index.embeddings
+            && same_provenance(old, &index)
+            && !pruned_anything(&index)
+    }) && is_active_branch(root, &repo.prefix, branch.as_deref())?;
+
+    if !cli.is_quiet() {
+        if is_incremental {
+            eprintln!(
+                "Incremental update{label}: {} files indexed ({} changed)",
+                index.total_docs, reindexed
+            );
+        } else {
+            eprintln!(
+                "Full index build{label}: {} files indexed",
+                index.total_docs
+            );
+        }
+        if let Some(pruned) = index.meta.as_ref().and_then(|meta| meta.pruned.as_ref()) {
+            eprintln!("{}", prune_summary(pruned));
+        }
+    }
+
+    if options.dry_run {
         if !cli.is_quiet() {
-            if is_incremental {
-                eprintln!(
-                    "Incremental update: {} files indexed ({} changed)",
-                    index.total_docs, reindexed
+            print_paths(
+                "Would reindex",
+                &reindexed_paths(&bundle.files, existing.as_ref()),
+            );
+            if let Some(existing) = &existing {
+                print_paths(
+                    "Would drop deleted",
+                    &removed_paths(&bundle.files, existing, &sparse),
                 );
+            }
+            if nothing_changed {
+                eprintln!("Index would stay unchanged at {}", index_path.display());
             } else {
-                eprintln!("Full index build: {} files indexed", index.total_docs);
+                let plan = topo_index::plan_save_repo(
+                    &index,
+                    root,
+                    &repo.prefix,
+                    branch.as_deref(),
+                    config.index.compression_level,
+                )?;
+                print_plan(root, &plan);
             }
         }
+        return Ok(());
+    }
 
-        if nothing_changed {
-            if !cli.is_quiet() {
-                eprintln!(
-                    "Index unchanged at {}",
-                    topo_index::index_path(&root).display()
-                );
-            }
-        } else {
-            topo_index::save(&index, &root)?;
+    if nothing_changed {
+        if !cli.is_quiet() {
+            eprintln!("Index unchanged at {}", index_path.display());
+        }
+    } else {
+        let saved = topo_index::save_repo(
+            &index,
+            root,
+            &repo.prefix,
+            branch.as_deref(),
+            config.index.compression_level,
+        )?;
+
+        if !cli.is_quiet() {
+            let deltas = match saved.deltas {
+                0 => String::new(),
+                n => format!(", {n} as deltas"),
+            };
+            eprintln!(
+                "Index saved to {} ({} of {} shards written{deltas})",
+                index_path.display(),
+                saved.written,
+                saved.shards
+            );
+        }
+    }
+    record_index(root, &index, reindexed);
+    Ok(())
+}
 
-            if !cli.is_quiet() {
-                eprintln!("Index saved to {}", topo_index::index_path(&root).display());
+/// Check one repository's deep index and, with `--repair`, rebuild what is
+/// wrong with it. Returns the number of problems left unrepaired.
+fn verify_repo(
+    cli: &Cli,
+    root: &Path,
+    repo: &RepoBundle,
+    options: &IndexOptions,
+    config: &Config,
+) -> Result<usize> {
+    let bundle = &repo.bundle;
+    let label = repo_label(&repo.prefix);
+    let branch = current_branch(&bundle.root);
+    let Some(mut report) =
+        topo_index::verify_repo(root, &repo.prefix, branch.as_deref(), &bundle.root)?
+    else {
+        if !cli.is_quiet() {
+            eprintln!("No deep index to verify{label}");
+        }
+        return Ok(0);
+    };
+    // Files outside a sparse checkout are missing on purpose
+    let sparse = sparse_paths(&bundle.root);
+    report
+        .issues
+        .retain(|issue| !matches!(issue, IndexIssue::Missing { path } if sparse.contains(path)));
+
+    for issue in &report.issues {
+        eprintln!("  {issue}");
+    }
+    if report.is_ok() {
+        if !cli.is_quiet() {
+            eprintln!("Index OK{label}: {} files verified", report.files);
+        }
+        return Ok(0);
+    }
+    if !options.repair {
+        eprintln!("Found {} problems{label}", report.issues.len());
+        return Ok(report.issues.len());
+    }
+
+    // Rebuild bad entries from scratch and everything else incrementally,
+    // which also recomputes corpus statistics and the dependency graph
+    let existing = if report.issues.contains(&IndexIssue::Unreadable) {
+        None
+    } else {
+        topo_index::load_repo(root, &repo.prefix, branch.as_deref())?.map(|mut index| {
+            for path in report.bad_paths() {
+                index.files.remove(path);
             }
+            index
+        })
+    };
+    let builder = IndexBuilder::new(&bundle.root)
+        .sparse_checkout(&sparse)
+        .trigrams(options.trigrams)
+        .chunk_postings(options.chunks)
+        .git_activity(true)
+        .git_lookback_days(super::query::recency_model(config).lookback_days)
+        .fingerprint(&bundle.fingerprint)
+        .prune(config.index.prune)
+        .strip_license_headers(!options.keep_license_headers)
+        .lockfile_bodies(config.index.lockfile_bodies)
+        .comments(config.index.comments)
+        .stemming(config.index.stemming);
+    let (index, reindexed) = builder.build(&bundle.files, existing.as_ref())?;
+    if options.dry_run {
+        if !cli.is_quiet() {
+            print_paths(
+                "Would reindex",
+                &reindexed_paths(&bundle.files, existing.as_ref()),
+            );
+            let plan = topo_index::plan_save_repo(
+                &index,
+                root,
+                &repo.prefix,
+                branch.as_deref(),
+                config.index.compression_level,
+            )?;
+            print_plan(root, &plan);
         }
+        return Ok(0);
+    }
+    topo_index::save_repo(
+        &index,
+        root,
+        &repo.prefix,
+        branch.as_deref(),
+        config.index.compression_level,
+    )?;
+    record_index(root, &index, reindexed);
+    if !cli.is_quiet() {
+        eprintln!(
+            "Repaired {} problems{label} ({reindexed} files reindexed)",
+            report.issues.len()
+        );
     }
+    Ok(0)
+}
+
+/// Remove entries for deleted files from one repository's deep index and
+/// save it, if anything was pruned.
+fn prune_repo(
+    cli: &Cli,
+    root: &Path,
+    repo: &RepoBundle,
+    options: &IndexOptions,
+    config: &Config,
+) -> Result<()> {
+    let label = repo_label(&repo.prefix);
+    let branch = current_branch(&repo.bundle.root);
+    let Some(mut index) = topo_index::load_repo(root, &repo.prefix, branch.as_deref())? else {
+        if !cli.is_quiet() {
+            eprintln!("No deep index to prune{label}");
+        }
+        return Ok(());
+    };
 
+    let sparse = sparse_paths(&repo.bundle.root);
+    let pruned = topo_index::prune(&mut index, &repo.bundle.root, &sparse);
+    if options.dry_run {
+        if !cli.is_quiet() {
+            let paths: Vec<&str> = pruned.iter().map(String::as_str).collect();
+            print_paths("Would prune", &paths);
+            if !pruned.is_empty() {
+                let plan = topo_index::plan_save_repo(
+                    &index,
+                    root,
+                    &repo.prefix,
+                    branch.as_deref(),
+                    config.index.compression_level,
+                )?;
+                print_plan(root, &plan);
+            }
+        }
+        return Ok(());
+    }
+    if !pruned.is_empty() {
+        topo_index::save_repo(
+            &index,
+            root,
+            &repo.prefix,
+            branch.as_deref(),
+            config.index.compression_level,
+        )?;
+    }
     if !cli.is_quiet() {
-        eprintln!("Done.");
+        eprintln!(
+            "Pruned {} deleted files{label} ({} files remain indexed)",
+            pruned.len(),
+            index.total_docs
+        );
+    }
+    Ok(())
+}
+
+/// Formats for `topo index export`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    Json,
+    Sqlite,
+}
+
+/// Export the current branch's deep index of the repository as JSON or
+/// SQLite, see [`topo_index::export_json`] and [`topo_index::export_sqlite`].
+pub fn export(cli: &Cli, format: ExportFormat, output: Option<&Path>) -> Result<()> {
+    let root = cli.repo_root()?;
+    let branch = current_branch(&root);
+    let Some(index) = topo_index::load_repo(&root, "", branch.as_deref())? else {
+        return Err(TopoError::index_missing("no deep index to export")
+            .with_path(&root)
+            .into());
+    };
+
+    match (format, output) {
+        (ExportFormat::Json, Some(path)) => {
+            let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+            topo_index::export_json(&index, &mut file)?;
+            file.flush()?;
+        }
+        (ExportFormat::Json, None) => {
+            topo_index::export_json(&index, &mut std::io::stdout().lock())?;
+        }
+        (ExportFormat::Sqlite, Some(path)) => topo_index::export_sqlite(&index, path)?,
+        (ExportFormat::Sqlite, None) => {
+            anyhow::bail!("SQLite exports need a file. Pass --output FILE.")
+        }
     }
 
+    if !cli.is_quiet()
+        && let Some(path) = output
+    {
+        eprintln!(
+            "Exported {} indexed files to {}",
+            index.files.len(),
+            path.display()
+        );
+    }
     Ok(())
 }
+
+/// Files `IndexBuilder::build` would reindex rather than carry over from
+/// `existing`: new and changed files that aren't renames.
+fn reindexed_paths<'a>(files: &'a [FileInfo], existing: Option<&DeepIndex>) -> Vec<&'a str> {
+    let Some(existing) = existing else {
+        return files.iter().map(|f| f.path.as_str()).collect();
+    };
+    let present: HashSet<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    let moved: HashSet<[u8; 32]> = existing
+        .files
+        .iter()
+        .filter(|(path, _)| !present.contains(path.as_str()))
+        .map(|(_, entry)| entry.sha256)
+        .collect();
+    let mut paths: Vec<&str> = files
+        .iter()
+        .filter(|f| match existing.files.get(&f.path) {
+            Some(entry) => entry.sha256 != f.sha256,
+            None => !moved.contains(&f.sha256),
+        })
+        .map(|f| f.path.as_str())
+        .collect();
+    paths.sort_unstable();
+    paths
+}
+
+/// Indexed files gone from `files`, other than renames.
+fn removed_paths<'a>(
+    files: &[FileInfo],
+    existing: &'a DeepIndex,
+    sparse: &HashSet<String>,
+) -> Vec<&'a str> {
+    let present: HashSet<&str> = files
+        .iter()
+        .map(|f| f.path.as_str())
+        .chain(sparse.iter().map(String::as_str))
+        .collect();
+    let hashes: HashSet<[u8; 32]> = files.iter().map(|f| f.sha256).collect();
+    let mut paths: Vec<&str> = existing
+        .files
+        .iter()
+        .filter(|(path, entry)| !present.contains(path.as_str()) && !hashes.contains(&entry.sha256))
+        .map(|(path, _)| path.as_str())
+        .collect();
+    paths.sort_unstable();
+    paths
+}
+
+/// Print `action` with the number of `paths`, then the paths.
+fn print_paths(action: &str, paths: &[&str]) {
+    eprintln!(
+        "{action} {} {}",
+        paths.len(),
+        if paths.len() == 1 { "file" } else { "files" }
+    );
+    for path in paths {
+        eprintln!("  {}", display_path(path));
+    }
+}
+
+/// Print the files a save would change, relative to `root`.
+fn print_plan(root: &Path, plan: &topo_index::SavePlan) {
+    if plan.is_empty() {
+        eprintln!("No index files would change");
+    }
+    let groups = [
+        ("create", &plan.created),
+        ("overwrite", &plan.overwritten),
+        ("delete", &plan.deleted),
+    ];
+    for (action, files) in groups {
+        for file in files {
+            let path = file.path.strip_prefix(root).unwrap_or(&file.path);
+            eprintln!("Would {action} {} ({} bytes)", path.display(), file.bytes);
+        }
+    }
+}
+
+/// Suffix naming a nested repository in messages, empty for the root.
+fn repo_label(prefix: &str) -> String {
+    if prefix.is_empty() {
+        String::new()
+    } else {
+        format!(" [{prefix}]")
+    }
+}
+
+/// Add an index build to the local usage stats. Best-effort, like
+/// [`super::query::record_query`].
+pub(crate) fn record_index(root: &Path, index: &DeepIndex, reindexed: usize) {
+    let reused = (index.total_docs as usize).saturating_sub(reindexed);
+    let _ = topo_index::record_usage(root, |stats| stats.record_index(reused, reindexed));
+}
+
+/// Branch checked out in `repo_root`, to key its index by. `None` outside
+/// version control or on a detached HEAD.
+pub(crate) fn current_branch(repo_root: &Path) -> Option<String> {
+    topo_vcs::detect(repo_root)?.branch().ok().flatten()
+}
+
+/// Tracked files a sparse checkout leaves out of `repo_root`. They aren't
+/// scanned, but aren't deleted either, so their entries stay indexed.
+fn sparse_paths(repo_root: &Path) -> HashSet<String> {
+    topo_vcs::detect(repo_root)
+        .and_then(|vcs| vcs.sparse_paths().ok().flatten())
+        .unwrap_or_default()
+        .into_iter()
+        .collect()
+}
+
+/// Whether `old` was built by this topo from the same scan as `new`. If not,
+/// `new` is saved even when no file changed, so its [`topo_core::IndexMeta`]
+/// stays truthful.
+pub(crate) fn same_provenance(old: &DeepIndex, new: &DeepIndex) -> bool {
+    let source = |index: &DeepIndex| {
+        index
+            .meta
+            .as_ref()
+            .map(|meta| (meta.tool_version.clone(), meta.fingerprint.clone()))
+    };
+    source(old) == source(new)
+}
+
+/// Whether building `index` pruned any term postings, which changes it even
+/// when no file did, e.g. after `[index.prune]` was tightened.
+pub(crate) fn pruned_anything(index: &DeepIndex) -> bool {
+    index
+        .meta
+        .as_ref()
+        .and_then(|meta| meta.pruned.as_ref())
+        .is_some_and(|pruned| pruned.postings() > 0)
+}
+
+/// One line on what `[index.prune]` left out of an index.
+pub(crate) fn prune_summary(pruned: &PruneReport) -> String {
+    let mb = |bytes: u64| bytes as f64 / 1_048_576.0;
+    format!(
+        "Pruned {} term postings ({} generated-file hapaxes, {} over the cap of {} terms, {} for the size budget); postings now ~{:.1} MB of ~{:.1} MB",
+        pruned.postings(),
+        pruned.generated_hapax,
+        pruned.capped_postings,
+        pruned.capped_terms,
+        pruned.budget_postings,
+        mb(pruned.bytes_after),
+        mb(pruned.bytes_before)
+    )
+}
+
+/// Whether the last saved index of the repository at `prefix` was built on
+/// `branch`. If not, it must be saved again even when nothing changed, so
+/// queries see this branch's index.
+pub(crate) fn is_active_branch(root: &Path, prefix: &str, branch: Option<&str>) -> Result<bool> {
+    let active = topo_index::open_repo(root, prefix)?;
+    Ok(active.is_some_and(|index| index.branch() == branch))
+}
@@ -11,26 +11,48 @@ const TOPO_CONTEXT_SH: &str = include_str!("../../templates/topo-context.sh");
 const TOPO_HINT_SH: &str = include_str!("../../templates/topo-hint.sh");
 const TOPO_TRACK_SH: &str = include_str!("../../templates/topo-track.sh");
 
+/// Claude Code settings file, relative to the repository root.
+const SETTINGS_JSON: &str = ".claude/settings.json";
+
 enum WriteResult {
     Created,
     Skipped,
 }
 
-fn write_template(path: &Path, content: &str, force: bool) -> Result<WriteResult> {
-    if path.exists() && !force {
+/// Contents to write to a template file, None to leave it alone.
+fn plan_template(path: &Path, content: &str, force: bool) -> Option<String> {
+    (force || !path.exists()).then(|| content.to_string())
+}
+
+/// Write `content` to `path` if there is any, creating parent dirs.
+fn apply(path: &Path, content: Option<String>, executable: bool) -> Result<WriteResult> {
+    let Some(content) = content else {
         return Ok(WriteResult::Skipped);
-    }
+    };
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
     }
     fs::write(path, content)?;
+
+    // Set executable permission on Unix
+    #[cfg(unix)]
+    if executable {
+        use std::os::unix::fs::PermissionsExt;
+        let perms = fs::Permissions::from_mode(0o755);
+        fs::set_permissions(path, perms)?;
+    }
+    #[cfg(not(unix))]
+    let _ = executable;
+
     Ok(WriteResult::Created)
 }
 
 const TOPO_START: &str = "<!-- topo:start -->";
 const TOPO_END: &str = "<!-- topo:end -->";
 
-fn inject_claude_md(path: &Path, section: &str, force: bool) -> Result<WriteResult> {
+/// Contents of `path` with the topo section injected, None to leave it
+/// alone.
+fn plan_claude_md(path: &Path, section: &str, force: bool) -> Result<Option<String>> {
     let content = if path.exists() {
         fs::read_to_string(path)?
     } else {
@@ -39,7 +61,7 @@ fn inject_claude_md(path: &Path, section: &str, force: bool) -> Result<WriteResu
 
     if let Some(start) = content.find(TOPO_START) {
         if !force {
-            return Ok(WriteResult::Skipped);
+            return Ok(None);
         }
         // Replace existing section (inclusive of markers)
         let end = content[start..]
@@ -56,10 +78,10 @@ fn inject_claude_md(path: &Path, section: &str, force: bool) -> Result<WriteResu
         } else {
             new_content.push('\n');
         }
-        fs::write(path, new_content)?;
+        Ok(Some(new_content))
     } else if content.is_empty() {
         // New file — just write the section
-        fs::write(path, section)?;
+        Ok(Some(section.to_string()))
     } else {
         // Existing file without markers — append
         let mut new_content = content;
@@ -68,37 +90,15 @@ fn inject_claude_md(path: &Path, section: &str, force: bool) -> Result<WriteResu
         }
         new_content.push('\n');
         new_content.push_str(section);
-        fs::write(path, new_content)?;
-    }
-
-    Ok(WriteResult::Created)
-}
-
-/// Write a hook script, creating parent dirs and setting executable permissions.
-fn write_hook(path: &Path, content: &str, force: bool) -> Result<WriteResult> {
-    if path.exists() && !force {
-        return Ok(WriteResult::Skipped);
-    }
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
-    }
-    fs::write(path, content)?;
-
-    // Set executable permission on Unix
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let perms = fs::Permissions::from_mode(0o755);
-        fs::set_permissions(path, perms)?;
+        Ok(Some(new_content))
     }
-
-    Ok(WriteResult::Created)
 }
 
-/// Patch `.claude/settings.json` to register topo hooks.
-/// Merges hook entries into existing settings without destroying user config.
-fn patch_claude_settings(root: &Path, force: bool) -> Result<WriteResult> {
-    let settings_path = root.join(".claude/settings.json");
+/// Contents of `.claude/settings.json` with topo's hooks registered, None
+/// to leave it alone. Merges hook entries into existing settings without
+/// destroying user config.
+fn plan_claude_settings(root: &Path, force: bool) -> Result<Option<String>> {
+    let settings_path = root.join(SETTINGS_JSON);
     let mut settings: serde_json::Value = if settings_path.exists() {
         let content = fs::read_to_string(&settings_path)?;
         serde_json::from_str(&content).unwrap_or_else(|_| serde_json::json!({}))
@@ -111,7 +111,7 @@ fn patch_claude_settings(root: &Path, force: bool) -> Result<WriteResult> {
         && let Some(hooks) = settings.get("hooks")
         && (hooks.get("UserPromptSubmit").is_some() || hooks.get("PreToolUse").is_some())
     {
-        return Ok(WriteResult::Skipped);
+        return Ok(None);
     }
 
     // Build the hook configuration
@@ -152,14 +152,8 @@ fn patch_claude_settings(root: &Path, force: bool) -> Result<WriteResult> {
         settings["hooks"] = topo_hooks;
     }
 
-    // Write back
-    if let Some(parent) = settings_path.parent() {
-        fs::create_dir_all(parent)?;
-    }
     let formatted = serde_json::to_string_pretty(&settings)?;
-    fs::write(&settings_path, formatted + "\n")?;
-
-    Ok(WriteResult::Created)
+    Ok(Some(formatted + "\n"))
 }
 
 fn check_topo_on_path() {
@@ -196,80 +190,93 @@ fn check_topo_on_path() {
     println!("See https://github.com/demwunz/topo#mcp for setup instructions.");
 }
 
-pub fn run(cli: &Cli, force: bool, hooks: bool) -> Result<()> {
-    let root = cli.repo_root()?;
-    let quiet = cli.is_quiet();
+/// One file `topo init` sets up.
+struct Step {
+    /// Path relative to the repository root.
+    path: &'static str,
+    /// New contents, None when the file is left alone.
+    content: Option<String>,
+    executable: bool,
+    /// What was done, e.g. "Created AGENTS.md".
+    done: String,
+    /// Why the file was left alone.
+    skipped: &'static str,
+}
 
-    // AGENTS.md at repo root
-    let agents_path = root.join("AGENTS.md");
-    match write_template(&agents_path, AGENTS_MD, force)? {
-        WriteResult::Created => {
-            if !quiet {
-                println!("  Created AGENTS.md");
-            }
+impl Step {
+    fn template(root: &Path, path: &'static str, content: &str, force: bool) -> Self {
+        Self {
+            path,
+            content: plan_template(&root.join(path), content, force),
+            executable: false,
+            done: format!("Created {path}"),
+            skipped: "already exists, use --force to overwrite",
         }
-        WriteResult::Skipped => {
-            if !quiet {
-                println!("  Skipped AGENTS.md (already exists, use --force to overwrite)");
-            }
+    }
+
+    fn hook(root: &Path, path: &'static str, content: &str, force: bool) -> Self {
+        Self {
+            executable: true,
+            ..Self::template(root, path, content, force)
         }
     }
 
-    // .cursor/rules/topo.md
-    let cursor_path = root.join(".cursor/rules/topo.md");
-    match write_template(&cursor_path, CURSOR_TOPO_MD, force)? {
-        WriteResult::Created => {
-            if !quiet {
-                println!("  Created .cursor/rules/topo.md");
-            }
+    /// Write the file, or with `dry_run` say what writing it would do.
+    fn run(self, root: &Path, dry_run: bool, quiet: bool) -> Result<()> {
+        let path = root.join(self.path);
+        let message = match (&self.content, dry_run) {
+            (None, _) => format!("Skipped {} ({})", self.path, self.skipped),
+            (Some(content), true) => format!(
+                "Would {} {} ({} bytes)",
+                if path.exists() { "overwrite" } else { "create" },
+                self.path,
+                content.len()
+            ),
+            (Some(_), false) => self.done,
+        };
+        if !dry_run {
+            apply(&path, self.content, self.executable)?;
         }
-        WriteResult::Skipped => {
-            if !quiet {
-                println!(
-                    "  Skipped .cursor/rules/topo.md (already exists, use --force to overwrite)"
-                );
-            }
+        if !quiet {
+            println!("  {message}");
         }
+        Ok(())
     }
+}
+
+pub fn run(cli: &Cli, force: bool, hooks: bool, dry_run: bool) -> Result<()> {
+    let root = cli.repo_root()?;
+    let quiet = cli.is_quiet();
+
+    // AGENTS.md at repo root
+    Step::template(&root, "AGENTS.md", AGENTS_MD, force).run(&root, dry_run, quiet)?;
+
+    // .cursor/rules/topo.md
+    Step::template(&root, ".cursor/rules/topo.md", CURSOR_TOPO_MD, force)
+        .run(&root, dry_run, quiet)?;
 
     // .github/copilot-instructions.md (only if .github/ exists)
-    let github_dir = root.join(".github");
-    if github_dir.is_dir() {
-        let copilot_path = github_dir.join("copilot-instructions.md");
-        match write_template(&copilot_path, COPILOT_INSTRUCTIONS_MD, force)? {
-            WriteResult::Created => {
-                if !quiet {
-                    println!("  Created .github/copilot-instructions.md");
-                }
-            }
-            WriteResult::Skipped => {
-                if !quiet {
-                    println!(
-                        "  Skipped .github/copilot-instructions.md (already exists, use --force to overwrite)"
-                    );
-                }
-            }
-        }
+    if root.join(".github").is_dir() {
+        Step::template(
+            &root,
+            ".github/copilot-instructions.md",
+            COPILOT_INSTRUCTIONS_MD,
+            force,
+        )
+        .run(&root, dry_run, quiet)?;
     } else if !quiet {
         println!("  Skipped .github/copilot-instructions.md (no .github/ directory)");
     }
 
     // CLAUDE.md — inject topo section (never overwrite user content)
-    let claude_path = root.join("CLAUDE.md");
-    match inject_claude_md(&claude_path, CLAUDE_MD_SECTION, force)? {
-        WriteResult::Created => {
-            if !quiet {
-                println!("  Created CLAUDE.md (topo section)");
-            }
-        }
-        WriteResult::Skipped => {
-            if !quiet {
-                println!(
-                    "  Skipped CLAUDE.md (topo section already present, use --force to update)"
-                );
-            }
-        }
+    Step {
+        path: "CLAUDE.md",
+        content: plan_claude_md(&root.join("CLAUDE.md"), CLAUDE_MD_SECTION, force)?,
+        executable: false,
+        done: "Created CLAUDE.md (topo section)".to_string(),
+        skipped: "topo section already present, use --force to update",
     }
+    .run(&root, dry_run, quiet)?;
 
     // Claude Code hooks (--hooks, on by default)
     if hooks {
@@ -278,74 +285,32 @@ pub fn run(cli: &Cli, force: bool, hooks: bool) -> Result<()> {
             println!("Claude Code hooks:");
         }
 
-        let hooks_dir = root.join(".claude/hooks");
-        let context_path = hooks_dir.join("topo-context.sh");
-        match write_hook(&context_path, TOPO_CONTEXT_SH, force)? {
-            WriteResult::Created => {
-                if !quiet {
-                    println!("  Created .claude/hooks/topo-context.sh");
-                }
-            }
-            WriteResult::Skipped => {
-                if !quiet {
-                    println!(
-                        "  Skipped .claude/hooks/topo-context.sh (already exists, use --force to overwrite)"
-                    );
-                }
-            }
+        let scripts = [
+            (".claude/hooks/topo-context.sh", TOPO_CONTEXT_SH),
+            (".claude/hooks/topo-hint.sh", TOPO_HINT_SH),
+            (".claude/hooks/topo-track.sh", TOPO_TRACK_SH),
+        ];
+        for (path, content) in scripts {
+            Step::hook(&root, path, content, force).run(&root, dry_run, quiet)?;
         }
 
-        let hint_path = hooks_dir.join("topo-hint.sh");
-        match write_hook(&hint_path, TOPO_HINT_SH, force)? {
-            WriteResult::Created => {
-                if !quiet {
-                    println!("  Created .claude/hooks/topo-hint.sh");
-                }
-            }
-            WriteResult::Skipped => {
-                if !quiet {
-                    println!(
-                        "  Skipped .claude/hooks/topo-hint.sh (already exists, use --force to overwrite)"
-                    );
-                }
-            }
-        }
-
-        let track_path = hooks_dir.join("topo-track.sh");
-        match write_hook(&track_path, TOPO_TRACK_SH, force)? {
-            WriteResult::Created => {
-                if !quiet {
-                    println!("  Created .claude/hooks/topo-track.sh");
-                }
-            }
-            WriteResult::Skipped => {
-                if !quiet {
-                    println!(
-                        "  Skipped .claude/hooks/topo-track.sh (already exists, use --force to overwrite)"
-                    );
-                }
-            }
-        }
-
-        match patch_claude_settings(&root, force)? {
-            WriteResult::Created => {
-                if !quiet {
-                    println!("  Patched .claude/settings.json (hook registration)");
-                }
-            }
-            WriteResult::Skipped => {
-                if !quiet {
-                    println!(
-                        "  Skipped .claude/settings.json (hooks already registered, use --force to update)"
-                    );
-                }
-            }
+        Step {
+            path: SETTINGS_JSON,
+            content: plan_claude_settings(&root, force)?,
+            executable: false,
+            done: format!("Patched {SETTINGS_JSON} (hook registration)"),
+            skipped: "hooks already registered, use --force to update",
         }
+        .run(&root, dry_run, quiet)?;
     }
 
     if !quiet {
         println!();
-        check_topo_on_path();
+        if dry_run {
+            println!("Dry run: nothing was written.");
+        } else {
+            check_topo_on_path();
+        }
     }
 
     Ok(())
@@ -356,6 +321,26 @@ mod tests {
     use super::*;
     use tempfile::tempdir;
 
+    fn write_template(path: &Path, content: &str, force: bool) -> Result<WriteResult> {
+        apply(path, plan_template(path, content, force), false)
+    }
+
+    fn write_hook(path: &Path, content: &str, force: bool) -> Result<WriteResult> {
+        apply(path, plan_template(path, content, force), true)
+    }
+
+    fn inject_claude_md(path: &Path, section: &str, force: bool) -> Result<WriteResult> {
+        apply(path, plan_claude_md(path, section, force)?, false)
+    }
+
+    fn patch_claude_settings(root: &Path, force: bool) -> Result<WriteResult> {
+        apply(
+            &root.join(SETTINGS_JSON),
+            plan_claude_settings(root, force)?,
+            false,
+        )
+    }
+
     #[test]
     fn templates_are_non_empty() {
         assert!(!AGENTS_MD.is_empty());
@@ -507,6 +492,28 @@ mod tests {
         assert!(matches!(result, WriteResult::Skipped));
     }
 
+    #[test]
+    fn dry_run_writes_nothing() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("AGENTS.md"), "mine").unwrap();
+        let step = Step::template(dir.path(), "AGENTS.md", AGENTS_MD, true);
+        assert_eq!(step.content.as_deref(), Some(AGENTS_MD));
+        step.run(dir.path(), true, true).unwrap();
+        Step::hook(
+            dir.path(),
+            ".claude/hooks/topo-hint.sh",
+            TOPO_HINT_SH,
+            false,
+        )
+        .run(dir.path(), true, true)
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("AGENTS.md")).unwrap(),
+            "mine"
+        );
+        assert!(!dir.path().join(".claude").exists());
+    }
+
     #[test]
     fn inject_claude_md_force_replaces() {
         let dir = tempdir().unwrap();
@@ -1,69 +1,122 @@
 use crate::Cli;
 use anyhow::Result;
+use topo_core::{IndexMeta, TopoError, display_path};
+use topo_index::IndexStats;
 
 pub fn run(cli: &Cli) -> Result<()> {
     let root = cli.repo_root()?;
     let index_path = topo_index::index_path(&root);
 
-    if !index_path.exists() {
-        anyhow::bail!(
-            "No index found at {}. Run `topo index --deep` first.",
-            index_path.display()
-        );
-    }
-
-    let metadata = std::fs::metadata(&index_path)?;
-    let file_size = metadata.len();
-
-    let index = topo_index::load(&root)?.ok_or_else(|| anyhow::anyhow!("Failed to load index"))?;
+    let sharded = topo_index::open_repo(&root, "")?.ok_or_else(|| {
+        TopoError::index_missing("no deep index to inspect").with_path(&index_path)
+    })?;
+    let shard_count = sharded.shard_count();
+    let branch = sharded.branch().unwrap_or("(unknown)").to_string();
 
-    // Collect language stats
-    let mut lang_counts: std::collections::HashMap<String, usize> =
-        std::collections::HashMap::new();
-    let mut total_chunks: usize = 0;
-    let mut total_terms: usize = 0;
-
-    for entry in index.files.values() {
-        total_chunks += entry.chunks.len();
-        total_terms += entry.term_frequencies.len();
-    }
-
-    // Count files by extension
-    for path in index.files.keys() {
-        let ext = std::path::Path::new(path)
-            .extension()
-            .and_then(|e| e.to_str())
-            .unwrap_or("(none)");
-        *lang_counts.entry(ext.to_string()).or_default() += 1;
-    }
+    let index = sharded
+        .into_index()
+        .ok_or_else(|| TopoError::index("failed to load index").with_path(&index_path))?;
+    let stats = IndexStats::compute(&index).with_disk_size(&index_path);
+    let file_size = stats.size_on_disk.unwrap_or_default();
 
     println!("Index: {}", index_path.display());
-    println!("Format: rkyv binary");
+    println!("Format: rkyv binary, {} shards", shard_count);
     println!(
         "Size: {:.1} MB ({} bytes)",
         file_size as f64 / 1_048_576.0,
         file_size
     );
-    println!("Version: {}", index.version);
-    println!("Files: {}", index.total_docs);
-    println!("Chunks: {}", total_chunks);
-    println!("Unique terms: {}", index.doc_frequencies.len());
-    println!("Terms (file-level): {}", total_terms);
-    println!("Avg doc length: {:.1}", index.avg_doc_length);
+    println!("Version: {}", stats.version);
+    println!("Branch: {}", branch);
+    match &index.meta {
+        Some(meta) => print_meta(meta),
+        None => println!("Built: (not recorded)"),
+    }
+    println!("Files: {}", stats.files);
+    println!("Chunks: {}", stats.chunks);
+    println!("Unique terms: {}", stats.unique_terms);
+    println!("Terms (file-level): {}", stats.file_terms);
+    match stats.chunk_terms {
+        Some(chunk_terms) => println!("Terms (chunk-level): {}", chunk_terms),
+        None => println!("Terms (chunk-level): (not built)"),
+    }
+    if let Some(pruned) = index.meta.as_ref().and_then(|meta| meta.pruned.as_ref()) {
+        println!("{}", super::index::prune_summary(pruned));
+    }
+    println!("Avg doc length: {:.1}", stats.avg_doc_length);
+    println!("Dependency edges: {}", stats.dependency_edges);
+    match stats.trigram_terms {
+        Some(terms) => println!("Trigram terms: {}", terms),
+        None => println!("Trigram terms: (not built)"),
+    }
+    match &index.git_activity {
+        Some(activity) => println!(
+            "Git activity: {} files as of {}",
+            activity.files.len(),
+            &activity.head[..activity.head.len().min(12)]
+        ),
+        None => println!("Git activity: (not recorded)"),
+    }
+    println!("Enriched files: {}", stats.enriched_files);
     println!();
 
     // Top extensions by file count
-    let mut sorted_langs: Vec<_> = lang_counts.into_iter().collect();
-    sorted_langs.sort_by(|a, b| b.1.cmp(&a.1));
-
     println!("Files by extension:");
-    for (ext, count) in sorted_langs.iter().take(15) {
-        println!("  .{ext:<12} {count:>6}");
+    for count in stats.extensions.iter().take(15) {
+        println!("  .{:<12} {:>6}", display_path(&count.name), count.files);
     }
-    if sorted_langs.len() > 15 {
-        let rest: usize = sorted_langs[15..].iter().map(|(_, c)| c).sum();
+    if stats.extensions.len() > 15 {
+        let rest: usize = stats.extensions[15..].iter().map(|c| c.files).sum();
         println!("  (other)       {rest:>6}");
     }
 
+    if index.meta.is_some() {
+        println!();
+        println!("Files by language:");
+        for count in &stats.languages {
+            println!("  {:<13} {:>6}", count.name, count.files);
+        }
+    }
+
     Ok(())
 }
+
+/// When, how, and by which topo the index was built.
+fn print_meta(meta: &IndexMeta) {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    println!(
+        "Built: {} (took {:.1}s)",
+        age(now.saturating_sub(meta.built_at)),
+        meta.build_ms as f64 / 1000.0
+    );
+    println!("Created: {}", age(now.saturating_sub(meta.created_at)));
+    let current = env!("CARGO_PKG_VERSION");
+    if meta.tool_version == current {
+        println!("Built by: topo {}", meta.tool_version);
+    } else {
+        println!(
+            "Built by: topo {} (this is topo {current}; run `topo index --deep --force` to rebuild)",
+            meta.tool_version
+        );
+    }
+    if !meta.fingerprint.is_empty() {
+        println!(
+            "Fingerprint: {}",
+            &meta.fingerprint[..meta.fingerprint.len().min(12)]
+        );
+    }
+}
+
+/// `secs` as a rough age, e.g. `3 hours ago`.
+fn age(secs: u64) -> String {
+    let (count, unit) = match secs {
+        0..60 => return "just now".to_string(),
+        60..3_600 => (secs / 60, "minute"),
+        3_600..86_400 => (secs / 3_600, "hour"),
+        _ => (secs / 86_400, "day"),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    format!("{count} {unit}{plural} ago")
+}
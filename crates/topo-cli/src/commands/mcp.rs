@@ -1,4 +1,5 @@
 use crate::Cli;
+use crate::error::ErrorReport;
 use crate::preset::Preset;
 use anyhow::Result;
 use rmcp::{
@@ -11,7 +12,9 @@ use rmcp::{
     transport::stdio,
 };
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use topo_core::TopoError;
 
 // ---------------------------------------------------------------------------
 // Parameter structs
@@ -24,7 +27,9 @@ struct QueryParams {
     task: String,
 
     /// Scoring preset: fast, balanced, deep, thorough
-    #[schemars(description = "Scoring preset: fast, balanced, deep, thorough (default: balanced)")]
+    #[schemars(
+        description = "Scoring preset: fast, balanced, deep, thorough (default: query.preset in .topo.toml, else balanced)"
+    )]
     preset: Option<String>,
 
     /// Maximum bytes for token budget
@@ -42,6 +47,24 @@ struct QueryParams {
     /// Return only the top N files
     #[schemars(description = "Return only the top N files")]
     top: Option<usize>,
+
+    /// Only consider files whose index metadata has these key-value pairs
+    #[schemars(
+        description = "Only consider files whose index metadata has all of these key-value pairs (needs an enriched deep index)"
+    )]
+    meta: Option<BTreeMap<String, String>>,
+
+    /// Mark query terms in previews and list matching line numbers
+    #[schemars(
+        description = "Mark query terms in previews with «» and list the matching line numbers of each file (default: false)"
+    )]
+    highlight: Option<bool>,
+
+    /// Return only the lines around each query match
+    #[schemars(
+        description = "Return only this many lines around each query match instead of whole files; files without a match are dropped and only the returned lines count against the budget"
+    )]
+    context: Option<u32>,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
@@ -55,7 +78,9 @@ struct ExplainParams {
     top: Option<usize>,
 
     /// Scoring preset: fast, balanced, deep, thorough
-    #[schemars(description = "Scoring preset: fast, balanced, deep, thorough (default: balanced)")]
+    #[schemars(
+        description = "Scoring preset: fast, balanced, deep, thorough (default: query.preset in .topo.toml, else balanced)"
+    )]
     preset: Option<String>,
 }
 
@@ -80,12 +105,14 @@ pub struct TopoServer {
     tool_router: ToolRouter<TopoServer>,
 }
 
-fn parse_preset(s: Option<&str>) -> Preset {
+/// The preset named `s`, or the `query.preset` of `config` without one.
+fn parse_preset(s: Option<&str>, config: &topo_core::Config) -> Preset {
     match s {
         Some("fast") => Preset::Fast,
         Some("deep") => Preset::Deep,
         Some("thorough") => Preset::Thorough,
-        _ => Preset::Balanced,
+        Some(_) => Preset::Balanced,
+        None => Preset::from_config(config),
     }
 }
 
@@ -95,41 +122,82 @@ fn parse_preset(s: Option<&str>) -> Preset {
 
 impl TopoServer {
     fn do_query(&self, params: QueryParams) -> Result<serde_json::Value> {
-        let preset = parse_preset(params.preset.as_deref());
+        let config = topo_core::Config::load(&self.root)?;
+        let preset = parse_preset(params.preset.as_deref(), &config);
 
         // Auto-index if preset requires it
         if preset.needs_deep_index() {
             self.do_index_inner(true, preset.force_rebuild())?;
         }
 
-        let bundle = topo_scanner::BundleBuilder::new(&self.root).build()?;
-
-        let deep_index = if preset.use_structural_signals() {
-            topo_index::load(&self.root)?
-        } else {
-            None
-        };
-
-        let scored =
-            super::query::score_files(&params.task, &bundle.files, preset, deep_index.as_ref());
+        let started = std::time::Instant::now();
+        let meta: Vec<(String, String)> = params.meta.unwrap_or_default().into_iter().collect();
+        let needs_index = preset.use_structural_signals() || !meta.is_empty();
+        let federation = super::query::Federation::scan(&self.root, &config, needs_index)?;
+        if !meta.is_empty() && federation.root_index().is_none() {
+            return Err(topo_core::TopoError::index_missing(
+                "meta filters need a deep index; run topo_index first",
+            )
+            .into());
+        }
+        let query = topo_score::ParsedQuery::parse(&params.task);
+        let (scored, _) = federation.score(
+            &query.text,
+            preset,
+            super::query::Matching {
+                penalties: &query.penalties,
+                ..Default::default()
+            },
+            &meta,
+            &[],
+            &query.filters,
+        );
 
         let effective_min_score = params.min_score.unwrap_or(preset.default_min_score());
         let mut filtered: Vec<topo_core::ScoredFile> = scored
-            .into_iter()
+            .iter()
             .filter(|f| f.score >= effective_min_score)
+            .cloned()
             .collect();
+        if let Some(lambda) = config.query.mmr_lambda {
+            federation.diversify(lambda, &mut filtered);
+        }
 
         if let Some(n) = params.top {
             filtered.truncate(n);
         }
+        let context = match params.context {
+            Some(lines) => {
+                super::query::context_windows(&self.root, &params.task, &[], &mut filtered, lines)
+            }
+            None => std::collections::HashMap::new(),
+        };
 
         let effective_max_bytes = params.max_bytes.unwrap_or(preset.default_max_bytes());
         let budget = topo_core::TokenBudget {
             max_bytes: Some(effective_max_bytes),
             max_tokens: params.max_tokens,
         };
-        let budgeted = budget.enforce(&filtered);
-
+        let (mut budgeted, orientation) =
+            super::query::select(&budget, config.budget.orientation, &filtered, &scored);
+        let matches = super::query::add_previews(
+            &self.root,
+            &params.task,
+            &[],
+            &mut budgeted,
+            |path| federation.chunks(path),
+            params.highlight.unwrap_or(false),
+        );
+
+        let mut environment = super::query::environment(
+            preset,
+            &config,
+            federation.root(),
+            federation
+                .root_index()
+                .filter(|_| preset.use_structural_signals()),
+        );
+        environment.degraded = super::query::degraded(preset, false, federation.root_index());
         let result = serde_json::json!({
             "query": params.task,
             "preset": preset.as_str(),
@@ -139,16 +207,28 @@ impl TopoServer {
                 "tokens": f.tokens,
                 "language": f.language.as_str(),
                 "role": f.role.as_str(),
+                "preview": f.preview,
+                "matched_lines": matches.lines.get(&f.path),
+                "context": context.get(&f.path),
+                "orientation": orientation.paths.contains(&f.path),
             })).collect::<Vec<_>>(),
+            "tree": orientation.tree,
             "total_selected": budgeted.len(),
-            "total_scanned": bundle.file_count(),
+            "total_scanned": federation.file_count(),
+            "environment": environment,
         });
 
+        super::query::record_query(
+            &self.root,
+            started,
+            needs_index.then(|| federation.root_index().is_some()),
+        );
         Ok(result)
     }
 
     fn do_explain(&self, params: ExplainParams) -> Result<serde_json::Value> {
-        let preset = parse_preset(params.preset.as_deref());
+        let config = topo_core::Config::load(&self.root)?;
+        let preset = parse_preset(params.preset.as_deref(), &config);
         let top = params.top.unwrap_or(10);
 
         let bundle = topo_scanner::BundleBuilder::new(&self.root).build()?;
@@ -159,8 +239,21 @@ impl TopoServer {
             None
         };
 
-        let scored =
-            super::query::score_files(&params.task, &bundle.files, preset, deep_index.as_ref());
+        let options = super::query::ScoringOptions {
+            profile: topo_core::WeightProfile::load(&self.root)?,
+            ..super::query::ScoringOptions::from_config(&config)
+        };
+        let mut scored = super::query::score_files(
+            &params.task,
+            &bundle.files,
+            preset,
+            deep_index.as_ref(),
+            None,
+            &options,
+        );
+        options
+            .normalization
+            .apply(&mut scored, preset.scoring().candidates);
 
         let display_count = top.min(scored.len());
         let results = &scored[..display_count];
@@ -176,6 +269,7 @@ impl TopoServer {
                         "heuristic": f.signals.heuristic,
                         "pagerank": f.signals.pagerank,
                         "git_recency": f.signals.git_recency,
+                        "raw": f.signals.raw,
                     },
                     "tokens": f.tokens,
                     "language": f.language.as_str(),
@@ -187,6 +281,23 @@ impl TopoServer {
         Ok(serde_json::Value::Array(output))
     }
 
+    fn do_inspect(&self) -> Result<serde_json::Value> {
+        let index_path = topo_index::index_path(&self.root);
+        let index = topo_index::open_repo(&self.root, "")?
+            .and_then(|sharded| sharded.into_index())
+            .ok_or_else(|| {
+                TopoError::index_missing("no deep index to inspect").with_path(&index_path)
+            })?;
+        let stats = topo_index::IndexStats::compute(&index).with_disk_size(&index_path);
+        Ok(serde_json::to_value(stats)?)
+    }
+
+    fn do_capabilities(&self) -> Result<serde_json::Value> {
+        Ok(serde_json::to_value(super::capabilities::detect(
+            &self.root,
+        )?)?)
+    }
+
     fn do_index(&self, params: IndexParams) -> Result<serde_json::Value> {
         let deep = params.deep.unwrap_or(true);
         let force = params.force.unwrap_or(false);
@@ -194,31 +305,62 @@ impl TopoServer {
     }
 
     fn do_index_inner(&self, deep: bool, force: bool) -> Result<serde_json::Value> {
-        let bundle = topo_scanner::BundleBuilder::new(&self.root).build()?;
-        let file_count = bundle.file_count();
+        let config = topo_core::Config::load(&self.root)?;
+        let repos = topo_scanner::BundleBuilder::new(&self.root)
+            .config(&config)
+            .build_federated()?;
+        let file_count: usize = repos.iter().map(|r| r.bundle.file_count()).sum();
 
         if deep {
-            let existing = if force {
-                None
-            } else {
-                topo_index::load(&self.root)?
-            };
-
-            let builder = topo_index::IndexBuilder::new(&self.root);
-            let (index, reindexed) = builder.build(&bundle.files, existing.as_ref())?;
-            let is_incremental = existing.is_some();
-            let nothing_changed = is_incremental && reindexed == 0;
-
-            if !nothing_changed {
-                topo_index::save(&index, &self.root)?;
+            let mut is_incremental = true;
+            let mut files_indexed = 0;
+            let mut files_changed = 0;
+            // Each repository keeps its own index, see `topo_index::repo_index_path`
+            for repo in &repos {
+                let branch = super::index::current_branch(&repo.bundle.root);
+                let existing = if force {
+                    None
+                } else {
+                    topo_index::load_repo(&self.root, &repo.prefix, branch.as_deref())?
+                };
+
+                let builder = topo_index::IndexBuilder::new(&repo.bundle.root)
+                    .git_activity(true)
+                    .git_lookback_days(super::query::recency_model(&config).lookback_days)
+                    .fingerprint(&repo.bundle.fingerprint)
+                    .prune(config.index.prune)
+                    .lockfile_bodies(config.index.lockfile_bodies)
+                    .comments(config.index.comments)
+                    .stemming(config.index.stemming);
+                let (index, reindexed) = builder.build(&repo.bundle.files, existing.as_ref())?;
+                is_incremental &= existing.is_some();
+                let nothing_changed = existing.as_ref().is_some_and(|old| {
+                    old.git_activity == index.git_activity
+                        && super::index::same_provenance(old, &index)
+                        && !super::index::pruned_anything(&index)
+                }) && reindexed == 0
+                    && super::index::is_active_branch(&self.root, &repo.prefix, branch.as_deref())?;
+
+                if !nothing_changed {
+                    topo_index::save_repo(
+                        &index,
+                        &self.root,
+                        &repo.prefix,
+                        branch.as_deref(),
+                        config.index.compression_level,
+                    )?;
+                }
+                super::index::record_index(&self.root, &index, reindexed);
+                files_indexed += index.total_docs;
+                files_changed += reindexed;
             }
 
             Ok(serde_json::json!({
                 "status": "ok",
                 "mode": if is_incremental { "incremental" } else { "full" },
                 "files_scanned": file_count,
-                "files_indexed": index.total_docs,
-                "files_changed": reindexed,
+                "files_indexed": files_indexed,
+                "files_changed": files_changed,
             }))
         } else {
             Ok(serde_json::json!({
@@ -255,7 +397,7 @@ impl TopoServer {
         let result = tokio::task::spawn_blocking(move || server.do_query(params))
             .await
             .map_err(|e| McpError::internal_error(format!("join error: {e}"), None))?
-            .map_err(|e| McpError::internal_error(format!("{e:#}"), None))?;
+            .map_err(|e| tool_error(&e))?;
 
         let text = serde_json::to_string_pretty(&result)
             .map_err(|e| McpError::internal_error(format!("{e}"), None))?;
@@ -274,7 +416,7 @@ impl TopoServer {
         let result = tokio::task::spawn_blocking(move || server.do_explain(params))
             .await
             .map_err(|e| McpError::internal_error(format!("join error: {e}"), None))?
-            .map_err(|e| McpError::internal_error(format!("{e:#}"), None))?;
+            .map_err(|e| tool_error(&e))?;
 
         let text = serde_json::to_string_pretty(&result)
             .map_err(|e| McpError::internal_error(format!("{e}"), None))?;
@@ -293,7 +435,39 @@ impl TopoServer {
         let result = tokio::task::spawn_blocking(move || server.do_index(params))
             .await
             .map_err(|e| McpError::internal_error(format!("join error: {e}"), None))?
-            .map_err(|e| McpError::internal_error(format!("{e:#}"), None))?;
+            .map_err(|e| tool_error(&e))?;
+
+        let text = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(format!("{e}"), None))?;
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        name = "topo_inspect",
+        description = "Report statistics of the deep index: files, chunks, terms, files per language and extension, and size on disk."
+    )]
+    async fn topo_inspect(&self) -> Result<CallToolResult, McpError> {
+        let server = self.clone();
+        let result = tokio::task::spawn_blocking(move || server.do_inspect())
+            .await
+            .map_err(|e| McpError::internal_error(format!("join error: {e}"), None))?
+            .map_err(|e| tool_error(&e))?;
+
+        let text = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(format!("{e}"), None))?;
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        name = "topo_capabilities",
+        description = "Report which optional subsystems are available and active for this repository (deep index, tree-sitter, embeddings, git, daemon, language servers, remote), with the fallback used when one is missing."
+    )]
+    async fn topo_capabilities(&self) -> Result<CallToolResult, McpError> {
+        let server = self.clone();
+        let result = tokio::task::spawn_blocking(move || server.do_capabilities())
+            .await
+            .map_err(|e| McpError::internal_error(format!("join error: {e}"), None))?
+            .map_err(|e| tool_error(&e))?;
 
         let text = serde_json::to_string_pretty(&result)
             .map_err(|e| McpError::internal_error(format!("{e}"), None))?;
@@ -356,22 +530,45 @@ pub fn run(cli: &Cli) -> Result<()> {
 // Tests
 // ---------------------------------------------------------------------------
 
+/// An MCP error for a failed tool call, with the code and hint of the
+/// failure as data, see [`ErrorReport`].
+fn tool_error(err: &anyhow::Error) -> McpError {
+    let report = ErrorReport::new(err);
+    let data = serde_json::to_value(&report).ok();
+    McpError::internal_error(report.message, data)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use topo_core::Config;
 
     #[test]
     fn parse_preset_defaults_to_balanced() {
-        assert!(matches!(parse_preset(None), Preset::Balanced));
-        assert!(matches!(parse_preset(Some("unknown")), Preset::Balanced));
+        let config = Config::default();
+        assert!(matches!(parse_preset(None, &config), Preset::Balanced));
+        assert!(matches!(
+            parse_preset(Some("unknown"), &config),
+            Preset::Balanced
+        ));
+        let config = Config::parse("[query]\npreset = \"fast\"\n").unwrap();
+        assert!(matches!(parse_preset(None, &config), Preset::Fast));
+        assert!(matches!(parse_preset(Some("deep"), &config), Preset::Deep));
     }
 
     #[test]
     fn parse_preset_recognizes_all_variants() {
-        assert!(matches!(parse_preset(Some("fast")), Preset::Fast));
-        assert!(matches!(parse_preset(Some("balanced")), Preset::Balanced));
-        assert!(matches!(parse_preset(Some("deep")), Preset::Deep));
-        assert!(matches!(parse_preset(Some("thorough")), Preset::Thorough));
+        let config = Config::default();
+        assert!(matches!(parse_preset(Some("fast"), &config), Preset::Fast));
+        assert!(matches!(
+            parse_preset(Some("balanced"), &config),
+            Preset::Balanced
+        ));
+        assert!(matches!(parse_preset(Some("deep"), &config), Preset::Deep));
+        assert!(matches!(
+            parse_preset(Some("thorough"), &config),
+            Preset::Thorough
+        ));
     }
 
     #[test]
@@ -387,11 +584,166 @@ mod tests {
             max_tokens: None,
             min_score: None,
             top: None,
+            meta: None,
+            highlight: None,
+            context: None,
         };
 
         let result = server.do_query(params).unwrap();
         assert!(result.get("files").unwrap().is_array());
         assert!(result.get("total_scanned").unwrap().as_u64().unwrap() > 0);
+        assert_eq!(result["environment"]["preset"], "fast");
+        assert_eq!(result["environment"]["config_hash"], "default");
+    }
+
+    #[test]
+    fn do_query_highlights_matches() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("auth.rs"),
+            "use std::io;\n\n// auth guard\nfn check_auth() {}\n",
+        )
+        .unwrap();
+
+        let server = TopoServer::new(dir.path().to_path_buf());
+        let params = QueryParams {
+            task: "auth".to_string(),
+            preset: Some("fast".to_string()),
+            max_bytes: None,
+            max_tokens: None,
+            min_score: None,
+            top: None,
+            meta: None,
+            highlight: Some(true),
+            context: None,
+        };
+
+        let result = server.do_query(params).unwrap();
+        let file = &result["files"][0];
+        assert_eq!(file["path"], "auth.rs");
+        assert_eq!(file["preview"], "// «auth» guard");
+        assert_eq!(file["matched_lines"], serde_json::json!([3, 4]));
+    }
+
+    #[test]
+    fn do_query_returns_context_windows() {
+        let dir = tempfile::tempdir().unwrap();
+        let body: String = (1..=20).map(|n| format!("let x{n} = {n};\n")).collect();
+        std::fs::write(
+            dir.path().join("auth.rs"),
+            format!("{body}fn check_auth() {{}}\n{body}"),
+        )
+        .unwrap();
+
+        let server = TopoServer::new(dir.path().to_path_buf());
+        let params = QueryParams {
+            task: "auth".to_string(),
+            preset: Some("fast".to_string()),
+            max_bytes: None,
+            max_tokens: None,
+            min_score: None,
+            top: None,
+            meta: None,
+            highlight: None,
+            context: Some(1),
+        };
+
+        let result = server.do_query(params).unwrap();
+        let file = &result["files"][0];
+        assert_eq!(
+            file["context"],
+            serde_json::json!([{
+                "start_line": 20,
+                "end_line": 22,
+                "text": "let x20 = 20;\nfn check_auth() {}\nlet x1 = 1;",
+            }])
+        );
+        assert_eq!(file["tokens"], 11);
+    }
+
+    #[test]
+    fn do_query_reserves_budget_for_orientation() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(".topo.toml"),
+            "[budget]\norientation = 0.5\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("README.md"), "# Payments service\n").unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/refund.rs"), "fn refund() {}\n").unwrap();
+
+        let server = TopoServer::new(dir.path().to_path_buf());
+        let params = QueryParams {
+            task: "refund".to_string(),
+            preset: Some("fast".to_string()),
+            max_bytes: Some(400),
+            max_tokens: None,
+            min_score: None,
+            top: None,
+            meta: None,
+            highlight: None,
+            context: None,
+        };
+
+        let result = server.do_query(params).unwrap();
+        let files = result["files"].as_array().unwrap();
+        assert_eq!(files[0]["path"], "README.md");
+        assert_eq!(files[0]["orientation"], true);
+        assert_eq!(files[1]["path"], "src/refund.rs");
+        assert_eq!(files[1]["orientation"], false);
+        assert!(result["tree"].as_str().unwrap().contains("src/ (1 file)"));
+    }
+
+    #[test]
+    fn do_query_filters_on_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("payments")).unwrap();
+        std::fs::create_dir_all(dir.path().join("billing")).unwrap();
+        std::fs::write(dir.path().join("payments/refund.rs"), "fn refund() {}").unwrap();
+        std::fs::write(dir.path().join("billing/refund.rs"), "fn refund() {}").unwrap();
+
+        let query = |meta: Option<BTreeMap<String, String>>| QueryParams {
+            task: "refund".to_string(),
+            preset: Some("fast".to_string()),
+            max_bytes: None,
+            max_tokens: None,
+            min_score: Some(0.0),
+            top: None,
+            meta,
+            highlight: None,
+            context: None,
+        };
+        let service = BTreeMap::from([("service".to_string(), "payments".to_string())]);
+        let server = TopoServer::new(dir.path().to_path_buf());
+        let err = server.do_query(query(Some(service.clone()))).unwrap_err();
+        let data = tool_error(&err).data.unwrap();
+        assert_eq!(data["code"], "E_INDEX_MISSING");
+
+        let bundle = topo_scanner::BundleBuilder::new(dir.path())
+            .build()
+            .unwrap();
+        let tag = |file: &topo_core::FileInfo, _: &str, _: &[topo_core::Chunk]| {
+            let service = file.path.split('/').next().unwrap_or_default();
+            vec![("service".to_string(), service.to_string())]
+        };
+        let (index, _) = topo_index::IndexBuilder::new(dir.path())
+            .enrich(&tag)
+            .build(&bundle.files, None)
+            .unwrap();
+        topo_index::save(&index, dir.path()).unwrap();
+
+        let result = server.do_query(query(Some(service))).unwrap();
+        let paths: Vec<&str> = result["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| f["path"].as_str().unwrap())
+            .collect();
+        assert_eq!(paths, vec!["payments/refund.rs"]);
+
+        let unfiltered = server.do_query(query(None)).unwrap();
+        assert_eq!(unfiltered["files"].as_array().unwrap().len(), 2);
     }
 
     #[test]
@@ -425,4 +777,75 @@ mod tests {
         assert_eq!(result.get("status").unwrap(), "ok");
         assert!(result.get("files_scanned").unwrap().as_u64().unwrap() > 0);
     }
+
+    #[test]
+    fn do_inspect_reports_index_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("hello.rs"), "fn main() {}").unwrap();
+        let server = TopoServer::new(dir.path().to_path_buf());
+        assert!(server.do_inspect().is_err());
+
+        server.do_index_inner(true, false).unwrap();
+        let stats = server.do_inspect().unwrap();
+        assert_eq!(stats["files"], 1);
+        assert_eq!(stats["languages"][0]["name"], "rust");
+        assert!(stats["size_on_disk"].as_u64().unwrap() > 0);
+    }
+
+    #[test]
+    fn do_capabilities_reports_deep_index_and_fallbacks() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("hello.rs"), "fn main() {}").unwrap();
+        let server = TopoServer::new(dir.path().to_path_buf());
+        let capability = |report: &serde_json::Value, name: &str| {
+            report["capabilities"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|c| c["name"] == name)
+                .cloned()
+                .unwrap()
+        };
+
+        let report = server.do_capabilities().unwrap();
+        let deep_index = capability(&report, "deep_index");
+        assert_eq!(deep_index["active"], false);
+        assert!(!deep_index["fallback"].as_str().unwrap().is_empty());
+        assert_eq!(capability(&report, "embeddings")["active"], false);
+        let tree_sitter = capability(&report, "tree_sitter");
+        assert_eq!(tree_sitter["available"], topo_treesit::TREE_SITTER);
+        assert_eq!(tree_sitter["active"], topo_treesit::TREE_SITTER);
+
+        server.do_index_inner(true, false).unwrap();
+        let report = server.do_capabilities().unwrap();
+        assert_eq!(capability(&report, "deep_index")["active"], true);
+    }
+
+    #[test]
+    fn do_query_reports_degraded_signals() {
+        let dir = tempfile::tempdir().unwrap();
+        // tempdir may live under a checkout; git history is then available
+        if dir.path().ancestors().any(|d| d.join(".git").exists()) {
+            return;
+        }
+        std::fs::write(dir.path().join("hello.rs"), "fn main() {}").unwrap();
+        let server = TopoServer::new(dir.path().to_path_buf());
+        let params = QueryParams {
+            task: "main function".to_string(),
+            preset: Some("deep".to_string()),
+            max_bytes: None,
+            max_tokens: None,
+            min_score: None,
+            top: None,
+            meta: None,
+            highlight: None,
+            context: None,
+        };
+
+        let result = server.do_query(params).unwrap();
+        assert_eq!(
+            result["environment"]["degraded"],
+            serde_json::json!(["git"])
+        );
+    }
 }
@@ -1,10 +1,18 @@
+pub mod capabilities;
+pub mod clean;
+pub mod daemon;
 pub mod describe;
 pub mod explain;
+pub mod feedback;
 pub mod gain;
 pub mod index;
 pub mod init;
 pub mod inspect;
 pub mod mcp;
+pub mod pin;
 pub mod query;
+pub mod query_help;
 pub mod quick;
 pub mod render;
+pub mod setup;
+pub mod stats;
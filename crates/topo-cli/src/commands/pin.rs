@@ -0,0 +1,137 @@
+use crate::{Cli, OutputFormat};
+use anyhow::{Result, bail};
+use std::path::{Path, PathBuf};
+use topo_core::Pins;
+
+/// Pin `paths` so every query ranks and lists them first, or with no
+/// paths list the pinned files.
+pub fn pin(cli: &Cli, paths: &[PathBuf]) -> Result<()> {
+    let root = cli.repo_root()?;
+    let mut pins = Pins::load(&root)?;
+    if paths.is_empty() {
+        return list(cli, &pins);
+    }
+
+    let mut added = Vec::new();
+    for path in paths {
+        let rel = repo_relative(&root, path)?;
+        if !root.join(&rel).is_file() {
+            bail!("{} is not a file in {}", path.display(), root.display());
+        }
+        if pins.add(&rel) {
+            added.push(rel);
+        } else if !cli.is_quiet() {
+            eprintln!("{rel} is already pinned");
+        }
+    }
+    if !added.is_empty() {
+        pins.save(&root)?;
+    }
+    if !cli.is_quiet() {
+        for rel in &added {
+            eprintln!("Pinned {rel}");
+        }
+    }
+    Ok(())
+}
+
+/// Unpin `paths`, or every pinned file with `all`.
+pub fn unpin(cli: &Cli, paths: &[PathBuf], all: bool) -> Result<()> {
+    let root = cli.repo_root()?;
+    let mut pins = Pins::load(&root)?;
+    let before = pins.len();
+    if all {
+        pins.clear();
+    }
+    for path in paths {
+        // Deleted files can still be unpinned, so don't require them on disk
+        let rel = repo_relative(&root, path)?;
+        if !pins.remove(&rel) && !all && !cli.is_quiet() {
+            eprintln!("{rel} isn't pinned");
+        }
+    }
+    let removed = before - pins.len();
+    if removed > 0 {
+        pins.save(&root)?;
+    }
+    if !cli.is_quiet() {
+        eprintln!("Unpinned {removed} files ({} still pinned)", pins.len());
+    }
+    Ok(())
+}
+
+fn list(cli: &Cli, pins: &Pins) -> Result<()> {
+    match cli.effective_format() {
+        OutputFormat::Json | OutputFormat::Jsonl => {
+            let paths: Vec<&str> = pins.iter().collect();
+            println!("{}", serde_json::to_string_pretty(&paths)?);
+        }
+        _ if pins.is_empty() => {
+            if !cli.is_quiet() {
+                eprintln!("No pinned files; pin one with `topo pin <path>`");
+            }
+        }
+        _ => {
+            for path in pins.iter() {
+                println!("{path}");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `path` relative to the repository at `root`, with `/` separators.
+///
+/// Relative paths name a file under `root` if there is one, and are taken
+/// from the current directory otherwise.
+pub(crate) fn repo_relative(root: &Path, path: &Path) -> Result<String> {
+    let resolved = if path.is_relative() && root.join(path).exists() {
+        root.join(path)
+    } else {
+        std::path::absolute(path)?
+    };
+    let canonical = |p: &Path| p.canonicalize().unwrap_or_else(|_| p.to_path_buf());
+    let root = canonical(&std::path::absolute(root)?);
+    let resolved = match resolved.parent().zip(resolved.file_name()) {
+        // Canonicalize the directory, so deleted files still resolve
+        Some((dir, name)) => canonical(dir).join(name),
+        None => resolved,
+    };
+    match resolved.strip_prefix(&root) {
+        Ok(rel) if !rel.as_os_str().is_empty() => Ok(rel.to_string_lossy().replace('\\', "/")),
+        _ => bail!(
+            "{} is not inside the repository {}",
+            path.display(),
+            root.display()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_resolve_against_the_repository_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+
+        assert_eq!(
+            repo_relative(root, Path::new("src/main.rs")).unwrap(),
+            "src/main.rs"
+        );
+        assert_eq!(
+            repo_relative(root, &root.join("src/./main.rs")).unwrap(),
+            "src/main.rs"
+        );
+        // Deleted files still resolve, for unpinning
+        assert_eq!(
+            repo_relative(root, &root.join("src/gone.rs")).unwrap(),
+            "src/gone.rs"
+        );
+        assert!(repo_relative(root, Path::new("/elsewhere/main.rs")).is_err());
+        assert!(repo_relative(root, root).is_err());
+    }
+}
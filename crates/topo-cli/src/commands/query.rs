@@ -1,77 +1,1338 @@
 use crate::preset::Preset;
-use crate::{Cli, OutputFormat};
-use anyhow::Result;
-use topo_core::{DeepIndex, ScoredFile, TokenBudget};
-use topo_render::{CompactWriter, JsonlWriter};
-use topo_scanner::BundleBuilder;
-use topo_score::{HybridScorer, RrfFusion};
-
-pub fn run(
-    cli: &Cli,
-    task: &str,
-    preset: Preset,
-    max_bytes: Option<u64>,
-    max_tokens: Option<u64>,
-    min_score: Option<f64>,
-    top: Option<usize>,
-) -> Result<()> {
+use crate::resources::{self, Meter, ResourceUsage};
+use crate::{Cli, Command, OutputFormat};
+use anyhow::{Result, bail};
+use clap::{Args, ValueEnum};
+use globset::Glob;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+use topo_core::{
+    Bundle, Chunk, ChunkKind, Config, ContextWindow, DEFAULT_MIN_SIMILARITY, DeepIndex, Embedder,
+    EmbeddingSegment, FileEntry, FileInfo, GitActivity, Ownership, Pins, ScoredFile,
+    TOKEN_ESTIMATOR, TermFreqs, TokenBudget, Tombstone, TopoError, WeightProfile, display_path,
+    estimate_tokens, is_lockfile,
+};
+use topo_index::ShardedIndex;
+use topo_render::{CompactWriter, Environment, HtmlWriter, JsonlWriter, SarifWriter};
+use topo_scanner::{BundleBuilder, RepoBundle};
+use topo_score::{
+    Centrality, ChunkPostings, CorpusStats, Coupling, DEFAULT_CENTRALITY_WEIGHT,
+    DEFAULT_MMR_CANDIDATES, EmbeddingScorer, FieldWeights, HybridScorer, MAX_PREVIEW_READ_BYTES,
+    Matches, Mmr, Normalization, PHRASE_WEIGHT, ParsedQuery, PathScope, Phrases, QueryFilters,
+    RankedPath, RecencyModel, RetrievalComparison, RrfFusion, Synonyms, TermVector, Tokenizer,
+    path_similarity, term_similarity,
+};
+
+/// Arguments shared by `query` and `quick`.
+#[derive(Args, Debug, Clone)]
+pub struct QueryArgs {
+    /// The task or query to search for
+    #[arg(
+        default_value = "",
+        hide_default_value = true,
+        required_unless_present_any = ["query_file", "query_from_diff"]
+    )]
+    pub task: String,
+
+    /// Preset: fast, balanced, deep, thorough (default: `query.preset` in
+    /// .topo.toml, else balanced)
+    #[arg(long, value_enum)]
+    pub preset: Option<Preset>,
+
+    /// Maximum bytes for token budget
+    #[arg(long)]
+    pub max_bytes: Option<u64>,
+
+    /// Maximum tokens for token budget
+    #[arg(long)]
+    pub max_tokens: Option<u64>,
+
+    /// Minimum score threshold
+    #[arg(long)]
+    pub min_score: Option<f64>,
+
+    /// Rescale scores before thresholds apply: raw, minmax (best 1.0,
+    /// worst 0.0), or softmax over the leading candidates (default:
+    /// `query.normalize` in .topo.toml, else raw)
+    #[arg(long, value_name = "METHOD", value_parser = parse_normalization)]
+    pub normalize: Option<Normalization>,
+
+    /// Return top N files
+    #[arg(long)]
+    pub top: Option<usize>,
+
+    /// Mention files deleted since recent index builds
+    #[arg(long)]
+    pub deleted: bool,
+
+    /// Also match near misses of query terms, for typos: in paths, and in
+    /// identifiers with `topo index --deep --trigrams`
+    #[arg(long)]
+    pub fuzzy: bool,
+
+    /// Boost tests coupled by name or imports to implementation files
+    /// matching the query: `coupled`, or `coupled-both` to also boost
+    /// implementation files whose tests match
+    #[arg(long, value_enum, default_value = "all")]
+    pub include_tests: IncludeTests,
+
+    /// Boost files this author committed to recently, to pick up one's own
+    /// work in progress; without EMAIL, the `user.email` of git config
+    /// (needs a deep index)
+    #[arg(long, value_name = "EMAIL", num_args = 0..=1)]
+    pub author: Option<Option<String>>,
+
+    /// Only consider files whose index metadata has KEY set to VALUE
+    /// (repeatable, all must match; needs an enriched deep index)
+    #[arg(long = "meta", value_name = "KEY=VALUE", value_parser = parse_meta)]
+    pub meta: Vec<(String, String)>,
+
+    /// Only consider files matching GLOB, like `src/auth/**` (repeatable,
+    /// any may match). Only their index entries are read
+    #[arg(long = "path", value_name = "GLOB")]
+    pub paths: Vec<String>,
+
+    /// Globs after the task, usually after `--` like `"task" --
+    /// src/payments/**`, scoping the query to the files they match: terms
+    /// are weighed by how rare they are among those files rather than
+    /// across the whole repository
+    #[arg(value_name = "GLOB")]
+    pub scope: Vec<String>,
+
+    /// Mark query terms in previews and reports, and list matching line
+    /// numbers per file
+    #[arg(long)]
+    pub highlight: bool,
+
+    /// Include only the N lines around each query match instead of whole
+    /// files, and count just those lines against the budget
+    #[arg(long, value_name = "N")]
+    pub context: Option<u32>,
+
+    /// Include only each file's N best-matching indexed chunks instead of
+    /// whole files (needs `topo index --deep --chunks`)
+    #[arg(long, value_name = "N", conflicts_with = "context")]
+    pub chunks: Option<usize>,
+
+    /// Include only the first line and doc comment of each function and
+    /// type instead of whole files (needs a deep index)
+    #[arg(long, conflicts_with_all = ["context", "chunks"])]
+    pub signatures: bool,
+
+    /// Include only the first line and doc comment of each file's exported
+    /// functions and types, its public API, instead of whole files (needs
+    /// a deep index)
+    #[arg(long, conflicts_with_all = ["context", "chunks", "signatures"])]
+    pub api: bool,
+
+    /// Reserve this share of the budget (0.0–1.0) for a directory outline,
+    /// READMEs, and entry points (default: `budget.orientation` in
+    /// .topo.toml, else 0)
+    #[arg(long, value_name = "FRACTION", value_parser = parse_fraction)]
+    pub orientation: Option<f64>,
+
+    /// Re-rank results for diversity with Maximal Marginal Relevance:
+    /// 1.0 keeps the ranking, lower values favor files unlike those ranked
+    /// above them (default: `query.mmr_lambda` in .topo.toml, else 1.0)
+    #[arg(long, value_name = "LAMBDA", value_parser = parse_fraction)]
+    pub mmr_lambda: Option<f64>,
+
+    /// Search only lockfiles (Cargo.lock, package-lock.json, go.sum, …),
+    /// ranked by their lines matching the query, and include the matching
+    /// entries with N lines of context (default 3) instead of whole files
+    #[arg(long, conflicts_with_all = ["chunks", "signatures", "api"])]
+    pub lockfiles: bool,
+
+    /// Instead of selecting files, print the BM25F-only, embedding-only,
+    /// and fused rankings side by side as JSON. The query is embedded with
+    /// `embedding.model` of .topo.toml, or FILE holds its embedding as a
+    /// JSON array, from the model the index was embedded with (`-` reads
+    /// it from stdin)
+    #[arg(long, value_name = "FILE", num_args = 0..=1)]
+    pub compare_retrieval: Option<Option<PathBuf>>,
+
+    /// Query with a whole document, like a bug report (`-` reads it from
+    /// stdin): its most used terms are added to the query, weighted by
+    /// how often it uses them
+    #[arg(long, value_name = "FILE", conflicts_with = "query_from_diff")]
+    pub query_file: Option<PathBuf>,
+
+    /// Query with a unified diff, like `git diff` prints (`-` reads it
+    /// from stdin): the paths it changes and the lines it adds and removes
+    #[arg(long, value_name = "FILE")]
+    pub query_from_diff: Option<PathBuf>,
+
+    /// Weights of query terms taken from `--query-file` or
+    /// `--query-from-diff`, see [`QueryArgs::with_documents`].
+    #[arg(skip)]
+    pub query_weights: Vec<(String, f64)>,
+}
+
+/// Which tests a query brings in with the files they cover, for
+/// `--include-tests`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum IncludeTests {
+    /// Score tests like any other file
+    #[default]
+    All,
+    /// Boost tests of implementation files matching the query
+    Coupled,
+    /// Also boost implementation files of tests matching the query
+    CoupledBoth,
+}
+
+impl IncludeTests {
+    pub fn coupling(self) -> Coupling {
+        match self {
+            Self::All => Coupling::Off,
+            Self::Coupled => Coupling::Tests,
+            Self::CoupledBoth => Coupling::Both,
+        }
+    }
+}
+
+/// Parsed `--path` globs, empty without any.
+pub fn path_globs(patterns: &[String]) -> Result<Vec<Glob>> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            Glob::new(pattern).map_err(|source| {
+                TopoError::Pattern {
+                    pattern: pattern.clone(),
+                    source,
+                }
+                .into()
+            })
+        })
+        .collect()
+}
+
+/// Parse a `--meta` filter.
+fn parse_meta(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got `{s}`")),
+    }
+}
+
+/// Parse a `--normalize` method.
+fn parse_normalization(s: &str) -> Result<Normalization, String> {
+    Normalization::named(s).ok_or_else(|| {
+        format!(
+            "expected one of {}, got `{s}`",
+            topo_core::NORMALIZATIONS.join(", ")
+        )
+    })
+}
+
+/// Parse a `--orientation` share.
+fn parse_fraction(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(f) if (0.0..=1.0).contains(&f) => Ok(f),
+        _ => Err(format!("expected a number from 0.0 to 1.0, got `{s}`")),
+    }
+}
+
+impl QueryArgs {
+    /// The `--preset` given, or the one [`default_preset`] filled in.
+    pub fn preset(&self) -> Preset {
+        self.preset.unwrap_or_default()
+    }
+
+    pub fn effective_max_bytes(&self) -> u64 {
+        self.max_bytes.unwrap_or(self.preset().default_max_bytes())
+    }
+
+    pub fn effective_min_score(&self) -> f64 {
+        self.min_score.unwrap_or(self.preset().default_min_score())
+    }
+
+    /// How query terms are matched beyond the config.
+    pub fn matching(&self) -> Matching<'_> {
+        Matching {
+            fuzzy: self.fuzzy,
+            coupling: self.include_tests.coupling(),
+            normalization: self.normalize,
+            author: None,
+            query_weights: &self.query_weights,
+            penalties: &[],
+        }
+    }
+
+    /// The `--author` email, looked up in the VCS config of `root` if not
+    /// given.
+    pub fn author(&self, root: &Path) -> Result<Option<String>> {
+        match &self.author {
+            None => Ok(None),
+            Some(Some(email)) => Ok(Some(email.clone())),
+            Some(None) => {
+                let email = match topo_vcs::detect(root) {
+                    Some(vcs) => vcs.user_email()?,
+                    None => None,
+                };
+                match email {
+                    Some(email) => Ok(Some(email)),
+                    None => bail!("--author needs an email: none is set in git config"),
+                }
+            }
+        }
+    }
+
+    /// These arguments with the terms of `--query-file` or
+    /// `--query-from-diff` added to the task, and their weights.
+    pub fn with_documents(&self) -> Result<Self> {
+        let (path, vector) = match (&self.query_file, &self.query_from_diff) {
+            (Some(path), _) => (path, TermVector::from_text(&read_input(path)?)),
+            (_, Some(path)) => (path, TermVector::from_diff(&read_input(path)?)),
+            _ => return Ok(self.clone()),
+        };
+        // Terms typed alongside the document keep their full weight
+        let typed = Tokenizer::tokenize(&ParsedQuery::parse(&self.task).text);
+        let vector = vector.without(&typed);
+        if vector.is_empty() && typed.is_empty() {
+            bail!("{} has no terms to query for", path.display());
+        }
+        Ok(Self {
+            task: format!("{} {}", self.task, vector.query())
+                .trim()
+                .to_string(),
+            query_weights: vector.terms().to_vec(),
+            ..self.clone()
+        })
+    }
+
+    /// Whether answering needs the deep index opened.
+    pub fn needs_index(&self) -> bool {
+        self.preset().use_structural_signals()
+            || self.deleted
+            || self.fuzzy
+            || !self.meta.is_empty()
+            || self.chunks.is_some()
+            || self.signatures
+            || self.api
+            || self.compare_retrieval.is_some()
+            || self.author.is_some()
+            || ParsedQuery::parse(&self.task).filters.has_exclusions()
+    }
+
+    /// `--path` and `-- GLOB...` globs together.
+    pub fn path_globs(&self) -> Result<Vec<Glob>> {
+        path_globs(&[self.paths.as_slice(), self.scope.as_slice()].concat())
+    }
+}
+
+/// Give a `query` or `quick` without `--preset` the `query.preset` of the
+/// repository's .topo.toml.
+pub fn default_preset(cli: &mut Cli) -> Result<()> {
+    let unset = matches!(
+        &cli.command,
+        Some(Command::Query(args) | Command::Quick(args)) if args.preset.is_none()
+    );
+    if !unset {
+        return Ok(());
+    }
+    let config = Config::load(&cli.repo_root()?)?;
+    if let Some(Command::Query(args) | Command::Quick(args)) = &mut cli.command {
+        args.preset = Some(Preset::from_config(&config));
+    }
+    Ok(())
+}
+
+pub fn run(cli: &Cli, args: &QueryArgs) -> Result<()> {
+    let mut meter = Meter::new(Instant::now());
     let root = cli.repo_root()?;
+    let config = Config::load(&root)?;
+    let progress = cli.progress();
 
-    // Scan files
-    let bundle = BundleBuilder::new(&root).build()?;
+    // Scan the repository and any nested repositories, opening their deep
+    // indexes for PageRank when using structural signals, and for file
+    // summaries when embedding them
+    let embedder = local_embedder(&root, &config)?;
+    progress.start("scan", None);
+    let needs_index = args.needs_index() || embedder.is_some();
+    let federation = Federation::scan(&root, &config, needs_index)?.embedder(embedder);
+    progress.finish(federation.file_count());
+    meter.phase("scan");
+    meter.hashed(federation.scanned_bytes());
+    print!("{}", respond(cli, args, &config, &federation, &mut meter)?);
+    if cli.is_verbose() && !reports_usage_inline(cli) {
+        eprintln!("{}", meter.usage().summary());
+    }
+    Ok(())
+}
 
-    // Load deep index for PageRank when using structural signals
-    let deep_index = if preset.use_structural_signals() {
-        topo_index::load(&root)?
-    } else {
-        None
+/// The local model of `embedding.model` in .topo.toml, if one is set.
+#[cfg(feature = "embedding")]
+pub fn local_embedder(root: &Path, config: &Config) -> Result<Option<Arc<dyn Embedder>>> {
+    let Some(model) = &config.embedding.model else {
+        return Ok(None);
     };
+    let cache = topo_index::model_cache_path(root);
+    Ok(Some(Arc::new(topo_score::LocalEmbedder::new(
+        model, &cache,
+    )?)))
+}
+
+/// The local model of `embedding.model` in .topo.toml, which this build
+/// can't run.
+#[cfg(not(feature = "embedding"))]
+pub fn local_embedder(_root: &Path, config: &Config) -> Result<Option<Arc<dyn Embedder>>> {
+    if config.embedding.model.is_some() {
+        eprintln!("Ignoring embedding.model: topo was built without the `embedding` feature");
+    }
+    Ok(None)
+}
+
+/// Whether `--verbose` puts the resource usage in the output itself rather
+/// than on stderr.
+pub(crate) fn reports_usage_inline(cli: &Cli) -> bool {
+    matches!(cli.effective_format(), OutputFormat::Json)
+}
+
+/// Answer a query against a scanned `federation`, returning the output in
+/// the selected format. Also used by `topo daemon` with the federation it
+/// keeps loaded. Phases are timed on `meter` as they finish.
+pub(crate) fn respond(
+    cli: &Cli,
+    args: &QueryArgs,
+    config: &Config,
+    federation: &Federation,
+    meter: &mut Meter,
+) -> Result<String> {
+    let root = cli.repo_root()?;
+    let progress = cli.progress();
+    let preset = args.preset();
+    let needs_index = args.needs_index();
+    let scanned_count = federation.file_count();
+    if !args.meta.is_empty() && federation.root_index().is_none() {
+        return Err(TopoError::index_missing("--meta filters need a deep index").into());
+    }
+    if args.chunks.is_some() && !federation.root_index().is_some_and(|i| i.chunk_postings) {
+        return Err(TopoError::index_missing(
+            "--chunks needs chunk postings, built by `topo index --deep --chunks`",
+        )
+        .into());
+    }
 
     // Score files
-    let scored = score_files(task, &bundle.files, preset, deep_index.as_ref());
+    progress.start("score", Some(scanned_count));
+    let paths = args.path_globs()?;
+    let query = ParsedQuery::parse(&args.task);
+    let task = query.text.as_str();
+    let author = args.author(&root)?;
+    let options = Matching {
+        author: author.as_deref(),
+        penalties: &query.penalties,
+        ..args.matching()
+    };
+    let (scored, expansions) =
+        federation.score(task, preset, options, &args.meta, &paths, &query.filters);
+    progress.finish(scanned_count);
+    meter.phase("score");
 
-    // Apply score filter
-    let effective_min_score = min_score.unwrap_or(preset.default_min_score());
+    if let Some(vector) = &args.compare_retrieval {
+        let output = compare_retrieval(federation, task, vector.as_deref(), &scored, args.top)?;
+        meter.phase("compare");
+        record_query(&root, meter.started(), Some(true));
+        return Ok(output);
+    }
+
+    // Apply score filter; lockfiles have only their filenames indexed, so
+    // they are ranked on their contents instead
+    let effective_min_score = args.effective_min_score();
     let mut filtered: Vec<ScoredFile> = scored
-        .into_iter()
-        .filter(|f| f.score >= effective_min_score)
+        .iter()
+        .filter(|f| {
+            if args.lockfiles {
+                is_lockfile(f.path.rsplit('/').next().unwrap_or(&f.path))
+            } else {
+                f.score >= effective_min_score
+            }
+        })
+        .cloned()
         .collect();
+    let lockfile_context = args.lockfiles.then(|| {
+        let lines = args.context.unwrap_or(LOCKFILE_CONTEXT);
+        lockfile_windows(&root, task, &mut filtered, lines)
+    });
+
+    // Blame the leading candidates for the ownership signal
+    let ownership = if preset.use_structural_signals() {
+        apply_ownership(
+            &root,
+            &federation.root().files,
+            &mut filtered,
+            preset.scoring().blame_candidates,
+        )?
+    } else {
+        HashMap::new()
+    };
+
+    // Spread the leading results over distinct areas
+    if let Some(lambda) = args.mmr_lambda.or(config.query.mmr_lambda) {
+        federation.diversify(lambda, &mut filtered);
+    }
 
     // Apply top-N filter
-    if let Some(n) = top {
+    if let Some(n) = args.top {
         filtered.truncate(n);
     }
+    meter.phase("filter");
+
+    let context = match (lockfile_context, args.context, args.chunks) {
+        (Some(windows), _, _) => windows,
+        (None, Some(lines), _) => context_windows(&root, task, &expansions, &mut filtered, lines),
+        (None, None, Some(per_file)) => {
+            federation.chunk_windows(&root, task, &mut filtered, per_file)
+        }
+        (None, None, None) if args.signatures || args.api => {
+            federation.signature_windows(&root, &mut filtered, args.api)
+        }
+        (None, None, None) => HashMap::new(),
+    };
+    meter.phase("context");
 
     // Enforce token budget
-    let effective_max_bytes = max_bytes.unwrap_or(preset.default_max_bytes());
     let budget = TokenBudget {
-        max_bytes: Some(effective_max_bytes),
-        max_tokens,
+        max_bytes: Some(args.effective_max_bytes()),
+        max_tokens: args.max_tokens,
     };
-    let budgeted = budget.enforce(&filtered);
+    let share = args.orientation.unwrap_or(config.budget.orientation);
+    let (mut budgeted, orientation) = select(&budget, share, &filtered, &scored);
+    let matches = add_previews(
+        &root,
+        task,
+        &expansions,
+        &mut budgeted,
+        |path| federation.chunks(path),
+        args.highlight,
+    );
+
+    let recently_deleted = if args.deleted {
+        federation.recently_deleted()
+    } else {
+        Vec::new()
+    };
+    meter.phase("select");
 
     // Output
-    output_results(
+    let structural = federation
+        .root_index()
+        .filter(|_| preset.use_structural_signals());
+    let mut env = environment(preset, config, federation.root(), structural);
+    env.degraded = degraded(preset, args.fuzzy, federation.root_index());
+    let output = render_results(
         cli,
-        task,
-        preset,
+        args,
         &budgeted,
-        bundle.file_count(),
-        effective_max_bytes,
-        effective_min_score,
+        scanned_count,
+        &env,
+        &recently_deleted,
+        &Annotations {
+            ownership,
+            matches,
+            context,
+            orientation,
+            usage: (cli.is_verbose() && reports_usage_inline(cli)).then(|| meter.usage()),
+        },
     )?;
+    meter.phase("render");
 
-    Ok(())
+    record_query(
+        &root,
+        meter.started(),
+        needs_index.then(|| federation.root_index().is_some()),
+    );
+    Ok(output)
+}
+
+/// Files listed per ranking by `--compare-retrieval` unless `--top` says
+/// otherwise.
+const COMPARE_TOP: usize = 20;
+
+/// Print the BM25F-only, embedding-only, and fused rankings of `scored`
+/// as JSON, with the query embedding read from `vector`.
+///
+/// Only the top-level repository's vectors are searched, and only for
+/// files that were scanned (and matched any `--meta` filters).
+fn compare_retrieval(
+    federation: &Federation,
+    task: &str,
+    vector: Option<&Path>,
+    scored: &[ScoredFile],
+    top: Option<usize>,
+) -> Result<String> {
+    let segment = federation.root_embeddings().ok_or_else(|| {
+        TopoError::index_missing(
+            "--compare-retrieval needs embedding vectors in the deep index, \
+             built by `topo index --deep` with `embedding.model` set",
+        )
+    })?;
+    let query = match (vector, &federation.embedder) {
+        (Some(path), _) => read_vector(path)?,
+        (None, Some(embedder)) if embedder.model() == segment.model => {
+            embedder.embed(&[task])?.pop().unwrap_or_default()
+        }
+        (None, Some(embedder)) => bail!(
+            "The index was embedded with {}, not {}; run `topo index --deep` again.",
+            segment.model,
+            embedder.model()
+        ),
+        (None, None) => {
+            bail!("--compare-retrieval needs `embedding.model` set, or the query embedding in FILE")
+        }
+    };
+    if query.len() != segment.dimension as usize {
+        anyhow::bail!(
+            "The query embedding has {} dimensions, but the index holds {} vectors of {} dimensions.",
+            query.len(),
+            segment.model,
+            segment.dimension
+        );
+    }
+
+    let candidates: HashSet<&str> = scored.iter().map(|f| f.path.as_str()).collect();
+    let mut dense = topo_core::VectorStore::nearest(segment, &query, segment.len())?;
+    dense.retain(|m| candidates.contains(m.path.as_str()));
+
+    let comparison = RetrievalComparison::new(scored, &dense, top.unwrap_or(COMPARE_TOP));
+    let ranking = |ranking: &[RankedPath]| -> Vec<serde_json::Value> {
+        ranking
+            .iter()
+            .enumerate()
+            .map(|(i, r)| serde_json::json!({ "rank": i + 1, "path": r.path, "score": r.score }))
+            .collect()
+    };
+    let output = serde_json::json!({
+        "query": task,
+        "model": segment.model,
+        "overlap": comparison.overlap(),
+        "bm25f": ranking(&comparison.bm25f),
+        "embedding": ranking(&comparison.embedding),
+        "fused": ranking(&comparison.fused),
+    });
+    Ok(format!("{}\n", serde_json::to_string_pretty(&output)?))
+}
+
+/// Read the file at `path`, or stdin for `-`.
+fn read_input(path: &Path) -> Result<String> {
+    if path.as_os_str() == "-" {
+        let mut text = String::new();
+        std::io::stdin().read_to_string(&mut text)?;
+        Ok(text)
+    } else {
+        Ok(std::fs::read_to_string(path).map_err(|e| TopoError::io(path, e))?)
+    }
+}
+
+/// Read a query embedding, a JSON array of numbers, from `path` or stdin
+/// for `-`.
+fn read_vector(path: &Path) -> Result<Vec<f32>> {
+    let json = read_input(path)?;
+    serde_json::from_str(&json).map_err(|e| {
+        anyhow::anyhow!(
+            "{} is not a query embedding (expected a JSON array of numbers): {e}",
+            path.display()
+        )
+    })
+}
+
+/// Add a finished query to the local usage stats. `index_hit` is `None`
+/// when the query didn't need a deep index.
+///
+/// Best-effort: failing to write the stats never fails the query.
+pub(crate) fn record_query(root: &Path, started: Instant, index_hit: Option<bool>) {
+    let latency = started.elapsed();
+    let _ = topo_index::record_usage(root, |stats| stats.record_query(latency, index_hit));
+}
+
+/// The repository and the repositories nested inside it, each with its
+/// own deep index, queried as one.
+///
+/// Indexes are opened lazily, so only the shards holding files a query
+/// actually looks at are read.
+pub struct Federation {
+    /// Root first, see [`BundleBuilder::build_federated`].
+    repos: Vec<RepoBundle>,
+    indexes: Vec<Option<ShardedIndex>>,
+    scoring: ScoringOptions,
+    /// Model the query and file summaries are embedded with, see
+    /// [`EmbeddingScorer`].
+    embedder: Option<Arc<dyn Embedder>>,
+}
+
+impl Federation {
+    /// Scan `root` and its nested repositories, opening each one's deep
+    /// index when `load_indexes` is set.
+    pub fn scan(root: &Path, config: &Config, load_indexes: bool) -> Result<Self> {
+        let repos = BundleBuilder::new(root).config(config).build_federated()?;
+        Self::from_repos(root, repos, config, load_indexes)
+    }
+
+    /// The federation of `repos`, a scan of `root` and its nested
+    /// repositories, see [`Self::scan`].
+    pub fn from_repos(
+        root: &Path,
+        repos: Vec<RepoBundle>,
+        config: &Config,
+        load_indexes: bool,
+    ) -> Result<Self> {
+        let indexes = repos
+            .iter()
+            .map(|repo| {
+                if load_indexes {
+                    topo_index::open_repo(root, &repo.prefix)
+                } else {
+                    Ok(None)
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            repos,
+            indexes,
+            scoring: ScoringOptions::from_config(config),
+            embedder: None,
+        })
+    }
+
+    /// Fill the `embedding` signal of the leading files with `embedder`
+    /// and fuse it into the ranking, see [`EmbeddingScorer`].
+    pub fn embedder(mut self, embedder: Option<Arc<dyn Embedder>>) -> Self {
+        self.embedder = embedder;
+        self
+    }
+
+    /// Whether queries are embedded, which summarizes files from their
+    /// deep index.
+    pub fn embeds(&self) -> bool {
+        self.embedder.is_some()
+    }
+
+    /// The same scan with no deep indexes open, answering as a scan made
+    /// without `load_indexes` would.
+    pub fn without_indexes(&self) -> Self {
+        Self {
+            repos: self.repos.clone(),
+            indexes: self.repos.iter().map(|_| None).collect(),
+            scoring: self.scoring.clone(),
+            embedder: self.embedder.clone(),
+        }
+    }
+
+    /// Scan fingerprint of each repository, root first.
+    pub fn fingerprints(&self) -> Vec<String> {
+        self.repos
+            .iter()
+            .map(|repo| repo.bundle.fingerprint.clone())
+            .collect()
+    }
+
+    /// The top-level repository's bundle.
+    pub fn root(&self) -> &Bundle {
+        &self.repos[0].bundle
+    }
+
+    /// The top-level repository's deep index.
+    pub fn root_index(&self) -> Option<&DeepIndex> {
+        self.indexes[0].as_ref().map(ShardedIndex::corpus)
+    }
+
+    /// The top-level repository's embedding vectors, if its deep index
+    /// has any.
+    pub fn root_embeddings(&self) -> Option<&EmbeddingSegment> {
+        self.indexes[0].as_ref()?.embeddings()
+    }
+
+    /// Files scanned across all repositories.
+    pub fn file_count(&self) -> usize {
+        self.repos.iter().map(|r| r.bundle.file_count()).sum()
+    }
+
+    /// Total size of the scanned files, all of which the scan hashed.
+    pub fn scanned_bytes(&self) -> u64 {
+        self.repos
+            .iter()
+            .flat_map(|r| &r.bundle.files)
+            .map(|f| f.size)
+            .sum()
+    }
+
+    /// Score each repository against its own index and merge the results
+    /// best first, with nested repositories' paths prefixed. Also returns
+    /// the fuzzy expansion terms used, when `options` has `fuzzy` set.
+    ///
+    /// With `paths`, only files whose path (prefixed, for nested
+    /// repositories) matches any of them are scored, see
+    /// [`HybridScorer::scoped_to`], and likewise with field `filters` split
+    /// from the query, see [`ParsedQuery`]; their `!term` words also leave
+    /// out files with the term among their indexed terms. With `meta`
+    /// filters, only files
+    /// whose index metadata matches every `(key, value)` pair are;
+    /// unindexed files never match. Metadata is only read for files in
+    /// scope that pass the other filters.
+    pub fn score(
+        &self,
+        task: &str,
+        preset: Preset,
+        options: Matching<'_>,
+        meta: &[(String, String)],
+        paths: &[Glob],
+        filters: &QueryFilters,
+    ) -> (Vec<ScoredFile>, Vec<String>) {
+        let mut scored = Vec::new();
+        let mut expansions: Vec<String> = Vec::new();
+        // Read on every query, like pins, so `topo feedback` applies to a
+        // running daemon too
+        let profile = WeightProfile::load(&self.root().root).unwrap_or_else(|e| {
+            eprintln!("Ignoring fitted weights: {e}");
+            None
+        });
+        let unfiltered = meta.is_empty() && filters.is_empty();
+        for (repo, index) in self.repos.iter().zip(&self.indexes) {
+            let scope = PathScope::new(paths).under(&repo.prefix);
+            let matching: Vec<FileInfo>;
+            let files = if unfiltered {
+                &repo.bundle.files
+            } else {
+                matching = repo
+                    .bundle
+                    .files
+                    .iter()
+                    .filter(|file| filters.matches(&repo.qualify(&file.path), file))
+                    .filter(|file| {
+                        // `!term` words also leave out files with the term
+                        // in their content
+                        let checked = filters.has_exclusions() && scope.contains(&file.path);
+                        let Some(index) = index.as_ref().filter(|_| checked) else {
+                            return true;
+                        };
+                        let stemmed = index.corpus().stemmed;
+                        index.entry(&file.path).is_none_or(|entry| {
+                            !filters.excludes_terms(&entry.term_frequencies, stemmed)
+                        })
+                    })
+                    .filter(|file| {
+                        // Files out of scope are left to the scorer to drop
+                        meta.is_empty()
+                            || !scope.contains(&file.path)
+                            || index
+                                .as_ref()
+                                .and_then(|index| index.entry(&file.path))
+                                .is_some_and(|entry| {
+                                    meta.iter()
+                                        .all(|(key, value)| entry.has_metadata(key, value))
+                                })
+                    })
+                    .cloned()
+                    .collect();
+                &matching
+            };
+            let structural = index
+                .as_ref()
+                .map(ShardedIndex::corpus)
+                .filter(|_| preset.use_structural_signals());
+            let candidates = index
+                .as_ref()
+                .filter(|_| options.fuzzy)
+                .map(|index| FuzzyCandidates::from_index(task, index, files, &scope));
+            let stemming = index
+                .as_ref()
+                .map_or(self.scoring.stemming, |i| i.corpus().stemmed);
+            // Path stats only change with the files, so a query of every
+            // file reuses those of the last one
+            let path_stats = (unfiltered && paths.is_empty()).then(|| {
+                topo_index::cached_corpus_stats(
+                    &repo.bundle.root,
+                    &repo.bundle.fingerprint,
+                    stemming,
+                    || {
+                        let paths: Vec<&str> =
+                            repo.bundle.files.iter().map(|f| f.path.as_str()).collect();
+                        CorpusStats::path_stats(&paths, stemming)
+                    },
+                )
+            });
+            let scoring = ScoringOptions {
+                stemming,
+                fuzzy: options.fuzzy,
+                coupling: options.coupling,
+                profile,
+                path_stats,
+                query_weights: options.query_weights.to_vec(),
+                penalties: options.penalties.to_vec(),
+                scope,
+                ..self.scoring.clone()
+            };
+            let mut files = score_files(
+                task,
+                files,
+                preset,
+                structural,
+                candidates.as_ref(),
+                &scoring,
+            );
+            let structural_index = index.as_ref().filter(|_| preset.use_structural_signals());
+            let boosted = preset.scoring().candidates;
+            if let Some(index) = structural_index {
+                apply_exports(task, &mut files, boosted, |path| index.entry(path));
+            }
+            apply_phrases(task, scoring.stemming, &mut files, boosted, |path| {
+                structural_index.and_then(|index| index.entry(path))
+            });
+            let activity = index
+                .as_ref()
+                .and_then(|index| index.corpus().git_activity.as_ref());
+            if let (Some(author), Some(activity)) = (options.author, activity) {
+                apply_author(author, activity, &scoring.recency, &mut files);
+            }
+            scored.extend(files.into_iter().map(|mut file| {
+                file.path = repo.qualify(&file.path);
+                file
+            }));
+            for (term, _) in candidates.into_iter().flat_map(|c| c.terms) {
+                if !expansions.contains(&term) {
+                    expansions.push(term);
+                }
+            }
+        }
+        if self.repos.len() > 1 {
+            scored.sort_by(|a, b| {
+                b.score
+                    .partial_cmp(&a.score)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+        }
+        if let Some(embedder) = &self.embedder {
+            let embedding =
+                EmbeddingScorer::new(embedder.as_ref()).candidates(preset.scoring().candidates);
+            if let Err(e) = embedding.apply(task, &mut scored, |path| self.entry(path)) {
+                eprintln!("Ignoring the embedding signal: {e}");
+            }
+        }
+        // Normalized over all repositories, so their scores stay comparable
+        options
+            .normalization
+            .unwrap_or(self.scoring.normalization)
+            .apply(&mut scored, preset.scoring().candidates);
+        // Read on every query, so `topo pin` applies to a running daemon too
+        match Pins::load(&self.root().root) {
+            Ok(pins) => topo_score::apply_pins(&mut scored, &pins),
+            Err(e) => eprintln!("Ignoring pinned files: {e}"),
+        }
+        (scored, expansions)
+    }
+
+    /// Indexed chunks of `path` (from the top-level root), from the index
+    /// of the repository it belongs to.
+    pub fn chunks(&self, path: &str) -> &[Chunk] {
+        self.entry(path)
+            .map(|entry| entry.chunks.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Re-rank the leading `files` with Maximal Marginal Relevance at
+    /// `lambda`, see [`Mmr`]. Files are similar when they share
+    /// directories and, when both are indexed, terms.
+    pub fn diversify(&self, lambda: f64, files: &mut [ScoredFile]) {
+        let terms: HashMap<String, HashSet<String>> = files
+            .iter()
+            .take(DEFAULT_MMR_CANDIDATES)
+            .filter_map(|file| {
+                let entry = self.entry(&file.path)?;
+                let terms = entry.term_frequencies.keys().cloned().collect();
+                Some((file.path.clone(), terms))
+            })
+            .collect();
+        Mmr::new(lambda).rerank(files, |a, b| {
+            let directories = path_similarity(&a.path, &b.path);
+            match (terms.get(&a.path), terms.get(&b.path)) {
+                (Some(a), Some(b)) => (directories + term_similarity(a, b)) / 2.0,
+                _ => directories,
+            }
+        });
+    }
+
+    /// Index entry of `path` (from the top-level root).
+    fn entry(&self, path: &str) -> Option<&FileEntry> {
+        let (index, rel) = self.index_of(path)?;
+        index.entry(rel)
+    }
+
+    /// Deep index of the repository `path` (from the top-level root)
+    /// belongs to, and the path within it.
+    fn index_of<'p>(&self, path: &'p str) -> Option<(&ShardedIndex, &'p str)> {
+        let (repo, rel) = RepoBundle::locate(&self.repos, path)?;
+        let i = self.repos.iter().position(|r| r.prefix == repo.prefix)?;
+        Some((self.indexes[i].as_ref()?, rel))
+    }
+
+    /// Hash of the `chunk`th chunk of `path`, see
+    /// [`FileEntry::chunk_hashes`], and the number of files in its
+    /// repository holding a copy of it.
+    fn chunk_copies(&self, path: &str, chunk: usize) -> (u64, usize) {
+        let Some((index, rel)) = self.index_of(path) else {
+            return (0, 1);
+        };
+        match index.entry(rel).and_then(|e| e.chunk_hashes.get(chunk)) {
+            Some(&hash) if hash != 0 => (hash, index.corpus().duplicate_files(hash)),
+            _ => (0, 1),
+        }
+    }
+
+    /// Narrow `files` to their `per_file` chunks that best match `task`.
+    ///
+    /// The chunks of all `files` are ranked against each other, with
+    /// chunks copied into other files damped. Copies of a chunk already
+    /// picked are left out in favour of it, and its window notes how many
+    /// other files hold one; a file whose matching chunks are all such
+    /// copies is narrowed to nothing. Each narrowed file's tokens become
+    /// those of its chunks, so the budget counts only what gets embedded.
+    /// Files without chunk postings or without a matching chunk stay whole.
+    pub fn chunk_windows(
+        &self,
+        root: &Path,
+        task: &str,
+        files: &mut [ScoredFile],
+        per_file: usize,
+    ) -> HashMap<String, Vec<ContextWindow>> {
+        let entries: Vec<(&str, &FileEntry)> = files
+            .iter()
+            .filter_map(|file| Some((file.path.as_str(), self.entry(&file.path)?)))
+            .collect();
+        let postings = ChunkPostings::build(
+            entries
+                .iter()
+                .filter_map(|&(path, entry)| Some((path, entry.chunk_terms.as_deref()?))),
+        )
+        .copies(|path, chunk| self.chunk_copies(path, chunk).1)
+        .stemming(self.root_index().is_some_and(|index| index.stemmed));
+        let mut picked: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut shown: HashSet<u64> = HashSet::new();
+        for hit in postings.rank(task, self.scoring.weights) {
+            let chosen = picked.entry(hit.path.to_string()).or_default();
+            if chosen.len() >= per_file {
+                continue;
+            }
+            // Best first, so a picked chunk stands for its later copies
+            let (hash, _) = self.chunk_copies(hit.path, hit.chunk);
+            if hash == 0 || shown.insert(hash) {
+                chosen.push(hit.chunk);
+            }
+        }
+
+        let mut windows = HashMap::new();
+        for file in files.iter_mut() {
+            let (Some(chosen), Some(entry)) = (picked.get(&file.path), self.entry(&file.path))
+            else {
+                continue;
+            };
+            let (Some(content), Some(terms)) =
+                (read_small(&root.join(&file.path)), &entry.chunk_terms)
+            else {
+                continue;
+            };
+            // Chunk postings follow the order of the chunks
+            let mut spans: Vec<(u32, u32, ChunkKind)> = chosen
+                .iter()
+                .map(|&i| {
+                    let kind = entry.chunks.get(i).map_or(ChunkKind::Other, |c| c.kind);
+                    (terms[i].start_line, terms[i].end_line, kind)
+                })
+                .collect();
+            spans.sort_unstable_by_key(|&(start, end, _)| (start, end));
+            let mut found = chunk_spans(&content, &spans);
+            for &i in chosen {
+                let copies = self.chunk_copies(&file.path, i).1;
+                let start = terms[i].start_line;
+                let window = found
+                    .iter_mut()
+                    .find(|w| w.start_line <= start && start <= w.end_line);
+                if let Some(window) = window.filter(|_| copies > 1) {
+                    let others = copies as u32 - 1;
+                    window.duplicated_in =
+                        Some(window.duplicated_in.map_or(others, |n| n.max(others)));
+                }
+            }
+            file.tokens = found.iter().map(ContextWindow::estimated_tokens).sum();
+            windows.insert(file.path.clone(), found);
+        }
+        windows
+    }
+
+    /// Narrow `files` to the first line and doc comment of each indexed
+    /// function, method, type, and impl, see [`topo_treesit::summary_span`].
+    ///
+    /// With `exported_only`, only exported declarations are summarized,
+    /// see [`Chunk::exported`].
+    ///
+    /// Each narrowed file's tokens become those of its summaries. Files
+    /// without indexed declarations stay whole. Files none of whose
+    /// declarations have a doc comment are summarized from the signatures
+    /// in the index, without reading them.
+    pub fn signature_windows(
+        &self,
+        root: &Path,
+        files: &mut [ScoredFile],
+        exported_only: bool,
+    ) -> HashMap<String, Vec<ContextWindow>> {
+        let mut windows = HashMap::new();
+        for file in files.iter_mut() {
+            let declarations: Vec<&Chunk> = self
+                .chunks(&file.path)
+                .iter()
+                .filter(|c| c.kind.is_declaration() && (c.exported || !exported_only))
+                .collect();
+            if declarations.is_empty() {
+                continue;
+            }
+            let indexed: Option<Vec<(u32, &str, ChunkKind)>> = declarations
+                .iter()
+                .map(|c| match (&c.signature, &c.doc) {
+                    (Some(signature), None) => Some((c.start_line, signature.as_str(), c.kind)),
+                    _ => None,
+                })
+                .collect();
+            let found = match indexed {
+                Some(signatures) => signature_lines(signatures),
+                None => {
+                    let Some(content) = read_small(&root.join(&file.path)) else {
+                        continue;
+                    };
+                    let lines: Vec<&str> = content.lines().collect();
+                    let mut spans: Vec<(u32, u32, ChunkKind)> = declarations
+                        .iter()
+                        .map(|c| {
+                            let (start, end) = topo_treesit::summary_span(&lines, c, file.language);
+                            (start, end, c.kind)
+                        })
+                        .collect();
+                    spans.sort_unstable_by_key(|&(start, end, _)| (start, end));
+                    chunk_spans(&content, &spans)
+                }
+            };
+            file.tokens = found.iter().map(ContextWindow::estimated_tokens).sum();
+            windows.insert(file.path.clone(), found);
+        }
+        windows
+    }
+
+    /// Files recently deleted from any repository, with prefixed paths.
+    pub fn recently_deleted(&self) -> Vec<Tombstone> {
+        self.repos
+            .iter()
+            .zip(&self.indexes)
+            .filter_map(|(repo, index)| Some((repo, index.as_ref()?)))
+            .flat_map(|(repo, index)| {
+                topo_index::recently_deleted(index, &repo.bundle.files)
+                    .into_iter()
+                    .map(|tombstone| Tombstone {
+                        path: repo.qualify(&tombstone.path),
+                        ..tombstone
+                    })
+            })
+            .collect()
+    }
+}
+
+/// Capture the tool state that produced a selection.
+pub fn environment(
+    preset: Preset,
+    config: &Config,
+    bundle: &Bundle,
+    deep_index: Option<&DeepIndex>,
+) -> Environment {
+    Environment {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        index_version: deep_index.map(|index| index.version),
+        fingerprint: bundle.fingerprint.clone(),
+        preset: preset.as_str().to_string(),
+        tokenizer: TOKEN_ESTIMATOR.to_string(),
+        config_hash: config.hash().to_string(),
+        degraded: Vec::new(),
+    }
+}
+
+/// Signals `preset` (and `fuzzy`) would use that are missing from
+/// `deep_index`, which scoring silently skips: the deep index itself, its
+/// trigrams, or the git history it was built with.
+pub fn degraded(preset: Preset, fuzzy: bool, deep_index: Option<&DeepIndex>) -> Vec<String> {
+    let structural = preset.use_structural_signals();
+    let mut missing = Vec::new();
+    match deep_index {
+        None if structural || fuzzy => missing.push("deep_index"),
+        None => {}
+        Some(index) => {
+            if fuzzy && index.trigrams.is_none() {
+                missing.push("trigrams");
+            }
+            if structural && index.git_activity.is_none() {
+                missing.push("git");
+            }
+        }
+    }
+    missing.into_iter().map(String::from).collect()
 }
 
+/// How query terms are matched, from the `[scoring]`, `[index]`, and
+/// `[synonyms]` sections of the config.
+#[derive(Debug, Clone)]
+pub struct ScoringOptions {
+    /// BM25F field weights, see [`field_weights`].
+    pub weights: FieldWeights,
+    /// `[index] stemming`, for repositories without a deep index.
+    pub stemming: bool,
+    /// The built-in abbreviations extended with `[synonyms]`.
+    pub synonyms: Synonyms,
+    /// Whether query terms also match near misses, with `--fuzzy`.
+    pub fuzzy: bool,
+    /// Which tests and implementation files boost each other, with
+    /// `--include-tests`.
+    pub coupling: Coupling,
+    /// Weights fitted by `topo feedback`, replacing the preset's.
+    pub profile: Option<WeightProfile>,
+    /// How scores are rescaled once all signals are in, `query.normalize`.
+    pub normalization: Normalization,
+    /// How git history makes files recent, see [`recency_model`].
+    pub recency: RecencyModel,
+    /// Boost of entry points and hub files, `[scoring] centrality_weight`.
+    pub centrality_weight: f64,
+    /// Path corpus statistics of all the files scored, when cached.
+    pub path_stats: Option<CorpusStats>,
+    /// Weights of query terms other than 1.0, from a document.
+    pub query_weights: Vec<(String, f64)>,
+    /// Terms whose matches lower a file's score.
+    pub penalties: Vec<String>,
+    /// Globs restricting the files scored, see [`HybridScorer::scope`].
+    pub scope: PathScope,
+}
+
+/// Query options overriding [`ScoringOptions`] per query.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Matching<'a> {
+    pub fuzzy: bool,
+    pub coupling: Coupling,
+    /// `--normalize`, if given.
+    pub normalization: Option<Normalization>,
+    /// `--author`: files this author committed to recently are boosted.
+    pub author: Option<&'a str>,
+    /// Weights of query terms from a document, see
+    /// [`QueryArgs::with_documents`].
+    pub query_weights: &'a [(String, f64)],
+    /// Terms of the query's `-term` words, see [`ParsedQuery::parse`].
+    pub penalties: &'a [String],
+}
+
+impl ScoringOptions {
+    pub fn from_config(config: &Config) -> Self {
+        let synonyms = config
+            .synonyms
+            .iter()
+            .fold(Synonyms::builtin(), |synonyms, (term, values)| {
+                synonyms.with(term, values.iter().map(String::as_str))
+            });
+        Self {
+            weights: field_weights(config),
+            stemming: config.index.stemming,
+            synonyms,
+            fuzzy: false,
+            coupling: Coupling::Off,
+            profile: None,
+            normalization: config
+                .query
+                .normalize
+                .as_deref()
+                .and_then(Normalization::named)
+                .unwrap_or_default(),
+            recency: recency_model(config),
+            centrality_weight: config
+                .scoring
+                .centrality_weight
+                .unwrap_or(DEFAULT_CENTRALITY_WEIGHT),
+            path_stats: None,
+            query_weights: Vec::new(),
+            penalties: Vec::new(),
+            scope: PathScope::default(),
+        }
+    }
+}
+
+/// BM25F field weights with the `[scoring]` overrides of `config`.
+pub fn field_weights(config: &Config) -> FieldWeights {
+    let defaults = FieldWeights::default();
+    FieldWeights {
+        docs: config.scoring.docs_weight.unwrap_or(defaults.docs),
+        ..defaults
+    }
+}
+
+/// The git recency model with the `[scoring]` overrides of `config`.
+pub fn recency_model(config: &Config) -> RecencyModel {
+    let defaults = RecencyModel::default();
+    RecencyModel {
+        half_life_days: config
+            .scoring
+            .recency_half_life_days
+            .unwrap_or(defaults.half_life_days),
+        lookback_days: config
+            .scoring
+            .recency_lookback_days
+            .unwrap_or(defaults.lookback_days),
+        commit_weight: config
+            .scoring
+            .recency_commit_weight
+            .unwrap_or(defaults.commit_weight),
+    }
+}
+
+/// Score `files` for `task`, with query terms stemmed as `deep_index` was
+/// built, or without one, as `scoring` says.
 pub fn score_files(
     task: &str,
     files: &[topo_core::FileInfo],
-    _preset: Preset,
+    preset: Preset,
     deep_index: Option<&DeepIndex>,
+    fuzzy: Option<&FuzzyCandidates>,
+    scoring: &ScoringOptions,
 ) -> Vec<ScoredFile> {
-    let scorer = HybridScorer::new(task);
-    let mut scored = scorer.score(files);
+    let stemming = deep_index.map_or(scoring.stemming, |index| index.stemmed);
+    let mut scorer = HybridScorer::new(task)
+        .preset(&preset.scoring())
+        .field_weights(scoring.weights)
+        .stemming(stemming)
+        .synonyms(scoring.synonyms.clone())
+        .fuzzy(scoring.fuzzy)
+        .coupling(scoring.coupling)
+        .query_weights(scoring.query_weights.clone())
+        .penalize(scoring.penalties.iter().cloned())
+        .scope(scoring.scope.clone());
+    if let Some(stats) = &scoring.path_stats {
+        scorer = scorer.corpus_stats(stats.clone());
+    }
+    if scoring.coupling != Coupling::Off
+        && let Some(index) = deep_index
+    {
+        scorer = scorer.imports(index.dependencies.clone());
+    }
+    // Weights fitted with `topo feedback` replace the preset's, unless the
+    // preset leaves BM25F out
+    let profile = scoring
+        .profile
+        .filter(|_| preset.scoring().bm25f_weight > 0.0);
+    if let Some(profile) = profile {
+        scorer = scorer.weights(profile.bm25f, profile.heuristic);
+    }
+    let mut scored = match fuzzy.filter(|f| !f.terms.is_empty()) {
+        // Candidates containing a fuzzy match are ranked on their indexed
+        // terms; everything else keeps path-only scoring
+        Some(fuzzy) => scorer
+            .expand_weighted(fuzzy.terms.iter().cloned())
+            .score_with_index(files, &fuzzy.documents, fuzzy.stats()),
+        None => scorer.score(files),
+    };
 
     // Apply PageRank via RRF fusion when available
     if let Some(index) = deep_index
@@ -93,86 +1354,957 @@ pub fn score_files(
 
         // Fuse base ranking with PageRank ranking via RRF
         if !pr_ranking.is_empty() {
+            let weight = profile.map_or(1.0, |profile| profile.pagerank);
             let fusion = RrfFusion::new();
-            fusion.fuse_scored(&mut scored, &[pr_ranking]);
+            fusion.fuse_scored_weighted(&mut scored, &[(pr_ranking, weight)]);
+        }
+    }
+
+    // Boost entry points, and with a deep index, files many others import
+    if scoring.centrality_weight > 0.0 {
+        let centrality = deep_index.map_or_else(Centrality::default, |index| {
+            Centrality::new(&index.dependencies)
+        });
+        centrality.apply(scoring.centrality_weight, &mut scored);
+        scored.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+    }
+
+    // Boost recently active files from the history recorded at index time
+    if let Some(activity) = deep_index.and_then(|index| index.git_activity.as_ref()) {
+        let recency = topo_score::activity_recency(activity, &scoring.recency);
+        for file in &mut scored {
+            if let Some(&score) = recency.get(&file.path) {
+                file.signals.git_recency = Some(score);
+                file.score *= 1.0 + RECENCY_WEIGHT * score;
+            }
         }
+        scored.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
     }
 
     scored
 }
 
-pub fn output_results(
-    cli: &Cli,
+/// Largest fraction the git recency signal adds to a file's score.
+const RECENCY_WEIGHT: f64 = 0.1;
+
+/// Largest fraction the ownership signal adds to a file's score.
+const OWNERSHIP_WEIGHT: f64 = 0.25;
+
+/// Boost leading files that the querying author owns or that changed
+/// recently, keeping `scored` sorted. Returns the blame summaries.
+///
+/// Only the top `candidates` are blamed, see
+/// [`topo_score::ScoringPreset::blame_candidates`], since blame is the
+/// most expensive signal, and their blame is cached by the contents hash
+/// `files` record, see [`topo_index::cached_blame_summaries`]. Boosts only
+/// raise scores, so the rest of the ranking stays below them.
+pub fn apply_ownership(
+    root: &Path,
+    files: &[FileInfo],
+    scored: &mut [ScoredFile],
+    candidates: usize,
+) -> Result<HashMap<String, Ownership>> {
+    let Some(vcs) = topo_vcs::detect(root) else {
+        return Ok(HashMap::new());
+    };
+    let count = candidates.min(scored.len());
+    let candidates = &mut scored[..count];
+    let hashes: HashMap<&str, [u8; 32]> =
+        files.iter().map(|f| (f.path.as_str(), f.sha256)).collect();
+    let blamed: Vec<(&str, [u8; 32])> = candidates
+        .iter()
+        .map(|f| {
+            (
+                f.path.as_str(),
+                hashes.get(f.path.as_str()).copied().unwrap_or([0; 32]),
+            )
+        })
+        .collect();
+    let summaries = topo_index::cached_blame_summaries(root, &blamed, |paths| {
+        topo_score::blame_summaries(vcs.as_ref(), paths)
+    })?;
+    let ownership = topo_score::summarized_ownership(vcs.as_ref(), &summaries)?;
+
+    for file in candidates.iter_mut() {
+        if let Some(owner) = ownership.get(&file.path) {
+            let boost = owner.score();
+            file.signals.ownership = Some(boost);
+            file.score *= 1.0 + OWNERSHIP_WEIGHT * boost;
+        }
+    }
+    candidates.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    Ok(ownership)
+}
+
+/// Largest fraction the author affinity signal adds to a file's score.
+const AUTHOR_WEIGHT: f64 = 0.3;
+
+/// Boost files that `author` committed to recently per `activity`, keeping
+/// `scored` sorted, see [`RecencyModel::affinity`].
+fn apply_author(
+    author: &str,
+    activity: &GitActivity,
+    model: &RecencyModel,
+    scored: &mut [ScoredFile],
+) {
+    let affinity = topo_score::author_affinity(activity, author, model);
+    if affinity.is_empty() {
+        return;
+    }
+    for file in scored.iter_mut() {
+        if let Some(&affinity) = affinity.get(&file.path) {
+            file.signals.author = Some(affinity);
+            file.score *= 1.0 + AUTHOR_WEIGHT * affinity;
+        }
+    }
+    scored.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
+
+/// Largest fraction the public API signal adds to a file's score.
+const EXPORTS_WEIGHT: f64 = 0.2;
+
+/// Boost leading files whose public API names query terms, keeping
+/// `scored` sorted, see [`FileEntry::exports`]. `entry` looks up a file's
+/// index entry.
+///
+/// Only the top `candidates` are checked, see
+/// [`topo_score::ScoringPreset::candidates`], since their entries may have
+/// to be read from the index. Boosts only raise scores, so the rest of the
+/// ranking stays below them.
+pub fn apply_exports<'a>(
     task: &str,
-    preset: Preset,
+    scored: &mut [ScoredFile],
+    candidates: usize,
+    entry: impl Fn(&str) -> Option<&'a FileEntry>,
+) {
+    let terms: HashSet<String> = Tokenizer::tokenize(task).into_iter().collect();
+    if terms.is_empty() {
+        return;
+    }
+    let count = candidates.min(scored.len());
+    let candidates = &mut scored[..count];
+    for file in candidates.iter_mut() {
+        let Some(entry) = entry(&file.path) else {
+            continue;
+        };
+        let api: HashSet<String> = entry
+            .exports
+            .iter()
+            .flat_map(|name| Tokenizer::tokenize(&name.replace("::", " ")))
+            .collect();
+        let matched = terms.iter().filter(|term| api.contains(*term)).count();
+        if matched > 0 {
+            let share = matched as f64 / terms.len() as f64;
+            file.signals.exports = Some(share);
+            file.score *= 1.0 + EXPORTS_WEIGHT * share;
+        }
+    }
+    candidates.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
+
+/// Boost leading files confirming the quoted phrases of `task`, keeping
+/// `scored` sorted, see [`Phrases`]: a phrase's words adjacent in the
+/// path, or together in one indexed chunk. Words of a phrase found apart
+/// earn nothing beyond their own term matches. Only the top `candidates`
+/// are checked. `entry` looks up a file's index entry; `stemming` must
+/// match the index's.
+pub fn apply_phrases<'a>(
+    task: &str,
+    stemming: bool,
+    scored: &mut [ScoredFile],
+    candidates: usize,
+    entry: impl Fn(&str) -> Option<&'a FileEntry>,
+) {
+    let phrases = Phrases::parse(task, stemming);
+    if phrases.is_empty() {
+        return;
+    }
+    let count = candidates.min(scored.len());
+    let candidates = &mut scored[..count];
+    for file in candidates.iter_mut() {
+        let chunks = entry(&file.path)
+            .and_then(|entry| entry.chunk_terms.as_deref())
+            .unwrap_or_default();
+        let confirmed = phrases.confirmed(&file.path, chunks);
+        if confirmed > 0.0 {
+            file.signals.phrase = Some(confirmed);
+            file.score *= 1.0 + PHRASE_WEIGHT * confirmed;
+        }
+    }
+    candidates.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
+
+/// Fuzzy matches per query term, at most this many.
+const MAX_FUZZY_MATCHES: usize = 3;
+
+/// Indexed terms similar to query terms, and the files containing them.
+///
+/// Built from the index's trigram index; empty if it was built without one.
+/// Only the shards holding candidate files are read.
+pub struct FuzzyCandidates {
+    /// Vocabulary terms similar to query terms missing from the index, with
+    /// their trigram similarity.
+    pub terms: Vec<(String, f64)>,
+    /// Term frequencies and lengths of files containing any of `terms`.
+    documents: HashMap<String, (HashMap<String, TermFreqs>, u32)>,
+    total_docs: usize,
+    avg_doc_length: f64,
+    doc_frequencies: HashMap<String, usize>,
+}
+
+impl FuzzyCandidates {
+    /// Fuzzy matches of `task` in `sharded`, with corpus statistics over
+    /// the `files` in `scope`, if the query is scoped, else the whole index.
+    pub fn from_index(
+        task: &str,
+        sharded: &ShardedIndex,
+        files: &[FileInfo],
+        scope: &PathScope,
+    ) -> Self {
+        let index = sharded.corpus();
+        let mut terms: Vec<(String, f64)> = Vec::new();
+        let mut documents = HashMap::new();
+        if let Some(trigrams) = &index.trigrams {
+            for token in Tokenizer::terms(task, index.stemmed) {
+                if trigrams.contains(&token) {
+                    continue;
+                }
+                for found in trigrams
+                    .similar_terms(&token, DEFAULT_MIN_SIMILARITY)
+                    .into_iter()
+                    .take(MAX_FUZZY_MATCHES)
+                {
+                    if terms.iter().any(|(t, _)| t == found.term) {
+                        continue;
+                    }
+                    for path in trigrams.files_with(found.term) {
+                        if let Some(entry) = sharded.entry(path) {
+                            documents.insert(
+                                path.to_string(),
+                                (entry.term_frequencies.clone(), entry.doc_length),
+                            );
+                        }
+                    }
+                    terms.push((found.term.to_string(), found.similarity));
+                }
+            }
+        }
+        if !scope.is_empty() {
+            let in_scope = files.iter().filter(|file| scope.contains(&file.path));
+            let stats = CorpusStats::from_documents(in_scope.filter_map(|file| {
+                let entry = sharded.entry(&file.path)?;
+                Some((
+                    file.path.as_str(),
+                    &entry.term_frequencies,
+                    entry.doc_length,
+                ))
+            }));
+            return Self {
+                terms,
+                documents,
+                total_docs: stats.total_docs,
+                avg_doc_length: stats.avg_doc_length,
+                doc_frequencies: stats.doc_frequencies,
+            };
+        }
+        Self {
+            terms,
+            documents,
+            total_docs: index.total_docs as usize,
+            avg_doc_length: index.avg_doc_length,
+            doc_frequencies: index
+                .doc_frequencies
+                .iter()
+                .map(|(term, df)| (term.clone(), *df as usize))
+                .collect(),
+        }
+    }
+
+    fn stats(&self) -> CorpusStats {
+        CorpusStats {
+            total_docs: self.total_docs,
+            avg_doc_length: self.avg_doc_length,
+            doc_frequencies: self.doc_frequencies.clone(),
+        }
+    }
+}
+
+/// Extras shown alongside query results.
+#[derive(Debug, Default)]
+pub struct Annotations {
+    /// Blame ownership by path, for presets with structural signals.
+    pub ownership: HashMap<String, Ownership>,
+    /// Where the query matched, with `--highlight`.
+    pub matches: Matches,
+    /// Lines around query matches by path, with `--context`, or the best
+    /// matching chunks, with `--chunks`.
+    pub context: HashMap<String, Vec<ContextWindow>>,
+    /// Material picked to orient the reader, with `--orientation`.
+    pub orientation: Orientation,
+    /// What the query cost up to rendering, with `--verbose` and JSON
+    /// output.
+    pub usage: Option<ResourceUsage>,
+}
+
+/// Orientation material in a selection, see [`select`].
+#[derive(Debug, Default)]
+pub struct Orientation {
+    /// Selected files picked for orientation rather than rank.
+    pub paths: HashSet<String>,
+    /// Directory outline of the repository.
+    pub tree: Option<String>,
+}
+
+/// Spend `budget` on the `ranked` files, first reserving `share` of it for
+/// orientation material drawn from `all` scored files.
+///
+/// The reserved part goes to a directory outline, then to READMEs and
+/// entry points wherever they fit. Whatever it leaves unused goes to the
+/// ranked files. Orientation files come first in the selection.
+pub fn select(
+    budget: &TokenBudget,
+    share: f64,
+    ranked: &[ScoredFile],
+    all: &[ScoredFile],
+) -> (Vec<ScoredFile>, Orientation) {
+    if share <= 0.0 {
+        return (budget.enforce(ranked), Orientation::default());
+    }
+    let mut reserved = budget.share(share);
+    let paths = || all.iter().map(|f| f.path.as_str());
+
+    let outline = topo_scanner::tree_summary(paths());
+    let outline_tokens = estimate_tokens(outline.len() as u64);
+    let tree = reserved.fits(outline_tokens).then(|| {
+        reserved = reserved.remaining(outline_tokens);
+        outline
+    });
+
+    let by_path: HashMap<&str, &ScoredFile> = all.iter().map(|f| (f.path.as_str(), f)).collect();
+    let candidates: Vec<ScoredFile> = topo_scanner::orientation_files(paths())
+        .into_iter()
+        .map(|path| by_path[path].clone())
+        .collect();
+    let mut files = reserved.fill(&candidates);
+    let spent = files.iter().map(|f| f.tokens).sum::<u64>()
+        + if tree.is_some() { outline_tokens } else { 0 };
+
+    let orientation = Orientation {
+        paths: files.iter().map(|f| f.path.clone()).collect(),
+        tree,
+    };
+    let rest: Vec<ScoredFile> = ranked
+        .iter()
+        .filter(|f| !orientation.paths.contains(&f.path))
+        .cloned()
+        .collect();
+    files.extend(budget.remaining(spent).enforce(&rest));
+    (files, orientation)
+}
+
+/// Render the selected files in the output format `cli` selects.
+pub fn render_results(
+    cli: &Cli,
+    args: &QueryArgs,
     files: &[ScoredFile],
     scanned_count: usize,
-    max_bytes: u64,
-    min_score: f64,
-) -> Result<()> {
+    env: &Environment,
+    recently_deleted: &[Tombstone],
+    annotations: &Annotations,
+) -> Result<String> {
+    let files = &*topo_render::pinned_first(files);
+    let mut out = String::new();
+    let ownership = &annotations.ownership;
+    let matched_lines = &annotations.matches.lines;
+    let task = args.task.as_str();
+    let preset = env.preset.as_str();
     match cli.effective_format() {
         OutputFormat::Jsonl | OutputFormat::Auto => {
-            let output = JsonlWriter::new(task, preset.as_str())
-                .max_bytes(Some(max_bytes))
-                .min_score(min_score)
+            let output = JsonlWriter::new(task, preset)
+                .max_bytes(Some(args.effective_max_bytes()))
+                .min_score(args.effective_min_score())
+                .environment(env.clone())
+                .recently_deleted(recently_deleted.to_vec())
+                .ownership(ownership.clone())
+                .matched_lines(matched_lines.clone())
+                .context(annotations.context.clone())
+                .orientation(annotations.orientation.paths.clone())
+                .tree(annotations.orientation.tree.clone())
                 .render(files, scanned_count)?;
-            print!("{output}");
+            out.push_str(&output);
         }
         OutputFormat::Json => {
-            let json_output = serde_json::json!({
-                "version": "0.3",
+            let mut json_output = serde_json::json!({
+                "version": "0.4",
                 "query": task,
-                "preset": preset.as_str(),
+                "preset": preset,
                 "files": files.iter().map(|f| serde_json::json!({
                     "path": f.path,
                     "score": f.score,
                     "tokens": f.tokens,
                     "language": f.language.as_str(),
                     "role": f.role.as_str(),
+                    "preview": f.preview,
+                    "owner": ownership.get(&f.path),
+                    "matched_lines": matched_lines.get(&f.path),
+                    "context": annotations.context.get(&f.path),
+                    "orientation": annotations.orientation.paths.contains(&f.path),
+                    "pinned": f.signals.pinned,
+                    "raw_score": f.signals.raw,
+                    "duplicate_of": f.signals.duplicate_of,
                 })).collect::<Vec<_>>(),
                 "total_files": files.len(),
                 "scanned_files": scanned_count,
+                "environment": env,
             });
-            println!("{}", serde_json::to_string_pretty(&json_output)?);
+            if !recently_deleted.is_empty() {
+                json_output["recently_deleted"] = serde_json::to_value(recently_deleted)?;
+            }
+            if let Some(tree) = &annotations.orientation.tree {
+                json_output["tree"] = tree.as_str().into();
+            }
+            if let Some(usage) = &annotations.usage {
+                json_output["resources"] = serde_json::to_value(usage)?;
+            }
+            writeln!(out, "{}", serde_json::to_string_pretty(&json_output)?)?;
         }
         OutputFormat::Compact => {
-            let output = CompactWriter::new().render(files);
-            print!("{output}");
+            let output = CompactWriter::new()
+                .environment(env.clone())
+                .recently_deleted(recently_deleted.to_vec())
+                .render(files);
+            out.push_str(&output);
+        }
+        OutputFormat::Html => {
+            let root = cli.repo_root()?;
+            let output = HtmlWriter::new(task, preset)
+                .contents(read_contents(&root, files))
+                .context(annotations.context.clone())
+                .environment(env.clone())
+                .highlight(annotations.matches.words.clone())
+                .render(files, scanned_count)?;
+            out.push_str(&output);
+        }
+        OutputFormat::Sarif => {
+            let config = Config::load(&cli.repo_root()?)?;
+            let violations = config.policy.check(files)?;
+            let output = SarifWriter::new(&env.version)
+                .environment(env.clone())
+                .render(&violations)?;
+            out.push_str(&output);
         }
         OutputFormat::Human => {
+            if let Some(tree) = &annotations.orientation.tree {
+                writeln!(out, "{tree}")?;
+                writeln!(out)?;
+            }
             if !files.is_empty() {
-                println!(
+                writeln!(
+                    out,
                     "{:<60} {:>8} {:>8} {:>8}",
                     "PATH", "SCORE", "TOKENS", "LANG"
-                );
-                println!("{}", "-".repeat(88));
+                )?;
+                writeln!(out, "{}", "-".repeat(88))?;
                 for f in files {
-                    println!(
+                    writeln!(
+                        out,
                         "{:<60} {:>8.4} {:>8} {:>8}",
                         truncate_path(&f.path, 60),
                         f.score,
                         f.tokens,
                         f.language.as_str(),
-                    );
+                    )?;
+                    if let Some(windows) = annotations.context.get(&f.path) {
+                        write_windows(&mut out, windows)?;
+                    }
                 }
-                println!("{}", "-".repeat(88));
+                writeln!(out, "{}", "-".repeat(88))?;
             }
-            println!(
+            writeln!(
+                out,
                 "{} files selected (of {} scanned) for query: \"{}\"",
                 files.len(),
                 scanned_count,
                 task
-            );
+            )?;
+            if !recently_deleted.is_empty() {
+                let paths: Vec<_> = recently_deleted
+                    .iter()
+                    .map(|t| display_path(&t.path))
+                    .collect();
+                writeln!(out, "recently deleted: {}", paths.join(", "))?;
+            }
+            writeln!(out, "{}", env.summary())?;
         }
     }
 
+    Ok(out)
+}
+
+/// Write context windows under a result row, numbered like `grep -n`.
+fn write_windows(out: &mut String, windows: &[ContextWindow]) -> std::fmt::Result {
+    for (i, window) in windows.iter().enumerate() {
+        if i > 0 {
+            writeln!(out, "    --")?;
+        }
+        if !window.kinds.is_empty() {
+            let kinds: Vec<&str> = window.kinds.iter().map(ChunkKind::as_str).collect();
+            write!(out, "    [{}]", kinds.join(", "))?;
+            if let Some(files) = window.duplicated_in {
+                write!(out, " (duplicated in {files} files)")?;
+            }
+            writeln!(out)?;
+        }
+        for (n, line) in (window.start_line..).zip(window.text.lines()) {
+            writeln!(out, "    {n:>5}: {line}")?;
+        }
+    }
     Ok(())
 }
 
-fn truncate_path(path: &str, max_len: usize) -> String {
-    if path.len() <= max_len {
-        path.to_string()
+/// Fill in one-line match previews for the selected files, read from
+/// under `root`, see [`Previewer::annotate`].
+///
+/// `chunks` looks up a file's indexed chunks by path, see [`Federation::chunks`].
+pub fn add_previews<'i>(
+    root: &Path,
+    task: &str,
+    expansions: &[String],
+    files: &mut [ScoredFile],
+    chunks: impl Fn(&str) -> &'i [Chunk],
+    highlight: bool,
+) -> Matches {
+    HybridScorer::new(task)
+        .expand(expansions.iter().cloned())
+        .previewer()
+        .annotate(
+            files,
+            |path| read_small(&root.join(path)),
+            chunks,
+            highlight,
+        )
+}
+
+/// Lines of context around each match in `--lockfiles` mode.
+const LOCKFILE_CONTEXT: u32 = 3;
+
+/// Largest lockfile searched in `--lockfiles` mode.
+const MAX_LOCKFILE_READ_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Rank lockfiles `files` by how many of their lines match `task`, best
+/// first, narrowing each to the lines within `context` lines of a match.
+///
+/// Lockfiles without a matching line are dropped. Scores are the share of
+/// [`topo_score::MAX_MATCHED_LINES`] matched, so a dependency pinned in
+/// many places ranks its lockfile higher.
+fn lockfile_windows(
+    root: &Path,
+    task: &str,
+    files: &mut Vec<ScoredFile>,
+    context: u32,
+) -> HashMap<String, Vec<ContextWindow>> {
+    let previewer = HybridScorer::new(task).previewer();
+    let mut windows = HashMap::new();
+    files.retain_mut(|file| {
+        let path = root.join(&file.path);
+        let Some(content) = std::fs::metadata(&path)
+            .ok()
+            .filter(|m| m.len() <= MAX_LOCKFILE_READ_BYTES)
+            .and_then(|_| resources::read_to_string(&path).ok())
+        else {
+            return false;
+        };
+        let matched = previewer.matched_lines(&content);
+        if matched.is_empty() {
+            return false;
+        }
+        let found = ContextWindow::around(&content, &matched, context);
+        file.score = matched.len() as f64 / topo_score::MAX_MATCHED_LINES as f64;
+        file.tokens = found.iter().map(ContextWindow::estimated_tokens).sum();
+        windows.insert(file.path.clone(), found);
+        true
+    });
+    files.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.path.cmp(&b.path)));
+    windows
+}
+
+/// Narrow `files` to the lines within `context` lines of a query match.
+///
+/// Each file's tokens become those of its windows, so the budget counts
+/// only what gets embedded. Files without a matching line are dropped.
+pub fn context_windows(
+    root: &Path,
+    task: &str,
+    expansions: &[String],
+    files: &mut Vec<ScoredFile>,
+    context: u32,
+) -> HashMap<String, Vec<ContextWindow>> {
+    let previewer = HybridScorer::new(task)
+        .expand(expansions.iter().cloned())
+        .previewer();
+    let mut windows = HashMap::new();
+    files.retain_mut(|file| {
+        let Some(content) = read_small(&root.join(&file.path)) else {
+            return false;
+        };
+        let found = ContextWindow::around(&content, &previewer.matched_lines(&content), context);
+        if found.is_empty() {
+            return false;
+        }
+        file.tokens = found.iter().map(ContextWindow::estimated_tokens).sum();
+        windows.insert(file.path.clone(), found);
+        true
+    });
+    windows
+}
+
+/// The lines of `content` in each sorted 1-based `(start, end)` span of a
+/// chunk of the given kind, with adjacent spans joined.
+fn chunk_spans(content: &str, spans: &[(u32, u32, ChunkKind)]) -> Vec<ContextWindow> {
+    let lines: Vec<&str> = content.lines().collect();
+    let last = lines.len() as u32;
+    let mut merged: Vec<(u32, u32, Vec<ChunkKind>)> = Vec::new();
+    for &(start, end, kind) in spans {
+        let (start, end) = (start.max(1), end.min(last));
+        if start > end {
+            continue;
+        }
+        match merged.last_mut() {
+            Some((_, prev_end, kinds)) if start <= *prev_end + 1 => {
+                *prev_end = (*prev_end).max(end);
+                kinds.push(kind);
+            }
+            _ => merged.push((start, end, vec![kind])),
+        }
+    }
+    merged
+        .into_iter()
+        .map(|(start, end, kinds)| ContextWindow {
+            start_line: start,
+            end_line: end,
+            text: lines[start as usize - 1..end as usize].join("\n"),
+            kinds,
+            duplicated_in: None,
+        })
+        .collect()
+}
+
+/// Windows of 1-based `(line, text, kind)` signature lines, with adjacent
+/// lines joined, like [`chunk_spans`] of one-line spans.
+fn signature_lines(mut signatures: Vec<(u32, &str, ChunkKind)>) -> Vec<ContextWindow> {
+    signatures.sort_unstable_by_key(|&(line, _, _)| line);
+    signatures.dedup_by_key(|&mut (line, _, _)| line);
+    let mut windows: Vec<ContextWindow> = Vec::new();
+    for (line, text, kind) in signatures {
+        match windows.last_mut() {
+            Some(window) if line == window.end_line + 1 => {
+                window.end_line = line;
+                window.text.push('\n');
+                window.text.push_str(text);
+                window.kinds.push(kind);
+            }
+            _ => windows.push(ContextWindow {
+                start_line: line,
+                end_line: line,
+                text: text.to_string(),
+                kinds: vec![kind],
+                duplicated_in: None,
+            }),
+        }
+    }
+    windows
+}
+
+/// Read a file small enough to preview, if it is UTF-8.
+fn read_small(path: &Path) -> Option<String> {
+    std::fs::metadata(path)
+        .ok()
+        .filter(|m| m.len() <= MAX_PREVIEW_READ_BYTES)
+        .and_then(|_| resources::read_to_string(path).ok())
+}
+
+/// Read the contents of the selected files for embedding in reports.
+///
+/// Unreadable or non-UTF-8 files are skipped, and so are files larger than
+/// [`MAX_PREVIEW_READ_BYTES`], like generated files or blobs.
+pub fn read_contents(root: &Path, files: &[ScoredFile]) -> HashMap<String, String> {
+    files
+        .iter()
+        .filter_map(|f| Some((f.path.clone(), read_small(&root.join(&f.path))?)))
+        .collect()
+}
+
+/// Escape `path` for display and keep at most its last `max_len` characters.
+pub(crate) fn truncate_path(path: &str, max_len: usize) -> String {
+    let path = display_path(path);
+    let len = path.chars().count();
+    if len <= max_len {
+        path.into_owned()
     } else {
-        format!("...{}", &path[path.len() - max_len + 3..])
+        let tail: String = path.chars().skip(len - max_len + 3).collect();
+        format!("...{tail}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use topo_core::EmbedError;
+
+    /// Embeds texts by the concepts their words stand for, so synonyms
+    /// land on the same axis.
+    struct Concepts;
+
+    impl Embedder for Concepts {
+        fn model(&self) -> &str {
+            "concepts"
+        }
+
+        fn dimension(&self) -> u32 {
+            2
+        }
+
+        fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, EmbedError> {
+            let axis = |word: &str| match word {
+                "rate" | "limiting" | "throttle" => Some(0),
+                "render" | "view" => Some(1),
+                _ => None,
+            };
+            Ok(texts
+                .iter()
+                .map(|text| {
+                    let mut vector = vec![0.0; 2];
+                    let words = text.split(|c: char| !c.is_alphanumeric());
+                    for i in words.filter_map(|w| axis(&w.to_lowercase())) {
+                        vector[i] += 1.0;
+                    }
+                    vector
+                })
+                .collect())
+        }
+    }
+
+    #[test]
+    fn embedded_queries_fuse_conceptual_matches() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir(root.join("src")).unwrap();
+        for (path, content) in [
+            ("src/logic.rs", "fn logic() {}\n"),
+            ("src/view.rs", "fn view() {}\n"),
+            ("src/throttle.rs", "fn allow() {}\n"),
+        ] {
+            std::fs::write(root.join(path), content).unwrap();
+        }
+        let federation = Federation::scan(root, &Config::default(), false).unwrap();
+        let score = |federation: &Federation| {
+            let (scored, _) = federation.score(
+                "rate limiting logic",
+                Preset::Fast,
+                Matching::default(),
+                &[],
+                &[],
+                &QueryFilters::default(),
+            );
+            scored
+        };
+
+        let lexical = score(&federation);
+        assert_eq!(lexical[0].path, "src/logic.rs");
+        assert!(lexical.iter().all(|f| f.signals.embedding.is_none()));
+
+        let federation = federation.embedder(Some(Arc::new(Concepts)));
+        let embedded = score(&federation);
+        assert_eq!(embedded[0].path, "src/throttle.rs");
+        assert_eq!(embedded[0].signals.embedding, Some(1.0));
+        let view = embedded.iter().find(|f| f.path == "src/view.rs").unwrap();
+        assert_eq!(view.signals.embedding, Some(0.0));
+    }
+
+    #[test]
+    fn compares_retrieval_with_the_configured_model() {
+        use crate::commands::index::{self, IndexOptions};
+        use clap::Parser;
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::create_dir(root.join("src")).unwrap();
+        std::fs::write(root.join("src/logic.rs"), "fn logic() {}\n").unwrap();
+        std::fs::write(root.join("src/view.rs"), "fn view() {}\n").unwrap();
+        std::fs::write(root.join("src/throttle.rs"), "fn allow() {}\n").unwrap();
+        let root_arg = root.to_string_lossy().into_owned();
+        let cli = Cli::try_parse_from(["topo", "--quiet", "--root", &root_arg]).unwrap();
+        let options = IndexOptions {
+            deep: true,
+            embedder: Some(&Concepts),
+            ..Default::default()
+        };
+        index::run(&cli, &options).unwrap();
+
+        let cli = Cli::try_parse_from([
+            "topo",
+            "--quiet",
+            "--root",
+            &root_arg,
+            "query",
+            "rate limiting logic",
+            "--compare-retrieval",
+        ])
+        .unwrap();
+        let Some(Command::Query(args)) = &cli.command else {
+            panic!("expected Query");
+        };
+        let config = Config::default();
+        let federation = Federation::scan(&root, &config, args.needs_index())
+            .unwrap()
+            .embedder(Some(Arc::new(Concepts)));
+        let mut meter = Meter::new(Instant::now());
+        let output = respond(&cli, args, &config, &federation, &mut meter).unwrap();
+
+        let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(output["model"], "concepts");
+        let paths = |ranking: &str| -> Vec<String> {
+            output[ranking]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|r| r["path"].as_str().unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(paths("bm25f"), ["src/logic.rs"]);
+        assert_eq!(paths("embedding")[0], "src/throttle.rs");
+        let fused = paths("fused");
+        assert!(fused.contains(&"src/logic.rs".to_string()));
+        assert!(fused.contains(&"src/throttle.rs".to_string()));
+    }
+
+    #[test]
+    fn excluded_terms_leave_out_files_by_their_content() {
+        use crate::commands::index::{self, IndexOptions};
+        use clap::Parser;
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::create_dir(root.join("src")).unwrap();
+        std::fs::write(root.join("src/auth.rs"), "fn login() {}\n").unwrap();
+        std::fs::write(root.join("src/session.rs"), "// legacy auth\nfn old() {}\n").unwrap();
+        let root_arg = root.to_string_lossy().into_owned();
+        let cli = Cli::try_parse_from(["topo", "--quiet", "--root", &root_arg]).unwrap();
+        let options = IndexOptions {
+            deep: true,
+            ..Default::default()
+        };
+        index::run(&cli, &options).unwrap();
+
+        let federation = Federation::scan(&root, &Config::default(), true).unwrap();
+        let paths = |task: &str| -> Vec<String> {
+            let query = ParsedQuery::parse(task);
+            let (scored, _) = federation.score(
+                &query.text,
+                Preset::Fast,
+                Matching::default(),
+                &[],
+                &[],
+                &query.filters,
+            );
+            scored.into_iter().map(|f| f.path).collect()
+        };
+        assert!(paths("auth").contains(&"src/session.rs".to_string()));
+        assert_eq!(paths("auth !legacy"), ["src/auth.rs"]);
+    }
+
+    #[test]
+    fn report_contents_leave_out_large_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("small.rs"), "fn f() {}\n").unwrap();
+        let large = "x".repeat(MAX_PREVIEW_READ_BYTES as usize + 1);
+        std::fs::write(root.join("large.rs"), large).unwrap();
+        let files: Vec<ScoredFile> = ["small.rs", "large.rs"]
+            .iter()
+            .map(|path| ScoredFile {
+                path: path.to_string(),
+                score: 1.0,
+                signals: Default::default(),
+                tokens: 1,
+                language: topo_core::Language::Rust,
+                role: topo_core::FileRole::Implementation,
+                preview: None,
+            })
+            .collect();
+
+        let contents = read_contents(root, &files);
+        assert_eq!(contents["small.rs"], "fn f() {}\n");
+        assert!(!contents.contains_key("large.rs"));
+    }
+
+    #[test]
+    fn scoped_queries_count_only_files_in_scope() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for path in [
+            "src/auth/handler.rs",
+            "src/auth/middleware.rs",
+            "lib/cart.rs",
+            "lib/orders.rs",
+        ] {
+            std::fs::create_dir_all(root.join(path).parent().unwrap()).unwrap();
+            std::fs::write(root.join(path), "fn f() {}\n").unwrap();
+        }
+        let federation = Federation::scan(root, &Config::default(), false).unwrap();
+        let score = |scope: &[&str]| {
+            let scope: Vec<String> = scope.iter().map(|g| g.to_string()).collect();
+            let globs = path_globs(&scope).unwrap();
+            let query = ParsedQuery::parse("auth");
+            let (scored, _) = federation.score(
+                &query.text,
+                Preset::Fast,
+                Matching::default(),
+                &[],
+                &globs,
+                &query.filters,
+            );
+            scored
+        };
+        let bm25f = |scored: &[ScoredFile], path: &str| {
+            scored
+                .iter()
+                .find(|f| f.path == path)
+                .unwrap()
+                .signals
+                .bm25f
+        };
+
+        let everywhere = score(&[]);
+        let scoped = score(&["src/auth/**"]);
+        let mut paths: Vec<&str> = scoped.iter().map(|f| f.path.as_str()).collect();
+        paths.sort_unstable();
+        assert_eq!(paths, ["src/auth/handler.rs", "src/auth/middleware.rs"]);
+        // `auth` is in every file in scope, so it no longer tells them apart
+        assert!(bm25f(&scoped, "src/auth/handler.rs") < bm25f(&everywhere, "src/auth/handler.rs"));
     }
 }
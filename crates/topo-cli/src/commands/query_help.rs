@@ -0,0 +1,337 @@
+use super::query::{self, Federation, Matching, QueryArgs};
+use crate::preset::Preset;
+use crate::{Cli, Command, OutputFormat};
+use anyhow::{Result, bail};
+use clap::{CommandFactory, Parser, ValueEnum};
+use topo_core::Config;
+use topo_score::{FieldWeights, ParsedQuery, Tokenizer};
+
+/// Hits shown per example.
+const EXAMPLE_HITS: usize = 3;
+
+/// Query text whose terms are shown, to demonstrate the tokenizer.
+const SAMPLE_TEXT: &str = "How does parseHTTPResponse handle auth_token in refresh.rs";
+
+/// Example queries, as `topo query` arguments, with what each demonstrates.
+const EXAMPLES: &[(&str, &[&str])] = &[
+    (
+        "Plain words: every term adds to the score",
+        &["config loading"],
+    ),
+    (
+        "Identifiers are split on camelCase and snake_case",
+        &["parseConfig"],
+    ),
+    (
+        "Only files under a path are considered",
+        &["main", "--path", "**/src/**"],
+    ),
+    (
+        "Field filters in the query restrict the files scored",
+        &["role:impl lang:rust main"],
+    ),
+    (
+        "`-term` lowers files matching it; `!term` leaves out paths with it",
+        &["config -test !vendor"],
+    ),
+    (
+        "Heuristic scoring of paths alone, no index needed",
+        &["readme", "--preset", "fast"],
+    ),
+    (
+        "Typos match near misses, in identifiers with `topo index --deep --trigrams`",
+        &["confg", "--fuzzy"],
+    ),
+];
+
+/// Global flags and the positional task, which aren't query options.
+const NOT_OPTIONS: &[&str] = &[
+    "task", "verbose", "quiet", "format", "no_color", "progress", "root", "help",
+];
+
+/// Print how query text is turned into terms, the fields and weights terms
+/// are matched against, the query options and presets, and example queries
+/// with their top hits in this repository.
+///
+/// Everything is read from the parser and scorer rather than written out,
+/// so it can't fall out of date.
+pub fn run(cli: &Cli) -> Result<()> {
+    let root = cli.repo_root()?;
+    let config = Config::load(&root)?;
+    let federation = Federation::scan(&root, &config, true)?;
+    let preset = Preset::from_config(&config);
+    let examples = EXAMPLES
+        .iter()
+        .map(|(description, argv)| run_example(&federation, preset, description, argv))
+        .collect::<Result<Vec<_>>>()?;
+    let weights = query::field_weights(&config);
+
+    match cli.effective_format() {
+        OutputFormat::Json | OutputFormat::Jsonl => {
+            let json = serde_json::json!({
+                "terms": {
+                    "text": SAMPLE_TEXT,
+                    "terms": Tokenizer::tokenize(SAMPLE_TEXT),
+                },
+                "fields": fields(weights)
+                    .iter()
+                    .map(|(name, weight, _)| serde_json::json!({"field": name, "weight": weight}))
+                    .collect::<Vec<_>>(),
+                "options": options()
+                    .iter()
+                    .map(|(flag, help)| serde_json::json!({"option": flag, "help": help}))
+                    .collect::<Vec<_>>(),
+                "presets": Preset::value_variants()
+                    .iter()
+                    .map(preset_json)
+                    .collect::<Vec<_>>(),
+                "deep_index": federation.root_index().is_some(),
+                "examples": examples.iter().map(Example::to_json).collect::<Vec<_>>(),
+            });
+            println!("{}", serde_json::to_string_pretty(&json)?);
+        }
+        _ => {
+            println!("Query text");
+            println!(
+                "  Split on whitespace, punctuation, camelCase, and snake_case into lowercase"
+            );
+            println!("  terms; short and stop words are dropped. There are no boolean operators:");
+            println!("  every term adds to a file's score, except that `-term` takes off what it");
+            println!("  would add and `!term` leaves out files whose path has it. Pinned files");
+            println!("  always lead.");
+            println!("  {SAMPLE_TEXT:?}");
+            println!("    -> {}", Tokenizer::tokenize(SAMPLE_TEXT).join(" "));
+            println!();
+            println!("Fields (BM25F weight)");
+            for (name, weight, description) in fields(weights) {
+                println!("  {name:<10} {weight:>4}  {description}");
+            }
+            println!();
+            println!("Options");
+            for (flag, help) in options() {
+                println!("  {flag}");
+                println!("      {help}");
+            }
+            println!();
+            println!("Presets");
+            for preset in Preset::value_variants() {
+                println!("  {:<10} {}", preset.as_str(), preset_summary(preset));
+            }
+            println!();
+            println!("Examples");
+            if federation.root_index().is_none() {
+                println!("  (no deep index: only paths are matched; run `topo index --deep`)");
+            }
+            for example in &examples {
+                println!();
+                println!("  # {}", example.description);
+                println!("  {}", example.command());
+                if example.hits.is_empty() {
+                    println!("      (no matches)");
+                }
+                for (path, score) in &example.hits {
+                    println!("      {score:>7.4}  {path}");
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// An example query and its best hits.
+struct Example {
+    description: &'static str,
+    argv: &'static [&'static str],
+    hits: Vec<(String, f64)>,
+}
+
+impl Example {
+    /// The example as a command line.
+    fn command(&self) -> String {
+        let args: Vec<String> = self
+            .argv
+            .iter()
+            .map(|arg| {
+                if arg.contains([' ', '*']) {
+                    format!("'{arg}'")
+                } else {
+                    arg.to_string()
+                }
+            })
+            .collect();
+        format!("topo query {}", args.join(" "))
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let hits: Vec<serde_json::Value> = self
+            .hits
+            .iter()
+            .map(|(path, score)| serde_json::json!({"path": path, "score": score}))
+            .collect();
+        serde_json::json!({
+            "description": self.description,
+            "command": self.command(),
+            "hits": hits,
+        })
+    }
+}
+
+/// Parse `argv` as `topo query` would, with `preset` unless it gives one,
+/// and score it against `federation`.
+fn run_example(
+    federation: &Federation,
+    preset: Preset,
+    description: &'static str,
+    argv: &'static [&'static str],
+) -> Result<Example> {
+    let mut args = parse_example(argv)?;
+    args.preset = args.preset.or(Some(preset));
+    let globs = args.path_globs()?;
+    let parsed = ParsedQuery::parse(&args.task);
+    let (scored, _) = federation.score(
+        &parsed.text,
+        args.preset(),
+        Matching {
+            penalties: &parsed.penalties,
+            ..args.matching()
+        },
+        &args.meta,
+        &globs,
+        &parsed.filters,
+    );
+    let hits = scored
+        .into_iter()
+        .filter(|file| file.score >= args.effective_min_score())
+        .take(EXAMPLE_HITS)
+        .map(|file| (file.path, file.score))
+        .collect();
+    Ok(Example {
+        description,
+        argv,
+        hits,
+    })
+}
+
+fn parse_example(argv: &[&str]) -> Result<QueryArgs> {
+    let cli = Cli::try_parse_from(["topo", "query"].iter().chain(argv))?;
+    match cli.command {
+        Some(Command::Query(args)) => Ok(args),
+        _ => bail!("`{}` is not a query", argv.join(" ")),
+    }
+}
+
+/// Each BM25F field with its weight and what it holds.
+fn fields(weights: FieldWeights) -> [(&'static str, f64, &'static str); 4] {
+    [
+        ("filename", weights.filename, "Path components of the file"),
+        (
+            "symbols",
+            weights.symbols,
+            "Function, method, type, and other definition names (deep index)",
+        ),
+        (
+            "docs",
+            weights.docs,
+            "Doc comments and docstrings (deep index; `scoring.docs_weight`)",
+        ),
+        (
+            "body",
+            weights.body,
+            "Everything else in the file (deep index)",
+        ),
+    ]
+}
+
+/// `topo query` options with their help, read from the argument parser.
+fn options() -> Vec<(String, String)> {
+    let cli = Cli::command();
+    let Some(query) = cli.find_subcommand("query") else {
+        return Vec::new();
+    };
+    query
+        .get_arguments()
+        .filter(|arg| !arg.is_hide_set() && !NOT_OPTIONS.contains(&arg.get_id().as_str()))
+        .filter_map(|arg| {
+            let long = arg.get_long()?;
+            let flag = match arg.get_value_names() {
+                Some([name, ..]) if arg.get_action().takes_values() => {
+                    format!("--{long} <{name}>")
+                }
+                _ => format!("--{long}"),
+            };
+            let help = arg.get_help().map(ToString::to_string).unwrap_or_default();
+            Some((flag, help))
+        })
+        .collect()
+}
+
+/// What a preset does, in one line.
+fn preset_summary(preset: &Preset) -> String {
+    let help = preset
+        .to_possible_value()
+        .and_then(|value| value.get_help().map(ToString::to_string))
+        .unwrap_or_default();
+    let scoring = preset.scoring();
+    format!(
+        "{help}; BM25F {} / heuristic {}, boosts check the top {}; budget {} bytes, min score {}",
+        scoring.bm25f_weight,
+        scoring.heuristic_weight,
+        scoring.candidates,
+        scoring.max_bytes,
+        scoring.min_score
+    )
+}
+
+fn preset_json(preset: &Preset) -> serde_json::Value {
+    serde_json::json!({
+        "preset": preset.as_str(),
+        "summary": preset_summary(preset),
+        "max_bytes": preset.default_max_bytes(),
+        "min_score": preset.default_min_score(),
+        "deep_index": preset.needs_deep_index(),
+        "structural_signals": preset.use_structural_signals(),
+        "weights": {
+            "bm25f": preset.scoring().bm25f_weight,
+            "heuristic": preset.scoring().heuristic_weight,
+        },
+        "candidates": preset.scoring().candidates,
+        "blame_candidates": preset.scoring().blame_candidates,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn examples_parse_as_queries() {
+        for (_, argv) in EXAMPLES {
+            let args = parse_example(argv).unwrap();
+            assert_eq!(args.task, argv[0]);
+        }
+        assert!(parse_example(&["auth", "--no-such-flag"]).is_err());
+    }
+
+    #[test]
+    fn options_are_read_from_the_parser() {
+        let options = options();
+        let flags: Vec<&str> = options.iter().map(|(flag, _)| flag.as_str()).collect();
+        assert!(flags.contains(&"--path <GLOB>"));
+        assert!(flags.contains(&"--fuzzy"));
+        assert!(!flags.iter().any(|flag| flag.starts_with("--format")));
+        assert!(options.iter().all(|(_, help)| !help.is_empty()));
+    }
+
+    #[test]
+    fn example_commands_quote_shell_words() {
+        let example = Example {
+            description: "",
+            argv: &["config loading", "--path", "src/**"],
+            hits: Vec::new(),
+        };
+        assert_eq!(
+            example.command(),
+            "topo query 'config loading' --path 'src/**'"
+        );
+    }
+}
@@ -2,6 +2,8 @@ use crate::Cli;
 use anyhow::Result;
 use std::fs;
 use std::path::Path;
+use topo_core::{FileRole, Language, ScoredFile, SignalBreakdown, display_path};
+use topo_render::HtmlWriter;
 
 /// Read a JSONL file and re-render it.
 pub fn run(cli: &Cli, file: &Path, _max_tokens: Option<u64>) -> Result<()> {
@@ -38,14 +40,51 @@ pub fn run(cli: &Cli, file: &Path, _max_tokens: Option<u64>) -> Result<()> {
                     // File entry
                     println!(
                         "  {:<50} score={:.4} tokens={} lang={}",
-                        v["Path"].as_str().unwrap_or("?"),
+                        display_path(v["Path"].as_str().unwrap_or("?")),
                         v["Score"].as_f64().unwrap_or(0.0),
                         v["Tokens"],
                         v["Language"].as_str().unwrap_or("?"),
                     );
+                    if let Some(preview) = v["Preview"].as_str() {
+                        println!("    {preview}");
+                    }
                 }
             }
         }
+        crate::OutputFormat::Html => {
+            let mut query = String::new();
+            let mut preset = String::new();
+            let mut scanned = 0;
+            let mut files = Vec::new();
+            for line in content.lines().filter(|l| !l.trim().is_empty()) {
+                let v: serde_json::Value = serde_json::from_str(line)?;
+                if v.get("Version").is_some() {
+                    query = v["Query"].as_str().unwrap_or_default().to_string();
+                    preset = v["Preset"].as_str().unwrap_or_default().to_string();
+                } else if v.get("TotalFiles").is_some() {
+                    scanned = v["ScannedFiles"].as_u64().unwrap_or(0) as usize;
+                } else if let Some(path) = v["Path"].as_str() {
+                    files.push(ScoredFile {
+                        path: path.to_string(),
+                        score: v["Score"].as_f64().unwrap_or(0.0),
+                        signals: SignalBreakdown::default(),
+                        tokens: v["Tokens"].as_u64().unwrap_or(0),
+                        language: v["Language"]
+                            .as_str()
+                            .and_then(Language::from_name)
+                            .unwrap_or_else(|| Language::from_path(Path::new(path))),
+                        role: serde_json::from_value(v["Role"].clone()).unwrap_or(FileRole::Other),
+                        preview: v["Preview"].as_str().map(str::to_string),
+                    });
+                }
+            }
+
+            let root = cli.repo_root()?;
+            let output = HtmlWriter::new(&query, &preset)
+                .contents(super::query::read_contents(&root, &files))
+                .render(&files, scanned)?;
+            print!("{output}");
+        }
         _ => {
             // JSONL or JSON: pass through
             print!("{content}");
@@ -0,0 +1,442 @@
+use super::index::IndexOptions;
+use crate::preset::Preset;
+use crate::{Cli, OutputFormat};
+use anyhow::{Result, bail};
+use clap::ValueEnum;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::Path;
+use topo_core::{CONFIG_FILE, Config, FileInfo, IgnoreRules, Pins, TOKEN_ESTIMATOR};
+use topo_scanner::{BundleBuilder, GitAttributes, language_breakdown, orientation_files};
+
+/// Files at the repository root that mark a kind of project.
+const PROJECT_MARKERS: &[(&str, &str)] = &[
+    ("Cargo.toml", "Rust (Cargo)"),
+    ("go.mod", "Go (modules)"),
+    ("package.json", "JavaScript/TypeScript (npm)"),
+    ("pyproject.toml", "Python"),
+    ("setup.py", "Python"),
+    ("requirements.txt", "Python"),
+    ("pom.xml", "Java (Maven)"),
+    ("build.gradle", "JVM (Gradle)"),
+    ("build.gradle.kts", "JVM (Gradle)"),
+    ("Gemfile", "Ruby (Bundler)"),
+    ("composer.json", "PHP (Composer)"),
+    ("CMakeLists.txt", "C/C++ (CMake)"),
+];
+
+/// Directories of vendored, built, or generated files, proposed as ignore
+/// rules when the scan finds files in them.
+const IGNORABLE_DIRS: &[&str] = &[
+    "vendor",
+    "third_party",
+    "dist",
+    "build",
+    "target",
+    "out",
+    "coverage",
+    ".next",
+    "bower_components",
+];
+
+/// Suffixes of minified and source-map files, proposed as ignore rules
+/// when the scan finds any.
+const IGNORABLE_SUFFIXES: &[&str] = &[".min.js", ".min.css", ".map"];
+
+/// Orientation files proposed for pinning, at most.
+const MAX_PINS: usize = 3;
+
+/// Repositories with more files than this are proposed the fast preset.
+const FAST_PRESET_FILES: usize = 50_000;
+
+/// What `topo setup` proposes for a repository.
+#[derive(Debug)]
+struct Plan {
+    /// Kinds of project, from marker files at the root.
+    projects: Vec<&'static str>,
+    /// Languages by share of bytes, largest first.
+    languages: Vec<String>,
+    files: usize,
+    /// Globs for `[scan] ignore`.
+    ignore: Vec<String>,
+    /// Files to pin, so they lead every query.
+    pins: Vec<String>,
+    preset: Preset,
+}
+
+impl Plan {
+    fn propose(root: &Path, files: &[FileInfo]) -> Self {
+        let projects = PROJECT_MARKERS
+            .iter()
+            .filter(|(marker, _)| root.join(marker).is_file())
+            .map(|(_, project)| *project)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let breakdown = language_breakdown(files, &GitAttributes::load(root, files));
+        let languages = breakdown
+            .languages
+            .iter()
+            .take(3)
+            .map(|share| format!("{} {:.0}%", share.name, share.percentage))
+            .collect();
+        let ignore = ignore_rules(files);
+        // Vendored `lib.rs`s and such aren't worth pinning
+        let ignored = IgnoreRules::new(&ignore).unwrap_or_default();
+        let pins = orientation_files(files.iter().map(|f| f.path.as_str()))
+            .into_iter()
+            .filter(|path| !ignored.is_ignored(Path::new(path)))
+            .take(MAX_PINS)
+            .map(str::to_string)
+            .collect();
+        let preset = if files.len() > FAST_PRESET_FILES {
+            Preset::Fast
+        } else {
+            Preset::Balanced
+        };
+        Self {
+            projects,
+            languages,
+            files: files.len(),
+            ignore,
+            pins,
+            preset,
+        }
+    }
+}
+
+/// Ignore globs for the [`IGNORABLE_DIRS`] and [`IGNORABLE_SUFFIXES`]
+/// that `files` has any of.
+fn ignore_rules(files: &[FileInfo]) -> Vec<String> {
+    let mut dirs = BTreeSet::new();
+    let mut suffixes: BTreeSet<&str> = BTreeSet::new();
+    for file in files {
+        let mut components: Vec<&str> = file.path.split('/').collect();
+        let name = components.pop().unwrap_or_default();
+        dirs.extend(
+            components
+                .into_iter()
+                .filter(|dir| IGNORABLE_DIRS.contains(dir)),
+        );
+        suffixes.extend(
+            IGNORABLE_SUFFIXES
+                .iter()
+                .filter(|suffix| name.ends_with(*suffix)),
+        );
+    }
+    let dirs = dirs.into_iter().map(|dir| format!("**/{dir}/**"));
+    let suffixes = suffixes.into_iter().map(|suffix| format!("*{suffix}"));
+    dirs.chain(suffixes).collect()
+}
+
+/// Detect the kind of project, propose ignore rules, pins, and a preset,
+/// write them to `.topo.toml` and `.topo/pins`, and build the first deep
+/// index.
+///
+/// Each proposal is asked about when stdin is a terminal, unless `yes`.
+/// Sections `.topo.toml` already has are left as they are.
+pub fn run(cli: &Cli, yes: bool, no_index: bool, dry_run: bool) -> Result<()> {
+    let root = cli.repo_root()?;
+    let config = Config::load(&root)?;
+    let bundle = BundleBuilder::new(&root).config(&config).build()?;
+    let mut plan = Plan::propose(&root, &bundle.files);
+    // Keep what an earlier setup or the user already chose
+    if !config.scan.ignore.is_empty() {
+        plan.ignore.clear();
+    }
+    let configured_preset = config.query.preset.is_some();
+    let mut build = !no_index;
+
+    let interactive = !yes && !dry_run && std::io::stdin().is_terminal();
+    if !cli.is_quiet() || interactive {
+        report(&plan, &config);
+    }
+    if interactive {
+        let mut input = std::io::stdin().lock();
+        if !plan.ignore.is_empty() && !confirm(&mut input, "Ignore these files?")? {
+            plan.ignore.clear();
+        }
+        if !plan.pins.is_empty() && !confirm(&mut input, "Pin these files?")? {
+            plan.pins.clear();
+        }
+        if !configured_preset {
+            plan.preset = choose_preset(&mut input, plan.preset)?;
+        }
+        if build {
+            build = confirm(&mut input, "Build the deep index now?")?;
+        }
+    }
+
+    let path = root.join(CONFIG_FILE);
+    let existing = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let preset = (!configured_preset).then_some(plan.preset);
+    let updated = render_config(&existing, &plan.ignore, preset)?;
+    let mut pins = Pins::load(&root)?;
+    let pinned: Vec<&String> = plan.pins.iter().filter(|p| !pins.contains(p)).collect();
+
+    if dry_run {
+        if !cli.is_quiet() {
+            match &updated {
+                Some(text) => eprintln!("Would write {CONFIG_FILE}:\n{text}"),
+                None => eprintln!("{CONFIG_FILE} needs no changes"),
+            }
+            for rel in &pinned {
+                eprintln!("Would pin {rel}");
+            }
+        }
+    } else {
+        if let Some(text) = &updated {
+            fs::write(&path, text)?;
+            if !cli.is_quiet() {
+                eprintln!("Wrote {CONFIG_FILE}");
+            }
+        }
+        if !pinned.is_empty() {
+            for rel in &pinned {
+                pins.add(rel);
+            }
+            pins.save(&root)?;
+            if !cli.is_quiet() {
+                for rel in &pinned {
+                    eprintln!("Pinned {rel}");
+                }
+            }
+        }
+        if build {
+            super::index::run(
+                cli,
+                &IndexOptions {
+                    deep: true,
+                    ..Default::default()
+                },
+            )?;
+        }
+    }
+
+    if matches!(
+        cli.effective_format(),
+        OutputFormat::Json | OutputFormat::Jsonl
+    ) {
+        let json = serde_json::json!({
+            "projects": plan.projects,
+            "languages": plan.languages,
+            "files": plan.files,
+            "ignore": plan.ignore,
+            "pins": plan.pins,
+            "preset": config.query.preset.as_deref().unwrap_or(plan.preset.as_str()),
+            "token_estimator": TOKEN_ESTIMATOR,
+            "config_written": updated.is_some() && !dry_run,
+            "indexed": build && !dry_run,
+        });
+        println!("{}", serde_json::to_string_pretty(&json)?);
+    } else if build && !dry_run && !cli.is_quiet() {
+        eprintln!("Ready: try `topo query \"<task>\"`");
+    }
+    Ok(())
+}
+
+/// Print what was detected and what is proposed.
+fn report(plan: &Plan, config: &Config) {
+    let projects = if plan.projects.is_empty() {
+        "no build files recognised".to_string()
+    } else {
+        plan.projects.join(", ")
+    };
+    eprintln!("Project: {projects}");
+    eprintln!(
+        "Files: {} ({})",
+        plan.files,
+        if plan.languages.is_empty() {
+            "no source languages".to_string()
+        } else {
+            plan.languages.join(", ")
+        }
+    );
+    if !config.scan.ignore.is_empty() {
+        eprintln!(
+            "Ignore rules: keeping {} from {CONFIG_FILE}",
+            config.scan.ignore.join(", ")
+        );
+    } else if plan.ignore.is_empty() {
+        eprintln!("Ignore rules: none needed beyond .gitignore");
+    } else {
+        eprintln!("Ignore rules, on top of .gitignore:");
+        for glob in &plan.ignore {
+            eprintln!("  {glob}");
+        }
+    }
+    if !plan.pins.is_empty() {
+        eprintln!("Pins, ranked first in every query:");
+        for rel in &plan.pins {
+            eprintln!("  {rel}");
+        }
+    }
+    match &config.query.preset {
+        Some(preset) => eprintln!("Preset: keeping {preset} from {CONFIG_FILE}"),
+        None => eprintln!("Preset: {}", plan.preset),
+    }
+    eprintln!("Token estimate: {TOKEN_ESTIMATOR} (the only estimator)");
+}
+
+/// Ask a yes/no `question` on stderr, defaulting to yes.
+fn confirm(input: &mut impl BufRead, question: &str) -> Result<bool> {
+    let answer = ask(input, &format!("{question} [Y/n] "))?;
+    Ok(!matches!(answer.to_lowercase().as_str(), "n" | "no"))
+}
+
+/// Ask for a preset on stderr, defaulting to `proposed`.
+fn choose_preset(input: &mut impl BufRead, proposed: Preset) -> Result<Preset> {
+    let names: Vec<&str> = Preset::value_variants()
+        .iter()
+        .map(Preset::as_str)
+        .collect();
+    loop {
+        let answer = ask(
+            input,
+            &format!("Preset ({}) [{proposed}] ", names.join("/")),
+        )?;
+        if answer.is_empty() {
+            return Ok(proposed);
+        }
+        match Preset::from_str(&answer, true) {
+            Ok(preset) => return Ok(preset),
+            Err(_) => eprintln!("Unknown preset {answer:?}"),
+        }
+    }
+}
+
+/// Print `prompt` and read a trimmed line; an error at end of input.
+fn ask(input: &mut impl BufRead, prompt: &str) -> Result<String> {
+    eprint!("{prompt}");
+    std::io::stderr().flush()?;
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        bail!("setup cancelled: no answer given");
+    }
+    Ok(line.trim().to_string())
+}
+
+/// `existing` `.topo.toml` text with `[scan] ignore` and `[query] preset`
+/// sections added; None if there is nothing to add.
+fn render_config(
+    existing: &str,
+    ignore: &[String],
+    preset: Option<Preset>,
+) -> Result<Option<String>> {
+    let mut text = existing.to_string();
+    let mut section = |body: String| {
+        if !text.is_empty() && !text.ends_with("\n\n") {
+            text.push_str(if text.ends_with('\n') { "\n" } else { "\n\n" });
+        }
+        text.push_str(&body);
+    };
+    if !ignore.is_empty() {
+        let globs: Vec<String> = ignore
+            .iter()
+            .map(|glob| format!("    {glob:?},\n"))
+            .collect();
+        section(format!(
+            "# Left out of scans and indexes, on top of .gitignore\n[scan]\nignore = [\n{}]\n",
+            globs.concat()
+        ));
+    }
+    if let Some(preset) = preset {
+        section(format!(
+            "# Preset for `topo query` and `topo quick` without --preset\n[query]\npreset = \"{preset}\"\n"
+        ));
+    }
+    if text == existing {
+        return Ok(None);
+    }
+    if let Err(e) = Config::parse(&text) {
+        bail!("can't add setup's sections to {CONFIG_FILE}: {e}");
+    }
+    Ok(Some(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn write(root: &Path, rel: &str) {
+        let path = root.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "fn main() {}\n").unwrap();
+    }
+
+    #[test]
+    fn proposes_from_the_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for rel in [
+            "Cargo.toml",
+            "README.md",
+            "src/main.rs",
+            "src/auth.rs",
+            "vendor/dep/lib.rs",
+            "web/dist/app.min.js",
+        ] {
+            write(root, rel);
+        }
+        let bundle = BundleBuilder::new(root).build().unwrap();
+        let plan = Plan::propose(root, &bundle.files);
+
+        assert_eq!(plan.projects, ["Rust (Cargo)"]);
+        assert!(plan.languages[0].starts_with("Rust"));
+        assert_eq!(plan.ignore, ["**/dist/**", "**/vendor/**", "*.min.js"]);
+        assert_eq!(plan.pins, ["README.md", "src/main.rs"]);
+        assert!(matches!(plan.preset, Preset::Balanced));
+    }
+
+    #[test]
+    fn ignore_rules_cover_only_what_the_repository_has() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "src/lib.rs");
+        write(dir.path(), "docs/build.md");
+        let bundle = BundleBuilder::new(dir.path()).build().unwrap();
+        assert!(ignore_rules(&bundle.files).is_empty());
+    }
+
+    #[test]
+    fn config_sections_are_added_once() {
+        let ignore = ["**/vendor/**".to_string()];
+        let text = render_config("", &ignore, Some(Preset::Fast))
+            .unwrap()
+            .unwrap();
+        let config = Config::parse(&text).unwrap();
+        assert_eq!(config.scan.ignore, ignore);
+        assert_eq!(config.query.preset.as_deref(), Some("fast"));
+
+        let existing = "[index]\ncompression_level = 9\n";
+        let text = render_config(existing, &[], Some(Preset::Deep))
+            .unwrap()
+            .unwrap();
+        assert!(text.starts_with(existing));
+        assert_eq!(Config::parse(&text).unwrap().index.compression_level, 9);
+        assert_eq!(render_config(existing, &[], None).unwrap(), None);
+        // A second [query] table would be invalid TOML
+        assert!(render_config("[query]\n", &[], Some(Preset::Fast)).is_err());
+    }
+
+    #[test]
+    fn prompts_default_to_the_proposal() {
+        let mut input = Cursor::new("\nno\n\nthorough\n");
+        assert!(confirm(&mut input, "?").unwrap());
+        assert!(!confirm(&mut input, "?").unwrap());
+        assert!(matches!(
+            choose_preset(&mut input, Preset::Fast).unwrap(),
+            Preset::Fast
+        ));
+        assert!(matches!(
+            choose_preset(&mut input, Preset::Fast).unwrap(),
+            Preset::Thorough
+        ));
+        assert!(confirm(&mut input, "?").is_err());
+    }
+}
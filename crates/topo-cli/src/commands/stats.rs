@@ -0,0 +1,87 @@
+use crate::Cli;
+use anyhow::Result;
+use topo_core::Config;
+use topo_scanner::{BundleBuilder, GitAttributes, LanguageBreakdown, language_breakdown};
+
+pub fn run(cli: &Cli) -> Result<()> {
+    let root = cli.repo_root()?;
+    let config = Config::load(&root)?;
+    // GitHub doesn't count submodules toward a repository's languages
+    let bundle = BundleBuilder::new(&root)
+        .config(&config)
+        .skip_nested_repos(true)
+        .build()?;
+    let attributes = GitAttributes::load(&root, &bundle.files);
+    let breakdown = language_breakdown(&bundle.files, &attributes);
+
+    match cli.effective_format() {
+        crate::OutputFormat::Json | crate::OutputFormat::Jsonl => {
+            println!("{}", serde_json::to_string_pretty(&to_json(&breakdown))?);
+        }
+        _ => {
+            println!(
+                "{:<16} {:>12} {:>8} {:>6}",
+                "LANGUAGE", "BYTES", "PERCENT", "FILES"
+            );
+            for language in &breakdown.languages {
+                println!(
+                    "{:<16} {:>12} {:>7.2}% {:>6}",
+                    language.name, language.bytes, language.percentage, language.files
+                );
+            }
+            println!();
+            println!("Total: {} bytes", breakdown.total_bytes);
+            println!(
+                "Excluded: {} vendored, generated, or documentation files",
+                breakdown.excluded
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn to_json(breakdown: &LanguageBreakdown) -> serde_json::Value {
+    let languages: Vec<serde_json::Value> = breakdown
+        .languages
+        .iter()
+        .map(|language| {
+            serde_json::json!({
+                "language": language.name,
+                "bytes": language.bytes,
+                "percentage": (language.percentage * 100.0).round() / 100.0,
+                "files": language.files,
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "languages": languages,
+        "total_bytes": breakdown.total_bytes,
+        "excluded_files": breakdown.excluded,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use topo_scanner::LanguageShare;
+
+    #[test]
+    fn json_rounds_percentages() {
+        let breakdown = LanguageBreakdown {
+            languages: vec![LanguageShare {
+                name: "Rust".to_string(),
+                bytes: 200,
+                percentage: 66.666_666,
+                files: 2,
+            }],
+            total_bytes: 300,
+            excluded: 1,
+        };
+        let json = to_json(&breakdown);
+        assert_eq!(json["languages"][0]["language"], "Rust");
+        assert_eq!(json["languages"][0]["percentage"], 66.67);
+        assert_eq!(json["total_bytes"], 300);
+        assert_eq!(json["excluded_files"], 1);
+    }
+}
@@ -0,0 +1,91 @@
+use serde::Serialize;
+use std::io::Write;
+use topo_core::{ErrorCode, TopoError};
+
+/// A failed command as reported on stderr, with the code and hint of the
+/// first [`TopoError`] in the error's chain.
+///
+/// In JSON it is one line shaped like the `--progress ndjson` events:
+/// `{"event":"error","code":"E_INDEX_MISSING","message":…,"hint":…,"path":…}`.
+#[derive(Debug, Serialize)]
+pub struct ErrorReport {
+    event: &'static str,
+    pub code: &'static str,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+impl ErrorReport {
+    pub fn new(err: &anyhow::Error) -> Self {
+        let topo = err.chain().find_map(|e| e.downcast_ref::<TopoError>());
+        let code = topo.map_or(ErrorCode::Other, TopoError::code);
+        Self {
+            event: "error",
+            code: code.as_str(),
+            message: format!("{err:#}"),
+            hint: code.hint(),
+            path: topo
+                .and_then(TopoError::path)
+                .map(|p| p.display().to_string()),
+        }
+    }
+
+    /// Write the report to stderr, as a JSON line or for people.
+    pub fn print(&self, json: bool) {
+        let mut stderr = std::io::stderr().lock();
+        let _ = if json {
+            serde_json::to_writer(&mut stderr, self)
+                .map_err(std::io::Error::from)
+                .and_then(|()| writeln!(stderr))
+        } else {
+            self.write_human(&mut stderr)
+        };
+    }
+
+    fn write_human(&self, writer: &mut dyn Write) -> std::io::Result<()> {
+        writeln!(writer, "Error [{}]: {}", self.code, self.message)?;
+        if let Some(hint) = self.hint {
+            writeln!(writer, "Hint: {hint}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_finds_topo_error_in_chain() {
+        let err = anyhow::Error::new(TopoError::index_missing("no index").with_path("/repo"))
+            .context("query failed");
+        let report = ErrorReport::new(&err);
+        assert_eq!(report.code, "E_INDEX_MISSING");
+        assert_eq!(
+            report.message,
+            "query failed: index missing at /repo: no index"
+        );
+        assert_eq!(report.path.as_deref(), Some("/repo"));
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["event"], "error");
+        assert_eq!(json["hint"], report.hint.unwrap());
+
+        let mut human = Vec::new();
+        report.write_human(&mut human).unwrap();
+        let human = String::from_utf8(human).unwrap();
+        assert!(human.starts_with("Error [E_INDEX_MISSING]: query failed"));
+        assert!(human.contains("\nHint: Build the deep index"));
+    }
+
+    #[test]
+    fn other_errors_have_no_hint() {
+        let report = ErrorReport::new(&anyhow::anyhow!("SQLite exports need a file"));
+        assert_eq!(report.code, "E_OTHER");
+        assert!(report.hint.is_none());
+        assert!(!serde_json::to_string(&report).unwrap().contains("hint"));
+    }
+}
@@ -1,10 +1,14 @@
 mod commands;
+mod error;
 mod preset;
+mod progress;
+mod resources;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand, ValueEnum};
 use std::io::IsTerminal;
 use std::path::PathBuf;
+use std::process::ExitCode;
 
 /// Topo — fast codebase indexer and file selector for LLMs.
 #[derive(Parser, Debug)]
@@ -26,6 +30,10 @@ pub struct Cli {
     #[arg(long, global = true)]
     no_color: bool,
 
+    /// Progress reporting on stderr: human, ndjson, none
+    #[arg(long, value_enum, default_value = "human", global = true)]
+    progress: progress::ProgressMode,
+
     /// Repository root (default: current directory)
     #[arg(long, global = true)]
     root: Option<PathBuf>,
@@ -34,6 +42,55 @@ pub struct Cli {
     command: Option<Command>,
 }
 
+#[derive(Debug, Subcommand)]
+pub enum IndexAction {
+    /// Export the deep index for other tools
+    Export {
+        /// Export format: json, sqlite
+        #[arg(long, value_enum, default_value = "json")]
+        to: commands::index::ExportFormat,
+
+        /// File to write (default: stdout, JSON only)
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+
+    /// Update the deep index, then upload it to a shared remote
+    Push {
+        /// Remote: s3://bucket/prefix, https://host/path, or a directory
+        /// (default: `url` under [remote] in .topo.toml)
+        #[arg(long, value_name = "URL")]
+        remote: Option<String>,
+
+        /// Name to store the index under (default: `key` under [remote],
+        /// or "latest")
+        #[arg(long)]
+        key: Option<String>,
+    },
+
+    /// Download a pushed index, then update it with local changes
+    Pull {
+        /// Remote: s3://bucket/prefix, https://host/path, or a directory
+        /// (default: `url` under [remote] in .topo.toml)
+        #[arg(long, value_name = "URL")]
+        remote: Option<String>,
+
+        /// Name the index was pushed under (default: `key` under [remote],
+        /// or "latest")
+        #[arg(long)]
+        key: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DaemonAction {
+    /// Show whether a daemon is serving the repository
+    Status,
+
+    /// Stop the daemon serving the repository
+    Stop,
+}
+
 #[derive(Debug, Clone, ValueEnum)]
 pub enum OutputFormat {
     Auto,
@@ -41,12 +98,18 @@ pub enum OutputFormat {
     Jsonl,
     Human,
     Compact,
+    Html,
+    Sarif,
 }
 
 #[derive(Debug, Subcommand)]
 pub enum Command {
     /// Build or update the file index
+    #[command(args_conflicts_with_subcommands = true)]
     Index {
+        #[command(subcommand)]
+        action: Option<IndexAction>,
+
         /// Enable deep indexing with AST chunking
         #[arg(long)]
         deep: bool,
@@ -54,60 +117,61 @@ pub enum Command {
         /// Rebuild index from scratch (ignore cache)
         #[arg(long)]
         force: bool,
-    },
 
-    /// Score and select files for a query
-    Query {
-        /// The task or query to search for
-        task: String,
-
-        /// Preset: fast, balanced, deep, thorough
-        #[arg(long, value_enum, default_value = "balanced")]
-        preset: preset::Preset,
-
-        /// Maximum bytes for token budget
+        /// Also build a trigram index for fuzzy identifier search (with --deep)
         #[arg(long)]
-        max_bytes: Option<u64>,
+        trigrams: bool,
 
-        /// Maximum tokens for token budget
+        /// Also record term frequencies per chunk for `query --chunks` (with
+        /// --deep; makes the index larger)
         #[arg(long)]
-        max_tokens: Option<u64>,
+        chunks: bool,
 
-        /// Minimum score threshold
+        /// Count license and copyright headers at the top of files in term
+        /// statistics instead of leaving them out (with --deep)
         #[arg(long)]
-        min_score: Option<f64>,
+        keep_license_headers: bool,
 
-        /// Return top N files
+        /// Take symbols from the language servers configured under [lsp]
+        /// instead of the built-in chunker (with --deep)
         #[arg(long)]
-        top: Option<usize>,
-    },
+        lsp: bool,
 
-    /// One-shot: index + query in a single command
-    Quick {
-        /// The task or query to search for
-        task: String,
+        /// Write the index cache to a portable .tar.zst archive after indexing
+        #[arg(long, value_name = "FILE")]
+        export_cache: Option<PathBuf>,
 
-        /// Preset: fast, balanced, deep, thorough
-        #[arg(long, value_enum, default_value = "balanced")]
-        preset: preset::Preset,
+        /// Seed the index cache from an archive made with --export-cache
+        #[arg(long, value_name = "FILE", conflicts_with = "force")]
+        import_cache: Option<PathBuf>,
 
-        /// Maximum bytes for token budget
-        #[arg(long)]
-        max_bytes: Option<u64>,
+        /// Drop deep index entries for deleted files without reindexing
+        #[arg(long, conflicts_with_all = ["deep", "force", "trigrams", "chunks", "import_cache"])]
+        prune: bool,
 
-        /// Maximum tokens for token budget
-        #[arg(long)]
-        max_tokens: Option<u64>,
+        /// Check deep indexes for corruption and entries out of date with disk
+        #[arg(long, conflicts_with_all = ["deep", "force", "import_cache", "prune"])]
+        verify: bool,
 
-        /// Minimum score threshold
-        #[arg(long)]
-        min_score: Option<f64>,
+        /// Rebuild the bad entries found by --verify
+        #[arg(long, requires = "verify")]
+        repair: bool,
 
-        /// Return top N files
-        #[arg(long)]
-        top: Option<usize>,
+        /// Show what would be reindexed, written, and deleted without writing
+        #[arg(long, conflicts_with_all = ["export_cache", "import_cache"])]
+        dry_run: bool,
     },
 
+    /// Score and select files for a query
+    Query(commands::query::QueryArgs),
+
+    /// One-shot: index + query in a single command
+    Quick(commands::query::QueryArgs),
+
+    /// Explain query syntax, fields, options, and presets, with example
+    /// queries run against this repository
+    QueryHelp,
+
     /// Convert JSONL selection to formatted output
     Render {
         /// Path to JSONL file
@@ -135,18 +199,56 @@ pub enum Command {
     /// Inspect the index (file count, size, stats)
     Inspect,
 
+    /// Show bytes per language, counted like github-linguist
+    Stats,
+
     /// Print machine-readable tool capabilities
     Describe,
 
+    /// Report which optional subsystems (deep index, embeddings, git,
+    /// daemon, language servers) are available and in use
+    Capabilities,
+
     /// Start MCP (Model Context Protocol) server on stdio
     Mcp,
 
+    /// Keep the deep index loaded and up to date in the background, and
+    /// answer `query` and `quick` from it
+    Daemon {
+        #[command(subcommand)]
+        action: Option<DaemonAction>,
+
+        /// Seconds between checks for changed files
+        #[arg(long, default_value_t = 2)]
+        interval: u64,
+    },
+
+    /// Detect the project, propose ignore rules, pins, and a preset, write
+    /// them to .topo.toml, and build the first deep index
+    Setup {
+        /// Accept every proposal without asking
+        #[arg(long, short = 'y')]
+        yes: bool,
+
+        /// Don't build the deep index
+        #[arg(long)]
+        no_index: bool,
+
+        /// Show what would be written without writing or indexing
+        #[arg(long)]
+        dry_run: bool,
+    },
+
     /// Set up AI assistant instruction files (AGENTS.md, Cursor rules, Copilot instructions)
     Init {
         /// Overwrite existing files
         #[arg(long)]
         force: bool,
 
+        /// Show what would be created or overwritten without writing
+        #[arg(long)]
+        dry_run: bool,
+
         /// Install Claude Code hooks for automatic context injection (default: true)
         #[arg(long, default_value = "true", action = clap::ArgAction::Set)]
         hooks: bool,
@@ -154,6 +256,51 @@ pub enum Command {
 
     /// Show context savings from topo hook usage
     Gain,
+
+    /// Delete the saved deep indexes, keeping usage stats
+    Clean {
+        /// List the files that would be deleted without deleting them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Always rank and list these files first; with no paths, list the
+    /// pinned files
+    Pin {
+        /// Files to pin
+        paths: Vec<PathBuf>,
+    },
+
+    /// Stop pinning files
+    Unpin {
+        /// Files to unpin
+        #[arg(required_unless_present = "all")]
+        paths: Vec<PathBuf>,
+
+        /// Unpin every file
+        #[arg(long, conflicts_with = "paths")]
+        all: bool,
+    },
+
+    /// Record files as relevant or not to a query, and fit the scoring
+    /// weights to every judgment recorded so far
+    Feedback {
+        /// The query the files were judged for
+        task: String,
+
+        /// A file that should rank high for the query (repeatable)
+        #[arg(long, value_name = "PATH", required_unless_present = "bad")]
+        good: Vec<PathBuf>,
+
+        /// A file that should rank low for the query (repeatable)
+        #[arg(long, value_name = "PATH")]
+        bad: Vec<PathBuf>,
+
+        /// Preset to score judged queries with (default: `query.preset` in
+        /// .topo.toml, else balanced)
+        #[arg(long, value_enum)]
+        preset: Option<preset::Preset>,
+    },
 }
 
 impl Cli {
@@ -192,37 +339,127 @@ impl Cli {
         }
     }
 
+    /// Whether `-v` was given.
+    pub fn is_verbose(&self) -> bool {
+        self.verbose > 0
+    }
+
+    /// Whether human status messages should be suppressed.
+    ///
+    /// Machine progress modes imply quiet so stderr stays parseable.
     pub fn is_quiet(&self) -> bool {
-        self.quiet
+        self.quiet || self.progress != progress::ProgressMode::Human
+    }
+
+    /// Create a progress reporter for the selected mode.
+    pub fn progress(&self) -> progress::Progress {
+        progress::Progress::new(self.progress)
+    }
+
+    /// Whether errors should be reported as JSON: with an explicit JSON
+    /// output format or machine progress.
+    pub fn json_errors(&self) -> bool {
+        matches!(self.format, OutputFormat::Json | OutputFormat::Jsonl)
+            || self.progress == progress::ProgressMode::Ndjson
     }
 }
 
-fn main() -> Result<()> {
+fn main() -> ExitCode {
     let cli = Cli::parse();
+    let json_errors = cli.json_errors();
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            error::ErrorReport::new(&err).print(json_errors);
+            ExitCode::FAILURE
+        }
+    }
+}
 
+fn run(mut cli: Cli) -> Result<()> {
+    commands::query::default_preset(&mut cli)?;
     match cli.command {
-        Some(Command::Index { deep, force }) => {
-            commands::index::run(&cli, deep, force)?;
+        Some(Command::Index {
+            action: Some(IndexAction::Export { to, ref output }),
+            ..
+        }) => {
+            commands::index::export(&cli, to, output.as_deref())?;
         }
-        Some(Command::Query {
-            ref task,
-            preset,
-            max_bytes,
-            max_tokens,
-            min_score,
-            top,
+        Some(Command::Index {
+            action:
+                Some(IndexAction::Push {
+                    ref remote,
+                    ref key,
+                }),
+            ..
         }) => {
-            commands::query::run(&cli, task, preset, max_bytes, max_tokens, min_score, top)?;
+            let options = commands::index::IndexOptions {
+                deep: true,
+                push: true,
+                remote: remote.as_deref(),
+                remote_key: key.as_deref(),
+                ..Default::default()
+            };
+            commands::index::run(&cli, &options)?;
         }
-        Some(Command::Quick {
-            ref task,
-            preset,
-            max_bytes,
-            max_tokens,
-            min_score,
-            top,
+        Some(Command::Index {
+            action:
+                Some(IndexAction::Pull {
+                    ref remote,
+                    ref key,
+                }),
+            ..
+        }) => {
+            let options = commands::index::IndexOptions {
+                deep: true,
+                pull: true,
+                remote: remote.as_deref(),
+                remote_key: key.as_deref(),
+                ..Default::default()
+            };
+            commands::index::run(&cli, &options)?;
+        }
+        Some(Command::Index {
+            action: None,
+            deep,
+            force,
+            trigrams,
+            chunks,
+            keep_license_headers,
+            lsp,
+            ref export_cache,
+            ref import_cache,
+            prune,
+            verify,
+            repair,
+            dry_run,
         }) => {
-            commands::quick::run(&cli, task, preset, max_bytes, max_tokens, min_score, top)?;
+            let options = commands::index::IndexOptions {
+                deep,
+                force,
+                trigrams,
+                chunks,
+                keep_license_headers,
+                lsp,
+                export_cache: export_cache.as_deref(),
+                import_cache: import_cache.as_deref(),
+                prune,
+                verify,
+                repair,
+                dry_run,
+                ..Default::default()
+            };
+            commands::index::run(&cli, &options)?;
+        }
+        Some(Command::Query(ref args)) => {
+            if !commands::daemon::delegate(&cli) {
+                commands::query::run(&cli, &args.with_documents()?)?;
+            }
+        }
+        Some(Command::Quick(ref args)) => {
+            if !commands::daemon::delegate(&cli) {
+                commands::quick::run(&cli, &args.with_documents()?)?;
+            }
         }
         Some(Command::Render {
             ref file,
@@ -237,21 +474,64 @@ fn main() -> Result<()> {
         }) => {
             commands::explain::run(&cli, task, top, preset)?;
         }
+        Some(Command::QueryHelp) => {
+            commands::query_help::run(&cli)?;
+        }
         Some(Command::Inspect) => {
             commands::inspect::run(&cli)?;
         }
+        Some(Command::Stats) => {
+            commands::stats::run(&cli)?;
+        }
         Some(Command::Describe) => {
             commands::describe::run(&cli)?;
         }
+        Some(Command::Capabilities) => {
+            commands::capabilities::run(&cli)?;
+        }
         Some(Command::Mcp) => {
             commands::mcp::run(&cli)?;
         }
-        Some(Command::Init { force, hooks }) => {
-            commands::init::run(&cli, force, hooks)?;
+        Some(Command::Daemon {
+            ref action,
+            interval,
+        }) => {
+            commands::daemon::run(&cli, action.as_ref(), interval)?;
+        }
+        Some(Command::Setup {
+            yes,
+            no_index,
+            dry_run,
+        }) => {
+            commands::setup::run(&cli, yes, no_index, dry_run)?;
+        }
+        Some(Command::Init {
+            force,
+            dry_run,
+            hooks,
+        }) => {
+            commands::init::run(&cli, force, hooks, dry_run)?;
         }
         Some(Command::Gain) => {
             commands::gain::run(&cli)?;
         }
+        Some(Command::Clean { dry_run }) => {
+            commands::clean::run(&cli, dry_run)?;
+        }
+        Some(Command::Pin { ref paths }) => {
+            commands::pin::pin(&cli, paths)?;
+        }
+        Some(Command::Unpin { ref paths, all }) => {
+            commands::pin::unpin(&cli, paths, all)?;
+        }
+        Some(Command::Feedback {
+            ref task,
+            ref good,
+            ref bad,
+            preset,
+        }) => {
+            commands::feedback::run(&cli, task, good, bad, preset)?;
+        }
         None => {
             // No subcommand: print version info
             if !cli.is_quiet() {
@@ -292,8 +572,19 @@ mod tests {
         assert!(matches!(
             cli.command,
             Some(Command::Index {
+                action: None,
                 deep: false,
-                force: false
+                force: false,
+                trigrams: false,
+                chunks: false,
+                keep_license_headers: false,
+                lsp: false,
+                export_cache: None,
+                import_cache: None,
+                prune: false,
+                verify: false,
+                repair: false,
+                dry_run: false,
             })
         ));
     }
@@ -301,35 +592,310 @@ mod tests {
     #[test]
     fn cli_parses_index_deep() {
         let cli = Cli::try_parse_from(["topo", "index", "--deep"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Index {
+                action: None,
+                deep: true,
+                force: false,
+                trigrams: false,
+                chunks: false,
+                keep_license_headers: false,
+                lsp: false,
+                export_cache: None,
+                import_cache: None,
+                prune: false,
+                verify: false,
+                repair: false,
+                dry_run: false,
+            })
+        ));
+    }
+
+    #[test]
+    fn cli_parses_index_cache_flags() {
+        let cli = Cli::try_parse_from([
+            "topo",
+            "index",
+            "--deep",
+            "--import-cache",
+            "ci.tar.zst",
+            "--export-cache",
+            "out.tar.zst",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::Index {
+                ref export_cache,
+                ref import_cache,
+                ..
+            }) => {
+                assert_eq!(
+                    export_cache.as_deref(),
+                    Some(std::path::Path::new("out.tar.zst"))
+                );
+                assert_eq!(
+                    import_cache.as_deref(),
+                    Some(std::path::Path::new("ci.tar.zst"))
+                );
+            }
+            _ => panic!("expected Index command"),
+        }
+        assert!(
+            Cli::try_parse_from(["topo", "index", "--force", "--import-cache", "c.tar.zst"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn cli_parses_index_prune() {
+        let cli = Cli::try_parse_from(["topo", "index", "--prune"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Index {
+                deep: false,
+                prune: true,
+                ..
+            })
+        ));
+        assert!(Cli::try_parse_from(["topo", "index", "--deep", "--prune"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_index_verify() {
+        let cli = Cli::try_parse_from(["topo", "index", "--verify", "--repair"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Index {
+                verify: true,
+                repair: true,
+                ..
+            })
+        ));
+        assert!(Cli::try_parse_from(["topo", "index", "--repair"]).is_err());
+        assert!(Cli::try_parse_from(["topo", "index", "--verify", "--prune"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_index_dry_run() {
+        let cli = Cli::try_parse_from(["topo", "index", "--deep", "--dry-run"]).unwrap();
         assert!(matches!(
             cli.command,
             Some(Command::Index {
                 deep: true,
-                force: false
+                dry_run: true,
+                ..
+            })
+        ));
+        assert!(
+            Cli::try_parse_from(["topo", "index", "--dry-run", "--export-cache", "c.tar.zst"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn cli_parses_index_export() {
+        let cli = Cli::try_parse_from([
+            "topo", "index", "export", "--to", "sqlite", "-o", "index.db",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::Index {
+                action: Some(IndexAction::Export { to, ref output }),
+                ..
+            }) => {
+                assert_eq!(to, commands::index::ExportFormat::Sqlite);
+                assert_eq!(output.as_deref(), Some(std::path::Path::new("index.db")));
+            }
+            _ => panic!("expected index export"),
+        }
+        assert!(Cli::try_parse_from(["topo", "index", "--deep", "export"]).is_err());
+        assert!(Cli::try_parse_from(["topo", "index", "export", "--to", "csv"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_daemon() {
+        let cli = Cli::try_parse_from(["topo", "daemon", "--interval", "5"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Daemon {
+                action: None,
+                interval: 5
+            })
+        ));
+        let cli = Cli::try_parse_from(["topo", "daemon", "stop"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Daemon {
+                action: Some(DaemonAction::Stop),
+                interval: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn cli_parses_index_push_and_pull() {
+        let cli = Cli::try_parse_from([
+            "topo",
+            "index",
+            "push",
+            "--remote",
+            "s3://ci-cache/topo",
+            "--key",
+            "main",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::Index {
+                action: Some(IndexAction::Push { remote, key }),
+                ..
+            }) => {
+                assert_eq!(remote.as_deref(), Some("s3://ci-cache/topo"));
+                assert_eq!(key.as_deref(), Some("main"));
+            }
+            _ => panic!("expected index push"),
+        }
+        let cli = Cli::try_parse_from(["topo", "index", "pull"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Index {
+                action: Some(IndexAction::Pull {
+                    remote: None,
+                    key: None
+                }),
+                ..
             })
         ));
+        assert!(Cli::try_parse_from(["topo", "index", "--force", "pull"]).is_err());
     }
 
     #[test]
     fn cli_parses_query() {
         let cli = Cli::try_parse_from(["topo", "query", "auth middleware"]).unwrap();
         match cli.command {
-            Some(Command::Query { ref task, .. }) => {
-                assert_eq!(task, "auth middleware");
+            Some(Command::Query(ref args)) => {
+                assert_eq!(args.task, "auth middleware");
             }
             _ => panic!("expected Query"),
         }
     }
 
+    #[test]
+    fn cli_parses_fuzzy_query_and_trigram_index() {
+        let cli = Cli::try_parse_from(["topo", "query", "authz", "--fuzzy"]).unwrap();
+        match cli.command {
+            Some(Command::Query(ref args)) => assert!(args.fuzzy),
+            _ => panic!("expected Query"),
+        }
+        let cli = Cli::try_parse_from(["topo", "index", "--deep", "--trigrams"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Index { trigrams: true, .. })
+        ));
+    }
+
+    #[test]
+    fn cli_parses_include_tests() {
+        use commands::query::IncludeTests;
+        let cli =
+            Cli::try_parse_from(["topo", "query", "auth", "--include-tests", "coupled"]).unwrap();
+        match cli.command {
+            Some(Command::Query(ref args)) => {
+                assert_eq!(args.include_tests, IncludeTests::Coupled);
+                assert_eq!(args.matching().coupling, topo_score::Coupling::Tests);
+            }
+            _ => panic!("expected Query"),
+        }
+        let cli = Cli::try_parse_from(["topo", "query", "auth"]).unwrap();
+        match cli.command {
+            Some(Command::Query(ref args)) => assert_eq!(args.include_tests, IncludeTests::All),
+            _ => panic!("expected Query"),
+        }
+        assert!(Cli::try_parse_from(["topo", "query", "auth", "--include-tests", "some"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_signature_query() {
+        let cli = Cli::try_parse_from(["topo", "quick", "auth", "--signatures"]).unwrap();
+        match cli.command {
+            Some(Command::Quick(ref args)) => assert!(args.signatures && args.needs_index()),
+            _ => panic!("expected Quick"),
+        }
+        assert!(
+            Cli::try_parse_from(["topo", "query", "auth", "--signatures", "--chunks", "2"])
+                .is_err()
+        );
+        let cli = Cli::try_parse_from(["topo", "query", "auth", "--api"]).unwrap();
+        match cli.command {
+            Some(Command::Query(ref args)) => assert!(args.api && args.needs_index()),
+            _ => panic!("expected Query"),
+        }
+        assert!(Cli::try_parse_from(["topo", "query", "auth", "--api", "--signatures"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_chunk_query_and_chunk_index() {
+        let cli = Cli::try_parse_from(["topo", "query", "auth", "--chunks", "2"]).unwrap();
+        match cli.command {
+            Some(Command::Query(ref args)) => assert_eq!(args.chunks, Some(2)),
+            _ => panic!("expected Query"),
+        }
+        assert!(
+            Cli::try_parse_from(["topo", "query", "auth", "--chunks", "2", "--context", "3"])
+                .is_err()
+        );
+        let cli = Cli::try_parse_from(["topo", "index", "--deep", "--chunks"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Index { chunks: true, .. })
+        ));
+        let cli =
+            Cli::try_parse_from(["topo", "index", "--deep", "--keep-license-headers"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Index {
+                keep_license_headers: true,
+                ..
+            })
+        ));
+        let cli = Cli::try_parse_from(["topo", "index", "--deep", "--lsp"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Index { lsp: true, .. })
+        ));
+    }
+
     #[test]
     fn cli_parses_quick_with_preset() {
         let cli = Cli::try_parse_from(["topo", "quick", "auth", "--preset", "fast"]).unwrap();
         match cli.command {
-            Some(Command::Quick {
-                ref task, preset, ..
-            }) => {
-                assert_eq!(task, "auth");
-                assert!(matches!(preset, preset::Preset::Fast));
+            Some(Command::Quick(ref args)) => {
+                assert_eq!(args.task, "auth");
+                assert!(matches!(args.preset, Some(preset::Preset::Fast)));
+            }
+            _ => panic!("expected Quick"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_quick_task_after_separator() {
+        // As the hooks call it, so prompts starting with `-` stay the task
+        let cli = Cli::try_parse_from([
+            "topo",
+            "quick",
+            "--format",
+            "compact",
+            "--top",
+            "10",
+            "--",
+            "--root /tmp explain the auth flow",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::Quick(ref args)) => {
+                assert_eq!(args.task, "--root /tmp explain the auth flow");
+                assert!(args.scope.is_empty());
+                assert!(cli.root.is_none());
             }
             _ => panic!("expected Quick"),
         }
@@ -347,12 +913,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn cli_parses_stats() {
+        let cli = Cli::try_parse_from(["topo", "stats"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Stats)));
+    }
+
     #[test]
     fn cli_parses_describe() {
         let cli = Cli::try_parse_from(["topo", "describe"]).unwrap();
         assert!(matches!(cli.command, Some(Command::Describe)));
     }
 
+    #[test]
+    fn cli_parses_capabilities() {
+        let cli = Cli::try_parse_from(["topo", "capabilities"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Capabilities)));
+    }
+
     #[test]
     fn cli_parses_format_json() {
         let cli = Cli::try_parse_from(["topo", "--format", "json"]).unwrap();
@@ -369,9 +947,14 @@ mod tests {
     fn cli_parses_init_default_hooks() {
         let cli = Cli::try_parse_from(["topo", "init"]).unwrap();
         match cli.command {
-            Some(Command::Init { force, hooks }) => {
+            Some(Command::Init {
+                force,
+                hooks,
+                dry_run,
+            }) => {
                 assert!(!force);
                 assert!(hooks); // hooks default to true
+                assert!(!dry_run);
             }
             _ => panic!("expected Init"),
         }
@@ -388,6 +971,99 @@ mod tests {
         }
     }
 
+    #[test]
+    fn cli_parses_init_and_clean_dry_run() {
+        let cli = Cli::try_parse_from(["topo", "init", "--dry-run"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Init { dry_run: true, .. })
+        ));
+        let cli = Cli::try_parse_from(["topo", "clean", "--dry-run"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Clean { dry_run: true })
+        ));
+        let cli = Cli::try_parse_from(["topo", "clean"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Clean { dry_run: false })
+        ));
+    }
+
+    #[test]
+    fn cli_parses_setup() {
+        let cli = Cli::try_parse_from(["topo", "setup"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Setup {
+                yes: false,
+                no_index: false,
+                dry_run: false
+            })
+        ));
+        let cli = Cli::try_parse_from(["topo", "setup", "-y", "--no-index"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Setup {
+                yes: true,
+                no_index: true,
+                dry_run: false
+            })
+        ));
+    }
+
+    #[test]
+    fn cli_parses_query_help() {
+        let cli = Cli::try_parse_from(["topo", "query-help"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::QueryHelp)));
+    }
+
+    #[test]
+    fn cli_parses_pin_and_unpin() {
+        let cli = Cli::try_parse_from(["topo", "pin", "src/main.rs", "README.md"]).unwrap();
+        match cli.command {
+            Some(Command::Pin { paths }) => assert_eq!(paths.len(), 2),
+            _ => panic!("expected pin"),
+        }
+        let cli = Cli::try_parse_from(["topo", "pin"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Pin { paths }) if paths.is_empty()));
+        let cli = Cli::try_parse_from(["topo", "unpin", "--all"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Unpin { all: true, .. })
+        ));
+        assert!(Cli::try_parse_from(["topo", "unpin"]).is_err());
+        assert!(Cli::try_parse_from(["topo", "unpin", "a.rs", "--all"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_feedback() {
+        let cli = Cli::try_parse_from([
+            "topo",
+            "feedback",
+            "auth",
+            "--good",
+            "src/auth/mod.rs",
+            "--bad",
+            "src/legacy/old.rs",
+            "--good",
+            "src/auth/jwt.rs",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::Feedback {
+                task, good, bad, ..
+            }) => {
+                assert_eq!(task, "auth");
+                assert_eq!(good.len(), 2);
+                assert_eq!(bad.len(), 1);
+            }
+            _ => panic!("expected feedback"),
+        }
+        assert!(Cli::try_parse_from(["topo", "feedback", "auth", "--bad", "a.rs"]).is_ok());
+        assert!(Cli::try_parse_from(["topo", "feedback", "auth"]).is_err());
+    }
+
     #[test]
     fn cli_parses_gain() {
         let cli = Cli::try_parse_from(["topo", "gain"]).unwrap();
@@ -400,6 +1076,32 @@ mod tests {
         assert!(matches!(cli.format, OutputFormat::Compact));
     }
 
+    #[test]
+    fn cli_parses_format_html() {
+        let cli = Cli::try_parse_from(["topo", "--format", "html"]).unwrap();
+        assert!(matches!(cli.format, OutputFormat::Html));
+    }
+
+    #[test]
+    fn cli_parses_format_sarif() {
+        let cli = Cli::try_parse_from(["topo", "query", "auth", "--format", "sarif"]).unwrap();
+        assert!(matches!(cli.format, OutputFormat::Sarif));
+    }
+
+    #[test]
+    fn cli_parses_progress_ndjson() {
+        let cli = Cli::try_parse_from(["topo", "index", "--progress", "ndjson"]).unwrap();
+        assert_eq!(cli.progress, progress::ProgressMode::Ndjson);
+        assert!(cli.is_quiet());
+    }
+
+    #[test]
+    fn cli_progress_defaults_to_human() {
+        let cli = Cli::try_parse_from(["topo", "index"]).unwrap();
+        assert_eq!(cli.progress, progress::ProgressMode::Human);
+        assert!(!cli.is_quiet());
+    }
+
     #[test]
     fn cli_parses_query_with_budget() {
         let cli = Cli::try_parse_from([
@@ -415,16 +1117,198 @@ mod tests {
         ])
         .unwrap();
         match cli.command {
-            Some(Command::Query {
-                max_bytes,
-                min_score,
-                top,
-                ..
-            }) => {
-                assert_eq!(max_bytes, Some(100_000));
-                assert_eq!(min_score, Some(0.1));
-                assert_eq!(top, Some(20));
+            Some(Command::Query(ref args)) => {
+                assert_eq!(args.max_bytes, Some(100_000));
+                assert_eq!(args.min_score, Some(0.1));
+                assert_eq!(args.top, Some(20));
+                assert!(!args.deleted);
+            }
+            _ => panic!("expected Query"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_query_highlight() {
+        let cli = Cli::try_parse_from(["topo", "query", "auth", "--highlight"]).unwrap();
+        match cli.command {
+            Some(Command::Query(ref args)) => assert!(args.highlight),
+            _ => panic!("expected Query"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_query_context() {
+        let cli = Cli::try_parse_from(["topo", "quick", "auth", "--context", "3"]).unwrap();
+        match cli.command {
+            Some(Command::Quick(ref args)) => assert_eq!(args.context, Some(3)),
+            _ => panic!("expected Quick"),
+        }
+        assert!(Cli::try_parse_from(["topo", "query", "auth", "--context", "-1"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_query_orientation() {
+        let cli = Cli::try_parse_from(["topo", "query", "auth", "--orientation", "0.2"]).unwrap();
+        match cli.command {
+            Some(Command::Query(ref args)) => assert_eq!(args.orientation, Some(0.2)),
+            _ => panic!("expected Query"),
+        }
+        assert!(Cli::try_parse_from(["topo", "query", "auth", "--orientation", "1.5"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_query_mmr_lambda() {
+        let cli = Cli::try_parse_from(["topo", "query", "auth", "--mmr-lambda", "0.7"]).unwrap();
+        match cli.command {
+            Some(Command::Query(ref args)) => assert_eq!(args.mmr_lambda, Some(0.7)),
+            _ => panic!("expected Query"),
+        }
+        assert!(Cli::try_parse_from(["topo", "query", "auth", "--mmr-lambda", "1.5"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_query_author() {
+        let author = |argv: &[&str]| match Cli::try_parse_from(argv).unwrap().command {
+            Some(Command::Query(args)) => args.author,
+            _ => panic!("expected Query"),
+        };
+        assert_eq!(author(&["topo", "query", "auth"]), None);
+        assert_eq!(author(&["topo", "query", "auth", "--author"]), Some(None));
+        assert_eq!(
+            author(&["topo", "query", "auth", "--author", "me@corp.com"]),
+            Some(Some("me@corp.com".to_string()))
+        );
+    }
+
+    #[test]
+    fn cli_parses_query_documents() {
+        let cli = Cli::try_parse_from(["topo", "quick", "--query-file", "issue.md"]).unwrap();
+        match cli.command {
+            Some(Command::Quick(ref args)) => {
+                assert_eq!(args.task, "");
+                assert_eq!(args.query_file, Some(PathBuf::from("issue.md")));
+            }
+            _ => panic!("expected Quick"),
+        }
+        let cli = Cli::try_parse_from(["topo", "query", "refund", "--query-from-diff", "-"]);
+        assert!(cli.is_ok());
+        assert!(Cli::try_parse_from(["topo", "quick"]).is_err());
+        assert!(
+            Cli::try_parse_from([
+                "topo",
+                "quick",
+                "--query-file",
+                "a.md",
+                "--query-from-diff",
+                "b.diff"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn cli_parses_query_normalize() {
+        let cli = Cli::try_parse_from(["topo", "query", "auth", "--normalize", "minmax"]).unwrap();
+        match cli.command {
+            Some(Command::Query(ref args)) => {
+                assert_eq!(args.normalize, Some(topo_score::Normalization::MinMax))
+            }
+            _ => panic!("expected Query"),
+        }
+        assert!(Cli::try_parse_from(["topo", "query", "auth", "--normalize", "zscore"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_lockfile_mode() {
+        let cli = Cli::try_parse_from(["topo", "query", "serde", "--lockfiles"]).unwrap();
+        match cli.command {
+            Some(Command::Query(ref args)) => assert!(args.lockfiles),
+            _ => panic!("expected Query"),
+        }
+        assert!(
+            Cli::try_parse_from(["topo", "query", "serde", "--lockfiles", "--chunks", "2"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn cli_parses_compare_retrieval() {
+        let cli =
+            Cli::try_parse_from(["topo", "query", "auth", "--compare-retrieval", "query.json"])
+                .unwrap();
+        match cli.command {
+            Some(Command::Query(ref args)) => assert_eq!(
+                args.compare_retrieval,
+                Some(Some(std::path::PathBuf::from("query.json")))
+            ),
+            _ => panic!("expected Query"),
+        }
+        let cli = Cli::try_parse_from(["topo", "query", "auth", "--compare-retrieval"]).unwrap();
+        match cli.command {
+            Some(Command::Query(ref args)) => assert_eq!(args.compare_retrieval, Some(None)),
+            _ => panic!("expected Query"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_meta_filters() {
+        let cli = Cli::try_parse_from([
+            "topo",
+            "query",
+            "refunds",
+            "--meta",
+            "service=payments",
+            "--meta",
+            "tier=gold=1",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::Query(ref args)) => assert_eq!(
+                args.meta,
+                vec![
+                    ("service".to_string(), "payments".to_string()),
+                    ("tier".to_string(), "gold=1".to_string()),
+                ]
+            ),
+            _ => panic!("expected Query"),
+        }
+        assert!(Cli::try_parse_from(["topo", "query", "x", "--meta", "payments"]).is_err());
+        assert!(Cli::try_parse_from(["topo", "query", "x", "--meta", "=payments"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_path_globs() {
+        let cli = Cli::try_parse_from([
+            "topo",
+            "query",
+            "login",
+            "--path",
+            "src/auth/**",
+            "--path",
+            "lib/*.rs",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::Query(ref args)) => assert_eq!(args.paths, ["src/auth/**", "lib/*.rs"]),
+            _ => panic!("expected Query"),
+        }
+
+        let cli =
+            Cli::try_parse_from(["topo", "quick", "charge", "--", "src/payments/**"]).unwrap();
+        match cli.command {
+            Some(Command::Quick(ref args)) => {
+                assert_eq!(args.task, "charge");
+                assert_eq!(args.scope, ["src/payments/**"]);
             }
+            _ => panic!("expected Quick"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_query_deleted_flag() {
+        let cli = Cli::try_parse_from(["topo", "query", "build", "--deleted"]).unwrap();
+        match cli.command {
+            Some(Command::Query(ref args)) => assert!(args.deleted),
             _ => panic!("expected Query"),
         }
     }
@@ -1,19 +1,32 @@
 use clap::ValueEnum;
+use topo_core::Config;
+use topo_score::ScoringPreset;
 
 /// Scoring presets that configure index depth and signal selection.
-#[derive(Debug, Clone, Copy, ValueEnum)]
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
 pub enum Preset {
-    /// Shallow index, heuristic-only scoring (fastest)
+    /// Shallow index, path heuristic only (fastest)
     Fast,
     /// Deep index (cached), hybrid BM25F + heuristic scoring
+    #[default]
     Balanced,
     /// Deep index (fresh rebuild), hybrid + structural signals
     Deep,
-    /// Deep index + reranking, all signals including embeddings
+    /// Like deep, with boosts checking twice as many leading files
     Thorough,
 }
 
 impl Preset {
+    /// The `query.preset` of `config`, or balanced.
+    pub fn from_config(config: &Config) -> Self {
+        config
+            .query
+            .preset
+            .as_deref()
+            .and_then(|name| Self::from_str(name, true).ok())
+            .unwrap_or_default()
+    }
+
     pub fn as_str(&self) -> &'static str {
         match self {
             Self::Fast => "fast",
@@ -23,9 +36,19 @@ impl Preset {
         }
     }
 
+    /// How this preset scores queries.
+    pub fn scoring(&self) -> ScoringPreset {
+        match self {
+            Self::Fast => ScoringPreset::FAST,
+            Self::Balanced => ScoringPreset::BALANCED,
+            Self::Deep => ScoringPreset::DEEP,
+            Self::Thorough => ScoringPreset::THOROUGH,
+        }
+    }
+
     /// Whether this preset needs a deep index.
     pub fn needs_deep_index(&self) -> bool {
-        matches!(self, Self::Balanced | Self::Deep | Self::Thorough)
+        self.scoring().deep_index
     }
 
     /// Whether this preset should force-rebuild the index.
@@ -33,29 +56,19 @@ impl Preset {
         matches!(self, Self::Deep | Self::Thorough)
     }
 
-    /// Whether to include structural signals (PageRank, git recency).
+    /// Whether to include structural signals (PageRank, git recency, ownership).
     pub fn use_structural_signals(&self) -> bool {
-        matches!(self, Self::Deep | Self::Thorough)
+        self.scoring().structural
     }
 
     /// Default max bytes budget for this preset.
     pub fn default_max_bytes(&self) -> u64 {
-        match self {
-            Self::Fast => 50_000,
-            Self::Balanced => 100_000,
-            Self::Deep => 200_000,
-            Self::Thorough => 500_000,
-        }
+        self.scoring().max_bytes
     }
 
     /// Default minimum score threshold.
     pub fn default_min_score(&self) -> f64 {
-        match self {
-            Self::Fast => 0.05,
-            Self::Balanced => 0.01,
-            Self::Deep => 0.005,
-            Self::Thorough => 0.001,
-        }
+        self.scoring().min_score
     }
 }
 
@@ -69,6 +82,16 @@ impl std::fmt::Display for Preset {
 mod tests {
     use super::*;
 
+    #[test]
+    fn preset_defaults_from_config() {
+        assert!(matches!(
+            Preset::from_config(&Config::default()),
+            Preset::Balanced
+        ));
+        let config = Config::parse("[query]\npreset = \"deep\"\n").unwrap();
+        assert!(matches!(Preset::from_config(&config), Preset::Deep));
+    }
+
     #[test]
     fn preset_as_str() {
         assert_eq!(Preset::Fast.as_str(), "fast");
@@ -101,6 +124,13 @@ mod tests {
         assert!(Preset::Thorough.use_structural_signals());
     }
 
+    #[test]
+    fn presets_match_their_scoring() {
+        for preset in Preset::value_variants() {
+            assert_eq!(preset.scoring().name, preset.as_str());
+        }
+    }
+
     #[test]
     fn preset_budgets_increase() {
         assert!(Preset::Fast.default_max_bytes() < Preset::Balanced.default_max_bytes());
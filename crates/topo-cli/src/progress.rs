@@ -0,0 +1,248 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How progress is reported on stderr.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ProgressMode {
+    /// Human-readable status lines
+    #[default]
+    Human,
+    /// One JSON event per line, for GUI wrappers
+    Ndjson,
+    /// No progress output
+    None,
+}
+
+/// Minimum interval between `progress` events within a phase.
+const THROTTLE: Duration = Duration::from_millis(100);
+
+#[derive(Serialize)]
+struct Event<'a> {
+    event: &'a str,
+    phase: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    done: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    eta_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    elapsed_ms: Option<u64>,
+}
+
+struct PhaseState {
+    name: &'static str,
+    total: Option<usize>,
+    started: Instant,
+    last_emit: Option<Instant>,
+}
+
+/// Structured progress reporter.
+///
+/// In `ndjson` mode, emits `start`, `progress`, and `finish` events on stderr
+/// while results go to stdout. Other modes ignore phase events; human status
+/// lines are printed by the commands themselves.
+pub struct Progress {
+    mode: ProgressMode,
+    phase: Mutex<Option<PhaseState>>,
+    sink: Mutex<Box<dyn Write + Send>>,
+}
+
+impl Progress {
+    pub fn new(mode: ProgressMode) -> Self {
+        Self::with_sink(mode, Box::new(std::io::stderr()))
+    }
+
+    fn with_sink(mode: ProgressMode, sink: Box<dyn Write + Send>) -> Self {
+        Self {
+            mode,
+            phase: Mutex::new(None),
+            sink: Mutex::new(sink),
+        }
+    }
+
+    /// Begin a phase; `total` is the item count when known up front.
+    pub fn start(&self, phase: &'static str, total: Option<usize>) {
+        if self.mode != ProgressMode::Ndjson {
+            return;
+        }
+        *self.phase.lock().unwrap() = Some(PhaseState {
+            name: phase,
+            total,
+            started: Instant::now(),
+            last_emit: None,
+        });
+        self.emit(&Event {
+            event: "start",
+            phase,
+            done: None,
+            total,
+            eta_ms: None,
+            elapsed_ms: None,
+        });
+    }
+
+    /// Report items done in the current phase. Throttled; safe to call per item.
+    pub fn update(&self, done: usize) {
+        if self.mode != ProgressMode::Ndjson {
+            return;
+        }
+        let mut guard = self.phase.lock().unwrap();
+        let Some(state) = guard.as_mut() else {
+            return;
+        };
+        let now = Instant::now();
+        if state
+            .last_emit
+            .is_some_and(|last| now.duration_since(last) < THROTTLE)
+        {
+            return;
+        }
+        state.last_emit = Some(now);
+
+        let elapsed = now.duration_since(state.started);
+        let eta_ms = state.total.and_then(|total| eta(elapsed, done, total));
+        let (phase, total) = (state.name, state.total);
+        drop(guard);
+
+        self.emit(&Event {
+            event: "progress",
+            phase,
+            done: Some(done),
+            total,
+            eta_ms,
+            elapsed_ms: None,
+        });
+    }
+
+    /// End the current phase with the final item count.
+    pub fn finish(&self, done: usize) {
+        if self.mode != ProgressMode::Ndjson {
+            return;
+        }
+        let Some(state) = self.phase.lock().unwrap().take() else {
+            return;
+        };
+        self.emit(&Event {
+            event: "finish",
+            phase: state.name,
+            done: Some(done),
+            total: state.total.or(Some(done)),
+            eta_ms: None,
+            elapsed_ms: Some(state.started.elapsed().as_millis() as u64),
+        });
+    }
+
+    fn emit(&self, event: &Event) {
+        let mut sink = self.sink.lock().unwrap();
+        if let Ok(line) = serde_json::to_string(event) {
+            let _ = writeln!(sink, "{line}");
+        }
+    }
+}
+
+/// Linear estimate of remaining time from the rate so far.
+fn eta(elapsed: Duration, done: usize, total: usize) -> Option<u64> {
+    if done == 0 || done > total {
+        return None;
+    }
+    let per_item = elapsed.as_secs_f64() / done as f64;
+    Some((per_item * (total - done) as f64 * 1000.0) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// Shared in-memory sink for capturing events.
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn events(buffer: &Buffer) -> Vec<serde_json::Value> {
+        let bytes = buffer.0.lock().unwrap().clone();
+        String::from_utf8(bytes)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn ndjson_emits_start_progress_finish() {
+        let buffer = Buffer::default();
+        let progress = Progress::with_sink(ProgressMode::Ndjson, Box::new(buffer.clone()));
+        progress.start("index", Some(10));
+        progress.update(5);
+        progress.finish(10);
+
+        let events = events(&buffer);
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0]["event"], "start");
+        assert_eq!(events[0]["phase"], "index");
+        assert_eq!(events[0]["total"], 10);
+        assert_eq!(events[1]["event"], "progress");
+        assert_eq!(events[1]["done"], 5);
+        assert!(events[1]["eta_ms"].is_u64());
+        assert_eq!(events[2]["event"], "finish");
+        assert_eq!(events[2]["done"], 10);
+        assert!(events[2]["elapsed_ms"].is_u64());
+    }
+
+    #[test]
+    fn updates_are_throttled() {
+        let buffer = Buffer::default();
+        let progress = Progress::with_sink(ProgressMode::Ndjson, Box::new(buffer.clone()));
+        progress.start("index", Some(1000));
+        for i in 1..=1000 {
+            progress.update(i);
+        }
+        let count = events(&buffer)
+            .iter()
+            .filter(|e| e["event"] == "progress")
+            .count();
+        assert!((1..1000).contains(&count));
+    }
+
+    #[test]
+    fn unknown_total_finishes_with_done() {
+        let buffer = Buffer::default();
+        let progress = Progress::with_sink(ProgressMode::Ndjson, Box::new(buffer.clone()));
+        progress.start("scan", None);
+        progress.finish(42);
+        let events = events(&buffer);
+        assert!(events[0].get("total").is_none());
+        assert_eq!(events[1]["total"], 42);
+    }
+
+    #[test]
+    fn other_modes_emit_nothing() {
+        for mode in [ProgressMode::Human, ProgressMode::None] {
+            let buffer = Buffer::default();
+            let progress = Progress::with_sink(mode, Box::new(buffer.clone()));
+            progress.start("scan", None);
+            progress.update(1);
+            progress.finish(1);
+            assert!(events(&buffer).is_empty());
+        }
+    }
+
+    #[test]
+    fn eta_is_linear() {
+        assert_eq!(eta(Duration::from_secs(1), 10, 20), Some(1000));
+        assert_eq!(eta(Duration::from_secs(1), 0, 20), None);
+    }
+}
@@ -0,0 +1,162 @@
+use serde::Serialize;
+use std::cell::Cell;
+use std::path::Path;
+use std::time::Instant;
+
+thread_local! {
+    /// Files and bytes read through [`read_to_string`] on this thread.
+    static READS: Cell<(u64, u64)> = const { Cell::new((0, 0)) };
+}
+
+/// Read `path` like [`std::fs::read_to_string`], counting the file toward
+/// the current thread's [`ResourceUsage`].
+pub fn read_to_string(path: &Path) -> std::io::Result<String> {
+    let text = std::fs::read_to_string(path)?;
+    READS.with(|reads| {
+        let (files, bytes) = reads.get();
+        reads.set((files + 1, bytes + text.len() as u64));
+    });
+    Ok(text)
+}
+
+/// What one query cost, as reported with `--verbose`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ResourceUsage {
+    /// Wall time of each phase, in the order they ran.
+    pub phases: Vec<PhaseTime>,
+    pub total_ms: f64,
+    /// Peak resident set size of the process, where the platform reports
+    /// it. A daemon's peak covers every query it has answered.
+    pub peak_rss_bytes: Option<u64>,
+    /// Files read for previews, context windows, and reports.
+    pub files_read: u64,
+    pub bytes_read: u64,
+    /// Bytes hashed to fingerprint the scanned files.
+    pub bytes_hashed: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PhaseTime {
+    pub name: &'static str,
+    pub ms: f64,
+}
+
+impl ResourceUsage {
+    /// One line per measurement, for stderr.
+    pub fn summary(&self) -> String {
+        let mut lines = vec![format!("Query took {:.1} ms", self.total_ms)];
+        for phase in &self.phases {
+            lines.push(format!("  {:<8} {:>9.1} ms", phase.name, phase.ms));
+        }
+        if let Some(rss) = self.peak_rss_bytes {
+            lines.push(format!("Peak RSS: {:.1} MB", rss as f64 / 1_048_576.0));
+        }
+        lines.push(format!(
+            "Files read: {} ({} bytes)",
+            self.files_read, self.bytes_read
+        ));
+        lines.push(format!("Bytes hashed: {}", self.bytes_hashed));
+        lines.join("\n")
+    }
+}
+
+/// Times the phases of a query on the current thread and counts what it
+/// read and hashed.
+#[derive(Debug)]
+pub struct Meter {
+    started: Instant,
+    mark: Instant,
+    phases: Vec<PhaseTime>,
+    reads: (u64, u64),
+    bytes_hashed: u64,
+}
+
+impl Meter {
+    /// Start measuring a query that began at `started`.
+    pub fn new(started: Instant) -> Self {
+        Self {
+            started,
+            mark: started,
+            phases: Vec::new(),
+            reads: READS.with(Cell::get),
+            bytes_hashed: 0,
+        }
+    }
+
+    /// When the query began.
+    pub fn started(&self) -> Instant {
+        self.started
+    }
+
+    /// End the phase `name`, which ran since the previous one ended.
+    pub fn phase(&mut self, name: &'static str) {
+        let now = Instant::now();
+        self.phases.push(PhaseTime {
+            name,
+            ms: millis(now - self.mark),
+        });
+        self.mark = now;
+    }
+
+    /// Count `bytes` as hashed.
+    pub fn hashed(&mut self, bytes: u64) {
+        self.bytes_hashed += bytes;
+    }
+
+    /// Usage so far.
+    pub fn usage(&self) -> ResourceUsage {
+        let (files, bytes) = READS.with(Cell::get);
+        ResourceUsage {
+            phases: self.phases.clone(),
+            total_ms: millis(self.started.elapsed()),
+            peak_rss_bytes: peak_rss(),
+            files_read: files - self.reads.0,
+            bytes_read: bytes - self.reads.1,
+            bytes_hashed: self.bytes_hashed,
+        }
+    }
+}
+
+fn millis(duration: std::time::Duration) -> f64 {
+    (duration.as_secs_f64() * 10_000.0).round() / 10.0
+}
+
+/// Peak resident set size, from `VmHWM` in `/proc/self/status`.
+fn peak_rss() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn meter_times_phases_and_counts_reads() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.rs");
+        std::fs::write(&path, "fn a() {}\n").unwrap();
+        // Reads before the meter starts aren't counted
+        read_to_string(&path).unwrap();
+
+        let mut meter = Meter::new(Instant::now());
+        meter.phase("scan");
+        meter.hashed(10);
+        read_to_string(&path).unwrap();
+        meter.phase("select");
+
+        let usage = meter.usage();
+        let names: Vec<&str> = usage.phases.iter().map(|p| p.name).collect();
+        assert_eq!(names, ["scan", "select"]);
+        assert!(usage.total_ms >= usage.phases.iter().map(|p| p.ms).sum::<f64>() - 0.2);
+        assert_eq!(usage.files_read, 1);
+        assert_eq!(usage.bytes_read, 10);
+        assert_eq!(usage.bytes_hashed, 10);
+        if cfg!(target_os = "linux") {
+            assert!(usage.peak_rss_bytes.unwrap() > 0);
+        }
+        assert!(usage.summary().contains("Files read: 1 (10 bytes)"));
+    }
+}
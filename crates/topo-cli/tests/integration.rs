@@ -1,4 +1,4 @@
-//! Integration tests: scan, bundle, render JSONL v0.3, compatibility checks.
+//! Integration tests: scan, bundle, render JSONL v0.4, compatibility checks.
 
 use std::fs;
 use topo_core::{FileRole, Language, ScoredFile, SignalBreakdown, TokenBudget};
@@ -72,6 +72,7 @@ fn bundle_to_jsonl_roundtrip() {
             tokens: f.estimated_tokens(),
             language: f.language,
             role: f.role,
+            preview: None,
         })
         .collect();
 
@@ -92,7 +93,7 @@ fn bundle_to_jsonl_roundtrip() {
 
     // Header
     let header: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
-    assert_eq!(header["Version"], "0.3");
+    assert_eq!(header["Version"], "0.4");
     assert_eq!(header["Query"], "auth middleware");
     assert_eq!(header["Preset"], "balanced");
 
@@ -136,7 +137,7 @@ fn incremental_hash_changes_on_edit() {
     assert_ne!(main1.sha256, main2.sha256);
 }
 
-// ── Compatibility tests: JSONL v0.3 format matches spec ────────────
+// ── Compatibility tests: JSONL v0.4 format matches spec ────────────
 
 fn make_scored(path: &str, score: f64, tokens: u64, lang: Language, role: FileRole) -> ScoredFile {
     ScoredFile {
@@ -150,6 +151,7 @@ fn make_scored(path: &str, score: f64, tokens: u64, lang: Language, role: FileRo
         tokens,
         language: lang,
         role,
+        preview: None,
     }
 }
 
@@ -163,8 +165,8 @@ fn compat_jsonl_header_format() {
 
     let header: serde_json::Value = serde_json::from_str(output.lines().next().unwrap()).unwrap();
 
-    // Required header fields per JSONL v0.3 spec
-    assert_eq!(header["Version"], "0.3");
+    // Required header fields per JSONL v0.4 spec
+    assert_eq!(header["Version"], "0.4");
     assert!(header["Query"].is_string());
     assert!(header["Preset"].is_string());
     assert!(header["Budget"].is_object());
@@ -274,6 +276,7 @@ fn budget_enforcement_end_to_end() {
             tokens: f.estimated_tokens(),
             language: f.language,
             role: f.role,
+            preview: None,
         })
         .collect();
 
@@ -335,3 +338,197 @@ fn score_pipeline_end_to_end() {
         "auth file should be in top 5 for 'authenticate' query, got: {top5:?}"
     );
 }
+
+// ── Nested repositories ────────────────────────────────────────────
+
+#[test]
+fn nested_repo_federation_end_to_end() {
+    let dir = create_test_project();
+    let root = dir.path();
+    fs::create_dir_all(root.join("vendor/tokens/src")).unwrap();
+    fs::create_dir_all(root.join("vendor/tokens/.git")).unwrap();
+    fs::write(
+        root.join("vendor/tokens/src/verify.rs"),
+        "pub fn verify_token(token: &str) -> bool {\n    token.len() > 8\n}\n",
+    )
+    .unwrap();
+
+    let repos = BundleBuilder::new(root).build_federated().unwrap();
+    assert_eq!(repos.len(), 2);
+    assert!(
+        repos[0]
+            .bundle
+            .files
+            .iter()
+            .all(|f| !f.path.starts_with("vendor/"))
+    );
+
+    for repo in &repos {
+        let builder = topo_index::IndexBuilder::new(&repo.bundle.root);
+        let (index, _) = builder.build(&repo.bundle.files, None).unwrap();
+        topo_index::save_repo(&index, root, &repo.prefix, None, 3).unwrap();
+    }
+
+    // Editing the nested repo leaves the root's fingerprint alone
+    let before = repos[0].bundle.fingerprint.clone();
+    fs::write(root.join("vendor/tokens/src/verify.rs"), "pub fn v() {}\n").unwrap();
+    let repos = BundleBuilder::new(root).build_federated().unwrap();
+    assert_eq!(repos[0].bundle.fingerprint, before);
+
+    let existing = topo_index::load_repo(root, "vendor/tokens", None)
+        .unwrap()
+        .unwrap();
+    assert!(existing.files.contains_key("src/verify.rs"));
+    let (_, reindexed) = topo_index::IndexBuilder::new(&repos[1].bundle.root)
+        .build(&repos[1].bundle.files, Some(&existing))
+        .unwrap();
+    assert_eq!(reindexed, 1);
+    assert_eq!(
+        repos[1].qualify("src/verify.rs"),
+        "vendor/tokens/src/verify.rs"
+    );
+}
+
+// ── Git activity ───────────────────────────────────────────────────
+
+#[test]
+fn git_activity_is_saved_with_the_index() {
+    let dir = create_test_project();
+    let root = dir.path();
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .args(args)
+            .current_dir(root)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {args:?} failed");
+    };
+    git(&["init", "-q"]);
+    git(&["config", "user.email", "dev@example.com"]);
+    git(&["config", "user.name", "Dev"]);
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "init"]);
+
+    let bundle = BundleBuilder::new(root).build().unwrap();
+    let (index, _) = topo_index::IndexBuilder::new(root)
+        .git_activity(true)
+        .build(&bundle.files, None)
+        .unwrap();
+    topo_index::save_repo(&index, root, "", None, 3).unwrap();
+
+    let loaded = topo_index::load_repo(root, "", None).unwrap().unwrap();
+    let activity = loaded.git_activity.as_ref().unwrap();
+    assert_eq!(activity, index.git_activity.as_ref().unwrap());
+    let auth = &activity.files["src/auth/mod.rs"];
+    assert_eq!(auth.commit_count(), 1);
+    assert_eq!(auth.churn(), 3);
+
+    // Later builds keep recording history without being asked again
+    fs::write(root.join("src/auth/mod.rs"), "pub fn authenticate() {}\n").unwrap();
+    git(&["commit", "-q", "-am", "simplify auth"]);
+    let bundle = BundleBuilder::new(root).build().unwrap();
+    let (index, _) = topo_index::IndexBuilder::new(root)
+        .build(&bundle.files, Some(&loaded))
+        .unwrap();
+    let activity = index.git_activity.unwrap();
+    assert_ne!(activity.head, loaded.git_activity.unwrap().head);
+    assert_eq!(activity.files["src/auth/mod.rs"].commit_count(), 2);
+    assert_eq!(activity.files["src/main.rs"].commit_count(), 1);
+}
+
+#[test]
+fn index_meta_records_provenance() {
+    let dir = create_test_project();
+    let root = dir.path();
+    let bundle = BundleBuilder::new(root).build().unwrap();
+    let (index, _) = topo_index::IndexBuilder::new(root)
+        .fingerprint(&bundle.fingerprint)
+        .build(&bundle.files, None)
+        .unwrap();
+    topo_index::save_repo(&index, root, "", None, 3).unwrap();
+
+    let loaded = topo_index::load_repo(root, "", None).unwrap().unwrap();
+    let meta = loaded.meta.as_ref().unwrap();
+    assert_eq!(meta, index.meta.as_ref().unwrap());
+    assert_eq!(meta.tool_version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(meta.fingerprint, bundle.fingerprint);
+    assert!(meta.built_at > 0);
+    let counted: u32 = meta.languages.values().sum();
+    assert_eq!(counted, index.total_docs);
+    assert!(meta.languages["rust"] >= 1);
+
+    // Incremental builds keep the creation time
+    let (rebuilt, _) = topo_index::IndexBuilder::new(root)
+        .build(&bundle.files, Some(&loaded))
+        .unwrap();
+    let rebuilt = rebuilt.meta.unwrap();
+    assert_eq!(rebuilt.created_at, meta.created_at);
+    assert!(rebuilt.fingerprint.is_empty());
+}
+
+// ── HTML report ────────────────────────────────────────────────────
+
+#[test]
+fn html_report_end_to_end() {
+    let dir = create_test_project();
+    let bundle = BundleBuilder::new(dir.path()).build().unwrap();
+
+    let scored = topo_score::HybridScorer::new("authenticate").score(&bundle.files);
+    let contents = scored
+        .iter()
+        .map(|f| {
+            let text = fs::read_to_string(dir.path().join(&f.path)).unwrap();
+            (f.path.clone(), text)
+        })
+        .collect();
+
+    let output = topo_render::HtmlWriter::new("authenticate", "balanced")
+        .contents(contents)
+        .render(&scored, bundle.file_count())
+        .unwrap();
+
+    assert!(output.starts_with("<!DOCTYPE html>"));
+    assert!(output.contains("src/auth/mod.rs"));
+    assert!(output.contains(
+        "<span class=\"kw\">pub</span> <span class=\"kw\">fn</span> authenticate(token: &amp;str) -&gt; bool"
+    ));
+    assert!(!output.contains("(content not available)"));
+}
+
+// ── Policy / SARIF ─────────────────────────────────────────────────
+
+#[test]
+fn policy_sarif_end_to_end() {
+    let dir = create_test_project();
+    fs::write(dir.path().join(".env"), "API_KEY=secret").unwrap();
+    fs::write(
+        dir.path().join(".topo.toml"),
+        "[policy]\ndeny = [\"tests/**\"]\nmax_files = 3\n",
+    )
+    .unwrap();
+
+    let config = topo_core::Config::load(dir.path()).unwrap();
+    let bundle = BundleBuilder::new(dir.path()).build().unwrap();
+    let selection: Vec<ScoredFile> = bundle
+        .files
+        .iter()
+        .map(|f| make_scored(&f.path, 1.0, f.estimated_tokens(), f.language, f.role))
+        .collect();
+
+    let violations = config.policy.check(&selection).unwrap();
+    let output = topo_render::SarifWriter::new("0.1.0")
+        .render(&violations)
+        .unwrap();
+    let log: serde_json::Value = serde_json::from_str(&output).unwrap();
+    let rule_ids: Vec<&str> = log["runs"][0]["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["ruleId"].as_str().unwrap())
+        .collect();
+
+    assert!(rule_ids.contains(&"topo/secret-file"));
+    assert!(rule_ids.contains(&"topo/denied-path"));
+    assert!(rule_ids.contains(&"topo/file-budget"));
+}
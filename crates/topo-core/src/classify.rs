@@ -0,0 +1,244 @@
+use crate::config::RoleRule;
+use crate::error::TopoError;
+use crate::types::{FileRole, Language};
+use globset::{Glob, GlobMatcher, GlobSet, GlobSetBuilder};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Rule-based file role classifier.
+///
+/// User rules are evaluated in order and the first matching glob wins;
+/// paths that match no rule fall back to [`FileRole::from_path`].
+#[derive(Debug, Clone, Default)]
+pub struct RoleClassifier {
+    rules: Vec<(GlobMatcher, FileRole)>,
+}
+
+impl RoleClassifier {
+    /// Compile role rules into a classifier.
+    pub fn new(rules: &[RoleRule]) -> Result<Self, TopoError> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                let glob = Glob::new(&rule.pattern).map_err(|source| TopoError::Pattern {
+                    pattern: rule.pattern.clone(),
+                    source,
+                })?;
+                Ok((glob.compile_matcher(), rule.role))
+            })
+            .collect::<Result<Vec<_>, TopoError>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Classify a repo-relative path.
+    pub fn classify(&self, path: &Path) -> FileRole {
+        self.rules
+            .iter()
+            .find(|(matcher, _)| matcher.is_match(path))
+            .map(|(_, role)| *role)
+            .unwrap_or_else(|| FileRole::from_path(path))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+/// Files left out of scans by the `[scan] ignore` globs.
+#[derive(Debug, Clone)]
+pub struct IgnoreRules {
+    globs: GlobSet,
+}
+
+impl Default for IgnoreRules {
+    fn default() -> Self {
+        Self {
+            globs: GlobSet::empty(),
+        }
+    }
+}
+
+impl IgnoreRules {
+    /// Compile ignore globs.
+    pub fn new(patterns: &[String]) -> Result<Self, TopoError> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            builder.add(Glob::new(pattern).map_err(|source| TopoError::Pattern {
+                pattern: pattern.clone(),
+                source,
+            })?);
+        }
+        let globs = builder.build().map_err(|source| TopoError::Pattern {
+            pattern: patterns.join(", "),
+            source,
+        })?;
+        Ok(Self { globs })
+    }
+
+    /// Whether the repo-relative `path` is ignored.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        self.globs.is_match(path)
+    }
+}
+
+/// Extension-based language detector with user overrides.
+///
+/// Overrides are consulted first; anything else falls back to the
+/// built-in table via [`Language::from_extension`].
+#[derive(Debug, Clone, Default)]
+pub struct LanguageDetector {
+    overrides: HashMap<String, Language>,
+}
+
+impl LanguageDetector {
+    /// Build a detector from `extension → language` overrides.
+    ///
+    /// Extensions may be given with or without a leading dot.
+    pub fn new(overrides: &BTreeMap<String, Language>) -> Self {
+        let overrides = overrides
+            .iter()
+            .map(|(ext, lang)| (ext.trim_start_matches('.').to_string(), *lang))
+            .collect();
+        Self { overrides }
+    }
+
+    /// Detect the language of a path from its extension.
+    pub fn detect(&self, path: &Path) -> Language {
+        let Some(ext) = path.extension().and_then(|e| e.to_str()) else {
+            return Language::Other;
+        };
+        self.overrides
+            .get(ext)
+            .copied()
+            .unwrap_or_else(|| Language::from_extension(ext))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.overrides.is_empty()
+    }
+}
+
+/// Entry points are looked for this many directories deep at most.
+const MAX_ENTRY_POINT_DEPTH: usize = 3;
+
+/// File names that conventionally start a program or library.
+const ENTRY_POINTS: &[&str] = &[
+    "main.rs",
+    "lib.rs",
+    "main.go",
+    "main.py",
+    "__main__.py",
+    "app.py",
+    "manage.py",
+    "index.js",
+    "index.mjs",
+    "index.ts",
+    "index.tsx",
+    "main.js",
+    "main.ts",
+    "main.c",
+    "main.cpp",
+    "Main.java",
+    "main.swift",
+    "main.kt",
+];
+
+/// Whether the repo-relative `path` is a conventional entry point, like
+/// `src/main.rs` or `cmd/app/main.go`, at most three directories deep.
+pub fn is_entry_point(path: &str) -> bool {
+    let depth = path.matches('/').count();
+    let name = path.rsplit('/').next().unwrap_or(path);
+    ENTRY_POINTS.contains(&name) && depth <= MAX_ENTRY_POINT_DEPTH
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str, role: FileRole) -> RoleRule {
+        RoleRule {
+            pattern: pattern.to_string(),
+            role,
+        }
+    }
+
+    #[test]
+    fn empty_classifier_uses_builtin_heuristics() {
+        let classifier = RoleClassifier::default();
+        assert!(classifier.is_empty());
+        assert_eq!(
+            classifier.classify(Path::new("src/main.rs")),
+            FileRole::Implementation
+        );
+    }
+
+    #[test]
+    fn rule_overrides_builtin_heuristics() {
+        let classifier = RoleClassifier::new(&[
+            rule("qa/**", FileRole::Test),
+            rule("contracts/**", FileRole::Config),
+        ])
+        .unwrap();
+        assert_eq!(
+            classifier.classify(Path::new("qa/login.py")),
+            FileRole::Test
+        );
+        assert_eq!(
+            classifier.classify(Path::new("contracts/user.json")),
+            FileRole::Config
+        );
+        assert_eq!(
+            classifier.classify(Path::new("src/lib.rs")),
+            FileRole::Implementation
+        );
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let classifier = RoleClassifier::new(&[
+            rule("vendor/ours/**", FileRole::Implementation),
+            rule("vendor/**", FileRole::Generated),
+        ])
+        .unwrap();
+        assert_eq!(
+            classifier.classify(Path::new("vendor/ours/lib.go")),
+            FileRole::Implementation
+        );
+        assert_eq!(
+            classifier.classify(Path::new("vendor/theirs/lib.go")),
+            FileRole::Generated
+        );
+    }
+
+    #[test]
+    fn invalid_pattern_is_pattern_error() {
+        let err = RoleClassifier::new(&[rule("src/[", FileRole::Test)]).unwrap_err();
+        assert!(matches!(err, TopoError::Pattern { ref pattern, .. } if pattern == "src/["));
+        assert!(std::error::Error::source(&err).is_some());
+    }
+
+    #[test]
+    fn detector_without_overrides_uses_table() {
+        let detector = LanguageDetector::default();
+        assert!(detector.is_empty());
+        assert_eq!(detector.detect(Path::new("src/main.zig")), Language::Zig);
+        assert_eq!(detector.detect(Path::new("Makefile")), Language::Other);
+    }
+
+    #[test]
+    fn detector_overrides_take_precedence() {
+        let mut overrides = BTreeMap::new();
+        overrides.insert(".tpl".to_string(), Language::Go);
+        overrides.insert("bazel".to_string(), Language::Starlark);
+        overrides.insert("h".to_string(), Language::Cpp);
+        let detector = LanguageDetector::new(&overrides);
+
+        assert_eq!(detector.detect(Path::new("tmpl/page.tpl")), Language::Go);
+        assert_eq!(
+            detector.detect(Path::new("pkg/BUILD.bazel")),
+            Language::Starlark
+        );
+        assert_eq!(detector.detect(Path::new("include/foo.h")), Language::Cpp);
+        assert_eq!(detector.detect(Path::new("src/lib.rs")), Language::Rust);
+    }
+}
@@ -0,0 +1,693 @@
+use crate::error::TopoError;
+use crate::policy::Policy;
+use crate::types::{FileRole, Language};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Repository-level configuration file, read from the repo root.
+pub const CONFIG_FILE: &str = ".topo.toml";
+
+/// User configuration loaded from `.topo.toml`.
+///
+/// Every section is optional; a missing file yields the defaults.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Glob → role mappings evaluated before the built-in heuristics.
+    pub roles: Vec<RoleRule>,
+    /// Extension → language overrides applied before the built-in table.
+    pub languages: BTreeMap<String, Language>,
+    /// Selection policy checks (`--format sarif`).
+    pub policy: Policy,
+    /// Deep index storage settings.
+    pub index: IndexConfig,
+    /// How query budgets are spent.
+    pub budget: BudgetConfig,
+    /// Ranking settings.
+    pub scoring: ScoringConfig,
+    /// How embedding providers are called while indexing.
+    pub embedding: EmbeddingConfig,
+    /// Where `topo index push` and `pull` share the index.
+    pub remote: RemoteConfig,
+    /// Language servers `topo index --lsp` takes symbols from.
+    pub lsp: LspConfig,
+    /// What scans leave out, beyond `.gitignore`.
+    pub scan: ScanConfig,
+    /// Defaults for `topo query` and `topo quick`.
+    pub query: QueryConfig,
+    /// Query term → synonyms it also matches, on top of the built-in
+    /// developer abbreviations like `db` and `k8s`.
+    pub synonyms: BTreeMap<String, Vec<String>>,
+    /// SHA-256 of the source text, when loaded from a file.
+    #[serde(skip)]
+    source_hash: Option<String>,
+}
+
+/// A single `[[roles]]` entry: files matching `pattern` get `role`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RoleRule {
+    pub pattern: String,
+    pub role: FileRole,
+}
+
+/// Names of the query presets, for `query.preset`.
+pub const PRESETS: &[&str] = &["fast", "balanced", "deep", "thorough"];
+
+/// Names of the score normalizations, for `query.normalize`.
+pub const NORMALIZATIONS: &[&str] = &["raw", "minmax", "softmax"];
+
+/// Default zstd level for the persisted index.
+pub const DEFAULT_INDEX_COMPRESSION: i32 = 3;
+
+/// The `[index]` section.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct IndexConfig {
+    /// Zstd level (1–22) for `.topo/index.bin`; 0 stores it uncompressed.
+    pub compression_level: i32,
+    /// Index the contents of lockfiles like `Cargo.lock`, not just their
+    /// filenames.
+    pub lockfile_bodies: bool,
+    /// Whether comments count as body text, docs, or not at all.
+    pub comments: CommentMode,
+    /// Index and query terms by their stems, so "authenticate" matches
+    /// "authentication".
+    pub stemming: bool,
+    /// What to leave out of the term postings of huge repositories.
+    pub prune: PruneConfig,
+}
+
+impl Default for IndexConfig {
+    fn default() -> Self {
+        Self {
+            compression_level: DEFAULT_INDEX_COMPRESSION,
+            lockfile_bodies: false,
+            comments: CommentMode::Keep,
+            stemming: false,
+            prune: PruneConfig::default(),
+        }
+    }
+}
+
+/// What indexing does with comments, `[index] comments`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommentMode {
+    /// Count comments as body text, like code.
+    #[default]
+    Keep,
+    /// Leave comments out of the body field and document lengths, so
+    /// headers and commented-out code don't count.
+    Strip,
+    /// Count comments in the docs field instead of the body.
+    Docs,
+}
+
+/// The `[index.prune]` section. Nothing is pruned by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct PruneConfig {
+    /// Drop terms found in a single file when that file is generated, like
+    /// the unique identifiers of protobuf or GraphQL codegen output.
+    pub generated_hapax: bool,
+    /// Keep each term's postings only in the N files where it occurs most.
+    pub max_postings: Option<u32>,
+    /// Estimated size budget, in MB, for the term postings; beyond it,
+    /// single body occurrences of the most common terms are dropped.
+    pub max_size_mb: Option<u64>,
+}
+
+/// The `[budget]` section.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct BudgetConfig {
+    /// Share of each query budget (0.0–1.0) reserved for orientation
+    /// material: a directory outline, READMEs, and entry points.
+    pub orientation: f64,
+}
+
+/// The `[scoring]` section.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ScoringConfig {
+    /// BM25F weight of terms in doc comments and docstrings; unset keeps
+    /// the built-in weight.
+    pub docs_weight: Option<f64>,
+    /// Days after which a commit counts half as much for git recency;
+    /// unset keeps the built-in half-life.
+    pub recency_half_life_days: Option<f64>,
+    /// Days of history recorded in the deep index and counted for git
+    /// recency; unset keeps the built-in window.
+    pub recency_lookback_days: Option<u32>,
+    /// Share (0.0–1.0) of git recency from decayed commit counts rather
+    /// than the age of the last commit; unset keeps the built-in blend.
+    pub recency_commit_weight: Option<f64>,
+    /// Largest fraction entry points and widely imported files are
+    /// boosted by, 0 to turn it off; unset keeps the built-in weight.
+    pub centrality_weight: Option<f64>,
+}
+
+/// The `[embedding]` section: the local embedding model, and backpressure
+/// for embedding providers, so indexing a large repository stays within
+/// rate limits.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct EmbeddingConfig {
+    /// fastembed model queries are embedded with, e.g. `AllMiniLML6V2`;
+    /// needs the `embedding` feature. Unset scores without embeddings.
+    pub model: Option<String>,
+    /// Texts sent per request.
+    pub batch_size: usize,
+    /// Requests in flight at once.
+    pub concurrency: usize,
+    /// Retries of a failed request before indexing fails.
+    pub max_retries: u32,
+    /// Wait before the first retry, doubled on each further retry.
+    pub backoff_ms: u64,
+    /// Longest wait between retries.
+    pub max_backoff_ms: u64,
+}
+
+impl Default for EmbeddingConfig {
+    fn default() -> Self {
+        Self {
+            model: None,
+            batch_size: 64,
+            concurrency: 4,
+            max_retries: 5,
+            backoff_ms: 500,
+            max_backoff_ms: 30_000,
+        }
+    }
+}
+
+/// The `[remote]` section: a shared store for `topo index push` and `pull`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RemoteConfig {
+    /// Store URL: `s3://bucket/prefix`, `https://host/path`, or a
+    /// directory.
+    pub url: Option<String>,
+    /// Name of the pushed index within the store.
+    pub key: String,
+}
+
+impl Default for RemoteConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            key: "latest".to_string(),
+        }
+    }
+}
+
+/// The `[lsp]` section.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LspConfig {
+    /// Language name → server command and arguments, like
+    /// `rust = ["rust-analyzer"]`.
+    pub servers: BTreeMap<String, Vec<String>>,
+    /// How long to wait for each server response before falling back to
+    /// the built-in chunker.
+    pub timeout_ms: u64,
+}
+
+impl Default for LspConfig {
+    fn default() -> Self {
+        Self {
+            servers: BTreeMap::new(),
+            timeout_ms: 10_000,
+        }
+    }
+}
+
+/// The `[scan]` section.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ScanConfig {
+    /// Globs of files to leave out of scans and indexes, like
+    /// `"vendor/**"`, on top of `.gitignore`.
+    pub ignore: Vec<String>,
+}
+
+/// The `[query]` section.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct QueryConfig {
+    /// Preset used when `--preset` isn't given: one of [`PRESETS`].
+    pub preset: Option<String>,
+    /// Maximal Marginal Relevance lambda used when `--mmr-lambda` isn't
+    /// given, from 0.0 to 1.0; unset keeps the ranking as scored.
+    pub mmr_lambda: Option<f64>,
+    /// Score normalization used when `--normalize` isn't given: one of
+    /// [`NORMALIZATIONS`]; unset keeps raw scores.
+    pub normalize: Option<String>,
+}
+
+impl Config {
+    /// Load `.topo.toml` from the repository root.
+    ///
+    /// Returns the default configuration when the file does not exist.
+    pub fn load(repo_root: &Path) -> Result<Self, TopoError> {
+        let path = repo_root.join(CONFIG_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = fs::read_to_string(&path).map_err(|e| TopoError::io(&path, e))?;
+        Self::parse(&text).map_err(|e| e.with_path(&path))
+    }
+
+    /// Parse configuration from TOML text.
+    ///
+    /// Errors refer to [`CONFIG_FILE`]; [`Config::load`] replaces that with
+    /// the full path.
+    pub fn parse(text: &str) -> Result<Self, TopoError> {
+        let mut config: Self = toml::from_str(text).map_err(|e| TopoError::Config {
+            path: Some(CONFIG_FILE.into()),
+            message: "invalid configuration".to_string(),
+            source: Some(Box::new(e)),
+        })?;
+        if !(0..=22).contains(&config.index.compression_level) {
+            return Err(TopoError::Config {
+                path: Some(CONFIG_FILE.into()),
+                message: format!(
+                    "index.compression_level must be between 0 and 22, got {}",
+                    config.index.compression_level
+                ),
+                source: None,
+            });
+        }
+        if !(0.0..=1.0).contains(&config.budget.orientation) {
+            return Err(TopoError::Config {
+                path: Some(CONFIG_FILE.into()),
+                message: format!(
+                    "budget.orientation must be between 0.0 and 1.0, got {}",
+                    config.budget.orientation
+                ),
+                source: None,
+            });
+        }
+        if let Some(weight) = config.scoring.docs_weight
+            && !(weight.is_finite() && weight >= 0.0)
+        {
+            return Err(TopoError::Config {
+                path: Some(CONFIG_FILE.into()),
+                message: format!("scoring.docs_weight must be 0.0 or more, got {weight}"),
+                source: None,
+            });
+        }
+        if let Some(weight) = config.scoring.centrality_weight
+            && !(weight.is_finite() && weight >= 0.0)
+        {
+            return Err(TopoError::Config {
+                path: Some(CONFIG_FILE.into()),
+                message: format!("scoring.centrality_weight must be 0.0 or more, got {weight}"),
+                source: None,
+            });
+        }
+        if let Some(days) = config.scoring.recency_half_life_days
+            && !(days.is_finite() && days > 0.0)
+        {
+            return Err(TopoError::Config {
+                path: Some(CONFIG_FILE.into()),
+                message: format!("scoring.recency_half_life_days must be more than 0, got {days}"),
+                source: None,
+            });
+        }
+        if config.scoring.recency_lookback_days == Some(0) {
+            return Err(TopoError::Config {
+                path: Some(CONFIG_FILE.into()),
+                message: "scoring.recency_lookback_days must be at least 1".to_string(),
+                source: None,
+            });
+        }
+        if let Some(weight) = config.scoring.recency_commit_weight
+            && !(0.0..=1.0).contains(&weight)
+        {
+            return Err(TopoError::Config {
+                path: Some(CONFIG_FILE.into()),
+                message: format!(
+                    "scoring.recency_commit_weight must be between 0.0 and 1.0, got {weight}"
+                ),
+                source: None,
+            });
+        }
+        if config.index.prune.max_postings == Some(0) {
+            return Err(TopoError::Config {
+                path: Some(CONFIG_FILE.into()),
+                message: "index.prune.max_postings must be at least 1".to_string(),
+                source: None,
+            });
+        }
+        if config.embedding.batch_size == 0 || config.embedding.concurrency == 0 {
+            return Err(TopoError::Config {
+                path: Some(CONFIG_FILE.into()),
+                message: "embedding.batch_size and embedding.concurrency must be at least 1"
+                    .to_string(),
+                source: None,
+            });
+        }
+        if config.remote.key.is_empty() || config.remote.key.contains("..") {
+            return Err(TopoError::Config {
+                path: Some(CONFIG_FILE.into()),
+                message: format!("remote.key is not a valid key: {:?}", config.remote.key),
+                source: None,
+            });
+        }
+        if let Some(preset) = &config.query.preset
+            && !PRESETS.contains(&preset.as_str())
+        {
+            return Err(TopoError::Config {
+                path: Some(CONFIG_FILE.into()),
+                message: format!(
+                    "query.preset must be one of {}, got {preset:?}",
+                    PRESETS.join(", ")
+                ),
+                source: None,
+            });
+        }
+        if let Some(normalize) = &config.query.normalize
+            && !NORMALIZATIONS.contains(&normalize.as_str())
+        {
+            return Err(TopoError::Config {
+                path: Some(CONFIG_FILE.into()),
+                message: format!(
+                    "query.normalize must be one of {}, got {normalize:?}",
+                    NORMALIZATIONS.join(", ")
+                ),
+                source: None,
+            });
+        }
+        if let Some(lambda) = config.query.mmr_lambda
+            && !(0.0..=1.0).contains(&lambda)
+        {
+            return Err(TopoError::Config {
+                path: Some(CONFIG_FILE.into()),
+                message: format!("query.mmr_lambda must be between 0.0 and 1.0, got {lambda}"),
+                source: None,
+            });
+        }
+        for (language, command) in &config.lsp.servers {
+            if Language::from_name(language).is_none() {
+                return Err(TopoError::Config {
+                    path: Some(CONFIG_FILE.into()),
+                    message: format!("lsp.servers: unknown language {language:?}"),
+                    source: None,
+                });
+            }
+            if command.first().is_none_or(|program| program.is_empty()) {
+                return Err(TopoError::Config {
+                    path: Some(CONFIG_FILE.into()),
+                    message: format!("lsp.servers.{language} must name a command"),
+                    source: None,
+                });
+            }
+        }
+        let digest = Sha256::digest(text.as_bytes());
+        config.source_hash = Some(digest.iter().map(|b| format!("{b:02x}")).collect());
+        Ok(config)
+    }
+
+    /// Hex SHA-256 of the configuration source, or `"default"` when no
+    /// configuration file was read.
+    pub fn hash(&self) -> &str {
+        self.source_hash.as_deref().unwrap_or("default")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_config_is_default() {
+        let config = Config::parse("").unwrap();
+        assert!(config.roles.is_empty());
+    }
+
+    #[test]
+    fn parses_role_rules_in_order() {
+        let config = Config::parse(
+            r#"
+[[roles]]
+pattern = "qa/**"
+role = "test"
+
+[[roles]]
+pattern = "contracts/**"
+role = "config"
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.roles.len(), 2);
+        assert_eq!(config.roles[0].pattern, "qa/**");
+        assert_eq!(config.roles[0].role, FileRole::Test);
+        assert_eq!(config.roles[1].role, FileRole::Config);
+    }
+
+    #[test]
+    fn role_accepts_short_names() {
+        let config = Config::parse(
+            "[[roles]]\npattern = \"x/**\"\nrole = \"impl\"\n[[roles]]\npattern = \"y/**\"\nrole = \"docs\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.roles[0].role, FileRole::Implementation);
+        assert_eq!(config.roles[1].role, FileRole::Documentation);
+    }
+
+    #[test]
+    fn invalid_role_is_config_error() {
+        let err = Config::parse("[[roles]]\npattern = \"x\"\nrole = \"bogus\"\n").unwrap_err();
+        assert!(matches!(err, TopoError::Config { .. }));
+    }
+
+    #[test]
+    fn load_error_carries_full_path_and_source() {
+        let dir = std::env::temp_dir().join("topo-config-invalid-test");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(CONFIG_FILE), "roles = 3").unwrap();
+        let err = Config::load(&dir).unwrap_err();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(err.path(), Some(dir.join(CONFIG_FILE).as_path()));
+        let source = std::error::Error::source(&err).unwrap();
+        assert!(source.to_string().contains("roles"));
+    }
+
+    #[test]
+    fn load_missing_file_returns_default() {
+        let dir = std::env::temp_dir().join("topo-config-missing-test");
+        let config = Config::load(&dir).unwrap();
+        assert!(config.roles.is_empty());
+    }
+
+    #[test]
+    fn parses_language_overrides() {
+        let config = Config::parse(
+            r#"
+[languages]
+tpl = "go"
+".bazel" = "starlark"
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.languages["tpl"], Language::Go);
+        assert_eq!(config.languages[".bazel"], Language::Starlark);
+    }
+
+    #[test]
+    fn parses_synonyms() {
+        let config = Config::parse(
+            r#"
+[synonyms]
+tf = ["terraform"]
+pr = ["pull request", "merge_request"]
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.synonyms["tf"], ["terraform"]);
+        assert_eq!(config.synonyms["pr"], ["pull request", "merge_request"]);
+        assert!(Config::default().synonyms.is_empty());
+    }
+
+    #[test]
+    fn unknown_language_is_config_error() {
+        let err = Config::parse("[languages]\ntpl = \"klingon\"\n").unwrap_err();
+        assert!(matches!(err, TopoError::Config { .. }));
+    }
+
+    #[test]
+    fn parses_budget_section() {
+        let config = Config::parse("[budget]\norientation = 0.25\n").unwrap();
+        assert_eq!(config.budget.orientation, 0.25);
+        assert_eq!(Config::default().budget.orientation, 0.0);
+        let err = Config::parse("[budget]\norientation = 1.5\n").unwrap_err();
+        assert!(err.to_string().contains("budget.orientation"));
+    }
+
+    #[test]
+    fn parses_policy_section() {
+        let config = Config::parse(
+            r#"
+[policy]
+deny = ["secrets/**"]
+allow_generated = false
+max_tokens = 50000
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.policy.deny, vec!["secrets/**"]);
+        assert!(config.policy.secrets);
+        assert!(!config.policy.allow_generated);
+        assert_eq!(config.policy.max_tokens, Some(50_000));
+        assert_eq!(config.policy.max_files, None);
+    }
+
+    #[test]
+    fn parses_scan_and_query_sections() {
+        let config = Config::parse(
+            "[scan]\nignore = [\"vendor/**\", \"*.min.js\"]\n[query]\npreset = \"fast\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.scan.ignore, ["vendor/**", "*.min.js"]);
+        assert_eq!(config.query.preset.as_deref(), Some("fast"));
+        assert_eq!(Config::default().query.preset, None);
+        let err = Config::parse("[query]\npreset = \"quick\"\n").unwrap_err();
+        assert!(err.to_string().contains("query.preset"));
+        let config = Config::parse("[query]\nmmr_lambda = 0.7\n").unwrap();
+        assert_eq!(config.query.mmr_lambda, Some(0.7));
+        let err = Config::parse("[query]\nmmr_lambda = 1.5\n").unwrap_err();
+        assert!(err.to_string().contains("query.mmr_lambda"));
+        let config = Config::parse("[query]\nnormalize = \"minmax\"\n").unwrap();
+        assert_eq!(config.query.normalize.as_deref(), Some("minmax"));
+        let err = Config::parse("[query]\nnormalize = \"zscore\"\n").unwrap_err();
+        assert!(err.to_string().contains("query.normalize"));
+    }
+
+    #[test]
+    fn parses_index_compression_level() {
+        assert_eq!(
+            Config::default().index.compression_level,
+            DEFAULT_INDEX_COMPRESSION
+        );
+        let config = Config::parse("[index]\ncompression_level = 0\n").unwrap();
+        assert_eq!(config.index.compression_level, 0);
+        assert!(!config.index.lockfile_bodies);
+        let config = Config::parse("[index]\nlockfile_bodies = true\n").unwrap();
+        assert!(config.index.lockfile_bodies);
+        assert_eq!(config.index.comments, CommentMode::Keep);
+        let config = Config::parse("[index]\ncomments = \"docs\"\n").unwrap();
+        assert_eq!(config.index.comments, CommentMode::Docs);
+        assert!(!config.index.stemming);
+        assert!(
+            Config::parse("[index]\nstemming = true\n")
+                .unwrap()
+                .index
+                .stemming
+        );
+        let err = Config::parse("[index]\ncompression_level = 23\n").unwrap_err();
+        assert!(matches!(err, TopoError::Config { .. }));
+    }
+
+    #[test]
+    fn parses_index_pruning() {
+        assert_eq!(Config::default().index.prune, PruneConfig::default());
+        let config = Config::parse(
+            "[index.prune]\ngenerated_hapax = true\nmax_postings = 5000\nmax_size_mb = 256\n",
+        )
+        .unwrap();
+        assert!(config.index.prune.generated_hapax);
+        assert_eq!(config.index.prune.max_postings, Some(5000));
+        assert_eq!(config.index.prune.max_size_mb, Some(256));
+        assert_eq!(config.index.compression_level, DEFAULT_INDEX_COMPRESSION);
+        let err = Config::parse("[index.prune]\nmax_postings = 0\n").unwrap_err();
+        assert!(matches!(err, TopoError::Config { .. }));
+    }
+
+    #[test]
+    fn parses_remote() {
+        assert_eq!(Config::default().remote.url, None);
+        assert_eq!(Config::default().remote.key, "latest");
+        let config = Config::parse("[remote]\nurl = \"s3://ci-cache/topo\"\n").unwrap();
+        assert_eq!(config.remote.url.as_deref(), Some("s3://ci-cache/topo"));
+        assert_eq!(config.remote.key, "latest");
+        let err = Config::parse("[remote]\nkey = \"../main\"\n").unwrap_err();
+        assert!(matches!(err, TopoError::Config { .. }));
+    }
+
+    #[test]
+    fn parses_lsp_servers() {
+        assert!(Config::default().lsp.servers.is_empty());
+        let config = Config::parse(
+            "[lsp]\ntimeout_ms = 2000\n[lsp.servers]\nrust = [\"rust-analyzer\"]\ngo = [\"gopls\", \"serve\"]\n",
+        )
+        .unwrap();
+        assert_eq!(config.lsp.timeout_ms, 2000);
+        assert_eq!(config.lsp.servers["go"], ["gopls", "serve"]);
+        let err = Config::parse("[lsp.servers]\ncobol = [\"cobol-ls\"]\n").unwrap_err();
+        assert!(matches!(err, TopoError::Config { .. }));
+        let err = Config::parse("[lsp.servers]\nrust = []\n").unwrap_err();
+        assert!(matches!(err, TopoError::Config { .. }));
+    }
+
+    #[test]
+    fn parses_scoring_docs_weight() {
+        assert_eq!(Config::default().scoring.docs_weight, None);
+        let config = Config::parse("[scoring]\ndocs_weight = 0.5\n").unwrap();
+        assert_eq!(config.scoring.docs_weight, Some(0.5));
+        let err = Config::parse("[scoring]\ndocs_weight = -1.0\n").unwrap_err();
+        assert!(matches!(err, TopoError::Config { .. }));
+        let config = Config::parse("[scoring]\ncentrality_weight = 0\n").unwrap();
+        assert_eq!(config.scoring.centrality_weight, Some(0.0));
+        let err = Config::parse("[scoring]\ncentrality_weight = -0.1\n").unwrap_err();
+        assert!(err.to_string().contains("scoring.centrality_weight"));
+    }
+
+    #[test]
+    fn parses_scoring_recency() {
+        let config = Config::parse(
+            "[scoring]\nrecency_half_life_days = 14\nrecency_lookback_days = 180\nrecency_commit_weight = 0.25\n",
+        )
+        .unwrap();
+        assert_eq!(config.scoring.recency_half_life_days, Some(14.0));
+        assert_eq!(config.scoring.recency_lookback_days, Some(180));
+        assert_eq!(config.scoring.recency_commit_weight, Some(0.25));
+        for invalid in [
+            "recency_half_life_days = 0",
+            "recency_lookback_days = 0",
+            "recency_commit_weight = 1.5",
+        ] {
+            let err = Config::parse(&format!("[scoring]\n{invalid}\n")).unwrap_err();
+            assert!(err.to_string().contains("scoring.recency_"), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn parses_embedding_backpressure() {
+        assert_eq!(Config::default().embedding.concurrency, 4);
+        let config = Config::parse("[embedding]\nbatch_size = 16\nmax_retries = 8\n").unwrap();
+        assert_eq!(config.embedding.batch_size, 16);
+        assert_eq!(config.embedding.max_retries, 8);
+        assert_eq!(config.embedding.backoff_ms, 500);
+        assert_eq!(config.embedding.model, None);
+        let config = Config::parse("[embedding]\nmodel = \"AllMiniLML6V2\"\n").unwrap();
+        assert_eq!(config.embedding.model.as_deref(), Some("AllMiniLML6V2"));
+        let err = Config::parse("[embedding]\nconcurrency = 0\n").unwrap_err();
+        assert!(matches!(err, TopoError::Config { .. }));
+    }
+
+    #[test]
+    fn hash_identifies_config_source() {
+        assert_eq!(Config::default().hash(), "default");
+        let a = Config::parse("[languages]\ntpl = \"go\"\n").unwrap();
+        let b = Config::parse("[languages]\ntpl = \"go\"\n").unwrap();
+        let c = Config::parse("[languages]\ntpl = \"php\"\n").unwrap();
+        assert_eq!(a.hash().len(), 64);
+        assert_eq!(a.hash(), b.hash());
+        assert_ne!(a.hash(), c.hash());
+    }
+}
@@ -0,0 +1,79 @@
+use std::borrow::Cow;
+use std::fmt::Write;
+
+/// A path made safe to print in line-oriented text output.
+///
+/// Control characters, bidirectional overrides, line and paragraph
+/// separators, and invisible or zero-width characters are replaced with
+/// `\u{…}` escapes, so a crafted filename can neither start a new line in
+/// hook output nor render as something other than what it is. A literal
+/// backslash is doubled so escapes stay unambiguous. Paths needing no
+/// escapes are borrowed unchanged.
+pub fn display_path(path: &str) -> Cow<'_, str> {
+    if !path.chars().any(|c| c == '\\' || is_unsafe(c)) {
+        return Cow::Borrowed(path);
+    }
+    let mut out = String::with_capacity(path.len() + 8);
+    for c in path.chars() {
+        if c == '\\' {
+            out.push_str("\\\\");
+        } else if is_unsafe(c) {
+            let _ = write!(out, "\\u{{{:x}}}", c as u32);
+        } else {
+            out.push(c);
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// Characters that change layout or reading order, or render as nothing.
+fn is_unsafe(c: char) -> bool {
+    c.is_control()
+        || matches!(
+            c,
+            // Bidirectional marks, embeddings, overrides, and isolates
+            '\u{061c}'
+                | '\u{200e}'
+                | '\u{200f}'
+                | '\u{202a}'..='\u{202e}'
+                | '\u{2066}'..='\u{2069}'
+                // Zero-width and invisible formatting characters
+                | '\u{00ad}'
+                | '\u{034f}'
+                | '\u{115f}'
+                | '\u{1160}'
+                | '\u{180e}'
+                | '\u{200b}'..='\u{200d}'
+                | '\u{2060}'..='\u{2064}'
+                | '\u{3164}'
+                | '\u{feff}'
+                | '\u{ffa0}'
+                // Line and paragraph separators
+                | '\u{2028}'
+                | '\u{2029}'
+                // Tag characters
+                | '\u{e0000}'..='\u{e007f}'
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_path_escapes_unsafe_characters() {
+        assert!(matches!(display_path("src/auth.rs"), Cow::Borrowed(_)));
+        assert_eq!(display_path("src/日本語/ü.rs"), "src/日本語/ü.rs");
+
+        assert_eq!(display_path("a.rs\n# injected"), "a.rs\\u{a}# injected");
+        assert_eq!(display_path("evil\u{202e}sr.exe"), "evil\\u{202e}sr.exe");
+        assert_eq!(
+            display_path("a\u{200b}b\u{feff}.rs"),
+            "a\\u{200b}b\\u{feff}.rs"
+        );
+        assert_eq!(display_path("x\u{e0041}.rs"), "x\\u{e0041}.rs");
+        assert_eq!(display_path("a\u{2028}b\tc"), "a\\u{2028}b\\u{9}c");
+        // A literal backslash cannot be mistaken for an escape
+        assert_eq!(display_path("a\\u{a}.rs"), "a\\\\u{a}.rs");
+    }
+}
@@ -0,0 +1,343 @@
+use crate::error::TopoError;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// A source of embedding vectors, e.g. a local model or a remote API.
+///
+/// Each call embeds one batch. Batching, concurrency limits, and retries
+/// are left to the caller, see `topo_index::EmbedQueue`.
+pub trait Embedder: Send + Sync {
+    /// Model name, recorded as [`EmbeddingSegment::model`].
+    fn model(&self) -> &str;
+
+    /// Length of every vector.
+    fn dimension(&self) -> u32;
+
+    /// One vector per text, in order.
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, EmbedError>;
+}
+
+/// Why an [`Embedder`] call failed, and whether trying again can help.
+#[derive(Debug, thiserror::Error)]
+pub enum EmbedError {
+    /// The provider is throttling requests, and may say for how long.
+    #[error("rate limited")]
+    RateLimited { retry_after: Option<Duration> },
+
+    /// A failure worth retrying, e.g. a timeout or a server error.
+    #[error("{0}")]
+    Transient(String),
+
+    /// A failure retrying won't fix, e.g. bad credentials.
+    #[error("{0}")]
+    Fatal(String),
+}
+
+/// Storage and nearest-neighbour search for embedding vectors.
+///
+/// [`EmbeddingSegment`], saved with the deep index, is the built-in store.
+/// Implement this to keep vectors in existing infrastructure instead, e.g.
+/// LanceDB or a vector database behind an HTTP API; see
+/// `topo_index::IndexBuilder::vector_store`.
+pub trait VectorStore: Send + Sync {
+    /// Name of the model whose vectors are stored.
+    fn model(&self) -> &str;
+
+    /// Length of every vector.
+    fn dimension(&self) -> u32;
+
+    /// The vectors of `path`, if they were computed from content hashing
+    /// to `sha256`.
+    fn get(&self, path: &str, sha256: &[u8; 32]) -> Result<Option<FileEmbeddings>, TopoError>;
+
+    /// Store the vectors of `path`, replacing any it had.
+    fn upsert(&mut self, path: &str, embeddings: FileEmbeddings) -> Result<(), TopoError>;
+
+    /// Forget the vectors of `path`, if any.
+    fn remove(&mut self, path: &str) -> Result<(), TopoError>;
+
+    /// The `k` files whose vectors are most similar to `query`, best first.
+    fn nearest(&self, query: &[f32], k: usize) -> Result<Vec<VectorMatch>, TopoError>;
+}
+
+/// A file found by [`VectorStore::nearest`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct VectorMatch {
+    pub path: String,
+    /// Cosine similarity to the query in [-1.0, 1.0].
+    pub score: f64,
+}
+
+/// Embedding vectors for indexed files and their chunks, all from one
+/// model.
+///
+/// Vectors are kept with the hash of the content they were computed from,
+/// so [`EmbeddingSegment::get`] never hands out vectors for stale content
+/// and unchanged files never need embedding again.
+#[derive(Debug, Clone, PartialEq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct EmbeddingSegment {
+    /// Name of the model that produced the vectors.
+    pub model: String,
+    /// Length of every vector.
+    pub dimension: u32,
+    pub files: HashMap<String, FileEmbeddings>,
+}
+
+/// Embedding vectors of one file.
+#[derive(Debug, Clone, PartialEq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct FileEmbeddings {
+    /// SHA-256 of the content that was embedded.
+    pub sha256: [u8; 32],
+    /// Vector for the whole file.
+    pub file: Vec<f32>,
+    /// One vector per chunk, in the order of the file's index chunks.
+    pub chunks: Vec<Vec<f32>>,
+}
+
+impl EmbeddingSegment {
+    pub fn new(model: &str, dimension: u32) -> Self {
+        Self {
+            model: model.to_string(),
+            dimension,
+            files: HashMap::new(),
+        }
+    }
+
+    /// Whether vectors from `model` at `dimension` belong in this segment.
+    pub fn is_compatible(&self, model: &str, dimension: u32) -> bool {
+        self.model == model && self.dimension == dimension
+    }
+
+    /// Store the vectors of `path`, replacing any it had. Fails if a
+    /// vector's length isn't [`EmbeddingSegment::dimension`].
+    pub fn insert(&mut self, path: &str, embeddings: FileEmbeddings) -> Result<(), TopoError> {
+        let expected = self.dimension as usize;
+        let bad = std::iter::once(&embeddings.file)
+            .chain(&embeddings.chunks)
+            .find(|v| v.len() != expected);
+        if let Some(vector) = bad {
+            return Err(TopoError::index(format!(
+                "embedding for {path} has {} dimensions, {} expects {expected}",
+                vector.len(),
+                self.model
+            )));
+        }
+        self.files.insert(path.to_string(), embeddings);
+        Ok(())
+    }
+
+    /// The vectors of `path`, if they were computed from content hashing
+    /// to `sha256`.
+    pub fn get(&self, path: &str, sha256: &[u8; 32]) -> Option<&FileEmbeddings> {
+        self.files.get(path).filter(|e| &e.sha256 == sha256)
+    }
+
+    /// A segment keeping only vectors still valid for `current` paths and
+    /// content hashes. Vectors follow content that moved to another path.
+    pub fn carried_over<'a>(&self, current: impl IntoIterator<Item = (&'a str, [u8; 32])>) -> Self {
+        let by_sha: HashMap<&[u8; 32], &FileEmbeddings> =
+            self.files.values().map(|e| (&e.sha256, e)).collect();
+        let files = current
+            .into_iter()
+            .filter_map(|(path, sha256)| {
+                let embeddings = self
+                    .get(path, &sha256)
+                    .or_else(|| by_sha.get(&sha256).copied())?;
+                Some((path.to_string(), embeddings.clone()))
+            })
+            .collect();
+        Self {
+            model: self.model.clone(),
+            dimension: self.dimension,
+            files,
+        }
+    }
+
+    /// This segment with the vectors of `newer` added, replacing any for
+    /// the same paths. A `newer` segment from another model or dimension
+    /// replaces this one entirely.
+    pub fn updated_with(&self, newer: &Self) -> Self {
+        if !self.is_compatible(&newer.model, newer.dimension) {
+            return newer.clone();
+        }
+        let mut updated = self.clone();
+        updated
+            .files
+            .extend(newer.files.iter().map(|(p, e)| (p.clone(), e.clone())));
+        updated
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+impl VectorStore for EmbeddingSegment {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn dimension(&self) -> u32 {
+        self.dimension
+    }
+
+    fn get(&self, path: &str, sha256: &[u8; 32]) -> Result<Option<FileEmbeddings>, TopoError> {
+        Ok(EmbeddingSegment::get(self, path, sha256).cloned())
+    }
+
+    fn upsert(&mut self, path: &str, embeddings: FileEmbeddings) -> Result<(), TopoError> {
+        self.insert(path, embeddings)
+    }
+
+    fn remove(&mut self, path: &str) -> Result<(), TopoError> {
+        self.files.remove(path);
+        Ok(())
+    }
+
+    /// Exhaustive search over every file vector.
+    fn nearest(&self, query: &[f32], k: usize) -> Result<Vec<VectorMatch>, TopoError> {
+        let mut matches: Vec<VectorMatch> = self
+            .files
+            .iter()
+            .map(|(path, embeddings)| VectorMatch {
+                path: path.clone(),
+                score: cosine_similarity(query, &embeddings.file),
+            })
+            .collect();
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.path.cmp(&b.path)));
+        matches.truncate(k);
+        Ok(matches)
+    }
+}
+
+/// Cosine similarity of two vectors in [-1.0, 1.0]; 0.0 when their lengths
+/// differ or either is all zeros.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let (mut dot, mut norm_a, mut norm_b) = (0.0f64, 0.0f64, 0.0f64);
+    for (&x, &y) in a.iter().zip(b) {
+        let (x, y) = (f64::from(x), f64::from(y));
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn embeddings(sha: u8, file: Vec<f32>) -> FileEmbeddings {
+        FileEmbeddings {
+            sha256: [sha; 32],
+            file,
+            chunks: vec![vec![0.0, 1.0]],
+        }
+    }
+
+    #[test]
+    fn insert_checks_dimension() {
+        let mut segment = EmbeddingSegment::new("mini-lm", 2);
+        segment
+            .insert("a.rs", embeddings(1, vec![1.0, 0.0]))
+            .unwrap();
+        let err = segment
+            .insert("b.rs", embeddings(1, vec![1.0, 0.0, 0.0]))
+            .unwrap_err();
+        assert!(err.to_string().contains("3 dimensions"));
+        assert_eq!(segment.len(), 1);
+        assert!(segment.is_compatible("mini-lm", 2));
+        assert!(!segment.is_compatible("mini-lm", 384));
+    }
+
+    #[test]
+    fn get_ignores_stale_vectors() {
+        let mut segment = EmbeddingSegment::new("mini-lm", 2);
+        segment
+            .insert("a.rs", embeddings(1, vec![1.0, 0.0]))
+            .unwrap();
+        assert!(segment.get("a.rs", &[1; 32]).is_some());
+        assert!(segment.get("a.rs", &[2; 32]).is_none());
+        assert!(segment.get("b.rs", &[1; 32]).is_none());
+    }
+
+    #[test]
+    fn carried_over_drops_stale_and_follows_renames() {
+        let mut segment = EmbeddingSegment::new("mini-lm", 2);
+        segment
+            .insert("a.rs", embeddings(1, vec![1.0, 0.0]))
+            .unwrap();
+        segment
+            .insert("b.rs", embeddings(2, vec![0.0, 1.0]))
+            .unwrap();
+        segment
+            .insert("gone.rs", embeddings(3, vec![1.0, 1.0]))
+            .unwrap();
+
+        let kept = segment.carried_over([("a.rs", [1; 32]), ("b.rs", [9; 32]), ("c.rs", [3; 32])]);
+        assert_eq!(kept.len(), 2);
+        assert!(kept.get("a.rs", &[1; 32]).is_some());
+        assert!(kept.get("b.rs", &[9; 32]).is_none());
+        assert_eq!(kept.get("c.rs", &[3; 32]).unwrap().file, [1.0, 1.0]);
+        assert!(kept.is_compatible("mini-lm", 2));
+    }
+
+    #[test]
+    fn updated_with_prefers_newer_vectors() {
+        let mut old = EmbeddingSegment::new("mini-lm", 2);
+        old.insert("a.rs", embeddings(1, vec![1.0, 0.0])).unwrap();
+        old.insert("b.rs", embeddings(2, vec![0.0, 1.0])).unwrap();
+        let mut new = EmbeddingSegment::new("mini-lm", 2);
+        new.insert("b.rs", embeddings(5, vec![1.0, 1.0])).unwrap();
+
+        let updated = old.updated_with(&new);
+        assert_eq!(updated.len(), 2);
+        assert!(updated.get("a.rs", &[1; 32]).is_some());
+        assert!(updated.get("b.rs", &[2; 32]).is_none());
+        assert!(updated.get("b.rs", &[5; 32]).is_some());
+
+        let other = EmbeddingSegment::new("large", 2);
+        assert_eq!(old.updated_with(&other), other);
+    }
+
+    #[test]
+    fn segment_is_a_vector_store() {
+        let mut segment = EmbeddingSegment::new("mini-lm", 2);
+        let store: &mut dyn VectorStore = &mut segment;
+        store.upsert("x.rs", embeddings(1, vec![1.0, 0.0])).unwrap();
+        store.upsert("y.rs", embeddings(2, vec![0.0, 1.0])).unwrap();
+        store
+            .upsert("xy.rs", embeddings(3, vec![1.0, 1.0]))
+            .unwrap();
+        assert!(store.upsert("bad.rs", embeddings(4, vec![1.0])).is_err());
+
+        let nearest = store.nearest(&[1.0, 0.1], 2).unwrap();
+        let paths: Vec<&str> = nearest.iter().map(|m| m.path.as_str()).collect();
+        assert_eq!(paths, ["x.rs", "xy.rs"]);
+        assert!(nearest[0].score > nearest[1].score);
+
+        store.remove("x.rs").unwrap();
+        assert!(store.get("x.rs", &[1; 32]).unwrap().is_none());
+        assert!(store.get("y.rs", &[2; 32]).unwrap().is_some());
+        assert_eq!((store.model(), store.dimension()), ("mini-lm", 2));
+    }
+
+    #[test]
+    fn cosine_similarity_of_vectors() {
+        assert!((cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-9);
+        assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 3.0]).abs() < 1e-9);
+        assert!((cosine_similarity(&[1.0, 1.0], &[-1.0, -1.0]) + 1.0).abs() < 1e-9);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 0.0]), 0.0);
+    }
+}
@@ -1,30 +1,316 @@
+use std::path::{Path, PathBuf};
+
+/// Boxed underlying cause for errors that wrap another library's error.
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
 /// Errors that can occur in Topo operations.
+///
+/// Variants carry the offending path where one is known, and the underlying
+/// cause via [`std::error::Error::source`] rather than flattening it into
+/// the message. Each has a stable [`ErrorCode`] for programs that branch
+/// on failures.
 #[derive(Debug, thiserror::Error)]
 pub enum TopoError {
-    #[error("I/O error: {0}")]
-    Io(String),
+    #[error("I/O error{}", at(path))]
+    Io {
+        path: Option<PathBuf>,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("scan error{}: {message}", at(path))]
+    Scan {
+        path: Option<PathBuf>,
+        message: String,
+    },
+
+    #[error("index error{}: {message}", at(path))]
+    Index {
+        path: Option<PathBuf>,
+        message: String,
+        #[source]
+        source: Option<BoxError>,
+    },
+
+    #[error("index missing{}: {message}", at(path))]
+    IndexMissing {
+        path: Option<PathBuf>,
+        message: String,
+    },
+
+    #[error("index out of date{}: {message}", at(path))]
+    IndexStale {
+        path: Option<PathBuf>,
+        message: String,
+    },
+
+    #[error("index locked{}: {message}", at(path))]
+    IndexLocked {
+        path: Option<PathBuf>,
+        message: String,
+    },
+
+    #[error("score error: {message}")]
+    Score { message: String },
+
+    #[error("render error: {message}")]
+    Render {
+        message: String,
+        #[source]
+        source: Option<BoxError>,
+    },
+
+    #[error("parse error{}: {message}", at(path))]
+    Parse {
+        path: Option<PathBuf>,
+        message: String,
+    },
+
+    #[error("config error{}: {message}", at(path))]
+    Config {
+        path: Option<PathBuf>,
+        message: String,
+        #[source]
+        source: Option<BoxError>,
+    },
+
+    #[error("vcs error{}: {message}", at(path))]
+    Vcs {
+        path: Option<PathBuf>,
+        message: String,
+    },
+
+    #[error("remote error at {url}: {message}")]
+    Remote {
+        url: String,
+        message: String,
+        #[source]
+        source: Option<BoxError>,
+    },
+
+    #[error("invalid glob pattern `{pattern}`")]
+    Pattern {
+        pattern: String,
+        #[source]
+        source: globset::Error,
+    },
+}
+
+impl TopoError {
+    /// An I/O error on a known path.
+    pub fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        Self::Io {
+            path: Some(path.into()),
+            source,
+        }
+    }
+
+    /// An index error without an underlying cause.
+    pub fn index(message: impl Into<String>) -> Self {
+        Self::Index {
+            path: None,
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// A deep index, or a part of one, that an operation needs doesn't
+    /// exist.
+    pub fn index_missing(message: impl Into<String>) -> Self {
+        Self::IndexMissing {
+            path: None,
+            message: message.into(),
+        }
+    }
+
+    /// A deep index no longer matches the files it was built from.
+    pub fn index_stale(message: impl Into<String>) -> Self {
+        Self::IndexStale {
+            path: None,
+            message: message.into(),
+        }
+    }
+
+    /// Another process is writing the index and didn't finish in time.
+    pub fn index_locked(message: impl Into<String>) -> Self {
+        Self::IndexLocked {
+            path: None,
+            message: message.into(),
+        }
+    }
+
+    /// A request to a remote index store failed.
+    pub fn remote(url: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::Remote {
+            url: url.into(),
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// A configuration error without an underlying cause.
+    pub fn config(message: impl Into<String>) -> Self {
+        Self::Config {
+            path: None,
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// The file or directory the error relates to, if known.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Io { path, .. }
+            | Self::Scan { path, .. }
+            | Self::Index { path, .. }
+            | Self::IndexMissing { path, .. }
+            | Self::IndexStale { path, .. }
+            | Self::IndexLocked { path, .. }
+            | Self::Parse { path, .. }
+            | Self::Config { path, .. }
+            | Self::Vcs { path, .. } => path.as_deref(),
+            Self::Score { .. }
+            | Self::Render { .. }
+            | Self::Remote { .. }
+            | Self::Pattern { .. } => None,
+        }
+    }
+
+    /// Attach a path to variants that carry one, replacing any existing path.
+    pub fn with_path(mut self, new_path: impl Into<PathBuf>) -> Self {
+        match &mut self {
+            Self::Io { path, .. }
+            | Self::Scan { path, .. }
+            | Self::Index { path, .. }
+            | Self::IndexMissing { path, .. }
+            | Self::IndexStale { path, .. }
+            | Self::IndexLocked { path, .. }
+            | Self::Parse { path, .. }
+            | Self::Config { path, .. }
+            | Self::Vcs { path, .. } => *path = Some(new_path.into()),
+            Self::Score { .. }
+            | Self::Render { .. }
+            | Self::Remote { .. }
+            | Self::Pattern { .. } => {}
+        }
+        self
+    }
 
-    #[error("scan error: {0}")]
-    Scan(String),
+    /// The stable code of this kind of error.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Io { .. } => ErrorCode::Io,
+            Self::Scan { .. } => ErrorCode::Scan,
+            Self::Index { .. } => ErrorCode::Index,
+            Self::IndexMissing { .. } => ErrorCode::IndexMissing,
+            Self::IndexStale { .. } => ErrorCode::IndexStale,
+            Self::IndexLocked { .. } => ErrorCode::IndexLocked,
+            Self::Score { .. } => ErrorCode::Score,
+            Self::Render { .. } => ErrorCode::Render,
+            Self::Parse { .. } => ErrorCode::Parse,
+            Self::Config { .. } => ErrorCode::Config,
+            Self::Vcs { .. } => ErrorCode::Vcs,
+            Self::Remote { .. } => ErrorCode::Remote,
+            Self::Pattern { .. } => ErrorCode::Pattern,
+        }
+    }
 
-    #[error("index error: {0}")]
-    Index(String),
+    /// How to fix this kind of error, if there's a usual remedy.
+    pub fn hint(&self) -> Option<&'static str> {
+        self.code().hint()
+    }
+}
 
-    #[error("score error: {0}")]
-    Score(String),
+/// Stable identifier of a kind of [`TopoError`], like `E_INDEX_MISSING`.
+///
+/// Codes are never renamed or reused, so wrappers can match on them
+/// instead of on messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    Io,
+    Scan,
+    /// The index couldn't be read or written.
+    Index,
+    IndexMissing,
+    IndexStale,
+    /// Another process held the index's write lock for too long.
+    IndexLocked,
+    Score,
+    Render,
+    Parse,
+    Config,
+    Vcs,
+    /// A remote index store couldn't be reached or refused a request.
+    Remote,
+    Pattern,
+    /// Any error that doesn't come from a [`TopoError`].
+    Other,
+}
 
-    #[error("render error: {0}")]
-    Render(String),
+impl ErrorCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Io => "E_IO",
+            Self::Scan => "E_SCAN",
+            Self::Index => "E_INDEX",
+            Self::IndexMissing => "E_INDEX_MISSING",
+            Self::IndexStale => "E_INDEX_STALE",
+            Self::IndexLocked => "E_INDEX_LOCKED",
+            Self::Score => "E_SCORE",
+            Self::Render => "E_RENDER",
+            Self::Parse => "E_PARSE",
+            Self::Config => "E_CONFIG",
+            Self::Vcs => "E_VCS",
+            Self::Remote => "E_REMOTE",
+            Self::Pattern => "E_PATTERN",
+            Self::Other => "E_OTHER",
+        }
+    }
 
-    #[error("parse error: {0}")]
-    Parse(String),
+    /// How to fix errors with this code, if there's a usual remedy.
+    pub fn hint(self) -> Option<&'static str> {
+        match self {
+            Self::Io => Some("Check that the path exists and is readable."),
+            Self::Scan => Some("Check that --root points at a readable directory."),
+            Self::Index => Some(
+                "The index may be damaged or from another version. Rebuild it with `topo index --deep --force`.",
+            ),
+            Self::IndexMissing => Some("Build the deep index with `topo index --deep`."),
+            Self::IndexStale => Some(
+                "Fix the index with `topo index --verify --repair`, or update it with `topo index --deep`.",
+            ),
+            Self::IndexLocked => {
+                Some("Another topo process is updating the index. Try again once it has finished.")
+            }
+            Self::Config => Some("Check .topo.toml against the configuration reference."),
+            Self::Vcs => {
+                Some("Check that git is installed and the root is inside a Git repository.")
+            }
+            Self::Remote => Some(
+                "Check the remote URL and its credentials (TOPO_REMOTE_TOKEN, or AWS_* variables for S3).",
+            ),
+            Self::Pattern => Some("Check the glob patterns in .topo.toml."),
+            Self::Score | Self::Render | Self::Parse | Self::Other => None,
+        }
+    }
+}
 
-    #[error("config error: {0}")]
-    Config(String),
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 impl From<std::io::Error> for TopoError {
-    fn from(err: std::io::Error) -> Self {
-        Self::Io(err.to_string())
+    fn from(source: std::io::Error) -> Self {
+        Self::Io { path: None, source }
+    }
+}
+
+/// Format an optional path as a ` at <path>` message suffix.
+fn at(path: &Option<PathBuf>) -> String {
+    match path {
+        Some(p) => format!(" at {}", p.display()),
+        None => String::new(),
     }
 }
@@ -0,0 +1,171 @@
+use crate::TopoError;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Where relevance judgments are recorded, relative to the repository root.
+pub const FEEDBACK_FILE: &str = ".topo/feedback.jsonl";
+
+/// Where the weights fitted to them are kept, relative to the repository
+/// root.
+pub const WEIGHTS_FILE: &str = ".topo/weights.json";
+
+/// Files judged relevant or not to a query with `topo feedback`.
+///
+/// Recorded one JSON object per line, oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Judgment {
+    pub query: String,
+    /// Repository-relative paths that should rank high.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub good: Vec<String>,
+    /// Repository-relative paths that should rank low.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bad: Vec<String>,
+}
+
+impl Judgment {
+    /// Path of the feedback file of the repository at `root`.
+    pub fn path(root: &Path) -> PathBuf {
+        root.join(FEEDBACK_FILE)
+    }
+
+    /// Every judgment recorded for the repository at `root`, oldest first;
+    /// none if there is no feedback file.
+    pub fn load_all(root: &Path) -> Result<Vec<Self>, TopoError> {
+        let path = Self::path(root);
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(TopoError::io(&path, e)),
+        };
+        text.lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str(line).map_err(|e| TopoError::Parse {
+                    path: Some(path.clone()),
+                    message: format!("line {}: {e}", i + 1),
+                })
+            })
+            .collect()
+    }
+
+    /// Append this judgment to the feedback file of the repository at
+    /// `root`.
+    pub fn record(&self, root: &Path) -> Result<(), TopoError> {
+        let path = Self::path(root);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| TopoError::io(dir, e))?;
+        }
+        let mut line = serde_json::to_string(self).map_err(|e| TopoError::Parse {
+            path: Some(path.clone()),
+            message: e.to_string(),
+        })?;
+        line.push('\n');
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(|e| TopoError::io(&path, e))
+    }
+}
+
+/// Scoring weights of a repository fitted to its recorded judgments, used
+/// in place of the preset's.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WeightProfile {
+    /// Weight of BM25F in the hybrid score.
+    pub bm25f: f64,
+    /// Weight of the path heuristic in the hybrid score.
+    pub heuristic: f64,
+    /// Weight of the PageRank ranking in rank fusion, relative to the
+    /// hybrid ranking's 1.0.
+    pub pagerank: f64,
+    /// Judgments the weights were fitted to.
+    pub judgments: usize,
+    /// Mean NDCG@10 of the judged queries with these weights.
+    pub ndcg: f64,
+}
+
+impl WeightProfile {
+    /// Path of the weight profile of the repository at `root`.
+    pub fn path(root: &Path) -> PathBuf {
+        root.join(WEIGHTS_FILE)
+    }
+
+    /// The weight profile of the repository at `root`, if one was fitted.
+    pub fn load(root: &Path) -> Result<Option<Self>, TopoError> {
+        let path = Self::path(root);
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(TopoError::io(&path, e)),
+        };
+        serde_json::from_str(&text)
+            .map(Some)
+            .map_err(|e| TopoError::Parse {
+                path: Some(path),
+                message: e.to_string(),
+            })
+    }
+
+    /// Write the weight profile of the repository at `root`.
+    pub fn save(&self, root: &Path) -> Result<(), TopoError> {
+        let path = Self::path(root);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| TopoError::io(dir, e))?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| TopoError::Parse {
+            path: Some(path.clone()),
+            message: e.to_string(),
+        })?;
+        fs::write(&path, json).map_err(|e| TopoError::io(&path, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn judgments_append_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(Judgment::load_all(dir.path()).unwrap().is_empty());
+
+        let first = Judgment {
+            query: "auth".to_string(),
+            good: vec!["src/auth/mod.rs".to_string()],
+            bad: vec!["src/legacy/old.rs".to_string()],
+        };
+        let second = Judgment {
+            query: "db pool".to_string(),
+            good: vec!["src/db.rs".to_string()],
+            ..Default::default()
+        };
+        first.record(dir.path()).unwrap();
+        second.record(dir.path()).unwrap();
+        assert_eq!(Judgment::load_all(dir.path()).unwrap(), [first, second]);
+
+        fs::write(Judgment::path(dir.path()), "{\"query\":\"x\"}\nnot json\n").unwrap();
+        let err = Judgment::load_all(dir.path()).unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+    }
+
+    #[test]
+    fn weight_profiles_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(WeightProfile::load(dir.path()).unwrap(), None);
+        let profile = WeightProfile {
+            bm25f: 0.7,
+            heuristic: 0.3,
+            pagerank: 0.5,
+            judgments: 3,
+            ndcg: 0.9,
+        };
+        profile.save(dir.path()).unwrap();
+        assert_eq!(WeightProfile::load(dir.path()).unwrap(), Some(profile));
+    }
+}
@@ -1,17 +1,45 @@
 //! Topo core domain types, traits, and errors.
 
+mod classify;
+mod config;
+mod display;
+mod embedding;
 mod error;
+mod feedback;
+mod package;
+mod pins;
+mod policy;
+mod trigram;
 mod types;
 
-pub use error::TopoError;
+pub use classify::{IgnoreRules, LanguageDetector, RoleClassifier, is_entry_point};
+pub use config::{
+    BudgetConfig, CONFIG_FILE, CommentMode, Config, DEFAULT_INDEX_COMPRESSION, EmbeddingConfig,
+    IndexConfig, LspConfig, NORMALIZATIONS, PRESETS, PruneConfig, QueryConfig, RemoteConfig,
+    RoleRule, ScanConfig, ScoringConfig,
+};
+pub use display::display_path;
+pub use embedding::{
+    EmbedError, Embedder, EmbeddingSegment, FileEmbeddings, VectorMatch, VectorStore,
+    cosine_similarity,
+};
+pub use error::{BoxError, ErrorCode, TopoError};
+pub use feedback::{FEEDBACK_FILE, Judgment, WEIGHTS_FILE, WeightProfile};
+pub use package::{Package, PackageKind, PackageMap, is_lockfile, is_package_manifest};
+pub use pins::{PINS_FILE, Pins};
+pub use policy::{Policy, PolicyRule, PolicyViolation, Severity};
+pub use trigram::{DEFAULT_MIN_SIMILARITY, FuzzyMatch, TrigramIndex, trigrams};
 pub use types::{
-    Bundle, Chunk, ChunkKind, DeepIndex, FileEntry, FileInfo, FileRole, Language, ScoredFile,
-    SignalBreakdown, TermFreqs, TokenBudget,
+    BlameSummary, Bundle, Chunk, ChunkKind, ChunkTerms, ContextWindow, DeepIndex, DuplicateGroup,
+    FileActivity, FileCommit, FileEntry, FileInfo, FileRole, GitActivity, IndexMeta, Language,
+    Ownership, PruneReport, ScoredFile, SignalBreakdown, TOKEN_ESTIMATOR, TermFreqs, TokenBudget,
+    Tombstone, estimate_tokens,
 };
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
     use std::path::Path;
 
     // --- Language::from_extension ---
@@ -38,6 +66,70 @@ mod tests {
         assert_eq!(Language::from_extension("mts"), Language::TypeScript);
     }
 
+    #[test]
+    fn language_from_extension_new_languages() {
+        assert_eq!(Language::from_extension("zig"), Language::Zig);
+        assert_eq!(Language::from_extension("nix"), Language::Nix);
+        assert_eq!(Language::from_extension("tf"), Language::Terraform);
+        assert_eq!(Language::from_extension("bzl"), Language::Starlark);
+        assert_eq!(Language::from_extension("sol"), Language::Solidity);
+        assert_eq!(Language::from_extension("sql"), Language::Sql);
+        assert!(!Language::Sql.is_linguist_detectable());
+        assert_eq!(Language::from_extension("proto"), Language::Protobuf);
+        assert_eq!(Language::from_extension("gql"), Language::GraphQl);
+        assert_eq!(
+            Language::from_linguist_name("Protocol Buffer"),
+            Some(Language::Protobuf)
+        );
+        assert_eq!(Language::from_extension("vue"), Language::Vue);
+        assert!(Language::Svelte.is_linguist_detectable());
+        assert_eq!(Language::from_extension("cs"), Language::CSharp);
+        assert_eq!(Language::from_linguist_name("C#"), Some(Language::CSharp));
+        assert_eq!(Language::from_extension("dart"), Language::Dart);
+    }
+
+    #[test]
+    fn language_name_roundtrip() {
+        for lang in Language::all() {
+            assert_eq!(Language::from_name(lang.as_str()), Some(lang));
+            let json = serde_json::to_string(&lang).unwrap();
+            assert_eq!(json, format!("\"{}\"", lang.as_str()));
+        }
+        assert_eq!(Language::from_name("other"), Some(Language::Other));
+        assert_eq!(Language::from_name("klingon"), None);
+    }
+
+    #[test]
+    fn linguist_names() {
+        for lang in Language::all() {
+            assert_eq!(
+                Language::from_linguist_name(lang.linguist_name()),
+                Some(lang)
+            );
+        }
+        assert_eq!(Language::Cpp.linguist_name(), "C++");
+        assert_eq!(Language::from_linguist_name("c++"), Some(Language::Cpp));
+        assert_eq!(Language::from_linguist_name("cpp"), Some(Language::Cpp));
+        assert_eq!(
+            Language::from_linguist_name("hcl"),
+            Some(Language::Terraform)
+        );
+        assert_eq!(Language::from_linguist_name("Objective-C"), None);
+        assert!(Language::Css.is_linguist_detectable());
+        assert!(!Language::Json.is_linguist_detectable());
+        assert!(!Language::Markdown.is_linguist_detectable());
+    }
+
+    #[test]
+    fn language_table_extensions_are_unique() {
+        let mut seen = std::collections::HashSet::new();
+        for lang in Language::all() {
+            for ext in lang.extensions() {
+                assert!(seen.insert(*ext), "duplicate extension {ext}");
+            }
+        }
+    }
+
     // --- Language::from_path ---
 
     #[test]
@@ -265,6 +357,31 @@ mod tests {
         assert_eq!(FileRole::from_path(Path::new("Makefile")), FileRole::Build);
     }
 
+    #[test]
+    fn role_build_lockfiles() {
+        for path in [
+            "Cargo.lock",
+            "web/package-lock.json",
+            "yarn.lock",
+            "poetry.lock",
+            "go.sum",
+            "tests/fixtures/Gemfile.lock",
+        ] {
+            assert!(is_lockfile(path.rsplit('/').next().unwrap()), "{path}");
+            assert_eq!(
+                FileRole::from_path(Path::new(path)),
+                FileRole::Build,
+                "{path}"
+            );
+        }
+        // Vendored lockfiles are still vendored code
+        assert_eq!(
+            FileRole::from_path(Path::new("vendor/lib/go.sum")),
+            FileRole::Generated
+        );
+        assert!(!is_lockfile("lock.rs"));
+    }
+
     #[test]
     fn role_build_dockerfile() {
         assert_eq!(
@@ -319,6 +436,7 @@ mod tests {
             language: Language::Rust,
             role: FileRole::Implementation,
             sha256: [0u8; 32],
+            package: None,
         };
         assert_eq!(info.estimated_tokens(), 100);
     }
@@ -331,6 +449,7 @@ mod tests {
             fingerprint: "test".to_string(),
             root: std::path::PathBuf::from("/tmp"),
             files: vec![],
+            packages: vec![],
             scanned_at: std::time::SystemTime::now(),
         };
         assert!(bundle.is_empty());
@@ -350,6 +469,7 @@ mod tests {
                     language: Language::Rust,
                     role: FileRole::Implementation,
                     sha256: [0u8; 32],
+                    package: None,
                 },
                 FileInfo {
                     path: "b.rs".to_string(),
@@ -357,8 +477,10 @@ mod tests {
                     language: Language::Rust,
                     role: FileRole::Implementation,
                     sha256: [0u8; 32],
+                    package: None,
                 },
             ],
+            packages: vec![],
             scanned_at: std::time::SystemTime::now(),
         };
         assert!(!bundle.is_empty());
@@ -377,6 +499,7 @@ mod tests {
             tokens: 100,
             language: Language::Rust,
             role: FileRole::Implementation,
+            preview: None,
         };
         let b = ScoredFile {
             path: "b.rs".to_string(),
@@ -385,6 +508,7 @@ mod tests {
             tokens: 200,
             language: Language::Rust,
             role: FileRole::Implementation,
+            preview: None,
         };
         assert!(a.score > b.score);
     }
@@ -393,7 +517,7 @@ mod tests {
 
     #[test]
     fn topo_error_display() {
-        let err = TopoError::Io("test".to_string());
+        let err = TopoError::index("test");
         assert!(err.to_string().contains("test"));
     }
 
@@ -401,7 +525,52 @@ mod tests {
     fn topo_error_from_io() {
         let io_err = std::io::Error::new(std::io::ErrorKind::NotFound, "not found");
         let topo_err: TopoError = io_err.into();
-        assert!(topo_err.to_string().contains("not found"));
+        let source = std::error::Error::source(&topo_err).unwrap();
+        assert!(source.to_string().contains("not found"));
+        assert!(topo_err.path().is_none());
+    }
+
+    #[test]
+    fn topo_error_io_carries_path() {
+        let io_err = std::io::Error::new(std::io::ErrorKind::NotFound, "not found");
+        let err = TopoError::io("src/main.rs", io_err);
+        assert_eq!(err.path(), Some(Path::new("src/main.rs")));
+        assert_eq!(err.to_string(), "I/O error at src/main.rs");
+    }
+
+    #[test]
+    fn topo_error_with_path_replaces_path() {
+        let err = TopoError::config("bad").with_path("/repo/.topo.toml");
+        assert_eq!(err.path(), Some(Path::new("/repo/.topo.toml")));
+        assert!(err.to_string().contains("/repo/.topo.toml"));
+    }
+
+    #[test]
+    fn topo_error_codes_and_hints() {
+        let err = TopoError::index_missing("--meta filters need a deep index");
+        assert_eq!(err.code(), ErrorCode::IndexMissing);
+        assert_eq!(err.code().as_str(), "E_INDEX_MISSING");
+        assert!(err.hint().unwrap().contains("topo index --deep"));
+        assert_eq!(
+            err.to_string(),
+            "index missing: --meta filters need a deep index"
+        );
+
+        let err = TopoError::index_stale("2 problems").with_path("/repo");
+        assert_eq!(err.code().to_string(), "E_INDEX_STALE");
+        assert_eq!(err.path(), Some(Path::new("/repo")));
+        assert_eq!(TopoError::index("bad").code(), ErrorCode::Index);
+        let err = TopoError::index_locked("busy").with_path("/repo/.topo/index");
+        assert_eq!(err.code().as_str(), "E_INDEX_LOCKED");
+        assert_eq!(err.to_string(), "index locked at /repo/.topo/index: busy");
+        let err = TopoError::remote("s3://ci/topo", "403 Forbidden").with_path("/repo");
+        assert_eq!(err.code().as_str(), "E_REMOTE");
+        assert_eq!(err.path(), None);
+        assert_eq!(
+            err.to_string(),
+            "remote error at s3://ci/topo: 403 Forbidden"
+        );
+        assert!(ErrorCode::Other.hint().is_none());
     }
 
     // --- ChunkKind ---
@@ -412,6 +581,54 @@ mod tests {
         assert_eq!(format!("{kind:?}"), "Function");
     }
 
+    #[test]
+    fn chunk_kind_as_str() {
+        assert_eq!(ChunkKind::Function.as_str(), "function");
+        assert_eq!(ChunkKind::Import.as_str(), "import");
+        assert_eq!(ChunkKind::Method.as_str(), "method");
+        assert_eq!(ChunkKind::Trait.as_str(), "trait");
+        assert_eq!(ChunkKind::TestCase.as_str(), "test");
+    }
+
+    #[test]
+    fn chunk_kinds_serialize_as_str() {
+        for kind in [
+            ChunkKind::Function,
+            ChunkKind::Enum,
+            ChunkKind::Constant,
+            ChunkKind::Macro,
+            ChunkKind::TestCase,
+        ] {
+            let json = serde_json::to_string(&kind).unwrap();
+            assert_eq!(json, format!("\"{}\"", kind.as_str()));
+            assert_eq!(serde_json::from_str::<ChunkKind>(&json).unwrap(), kind);
+        }
+        assert!(ChunkKind::Trait.is_type() && ChunkKind::Trait.is_declaration());
+        assert!(!ChunkKind::Constant.is_type() && ChunkKind::Constant.is_declaration());
+        assert!(!ChunkKind::Import.is_declaration() && !ChunkKind::Key.is_declaration());
+    }
+
+    #[test]
+    fn chunk_qualified_names() {
+        let mut chunk = Chunk {
+            kind: ChunkKind::Function,
+            name: "check".to_string(),
+            start_line: 1,
+            end_line: 1,
+            content: String::new(),
+            target: None,
+            doc: None,
+            parent: None,
+            signature: None,
+            tokens: 0,
+            exported: false,
+        };
+        assert_eq!(chunk.qualified_name(), "check");
+        chunk.kind = ChunkKind::Method;
+        chunk.parent = Some("Auth".to_string());
+        assert_eq!(chunk.qualified_name(), "Auth::check");
+    }
+
     // --- TokenBudget ---
 
     fn make_scored(path: &str, tokens: u64, score: f64) -> ScoredFile {
@@ -422,6 +639,7 @@ mod tests {
             tokens,
             language: Language::Rust,
             role: FileRole::Implementation,
+            preview: None,
         }
     }
 
@@ -487,4 +705,181 @@ mod tests {
         };
         assert!(budget.enforce(&[]).is_empty());
     }
+
+    // --- ContextWindow ---
+
+    #[test]
+    fn context_windows_merge_overlaps() {
+        let content = (1..=12)
+            .map(|n| format!("line {n}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let windows = ContextWindow::around(&content, &[6, 1, 3, 12], 1);
+        let spans: Vec<(u32, u32)> = windows.iter().map(|w| (w.start_line, w.end_line)).collect();
+        assert_eq!(spans, [(1, 7), (11, 12)]);
+        assert_eq!(windows[1].text, "line 11\nline 12");
+    }
+
+    #[test]
+    fn context_windows_without_context_are_match_lines() {
+        let windows = ContextWindow::around("a\nb\nc\n", &[2, 9], 0);
+        assert_eq!(
+            windows,
+            [ContextWindow {
+                start_line: 2,
+                end_line: 2,
+                text: "b".to_string(),
+                kinds: Vec::new(),
+                duplicated_in: None,
+            }]
+        );
+        assert!(ContextWindow::around("a\nb", &[], 3).is_empty());
+    }
+
+    #[test]
+    fn duplicate_groups_count_files() {
+        let group = DuplicateGroup {
+            hash: 7,
+            copies: vec![
+                ("a.rs".to_string(), 1),
+                ("a.rs".to_string(), 4),
+                ("vendor/a.rs".to_string(), 1),
+            ],
+        };
+        assert_eq!(group.files(), 2);
+        let mut index = index_with_dependencies(&[]);
+        index.duplicate_chunks = vec![group];
+        assert_eq!(index.duplicate_files(7), 2);
+        assert_eq!(index.duplicate_files(8), 1);
+    }
+
+    #[test]
+    fn budget_fill_skips_files_that_do_not_fit() {
+        let budget = TokenBudget {
+            max_bytes: None,
+            max_tokens: Some(100),
+        };
+        let files = vec![
+            make_scored("a.rs", 60, 0.9),
+            make_scored("big.rs", 500, 0.8),
+            make_scored("b.rs", 40, 0.7),
+            make_scored("c.rs", 1, 0.6),
+        ];
+        let paths: Vec<String> = budget.fill(&files).into_iter().map(|f| f.path).collect();
+        assert_eq!(paths, ["a.rs", "b.rs"]);
+    }
+
+    #[test]
+    fn budget_share_and_remaining() {
+        let budget = TokenBudget {
+            max_bytes: Some(1000),
+            max_tokens: None,
+        };
+        let share = budget.share(0.2);
+        assert_eq!(share.max_bytes, Some(200));
+        assert_eq!(share.max_tokens, None);
+        assert_eq!(budget.remaining(50).max_bytes, Some(800));
+        assert_eq!(budget.remaining(500).max_bytes, Some(0));
+        assert!(share.fits(50));
+        assert!(!share.fits(51));
+    }
+
+    // --- FileActivity ---
+
+    #[test]
+    fn file_activity_summaries() {
+        let activity = FileActivity {
+            commits: vec![
+                FileCommit {
+                    timestamp: 300,
+                    churn: 4,
+                    author: 0,
+                },
+                FileCommit {
+                    timestamp: 100,
+                    churn: 10,
+                    author: 1,
+                },
+            ],
+        };
+        assert_eq!(activity.commit_count(), 2);
+        assert_eq!(activity.last_modified(), Some(300));
+        assert_eq!(activity.churn(), 14);
+        assert_eq!(FileActivity::default().last_modified(), None);
+    }
+
+    // --- DeepIndex ---
+
+    fn index_with_dependencies(edges: &[(&str, &[&str])]) -> DeepIndex {
+        DeepIndex {
+            version: 0,
+            files: HashMap::new(),
+            avg_doc_length: 0.0,
+            total_docs: 0,
+            doc_frequencies: HashMap::new(),
+            pagerank_scores: HashMap::new(),
+            tombstones: Vec::new(),
+            trigrams: None,
+            chunk_postings: false,
+            stemmed: false,
+            embeddings: None,
+            git_activity: None,
+            meta: None,
+            duplicate_chunks: Vec::new(),
+            dependencies: edges
+                .iter()
+                .map(|(from, to)| (from.to_string(), to.iter().map(|t| t.to_string()).collect()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn deep_index_dependency_lookups() {
+        let index = index_with_dependencies(&[
+            ("src/main.rs", &["src/auth.rs", "src/db.rs"]),
+            ("src/auth.rs", &["src/db.rs"]),
+        ]);
+        assert_eq!(index.imports("src/main.rs"), ["src/auth.rs", "src/db.rs"]);
+        assert!(index.imports("src/db.rs").is_empty());
+        assert_eq!(
+            index.imported_by("src/db.rs"),
+            vec!["src/auth.rs", "src/main.rs"]
+        );
+        assert_eq!(
+            index.related("src/auth.rs"),
+            vec!["src/db.rs", "src/main.rs"]
+        );
+    }
+
+    #[test]
+    fn deep_index_metadata_lookup() {
+        let mut index = index_with_dependencies(&[]);
+        for (path, tier) in [
+            ("src/b.rs", "gold"),
+            ("src/a.rs", "gold"),
+            ("src/c.rs", "bronze"),
+        ] {
+            index.files.insert(
+                path.to_string(),
+                FileEntry {
+                    sha256: [0; 32],
+                    size: 0,
+                    chunks: Vec::new(),
+                    term_frequencies: HashMap::new(),
+                    doc_length: 0,
+                    metadata: [("slo_tier".to_string(), tier.to_string())].into(),
+                    chunk_terms: None,
+                    exports: Vec::new(),
+                    chunk_hashes: Vec::new(),
+                },
+            );
+        }
+        assert_eq!(
+            index.files_with_metadata("slo_tier", "gold"),
+            vec!["src/a.rs", "src/b.rs"]
+        );
+        assert!(index.files_with_metadata("owner", "gold").is_empty());
+        assert!(index.files["src/a.rs"].has_metadata("slo_tier", "gold"));
+        assert!(!index.files["src/a.rs"].has_metadata("slo_tier", "silver"));
+    }
 }
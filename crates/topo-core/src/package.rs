@@ -0,0 +1,259 @@
+use serde::{Deserialize, Serialize};
+
+/// Ecosystem a package manifest belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageKind {
+    /// `Cargo.toml` with a `[package]` table (Cargo workspaces)
+    Cargo,
+    /// `package.json` with a `name` (npm, pnpm, yarn, turbo workspaces)
+    Npm,
+    /// `go.mod` module (go.work workspaces)
+    Go,
+    /// Nx `project.json`
+    Nx,
+}
+
+impl PackageKind {
+    /// Manifest filename for this ecosystem.
+    pub fn manifest(&self) -> &'static str {
+        match self {
+            Self::Cargo => "Cargo.toml",
+            Self::Npm => "package.json",
+            Self::Go => "go.mod",
+            Self::Nx => "project.json",
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cargo => "cargo",
+            Self::Npm => "npm",
+            Self::Go => "go",
+            Self::Nx => "nx",
+        }
+    }
+
+    fn from_manifest(filename: &str) -> Option<Self> {
+        [Self::Nx, Self::Cargo, Self::Npm, Self::Go]
+            .into_iter()
+            .find(|kind| kind.manifest() == filename)
+    }
+}
+
+/// A package within a (possibly monorepo) repository.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Package {
+    pub name: String,
+    /// Repo-relative directory containing the manifest; empty for the root.
+    pub root: String,
+    pub kind: PackageKind,
+}
+
+impl Package {
+    /// Parse a package from a manifest at a repo-relative `path`.
+    ///
+    /// Returns `None` for files that aren't manifests, manifests without a
+    /// name (e.g. a virtual Cargo workspace), and unparseable contents.
+    pub fn from_manifest(path: &str, contents: &str) -> Option<Self> {
+        let (root, filename) = match path.rsplit_once('/') {
+            Some((dir, name)) => (dir, name),
+            None => ("", path),
+        };
+        let kind = PackageKind::from_manifest(filename)?;
+        let name = match kind {
+            PackageKind::Cargo => {
+                let value: toml::Table = toml::from_str(contents).ok()?;
+                value.get("package")?.get("name")?.as_str()?.to_string()
+            }
+            PackageKind::Npm | PackageKind::Nx => {
+                let value: serde_json::Value = serde_json::from_str(contents).ok()?;
+                value.get("name")?.as_str()?.to_string()
+            }
+            PackageKind::Go => contents.lines().find_map(|line| {
+                let module = line.trim().strip_prefix("module")?;
+                module
+                    .starts_with(char::is_whitespace)
+                    .then(|| module.trim().trim_matches('"').to_string())
+            })?,
+        };
+        if name.is_empty() {
+            return None;
+        }
+        Some(Self {
+            name,
+            root: root.to_string(),
+            kind,
+        })
+    }
+
+    /// Whether a repo-relative path lies inside this package.
+    pub fn contains(&self, path: &str) -> bool {
+        self.root.is_empty()
+            || path
+                .strip_prefix(self.root.as_str())
+                .is_some_and(|rest| rest.starts_with('/'))
+    }
+}
+
+/// Whether a filename is a manifest recognised by [`Package::from_manifest`].
+pub fn is_package_manifest(filename: &str) -> bool {
+    PackageKind::from_manifest(filename).is_some()
+}
+
+/// Dependency lockfiles: enormous, machine-written, and only useful to
+/// search by filename or for a specific dependency.
+const LOCKFILES: &[&str] = &[
+    "Cargo.lock",
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "bun.lock",
+    "poetry.lock",
+    "Pipfile.lock",
+    "uv.lock",
+    "go.sum",
+    "Gemfile.lock",
+    "composer.lock",
+    "flake.lock",
+];
+
+/// Whether a filename is a dependency lockfile like `Cargo.lock` or
+/// `go.sum`.
+pub fn is_lockfile(filename: &str) -> bool {
+    LOCKFILES.contains(&filename)
+}
+
+/// Maps paths to the innermost enclosing package.
+#[derive(Debug, Clone, Default)]
+pub struct PackageMap {
+    /// Deepest roots first, so the first match is the innermost package.
+    packages: Vec<Package>,
+}
+
+impl PackageMap {
+    /// Build a map from detected packages.
+    ///
+    /// When two manifests share a directory (e.g. an Nx `project.json` next
+    /// to a `package.json`), the first one given wins.
+    pub fn new(packages: Vec<Package>) -> Self {
+        let mut deduped: Vec<Package> = Vec::with_capacity(packages.len());
+        for package in packages {
+            if !deduped.iter().any(|p| p.root == package.root) {
+                deduped.push(package);
+            }
+        }
+        deduped.sort_by(|a, b| {
+            let depth = |p: &Package| p.root.split('/').filter(|s| !s.is_empty()).count();
+            depth(b).cmp(&depth(a)).then_with(|| a.root.cmp(&b.root))
+        });
+        Self { packages: deduped }
+    }
+
+    /// The innermost package containing `path`.
+    pub fn find(&self, path: &str) -> Option<&Package> {
+        self.packages.iter().find(|p| p.contains(path))
+    }
+
+    pub fn packages(&self) -> &[Package] {
+        &self.packages
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.packages.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, root: &str) -> Package {
+        Package {
+            name: name.to_string(),
+            root: root.to_string(),
+            kind: PackageKind::Cargo,
+        }
+    }
+
+    #[test]
+    fn parses_cargo_package() {
+        let pkg = Package::from_manifest(
+            "crates/core/Cargo.toml",
+            "[package]\nname = \"demo-core\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        assert_eq!(pkg.name, "demo-core");
+        assert_eq!(pkg.root, "crates/core");
+        assert_eq!(pkg.kind, PackageKind::Cargo);
+    }
+
+    #[test]
+    fn virtual_cargo_workspace_is_not_a_package() {
+        let manifest = "[workspace]\nmembers = [\"crates/*\"]\n";
+        assert!(Package::from_manifest("Cargo.toml", manifest).is_none());
+    }
+
+    #[test]
+    fn parses_package_json_and_project_json() {
+        let npm =
+            Package::from_manifest("packages/ui/package.json", r#"{"name": "@acme/ui"}"#).unwrap();
+        assert_eq!(npm.name, "@acme/ui");
+        assert_eq!(npm.kind, PackageKind::Npm);
+
+        let nx = Package::from_manifest("apps/web/project.json", r#"{"name": "web"}"#).unwrap();
+        assert_eq!(nx.kind, PackageKind::Nx);
+    }
+
+    #[test]
+    fn parses_go_module() {
+        let pkg = Package::from_manifest(
+            "svc/api/go.mod",
+            "// comment\nmodule example.com/svc/api\n\ngo 1.22\n",
+        )
+        .unwrap();
+        assert_eq!(pkg.name, "example.com/svc/api");
+        assert_eq!(pkg.root, "svc/api");
+    }
+
+    #[test]
+    fn ignores_other_files_and_bad_contents() {
+        assert!(Package::from_manifest("src/main.rs", "fn main() {}").is_none());
+        assert!(Package::from_manifest("package.json", "not json").is_none());
+        assert!(Package::from_manifest("package.json", r#"{"private": true}"#).is_none());
+    }
+
+    #[test]
+    fn contains_respects_directory_boundaries() {
+        let pkg = package("core", "crates/core");
+        assert!(pkg.contains("crates/core/src/lib.rs"));
+        assert!(!pkg.contains("crates/core-extra/src/lib.rs"));
+        assert!(package("root", "").contains("anything.rs"));
+    }
+
+    #[test]
+    fn map_finds_innermost_package() {
+        let map = PackageMap::new(vec![
+            package("root", ""),
+            package("core", "crates/core"),
+            package("macros", "crates/core/macros"),
+        ]);
+        assert_eq!(map.find("crates/core/src/lib.rs").unwrap().name, "core");
+        assert_eq!(
+            map.find("crates/core/macros/src/lib.rs").unwrap().name,
+            "macros"
+        );
+        assert_eq!(map.find("README.md").unwrap().name, "root");
+    }
+
+    #[test]
+    fn map_keeps_first_package_per_root() {
+        let map = PackageMap::new(vec![
+            package("nx-name", "apps/web"),
+            package("npm", "apps/web"),
+        ]);
+        assert_eq!(map.packages().len(), 1);
+        assert_eq!(map.find("apps/web/index.ts").unwrap().name, "nx-name");
+    }
+}
@@ -0,0 +1,130 @@
+use crate::TopoError;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where pinned files are listed, relative to the repository root.
+pub const PINS_FILE: &str = ".topo/pins";
+
+/// Files pinned with `topo pin`: ranked above everything else and listed
+/// first, whatever the query.
+///
+/// Stored as one repository-relative path per line; blank lines and lines
+/// starting with `#` are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pins {
+    paths: BTreeSet<String>,
+}
+
+impl Pins {
+    /// Path of the pins file of the repository at `root`.
+    pub fn path(root: &Path) -> PathBuf {
+        root.join(PINS_FILE)
+    }
+
+    /// Load the pins of the repository at `root`; none if there is no pins
+    /// file.
+    pub fn load(root: &Path) -> Result<Self, TopoError> {
+        let path = Self::path(root);
+        match fs::read_to_string(&path) {
+            Ok(text) => Ok(Self::parse(&text)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(TopoError::io(&path, e)),
+        }
+    }
+
+    /// Parse the contents of a pins file.
+    pub fn parse(text: &str) -> Self {
+        let paths = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(normalize)
+            .collect();
+        Self { paths }
+    }
+
+    /// Write the pins file of the repository at `root`, sorted.
+    pub fn save(&self, root: &Path) -> Result<(), TopoError> {
+        let path = Self::path(root);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| TopoError::io(dir, e))?;
+        }
+        let mut text = String::from("# Pinned by `topo pin`; one path per line\n");
+        for pinned in &self.paths {
+            text.push_str(pinned);
+            text.push('\n');
+        }
+        fs::write(&path, text).map_err(|e| TopoError::io(&path, e))
+    }
+
+    /// Pin `path`. Returns false if it was already pinned.
+    pub fn add(&mut self, path: &str) -> bool {
+        self.paths.insert(normalize(path))
+    }
+
+    /// Unpin `path`. Returns false if it wasn't pinned.
+    pub fn remove(&mut self, path: &str) -> bool {
+        self.paths.remove(&normalize(path))
+    }
+
+    /// Unpin everything.
+    pub fn clear(&mut self) {
+        self.paths.clear();
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.paths.contains(path)
+    }
+
+    /// Pinned paths, sorted.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.paths.iter().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+}
+
+/// `path` with `/` separators and no leading `./`.
+fn normalize(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    path.trim_start_matches("./").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_paths_skipping_comments() {
+        let pins =
+            Pins::parse("# pinned\nsrc/main.rs\n\n  ./docs/ARCHITECTURE.md  \nsrc\\lib.rs\n");
+        let paths: Vec<&str> = pins.iter().collect();
+        assert_eq!(paths, ["docs/ARCHITECTURE.md", "src/lib.rs", "src/main.rs"]);
+    }
+
+    #[test]
+    fn saves_and_loads_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(Pins::load(dir.path()).unwrap().is_empty());
+
+        let mut pins = Pins::default();
+        assert!(pins.add("src/main.rs"));
+        assert!(!pins.add("./src/main.rs"));
+        assert!(pins.add("README.md"));
+        pins.save(dir.path()).unwrap();
+
+        let mut loaded = Pins::load(dir.path()).unwrap();
+        assert_eq!(loaded, pins);
+        assert!(loaded.contains("README.md"));
+        assert!(loaded.remove("README.md"));
+        assert!(!loaded.remove("README.md"));
+        assert_eq!(loaded.len(), 1);
+    }
+}
@@ -0,0 +1,294 @@
+use crate::config::CONFIG_FILE;
+use crate::error::TopoError;
+use crate::types::{FileRole, ScoredFile};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::Deserialize;
+
+/// File patterns that commonly hold credentials or private keys.
+const SECRET_PATTERNS: &[&str] = &[
+    "**/.env",
+    "**/.env.*",
+    "**/*.pem",
+    "**/*.key",
+    "**/*.p12",
+    "**/*.pfx",
+    "**/id_rsa*",
+    "**/id_dsa*",
+    "**/id_ecdsa*",
+    "**/id_ed25519*",
+    "**/.netrc",
+    "**/.npmrc",
+    "**/.pypirc",
+    "**/credentials.json",
+    "**/*.keystore",
+];
+
+/// Context selection policy, read from the `[policy]` section of `.topo.toml`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Policy {
+    /// Globs for paths that must never be selected.
+    pub deny: Vec<String>,
+    /// Flag files matching built-in secret patterns (`.env`, `*.pem`, SSH keys, …).
+    pub secrets: bool,
+    /// Allow generated files (vendored, lockfiles, `*.min.js`, …) in a selection.
+    pub allow_generated: bool,
+    /// Maximum total estimated tokens for a selection.
+    pub max_tokens: Option<u64>,
+    /// Maximum number of files in a selection.
+    pub max_files: Option<usize>,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            deny: Vec::new(),
+            secrets: true,
+            allow_generated: true,
+            max_tokens: None,
+            max_files: None,
+        }
+    }
+}
+
+/// A policy rule that a selection can violate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PolicyRule {
+    DeniedPath,
+    SecretFile,
+    GeneratedFile,
+    TokenBudget,
+    FileBudget,
+}
+
+/// How serious a violation is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl PolicyRule {
+    pub const ALL: &'static [PolicyRule] = &[
+        Self::DeniedPath,
+        Self::SecretFile,
+        Self::GeneratedFile,
+        Self::TokenBudget,
+        Self::FileBudget,
+    ];
+
+    /// Stable rule identifier.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::DeniedPath => "topo/denied-path",
+            Self::SecretFile => "topo/secret-file",
+            Self::GeneratedFile => "topo/generated-file",
+            Self::TokenBudget => "topo/token-budget",
+            Self::FileBudget => "topo/file-budget",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::DeniedPath => "Selection includes a path denied by policy",
+            Self::SecretFile => "Selection includes a file that may contain secrets",
+            Self::GeneratedFile => "Selection includes a generated file",
+            Self::TokenBudget => "Selection exceeds the token ceiling",
+            Self::FileBudget => "Selection exceeds the file count ceiling",
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::GeneratedFile => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+/// A single policy check failure.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyViolation {
+    pub rule: PolicyRule,
+    pub message: String,
+    /// Offending file; `None` for selection-wide rules such as budgets.
+    pub path: Option<String>,
+}
+
+impl Policy {
+    /// Check a selection against this policy.
+    ///
+    /// Per-file violations are reported in selection order, followed by
+    /// selection-wide budget violations.
+    pub fn check(&self, files: &[ScoredFile]) -> Result<Vec<PolicyViolation>, TopoError> {
+        let deny = build_set(self.deny.iter().map(String::as_str))?;
+        let secrets = if self.secrets {
+            build_set(SECRET_PATTERNS.iter().copied())?
+        } else {
+            GlobSet::empty()
+        };
+
+        let mut violations = Vec::new();
+        for file in files {
+            let path = file.path.as_str();
+            if deny.is_match(path) {
+                violations.push(PolicyViolation {
+                    rule: PolicyRule::DeniedPath,
+                    message: format!("{path} matches a deny pattern in {CONFIG_FILE}"),
+                    path: Some(file.path.clone()),
+                });
+            } else if secrets.is_match(path) {
+                violations.push(PolicyViolation {
+                    rule: PolicyRule::SecretFile,
+                    message: format!("{path} matches a secret file pattern"),
+                    path: Some(file.path.clone()),
+                });
+            }
+            if !self.allow_generated && file.role == FileRole::Generated {
+                violations.push(PolicyViolation {
+                    rule: PolicyRule::GeneratedFile,
+                    message: format!("{path} is a generated file"),
+                    path: Some(file.path.clone()),
+                });
+            }
+        }
+
+        let total_tokens: u64 = files.iter().map(|f| f.tokens).sum();
+        if let Some(max) = self.max_tokens
+            && total_tokens > max
+        {
+            violations.push(PolicyViolation {
+                rule: PolicyRule::TokenBudget,
+                message: format!("selection has {total_tokens} tokens (limit {max})"),
+                path: None,
+            });
+        }
+        if let Some(max) = self.max_files
+            && files.len() > max
+        {
+            violations.push(PolicyViolation {
+                rule: PolicyRule::FileBudget,
+                message: format!("selection has {} files (limit {max})", files.len()),
+                path: None,
+            });
+        }
+
+        Ok(violations)
+    }
+}
+
+fn build_set<'a>(patterns: impl Iterator<Item = &'a str>) -> Result<GlobSet, TopoError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|source| TopoError::Pattern {
+            pattern: pattern.to_string(),
+            source,
+        })?;
+        builder.add(glob);
+    }
+    builder.build().map_err(|source| TopoError::Config {
+        path: None,
+        message: "invalid policy patterns".to_string(),
+        source: Some(Box::new(source)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Language, SignalBreakdown};
+
+    fn file(path: &str, tokens: u64, role: FileRole) -> ScoredFile {
+        ScoredFile {
+            path: path.to_string(),
+            score: 1.0,
+            signals: SignalBreakdown::default(),
+            tokens,
+            language: Language::Other,
+            role,
+            preview: None,
+        }
+    }
+
+    #[test]
+    fn default_policy_flags_only_secrets() {
+        let files = vec![
+            file("src/main.rs", 100, FileRole::Implementation),
+            file("config/.env", 10, FileRole::Config),
+            file("vendor/lib.go", 10, FileRole::Generated),
+        ];
+        let violations = Policy::default().check(&files).unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule, PolicyRule::SecretFile);
+        assert_eq!(violations[0].path.as_deref(), Some("config/.env"));
+    }
+
+    #[test]
+    fn deny_patterns_take_precedence_over_secrets() {
+        let policy = Policy {
+            deny: vec!["certs/**".to_string()],
+            ..Default::default()
+        };
+        let files = vec![file("certs/server.pem", 10, FileRole::Other)];
+        let violations = policy.check(&files).unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule, PolicyRule::DeniedPath);
+    }
+
+    #[test]
+    fn secrets_check_can_be_disabled() {
+        let policy = Policy {
+            secrets: false,
+            ..Default::default()
+        };
+        let files = vec![file(".env", 10, FileRole::Config)];
+        assert!(policy.check(&files).unwrap().is_empty());
+    }
+
+    #[test]
+    fn generated_files_flagged_when_disallowed() {
+        let policy = Policy {
+            allow_generated: false,
+            ..Default::default()
+        };
+        let files = vec![file("vendor/lib.go", 10, FileRole::Generated)];
+        let violations = policy.check(&files).unwrap();
+        assert_eq!(violations[0].rule, PolicyRule::GeneratedFile);
+        assert_eq!(violations[0].rule.severity(), Severity::Warning);
+    }
+
+    #[test]
+    fn budget_ceilings_are_selection_wide() {
+        let policy = Policy {
+            max_tokens: Some(150),
+            max_files: Some(1),
+            ..Default::default()
+        };
+        let files = vec![
+            file("a.rs", 100, FileRole::Implementation),
+            file("b.rs", 100, FileRole::Implementation),
+        ];
+        let violations = policy.check(&files).unwrap();
+        let rules: Vec<PolicyRule> = violations.iter().map(|v| v.rule).collect();
+        assert_eq!(rules, vec![PolicyRule::TokenBudget, PolicyRule::FileBudget]);
+        assert!(violations.iter().all(|v| v.path.is_none()));
+    }
+
+    #[test]
+    fn invalid_deny_pattern_is_pattern_error() {
+        let policy = Policy {
+            deny: vec!["src/[".to_string()],
+            ..Default::default()
+        };
+        let err = policy.check(&[]).unwrap_err();
+        assert!(matches!(err, TopoError::Pattern { ref pattern, .. } if pattern == "src/["));
+    }
+
+    #[test]
+    fn rule_ids_are_unique() {
+        let mut ids: Vec<&str> = PolicyRule::ALL.iter().map(|r| r.id()).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), PolicyRule::ALL.len());
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::{HashMap, HashSet};
+
+/// Minimum share of a query term's trigrams a candidate must contain.
+pub const DEFAULT_MIN_SIMILARITY: f64 = 0.6;
+
+/// Trigram index over symbol and filename terms, for fuzzy lookup.
+///
+/// Maps each three-character window to the vocabulary terms containing it,
+/// and each term to the files it appears in, so near-misses like `authz`
+/// can find `authorization` without scanning the whole vocabulary.
+#[derive(Debug, Clone, Default, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct TrigramIndex {
+    /// Vocabulary, sorted.
+    terms: Vec<String>,
+    /// Indexes into `paths` per term.
+    term_files: Vec<Vec<u32>>,
+    paths: Vec<String>,
+    /// Trigram → indexes into `terms`.
+    postings: HashMap<String, Vec<u32>>,
+}
+
+/// A vocabulary term similar to a query term.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuzzyMatch<'a> {
+    pub term: &'a str,
+    /// Share of the query term's trigrams found in `term` (0.0–1.0).
+    pub similarity: f64,
+}
+
+impl TrigramIndex {
+    /// Build from `(path, terms)` pairs.
+    ///
+    /// Terms shorter than three characters have no trigrams and are skipped.
+    pub fn build<'a, I, T>(files: I) -> Self
+    where
+        I: IntoIterator<Item = (&'a str, T)>,
+        T: IntoIterator<Item = &'a str>,
+    {
+        let mut by_term: HashMap<&str, HashSet<u32>> = HashMap::new();
+        let mut paths = Vec::new();
+        for (path, terms) in files {
+            let file_id = paths.len() as u32;
+            paths.push(path.to_string());
+            for term in terms {
+                if term.chars().count() >= 3 {
+                    by_term.entry(term).or_default().insert(file_id);
+                }
+            }
+        }
+
+        let mut vocabulary: Vec<(&str, HashSet<u32>)> = by_term.into_iter().collect();
+        vocabulary.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut terms = Vec::with_capacity(vocabulary.len());
+        let mut term_files = Vec::with_capacity(vocabulary.len());
+        let mut postings: HashMap<String, Vec<u32>> = HashMap::new();
+        for (term_id, (term, files)) in vocabulary.into_iter().enumerate() {
+            for gram in trigrams(term) {
+                postings.entry(gram).or_default().push(term_id as u32);
+            }
+            let mut files: Vec<u32> = files.into_iter().collect();
+            files.sort_unstable();
+            terms.push(term.to_string());
+            term_files.push(files);
+        }
+
+        Self {
+            terms,
+            term_files,
+            paths,
+            postings,
+        }
+    }
+
+    /// Whether `term` is in the vocabulary.
+    pub fn contains(&self, term: &str) -> bool {
+        self.terms
+            .binary_search_by(|t| t.as_str().cmp(term))
+            .is_ok()
+    }
+
+    /// Vocabulary terms sharing at least `min_similarity` of `term`'s
+    /// trigrams, best first (ties go to the term closest in length).
+    pub fn similar_terms(&self, term: &str, min_similarity: f64) -> Vec<FuzzyMatch<'_>> {
+        let grams = trigrams(term);
+        if grams.is_empty() {
+            return Vec::new();
+        }
+
+        let mut shared: HashMap<u32, usize> = HashMap::new();
+        for gram in &grams {
+            for &term_id in self
+                .postings
+                .get(gram)
+                .map(Vec::as_slice)
+                .unwrap_or_default()
+            {
+                *shared.entry(term_id).or_default() += 1;
+            }
+        }
+
+        let len = term.chars().count();
+        let mut matches: Vec<FuzzyMatch<'_>> = shared
+            .into_iter()
+            .map(|(term_id, count)| FuzzyMatch {
+                term: self.terms[term_id as usize].as_str(),
+                similarity: count as f64 / grams.len() as f64,
+            })
+            .filter(|m| m.similarity >= min_similarity)
+            .collect();
+        matches.sort_by(|a, b| {
+            b.similarity
+                .partial_cmp(&a.similarity)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| {
+                    let distance = |t: &str| t.chars().count().abs_diff(len);
+                    distance(a.term).cmp(&distance(b.term))
+                })
+                .then_with(|| a.term.cmp(b.term))
+        });
+        matches
+    }
+
+    /// Files containing `term`, sorted.
+    pub fn files_with(&self, term: &str) -> Vec<&str> {
+        let Ok(term_id) = self.terms.binary_search_by(|t| t.as_str().cmp(term)) else {
+            return Vec::new();
+        };
+        let mut files: Vec<&str> = self.term_files[term_id]
+            .iter()
+            .map(|&id| self.paths[id as usize].as_str())
+            .collect();
+        files.sort_unstable();
+        files
+    }
+
+    /// Number of indexed terms.
+    pub fn term_count(&self) -> usize {
+        self.terms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+}
+
+/// Distinct lowercase three-character windows of `term`, in order of first
+/// appearance.
+pub fn trigrams(term: &str) -> Vec<String> {
+    let chars: Vec<char> = term.to_lowercase().chars().collect();
+    let mut seen = HashSet::new();
+    chars
+        .windows(3)
+        .map(|w| w.iter().collect::<String>())
+        .filter(|gram| seen.insert(gram.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index() -> TrigramIndex {
+        TrigramIndex::build([
+            ("src/authorization.rs", vec!["authorization", "policy"]),
+            ("src/auth/token.rs", vec!["auth", "token", "verify"]),
+            ("src/middleware.rs", vec!["middleware", "policy"]),
+        ])
+    }
+
+    #[test]
+    fn trigrams_are_distinct_windows() {
+        assert_eq!(trigrams("Auth"), vec!["aut", "uth"]);
+        assert_eq!(trigrams("aaaa"), vec!["aaa"]);
+        assert!(trigrams("ab").is_empty());
+    }
+
+    #[test]
+    fn abbreviation_finds_long_form() {
+        let index = index();
+        let matches = index.similar_terms("authz", DEFAULT_MIN_SIMILARITY);
+        let terms: Vec<&str> = matches.iter().map(|m| m.term).collect();
+        // "auth" is closer in length, so it ranks ahead of "authorization"
+        assert_eq!(terms, vec!["auth", "authorization"]);
+        assert!((matches[0].similarity - 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn typo_finds_term() {
+        let index = index();
+        let matches = index.similar_terms("middlware", DEFAULT_MIN_SIMILARITY);
+        assert_eq!(matches[0].term, "middleware");
+    }
+
+    #[test]
+    fn unrelated_term_has_no_matches() {
+        assert!(
+            index()
+                .similar_terms("database", DEFAULT_MIN_SIMILARITY)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn files_and_vocabulary_lookup() {
+        let index = index();
+        assert!(index.contains("policy"));
+        assert!(!index.contains("polic"));
+        assert_eq!(
+            index.files_with("policy"),
+            vec!["src/authorization.rs", "src/middleware.rs"]
+        );
+        assert!(index.files_with("missing").is_empty());
+        assert_eq!(index.term_count(), 6);
+    }
+}
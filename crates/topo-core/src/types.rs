@@ -1,3 +1,4 @@
+use crate::package::{Package, is_lockfile};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::path::{Path, PathBuf};
@@ -11,12 +12,24 @@ pub struct FileInfo {
     pub language: Language,
     pub role: FileRole,
     pub sha256: [u8; 32],
+    /// Name of the innermost workspace package containing this file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+}
+
+/// Identifier of the token estimator used by [`estimate_tokens`].
+pub const TOKEN_ESTIMATOR: &str = "bytes/4";
+
+/// Estimate the tokens of `bytes` bytes of text as bytes / 4 (rough
+/// heuristic).
+pub fn estimate_tokens(bytes: u64) -> u64 {
+    bytes / 4
 }
 
 impl FileInfo {
-    /// Estimate token count as bytes / 4 (rough heuristic).
+    /// Estimate token count with [`estimate_tokens`].
     pub fn estimated_tokens(&self) -> u64 {
-        self.size / 4
+        estimate_tokens(self.size)
     }
 }
 
@@ -48,38 +61,178 @@ pub enum Language {
     Lua,
     Php,
     R,
+    Zig,
+    Nix,
+    Terraform,
+    Starlark,
+    Solidity,
+    Sql,
+    Protobuf,
+    GraphQl,
+    Vue,
+    Svelte,
+    CSharp,
+    Dart,
     Other,
 }
 
+/// One row of the built-in language table.
+struct LanguageSpec {
+    language: Language,
+    name: &'static str,
+    /// Name GitHub's linguist reports the language under.
+    linguist: &'static str,
+    extensions: &'static [&'static str],
+    programming: bool,
+}
+
+const fn spec(
+    language: Language,
+    name: &'static str,
+    linguist: &'static str,
+    extensions: &'static [&'static str],
+    programming: bool,
+) -> LanguageSpec {
+    LanguageSpec {
+        language,
+        name,
+        linguist,
+        extensions,
+        programming,
+    }
+}
+
+/// Built-in language table: canonical name, linguist name, file
+/// extensions, and whether the language is code (as opposed to
+/// markup/config/data).
+///
+/// `Other` is deliberately absent; it is the fallback for unknown input.
+const LANGUAGE_TABLE: &[LanguageSpec] = &[
+    spec(Language::Rust, "rust", "Rust", &["rs"], true),
+    spec(Language::Go, "go", "Go", &["go"], true),
+    spec(Language::Python, "python", "Python", &["py", "pyi"], true),
+    spec(
+        Language::JavaScript,
+        "javascript",
+        "JavaScript",
+        &["js", "mjs", "cjs"],
+        true,
+    ),
+    spec(
+        Language::TypeScript,
+        "typescript",
+        "TypeScript",
+        &["ts", "tsx", "mts", "cts"],
+        true,
+    ),
+    spec(Language::Java, "java", "Java", &["java"], true),
+    spec(Language::Ruby, "ruby", "Ruby", &["rb"], true),
+    spec(Language::C, "c", "C", &["c", "h"], true),
+    spec(
+        Language::Cpp,
+        "cpp",
+        "C++",
+        &["cpp", "cc", "cxx", "hpp", "hh", "hxx"],
+        true,
+    ),
+    spec(
+        Language::Shell,
+        "shell",
+        "Shell",
+        &["sh", "bash", "zsh"],
+        true,
+    ),
+    spec(
+        Language::Markdown,
+        "markdown",
+        "Markdown",
+        &["md", "mdx"],
+        false,
+    ),
+    spec(Language::Yaml, "yaml", "YAML", &["yml", "yaml"], false),
+    spec(Language::Toml, "toml", "TOML", &["toml"], false),
+    spec(Language::Json, "json", "JSON", &["json"], false),
+    spec(Language::Html, "html", "HTML", &["html", "htm"], false),
+    spec(
+        Language::Css,
+        "css",
+        "CSS",
+        &["css", "scss", "sass", "less"],
+        false,
+    ),
+    spec(Language::Swift, "swift", "Swift", &["swift"], true),
+    spec(Language::Kotlin, "kotlin", "Kotlin", &["kt", "kts"], true),
+    spec(Language::Scala, "scala", "Scala", &["scala", "sc"], true),
+    spec(Language::Haskell, "haskell", "Haskell", &["hs"], true),
+    spec(Language::Elixir, "elixir", "Elixir", &["ex", "exs"], true),
+    spec(Language::Lua, "lua", "Lua", &["lua"], true),
+    spec(Language::Php, "php", "PHP", &["php"], true),
+    spec(Language::R, "r", "R", &["r", "R"], true),
+    spec(Language::Zig, "zig", "Zig", &["zig"], true),
+    spec(Language::Nix, "nix", "Nix", &["nix"], true),
+    spec(
+        Language::Terraform,
+        "terraform",
+        "HCL",
+        &["tf", "tfvars"],
+        true,
+    ),
+    spec(
+        Language::Starlark,
+        "starlark",
+        "Starlark",
+        &["bzl", "star"],
+        true,
+    ),
+    spec(Language::Solidity, "solidity", "Solidity", &["sol"], true),
+    spec(Language::Sql, "sql", "SQL", &["sql"], true),
+    spec(
+        Language::Protobuf,
+        "protobuf",
+        "Protocol Buffer",
+        &["proto"],
+        false,
+    ),
+    spec(
+        Language::GraphQl,
+        "graphql",
+        "GraphQL",
+        &["graphql", "gql"],
+        false,
+    ),
+    spec(Language::Vue, "vue", "Vue", &["vue"], true),
+    spec(Language::Svelte, "svelte", "Svelte", &["svelte"], true),
+    spec(Language::CSharp, "csharp", "C#", &["cs"], true),
+    spec(Language::Dart, "dart", "Dart", &["dart"], true),
+];
+
 impl Language {
+    fn spec(&self) -> Option<&'static LanguageSpec> {
+        LANGUAGE_TABLE.iter().find(|s| s.language == *self)
+    }
+
+    /// All known languages, in table order (excludes `Other`).
+    pub fn all() -> impl Iterator<Item = Language> {
+        LANGUAGE_TABLE.iter().map(|s| s.language)
+    }
+
     pub fn from_extension(ext: &str) -> Self {
-        match ext {
-            "rs" => Self::Rust,
-            "go" => Self::Go,
-            "py" | "pyi" => Self::Python,
-            "js" | "mjs" | "cjs" => Self::JavaScript,
-            "ts" | "tsx" | "mts" | "cts" => Self::TypeScript,
-            "java" => Self::Java,
-            "rb" => Self::Ruby,
-            "c" | "h" => Self::C,
-            "cpp" | "cc" | "cxx" | "hpp" | "hh" | "hxx" => Self::Cpp,
-            "sh" | "bash" | "zsh" => Self::Shell,
-            "md" | "mdx" => Self::Markdown,
-            "yml" | "yaml" => Self::Yaml,
-            "toml" => Self::Toml,
-            "json" => Self::Json,
-            "html" | "htm" => Self::Html,
-            "css" | "scss" | "sass" | "less" => Self::Css,
-            "swift" => Self::Swift,
-            "kt" | "kts" => Self::Kotlin,
-            "scala" | "sc" => Self::Scala,
-            "hs" => Self::Haskell,
-            "ex" | "exs" => Self::Elixir,
-            "lua" => Self::Lua,
-            "php" => Self::Php,
-            "r" | "R" => Self::R,
-            _ => Self::Other,
+        LANGUAGE_TABLE
+            .iter()
+            .find(|s| s.extensions.contains(&ext))
+            .map(|s| s.language)
+            .unwrap_or(Self::Other)
+    }
+
+    /// Look up a language by its canonical name (as returned by [`Language::as_str`]).
+    pub fn from_name(name: &str) -> Option<Self> {
+        if name == "other" {
+            return Some(Self::Other);
         }
+        LANGUAGE_TABLE
+            .iter()
+            .find(|s| s.name == name)
+            .map(|s| s.language)
     }
 
     /// Detect language from a file path by extracting its extension.
@@ -91,59 +244,40 @@ impl Language {
     }
 
     pub fn as_str(&self) -> &'static str {
-        match self {
-            Self::Rust => "rust",
-            Self::Go => "go",
-            Self::Python => "python",
-            Self::JavaScript => "javascript",
-            Self::TypeScript => "typescript",
-            Self::Java => "java",
-            Self::Ruby => "ruby",
-            Self::C => "c",
-            Self::Cpp => "cpp",
-            Self::Shell => "shell",
-            Self::Markdown => "markdown",
-            Self::Yaml => "yaml",
-            Self::Toml => "toml",
-            Self::Json => "json",
-            Self::Html => "html",
-            Self::Css => "css",
-            Self::Swift => "swift",
-            Self::Kotlin => "kotlin",
-            Self::Scala => "scala",
-            Self::Haskell => "haskell",
-            Self::Elixir => "elixir",
-            Self::Lua => "lua",
-            Self::Php => "php",
-            Self::R => "r",
-            Self::Other => "other",
-        }
+        self.spec().map(|s| s.name).unwrap_or("other")
+    }
+
+    /// File extensions mapped to this language by the built-in table.
+    pub fn extensions(&self) -> &'static [&'static str] {
+        self.spec().map(|s| s.extensions).unwrap_or(&[])
     }
 
     /// Returns true if this language is a programming language
     /// (as opposed to markup/config/data format).
     pub fn is_programming_language(&self) -> bool {
-        matches!(
-            self,
-            Self::Rust
-                | Self::Go
-                | Self::Python
-                | Self::JavaScript
-                | Self::TypeScript
-                | Self::Java
-                | Self::Ruby
-                | Self::C
-                | Self::Cpp
-                | Self::Shell
-                | Self::Swift
-                | Self::Kotlin
-                | Self::Scala
-                | Self::Haskell
-                | Self::Elixir
-                | Self::Lua
-                | Self::Php
-                | Self::R
-        )
+        self.spec().is_some_and(|s| s.programming)
+    }
+
+    /// Name GitHub's linguist uses for this language, e.g. `"C++"`.
+    pub fn linguist_name(&self) -> &'static str {
+        self.spec().map(|s| s.linguist).unwrap_or("Other")
+    }
+
+    /// Look up a language by linguist or canonical name, ignoring case, as
+    /// written in a `linguist-language` attribute.
+    pub fn from_linguist_name(name: &str) -> Option<Self> {
+        LANGUAGE_TABLE
+            .iter()
+            .find(|s| s.linguist.eq_ignore_ascii_case(name) || s.name.eq_ignore_ascii_case(name))
+            .map(|s| s.language)
+    }
+
+    /// Whether linguist counts this language in its breakdown by default.
+    /// Programming and markup languages count; data (JSON, YAML, TOML, and
+    /// SQL, which linguist files as data) and prose (Markdown) don't.
+    pub fn is_linguist_detectable(&self) -> bool {
+        (self.is_programming_language() && *self != Self::Sql)
+            || matches!(self, Self::Html | Self::Css)
     }
 }
 
@@ -157,9 +291,11 @@ impl fmt::Display for Language {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum FileRole {
+    #[serde(alias = "impl")]
     Implementation,
     Test,
     Config,
+    #[serde(alias = "docs")]
     Documentation,
     Generated,
     Build,
@@ -179,9 +315,27 @@ impl FileRole {
         }
     }
 
+    /// Look up a role by its [`FileRole::as_str`] name or the long form of
+    /// it (`implementation`, `documentation`), ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        let role = match name.as_str() {
+            "impl" | "implementation" => Self::Implementation,
+            "test" => Self::Test,
+            "config" => Self::Config,
+            "docs" | "documentation" => Self::Documentation,
+            "generated" => Self::Generated,
+            "build" => Self::Build,
+            "other" => Self::Other,
+            _ => return None,
+        };
+        Some(role)
+    }
+
     /// Classify a file's role based on its path.
     ///
-    /// Priority order: Generated > Test > Documentation > Build > Config > Implementation > Other
+    /// Priority order: Generated > Test > Documentation > Build > Config > Implementation > Other,
+    /// except that lockfiles outside generated directories are always Build.
     pub fn from_path(path: &Path) -> Self {
         let path_str = path.to_string_lossy();
         let file_name = path
@@ -203,6 +357,11 @@ impl FileRole {
             return Self::Generated;
         }
 
+        // Lockfiles are build inputs wherever they live, even under tests/
+        if is_lockfile(&file_name) {
+            return Self::Build;
+        }
+
         // Test directories
         if Self::path_contains_component(&path_str, "tests")
             || Self::path_contains_component(&path_str, "__tests__")
@@ -293,15 +452,10 @@ impl FileRole {
                 | "Justfile"
                 | "justfile"
                 | "go.mod"
-                | "go.sum"
                 | "setup.py"
                 | "setup.cfg"
                 | "pyproject.toml"
                 | "Pipfile"
-                | "Cargo.lock"
-                | "package-lock.json"
-                | "yarn.lock"
-                | "pnpm-lock.yaml"
                 | "flake.nix"
         )
     }
@@ -346,6 +500,8 @@ pub struct Bundle {
     pub fingerprint: String,
     pub root: PathBuf,
     pub files: Vec<FileInfo>,
+    /// Workspace packages detected from manifests, innermost first.
+    pub packages: Vec<Package>,
     pub scanned_at: SystemTime,
 }
 
@@ -361,6 +517,13 @@ impl Bundle {
     pub fn file_count(&self) -> usize {
         self.files.len()
     }
+
+    /// Files belonging to the named package.
+    pub fn package_files<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a FileInfo> {
+        self.files
+            .iter()
+            .filter(move |f| f.package.as_deref() == Some(name))
+    }
 }
 
 /// A file with its computed relevance score.
@@ -372,6 +535,9 @@ pub struct ScoredFile {
     pub tokens: u64,
     pub language: Language,
     pub role: FileRole,
+    /// One-line preview of why the file matched (matching line or chunk signature).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview: Option<String>,
 }
 
 /// Per-signal score breakdown for explainability.
@@ -381,7 +547,135 @@ pub struct SignalBreakdown {
     pub heuristic: f64,
     pub pagerank: Option<f64>,
     pub git_recency: Option<f64>,
+    /// Blame-based ownership boost, see [`Ownership::score`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ownership: Option<f64>,
+    /// Share of query terms naming the file's public API, see
+    /// [`FileEntry::exports`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exports: Option<f64>,
+    /// Share of the query's quoted phrases the file confirms, see
+    /// `topo_score::Phrases`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phrase: Option<f64>,
+    /// Score of the matching test or implementation file coupled to this
+    /// one, scaled by the coupling's strength, see `topo_score::TestPairs`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coupling: Option<f64>,
+    /// How much the file is the querying author's recent work, see
+    /// `topo_score::RecencyModel::affinity`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<f64>,
+    /// How much the file is an entry point or imported by many others, see
+    /// `topo_score::Centrality`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub centrality: Option<f64>,
+    /// Score taken off for matching the query's `-term` words, see
+    /// `topo_score::ParsedQuery`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub penalty: Option<f64>,
+    /// The better scoring file this one is an identical copy of, see
+    /// `topo_score::Duplicates`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<String>,
+    /// Cosine similarity between the query and the file's summary, see
+    /// `topo_score::EmbeddingScorer`.
     pub embedding: Option<f64>,
+    /// Score before it was normalized, see `topo_score::Normalization`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<f64>,
+    /// Pinned with `topo pin`, see [`crate::Pins`].
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+}
+
+/// Who wrote a file's current lines, summarized from blame.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ownership {
+    /// Author email owning the most lines.
+    pub top_author: String,
+    /// Share of lines owned by `top_author` (0.0–1.0).
+    pub top_share: f64,
+    /// Share of lines owned by the querying author (0.0–1.0).
+    pub own_share: f64,
+    /// Share of lines changed recently (0.0–1.0).
+    pub recent_share: f64,
+}
+
+impl Ownership {
+    /// Boost in [0.0, 1.0]: high when the querying author wrote most of the
+    /// file or most of its lines are fresh.
+    pub fn score(&self) -> f64 {
+        self.own_share.max(self.recent_share)
+    }
+}
+
+/// A file's blame reduced to what [`Ownership`] is computed from, small
+/// enough to cache by the file's contents.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlameSummary {
+    /// Lines last changed by each author at each author time: `(email,
+    /// lowercased; seconds since the Unix epoch; lines)`, sorted.
+    pub lines: Vec<(String, u64, usize)>,
+    /// Whether any line isn't committed yet, so the blame will change
+    /// without the contents changing.
+    pub uncommitted: bool,
+}
+
+/// Consecutive lines of a file around query matches.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContextWindow {
+    /// 1-based, inclusive.
+    pub start_line: u32,
+    pub end_line: u32,
+    pub text: String,
+    /// Kinds of the indexed chunks the window was cut from, in order, for
+    /// windows of `--chunks`; empty for the others.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub kinds: Vec<ChunkKind>,
+    /// For windows of `--chunks`, the number of other files holding a
+    /// copy of the window's chunks, which were left out in its favour.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duplicated_in: Option<u32>,
+}
+
+impl ContextWindow {
+    /// Windows of `context` lines either side of each 1-based line in
+    /// `matches`, in order. Overlapping or adjacent windows are merged.
+    pub fn around(content: &str, matches: &[u32], context: u32) -> Vec<Self> {
+        let lines: Vec<&str> = content.lines().collect();
+        let last = lines.len() as u32;
+        let mut spans: Vec<(u32, u32)> = Vec::new();
+        let mut sorted: Vec<u32> = matches
+            .iter()
+            .copied()
+            .filter(|&l| l >= 1 && l <= last)
+            .collect();
+        sorted.sort_unstable();
+        for line in sorted {
+            let start = line.saturating_sub(context).max(1);
+            let end = line.saturating_add(context).min(last);
+            match spans.last_mut() {
+                Some((_, prev_end)) if start <= *prev_end + 1 => *prev_end = (*prev_end).max(end),
+                _ => spans.push((start, end)),
+            }
+        }
+        spans
+            .into_iter()
+            .map(|(start, end)| Self {
+                start_line: start,
+                end_line: end,
+                text: lines[start as usize - 1..end as usize].join("\n"),
+                kinds: Vec::new(),
+                duplicated_in: None,
+            })
+            .collect()
+    }
+
+    /// Estimate token count with [`estimate_tokens`].
+    pub fn estimated_tokens(&self) -> u64 {
+        estimate_tokens(self.text.len() as u64)
+    }
 }
 
 /// The deep index containing pre-computed term frequencies and chunks.
@@ -394,20 +688,269 @@ pub struct DeepIndex {
     pub doc_frequencies: std::collections::HashMap<String, u32>,
     /// Normalized PageRank scores per file path (0.0–1.0).
     pub pagerank_scores: std::collections::HashMap<String, f64>,
+    /// Files removed since earlier index builds, most recent first.
+    pub tombstones: Vec<Tombstone>,
+    /// File-level dependency graph: path → sorted repo paths it imports.
+    ///
+    /// Files without resolved imports have no entry.
+    pub dependencies: std::collections::HashMap<String, Vec<String>>,
+    /// Fuzzy lookup over symbol and filename terms, when built with trigrams.
+    pub trigrams: Option<crate::TrigramIndex>,
+    /// Whether every file entry has [`FileEntry::chunk_terms`].
+    pub chunk_postings: bool,
+    /// Whether terms were stemmed when indexed, so queries must stem
+    /// theirs to match, see `topo_score::stem`.
+    pub stemmed: bool,
+    /// Embedding vectors for semantic scoring, when any were computed.
+    /// Saved to a file of their own rather than with the rest of the index.
+    pub embeddings: Option<crate::EmbeddingSegment>,
+    /// Recent commit history per file, when the repository is under
+    /// version control.
+    pub git_activity: Option<GitActivity>,
+    /// How and when the index was built, when recorded.
+    pub meta: Option<IndexMeta>,
+    /// Chunks whose text appears in more than one file, sorted by hash.
+    pub duplicate_chunks: Vec<DuplicateGroup>,
+}
+
+impl DeepIndex {
+    /// Repo paths imported by `path`.
+    pub fn imports(&self, path: &str) -> &[String] {
+        self.dependencies
+            .get(path)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Repo paths that import `path`, sorted.
+    pub fn imported_by(&self, path: &str) -> Vec<&str> {
+        let mut importers: Vec<&str> = self
+            .dependencies
+            .iter()
+            .filter(|(_, deps)| deps.iter().any(|d| d == path))
+            .map(|(from, _)| from.as_str())
+            .collect();
+        importers.sort_unstable();
+        importers
+    }
+
+    /// Paths whose enrichment metadata has `key` set to `value`, sorted.
+    pub fn files_with_metadata(&self, key: &str, value: &str) -> Vec<&str> {
+        let mut paths: Vec<&str> = self
+            .files
+            .iter()
+            .filter(|(_, entry)| entry.has_metadata(key, value))
+            .map(|(path, _)| path.as_str())
+            .collect();
+        paths.sort_unstable();
+        paths
+    }
+
+    /// Number of files holding a copy of the chunk with `hash`, see
+    /// [`FileEntry::chunk_hashes`]: 1 for chunks found in a single file.
+    pub fn duplicate_files(&self, hash: u64) -> usize {
+        self.duplicate_chunks
+            .binary_search_by_key(&hash, |group| group.hash)
+            .map_or(1, |i| self.duplicate_chunks[i].files())
+    }
+
+    /// Direct neighbours of `path` in either direction, sorted and deduplicated.
+    pub fn related(&self, path: &str) -> Vec<&str> {
+        let mut related: Vec<&str> = self.imports(path).iter().map(String::as_str).collect();
+        related.extend(self.imported_by(path));
+        related.sort_unstable();
+        related.dedup();
+        related.retain(|p| *p != path);
+        related
+    }
+}
+
+/// Chunks with the same text in more than one file: vendored or
+/// copy-pasted code. See [`FileEntry::chunk_hashes`].
+#[derive(Debug, Clone, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct DuplicateGroup {
+    /// The [`FileEntry::chunk_hashes`] entry every copy shares.
+    pub hash: u64,
+    /// `(path, position in the file's chunks)` of every copy, sorted.
+    pub copies: Vec<(String, u32)>,
+}
+
+impl DuplicateGroup {
+    /// Number of files holding a copy.
+    pub fn files(&self) -> usize {
+        self.copies.chunk_by(|a, b| a.0 == b.0).count()
+    }
+}
+
+/// A file that was deleted since a previous index build.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+pub struct Tombstone {
+    pub path: String,
+    /// Unix seconds when the deletion was first observed.
+    pub deleted_at: u64,
+}
+
+/// Build statistics and provenance of a deep index, to tell whether it is
+/// stale or was built by an older topo.
+#[derive(Debug, Clone, Default, PartialEq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct IndexMeta {
+    /// Version of topo that built the index.
+    pub tool_version: String,
+    /// [`Bundle::fingerprint`] of the scan the index was built from; empty
+    /// if unknown.
+    pub fingerprint: String,
+    /// Unix seconds of the first build, kept by incremental builds.
+    pub created_at: u64,
+    /// Unix seconds of the latest build.
+    pub built_at: u64,
+    /// How long the latest build took, in milliseconds.
+    pub build_ms: u64,
+    /// Indexed files per language.
+    pub languages: std::collections::BTreeMap<String, u32>,
+    /// What the latest build pruned, when pruning was configured.
+    pub pruned: Option<PruneReport>,
+}
+
+/// Term postings left out of a deep index by `[index.prune]`.
+///
+/// Counts cover the build that produced the report. Postings pruned by
+/// earlier builds stay out of unchanged files, so only the size estimate
+/// describes the whole index.
+#[derive(Debug, Clone, Default, PartialEq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct PruneReport {
+    /// Single-file terms dropped from generated files.
+    pub generated_hapax: u64,
+    /// Terms found in more files than the postings cap.
+    pub capped_terms: u64,
+    /// Postings dropped by the cap.
+    pub capped_postings: u64,
+    /// Postings dropped to fit the size budget.
+    pub budget_postings: u64,
+    /// Estimated size of the term postings before pruning, in bytes.
+    pub bytes_before: u64,
+    /// Estimated size of the term postings after pruning, in bytes.
+    pub bytes_after: u64,
+}
+
+impl PruneReport {
+    /// Postings dropped for any reason.
+    pub fn postings(&self) -> u64 {
+        self.generated_hapax + self.capped_postings + self.budget_postings
+    }
+}
+
+/// Recent commit history per file, read while indexing so history signals
+/// need no `git log` at query time and work on copies of the index.
+#[derive(Debug, Clone, Default, PartialEq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct GitActivity {
+    /// Newest commit read. Later builds only read the commits after it.
+    pub head: String,
+    /// Commits in the lookback window per current path, following renames.
+    pub files: std::collections::HashMap<String, FileActivity>,
+    /// Author emails, lowercased, numbered by [`FileCommit::author`].
+    pub authors: Vec<String>,
+}
+
+impl GitActivity {
+    /// The number of the author with `email`, any case, if they made any
+    /// of the commits.
+    pub fn author_id(&self, email: &str) -> Option<u32> {
+        let email = email.to_lowercase();
+        self.authors
+            .iter()
+            .position(|author| *author == email)
+            .map(|i| i as u32)
+    }
+}
+
+/// Commits that touched one file, newest first.
+#[derive(Debug, Clone, Default, PartialEq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct FileActivity {
+    pub commits: Vec<FileCommit>,
+}
+
+/// One commit's change to a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct FileCommit {
+    /// Author time, seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// Lines added plus lines removed; 0 for binary files.
+    pub churn: u32,
+    /// Position of the commit's author in [`GitActivity::authors`].
+    pub author: u32,
+}
+
+impl FileActivity {
+    pub fn commit_count(&self) -> u32 {
+        self.commits.len() as u32
+    }
+
+    /// Author time of the newest commit.
+    pub fn last_modified(&self) -> Option<u64> {
+        self.commits.iter().map(|c| c.timestamp).max()
+    }
+
+    /// Lines added and removed over all commits.
+    pub fn churn(&self) -> u64 {
+        self.commits.iter().map(|c| u64::from(c.churn)).sum()
+    }
 }
 
 /// Per-file entry in the deep index.
-#[derive(Debug, Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[derive(Debug, Clone, PartialEq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
 pub struct FileEntry {
     pub sha256: [u8; 32],
+    /// Size of the file in bytes when it was indexed.
+    pub size: u64,
     pub chunks: Vec<Chunk>,
     pub term_frequencies: std::collections::HashMap<String, TermFreqs>,
     pub doc_length: u32,
+    /// Key-value pairs attached by index enrichment hooks.
+    pub metadata: std::collections::BTreeMap<String, String>,
+    /// Term frequencies of each of `chunks`, in order, when the index was
+    /// built with chunk postings.
+    pub chunk_terms: Option<Vec<ChunkTerms>>,
+    /// The file's public API: its exported declarations, methods qualified
+    /// by their type (`Auth::check`), and the names it re-exports.
+    pub exports: Vec<String>,
+    /// Hash of each of `chunks`, in order, over its lines with surrounding
+    /// whitespace and blank lines ignored; 0 for imports and chunks too
+    /// short to count as duplicated code. Equal hashes in different files
+    /// make up [`DeepIndex::duplicate_chunks`].
+    pub chunk_hashes: Vec<u64>,
+}
+
+impl FileEntry {
+    /// Whether enrichment metadata has `key` set to `value`.
+    pub fn has_metadata(&self, key: &str, value: &str) -> bool {
+        self.metadata.get(key).is_some_and(|v| v == value)
+    }
 }
 
 /// A code chunk extracted by tree-sitter or regex fallback.
+///
+/// Import chunks are named by their import statement as written, such as
+/// `use crate::auth::Token;` or `from .models import User`.
 #[derive(
-    Debug, Clone, Serialize, Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
 )]
 pub struct Chunk {
     pub kind: ChunkKind,
@@ -415,6 +958,45 @@ pub struct Chunk {
     pub start_line: u32,
     pub end_line: u32,
     pub content: String,
+    /// For import chunks, the repository-relative path of the file the
+    /// import resolves to (the package directory for Go), when it names
+    /// one. Resolved when the deep index is built.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// For functions, methods, types, and impls, the doc comment or
+    /// docstring of the declaration, without comment markers. Attached when
+    /// the deep index is built.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
+    /// For methods, the type, impl, or class they are defined in: `Auth`
+    /// for `check` in `impl Auth` or `func (a *Auth) Check()`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    /// For functions, methods, types, and impls, the first line of the
+    /// declaration as written. Extracted when the deep index is built.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// Estimated tokens of the chunk's text, from the comments above its
+    /// declaration to the next declaration, as bytes / 4. Counted when the
+    /// deep index is built.
+    #[serde(default)]
+    pub tokens: u32,
+    /// Whether the declaration is part of its file's public API: `pub` in
+    /// Rust, `export` in JavaScript and TypeScript, a capitalized name in
+    /// Go, listed in `__all__` (or not underscored) in Python, `public` in
+    /// Java and C#. Detected when the deep index is built.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub exported: bool,
+}
+
+impl Chunk {
+    /// The name qualified by the parent, `Auth::check`, or just the name.
+    pub fn qualified_name(&self) -> String {
+        match &self.parent {
+            Some(parent) => format!("{parent}::{}", self.name),
+            None => self.name.clone(),
+        }
+    }
 }
 
 /// The kind of code chunk.
@@ -431,20 +1013,100 @@ pub struct Chunk {
     rkyv::Serialize,
     rkyv::Deserialize,
 )]
+#[serde(rename_all = "lowercase")]
 pub enum ChunkKind {
+    /// A free function.
     Function,
+    /// A function defined in a type, impl, or class, named by its
+    /// [`Chunk::parent`].
+    Method,
     Type,
+    /// A trait, interface, or protocol.
+    Trait,
+    Enum,
     Impl,
     Import,
+    /// A named constant or static.
+    Constant,
+    /// A macro definition: `macro_rules!`, `#define`, or `defmacro`.
+    Macro,
+    /// A test function, or a test case declared by a call such as
+    /// `it("logs in", ...)`, named by its description.
+    #[serde(rename = "test")]
+    TestCase,
+    /// A top-level key of a YAML, JSON, or TOML file, or a TOML table.
+    Key,
+    /// A top-level `<template>`, `<script>`, or `<style>` block of a Vue or
+    /// Svelte component.
+    Section,
     Other,
 }
 
+impl ChunkKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Function => "function",
+            Self::Method => "method",
+            Self::Type => "type",
+            Self::Trait => "trait",
+            Self::Enum => "enum",
+            Self::Impl => "impl",
+            Self::Import => "import",
+            Self::Constant => "constant",
+            Self::Macro => "macro",
+            Self::TestCase => "test",
+            Self::Key => "key",
+            Self::Section => "section",
+            Self::Other => "other",
+        }
+    }
+
+    /// Whether this is a named declaration in code, as opposed to an
+    /// import, config key, component section, or other chunk.
+    pub fn is_declaration(&self) -> bool {
+        !matches!(self, Self::Import | Self::Key | Self::Section | Self::Other)
+    }
+
+    /// Whether this declares a type, trait, or enum, whose functions are
+    /// its methods.
+    pub fn is_type(&self) -> bool {
+        matches!(self, Self::Type | Self::Trait | Self::Enum)
+    }
+}
+
 /// Term frequency counts across different fields.
-#[derive(Debug, Clone, Default, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
+)]
 pub struct TermFreqs {
     pub filename: u32,
     pub symbols: u32,
     pub body: u32,
+    /// Occurrences in doc comments and docstrings, which also count as
+    /// body.
+    pub docs: u32,
+}
+
+/// Term frequencies of one chunk, scored like a small file.
+///
+/// Index chunks only mark declarations, so a chunk's text runs from the
+/// comment and attribute lines directly above its declaration to the last
+/// non-blank line before the next chunk's. The symbols field counts the
+/// chunk's own name and the filename field is always empty.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
+)]
+pub struct ChunkTerms {
+    pub term_frequencies: std::collections::HashMap<String, TermFreqs>,
+    /// Body tokens in the chunk.
+    pub length: u32,
+    /// Lines of the chunk's text, 1-based and inclusive.
+    pub start_line: u32,
+    pub end_line: u32,
+    /// Hash of the chunk's name, kind, and the text its terms were counted
+    /// from, so an edited file's incremental build can reuse the terms of
+    /// the chunks that didn't change.
+    pub hash: u64,
 }
 
 /// Token budget configuration for query results.
@@ -489,4 +1151,42 @@ impl TokenBudget {
 
         result
     }
+
+    /// Take files in order wherever they fit, skipping any that would
+    /// exceed either limit. Unlike [`TokenBudget::enforce`], the result
+    /// never exceeds the budget.
+    pub fn fill(&self, files: &[ScoredFile]) -> Vec<ScoredFile> {
+        let mut budget = self.clone();
+        let mut result = Vec::new();
+        for file in files {
+            if budget.fits(file.tokens) {
+                budget = budget.remaining(file.tokens);
+                result.push(file.clone());
+            }
+        }
+        result
+    }
+
+    /// The part `fraction` (0.0–1.0) of this budget.
+    pub fn share(&self, fraction: f64) -> Self {
+        let scale = |limit: u64| (limit as f64 * fraction.clamp(0.0, 1.0)) as u64;
+        Self {
+            max_bytes: self.max_bytes.map(scale),
+            max_tokens: self.max_tokens.map(scale),
+        }
+    }
+
+    /// What is left of this budget after spending `tokens`.
+    pub fn remaining(&self, tokens: u64) -> Self {
+        Self {
+            max_bytes: self.max_bytes.map(|b| b.saturating_sub(tokens * 4)),
+            max_tokens: self.max_tokens.map(|t| t.saturating_sub(tokens)),
+        }
+    }
+
+    /// Whether `tokens` fit within both limits.
+    pub fn fits(&self, tokens: u64) -> bool {
+        self.max_bytes.is_none_or(|b| tokens * 4 <= b)
+            && self.max_tokens.is_none_or(|t| tokens <= t)
+    }
 }
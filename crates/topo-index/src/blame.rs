@@ -0,0 +1,162 @@
+use crate::crypt;
+use crate::shard::hex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use topo_core::{BlameSummary, TopoError};
+
+/// Blame summaries as cached, by path.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CachedBlame {
+    files: HashMap<String, CachedSummary>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedSummary {
+    /// Hex sha256 of the contents the file was blamed with.
+    sha256: String,
+    summary: BlameSummary,
+}
+
+/// Where blame summaries of the repository at `root` are cached.
+pub fn blame_cache_path(root: &Path) -> PathBuf {
+    root.join(".topo").join("blame.json")
+}
+
+/// Blame summaries of `files`, each a path and the sha256 of its
+/// contents: from the cache in `.topo/` for files whose contents haven't
+/// changed since, the rest from `compute`, and cached for the next query.
+///
+/// Summaries with uncommitted lines, and files with an all-zero (unknown)
+/// hash, are never cached, since their blame can change with the same
+/// contents. Only repositories that already have a `.topo` directory are
+/// cached, and a cache that can't be read or written is recomputed.
+pub fn cached_blame_summaries(
+    root: &Path,
+    files: &[(&str, [u8; 32])],
+    compute: impl FnOnce(&[&str]) -> Result<HashMap<String, BlameSummary>, TopoError>,
+) -> Result<HashMap<String, BlameSummary>, TopoError> {
+    let mut cache = load_blame(root);
+    let mut summaries = HashMap::new();
+    let mut missing = Vec::new();
+    for &(path, sha256) in files {
+        match cache.files.get(path) {
+            Some(cached) if sha256 != [0; 32] && cached.sha256 == hex(&sha256) => {
+                summaries.insert(path.to_string(), cached.summary.clone());
+            }
+            _ => missing.push(path),
+        }
+    }
+    if missing.is_empty() {
+        return Ok(summaries);
+    }
+
+    let computed = compute(&missing)?;
+    let mut changed = false;
+    for &(path, sha256) in files {
+        let Some(summary) = computed.get(path) else {
+            continue;
+        };
+        if sha256 != [0; 32] && !summary.uncommitted {
+            let sha256 = hex(&sha256);
+            cache.files.insert(
+                path.to_string(),
+                CachedSummary {
+                    sha256,
+                    summary: summary.clone(),
+                },
+            );
+            changed = true;
+        }
+    }
+    if changed && root.join(".topo").is_dir() {
+        let _ = save_blame(root, &cache);
+    }
+    summaries.extend(computed);
+    Ok(summaries)
+}
+
+fn load_blame(root: &Path) -> CachedBlame {
+    // Sealed like index files; one that can't be opened is blamed anew
+    fs::read(blame_cache_path(root))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&crypt::open(&bytes)?).ok())
+        .unwrap_or_default()
+}
+
+fn save_blame(root: &Path, cache: &CachedBlame) -> Result<(), TopoError> {
+    let path = blame_cache_path(root);
+    let json = serde_json::to_vec(cache).map_err(|e| TopoError::Index {
+        path: Some(path.clone()),
+        message: "failed to serialize blame summaries".to_string(),
+        source: Some(Box::new(e)),
+    })?;
+    let json = crypt::seal(&path, json)?;
+    // Write then rename so a concurrent query never reads a partial file
+    let staged = path.with_extension("json.tmp");
+    fs::write(&staged, json).map_err(|e| TopoError::io(&staged, e))?;
+    fs::rename(&staged, &path).map_err(|e| TopoError::io(&path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    fn summary(author: &str, uncommitted: bool) -> BlameSummary {
+        BlameSummary {
+            lines: vec![(author.to_string(), 1_700_000_000, 3)],
+            uncommitted,
+        }
+    }
+
+    #[test]
+    fn summaries_are_reused_until_the_contents_change() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join(".topo")).unwrap();
+        let blamed = RefCell::new(Vec::new());
+        let blame = |files: &[(&str, [u8; 32])]| {
+            cached_blame_summaries(dir.path(), files, |paths| {
+                blamed
+                    .borrow_mut()
+                    .extend(paths.iter().map(|p| p.to_string()));
+                Ok(paths
+                    .iter()
+                    .map(|&p| (p.to_string(), summary("a@x.com", p == "dirty.rs")))
+                    .collect())
+            })
+            .unwrap()
+        };
+
+        let first = blame(&[
+            ("a.rs", [1; 32]),
+            ("dirty.rs", [2; 32]),
+            ("new.rs", [0; 32]),
+        ]);
+        assert_eq!(first.len(), 3);
+        assert!(blame_cache_path(dir.path()).exists());
+
+        blamed.borrow_mut().clear();
+        let second = blame(&[
+            ("a.rs", [1; 32]),
+            ("dirty.rs", [2; 32]),
+            ("new.rs", [0; 32]),
+        ]);
+        assert_eq!(second, first);
+        assert_eq!(
+            *blamed.borrow(),
+            ["dirty.rs", "new.rs"],
+            "uncommitted and unhashed files are blamed again"
+        );
+
+        blamed.borrow_mut().clear();
+        blame(&[("a.rs", [3; 32])]);
+        assert_eq!(*blamed.borrow(), ["a.rs"], "edited files are blamed again");
+
+        fs::write(blame_cache_path(dir.path()), "not json").unwrap();
+        blamed.borrow_mut().clear();
+        blame(&[("a.rs", [3; 32])]);
+        assert_eq!(*blamed.borrow(), ["a.rs"]);
+    }
+}
@@ -1,24 +1,247 @@
+use crate::duplicates::{chunk_hashes, duplicate_groups};
+use crate::embed::EmbedQueue;
+use crate::license::strip_license_header;
+use crate::pruning;
+use crate::tombstone;
 use rayon::prelude::*;
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::path::Path;
-use topo_core::{ChunkKind, DeepIndex, FileEntry, FileInfo, Language, TermFreqs};
-use topo_treesit::{Chunker, RegexChunker};
+use std::sync::Mutex;
+use std::time::Instant;
+use topo_core::{
+    Chunk, ChunkKind, ChunkTerms, CommentMode, DeepIndex, Embedder, EmbeddingConfig,
+    EmbeddingSegment, FileEmbeddings, FileEntry, FileInfo, FileRole, IndexMeta, Language,
+    PruneConfig, TermFreqs, TopoError, TrigramIndex, VectorStore, is_lockfile,
+};
+use topo_scanner::{BundleDiff, Rename, long_path};
+use topo_treesit::{
+    Chunker, DefaultChunker, WindowChunker, attach_docs, doc_comments, mark_exports, strip_comments,
+};
+
+/// On-disk format version of [`DeepIndex`]; older indexes are rebuilt.
+pub const INDEX_VERSION: u32 = 33;
+
+/// Progress callback: `(files_done, files_total)`.
+pub type ProgressFn<'a> = &'a (dyn Fn(usize, usize) + Sync);
+
+/// Enrichment hook: `(file, content, chunks)` → key-value pairs stored in
+/// the file's [`FileEntry::metadata`]. Called from worker threads.
+pub type EnrichFn<'a> = &'a (dyn Fn(&FileInfo, &str, &[Chunk]) -> Vec<(String, String)> + Sync);
 
 /// Builds a DeepIndex from a list of scanned files.
 pub struct IndexBuilder<'a> {
     root: &'a Path,
+    progress: Option<ProgressFn<'a>>,
+    trigrams: bool,
+    chunk_postings: bool,
+    git_activity: bool,
+    git_lookback_days: u32,
+    enrichers: Vec<EnrichFn<'a>>,
+    embedder: Option<&'a dyn Embedder>,
+    embedding: EmbeddingConfig,
+    embed_progress: Option<ProgressFn<'a>>,
+    vector_store: Option<Mutex<&'a mut dyn VectorStore>>,
+    fingerprint: &'a str,
+    prune: PruneConfig,
+    strip_license_headers: bool,
+    lockfile_bodies: bool,
+    comments: CommentMode,
+    stemming: bool,
+    chunker: &'a (dyn Chunker + Sync),
+    sparse: Option<&'a HashSet<String>>,
 }
 
 impl<'a> IndexBuilder<'a> {
     pub fn new(root: &'a Path) -> Self {
-        Self { root }
+        Self {
+            root,
+            progress: None,
+            trigrams: false,
+            chunk_postings: false,
+            git_activity: false,
+            git_lookback_days: topo_score::DEFAULT_LOOKBACK_DAYS,
+            enrichers: Vec::new(),
+            embedder: None,
+            embedding: EmbeddingConfig::default(),
+            embed_progress: None,
+            vector_store: None,
+            fingerprint: "",
+            prune: PruneConfig::default(),
+            strip_license_headers: true,
+            lockfile_bodies: false,
+            comments: CommentMode::Keep,
+            stemming: false,
+            chunker: &DefaultChunker {},
+            sparse: None,
+        }
+    }
+
+    /// Report per-file progress while building. Called from worker threads.
+    pub fn progress(mut self, progress: ProgressFn<'a>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Also build a [`TrigramIndex`] over symbol and filename terms for fuzzy
+    /// lookup. Incremental builds keep trigrams if the existing index has them.
+    pub fn trigrams(mut self, enabled: bool) -> Self {
+        self.trigrams = enabled;
+        self
+    }
+
+    /// Also record term frequencies per chunk, see [`FileEntry::chunk_terms`],
+    /// so chunks can be ranked on their own. This stores a second set of
+    /// term frequencies for every file. Incremental builds keep chunk
+    /// postings if the existing index has them, and reindex carried files
+    /// that lack them.
+    pub fn chunk_postings(mut self, enabled: bool) -> Self {
+        self.chunk_postings = enabled;
+        self
+    }
+
+    /// Also record recent commit history per file, see [`topo_core::GitActivity`], so
+    /// history signals need no `git log` at query time. Incremental builds
+    /// keep it if the existing index has it, reading only new commits.
+    pub fn git_activity(mut self, enabled: bool) -> Self {
+        self.git_activity = enabled;
+        self
+    }
+
+    /// Days of history recorded with [`IndexBuilder::git_activity`]. A
+    /// longer window than the existing index's fills in only on a full
+    /// rebuild, as incremental builds read just the newer commits.
+    pub fn git_lookback_days(mut self, days: u32) -> Self {
+        self.git_lookback_days = days;
+        self
+    }
+
+    /// Attach metadata to each indexed file, e.g. service ownership or SLO
+    /// tier. Hooks run in the order added; later keys overwrite earlier ones.
+    ///
+    /// Hooks only run for files that are (re)indexed. Unchanged and renamed
+    /// files keep the metadata from the existing index, so rebuild with
+    /// `force` after changing what a hook produces.
+    pub fn enrich(mut self, hook: EnrichFn<'a>) -> Self {
+        self.enrichers.push(hook);
+        self
+    }
+
+    /// Embed new and changed files with `embedder` into the index's
+    /// [`EmbeddingSegment`]: each file, then each of its chunks. Requests
+    /// are batched, limited, and retried as `config` says, see
+    /// [`EmbedQueue`]. Vectors of unchanged files are kept unless the model
+    /// or dimension changed.
+    pub fn embedder(mut self, embedder: &'a dyn Embedder, config: EmbeddingConfig) -> Self {
+        self.embedder = Some(embedder);
+        self.embedding = config;
+        self
+    }
+
+    /// Report embedding progress as `(texts embedded, total)`. Called from
+    /// worker threads.
+    pub fn embed_progress(mut self, progress: ProgressFn<'a>) -> Self {
+        self.embed_progress = Some(progress);
+        self
+    }
+
+    /// Also keep `store` in step with the index's embedding vectors: vectors
+    /// it lacks are added, including those carried over from `existing`,
+    /// and those of files that left the index are removed. The index keeps
+    /// its own copy, which decides what needs embedding again.
+    pub fn vector_store(mut self, store: &'a mut dyn VectorStore) -> Self {
+        self.vector_store = Some(Mutex::new(store));
+        self
+    }
+
+    /// Record `fingerprint`, the [`topo_core::Bundle::fingerprint`] of the
+    /// scan being indexed, in the index's [`IndexMeta`].
+    pub fn fingerprint(mut self, fingerprint: &'a str) -> Self {
+        self.fingerprint = fingerprint;
+        self
+    }
+
+    /// Leave terms out of the index as `config` says, to keep huge
+    /// repositories' indexes in bounds. What was pruned is reported in the
+    /// index's [`IndexMeta`]. Already pruned entries of unchanged files stay
+    /// pruned when the settings are relaxed, until a full rebuild.
+    pub fn prune(mut self, config: PruneConfig) -> Self {
+        self.prune = config;
+        self
+    }
+
+    /// Leave recognized license and copyright headers at the top of files
+    /// out of term frequencies and document lengths (on by default), so
+    /// boilerplate repeated in every file doesn't skew them. Chunks and
+    /// enrichment hooks still see the full content. Changing this only
+    /// affects files indexed afterwards, until a full rebuild.
+    pub fn strip_license_headers(mut self, enabled: bool) -> Self {
+        self.strip_license_headers = enabled;
+        self
+    }
+
+    /// Index the contents of lockfiles (see [`topo_core::is_lockfile`]).
+    /// Off by default: they are enormous and would swamp term statistics,
+    /// so only their filenames are indexed.
+    pub fn lockfile_bodies(mut self, enabled: bool) -> Self {
+        self.lockfile_bodies = enabled;
+        self
+    }
+
+    /// Count comments as `mode` says: as body text like code (the
+    /// default), not at all, or in the docs field, so license headers and
+    /// commented-out code don't pollute body terms. Comments are found by
+    /// [`strip_comments`]; chunks and enrichment hooks still see them.
+    /// Changing this only affects files indexed afterwards, until a full
+    /// rebuild.
+    pub fn comments(mut self, mode: CommentMode) -> Self {
+        self.comments = mode;
+        self
+    }
+
+    /// Index every term by its stem, see [`topo_score::stem`], so queries
+    /// for "authenticate" find "authentication". Recorded as
+    /// [`DeepIndex::stemmed`]; an existing index built the other way is
+    /// reindexed in full.
+    pub fn stemming(mut self, enabled: bool) -> Self {
+        self.stemming = enabled;
+        self
+    }
+
+    /// Extract chunks of (re)indexed files with `chunker` instead of the
+    /// [`DefaultChunker`]. Chunks of unchanged files are kept, so rebuild
+    /// with `force` to chunk every file again. Files in which `chunker`
+    /// finds no chunks are split into line windows by a
+    /// [`WindowChunker`]. Pass a [`topo_treesit::ChunkerRegistry`] to
+    /// chunk some languages or paths with chunkers of your own.
+    pub fn chunker(mut self, chunker: &'a (dyn Chunker + Sync)) -> Self {
+        self.chunker = chunker;
+        self
+    }
+
+    /// Paths a sparse checkout leaves out of the working tree. Their entries
+    /// in `existing` are kept as they are instead of being dropped as
+    /// deleted, so the index still covers the full tree. Imports of those
+    /// files can't be read, so they contribute no dependency edges.
+    pub fn sparse_checkout(mut self, absent: &'a HashSet<String>) -> Self {
+        self.sparse = Some(absent);
+        self
     }
 
     /// Build a deep index from a list of scanned file metadata.
     ///
     /// When `existing` is provided, files whose SHA-256 matches the existing
-    /// entry are carried forward without re-reading or re-indexing.
+    /// entry are carried forward without re-reading or re-indexing. Files
+    /// that moved with identical content keep their old entry, re-keyed to
+    /// the new path. Files that moved and were lightly edited, as a
+    /// [`BundleDiff`] near match, are reindexed on top of their old entry
+    /// like files edited in place, and leave no tombstone behind.
+    ///
+    /// Files present in `existing` but missing from `files` are recorded as
+    /// tombstones, alongside still-recent tombstones from earlier builds,
+    /// unless a [sparse checkout](Self::sparse_checkout) left them out.
     ///
     /// Returns `(index, reindexed_count)` — the number of files that were
     /// actually re-indexed (0 means nothing changed).
@@ -26,41 +249,107 @@ impl<'a> IndexBuilder<'a> {
         &self,
         files: &[FileInfo],
         existing: Option<&DeepIndex>,
-    ) -> anyhow::Result<(DeepIndex, usize)> {
+    ) -> Result<(DeepIndex, usize), TopoError> {
         use std::sync::atomic::{AtomicUsize, Ordering};
+        let started = Instant::now();
         let reindexed = AtomicUsize::new(0);
+        let processed = AtomicUsize::new(0);
+        let report = || {
+            let done = processed.fetch_add(1, Ordering::Relaxed) + 1;
+            if let Some(progress) = self.progress {
+                progress(done, files.len());
+            }
+        };
+
+        // Entries of an index built with other term stems can't be reused
+        let reusable = existing.filter(|e| e.stemmed == self.stemming);
+
+        let sparse = self.sparse_entries(files, reusable);
+
+        // Files that moved, with or without edits, keyed by their new path,
+        // so they build on their old entry instead of being reindexed anew
+        let renamed: Vec<Rename> = match reusable {
+            Some(existing) => {
+                let kept: HashSet<&str> = sparse.iter().map(|(path, _)| *path).collect();
+                let indexed: Vec<FileInfo> = existing
+                    .files
+                    .iter()
+                    .filter(|(path, _)| !kept.contains(path.as_str()))
+                    .map(|(path, entry)| indexed_file(path, entry))
+                    .collect();
+                BundleDiff::new(&indexed, files).renamed
+            }
+            None => Vec::new(),
+        };
+        let moved: HashMap<&str, &FileEntry> = renamed
+            .iter()
+            .filter_map(|rename| {
+                let entry = reusable?.files.get(&rename.from)?;
+                Some((rename.to.as_str(), entry))
+            })
+            .collect();
+
+        let chunk_postings = self.chunk_postings || existing.is_some_and(|e| e.chunk_postings);
 
         // Process files in parallel, collecting entries and raw imports
-        let results: Vec<(String, FileEntry, Language, Vec<String>)> = files
-            .par_iter()
-            .filter_map(|info| {
-                // Skip unchanged files — carry forward existing entry
-                if let Some(existing) = existing
-                    && let Some(old_entry) = existing.files.get(&info.path)
-                    && old_entry.sha256 == info.sha256
-                {
-                    // Still need to read content for import extraction
-                    let full_path = self.root.join(&info.path);
-                    let imports = if info.language.is_programming_language() {
-                        fs::read_to_string(&full_path)
-                            .map(|c| topo_score::extract_imports(&c, info.language))
-                            .unwrap_or_default()
-                    } else {
-                        Vec::new()
-                    };
-                    return Some((info.path.clone(), old_entry.clone(), info.language, imports));
+        let process = |info: &FileInfo| {
+            // Skip unchanged or renamed files — carry forward existing entry
+            let previous = reusable.and_then(|existing| existing.files.get(&info.path));
+            let moved_from = moved.get(info.path.as_str()).copied();
+            let carried = match (previous, moved_from) {
+                (Some(old_entry), _) if old_entry.sha256 == info.sha256 => Some(old_entry.clone()),
+                (None, Some(old_entry)) if old_entry.sha256 == info.sha256 => {
+                    Some(retarget_entry(old_entry, &info.path))
                 }
-
+                _ => None,
+            }
+            .filter(|entry| !chunk_postings || entry.chunk_terms.is_some());
+            if let Some(entry) = carried {
+                // Still need to read content for import extraction
                 let full_path = self.root.join(&info.path);
-                let content = fs::read_to_string(&full_path).ok()?;
-                let entry = build_file_entry(info, &content);
                 let imports = if info.language.is_programming_language() {
-                    topo_score::extract_imports(&content, info.language)
+                    fs::read_to_string(long_path(&full_path))
+                        .map(|c| topo_score::extract_imports(&c, info.language))
+                        .unwrap_or_default()
                 } else {
                     Vec::new()
                 };
-                reindexed.fetch_add(1, Ordering::Relaxed);
-                Some((info.path.clone(), entry, info.language, imports))
+                return Some((info.path.clone(), entry, info.language, imports));
+            }
+
+            let full_path = self.root.join(&info.path);
+            let content = fs::read_to_string(long_path(&full_path)).ok()?;
+            // Lockfiles are indexed by filename only unless asked otherwise
+            let indexed = if !self.lockfile_bodies && is_lockfile(file_name(&info.path)) {
+                ""
+            } else {
+                content.as_str()
+            };
+            let options = EntryOptions {
+                chunk_postings,
+                strip_licenses: self.strip_license_headers,
+                comments: self.comments,
+                stemming: self.stemming,
+            };
+            let previous = previous.or(moved_from);
+            let mut entry = build_file_entry(info, indexed, self.chunker, previous, &options);
+            for enrich in &self.enrichers {
+                entry.metadata.extend(enrich(info, &content, &entry.chunks));
+            }
+            let imports = if info.language.is_programming_language() {
+                topo_score::extract_imports(&content, info.language)
+            } else {
+                Vec::new()
+            };
+            reindexed.fetch_add(1, Ordering::Relaxed);
+            Some((info.path.clone(), entry, info.language, imports))
+        };
+        let results: Vec<(String, FileEntry, Language, Vec<String>)> = files
+            .par_iter()
+            .filter_map(|info| {
+                let result = process(info);
+                report();
+                result
             })
             .collect();
 
@@ -77,6 +366,19 @@ impl<'a> IndexBuilder<'a> {
             }
             entries.push((path, entry));
         }
+        entries.extend(
+            sparse
+                .iter()
+                .map(|(path, entry)| (path.to_string(), (*entry).clone())),
+        );
+        resolve_imports(&mut entries, files);
+
+        let generated: HashSet<&str> = files
+            .iter()
+            .filter(|f| f.role == FileRole::Generated)
+            .map(|f| f.path.as_str())
+            .collect();
+        let pruned = pruning::prune_terms(&mut entries, &generated, self.prune);
 
         // Compute corpus-level stats
         let total_docs = entries.len() as u32;
@@ -99,62 +401,585 @@ impl<'a> IndexBuilder<'a> {
         let all_paths: Vec<&str> = entries.iter().map(|(p, _)| p.as_str()).collect();
         let graph = topo_score::build_import_graph(&file_imports, &all_paths);
         let pagerank_scores = graph.normalized_pagerank();
+        let dependencies = graph.adjacency();
 
         let file_map: HashMap<String, FileEntry> = entries.into_iter().collect();
+        let trigrams = (self.trigrams || existing.is_some_and(|e| e.trigrams.is_some()))
+            .then(|| build_trigrams(&file_map));
+        let duplicate_chunks = duplicate_groups(&file_map);
+
+        // Vectors of unchanged files stay valid; the rest get embedded anew
+        let embeddings = existing.and_then(|e| e.embeddings.as_ref()).map(|segment| {
+            segment.carried_over(
+                files
+                    .iter()
+                    .map(|f| (f.path.as_str(), f.sha256))
+                    .chain(sparse.iter().map(|(path, entry)| (*path, entry.sha256))),
+            )
+        });
+
+        let embeddings = match self.embedder {
+            Some(embedder) => Some(self.embed(embedder, files, &file_map, embeddings)?),
+            None => embeddings,
+        };
+        if let (Some(store), Some(segment)) = (&self.vector_store, &embeddings) {
+            let mut store = store.lock().unwrap_or_else(|e| e.into_inner());
+            let previous = existing.and_then(|e| e.embeddings.as_ref());
+            sync_vectors(&mut **store, previous, segment)?;
+        }
+
+        let git_activity =
+            if self.git_activity || existing.is_some_and(|e| e.git_activity.is_some()) {
+                topo_score::git_activity(
+                    self.root,
+                    existing.and_then(|e| e.git_activity.as_ref()),
+                    self.git_lookback_days,
+                )?
+            } else {
+                None
+            };
+
+        let tombstones = match existing {
+            Some(existing) => {
+                let current = files
+                    .iter()
+                    .map(|f| (f.path.as_str(), f.sha256))
+                    .chain(sparse.iter().map(|(path, entry)| (*path, entry.sha256)));
+                let mut removed =
+                    tombstone::removed_paths(&tombstone::hashes(&existing.files), current);
+                removed.retain(|path| !renamed.iter().any(|rename| rename.from == *path));
+                let present: HashSet<&str> = files
+                    .iter()
+                    .map(|f| f.path.as_str())
+                    .chain(sparse.iter().map(|(path, _)| *path))
+                    .collect();
+                tombstone::update(
+                    &existing.tombstones,
+                    removed,
+                    &present,
+                    tombstone::now_secs(),
+                )
+            }
+            None => Vec::new(),
+        };
+
+        let mut languages: BTreeMap<String, u32> = BTreeMap::new();
+        for info in files.iter().filter(|f| file_map.contains_key(&f.path)) {
+            *languages.entry(info.language.to_string()).or_default() += 1;
+        }
+        let built_at = tombstone::now_secs();
+        let meta = IndexMeta {
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            fingerprint: self.fingerprint.to_string(),
+            created_at: existing
+                .and_then(|e| e.meta.as_ref())
+                .map_or(built_at, |meta| meta.created_at),
+            built_at,
+            build_ms: started.elapsed().as_millis() as u64,
+            languages,
+            pruned,
+        };
 
         Ok((
             DeepIndex {
-                version: 2,
+                version: INDEX_VERSION,
                 files: file_map,
                 avg_doc_length,
                 total_docs,
                 doc_frequencies,
                 pagerank_scores,
+                tombstones,
+                dependencies,
+                trigrams,
+                chunk_postings,
+                stemmed: self.stemming,
+                embeddings,
+                git_activity,
+                meta: Some(meta),
+                duplicate_chunks,
             },
             reindexed_count,
         ))
     }
+
+    /// Entries of `existing` that the sparse checkout left out of `files`.
+    fn sparse_entries<'e>(
+        &self,
+        files: &[FileInfo],
+        existing: Option<&'e DeepIndex>,
+    ) -> Vec<(&'e str, &'e FileEntry)> {
+        let (Some(absent), Some(existing)) = (self.sparse, existing) else {
+            return Vec::new();
+        };
+        let scanned: HashSet<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        existing
+            .files
+            .iter()
+            .filter(|(path, _)| absent.contains(*path) && !scanned.contains(path.as_str()))
+            .map(|(path, entry)| (path.as_str(), entry))
+            .collect()
+    }
+
+    /// `carried` vectors, plus new ones for the `files` that lack them.
+    fn embed(
+        &self,
+        embedder: &dyn Embedder,
+        files: &[FileInfo],
+        entries: &HashMap<String, FileEntry>,
+        carried: Option<EmbeddingSegment>,
+    ) -> Result<EmbeddingSegment, TopoError> {
+        let (model, dimension) = (embedder.model(), embedder.dimension());
+        let mut segment = carried
+            .filter(|segment| segment.is_compatible(model, dimension))
+            .unwrap_or_else(|| EmbeddingSegment::new(model, dimension));
+
+        // Each pending file's text, followed by its chunks' texts
+        let mut pending: Vec<(&FileInfo, usize)> = Vec::new();
+        let mut texts: Vec<String> = Vec::new();
+        for info in files {
+            let Some(entry) = entries.get(&info.path) else {
+                continue;
+            };
+            if segment.get(&info.path, &info.sha256).is_some() {
+                continue;
+            }
+            let Ok(content) = fs::read_to_string(long_path(&self.root.join(&info.path))) else {
+                continue;
+            };
+            texts.push(embed_text(&format!("{}\n{content}", info.path)));
+            let lines: Vec<&str> = content.lines().collect();
+            for (start, end) in chunk_spans(&lines, &entry.chunks) {
+                texts.push(embed_text(&span_text(&lines, start, end)));
+            }
+            pending.push((info, entry.chunks.len()));
+        }
+
+        let mut queue = EmbedQueue::new(embedder, self.embedding.clone());
+        if let Some(progress) = self.embed_progress {
+            queue = queue.progress(progress);
+        }
+        let mut vectors = queue.embed(&texts)?.into_iter();
+        for (info, chunk_count) in pending {
+            let file = vectors.next().unwrap_or_default();
+            let chunks = vectors.by_ref().take(chunk_count).collect();
+            segment.insert(
+                &info.path,
+                FileEmbeddings {
+                    sha256: info.sha256,
+                    file,
+                    chunks,
+                },
+            )?;
+        }
+        Ok(segment)
+    }
+}
+
+/// Bring `store` up to date with `segment`, which replaces `previous`.
+fn sync_vectors(
+    store: &mut dyn VectorStore,
+    previous: Option<&EmbeddingSegment>,
+    segment: &EmbeddingSegment,
+) -> Result<(), TopoError> {
+    if !segment.is_compatible(store.model(), store.dimension()) {
+        return Err(TopoError::index(format!(
+            "vector store holds {} vectors of {} dimensions, but the index has {} of {}",
+            store.model(),
+            store.dimension(),
+            segment.model,
+            segment.dimension
+        )));
+    }
+    for (path, embeddings) in &segment.files {
+        if store.get(path, &embeddings.sha256)?.is_none() {
+            store.upsert(path, embeddings.clone())?;
+        }
+    }
+    let removed = previous
+        .into_iter()
+        .flat_map(|previous| previous.files.keys())
+        .filter(|path| !segment.files.contains_key(*path));
+    for path in removed {
+        store.remove(path)?;
+    }
+    Ok(())
+}
+
+/// Characters of a file or chunk sent for embedding; the rest is left out.
+const MAX_EMBED_CHARS: usize = 8_000;
+
+fn embed_text(text: &str) -> String {
+    match text.char_indices().nth(MAX_EMBED_CHARS) {
+        Some((end, _)) => text[..end].to_string(),
+        None => text.to_string(),
+    }
 }
 
-/// Build a FileEntry from file metadata and content.
-fn build_file_entry(info: &FileInfo, content: &str) -> FileEntry {
+/// Point each import chunk in `entries` at the indexed file it loads.
+///
+/// Every build resolves them afresh, so carried-over entries follow files
+/// that were added or removed since. Entries of files outside `files` keep
+/// their targets.
+fn resolve_imports(entries: &mut [(String, FileEntry)], files: &[FileInfo]) {
+    let resolver = topo_score::ImportResolver::new(entries.iter().map(|(path, _)| path.as_str()));
+    let languages: HashMap<&str, Language> = files
+        .iter()
+        .map(|f| (f.path.as_str(), f.language))
+        .collect();
+    for (path, entry) in entries.iter_mut() {
+        let Some(&language) = languages.get(path.as_str()) else {
+            continue;
+        };
+        for chunk in entry
+            .chunks
+            .iter_mut()
+            .filter(|c| c.kind == ChunkKind::Import)
+        {
+            chunk.target = resolver.resolve(&chunk.name, path, language);
+        }
+    }
+}
+
+/// Trigram index over each file's symbol and filename terms.
+pub(crate) fn build_trigrams(files: &HashMap<String, FileEntry>) -> TrigramIndex {
+    TrigramIndex::build(files.iter().map(|(path, entry)| {
+        let terms = entry
+            .term_frequencies
+            .iter()
+            .filter(|(_, tf)| tf.filename > 0 || tf.symbols > 0)
+            .map(|(term, _)| term.as_str());
+        (path.as_str(), terms)
+    }))
+}
+
+/// How [`build_file_entry`] counts terms.
+struct EntryOptions {
+    /// Count per-chunk term frequencies too.
+    chunk_postings: bool,
+    /// Leave the file's license header out.
+    strip_licenses: bool,
+    comments: CommentMode,
+    /// Count terms by their stems.
+    stemming: bool,
+}
+
+/// Build a FileEntry from file metadata and content, chunked by `chunker`,
+/// with terms counted as `options` says. Per-chunk term frequencies of the
+/// chunks unchanged since the file's `previous` entry are reused.
+fn build_file_entry(
+    info: &FileInfo,
+    content: &str,
+    chunker: &dyn Chunker,
+    previous: Option<&FileEntry>,
+    options: &EntryOptions,
+) -> FileEntry {
+    let (comments, stemming) = (options.comments, options.stemming);
+    let text = if options.strip_licenses {
+        strip_license_header(content)
+    } else {
+        Cow::Borrowed(content)
+    };
     let mut term_frequencies: HashMap<String, TermFreqs> = HashMap::new();
 
     // Tokenize filename for filename field
-    let filename_tokens = tokenize_path(&info.path);
-    for token in &filename_tokens {
-        term_frequencies.entry(token.clone()).or_default().filename += 1;
+    for token in stems(tokenize_path(&info.path), stemming) {
+        term_frequencies.entry(token).or_default().filename += 1;
     }
 
-    // Tokenize content for body field
-    let body_tokens = tokenize_content(content);
+    // Tokenize content for body field, without comments if asked
+    let stripped = (comments != CommentMode::Keep).then(|| strip_comments(&text, info.language));
+    let body = stripped.as_ref().map_or(&*text, |s| s.code.as_str());
+    let comment_docs = stripped
+        .as_ref()
+        .filter(|_| comments == CommentMode::Docs)
+        .map(|s| s.comments.as_str());
+    let body_tokens = stems(tokenize_content(body), stemming);
     let doc_length = body_tokens.len() as u32;
-    for token in &body_tokens {
-        term_frequencies.entry(token.clone()).or_default().body += 1;
+    for token in body_tokens {
+        term_frequencies.entry(token).or_default().body += 1;
+    }
+
+    // Tokenize doc comments and docstrings for docs field, or every comment
+    let docs = match comment_docs {
+        Some(comments) if has_docstrings(info.language) => {
+            let mut docs = doc_comments(&text, info.language);
+            docs.push(comments);
+            docs
+        }
+        Some(comments) => vec![comments],
+        None => doc_comments(&text, info.language),
+    };
+    for doc in docs {
+        for token in stems(tokenize_content(doc), stemming) {
+            term_frequencies.entry(token).or_default().docs += 1;
+        }
     }
 
-    // Extract chunks via regex (fast indexing pass)
-    let chunks = RegexChunker.chunk(content, info.language);
+    // Extract chunks, via regex unless the builder was given a chunker, and
+    // as line windows when it finds none
+    let mut chunks = chunker.chunk_file(&info.path, content, info.language);
+    if chunks.is_empty() {
+        chunks = WindowChunker::default().chunk(content, info.language);
+    }
+    attach_docs(content, &mut chunks, info.language);
+    measure_chunks(content, &mut chunks);
+    let exports = mark_exports(content, &mut chunks, info.language);
+    let chunk_hashes = chunk_hashes(content, &chunks);
 
     // Tokenize chunk names for symbols field
     for chunk in &chunks {
-        if matches!(
-            chunk.kind,
-            ChunkKind::Function | ChunkKind::Type | ChunkKind::Impl
-        ) {
-            let symbol_tokens = tokenize_identifier(&chunk.name);
-            for token in &symbol_tokens {
-                term_frequencies.entry(token.clone()).or_default().symbols += 1;
+        if chunk.kind.is_declaration() || chunk.kind == ChunkKind::Key {
+            for token in stems(symbol_tokens(chunk), stemming) {
+                term_frequencies.entry(token).or_default().symbols += 1;
             }
         }
     }
 
+    let chunk_terms = options.chunk_postings.then(|| {
+        let docstrings = has_docstrings(info.language);
+        let chunk_body = ChunkBody {
+            body,
+            comments: comment_docs,
+            docstrings,
+            stemming,
+        };
+        build_chunk_terms(&text, &chunks, &chunk_body, previous)
+    });
+
     FileEntry {
         sha256: info.sha256,
+        size: info.size,
         chunks,
         term_frequencies,
         doc_length,
+        metadata: Default::default(),
+        chunk_terms,
+        exports,
+        chunk_hashes,
+    }
+}
+
+/// Line prefixes of comments, attributes, and decorators, which belong to
+/// the declaration below them.
+const LEADING_MARKERS: &[&str] = &["//", "/*", "*", "#", "--", "@"];
+
+/// What [`build_chunk_terms`] counts chunk terms from: `body`, the
+/// content perhaps without comments, and when `comments` holds the
+/// content's comments, those as the docs field, along with the chunk's own
+/// docstring in languages with `docstrings`, with terms stemmed when
+/// `stemming`.
+struct ChunkBody<'a> {
+    body: &'a str,
+    comments: Option<&'a str>,
+    docstrings: bool,
+    stemming: bool,
+}
+
+/// Term frequencies of each of `chunks`, counted like those of a file but
+/// without the filename field, over the span described on [`ChunkTerms`],
+/// from the same lines of `source`. Chunks of the `previous` entry with
+/// the same name and [`ChunkTerms::hash`] keep their terms instead of
+/// being tokenized again, so editing one function of a large file only
+/// recounts that function.
+fn build_chunk_terms(
+    content: &str,
+    chunks: &[Chunk],
+    source: &ChunkBody,
+    previous: Option<&FileEntry>,
+) -> Vec<ChunkTerms> {
+    let lines: Vec<&str> = content.lines().collect();
+    let body_lines: Vec<&str> = source.body.lines().collect();
+    let comments = source.comments;
+    let comment_lines: Vec<&str> = comments.map(|c| c.lines().collect()).unwrap_or_default();
+    let reusable: HashMap<(&str, u64), &ChunkTerms> = previous
+        .and_then(|entry| Some(entry.chunks.iter().zip(entry.chunk_terms.as_ref()?)))
+        .into_iter()
+        .flatten()
+        .map(|(chunk, terms)| ((chunk.name.as_str(), terms.hash), terms))
+        .collect();
+    chunks
+        .iter()
+        .zip(chunk_spans(&lines, chunks))
+        .map(|(chunk, (start_line, end_line))| {
+            let text = span_text(&body_lines, start_line, end_line);
+            // The chunk's own doc comment, not those of declarations nested
+            // in it, unless every comment counts as docs
+            let docs = match comments {
+                Some(_) => {
+                    let mut docs = span_text(&comment_lines, start_line, end_line);
+                    if let Some(doc) = chunk.doc.as_deref().filter(|_| source.docstrings) {
+                        docs.push('\n');
+                        docs.push_str(doc);
+                    }
+                    docs
+                }
+                None => chunk.doc.clone().unwrap_or_default(),
+            };
+            let hash = chunk_terms_hash(chunk, &text, &docs);
+            if let Some(terms) = reusable.get(&(chunk.name.as_str(), hash)) {
+                return ChunkTerms {
+                    start_line,
+                    end_line,
+                    ..(*terms).clone()
+                };
+            }
+
+            let mut term_frequencies: HashMap<String, TermFreqs> = HashMap::new();
+            let body_tokens = stems(tokenize_content(&text), source.stemming);
+            let length = body_tokens.len() as u32;
+            for token in body_tokens {
+                term_frequencies.entry(token).or_default().body += 1;
+            }
+            for token in stems(tokenize_content(&docs), source.stemming) {
+                term_frequencies.entry(token).or_default().docs += 1;
+            }
+            if chunk.kind.is_declaration() || chunk.kind == ChunkKind::Key {
+                for token in stems(symbol_tokens(chunk), source.stemming) {
+                    term_frequencies.entry(token).or_default().symbols += 1;
+                }
+            }
+            ChunkTerms {
+                term_frequencies,
+                length,
+                start_line,
+                end_line,
+                hash,
+            }
+        })
+        .collect()
+}
+
+/// [`ChunkTerms::hash`] of `chunk`, whose body `text` and `docs` are what
+/// its terms are counted from.
+fn chunk_terms_hash(chunk: &Chunk, text: &str, docs: &str) -> u64 {
+    let mut hasher = Sha256::new();
+    for part in [
+        chunk.kind.as_str(),
+        &chunk.name,
+        chunk.parent.as_deref().unwrap_or_default(),
+        text,
+        docs,
+    ] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&hasher.finalize()[..8]);
+    u64::from_le_bytes(prefix)
+}
+
+/// 1-based `(start_line, end_line)` of each of `chunks` in `lines`, the
+/// span described on [`ChunkTerms`].
+pub(crate) fn chunk_spans(lines: &[&str], chunks: &[Chunk]) -> Vec<(u32, u32)> {
+    let last = lines.len() as u32;
+    let clamp = |line: u32| line.clamp(1, last.max(1));
+    let mut starts: Vec<u32> = chunks.iter().map(|c| clamp(c.start_line)).collect();
+    starts.sort_unstable();
+    starts.dedup();
+    // Where each declaration's text begins, with the comments above it
+    let leads: Vec<u32> = starts
+        .iter()
+        .enumerate()
+        .map(|(i, &start)| {
+            let floor = if i == 0 { 0 } else { starts[i - 1] };
+            leading_line(lines, start, floor)
+        })
+        .collect();
+
+    chunks
+        .iter()
+        .map(|chunk| {
+            let i = starts.partition_point(|&s| s < clamp(chunk.start_line));
+            let start_line = leads[i];
+            let mut end_line = leads.get(i + 1).map_or(last, |&lead| lead - 1);
+            while end_line > start_line && lines[end_line as usize - 1].trim().is_empty() {
+                end_line -= 1;
+            }
+            (start_line, end_line.max(chunk.end_line).min(last))
+        })
+        .collect()
+}
+
+/// Give each chunk its estimated tokens, over the span described on
+/// [`ChunkTerms`], and each declaration its signature.
+fn measure_chunks(content: &str, chunks: &mut [Chunk]) {
+    let lines: Vec<&str> = content.lines().collect();
+    if lines.is_empty() {
+        return;
     }
+    let spans = chunk_spans(&lines, chunks);
+    for (chunk, (start_line, end_line)) in chunks.iter_mut().zip(spans) {
+        chunk.tokens = (span_text(&lines, start_line, end_line).len() / 4) as u32;
+        if chunk.kind.is_declaration() {
+            chunk.signature = (chunk.start_line as usize)
+                .checked_sub(1)
+                .and_then(|i| lines.get(i))
+                .map(|line| line.trim_end().to_string());
+        }
+    }
+}
+
+/// Lines `start..=end` (1-based) of `lines`, joined.
+fn span_text(lines: &[&str], start: u32, end: u32) -> String {
+    let end = (end as usize).min(lines.len());
+    lines[(start as usize - 1).min(end)..end].join("\n")
+}
+
+/// Whether `language` documents declarations with string literals, which
+/// [`strip_comments`] leaves in the code.
+fn has_docstrings(language: Language) -> bool {
+    matches!(language, Language::Python | Language::Elixir)
+}
+
+/// 1-based first line of the comment and attribute lines directly above
+/// the declaration on `line`, staying below `floor`.
+fn leading_line(lines: &[&str], line: u32, floor: u32) -> u32 {
+    let mut first = line;
+    while first > floor + 1 {
+        let above = lines[first as usize - 2].trim_start();
+        if !LEADING_MARKERS.iter().any(|m| above.starts_with(m)) {
+            break;
+        }
+        first -= 1;
+    }
+    first
+}
+
+/// Re-key an entry to a new path after a rename.
+///
+/// Content-derived data (chunks, body and symbol terms) carries over; only
+/// the filename field is recomputed from the new path.
+/// The scanned file `entry` was built from, as far as the index records it.
+fn indexed_file(path: &str, entry: &FileEntry) -> FileInfo {
+    FileInfo {
+        path: path.to_string(),
+        size: entry.size,
+        language: Language::from_path(Path::new(path)),
+        role: FileRole::from_path(Path::new(path)),
+        sha256: entry.sha256,
+        package: None,
+    }
+}
+
+pub(crate) fn retarget_entry(entry: &FileEntry, path: &str) -> FileEntry {
+    let mut term_frequencies = entry.term_frequencies.clone();
+    for tf in term_frequencies.values_mut() {
+        tf.filename = 0;
+    }
+    for token in tokenize_path(path) {
+        term_frequencies.entry(token).or_default().filename += 1;
+    }
+    term_frequencies.retain(|_, tf| tf.filename + tf.symbols + tf.body + tf.docs > 0);
+
+    FileEntry {
+        term_frequencies,
+        ..entry.clone()
+    }
+}
+
+/// Last component of a repo-relative path.
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
 }
 
 /// Tokenize a file path into search terms.
@@ -180,6 +1005,28 @@ fn tokenize_content(content: &str) -> Vec<String> {
         .collect()
 }
 
+/// Symbol terms of a definition chunk: its name, and for a method the
+/// type it belongs to, so `Auth::check` finds `check` in `impl Auth`.
+/// Config keys may be dotted or dashed paths, `database.pool-size`.
+fn symbol_tokens(chunk: &Chunk) -> Vec<String> {
+    if chunk.kind == ChunkKind::Key {
+        return tokenize_content(&chunk.name);
+    }
+    let mut tokens = tokenize_identifier(&chunk.name);
+    if let Some(parent) = &chunk.parent {
+        tokens.extend(tokenize_identifier(parent));
+    }
+    tokens
+}
+
+/// `tokens` by their [`topo_score::stem`] when `stemming`.
+fn stems(tokens: Vec<String>, stemming: bool) -> Vec<String> {
+    if !stemming {
+        return tokens;
+    }
+    tokens.iter().map(|t| topo_score::stem(t)).collect()
+}
+
 /// Tokenize a single identifier (function/type name).
 fn tokenize_identifier(name: &str) -> Vec<String> {
     name.split('_')
@@ -229,6 +1076,7 @@ mod tests {
     use super::*;
     use std::fs;
     use topo_core::{ChunkKind, Language};
+    use topo_treesit::RegexChunker;
 
     fn make_file_info(path: &str, content: &str) -> FileInfo {
         use sha2::{Digest, Sha256};
@@ -242,6 +1090,7 @@ mod tests {
             language: Language::from_path(Path::new(path)),
             role: topo_core::FileRole::from_path(Path::new(path)),
             sha256: hash,
+            package: None,
         }
     }
 
@@ -259,6 +1108,501 @@ mod tests {
         assert!(index.files.contains_key("main.rs"));
     }
 
+    #[test]
+    fn methods_add_their_parent_to_symbols() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = "class Auth:\n    def check(self):\n        pass\n";
+        fs::write(dir.path().join("auth.py"), content).unwrap();
+
+        let files = vec![make_file_info("auth.py", content)];
+        let index = IndexBuilder::new(dir.path()).build(&files, None).unwrap().0;
+        let entry = &index.files["auth.py"];
+        let check = entry.chunks.iter().find(|c| c.name == "check").unwrap();
+        assert_eq!(check.kind, ChunkKind::Method);
+        assert_eq!(check.qualified_name(), "Auth::check");
+        // The class and its method
+        assert_eq!(entry.term_frequencies["auth"].symbols, 2);
+        assert_eq!(entry.term_frequencies["check"].symbols, 1);
+    }
+
+    #[test]
+    fn chunks_carry_signatures_and_tokens() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = "use std::io;\n\n/// Checks the token.\npub fn check(token: &str) -> bool {   \n    !token.is_empty()\n}\n";
+        fs::write(dir.path().join("auth.rs"), content).unwrap();
+
+        let files = vec![make_file_info("auth.rs", content)];
+        let index = IndexBuilder::new(dir.path()).build(&files, None).unwrap().0;
+        let chunks = &index.files["auth.rs"].chunks;
+        let import = chunks.iter().find(|c| c.kind == ChunkKind::Import).unwrap();
+        assert_eq!(import.signature, None);
+        assert_eq!(import.tokens, "use std::io;".len() as u32 / 4);
+        let check = chunks.iter().find(|c| c.name == "check").unwrap();
+        assert_eq!(
+            check.signature.as_deref(),
+            Some("pub fn check(token: &str) -> bool {")
+        );
+        // From its doc comment to the end of the file
+        let span = content.find("///").unwrap();
+        assert_eq!(check.tokens, (content.trim_end().len() - span) as u32 / 4);
+    }
+
+    #[test]
+    fn config_keys_fill_symbols() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = "database:\n  connection-pool: 20\nlog_level: info\n";
+        fs::write(dir.path().join("production.yaml"), content).unwrap();
+
+        let files = vec![make_file_info("production.yaml", content)];
+        let index = IndexBuilder::new(dir.path()).build(&files, None).unwrap().0;
+        let entry = &index.files["production.yaml"];
+        let names: Vec<&str> = entry.chunks.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["database", "log_level"]);
+        assert_eq!(entry.term_frequencies["database"].symbols, 1);
+        assert_eq!(entry.term_frequencies["level"].symbols, 1);
+        // Nested keys are body text only
+        assert_eq!(entry.term_frequencies["pool"].symbols, 0);
+    }
+
+    #[test]
+    fn doc_comments_fill_docs_field() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = "/// Verifies the session token.\nfn check() {\n    // session cache\n}\n";
+        fs::write(dir.path().join("auth.rs"), content).unwrap();
+
+        let files = vec![make_file_info("auth.rs", content)];
+        let index = IndexBuilder::new(dir.path()).build(&files, None).unwrap().0;
+        let tf = &index.files["auth.rs"].term_frequencies;
+        assert_eq!(tf["token"].docs, 1);
+        assert_eq!(tf["token"].body, 1);
+        // Plain comments are body only
+        assert_eq!(tf["session"].docs, 1);
+        assert_eq!(tf["session"].body, 2);
+        assert_eq!(tf["cache"].docs, 0);
+        // and the function they document carries them
+        let chunk = &index.files["auth.rs"].chunks[0];
+        assert_eq!(chunk.name, "check");
+        assert_eq!(chunk.doc.as_deref(), Some("Verifies the session token."));
+    }
+
+    #[test]
+    fn license_headers_are_left_out_of_term_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = "// Copyright 2024 Example Corp.\n// Licensed under the MIT license.\n\nfn checkout() {}\n";
+        fs::write(dir.path().join("cart.rs"), content).unwrap();
+        let files = vec![make_file_info("cart.rs", content)];
+
+        let index = IndexBuilder::new(dir.path()).build(&files, None).unwrap().0;
+        let entry = &index.files["cart.rs"];
+        assert!(!entry.term_frequencies.contains_key("copyright"));
+        assert!(!index.doc_frequencies.contains_key("licensed"));
+        assert_eq!(entry.doc_length, 2);
+        assert_eq!(entry.chunks[0].start_line, 4);
+
+        let kept = IndexBuilder::new(dir.path())
+            .strip_license_headers(false)
+            .build(&files, None)
+            .unwrap()
+            .0;
+        assert_eq!(kept.files["cart.rs"].term_frequencies["copyright"].body, 1);
+    }
+
+    #[test]
+    fn comments_can_be_stripped_or_counted_as_docs() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = "/// Checks the session.\nfn check() {\n    // legacy_check(session);\n    verify();\n}\n";
+        fs::write(dir.path().join("auth.rs"), content).unwrap();
+        let files = vec![make_file_info("auth.rs", content)];
+        let build = |mode| {
+            IndexBuilder::new(dir.path())
+                .chunk_postings(true)
+                .comments(mode)
+                .build(&files, None)
+                .unwrap()
+                .0
+                .files
+                .remove("auth.rs")
+                .unwrap()
+        };
+
+        let kept = build(CommentMode::Keep);
+        assert_eq!(kept.term_frequencies["legacy"].body, 1);
+        assert_eq!(kept.term_frequencies["session"].docs, 1);
+
+        let stripped = build(CommentMode::Strip);
+        assert!(!stripped.term_frequencies.contains_key("legacy"));
+        assert_eq!(stripped.term_frequencies["session"].body, 0);
+        assert_eq!(stripped.term_frequencies["session"].docs, 1);
+        assert_eq!(stripped.term_frequencies["verify"].body, 1);
+        assert!(stripped.doc_length < kept.doc_length);
+        let chunk = &stripped.chunk_terms.as_ref().unwrap()[0];
+        assert!(!chunk.term_frequencies.contains_key("legacy"));
+        assert_eq!(chunk.term_frequencies["session"].docs, 1);
+
+        let docs = build(CommentMode::Docs);
+        assert_eq!(docs.term_frequencies["legacy"].body, 0);
+        assert_eq!(docs.term_frequencies["legacy"].docs, 1);
+        assert_eq!(docs.term_frequencies["session"].docs, 2);
+        let chunk = &docs.chunk_terms.as_ref().unwrap()[0];
+        assert_eq!(chunk.term_frequencies["legacy"].docs, 1);
+        assert_eq!(chunk.term_frequencies["session"].docs, 2);
+        assert_eq!(
+            chunk.length,
+            stripped.chunk_terms.as_ref().unwrap()[0].length
+        );
+    }
+
+    #[test]
+    fn copied_chunks_are_grouped_across_builds() {
+        let dir = tempfile::tempdir().unwrap();
+        let parse = "fn parse(input: &str) -> u32 {\n    let n = input.trim();\n    n.parse().unwrap()\n}\n";
+        fs::create_dir(dir.path().join("vendor")).unwrap();
+        fs::write(dir.path().join("parse.rs"), parse).unwrap();
+        fs::write(dir.path().join("vendor/parse.rs"), parse).unwrap();
+        let files = vec![
+            make_file_info("parse.rs", parse),
+            make_file_info("vendor/parse.rs", parse),
+        ];
+
+        let index = IndexBuilder::new(dir.path()).build(&files, None).unwrap().0;
+        assert_eq!(index.duplicate_chunks.len(), 1);
+        let hash = index.files["parse.rs"].chunk_hashes[0];
+        assert_eq!(index.duplicate_files(hash), 2);
+
+        // Unchanged files keep their hashes, so the group survives
+        // incremental builds until a copy changes
+        let index = IndexBuilder::new(dir.path())
+            .build(&files, Some(&index))
+            .unwrap()
+            .0;
+        assert_eq!(index.duplicate_files(hash), 2);
+        let changed = "fn parse() {}\n";
+        fs::write(dir.path().join("vendor/parse.rs"), changed).unwrap();
+        let files = vec![files[0].clone(), make_file_info("vendor/parse.rs", changed)];
+        let index = IndexBuilder::new(dir.path())
+            .build(&files, Some(&index))
+            .unwrap()
+            .0;
+        assert!(index.duplicate_chunks.is_empty());
+    }
+
+    #[test]
+    fn edited_files_reuse_unchanged_chunk_terms() {
+        let dir = tempfile::tempdir().unwrap();
+        let before = "fn login() {\n    check();\n}\n\nfn logout() {\n    clear();\n}\n";
+        fs::write(dir.path().join("auth.rs"), before).unwrap();
+        let builder = IndexBuilder::new(dir.path()).chunk_postings(true);
+        let (mut existing, _) = builder
+            .build(&[make_file_info("auth.rs", before)], None)
+            .unwrap();
+        // Mark the old terms, to tell reused ones from recounted ones
+        let old = existing.files.get_mut("auth.rs").unwrap();
+        for terms in old.chunk_terms.as_mut().unwrap() {
+            terms.length = 99;
+        }
+
+        let after =
+            "fn login() {\n    check();\n    audit();\n}\n\nfn logout() {\n    clear();\n}\n";
+        fs::write(dir.path().join("auth.rs"), after).unwrap();
+        let files = [make_file_info("auth.rs", after)];
+        let (index, reindexed) = builder.build(&files, Some(&existing)).unwrap();
+        assert_eq!(reindexed, 1);
+        let terms = index.files["auth.rs"].chunk_terms.as_ref().unwrap();
+        assert_ne!(terms[0].length, 99, "login changed");
+        assert!(terms[0].term_frequencies.contains_key("audit"));
+        assert_eq!(terms[1].length, 99, "logout is reused");
+        assert_eq!((terms[1].start_line, terms[1].end_line), (6, 8));
+
+        // Apart from the mark, the same terms as a fresh build
+        let (fresh, _) = builder.build(&files, None).unwrap();
+        let fresh = fresh.files["auth.rs"].chunk_terms.as_ref().unwrap();
+        assert_eq!(terms[0], fresh[0]);
+        assert_eq!(terms[1].term_frequencies, fresh[1].term_frequencies);
+        assert_eq!(terms[1].hash, fresh[1].hash);
+    }
+
+    #[test]
+    fn lockfiles_are_indexed_by_filename_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let lock = "[[package]]\nname = \"serde\"\nversion = \"1.0.0\"\n";
+        fs::write(dir.path().join("Cargo.lock"), lock).unwrap();
+        let files = vec![make_file_info("Cargo.lock", lock)];
+
+        let index = IndexBuilder::new(dir.path()).build(&files, None).unwrap().0;
+        let entry = &index.files["Cargo.lock"];
+        assert_eq!(entry.term_frequencies["cargo"].filename, 1);
+        assert!(!entry.term_frequencies.contains_key("serde"));
+        assert_eq!(entry.doc_length, 0);
+
+        let index = IndexBuilder::new(dir.path())
+            .lockfile_bodies(true)
+            .build(&files, None)
+            .unwrap()
+            .0;
+        assert_eq!(index.files["Cargo.lock"].term_frequencies["serde"].body, 1);
+    }
+
+    #[test]
+    fn chunker_replaces_regex_chunks() {
+        struct Whole;
+        impl Chunker for Whole {
+            fn chunk(&self, _: &str, _: Language) -> Vec<Chunk> {
+                Vec::new()
+            }
+            fn chunk_file(&self, path: &str, content: &str, _: Language) -> Vec<Chunk> {
+                vec![Chunk {
+                    kind: ChunkKind::Type,
+                    name: path.replace(".rs", "_module"),
+                    start_line: 1,
+                    end_line: content.lines().count() as u32,
+                    content: String::new(),
+                    target: None,
+                    doc: None,
+                    parent: None,
+                    signature: None,
+                    tokens: 0,
+                    exported: false,
+                }]
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let source = "fn a() {}
+fn b() {}
+";
+        fs::write(dir.path().join("ab.rs"), source).unwrap();
+        let files = vec![make_file_info("ab.rs", source)];
+        let index = IndexBuilder::new(dir.path())
+            .chunker(&Whole)
+            .build(&files, None)
+            .unwrap()
+            .0;
+        let entry = &index.files["ab.rs"];
+        assert_eq!(entry.chunks.len(), 1);
+        assert_eq!(entry.chunks[0].end_line, 2);
+        assert_eq!(entry.term_frequencies["module"].symbols, 1);
+    }
+
+    #[test]
+    fn files_without_chunks_get_line_windows() {
+        let dir = tempfile::tempdir().unwrap();
+        let notes: String = (1..=60).map(|i| format!("note {i}\n")).collect();
+        fs::write(dir.path().join("notes.txt"), &notes).unwrap();
+        let files = vec![make_file_info("notes.txt", &notes)];
+        let index = IndexBuilder::new(dir.path())
+            .chunk_postings(true)
+            .build(&files, None)
+            .unwrap()
+            .0;
+        let entry = &index.files["notes.txt"];
+        let windows: Vec<(&str, u32, u32)> = entry
+            .chunks
+            .iter()
+            .map(|c| (c.name.as_str(), c.start_line, c.end_line))
+            .collect();
+        assert_eq!(windows, [("lines 1-50", 1, 50), ("lines 41-60", 41, 60)]);
+        let terms = entry.chunk_terms.as_ref().unwrap();
+        assert_eq!((terms[0].start_line, terms[0].end_line), (1, 50));
+        // Window names aren't symbols
+        assert!(!entry.term_frequencies.contains_key("lines"));
+    }
+
+    #[test]
+    fn registry_chunks_matching_files() {
+        struct Rules;
+        impl Chunker for Rules {
+            fn chunk(&self, content: &str, _: Language) -> Vec<Chunk> {
+                (1..)
+                    .zip(content.lines())
+                    .filter_map(|(line, text)| {
+                        Some(Chunk {
+                            kind: ChunkKind::Function,
+                            name: text.strip_prefix("rule ")?.to_string(),
+                            start_line: line,
+                            end_line: line,
+                            content: String::new(),
+                            target: None,
+                            doc: None,
+                            parent: None,
+                            signature: None,
+                            tokens: 0,
+                            exported: false,
+                        })
+                    })
+                    .collect()
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("rules")).unwrap();
+        let rules = "rule deny_guests\nrule allow_admins\n";
+        let source = "fn check() {}\n";
+        fs::write(dir.path().join("rules/auth.policy"), rules).unwrap();
+        fs::write(dir.path().join("main.rs"), source).unwrap();
+        let files = vec![
+            make_file_info("rules/auth.policy", rules),
+            make_file_info("main.rs", source),
+        ];
+        let registry = topo_treesit::ChunkerRegistry::new()
+            .glob("rules/*.policy", Rules)
+            .unwrap();
+        let index = IndexBuilder::new(dir.path())
+            .chunker(&registry)
+            .build(&files, None)
+            .unwrap()
+            .0;
+        let names = |path: &str| -> Vec<String> {
+            index.files[path]
+                .chunks
+                .iter()
+                .map(|c| c.name.clone())
+                .collect()
+        };
+        assert_eq!(names("rules/auth.policy"), ["deny_guests", "allow_admins"]);
+        assert_eq!(names("main.rs"), ["check"]);
+    }
+
+    #[test]
+    fn build_reports_progress_per_file() {
+        use std::sync::Mutex;
+
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.rs"), "fn a() {}").unwrap();
+        fs::write(dir.path().join("b.rs"), "fn b() {}").unwrap();
+        let mut files = vec![
+            make_file_info("a.rs", "fn a() {}"),
+            make_file_info("b.rs", "fn b() {}"),
+        ];
+        // Unreadable files still count towards progress
+        files.push(make_file_info("missing.rs", ""));
+
+        let seen = Mutex::new(Vec::new());
+        let callback = |done: usize, total: usize| seen.lock().unwrap().push((done, total));
+        IndexBuilder::new(dir.path())
+            .progress(&callback)
+            .build(&files, None)
+            .unwrap();
+
+        let mut seen = seen.into_inner().unwrap();
+        seen.sort();
+        assert_eq!(seen, vec![(1, 3), (2, 3), (3, 3)]);
+    }
+
+    #[test]
+    fn incremental_build_records_deleted_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.rs"), "fn a() {}").unwrap();
+        fs::write(dir.path().join("old_auth.rs"), "fn auth() {}").unwrap();
+        let builder = IndexBuilder::new(dir.path());
+        let (existing, _) = builder
+            .build(
+                &[
+                    make_file_info("a.rs", "fn a() {}"),
+                    make_file_info("old_auth.rs", "fn auth() {}"),
+                ],
+                None,
+            )
+            .unwrap();
+        assert!(existing.tombstones.is_empty());
+
+        let (index, _) = builder
+            .build(&[make_file_info("a.rs", "fn a() {}")], Some(&existing))
+            .unwrap();
+        assert_eq!(index.tombstones.len(), 1);
+        assert_eq!(index.tombstones[0].path, "old_auth.rs");
+        assert!(!index.files.contains_key("old_auth.rs"));
+    }
+
+    #[test]
+    fn incremental_build_keeps_files_outside_a_sparse_checkout() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.rs"), "fn a() {}").unwrap();
+        fs::write(dir.path().join("docs.md"), "# auth guide").unwrap();
+        fs::write(dir.path().join("old.rs"), "fn old() {}").unwrap();
+        let all = [
+            make_file_info("a.rs", "fn a() {}"),
+            make_file_info("docs.md", "# auth guide"),
+            make_file_info("old.rs", "fn old() {}"),
+        ];
+        let (existing, _) = IndexBuilder::new(dir.path()).build(&all, None).unwrap();
+
+        // docs.md is left out of the checkout, old.rs really was deleted
+        fs::remove_file(dir.path().join("docs.md")).unwrap();
+        fs::remove_file(dir.path().join("old.rs")).unwrap();
+        let absent: HashSet<String> = ["docs.md".to_string()].into();
+        let (index, reindexed) = IndexBuilder::new(dir.path())
+            .sparse_checkout(&absent)
+            .build(&all[..1], Some(&existing))
+            .unwrap();
+        assert_eq!(reindexed, 0);
+        assert_eq!(index.files["docs.md"], existing.files["docs.md"]);
+        assert!(!index.files.contains_key("old.rs"));
+        assert_eq!(index.total_docs, 2);
+        assert_eq!(index.doc_frequencies.get("guide"), Some(&1));
+        let tombstoned: Vec<&str> = index.tombstones.iter().map(|t| t.path.as_str()).collect();
+        assert_eq!(tombstoned, ["old.rs"]);
+    }
+
+    #[test]
+    fn incremental_build_carries_over_renamed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = "pub fn authenticate() {}";
+        fs::create_dir_all(dir.path().join("src/auth")).unwrap();
+        fs::write(dir.path().join("src/login.rs"), content).unwrap();
+
+        let builder = IndexBuilder::new(dir.path());
+        let (existing, _) = builder
+            .build(&[make_file_info("src/login.rs", content)], None)
+            .unwrap();
+        fs::rename(
+            dir.path().join("src/login.rs"),
+            dir.path().join("src/auth/session.rs"),
+        )
+        .unwrap();
+
+        let (index, reindexed) = builder
+            .build(
+                &[make_file_info("src/auth/session.rs", content)],
+                Some(&existing),
+            )
+            .unwrap();
+
+        assert_eq!(reindexed, 0);
+        let entry = &index.files["src/auth/session.rs"];
+        assert!(entry.term_frequencies["session"].filename > 0);
+        assert!(!entry.term_frequencies.contains_key("login"));
+        assert!(entry.chunks.iter().any(|c| c.name == "authenticate"));
+        assert!(index.tombstones.is_empty());
+    }
+
+    #[test]
+    fn incremental_build_follows_moved_and_edited_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let before = "pub fn authenticate(token: &str) -> bool {\n    !token.is_empty()\n}\n";
+        let after = "pub fn authenticate(token: &str) -> bool {\n    !token.trim().is_empty()\n}\n";
+        fs::create_dir_all(dir.path().join("src/auth")).unwrap();
+        fs::write(dir.path().join("src/auth.rs"), before).unwrap();
+
+        let builder = IndexBuilder::new(dir.path());
+        let (existing, _) = builder
+            .build(&[make_file_info("src/auth.rs", before)], None)
+            .unwrap();
+        fs::remove_file(dir.path().join("src/auth.rs")).unwrap();
+        fs::write(dir.path().join("src/auth/mod.rs"), after).unwrap();
+
+        let (index, reindexed) = builder
+            .build(&[make_file_info("src/auth/mod.rs", after)], Some(&existing))
+            .unwrap();
+
+        assert_eq!(reindexed, 1);
+        assert!(!index.files.contains_key("src/auth.rs"));
+        let entry = &index.files["src/auth/mod.rs"];
+        assert!(entry.term_frequencies.contains_key("trim"));
+        assert!(entry.chunks.iter().any(|c| c.name == "authenticate"));
+        // A move, not a deletion
+        assert!(index.tombstones.is_empty());
+    }
+
     #[test]
     fn index_term_frequencies() {
         let dir = tempfile::tempdir().unwrap();
@@ -329,6 +1673,452 @@ mod tests {
         assert_eq!(index.doc_frequencies.get("authenticate"), Some(&2));
     }
 
+    #[test]
+    fn index_stores_dependency_graph() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = [
+            ("app.py", "import auth\nimport db\n"),
+            ("auth.py", "import db\n"),
+            ("db.py", "def connect():\n    pass\n"),
+        ];
+        for (path, content) in files {
+            fs::write(dir.path().join(path), content).unwrap();
+        }
+        let infos: Vec<FileInfo> = files
+            .iter()
+            .map(|(path, content)| make_file_info(path, content))
+            .collect();
+
+        let index = IndexBuilder::new(dir.path()).build(&infos, None).unwrap().0;
+        assert_eq!(index.imports("app.py"), ["auth.py", "db.py"]);
+        assert_eq!(index.imported_by("db.py"), vec!["app.py", "auth.py"]);
+    }
+
+    #[test]
+    fn import_chunks_point_at_their_targets() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |path: &str, content: &str| {
+            fs::write(dir.path().join(path), content).unwrap();
+            make_file_info(path, content)
+        };
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        let lib = write("src/lib.rs", "mod auth;\nmod db;\nuse std::io;\n");
+        let auth = write("src/auth.rs", "use crate::db::Pool;\n");
+        let targets = |index: &DeepIndex, path: &str| -> Vec<(String, Option<String>)> {
+            index.files[path]
+                .chunks
+                .iter()
+                .filter(|c| c.kind == ChunkKind::Import)
+                .map(|c| (c.name.clone(), c.target.clone()))
+                .collect()
+        };
+
+        let (existing, _) = IndexBuilder::new(dir.path())
+            .build(&[lib.clone(), auth.clone()], None)
+            .unwrap();
+        assert_eq!(
+            targets(&existing, "src/lib.rs"),
+            [
+                ("mod auth;".to_string(), Some("src/auth.rs".to_string())),
+                ("mod db;".to_string(), None),
+                ("use std::io;".to_string(), None),
+            ]
+        );
+        assert_eq!(
+            targets(&existing, "src/auth.rs"),
+            [(
+                "use crate::db::Pool;".to_string(),
+                Some("src/lib.rs".to_string())
+            )]
+        );
+
+        // Unchanged entries follow a target that appears later
+        let db = write("src/db.rs", "pub struct Pool;\n");
+        let (index, reindexed) = IndexBuilder::new(dir.path())
+            .build(&[lib, auth, db], Some(&existing))
+            .unwrap();
+        assert_eq!(reindexed, 1);
+        assert_eq!(
+            targets(&index, "src/lib.rs")[1].1.as_deref(),
+            Some("src/db.rs")
+        );
+        assert_eq!(
+            targets(&index, "src/auth.rs")[0].1.as_deref(),
+            Some("src/db.rs")
+        );
+    }
+
+    #[test]
+    fn trigrams_are_opt_in_and_kept_incrementally() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = "pub fn check_authorization() {}\n";
+        fs::write(dir.path().join("policy.rs"), content).unwrap();
+        let files = vec![make_file_info("policy.rs", content)];
+
+        let plain = IndexBuilder::new(dir.path()).build(&files, None).unwrap().0;
+        assert!(plain.trigrams.is_none());
+
+        let fuzzy = IndexBuilder::new(dir.path())
+            .trigrams(true)
+            .build(&files, None)
+            .unwrap()
+            .0;
+        let trigrams = fuzzy.trigrams.as_ref().unwrap();
+        let matches = trigrams.similar_terms("authz", topo_core::DEFAULT_MIN_SIMILARITY);
+        assert_eq!(matches[0].term, "authorization");
+        assert_eq!(trigrams.files_with("authorization"), vec!["policy.rs"]);
+
+        let rebuilt = IndexBuilder::new(dir.path())
+            .build(&files, Some(&fuzzy))
+            .unwrap()
+            .0;
+        assert!(rebuilt.trigrams.is_some());
+    }
+
+    /// Embeds each text as `[chars, 1.0]`, counting the texts it is sent.
+    struct CountingEmbedder {
+        model: &'static str,
+        texts: std::sync::atomic::AtomicUsize,
+    }
+
+    impl Embedder for CountingEmbedder {
+        fn model(&self) -> &str {
+            self.model
+        }
+
+        fn dimension(&self) -> u32 {
+            2
+        }
+
+        fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, topo_core::EmbedError> {
+            self.texts
+                .fetch_add(texts.len(), std::sync::atomic::Ordering::SeqCst);
+            Ok(texts
+                .iter()
+                .map(|t| vec![t.chars().count() as f32, 1.0])
+                .collect())
+        }
+    }
+
+    #[test]
+    fn embedder_embeds_only_new_files_and_chunks() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let dir = tempfile::tempdir().unwrap();
+        let auth = "fn login() {}\n\nfn logout() {}\n";
+        fs::write(dir.path().join("auth.rs"), auth).unwrap();
+        let files = vec![make_file_info("auth.rs", auth)];
+        let embedder = CountingEmbedder {
+            model: "mini",
+            texts: AtomicUsize::new(0),
+        };
+
+        let index = IndexBuilder::new(dir.path())
+            .embedder(&embedder, EmbeddingConfig::default())
+            .build(&files, None)
+            .unwrap()
+            .0;
+        let segment = index.embeddings.as_ref().unwrap();
+        let vectors = segment.get("auth.rs", &files[0].sha256).unwrap();
+        assert_eq!(
+            vectors.file,
+            vec![(auth.len() + "auth.rs\n".len()) as f32, 1.0]
+        );
+        assert_eq!(vectors.chunks.len(), 2);
+        assert_eq!(vectors.chunks[0][0], "fn login() {}".len() as f32);
+        assert_eq!(embedder.texts.load(Ordering::SeqCst), 3);
+
+        // Unchanged files keep their vectors
+        let main = "fn main() {}\n";
+        fs::write(dir.path().join("main.rs"), main).unwrap();
+        let files = vec![files[0].clone(), make_file_info("main.rs", main)];
+        let updated = IndexBuilder::new(dir.path())
+            .embedder(&embedder, EmbeddingConfig::default())
+            .build(&files, Some(&index))
+            .unwrap()
+            .0;
+        assert_eq!(updated.embeddings.as_ref().unwrap().len(), 2);
+        assert_eq!(embedder.texts.load(Ordering::SeqCst), 5);
+
+        // A different model starts over
+        let other = CountingEmbedder {
+            model: "large",
+            texts: AtomicUsize::new(0),
+        };
+        let switched = IndexBuilder::new(dir.path())
+            .embedder(&other, EmbeddingConfig::default())
+            .build(&files, Some(&updated))
+            .unwrap()
+            .0;
+        assert_eq!(switched.embeddings.as_ref().unwrap().len(), 2);
+        assert_eq!(other.texts.load(Ordering::SeqCst), 5);
+    }
+
+    #[test]
+    fn embedder_reembeds_changed_files_and_drops_deleted() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let dir = tempfile::tempdir().unwrap();
+        let write = |path: &str, content: &str| {
+            fs::write(dir.path().join(path), content).unwrap();
+            make_file_info(path, content)
+        };
+        let files = vec![
+            write("a.rs", "fn a() {}\n"),
+            write("b.rs", "fn b() {}\n"),
+            write("gone.rs", "fn gone() {}\n"),
+        ];
+        let embedder = CountingEmbedder {
+            model: "mini",
+            texts: AtomicUsize::new(0),
+        };
+        let builder = IndexBuilder::new(dir.path()).embedder(&embedder, EmbeddingConfig::default());
+        let index = builder.build(&files, None).unwrap().0;
+        assert_eq!(embedder.texts.load(Ordering::SeqCst), 6);
+
+        // Edit b.rs, delete gone.rs
+        let files = vec![files[0].clone(), write("b.rs", "fn b() { 2 }\n")];
+        let updated = builder.build(&files, Some(&index)).unwrap().0;
+        assert_eq!(embedder.texts.load(Ordering::SeqCst), 8);
+        let segment = updated.embeddings.as_ref().unwrap();
+        assert_eq!(segment.len(), 2);
+        assert!(segment.get("b.rs", &files[1].sha256).is_some());
+        assert_eq!(
+            segment.get("a.rs", &files[0].sha256),
+            index
+                .embeddings
+                .as_ref()
+                .unwrap()
+                .get("a.rs", &files[0].sha256)
+        );
+
+        // Without an embedder, vectors of unchanged files are still kept
+        let files = vec![files[0].clone(), write("b.rs", "fn b() { 3 }\n")];
+        let plain = IndexBuilder::new(dir.path())
+            .build(&files, Some(&updated))
+            .unwrap()
+            .0;
+        let segment = plain.embeddings.as_ref().unwrap();
+        assert_eq!(segment.len(), 1);
+        assert!(segment.get("a.rs", &files[0].sha256).is_some());
+    }
+
+    #[test]
+    fn vector_store_follows_the_index() {
+        use std::sync::atomic::AtomicUsize;
+
+        let dir = tempfile::tempdir().unwrap();
+        let write = |path: &str, content: &str| {
+            fs::write(dir.path().join(path), content).unwrap();
+            make_file_info(path, content)
+        };
+        let files = vec![write("a.rs", "fn a() {}\n"), write("b.rs", "fn b() {}\n")];
+        let embedder = CountingEmbedder {
+            model: "mini",
+            texts: AtomicUsize::new(0),
+        };
+
+        let mut store = EmbeddingSegment::new("mini", 2);
+        let index = IndexBuilder::new(dir.path())
+            .embedder(&embedder, EmbeddingConfig::default())
+            .vector_store(&mut store)
+            .build(&files, None)
+            .unwrap()
+            .0;
+        assert_eq!(Some(&store), index.embeddings.as_ref());
+
+        // Changes and deletions reach the store too
+        let files = vec![write("a.rs", "fn a() { 1 }\n")];
+        let updated = IndexBuilder::new(dir.path())
+            .embedder(&embedder, EmbeddingConfig::default())
+            .vector_store(&mut store)
+            .build(&files, Some(&index))
+            .unwrap()
+            .0;
+        assert_eq!(Some(&store), updated.embeddings.as_ref());
+        assert_eq!(store.len(), 1);
+
+        let mut other = EmbeddingSegment::new("large", 2);
+        let err = IndexBuilder::new(dir.path())
+            .vector_store(&mut other)
+            .build(&files, Some(&updated))
+            .unwrap_err();
+        assert!(err.to_string().contains("vector store holds large vectors"));
+    }
+
+    #[test]
+    fn stemming_indexes_stems_and_reindexes_when_toggled() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = "/// Authenticates sessions.\nfn authenticate_users() {}\n";
+        fs::write(dir.path().join("authentication.rs"), content).unwrap();
+        let files = vec![make_file_info("authentication.rs", content)];
+
+        let (stemmed, _) = IndexBuilder::new(dir.path())
+            .stemming(true)
+            .chunk_postings(true)
+            .build(&files, None)
+            .unwrap();
+        assert!(stemmed.stemmed);
+        let entry = &stemmed.files["authentication.rs"];
+        let tf = &entry.term_frequencies["authent"];
+        assert_eq!((tf.filename, tf.symbols, tf.docs), (1, 1, 1));
+        assert_eq!(entry.term_frequencies["user"].symbols, 1);
+        assert!(!entry.term_frequencies.contains_key("authenticate"));
+        let chunk = &entry.chunk_terms.as_ref().unwrap()[0];
+        assert_eq!(chunk.term_frequencies["authent"].symbols, 1);
+
+        // Entries with other stems are never carried over
+        let (plain, reindexed) = IndexBuilder::new(dir.path())
+            .build(&files, Some(&stemmed))
+            .unwrap();
+        assert_eq!(reindexed, 1);
+        assert!(!plain.stemmed);
+        assert!(
+            plain.files["authentication.rs"]
+                .term_frequencies
+                .contains_key("authenticate")
+        );
+    }
+
+    #[test]
+    fn chunk_postings_are_opt_in_and_fill_carried_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = "fn login() {\n    let session = 1;\n}\n\n/// Ends the session.\nfn logout() {\n    drop(1);\n}\n";
+        fs::write(dir.path().join("auth.rs"), content).unwrap();
+        let files = vec![make_file_info("auth.rs", content)];
+
+        let plain = IndexBuilder::new(dir.path()).build(&files, None).unwrap().0;
+        assert!(!plain.chunk_postings);
+        assert!(plain.files["auth.rs"].chunk_terms.is_none());
+
+        // Turning chunk postings on reindexes files that lack them
+        let (chunked, reindexed) = IndexBuilder::new(dir.path())
+            .chunk_postings(true)
+            .build(&files, Some(&plain))
+            .unwrap();
+        assert_eq!(reindexed, 1);
+        assert!(chunked.chunk_postings);
+        let entry = &chunked.files["auth.rs"];
+        let terms = entry.chunk_terms.as_ref().unwrap();
+        assert_eq!(terms.len(), entry.chunks.len());
+        let login = entry.chunks.iter().position(|c| c.name == "login").unwrap();
+        assert_eq!(terms[login].term_frequencies["login"].symbols, 1);
+        assert!(terms[login].term_frequencies["session"].body > 0);
+        assert!(!terms[login].term_frequencies.contains_key("logout"));
+        assert_eq!((terms[login].start_line, terms[login].end_line), (1, 3));
+        let logout = &terms[entry
+            .chunks
+            .iter()
+            .position(|c| c.name == "logout")
+            .unwrap()];
+        assert_eq!((logout.start_line, logout.end_line), (5, 8));
+        assert_eq!(logout.term_frequencies["ends"].docs, 1);
+
+        let (rebuilt, reindexed) = IndexBuilder::new(dir.path())
+            .build(&files, Some(&chunked))
+            .unwrap();
+        assert_eq!(reindexed, 0);
+        assert!(rebuilt.chunk_postings);
+    }
+
+    #[test]
+    fn pruning_is_opt_in_and_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let generated = "pub fn zz_codegen_marker() {}\n";
+        let handwritten = "pub fn checkout() {}\n";
+        fs::write(dir.path().join("api.rs"), generated).unwrap();
+        fs::write(dir.path().join("cart.rs"), handwritten).unwrap();
+        let files = vec![
+            FileInfo {
+                role: topo_core::FileRole::Generated,
+                ..make_file_info("api.rs", generated)
+            },
+            make_file_info("cart.rs", handwritten),
+        ];
+
+        let (plain, _) = IndexBuilder::new(dir.path()).build(&files, None).unwrap();
+        assert!(plain.meta.unwrap().pruned.is_none());
+
+        let config = PruneConfig {
+            generated_hapax: true,
+            ..PruneConfig::default()
+        };
+        let (index, _) = IndexBuilder::new(dir.path())
+            .prune(config)
+            .build(&files, None)
+            .unwrap();
+        assert!(!index.doc_frequencies.contains_key("marker"));
+        assert!(index.doc_frequencies.contains_key("checkout"));
+        let pruned = index.meta.unwrap().pruned.unwrap();
+        assert!(pruned.generated_hapax > 0);
+        assert!(pruned.bytes_after < pruned.bytes_before);
+    }
+
+    #[test]
+    fn enrichment_hooks_attach_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let files_on_disk = [
+            (
+                "billing/charge.rs",
+                "pub fn charge() {}\npub fn refund() {}\n",
+            ),
+            ("docs/notes.md", "# Notes\n"),
+        ];
+        fs::create_dir_all(dir.path().join("billing")).unwrap();
+        fs::create_dir_all(dir.path().join("docs")).unwrap();
+        for (path, content) in files_on_disk {
+            fs::write(dir.path().join(path), content).unwrap();
+        }
+        let files: Vec<FileInfo> = files_on_disk
+            .iter()
+            .map(|(path, content)| make_file_info(path, content))
+            .collect();
+
+        let service = |info: &FileInfo, _: &str, _: &[Chunk]| {
+            let owner = info.path.split('/').next().unwrap_or_default();
+            vec![("service".to_string(), owner.to_string())]
+        };
+        let functions = |_: &FileInfo, content: &str, chunks: &[Chunk]| {
+            vec![
+                ("functions".to_string(), chunks.len().to_string()),
+                ("lines".to_string(), content.lines().count().to_string()),
+                ("service".to_string(), "overridden".to_string()),
+            ]
+        };
+        let index = IndexBuilder::new(dir.path())
+            .enrich(&service)
+            .enrich(&functions)
+            .build(&files, None)
+            .unwrap()
+            .0;
+
+        let charge = &index.files["billing/charge.rs"].metadata;
+        assert_eq!(charge["functions"], "2");
+        assert_eq!(charge["lines"], "2");
+        // Later hooks win on key conflicts
+        assert_eq!(charge["service"], "overridden");
+        assert_eq!(
+            index.files_with_metadata("functions", "2"),
+            vec!["billing/charge.rs"]
+        );
+
+        // Unchanged files keep their metadata without rerunning hooks
+        let rebuilt = IndexBuilder::new(dir.path())
+            .build(&files, Some(&index))
+            .unwrap()
+            .0;
+        assert_eq!(rebuilt.files["billing/charge.rs"].metadata, *charge);
+        assert!(
+            IndexBuilder::new(dir.path())
+                .build(&files, None)
+                .unwrap()
+                .0
+                .files["docs/notes.md"]
+                .metadata
+                .is_empty()
+        );
+    }
+
     #[test]
     fn index_empty_files() {
         let dir = tempfile::tempdir().unwrap();
@@ -411,7 +2201,7 @@ pub trait Handler {
         assert!(kinds.contains(&ChunkKind::Import));
         assert!(kinds.contains(&ChunkKind::Type));
         assert!(kinds.contains(&ChunkKind::Impl));
-        assert!(kinds.contains(&ChunkKind::Function));
+        assert!(kinds.contains(&ChunkKind::Method));
 
         assert!(
             chunks
@@ -0,0 +1,480 @@
+use crate::builder::INDEX_VERSION;
+use crate::lock::{IndexLock, LOCK_EXTENSION, LOCK_TIMEOUT};
+use crate::shard::{self, MANIFEST_FILE, SHARD_DIR};
+use crate::store::{self, INDEX_DIR};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use topo_core::{FEEDBACK_FILE, FileInfo, PINS_FILE, TopoError, WEIGHTS_FILE};
+
+/// Version of the cache archive layout.
+///
+/// Version 2 ships the sharded index instead of a single `index.bin`.
+pub const CACHE_FORMAT_VERSION: u32 = 2;
+
+/// Archive entry holding the [`CacheManifest`].
+const MANIFEST_ENTRY: &str = "manifest.json";
+
+/// Archive directory holding the cache files, relative to the index dir.
+const CACHE_PREFIX: &str = "cache";
+
+/// Index-dir files that are machine-local and never shipped.
+const LOCAL_FILES: &[&str] = &[
+    "stats.jsonl",
+    "stats.json",
+    "stats.json.tmp",
+    "corpus-stats.json",
+    "corpus-stats.json.tmp",
+    "blame.json",
+    "blame.json.tmp",
+    store::DAEMON_SOCKET,
+    index_relative(PINS_FILE),
+    index_relative(FEEDBACK_FILE),
+    index_relative(WEIGHTS_FILE),
+];
+
+/// `path`, a repository-relative path inside the index dir, relative to
+/// the index dir.
+const fn index_relative(path: &'static str) -> &'static str {
+    path.split_at(INDEX_DIR.len() + 1).1
+}
+
+/// Description of a cache archive, stored as its first entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheManifest {
+    pub format: u32,
+    pub topo_version: String,
+    pub index_version: u32,
+    /// Repository fingerprint at export time.
+    pub fingerprint: String,
+    pub files: Vec<CacheFile>,
+}
+
+/// One file in a cache archive, relative to the index dir.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheFile {
+    pub path: String,
+    pub size: u64,
+    /// Hex SHA-256 of the file contents.
+    pub sha256: String,
+}
+
+/// Outcome of [`import_cache`].
+#[derive(Debug, Clone)]
+pub struct ImportReport {
+    pub manifest: CacheManifest,
+    /// Whether the archive was exported from an identical working tree.
+    pub fingerprint_matches: bool,
+    /// Indexed files whose path and contents match the current scan.
+    pub matching_files: usize,
+    /// Files in the imported index.
+    pub indexed_files: usize,
+}
+
+/// Package the index directory into a zstd-compressed tar archive at `dest`.
+///
+/// Everything under `.topo/` except machine-local files is included, so
+/// future caches ship without changes here.
+pub fn export_cache(
+    repo_root: &Path,
+    dest: &Path,
+    fingerprint: &str,
+) -> Result<CacheManifest, TopoError> {
+    let dir = repo_root.join(INDEX_DIR);
+    if !dir.join(SHARD_DIR).join(MANIFEST_FILE).exists() {
+        return Err(
+            TopoError::index("no index to export; run `topo index --deep` first").with_path(dir),
+        );
+    }
+
+    // Hold off writers so the archive is one consistent index
+    let lock = IndexLock::acquire(&dir.join(SHARD_DIR), LOCK_TIMEOUT)?;
+    let mut entries = Vec::new();
+    collect_files(&dir, &dir, &mut entries)?;
+    drop(lock);
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let manifest = CacheManifest {
+        format: CACHE_FORMAT_VERSION,
+        topo_version: env!("CARGO_PKG_VERSION").to_string(),
+        index_version: INDEX_VERSION,
+        fingerprint: fingerprint.to_string(),
+        files: entries
+            .iter()
+            .map(|(path, bytes)| CacheFile {
+                path: path.clone(),
+                size: bytes.len() as u64,
+                sha256: sha256_hex(bytes),
+            })
+            .collect(),
+    };
+    let manifest_json = serde_json::to_vec_pretty(&manifest).map_err(|e| TopoError::Index {
+        path: Some(dest.to_path_buf()),
+        message: "failed to serialize cache manifest".to_string(),
+        source: Some(Box::new(e)),
+    })?;
+
+    let write = || -> std::io::Result<()> {
+        let file = fs::File::create(dest)?;
+        let encoder = zstd::Encoder::new(file, 0)?.auto_finish();
+        let mut tar = tar::Builder::new(encoder);
+        append(&mut tar, MANIFEST_ENTRY, &manifest_json)?;
+        for (path, bytes) in &entries {
+            append(&mut tar, &format!("{CACHE_PREFIX}/{path}"), bytes)?;
+        }
+        tar.into_inner()?;
+        Ok(())
+    };
+    write().map_err(|e| TopoError::io(dest, e))?;
+
+    Ok(manifest)
+}
+
+/// Unpack a cache archive into the index directory.
+///
+/// The archive is fully validated before anything is written: entry paths
+/// must stay inside the index dir, checksums must match the manifest, the
+/// index version must match this build, and the index must share at least
+/// one file with the current scan (`files`). Files that changed since export
+/// are simply reindexed by the next incremental build.
+pub fn import_cache(
+    repo_root: &Path,
+    archive: &Path,
+    files: &[FileInfo],
+    fingerprint: &str,
+) -> Result<ImportReport, TopoError> {
+    let (manifest, contents) = read_archive(archive)?;
+
+    if manifest.format != CACHE_FORMAT_VERSION {
+        return Err(invalid(
+            archive,
+            format!("unsupported cache format {}", manifest.format),
+        ));
+    }
+    if manifest.index_version != INDEX_VERSION {
+        return Err(invalid(
+            archive,
+            format!(
+                "cache has index version {} but this build uses {INDEX_VERSION}",
+                manifest.index_version
+            ),
+        ));
+    }
+    for file in &manifest.files {
+        let bytes = contents
+            .get(&file.path)
+            .ok_or_else(|| invalid(archive, format!("missing cache file `{}`", file.path)))?;
+        if bytes.len() as u64 != file.size || sha256_hex(bytes) != file.sha256 {
+            return Err(invalid(
+                archive,
+                format!("checksum mismatch for `{}`", file.path),
+            ));
+        }
+    }
+    if contents.len() != manifest.files.len() {
+        return Err(invalid(
+            archive,
+            "archive contains files not in its manifest",
+        ));
+    }
+
+    let index = shard::assemble(|name| contents.get(&format!("{SHARD_DIR}/{name}")).cloned())
+        .ok_or_else(|| invalid(archive, "cache does not contain a readable index"))?;
+    let current: HashMap<&str, [u8; 32]> =
+        files.iter().map(|f| (f.path.as_str(), f.sha256)).collect();
+    let present = index
+        .files
+        .keys()
+        .filter(|path| current.contains_key(path.as_str()))
+        .count();
+    if present == 0 && !index.files.is_empty() {
+        return Err(invalid(
+            archive,
+            "none of the cached paths exist here; was it exported from another repository?",
+        ));
+    }
+    let matching_files = index
+        .files
+        .iter()
+        .filter(|(path, entry)| current.get(path.as_str()) == Some(&entry.sha256))
+        .count();
+
+    let dir = repo_root.join(INDEX_DIR);
+    let _lock = IndexLock::acquire(&dir.join(SHARD_DIR), LOCK_TIMEOUT)?;
+    for (path, bytes) in &contents {
+        // Archives exported before a file was made local may still carry it
+        if LOCAL_FILES.contains(&path.as_str()) {
+            continue;
+        }
+        let target = dir.join(path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| TopoError::io(parent, e))?;
+        }
+        // Write beside the target and rename so a failed import never leaves
+        // a half-written index behind
+        let staged = target.with_extension("import");
+        fs::write(&staged, bytes).map_err(|e| TopoError::io(&staged, e))?;
+        fs::rename(&staged, &target).map_err(|e| TopoError::io(&target, e))?;
+    }
+
+    Ok(ImportReport {
+        fingerprint_matches: manifest.fingerprint == fingerprint,
+        matching_files,
+        indexed_files: index.files.len(),
+        manifest,
+    })
+}
+
+/// Read and path-check every entry of a cache archive.
+fn read_archive(archive: &Path) -> Result<(CacheManifest, HashMap<String, Vec<u8>>), TopoError> {
+    let file = fs::File::open(archive).map_err(|e| TopoError::io(archive, e))?;
+    let decoder = zstd::Decoder::new(file).map_err(|e| TopoError::io(archive, e))?;
+    let mut tar = tar::Archive::new(decoder);
+
+    let mut manifest = None;
+    let mut contents = HashMap::new();
+    let entries = tar.entries().map_err(|e| TopoError::io(archive, e))?;
+    for entry in entries {
+        let mut entry = entry.map_err(|e| TopoError::io(archive, e))?;
+        let name = entry
+            .path()
+            .map_err(|e| TopoError::io(archive, e))?
+            .to_string_lossy()
+            .into_owned();
+        if !entry.header().entry_type().is_file() {
+            return Err(invalid(
+                archive,
+                format!("unexpected non-file entry `{name}`"),
+            ));
+        }
+        let mut bytes = Vec::new();
+        entry
+            .read_to_end(&mut bytes)
+            .map_err(|e| TopoError::io(archive, e))?;
+
+        if name == MANIFEST_ENTRY {
+            let parsed = serde_json::from_slice(&bytes).map_err(|e| TopoError::Index {
+                path: Some(archive.to_path_buf()),
+                message: "invalid cache manifest".to_string(),
+                source: Some(Box::new(e)),
+            })?;
+            manifest = Some(parsed);
+            continue;
+        }
+        let path = name
+            .strip_prefix(CACHE_PREFIX)
+            .and_then(|rest| rest.strip_prefix('/'))
+            .filter(|rest| is_safe_relative(rest))
+            .ok_or_else(|| invalid(archive, format!("unsafe path `{name}` in cache archive")))?;
+        contents.insert(path.to_string(), bytes);
+    }
+
+    let manifest = manifest.ok_or_else(|| invalid(archive, "cache archive has no manifest"))?;
+    Ok((manifest, contents))
+}
+
+/// Recursively collect shippable files under `dir`, keyed by `/`-separated
+/// paths relative to `base`.
+fn collect_files(
+    base: &Path,
+    dir: &Path,
+    out: &mut Vec<(String, Vec<u8>)>,
+) -> Result<(), TopoError> {
+    for entry in fs::read_dir(dir).map_err(|e| TopoError::io(dir, e))? {
+        let path = entry.map_err(|e| TopoError::io(dir, e))?.path();
+        if path.is_dir() {
+            // Downloaded models are machine-local too, and large
+            if path != base.join(store::MODEL_DIR) {
+                collect_files(base, &path, out)?;
+            }
+            continue;
+        }
+        let relative: Vec<String> = path
+            .strip_prefix(base)
+            .unwrap_or(&path)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        let relative = relative.join("/");
+        if LOCAL_FILES.contains(&relative.as_str())
+            || path.extension().is_some_and(|ext| ext == LOCK_EXTENSION)
+        {
+            continue;
+        }
+        let bytes = fs::read(&path).map_err(|e| TopoError::io(&path, e))?;
+        out.push((relative, bytes));
+    }
+    Ok(())
+}
+
+fn append<W: std::io::Write>(
+    tar: &mut tar::Builder<W>,
+    path: &str,
+    bytes: &[u8],
+) -> std::io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(bytes.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    tar.append_data(&mut header, path, bytes)
+}
+
+/// Whether `path` is a non-empty relative path with no `..`, root, or prefix.
+fn is_safe_relative(path: &str) -> bool {
+    !path.is_empty()
+        && PathBuf::from(path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn invalid(archive: &Path, message: impl Into<String>) -> TopoError {
+    TopoError::index(message).with_path(archive)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IndexBuilder;
+    use crate::store;
+    use crate::test_support::file_info;
+
+    /// A repo with one indexed file, returning its scan.
+    fn indexed_repo(dir: &Path, content: &str) -> Vec<FileInfo> {
+        fs::write(dir.join("auth.rs"), content).unwrap();
+        let files = vec![file_info("auth.rs", content)];
+        let index = IndexBuilder::new(dir).build(&files, None).unwrap().0;
+        store::save(&index, dir).unwrap();
+        files
+    }
+
+    #[test]
+    fn export_then_import_round_trips() {
+        let source = tempfile::tempdir().unwrap();
+        let files = indexed_repo(source.path(), "fn authenticate() {}");
+        fs::write(source.path().join(".topo/stats.jsonl"), "{}\n").unwrap();
+        fs::write(source.path().join(".topo/stats.json"), "{}\n").unwrap();
+        fs::write(source.path().join(PINS_FILE), "src/theirs.rs\n").unwrap();
+        fs::write(source.path().join(FEEDBACK_FILE), "{}\n").unwrap();
+        fs::write(source.path().join(WEIGHTS_FILE), "{}\n").unwrap();
+        let models = store::model_cache_path(source.path());
+        fs::create_dir(&models).unwrap();
+        fs::write(models.join("model.onnx"), "weights").unwrap();
+        let archive = source.path().join("cache.tar.zst");
+
+        let manifest = export_cache(source.path(), &archive, "fp").unwrap();
+        let paths: Vec<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths.len(), 2);
+        assert!(paths.contains(&"index/manifest.bin"));
+
+        let target = tempfile::tempdir().unwrap();
+        fs::create_dir(target.path().join(INDEX_DIR)).unwrap();
+        fs::write(target.path().join(PINS_FILE), "src/mine.rs\n").unwrap();
+        let report = import_cache(target.path(), &archive, &files, "fp").unwrap();
+        assert!(report.fingerprint_matches);
+        assert_eq!(report.matching_files, 1);
+        assert_eq!(report.indexed_files, 1);
+
+        let loaded = store::load(target.path()).unwrap().unwrap();
+        assert!(loaded.files.contains_key("auth.rs"));
+        assert!(!target.path().join(".topo/stats.jsonl").exists());
+        assert!(!target.path().join(".topo/stats.json").exists());
+        assert!(!target.path().join(FEEDBACK_FILE).exists());
+        assert!(!target.path().join(WEIGHTS_FILE).exists());
+        assert_eq!(
+            fs::read_to_string(target.path().join(PINS_FILE)).unwrap(),
+            "src/mine.rs\n"
+        );
+    }
+
+    #[test]
+    fn import_counts_changed_files() {
+        let source = tempfile::tempdir().unwrap();
+        indexed_repo(source.path(), "fn authenticate() {}");
+        let archive = source.path().join("cache.tar.zst");
+        export_cache(source.path(), &archive, "old").unwrap();
+
+        let changed = vec![file_info("auth.rs", "fn authenticate() { todo!() }")];
+        let target = tempfile::tempdir().unwrap();
+        let report = import_cache(target.path(), &archive, &changed, "new").unwrap();
+        assert!(!report.fingerprint_matches);
+        assert_eq!(report.matching_files, 0);
+        assert_eq!(report.indexed_files, 1);
+    }
+
+    #[test]
+    fn import_rejects_other_repository() {
+        let source = tempfile::tempdir().unwrap();
+        indexed_repo(source.path(), "fn authenticate() {}");
+        let archive = source.path().join("cache.tar.zst");
+        export_cache(source.path(), &archive, "fp").unwrap();
+
+        let target = tempfile::tempdir().unwrap();
+        let other = vec![file_info("main.go", "package main")];
+        let err = import_cache(target.path(), &archive, &other, "fp").unwrap_err();
+        assert!(err.to_string().contains("another repository"));
+        assert!(!target.path().join(INDEX_DIR).exists());
+    }
+
+    #[test]
+    fn export_requires_an_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = export_cache(dir.path(), &dir.path().join("c.tar.zst"), "fp").unwrap_err();
+        assert!(err.to_string().contains("no index to export"));
+    }
+
+    #[test]
+    fn import_rejects_tampered_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = CacheManifest {
+            format: CACHE_FORMAT_VERSION,
+            topo_version: "0.0.0".to_string(),
+            index_version: INDEX_VERSION,
+            fingerprint: "fp".to_string(),
+            files: vec![CacheFile {
+                path: "index/manifest.bin".to_string(),
+                size: 3,
+                sha256: sha256_hex(b"abc"),
+            }],
+        };
+        let archive = dir.path().join("c.tar.zst");
+        write_archive(&archive, &manifest, &[("cache/index/manifest.bin", b"abd")]);
+
+        let err = import_cache(dir.path(), &archive, &[], "fp").unwrap_err();
+        assert!(err.to_string().contains("checksum mismatch"));
+    }
+
+    #[test]
+    fn safe_relative_paths() {
+        assert!(is_safe_relative("index.bin"));
+        assert!(is_safe_relative("shards/src.bin"));
+        assert!(!is_safe_relative("../escape"));
+        assert!(!is_safe_relative("/etc/passwd"));
+        assert!(!is_safe_relative(""));
+    }
+
+    fn write_archive(dest: &Path, manifest: &CacheManifest, files: &[(&str, &[u8])]) {
+        let file = fs::File::create(dest).unwrap();
+        let encoder = zstd::Encoder::new(file, 0).unwrap().auto_finish();
+        let mut tar = tar::Builder::new(encoder);
+        append(
+            &mut tar,
+            MANIFEST_ENTRY,
+            &serde_json::to_vec(manifest).unwrap(),
+        )
+        .unwrap();
+        for (path, bytes) in files {
+            append(&mut tar, path, bytes).unwrap();
+        }
+        tar.into_inner().unwrap();
+    }
+}
@@ -0,0 +1,122 @@
+use crate::crypt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use topo_core::TopoError;
+use topo_score::CorpusStats;
+
+/// Path corpus statistics as cached, with the scan they were computed for.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedStats {
+    /// [`topo_core::Bundle::fingerprint`] of the scan.
+    fingerprint: String,
+    stemmed: bool,
+    total_docs: usize,
+    avg_doc_length: f64,
+    doc_frequencies: HashMap<String, usize>,
+}
+
+/// Where path corpus statistics of the repository at `root` are cached.
+pub fn corpus_stats_path(root: &Path) -> PathBuf {
+    root.join(".topo").join("corpus-stats.json")
+}
+
+/// Path corpus statistics of every file of the scan with `fingerprint`,
+/// stemmed or not: from the cache in `.topo/` if they were computed for
+/// the same scan, else from `compute`, and cached for the next query.
+///
+/// Any change to the files changes the fingerprint, so stale statistics
+/// are never used. Only repositories that already have a `.topo`
+/// directory are cached, and a cache that can't be read or written is
+/// recomputed.
+pub fn cached_corpus_stats(
+    root: &Path,
+    fingerprint: &str,
+    stemmed: bool,
+    compute: impl FnOnce() -> CorpusStats,
+) -> CorpusStats {
+    if let Some(stats) = load_corpus_stats(root, fingerprint, stemmed) {
+        return stats;
+    }
+    let stats = compute();
+    if root.join(".topo").is_dir() {
+        let _ = save_corpus_stats(root, fingerprint, stemmed, &stats);
+    }
+    stats
+}
+
+fn load_corpus_stats(root: &Path, fingerprint: &str, stemmed: bool) -> Option<CorpusStats> {
+    // Sealed with TOPO_INDEX_KEY if set; stats that won't open are recomputed
+    let bytes = fs::read(corpus_stats_path(root)).ok()?;
+    let cached: CachedStats = serde_json::from_slice(&crypt::open(&bytes)?).ok()?;
+    (cached.fingerprint == fingerprint && cached.stemmed == stemmed).then_some(CorpusStats {
+        total_docs: cached.total_docs,
+        avg_doc_length: cached.avg_doc_length,
+        doc_frequencies: cached.doc_frequencies,
+    })
+}
+
+fn save_corpus_stats(
+    root: &Path,
+    fingerprint: &str,
+    stemmed: bool,
+    stats: &CorpusStats,
+) -> Result<(), TopoError> {
+    let path = corpus_stats_path(root);
+    let cached = CachedStats {
+        fingerprint: fingerprint.to_string(),
+        stemmed,
+        total_docs: stats.total_docs,
+        avg_doc_length: stats.avg_doc_length,
+        doc_frequencies: stats.doc_frequencies.clone(),
+    };
+    let json = serde_json::to_vec(&cached).map_err(|e| TopoError::Index {
+        path: Some(path.clone()),
+        message: "failed to serialize corpus stats".to_string(),
+        source: Some(Box::new(e)),
+    })?;
+    let json = crypt::seal(&path, json)?;
+    // Write then rename so a concurrent query never reads a partial file
+    let staged = path.with_extension("json.tmp");
+    fs::write(&staged, json).map_err(|e| TopoError::io(&staged, e))?;
+    fs::rename(&staged, &path).map_err(|e| TopoError::io(&path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn stats_are_reused_until_the_scan_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let computed = Cell::new(0);
+        let stats = |fingerprint: &str, stemmed: bool| {
+            cached_corpus_stats(dir.path(), fingerprint, stemmed, || {
+                computed.set(computed.get() + 1);
+                CorpusStats::from_paths(&["src/auth.rs", "src/db.rs"])
+            })
+        };
+
+        // Nothing is cached without a `.topo` directory
+        stats("a", false);
+        assert!(!corpus_stats_path(dir.path()).exists());
+        assert_eq!(computed.get(), 1);
+
+        fs::create_dir(dir.path().join(".topo")).unwrap();
+        stats("a", false);
+        let cached = stats("a", false);
+        assert_eq!(computed.get(), 2);
+        assert_eq!(cached.total_docs, 2);
+        assert_eq!(cached.doc_frequencies["src"], 2);
+
+        stats("b", false);
+        stats("b", true);
+        assert_eq!(computed.get(), 4, "another scan or stemming recomputes");
+
+        fs::write(corpus_stats_path(dir.path()), "not json").unwrap();
+        stats("b", true);
+        assert_eq!(computed.get(), 5);
+    }
+}
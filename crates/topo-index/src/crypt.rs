@@ -0,0 +1,233 @@
+use std::borrow::Cow;
+use std::path::Path;
+use topo_core::TopoError;
+
+/// Environment variable holding the secret index files are encrypted with.
+/// Unset, index files are written in the clear.
+pub const INDEX_KEY_VAR: &str = "TOPO_INDEX_KEY";
+
+/// Prefix of an encrypted index file, followed by the 16-byte salt its key
+/// was derived with, a 12-byte nonce, and the AES-256-GCM ciphertext of
+/// what would otherwise have been written.
+const ENCRYPTED_MAGIC: &[u8; 8] = b"TOPOAES1";
+
+/// Whether `bytes` were written encrypted.
+pub(crate) fn is_encrypted(bytes: &[u8]) -> bool {
+    bytes.starts_with(ENCRYPTED_MAGIC)
+}
+
+/// Fail unless `bytes`, read from `path`, can be decrypted: encrypted
+/// indexes read without the right key would otherwise be rebuilt and
+/// saved over in the clear.
+pub(crate) fn check_readable(path: &Path, bytes: &[u8]) -> Result<(), TopoError> {
+    if !is_encrypted(bytes) || open(bytes).is_some() {
+        return Ok(());
+    }
+    let reason = match std::env::var_os(INDEX_KEY_VAR) {
+        None => format!("the index is encrypted; set {INDEX_KEY_VAR} to its key"),
+        Some(_) if cfg!(feature = "encryption") => {
+            format!("the index is encrypted with another key than {INDEX_KEY_VAR}")
+        }
+        Some(_) => "the index is encrypted and this build can't decrypt it; \
+                    enable the `encryption` feature"
+            .to_string(),
+    };
+    Err(TopoError::index(format!("{reason}, or delete it with `topo clean`")).with_path(path))
+}
+
+/// Encrypt `bytes` for writing to `path` if [`INDEX_KEY_VAR`] is set.
+pub(crate) fn seal(path: &Path, bytes: Vec<u8>) -> Result<Vec<u8>, TopoError> {
+    match key()? {
+        Some(key) => aes::seal(key, &bytes).map_err(|message| TopoError::Index {
+            path: Some(path.to_path_buf()),
+            message,
+            source: None,
+        }),
+        None => Ok(bytes),
+    }
+}
+
+/// Undo [`seal`]. None if `bytes` are encrypted and can't be decrypted.
+pub(crate) fn open(bytes: &[u8]) -> Option<Cow<'_, [u8]>> {
+    if !is_encrypted(bytes) {
+        return Some(Cow::Borrowed(bytes));
+    }
+    aes::open(key().ok()??, bytes).map(Cow::Owned)
+}
+
+#[cfg(feature = "encryption")]
+use aes::Key;
+
+/// Without the `encryption` feature nothing is ever encrypted.
+#[cfg(not(feature = "encryption"))]
+type Key = std::convert::Infallible;
+
+/// The key derived from [`INDEX_KEY_VAR`], read once per process.
+fn key() -> Result<Option<&'static Key>, TopoError> {
+    static KEY: std::sync::OnceLock<Result<Option<Key>, String>> = std::sync::OnceLock::new();
+    let key = KEY.get_or_init(|| match std::env::var(INDEX_KEY_VAR) {
+        Ok(secret) if secret.is_empty() => Err(format!("{INDEX_KEY_VAR} is empty")),
+        #[cfg(feature = "encryption")]
+        Ok(secret) => Ok(Some(aes::derive(&secret))),
+        #[cfg(not(feature = "encryption"))]
+        Ok(_) => Err(format!(
+            "{INDEX_KEY_VAR} is set, but this build can't encrypt indexes; \
+             enable the `encryption` feature"
+        )),
+        Err(std::env::VarError::NotPresent) => Ok(None),
+        Err(std::env::VarError::NotUnicode(_)) => Err(format!("{INDEX_KEY_VAR} isn't UTF-8")),
+    });
+    key.as_ref()
+        .map(Option::as_ref)
+        .map_err(|message| TopoError::index(message.clone()))
+}
+
+#[cfg(feature = "encryption")]
+mod aes {
+    use super::ENCRYPTED_MAGIC;
+    use aes_gcm::aead::rand_core::RngCore;
+    use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+    use aes_gcm::{Aes256Gcm, Nonce};
+    use argon2::Argon2;
+    use std::collections::HashMap;
+    use std::sync::{Mutex, OnceLock, PoisonError};
+
+    const SALT_LEN: usize = 16;
+    const NONCE_LEN: usize = 12;
+
+    type Salt = [u8; SALT_LEN];
+
+    /// The secret index files are encrypted with, and the ciphers derived
+    /// from it.
+    ///
+    /// Keys are derived from the secret with Argon2id, at its default cost,
+    /// so a guessable passphrase is still slow to brute-force offline. The
+    /// salt is stored in every sealed file; files are sealed with the salt
+    /// of the first one opened, so an index keeps one salt across builds
+    /// and costs one derivation to read.
+    pub(super) struct Key {
+        secret: String,
+        /// Salt new files are sealed with.
+        salt: OnceLock<Salt>,
+        ciphers: Mutex<HashMap<Salt, Aes256Gcm>>,
+    }
+
+    pub(super) fn derive(secret: &str) -> Key {
+        Key {
+            secret: secret.to_string(),
+            salt: OnceLock::new(),
+            ciphers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    impl Key {
+        /// The cipher keyed by the secret with `salt`, derived once.
+        fn cipher(&self, salt: &Salt) -> Result<Aes256Gcm, String> {
+            let mut ciphers = self.ciphers.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Some(cipher) = ciphers.get(salt) {
+                return Ok(cipher.clone());
+            }
+            let mut key = [0u8; 32];
+            Argon2::default()
+                .hash_password_into(self.secret.as_bytes(), salt, &mut key)
+                .map_err(|e| format!("failed to derive the index key: {e}"))?;
+            let cipher = Aes256Gcm::new(&key.into());
+            ciphers.insert(*salt, cipher.clone());
+            Ok(cipher)
+        }
+    }
+
+    pub(super) fn seal(key: &Key, bytes: &[u8]) -> Result<Vec<u8>, String> {
+        let salt = key.salt.get_or_init(|| {
+            let mut salt = [0u8; SALT_LEN];
+            OsRng.fill_bytes(&mut salt);
+            salt
+        });
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = key
+            .cipher(salt)?
+            .encrypt(&nonce, bytes)
+            .map_err(|_| "failed to encrypt index".to_string())?;
+        let mut out =
+            Vec::with_capacity(ENCRYPTED_MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+        out.extend_from_slice(ENCRYPTED_MAGIC);
+        out.extend_from_slice(salt);
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&ciphertext);
+        Ok(out)
+    }
+
+    pub(super) fn open(key: &Key, bytes: &[u8]) -> Option<Vec<u8>> {
+        let sealed = bytes.strip_prefix(ENCRYPTED_MAGIC)?;
+        if sealed.len() < SALT_LEN + NONCE_LEN {
+            return None;
+        }
+        let (salt, sealed) = sealed.split_at(SALT_LEN);
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let mut salt_bytes = [0u8; SALT_LEN];
+        salt_bytes.copy_from_slice(salt);
+        let opened = key
+            .cipher(&salt_bytes)
+            .ok()?
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .ok()?;
+        key.salt.get_or_init(|| salt_bytes);
+        Some(opened)
+    }
+}
+
+#[cfg(not(feature = "encryption"))]
+mod aes {
+    use super::Key;
+
+    pub(super) fn seal(key: &Key, _bytes: &[u8]) -> Result<Vec<u8>, String> {
+        match *key {}
+    }
+
+    pub(super) fn open(key: &Key, _bytes: &[u8]) -> Option<Vec<u8>> {
+        match *key {}
+    }
+}
+
+#[cfg(all(test, feature = "encryption"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sealed_bytes_open_only_with_the_same_secret() {
+        let key = aes::derive("correct horse battery staple");
+        let sealed = aes::seal(&key, b"index bytes").unwrap();
+        assert!(is_encrypted(&sealed));
+        assert!(!sealed.windows(11).any(|w| w == b"index bytes"));
+        assert_eq!(aes::open(&key, &sealed).unwrap(), b"index bytes");
+        // A fresh nonce every time
+        assert_ne!(aes::seal(&key, b"index bytes").unwrap(), sealed);
+
+        assert!(aes::open(&aes::derive("wrong"), &sealed).is_none());
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(aes::open(&key, &tampered).is_none());
+        assert!(aes::open(&key, &sealed[..ENCRYPTED_MAGIC.len() + 4]).is_none());
+    }
+
+    #[test]
+    fn keys_are_salted_per_index() {
+        let salt = |sealed: &[u8]| sealed[ENCRYPTED_MAGIC.len()..][..16].to_vec();
+        let first = aes::derive("secret");
+        let sealed = aes::seal(&first, b"index bytes").unwrap();
+        assert_eq!(salt(&aes::seal(&first, b"more").unwrap()), salt(&sealed));
+
+        // Another index gets a salt of its own, so the same secret derives
+        // a different key
+        let other = aes::derive("secret");
+        assert_ne!(
+            salt(&aes::seal(&other, b"index bytes").unwrap()),
+            salt(&sealed)
+        );
+
+        // A build opening the index keeps sealing with its salt
+        let next = aes::derive("secret");
+        assert_eq!(aes::open(&next, &sealed).unwrap(), b"index bytes");
+        assert_eq!(salt(&aes::seal(&next, b"more").unwrap()), salt(&sealed));
+    }
+}
@@ -0,0 +1,133 @@
+//! Duplicate chunk detection: vendored or copy-pasted code found in
+//! several files.
+
+use crate::builder::chunk_spans;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use topo_core::{Chunk, ChunkKind, DuplicateGroup, FileEntry};
+
+/// Non-blank lines a chunk needs to count as duplicated code, so that
+/// one-line getters and empty constructors repeated everywhere don't.
+const MIN_DUPLICATE_LINES: usize = 3;
+
+/// [`FileEntry::chunk_hashes`] of `chunks` in `content`, each over its
+/// lines from the declaration to the next one, leaving out the comments
+/// above it.
+pub(crate) fn chunk_hashes(content: &str, chunks: &[Chunk]) -> Vec<u64> {
+    let lines: Vec<&str> = content.lines().collect();
+    chunks
+        .iter()
+        .zip(chunk_spans(&lines, chunks))
+        .map(|(chunk, (_, end_line))| {
+            if chunk.kind == ChunkKind::Import {
+                return 0;
+            }
+            let start = (chunk.start_line as usize).saturating_sub(1);
+            let end = end_line as usize;
+            let body: Vec<&str> = lines
+                .get(start..end)
+                .unwrap_or_default()
+                .iter()
+                .map(|line| line.trim())
+                .filter(|line| !line.is_empty())
+                .collect();
+            if body.len() < MIN_DUPLICATE_LINES {
+                return 0;
+            }
+            let mut prefix = [0u8; 8];
+            prefix.copy_from_slice(&Sha256::digest(body.join("\n").as_bytes())[..8]);
+            // 0 marks unhashed chunks
+            u64::from_le_bytes(prefix).max(1)
+        })
+        .collect()
+}
+
+/// Groups of chunks sharing a hash in more than one of `files`, sorted by
+/// hash, see [`topo_core::DeepIndex::duplicate_chunks`].
+pub(crate) fn duplicate_groups(files: &HashMap<String, FileEntry>) -> Vec<DuplicateGroup> {
+    let mut copies: HashMap<u64, Vec<(String, u32)>> = HashMap::new();
+    for (path, entry) in files {
+        for (i, &hash) in entry.chunk_hashes.iter().enumerate() {
+            if hash != 0 {
+                copies
+                    .entry(hash)
+                    .or_default()
+                    .push((path.clone(), i as u32));
+            }
+        }
+    }
+    let mut groups: Vec<DuplicateGroup> = copies
+        .into_iter()
+        .map(|(hash, mut copies)| {
+            copies.sort_unstable();
+            DuplicateGroup { hash, copies }
+        })
+        .filter(|group| group.files() > 1)
+        .collect();
+    groups.sort_unstable_by_key(|group| group.hash);
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use topo_core::Language;
+    use topo_treesit::{Chunker, RegexChunker};
+
+    fn entry(content: &str) -> FileEntry {
+        let chunks = RegexChunker.chunk(content, Language::Rust);
+        FileEntry {
+            sha256: [0; 32],
+            size: 0,
+            chunk_hashes: chunk_hashes(content, &chunks),
+            chunks,
+            term_frequencies: HashMap::new(),
+            doc_length: 0,
+            metadata: Default::default(),
+            chunk_terms: None,
+            exports: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn copies_in_other_files_are_grouped() {
+        let original = "use std::fmt;\n\nfn parse(input: &str) -> u32 {\n    let n = input.trim();\n    n.parse().unwrap()\n}\n";
+        // Reindented, with a blank line, under another import
+        let vendored = "use core::fmt;\nfn parse(input: &str) -> u32 {\n  let n = input.trim();\n\n  n.parse().unwrap()\n}\n";
+        let short = "fn parse(input: &str) -> u32 { 0 }\n";
+        let files: HashMap<String, FileEntry> = [
+            ("src/parse.rs", original),
+            ("vendor/parse.rs", vendored),
+            ("src/short.rs", short),
+        ]
+        .into_iter()
+        .map(|(path, content)| (path.to_string(), entry(content)))
+        .collect();
+
+        let hashes = &files["src/parse.rs"].chunk_hashes;
+        assert_eq!(hashes[0], 0, "imports are never duplicates");
+        assert_ne!(hashes[1], 0);
+        assert_eq!(files["src/short.rs"].chunk_hashes, [0]);
+
+        let groups = duplicate_groups(&files);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].hash, hashes[1]);
+        assert_eq!(
+            groups[0].copies,
+            [
+                ("src/parse.rs".to_string(), 1),
+                ("vendor/parse.rs".to_string(), 1)
+            ]
+        );
+        assert_eq!(groups[0].files(), 2);
+    }
+
+    #[test]
+    fn copies_within_one_file_are_not_duplicates() {
+        let twice = "fn a() {\n    one();\n    two();\n}\n\nfn a() {\n    one();\n    two();\n}\n";
+        let files: HashMap<String, FileEntry> = [("twice.rs".to_string(), entry(twice))]
+            .into_iter()
+            .collect();
+        assert!(duplicate_groups(&files).is_empty());
+    }
+}
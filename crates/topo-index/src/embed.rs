@@ -0,0 +1,293 @@
+use crate::builder::ProgressFn;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use topo_core::{EmbedError, Embedder, EmbeddingConfig, TopoError};
+
+/// Calls an [`Embedder`] with backpressure: texts go out in batches, a
+/// bounded number of requests run at once, and failed requests are retried
+/// with exponential backoff.
+///
+/// When the provider rate-limits a request, every worker holds off until
+/// the provider's `retry_after` (or the current backoff) has passed, rather
+/// than each one hammering it again on its own schedule.
+pub struct EmbedQueue<'a> {
+    embedder: &'a dyn Embedder,
+    config: EmbeddingConfig,
+    progress: Option<ProgressFn<'a>>,
+}
+
+impl<'a> EmbedQueue<'a> {
+    pub fn new(embedder: &'a dyn Embedder, config: EmbeddingConfig) -> Self {
+        Self {
+            embedder,
+            config,
+            progress: None,
+        }
+    }
+
+    /// Report `(texts embedded, total)` after each batch. Called from
+    /// worker threads.
+    pub fn progress(mut self, progress: ProgressFn<'a>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// One vector per text, in order. Fails on the first request that
+    /// can't be retried or runs out of retries; requests not yet sent are
+    /// then skipped.
+    pub fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, TopoError> {
+        let batches: Vec<&[String]> = texts.chunks(self.config.batch_size.max(1)).collect();
+        let results: Mutex<Vec<Option<Vec<Vec<f32>>>>> = Mutex::new(vec![None; batches.len()]);
+        let next = AtomicUsize::new(0);
+        let embedded = AtomicUsize::new(0);
+        let failed = AtomicBool::new(false);
+        let error: Mutex<Option<TopoError>> = Mutex::new(None);
+        let paused_until: Mutex<Option<Instant>> = Mutex::new(None);
+
+        let workers = self.config.concurrency.clamp(1, batches.len().max(1));
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| {
+                    while !failed.load(Ordering::Relaxed) {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(batch) = batches.get(i) else {
+                            break;
+                        };
+                        match self.embed_batch(batch, &paused_until) {
+                            Ok(vectors) => {
+                                lock(&results)[i] = Some(vectors);
+                                let done = embedded.fetch_add(batch.len(), Ordering::Relaxed)
+                                    + batch.len();
+                                if let Some(progress) = self.progress {
+                                    progress(done, texts.len());
+                                }
+                            }
+                            Err(e) => {
+                                failed.store(true, Ordering::Relaxed);
+                                lock(&error).get_or_insert(e);
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        if let Some(e) = lock(&error).take() {
+            return Err(e);
+        }
+        let results = results.into_inner().unwrap_or_else(|e| e.into_inner());
+        Ok(results.into_iter().flatten().flatten().collect())
+    }
+
+    /// Embed one batch, retrying throttled and transient failures.
+    fn embed_batch(
+        &self,
+        batch: &[String],
+        paused_until: &Mutex<Option<Instant>>,
+    ) -> Result<Vec<Vec<f32>>, TopoError> {
+        let texts: Vec<&str> = batch.iter().map(String::as_str).collect();
+        let model = self.embedder.model();
+        let max_backoff = Duration::from_millis(self.config.max_backoff_ms);
+        let mut backoff = Duration::from_millis(self.config.backoff_ms).min(max_backoff);
+        let mut retries = 0;
+        loop {
+            let paused = *lock(paused_until);
+            if let Some(wait) =
+                paused.and_then(|until| until.checked_duration_since(Instant::now()))
+            {
+                thread::sleep(wait);
+            }
+
+            let error = match self.embedder.embed(&texts) {
+                Ok(vectors) if vectors.len() == texts.len() => return Ok(vectors),
+                Ok(vectors) => {
+                    return Err(TopoError::index(format!(
+                        "{model} returned {} vectors for {} texts",
+                        vectors.len(),
+                        texts.len()
+                    )));
+                }
+                Err(e @ EmbedError::Fatal(_)) => return Err(embed_error(model, retries, e)),
+                Err(e) => e,
+            };
+            if retries >= self.config.max_retries {
+                return Err(embed_error(model, retries, error));
+            }
+            retries += 1;
+
+            match error {
+                EmbedError::RateLimited { retry_after } => {
+                    // Hold back every worker, not just this one
+                    let resume = Instant::now() + retry_after.unwrap_or(backoff);
+                    let mut paused = lock(paused_until);
+                    *paused = Some(paused.map_or(resume, |until| until.max(resume)));
+                }
+                _ => thread::sleep(backoff),
+            }
+            backoff = (backoff * 2).min(max_backoff);
+        }
+    }
+}
+
+fn embed_error(model: &str, retries: u32, source: EmbedError) -> TopoError {
+    let message = match retries {
+        0 => format!("embedding with {model} failed"),
+        n => format!("embedding with {model} failed after {n} retries"),
+    };
+    TopoError::Index {
+        path: None,
+        message,
+        source: Some(Box::new(source)),
+    }
+}
+
+/// Lock `mutex`, recovering the data if a worker panicked while holding it.
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU32;
+
+    /// Embeds each text as `[len, 1.0]`, failing the first `failures` calls
+    /// with `error`.
+    struct Flaky {
+        failures: AtomicU32,
+        error: fn() -> EmbedError,
+        calls: AtomicU32,
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+        max_batch: AtomicUsize,
+    }
+
+    impl Flaky {
+        fn new(failures: u32, error: fn() -> EmbedError) -> Self {
+            Self {
+                failures: AtomicU32::new(failures),
+                error,
+                calls: AtomicU32::new(0),
+                in_flight: AtomicUsize::new(0),
+                max_in_flight: AtomicUsize::new(0),
+                max_batch: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    impl Embedder for Flaky {
+        fn model(&self) -> &str {
+            "flaky"
+        }
+
+        fn dimension(&self) -> u32 {
+            2
+        }
+
+        fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, EmbedError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.max_batch.fetch_max(texts.len(), Ordering::SeqCst);
+            let running = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(running, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(2));
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+            let fail = self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok();
+            if fail {
+                return Err((self.error)());
+            }
+            Ok(texts.iter().map(|t| vec![t.len() as f32, 1.0]).collect())
+        }
+    }
+
+    fn config(batch_size: usize, concurrency: usize, max_retries: u32) -> EmbeddingConfig {
+        EmbeddingConfig {
+            batch_size,
+            concurrency,
+            max_retries,
+            backoff_ms: 1,
+            max_backoff_ms: 4,
+            ..EmbeddingConfig::default()
+        }
+    }
+
+    fn texts(n: usize) -> Vec<String> {
+        (0..n).map(|i| "x".repeat(i + 1)).collect()
+    }
+
+    #[test]
+    fn batches_within_concurrency_limit_in_order() {
+        let embedder = Flaky::new(0, || EmbedError::Transient(String::new()));
+        let progress_calls = AtomicUsize::new(0);
+        let report = |done: usize, total: usize| {
+            assert!(done <= total);
+            progress_calls.fetch_add(1, Ordering::SeqCst);
+        };
+        let vectors = EmbedQueue::new(&embedder, config(3, 2, 0))
+            .progress(&report)
+            .embed(&texts(10))
+            .unwrap();
+
+        assert_eq!(vectors.len(), 10);
+        for (i, vector) in vectors.iter().enumerate() {
+            assert_eq!(vector[0], (i + 1) as f32);
+        }
+        assert_eq!(embedder.calls.load(Ordering::SeqCst), 4);
+        assert_eq!(embedder.max_batch.load(Ordering::SeqCst), 3);
+        assert!(embedder.max_in_flight.load(Ordering::SeqCst) <= 2);
+        assert_eq!(progress_calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn retries_throttled_and_transient_failures() {
+        let throttled = Flaky::new(3, || EmbedError::RateLimited {
+            retry_after: Some(Duration::from_millis(1)),
+        });
+        let vectors = EmbedQueue::new(&throttled, config(4, 2, 5))
+            .embed(&texts(8))
+            .unwrap();
+        assert_eq!(vectors.len(), 8);
+        assert_eq!(throttled.calls.load(Ordering::SeqCst), 5);
+
+        let flaky = Flaky::new(2, || EmbedError::Transient("timeout".to_string()));
+        assert!(
+            EmbedQueue::new(&flaky, config(8, 1, 2))
+                .embed(&texts(3))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn gives_up_after_max_retries_or_fatal_errors() {
+        let flaky = Flaky::new(10, || EmbedError::Transient("503".to_string()));
+        let err = EmbedQueue::new(&flaky, config(8, 1, 2))
+            .embed(&texts(3))
+            .unwrap_err();
+        assert!(err.to_string().contains("flaky failed after 2 retries"));
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 3);
+
+        let denied = Flaky::new(1, || EmbedError::Fatal("bad API key".to_string()));
+        let err = EmbedQueue::new(&denied, config(2, 1, 5))
+            .embed(&texts(6))
+            .unwrap_err();
+        let source = std::error::Error::source(&err).unwrap();
+        assert_eq!(source.to_string(), "bad API key");
+        // Remaining batches are skipped once one fails for good
+        assert_eq!(denied.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn nothing_to_embed() {
+        let embedder = Flaky::new(0, || EmbedError::Transient(String::new()));
+        let vectors = EmbedQueue::new(&embedder, config(4, 4, 0))
+            .embed(&[])
+            .unwrap();
+        assert!(vectors.is_empty());
+        assert_eq!(embedder.calls.load(Ordering::SeqCst), 0);
+    }
+}
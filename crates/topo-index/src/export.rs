@@ -0,0 +1,396 @@
+use crate::shard::hex;
+use rusqlite::{Connection, params};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use topo_core::{DeepIndex, TopoError};
+
+/// Version of the export schemas below, bumped on incompatible changes.
+pub const EXPORT_SCHEMA_VERSION: u32 = 1;
+
+/// Tables written by [`export_sqlite`].
+const SQLITE_SCHEMA: &str = "
+CREATE TABLE index_info (
+    key   TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+CREATE TABLE files (
+    path       TEXT PRIMARY KEY,
+    sha256     TEXT NOT NULL,
+    doc_length INTEGER NOT NULL,
+    pagerank   REAL
+);
+CREATE TABLE metadata (
+    path  TEXT NOT NULL REFERENCES files(path),
+    key   TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (path, key)
+);
+CREATE TABLE chunks (
+    path       TEXT NOT NULL REFERENCES files(path),
+    kind       TEXT NOT NULL,
+    name       TEXT NOT NULL,
+    start_line INTEGER NOT NULL,
+    end_line   INTEGER NOT NULL,
+    content    TEXT NOT NULL,
+    target     TEXT,
+    doc        TEXT,
+    parent     TEXT,
+    signature  TEXT,
+    tokens     INTEGER NOT NULL,
+    exported   INTEGER NOT NULL
+);
+CREATE TABLE terms (
+    path     TEXT NOT NULL REFERENCES files(path),
+    term     TEXT NOT NULL,
+    filename INTEGER NOT NULL,
+    symbols  INTEGER NOT NULL,
+    body     INTEGER NOT NULL,
+    docs     INTEGER NOT NULL,
+    PRIMARY KEY (path, term)
+);
+CREATE TABLE exports (
+    path TEXT NOT NULL REFERENCES files(path),
+    name TEXT NOT NULL,
+    PRIMARY KEY (path, name)
+);
+CREATE TABLE imports (
+    path   TEXT NOT NULL REFERENCES files(path),
+    target TEXT NOT NULL REFERENCES files(path),
+    PRIMARY KEY (path, target)
+);
+CREATE INDEX chunks_path ON chunks(path);
+CREATE INDEX terms_term ON terms(term);
+";
+
+#[derive(Serialize)]
+struct JsonExport<'a> {
+    schema_version: u32,
+    index_version: u32,
+    total_docs: u32,
+    avg_doc_length: f64,
+    files: Vec<JsonFile<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonFile<'a> {
+    path: &'a str,
+    sha256: String,
+    doc_length: u32,
+    pagerank: Option<f64>,
+    metadata: &'a BTreeMap<String, String>,
+    imports: &'a [String],
+    exports: &'a [String],
+    chunks: Vec<JsonChunk<'a>>,
+    terms: BTreeMap<&'a str, JsonTerm>,
+}
+
+#[derive(Serialize)]
+struct JsonChunk<'a> {
+    kind: &'static str,
+    name: &'a str,
+    start_line: u32,
+    end_line: u32,
+    content: &'a str,
+    target: Option<&'a str>,
+    doc: Option<&'a str>,
+    parent: Option<&'a str>,
+    signature: Option<&'a str>,
+    tokens: u32,
+    exported: bool,
+}
+
+#[derive(Serialize)]
+struct JsonTerm {
+    filename: u32,
+    symbols: u32,
+    body: u32,
+    docs: u32,
+}
+
+/// Write `index` as one JSON document.
+///
+/// The document has `schema_version`, `index_version`, `total_docs`,
+/// `avg_doc_length`, and `files` sorted by path. Each file has `path`,
+/// hex `sha256`, `doc_length`, `pagerank` (or null), `metadata`, the repo
+/// paths it `imports`, the names it `exports`, its `chunks` (`kind`,
+/// `name`, `start_line`, `end_line`, `content`, for imports the `target`
+/// path or null, for declarations the `doc` comment and `signature` or
+/// null, for methods the `parent` type or null, estimated `tokens`, and
+/// whether it is `exported`), and per-field counts of its `terms`.
+pub fn export_json(index: &DeepIndex, writer: &mut dyn Write) -> Result<(), TopoError> {
+    let files = sorted_paths(index)
+        .into_iter()
+        .map(|path| {
+            let entry = &index.files[path];
+            JsonFile {
+                path,
+                sha256: hex(&entry.sha256),
+                doc_length: entry.doc_length,
+                pagerank: index.pagerank_scores.get(path).copied(),
+                metadata: &entry.metadata,
+                imports: index.imports(path),
+                exports: &entry.exports,
+                chunks: entry
+                    .chunks
+                    .iter()
+                    .map(|c| JsonChunk {
+                        kind: c.kind.as_str(),
+                        name: &c.name,
+                        start_line: c.start_line,
+                        end_line: c.end_line,
+                        content: &c.content,
+                        target: c.target.as_deref(),
+                        doc: c.doc.as_deref(),
+                        parent: c.parent.as_deref(),
+                        signature: c.signature.as_deref(),
+                        tokens: c.tokens,
+                        exported: c.exported,
+                    })
+                    .collect(),
+                terms: entry
+                    .term_frequencies
+                    .iter()
+                    .map(|(term, tf)| {
+                        let counts = JsonTerm {
+                            filename: tf.filename,
+                            symbols: tf.symbols,
+                            body: tf.body,
+                            docs: tf.docs,
+                        };
+                        (term.as_str(), counts)
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+    let export = JsonExport {
+        schema_version: EXPORT_SCHEMA_VERSION,
+        index_version: index.version,
+        total_docs: index.total_docs,
+        avg_doc_length: index.avg_doc_length,
+        files,
+    };
+    serde_json::to_writer_pretty(&mut *writer, &export).map_err(|e| TopoError::Index {
+        path: None,
+        message: "failed to write JSON export".to_string(),
+        source: Some(Box::new(e)),
+    })?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+/// Write `index` to a new SQLite database at `path`, replacing any file
+/// there.
+///
+/// Tables: `index_info` (key-value: `schema_version`, `index_version`,
+/// `total_docs`, `avg_doc_length`), `files`, `metadata`, `chunks`, `terms`
+/// (per-file, per-field counts), `exports`, and `imports`. Paths join them all.
+pub fn export_sqlite(index: &DeepIndex, path: &Path) -> Result<(), TopoError> {
+    let sqlite_error = |e: rusqlite::Error| TopoError::Index {
+        path: Some(path.to_path_buf()),
+        message: "failed to write SQLite export".to_string(),
+        source: Some(Box::new(e)),
+    };
+
+    // Build next to the target and rename, so a failed export never leaves
+    // a half-written database behind
+    let mut staged = path.as_os_str().to_owned();
+    staged.push(".tmp");
+    let staged = Path::new(&staged);
+    if staged.exists() {
+        fs::remove_file(staged).map_err(|e| TopoError::io(staged, e))?;
+    }
+    let mut conn = Connection::open(staged).map_err(sqlite_error)?;
+    write_tables(&mut conn, index).map_err(sqlite_error)?;
+    conn.close().map_err(|(_, e)| sqlite_error(e))?;
+    fs::rename(staged, path).map_err(|e| TopoError::io(path, e))
+}
+
+fn write_tables(conn: &mut Connection, index: &DeepIndex) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    tx.execute_batch(SQLITE_SCHEMA)?;
+    {
+        let mut info = tx.prepare("INSERT INTO index_info VALUES (?1, ?2)")?;
+        info.execute(params!["schema_version", EXPORT_SCHEMA_VERSION.to_string()])?;
+        info.execute(params!["index_version", index.version.to_string()])?;
+        info.execute(params!["total_docs", index.total_docs.to_string()])?;
+        info.execute(params!["avg_doc_length", index.avg_doc_length.to_string()])?;
+
+        let mut files = tx.prepare("INSERT INTO files VALUES (?1, ?2, ?3, ?4)")?;
+        let mut metadata = tx.prepare("INSERT INTO metadata VALUES (?1, ?2, ?3)")?;
+        let mut chunks = tx.prepare(
+            "INSERT INTO chunks VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        )?;
+        let mut terms = tx.prepare("INSERT INTO terms VALUES (?1, ?2, ?3, ?4, ?5, ?6)")?;
+        let mut exports = tx.prepare("INSERT OR IGNORE INTO exports VALUES (?1, ?2)")?;
+        let mut imports = tx.prepare("INSERT INTO imports VALUES (?1, ?2)")?;
+        for path in sorted_paths(index) {
+            let entry = &index.files[path];
+            files.execute(params![
+                path,
+                hex(&entry.sha256),
+                entry.doc_length,
+                index.pagerank_scores.get(path)
+            ])?;
+            for (key, value) in &entry.metadata {
+                metadata.execute(params![path, key, value])?;
+            }
+            for c in &entry.chunks {
+                chunks.execute(params![
+                    path,
+                    c.kind.as_str(),
+                    c.name,
+                    c.start_line,
+                    c.end_line,
+                    c.content,
+                    c.target,
+                    c.doc,
+                    c.parent,
+                    c.signature,
+                    c.tokens,
+                    c.exported
+                ])?;
+            }
+            for (term, tf) in &entry.term_frequencies {
+                terms.execute(params![
+                    path,
+                    term,
+                    tf.filename,
+                    tf.symbols,
+                    tf.body,
+                    tf.docs
+                ])?;
+            }
+            for name in &entry.exports {
+                exports.execute(params![path, name])?;
+            }
+            for target in index.imports(path) {
+                imports.execute(params![path, target])?;
+            }
+        }
+    }
+    tx.commit()
+}
+
+fn sorted_paths(index: &DeepIndex) -> Vec<&str> {
+    let mut paths: Vec<&str> = index.files.keys().map(String::as_str).collect();
+    paths.sort_unstable();
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IndexBuilder;
+    use crate::test_support::write_file;
+
+    fn sample_index(root: &Path) -> DeepIndex {
+        let files = vec![
+            write_file(
+                root,
+                "main.py",
+                "import auth\n\ndef main():\n    auth.login()\n",
+            ),
+            write_file(
+                root,
+                "auth.py",
+                "def login():\n    \"\"\"Start a session.\"\"\"\n    session = 1\n\nclass Session:\n    def close(self):\n        pass\n",
+            ),
+        ];
+        IndexBuilder::new(root).build(&files, None).unwrap().0
+    }
+
+    #[test]
+    fn json_export_lists_files_chunks_and_terms() {
+        let dir = tempfile::tempdir().unwrap();
+        let index = sample_index(dir.path());
+        let mut out = Vec::new();
+        export_json(&index, &mut out).unwrap();
+
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(json["schema_version"], EXPORT_SCHEMA_VERSION);
+        assert_eq!(json["total_docs"], 2);
+        let files = json["files"].as_array().unwrap();
+        assert_eq!(files[0]["path"], "auth.py");
+        assert_eq!(files[1]["path"], "main.py");
+        assert_eq!(files[1]["imports"], serde_json::json!(["auth.py"]));
+        assert_eq!(files[0]["sha256"].as_str().unwrap().len(), 64);
+        assert_eq!(files[0]["chunks"][0]["kind"], "function");
+        assert_eq!(files[0]["chunks"][0]["name"], "login");
+        assert_eq!(files[0]["chunks"][0]["doc"], "Start a session.");
+        assert_eq!(files[0]["chunks"][0]["parent"], serde_json::Value::Null);
+        assert_eq!(files[0]["chunks"][2]["kind"], "method");
+        assert_eq!(files[0]["chunks"][2]["parent"], "Session");
+        assert_eq!(files[0]["chunks"][2]["signature"], "    def close(self):");
+        assert!(files[0]["chunks"][2]["tokens"].as_u64().unwrap() > 0);
+        assert_eq!(files[0]["chunks"][2]["exported"], true);
+        assert_eq!(
+            files[0]["exports"],
+            serde_json::json!(["login", "Session", "Session::close"])
+        );
+        assert_eq!(files[1]["chunks"][0]["kind"], "import");
+        assert_eq!(files[1]["chunks"][0]["target"], "auth.py");
+        assert!(files[0]["terms"]["session"]["body"].as_u64().unwrap() > 0);
+    }
+
+    #[test]
+    fn sqlite_export_has_documented_tables() {
+        let dir = tempfile::tempdir().unwrap();
+        let index = sample_index(dir.path());
+        let db = dir.path().join("index.db");
+        fs::write(&db, "stale").unwrap();
+        export_sqlite(&index, &db).unwrap();
+
+        let conn = Connection::open(&db).unwrap();
+        let count = |sql: &str| -> i64 { conn.query_row(sql, [], |row| row.get(0)).unwrap() };
+        assert_eq!(count("SELECT COUNT(*) FROM files"), 2);
+        assert_eq!(
+            count("SELECT COUNT(*) FROM imports WHERE path = 'main.py' AND target = 'auth.py'"),
+            1
+        );
+        assert_eq!(
+            count("SELECT COUNT(*) FROM chunks WHERE kind = 'function' AND name = 'login'"),
+            1
+        );
+        assert_eq!(
+            count("SELECT COUNT(*) FROM chunks WHERE kind = 'import' AND target = 'auth.py'"),
+            1
+        );
+        assert_eq!(
+            count("SELECT COUNT(*) FROM chunks WHERE doc = 'Start a session.'"),
+            1
+        );
+        assert_eq!(
+            count("SELECT COUNT(*) FROM chunks WHERE name = 'close' AND parent = 'Session'"),
+            1
+        );
+        assert_eq!(
+            count("SELECT COUNT(*) FROM chunks WHERE signature = 'def login():' AND tokens > 0"),
+            1
+        );
+        assert_eq!(
+            count("SELECT COUNT(*) FROM chunks WHERE name = 'login' AND exported"),
+            1
+        );
+        assert_eq!(
+            count("SELECT COUNT(*) FROM exports WHERE path = 'auth.py' AND name = 'Session'"),
+            1
+        );
+        assert_eq!(
+            count("SELECT COUNT(DISTINCT path) FROM terms WHERE term = 'login'"),
+            2
+        );
+        let docs: String = conn
+            .query_row(
+                "SELECT value FROM index_info WHERE key = 'total_docs'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(docs, "2");
+        assert!(!dir.path().join("index.db.tmp").exists());
+    }
+}
@@ -0,0 +1,151 @@
+use crate::builder::build_trigrams;
+use crate::duplicates::duplicate_groups;
+use crate::tombstone;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use topo_core::{DeepIndex, FileEntry};
+use topo_score::ImportGraph;
+
+/// Drop entries for files that no longer exist under `root` and recompute
+/// corpus statistics, without reindexing anything.
+///
+/// Rebuilds always drop deleted files, but between builds their entries
+/// still count toward document frequencies and `total_docs`. Pruned files
+/// become tombstones. Files in `sparse`, left out of a sparse checkout,
+/// are kept. Returns the pruned paths, sorted.
+pub fn prune(index: &mut DeepIndex, root: &Path, sparse: &HashSet<String>) -> Vec<String> {
+    let mut pruned: Vec<String> = index
+        .files
+        .keys()
+        .filter(|path| !sparse.contains(*path) && !root.join(path.as_str()).is_file())
+        .cloned()
+        .collect();
+    if pruned.is_empty() {
+        return pruned;
+    }
+    pruned.sort();
+    for path in &pruned {
+        index.files.remove(path);
+    }
+
+    let present: HashSet<&str> = index.files.keys().map(String::as_str).collect();
+    index.tombstones = tombstone::update(
+        &index.tombstones,
+        pruned.clone(),
+        &present,
+        tombstone::now_secs(),
+    );
+
+    // Imports of pruned files go with them, like edges into them
+    let mut graph = ImportGraph::new();
+    for path in &present {
+        graph.add_node(path);
+    }
+    for (from, deps) in &index.dependencies {
+        if !present.contains(from.as_str()) {
+            continue;
+        }
+        for to in deps.iter().filter(|to| present.contains(to.as_str())) {
+            graph.add_edge(from, to);
+        }
+    }
+    index.dependencies = graph.adjacency();
+    index.pagerank_scores = graph.normalized_pagerank();
+    if index.trigrams.is_some() {
+        index.trigrams = Some(build_trigrams(&index.files));
+    }
+    index.duplicate_chunks = duplicate_groups(&index.files);
+    if let Some(segment) = &index.embeddings {
+        let current = index.files.iter().map(|(p, e)| (p.as_str(), e.sha256));
+        index.embeddings = Some(segment.carried_over(current));
+    }
+    recompute_corpus_stats(index);
+    pruned
+}
+
+/// Recompute `total_docs`, `avg_doc_length`, and `doc_frequencies` from the
+/// index's file entries.
+pub(crate) fn recompute_corpus_stats(index: &mut DeepIndex) {
+    let (total_docs, avg_doc_length, doc_frequencies) = corpus_stats(&index.files);
+    index.total_docs = total_docs;
+    index.avg_doc_length = avg_doc_length;
+    index.doc_frequencies = doc_frequencies;
+}
+
+fn corpus_stats(files: &HashMap<String, FileEntry>) -> (u32, f64, HashMap<String, u32>) {
+    let total_docs = files.len() as u32;
+    let total_length: u32 = files.values().map(|e| e.doc_length).sum();
+    let avg_doc_length = if total_docs > 0 {
+        total_length as f64 / total_docs as f64
+    } else {
+        1.0
+    };
+
+    let mut doc_frequencies: HashMap<String, u32> = HashMap::new();
+    for entry in files.values() {
+        for term in entry.term_frequencies.keys() {
+            *doc_frequencies.entry(term.clone()).or_default() += 1;
+        }
+    }
+    (total_docs, avg_doc_length, doc_frequencies)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IndexBuilder;
+    use crate::test_support::write_file;
+    use std::fs;
+
+    #[test]
+    fn prunes_deleted_files_and_recomputes_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let files = vec![
+            write_file(
+                root,
+                "main.py",
+                "import auth\n\ndef main():\n    auth.login()\n",
+            ),
+            write_file(root, "auth.py", "def login():\n    session = 1\n"),
+            write_file(root, "util.py", "def session():\n    pass\n"),
+        ];
+        let mut index = IndexBuilder::new(root)
+            .trigrams(true)
+            .build(&files, None)
+            .unwrap()
+            .0;
+        assert_eq!(index.imports("main.py"), ["auth.py"]);
+        assert_eq!(index.doc_frequencies["session"], 2);
+
+        fs::remove_file(root.join("auth.py")).unwrap();
+        let pruned = prune(&mut index, root, &HashSet::new());
+
+        assert_eq!(pruned, ["auth.py"]);
+        assert_eq!(index.total_docs, 2);
+        assert!(!index.files.contains_key("auth.py"));
+        assert_eq!(index.doc_frequencies["session"], 1);
+        assert!(index.imports("main.py").is_empty());
+        assert!(!index.pagerank_scores.contains_key("auth.py"));
+        let trigrams = index.trigrams.as_ref().unwrap();
+        assert!(!trigrams.files_with("login").contains(&"auth.py"));
+        assert_eq!(index.tombstones[0].path, "auth.py");
+    }
+
+    #[test]
+    fn nothing_to_prune_leaves_index_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = vec![write_file(dir.path(), "a.rs", "fn a() {}\n")];
+        let mut index = IndexBuilder::new(dir.path()).build(&files, None).unwrap().0;
+        let before = index.doc_frequencies.clone();
+        assert!(prune(&mut index, dir.path(), &HashSet::new()).is_empty());
+        assert_eq!(index.doc_frequencies, before);
+        assert!(index.tombstones.is_empty());
+
+        // Files outside a sparse checkout aren't deleted
+        fs::remove_file(dir.path().join("a.rs")).unwrap();
+        let sparse = HashSet::from(["a.rs".to_string()]);
+        assert!(prune(&mut index, dir.path(), &sparse).is_empty());
+        assert_eq!(index.doc_frequencies, before);
+    }
+}
@@ -1,10 +1,52 @@
 //! Deep index with serialization and incremental updates.
 
+mod blame;
 mod builder;
+mod cache;
+mod corpus;
+mod crypt;
+mod duplicates;
+mod embed;
+mod export;
+mod gc;
+mod license;
+mod lock;
+mod pruning;
+mod remote;
+mod shard;
+mod stats;
 mod store;
-
-pub use builder::IndexBuilder;
-pub use store::{index_path, load, merge_incremental, save};
+#[cfg(test)]
+pub(crate) mod test_support;
+mod tombstone;
+mod usage;
+mod verify;
+
+pub use blame::{blame_cache_path, cached_blame_summaries};
+pub use builder::{EnrichFn, INDEX_VERSION, IndexBuilder, ProgressFn};
+pub use cache::{
+    CACHE_FORMAT_VERSION, CacheFile, CacheManifest, ImportReport, export_cache, import_cache,
+};
+pub use corpus::{cached_corpus_stats, corpus_stats_path};
+pub use crypt::INDEX_KEY_VAR;
+pub use embed::EmbedQueue;
+pub use export::{EXPORT_SCHEMA_VERSION, export_json, export_sqlite};
+pub use gc::prune;
+pub use remote::{
+    DirBackend, REMOTE_TOKEN_VAR, RemoteBackend, pull_cache, push_cache, remote_backend,
+};
+#[cfg(feature = "remote")]
+pub use remote::{HttpBackend, S3Backend};
+pub use shard::{PlannedFile, SavePlan, SaveReport, ShardedIndex};
+pub use stats::{FileCount, IndexStats};
+pub use store::{
+    daemon_socket_path, index_files, index_path, load, load_repo, merge_incremental,
+    model_cache_path, open_repo, plan_save_repo, remove_indexes, repo_index_path, save, save_repo,
+    save_with_level,
+};
+pub use tombstone::{MAX_TOMBSTONES, TOMBSTONE_TTL_SECS, recently_deleted};
+pub use usage::{MAX_LATENCY_SAMPLES, UsageStats, load_usage, record_usage, usage_path};
+pub use verify::{IndexIssue, VerifyReport, verify, verify_repo};
 
 #[cfg(test)]
 mod tests {
@@ -25,6 +67,7 @@ mod tests {
             language: Language::from_path(Path::new(path)),
             role: topo_core::FileRole::from_path(Path::new(path)),
             sha256: hash,
+            package: None,
         }
     }
 
@@ -0,0 +1,151 @@
+use std::borrow::Cow;
+
+/// Phrases, lowercased, that mark a leading comment block as a license
+/// or copyright header.
+const LICENSE_MARKERS: &[&str] = &[
+    "spdx-license-identifier",
+    "copyright",
+    "licensed under",
+    "license, version",
+    "permission is hereby granted",
+    "gnu general public license",
+    "gnu lesser general public license",
+    "mozilla public license",
+    "all rights reserved",
+    "without warranties or conditions",
+    "the software is provided \"as is\"",
+];
+
+/// Line comment prefixes a header may be written with.
+const LINE_COMMENTS: &[&str] = &["//", "#", "--", ";"];
+
+/// `content` with its leading license headers blanked out, so that
+/// boilerplate repeated at the top of every file doesn't count toward
+/// term statistics.
+///
+/// A header is a comment block before any code (after an optional shebang
+/// and blank lines) that mentions a copyright or license; several such
+/// blocks in a row are all blanked. Lines are kept, emptied, so line
+/// numbers don't move. Doc comments (`///`, `//!`, `/*!`) are never headers.
+pub(crate) fn strip_license_header(content: &str) -> Cow<'_, str> {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let mut line = 0;
+    if lines.first().is_some_and(|l| l.starts_with("#!")) {
+        line = 1;
+    }
+
+    let mut header = line..line;
+    loop {
+        while lines.get(line).is_some_and(|l| l.trim().is_empty()) {
+            line += 1;
+        }
+        let Some(end) = comment_block(&lines, line) else {
+            break;
+        };
+        let text = lines[line..end].concat().to_lowercase();
+        if !LICENSE_MARKERS.iter().any(|marker| text.contains(marker)) {
+            break;
+        }
+        header.end = end;
+        line = end;
+    }
+    if header.is_empty() {
+        return Cow::Borrowed(content);
+    }
+
+    let mut stripped = String::with_capacity(content.len());
+    for (i, l) in lines.iter().enumerate() {
+        if header.contains(&i) {
+            if l.ends_with('\n') {
+                stripped.push('\n');
+            }
+        } else {
+            stripped.push_str(l);
+        }
+    }
+    Cow::Owned(stripped)
+}
+
+/// End (exclusive) of the comment block starting at line `start`, if one
+/// does.
+fn comment_block(lines: &[&str], start: usize) -> Option<usize> {
+    let first = lines.get(start)?.trim_start();
+    if first.starts_with("///") || first.starts_with("//!") || first.starts_with("/*!") {
+        return None;
+    }
+
+    let closing = if let Some(rest) = first.strip_prefix("/*") {
+        Some((rest, "*/"))
+    } else {
+        first.strip_prefix("<!--").map(|rest| (rest, "-->"))
+    };
+    if let Some((rest, close)) = closing {
+        if rest.contains(close) {
+            return Some(start + 1);
+        }
+        let end = (start + 1..lines.len()).find(|&i| lines[i].contains(close))?;
+        return Some(end + 1);
+    }
+
+    let prefix = LINE_COMMENTS
+        .iter()
+        .find(|&&prefix| is_line_comment(first, prefix))?;
+    let end = (start..lines.len())
+        .find(|&i| !is_line_comment(lines[i].trim_start(), prefix))
+        .unwrap_or(lines.len());
+    Some(end)
+}
+
+/// Whether `line` is a `prefix` comment. A `#` must be followed by
+/// whitespace or another `#`, so `#include` and `#[derive]` aren't.
+fn is_line_comment(line: &str, prefix: &str) -> bool {
+    let Some(rest) = line.strip_prefix(prefix) else {
+        return false;
+    };
+    if line.starts_with("///") || line.starts_with("//!") {
+        return false;
+    }
+    prefix != "#"
+        || rest.is_empty()
+        || rest.starts_with(char::is_whitespace)
+        || rest.starts_with('#')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_line_and_block_license_headers() {
+        let apache = "// Copyright 2024 Example Corp.\n//\n// Licensed under the Apache License, Version 2.0\n\npackage main\n";
+        assert_eq!(strip_license_header(apache), "\n\n\n\npackage main\n");
+
+        let mit =
+            "/*\n * Permission is hereby granted, free of charge...\n */\nimport x from 'y';\n";
+        assert_eq!(strip_license_header(mit), "\n\n\nimport x from 'y';\n");
+
+        let python = "#!/usr/bin/env python\n# SPDX-License-Identifier: MIT\n\"\"\"Tool.\"\"\"\n";
+        assert_eq!(
+            strip_license_header(python),
+            "#!/usr/bin/env python\n\n\"\"\"Tool.\"\"\"\n"
+        );
+
+        // A copyright line followed by a separate license block
+        let two = "/* Copyright (c) Example */\n\n/* SPDX-License-Identifier: GPL-2.0 */\nint x;";
+        assert_eq!(strip_license_header(two), "\n\n\nint x;");
+    }
+
+    #[test]
+    fn keeps_other_leading_comments_and_code() {
+        for content in [
+            "// Handles login and logout.\nfn login() {}\n",
+            "//! Copyright handling for the docs crate.\nfn f() {}\n",
+            "#include <stdio.h>\n// Copyright 2024\n",
+            "fn main() {}\n// Copyright 2024 Example\n",
+            "/* Copyright 2024, never closed\nint x;\n",
+            "",
+        ] {
+            assert!(matches!(strip_license_header(content), Cow::Borrowed(_)));
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::fs::{self, File, TryLockError};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+use topo_core::TopoError;
+
+/// Extension of the lock file beside an index directory.
+pub(crate) const LOCK_EXTENSION: &str = "lock";
+
+/// How long a writer waits for another to finish before giving up.
+pub(crate) const LOCK_TIMEOUT: Duration = Duration::from_secs(30);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Advisory lock on an index directory, held while writing it so that
+/// concurrent writers take turns instead of interleaving their files.
+///
+/// Released when dropped, and by the OS if the process dies, so a crashed
+/// writer never leaves the index locked.
+#[derive(Debug)]
+pub(crate) struct IndexLock {
+    _file: File,
+}
+
+impl IndexLock {
+    /// Lock `dir` through a file beside it (`index.lock` for `index`),
+    /// waiting up to `timeout` for another writer to let go.
+    pub(crate) fn acquire(dir: &Path, timeout: Duration) -> Result<Self, TopoError> {
+        let path = dir.with_extension(LOCK_EXTENSION);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| TopoError::io(parent, e))?;
+        }
+        let file = File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .map_err(|e| TopoError::io(&path, e))?;
+
+        let started = Instant::now();
+        loop {
+            match file.try_lock() {
+                Ok(()) => return Ok(Self { _file: file }),
+                Err(TryLockError::WouldBlock) if started.elapsed() < timeout => {
+                    thread::sleep(POLL_INTERVAL);
+                }
+                Err(TryLockError::WouldBlock) => {
+                    let message = format!(
+                        "another process has been writing the index for over {}s",
+                        timeout.as_secs()
+                    );
+                    return Err(TopoError::index_locked(message).with_path(dir));
+                }
+                Err(TryLockError::Error(e)) => return Err(TopoError::io(&path, e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use topo_core::ErrorCode;
+
+    #[test]
+    fn second_writer_waits_then_gives_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let index_dir = dir.path().join("index");
+        let held = IndexLock::acquire(&index_dir, LOCK_TIMEOUT).unwrap();
+
+        assert!(dir.path().join("index.lock").is_file());
+        let err = IndexLock::acquire(&index_dir, Duration::from_millis(100)).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::IndexLocked);
+        assert_eq!(err.path(), Some(index_dir.as_path()));
+
+        drop(held);
+        assert!(IndexLock::acquire(&index_dir, Duration::ZERO).is_ok());
+    }
+
+    #[test]
+    fn waiting_writer_proceeds_once_released() {
+        let dir = tempfile::tempdir().unwrap();
+        let index_dir = dir.path().join("index");
+        let held = IndexLock::acquire(&index_dir, LOCK_TIMEOUT).unwrap();
+        let waiter = thread::spawn({
+            let dir = index_dir.clone();
+            move || IndexLock::acquire(&dir, LOCK_TIMEOUT).is_ok()
+        });
+        thread::sleep(Duration::from_millis(120));
+        drop(held);
+        assert!(waiter.join().unwrap());
+    }
+}
@@ -0,0 +1,251 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use topo_core::{FileEntry, PruneConfig, PruneReport, TermFreqs};
+
+/// Estimated bytes of one posting besides its term: the frequencies plus
+/// the map's per-key overhead.
+const POSTING_OVERHEAD: u64 = size_of::<TermFreqs>() as u64 + 8;
+
+/// Leave terms out of `entries` as `config` asks, in order: single-file
+/// terms of `generated` files, postings beyond the per-term cap, then
+/// single body occurrences of the most common terms until the estimated
+/// size fits the budget.
+///
+/// Returns None when `config` prunes nothing. Document lengths are kept,
+/// so pruned files are still normalized by their full length.
+pub(crate) fn prune_terms(
+    entries: &mut [(String, FileEntry)],
+    generated: &HashSet<&str>,
+    config: PruneConfig,
+) -> Option<PruneReport> {
+    if config == PruneConfig::default() {
+        return None;
+    }
+    let mut report = PruneReport {
+        bytes_before: estimated_size(entries),
+        ..PruneReport::default()
+    };
+
+    if config.generated_hapax {
+        let frequencies = doc_frequencies(entries);
+        let dropped: Vec<(usize, String)> = entries
+            .iter()
+            .enumerate()
+            .filter(|(_, (path, _))| generated.contains(path.as_str()))
+            .flat_map(|(i, (_, entry))| {
+                entry
+                    .term_frequencies
+                    .keys()
+                    .filter(|term| frequencies[term.as_str()] == 1)
+                    .map(move |term| (i, term.clone()))
+            })
+            .collect();
+        report.generated_hapax = remove(entries, dropped);
+    }
+
+    if let Some(cap) = config.max_postings {
+        let cap = cap as usize;
+        let frequencies = doc_frequencies(entries);
+        let mut postings: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (i, (_, entry)) in entries.iter().enumerate() {
+            for term in entry.term_frequencies.keys() {
+                if frequencies[term.as_str()] as usize > cap {
+                    postings.entry(term).or_default().push(i);
+                }
+            }
+        }
+        report.capped_terms = postings.len() as u64;
+        let mut dropped: Vec<(usize, String)> = Vec::new();
+        for (term, mut files) in postings {
+            // Keep the files where the term weighs most
+            files.sort_by_key(|&i| {
+                let tf = &entries[i].1.term_frequencies[term];
+                (Reverse(tf.filename + tf.symbols + tf.body), &entries[i].0)
+            });
+            dropped.extend(files[cap..].iter().map(|&i| (i, term.to_string())));
+        }
+        report.capped_postings = remove(entries, dropped);
+    }
+
+    if let Some(budget) = config.max_size_mb.map(|mb| mb * 1024 * 1024) {
+        let mut size = estimated_size(entries);
+        if size > budget {
+            let frequencies = doc_frequencies(entries);
+            // Common terms carry the least weight in BM25F, so their
+            // lone occurrences go first
+            let mut candidates: Vec<(u32, &str, usize)> = entries
+                .iter()
+                .enumerate()
+                .flat_map(|(i, (_, entry))| {
+                    entry
+                        .term_frequencies
+                        .iter()
+                        .filter(|(_, tf)| tf.filename == 0 && tf.symbols == 0 && tf.body <= 1)
+                        .map(move |(term, _)| (i, term.as_str()))
+                })
+                .map(|(i, term)| (frequencies[term], term, i))
+                .collect();
+            candidates.sort_by(|a, b| {
+                b.0.cmp(&a.0)
+                    .then(a.1.cmp(b.1))
+                    .then(entries[a.2].0.cmp(&entries[b.2].0))
+            });
+            let mut dropped: Vec<(usize, String)> = Vec::new();
+            for (_, term, i) in candidates {
+                if size <= budget {
+                    break;
+                }
+                size -= posting_size(term);
+                dropped.push((i, term.to_string()));
+            }
+            report.budget_postings = remove(entries, dropped);
+        }
+    }
+
+    report.bytes_after = estimated_size(entries);
+    Some(report)
+}
+
+/// Estimated size of the term postings of `entries`, in bytes.
+fn estimated_size(entries: &[(String, FileEntry)]) -> u64 {
+    entries
+        .iter()
+        .flat_map(|(_, entry)| entry.term_frequencies.keys())
+        .map(|term| posting_size(term))
+        .sum()
+}
+
+fn posting_size(term: &str) -> u64 {
+    term.len() as u64 + POSTING_OVERHEAD
+}
+
+fn doc_frequencies(entries: &[(String, FileEntry)]) -> HashMap<&str, u32> {
+    let mut frequencies: HashMap<&str, u32> = HashMap::new();
+    for (_, entry) in entries {
+        for term in entry.term_frequencies.keys() {
+            *frequencies.entry(term).or_default() += 1;
+        }
+    }
+    frequencies
+}
+
+/// Remove `(entry index, term)` postings, returning how many there were.
+fn remove(entries: &mut [(String, FileEntry)], dropped: Vec<(usize, String)>) -> u64 {
+    let count = dropped.len() as u64;
+    for (i, term) in dropped {
+        entries[i].1.term_frequencies.remove(&term);
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, terms: &[(&str, u32, u32)]) -> (String, FileEntry) {
+        let term_frequencies = terms
+            .iter()
+            .map(|&(term, symbols, body)| {
+                let tf = TermFreqs {
+                    filename: 0,
+                    symbols,
+                    body,
+                    docs: 0,
+                };
+                (term.to_string(), tf)
+            })
+            .collect();
+        let file = FileEntry {
+            sha256: [0; 32],
+            size: 0,
+            chunks: Vec::new(),
+            term_frequencies,
+            doc_length: 10,
+            metadata: Default::default(),
+            chunk_terms: None,
+            exports: Vec::new(),
+            chunk_hashes: Vec::new(),
+        };
+        (path.to_string(), file)
+    }
+
+    fn terms(entry: &(String, FileEntry)) -> Vec<&str> {
+        let mut terms: Vec<&str> = entry
+            .1
+            .term_frequencies
+            .keys()
+            .map(String::as_str)
+            .collect();
+        terms.sort_unstable();
+        terms
+    }
+
+    #[test]
+    fn nothing_configured_prunes_nothing() {
+        let mut entries = vec![entry("a.rs", &[("auth", 1, 1)])];
+        assert!(prune_terms(&mut entries, &HashSet::new(), PruneConfig::default()).is_none());
+        assert_eq!(terms(&entries[0]), ["auth"]);
+    }
+
+    #[test]
+    fn drops_hapax_terms_of_generated_files_only() {
+        let mut entries = vec![
+            entry("gen/api.pb.rs", &[("xq7field", 3, 3), ("request", 1, 1)]),
+            entry("src/api.rs", &[("request", 1, 2), ("handwritten", 1, 1)]),
+        ];
+        let generated = HashSet::from(["gen/api.pb.rs"]);
+        let config = PruneConfig {
+            generated_hapax: true,
+            ..PruneConfig::default()
+        };
+        let report = prune_terms(&mut entries, &generated, config).unwrap();
+
+        assert_eq!(terms(&entries[0]), ["request"]);
+        assert_eq!(terms(&entries[1]), ["handwritten", "request"]);
+        assert_eq!(report.generated_hapax, 1);
+        assert_eq!(report.postings(), 1);
+        assert_eq!(
+            report.bytes_before - report.bytes_after,
+            posting_size("xq7field")
+        );
+    }
+
+    #[test]
+    fn caps_postings_keeping_the_heaviest() {
+        let mut entries = vec![
+            entry("a.rs", &[("util", 0, 1)]),
+            entry("b.rs", &[("util", 2, 5), ("rare", 0, 1)]),
+            entry("c.rs", &[("util", 0, 3)]),
+        ];
+        let config = PruneConfig {
+            max_postings: Some(2),
+            ..PruneConfig::default()
+        };
+        let report = prune_terms(&mut entries, &HashSet::new(), config).unwrap();
+
+        assert!(terms(&entries[0]).is_empty());
+        assert_eq!(terms(&entries[1]), ["rare", "util"]);
+        assert_eq!(terms(&entries[2]), ["util"]);
+        assert_eq!((report.capped_terms, report.capped_postings), (1, 1));
+    }
+
+    #[test]
+    fn size_budget_drops_lone_occurrences_of_common_terms_first() {
+        let long = "x".repeat(1024 * 1024);
+        let mut entries = vec![
+            entry("a.rs", &[("common", 0, 1), ("rare", 0, 1), (&long, 1, 0)]),
+            entry("b.rs", &[("common", 0, 1), ("symbol", 4, 0)]),
+        ];
+        let config = PruneConfig {
+            max_size_mb: Some(1),
+            ..PruneConfig::default()
+        };
+        let report = prune_terms(&mut entries, &HashSet::new(), config).unwrap();
+
+        // The symbol term never qualifies, so the budget stays unmet
+        assert_eq!(terms(&entries[0]), [long.as_str()]);
+        assert_eq!(terms(&entries[1]), ["symbol"]);
+        assert_eq!(report.budget_postings, 3);
+        assert!(report.bytes_after > 1024 * 1024);
+    }
+}
@@ -0,0 +1,1380 @@
+use crate::builder::INDEX_VERSION;
+use crate::crypt;
+use crate::lock::{IndexLock, LOCK_TIMEOUT};
+use crate::store;
+use crate::tombstone;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::SystemTime;
+use topo_core::{DeepIndex, EmbeddingSegment, FileEntry, TermFreqs, TopoError};
+
+/// Directory holding a sharded index, relative to the index dir.
+pub(crate) const SHARD_DIR: &str = "index";
+/// Manifest inside [`SHARD_DIR`], listing the shards.
+pub(crate) const MANIFEST_FILE: &str = "manifest.bin";
+/// Directory inside [`SHARD_DIR`] holding the last manifest saved on each
+/// branch.
+const BRANCH_DIR: &str = "branches";
+/// Branch manifests kept; older ones are dropped with their shards.
+const MAX_BRANCHES: usize = 8;
+/// Directory inside [`SHARD_DIR`] holding embedding segments.
+const EMBEDDING_DIR: &str = "embeddings";
+/// Suffix of files being written, renamed into place once complete.
+const STAGED_SUFFIX: &str = ".tmp";
+/// A changed shard is saved as a delta on its last saved file while the
+/// delta holds at most this share (1/n) of the file's entries, and is
+/// compacted into a full shard file once it grows past it.
+const MAX_DELTA_SHARE: usize = 4;
+
+/// Corpus-wide index data and the shards holding its file entries.
+#[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+struct Manifest {
+    /// Everything but `files`, which live in the shards.
+    index: DeepIndex,
+    /// Branch the index was built on, if known.
+    branch: Option<String>,
+    shards: Vec<ShardRef>,
+    /// Embedding segment file, relative to [`SHARD_DIR`].
+    embeddings: Option<String>,
+    /// zstd level the files were written at.
+    level: i32,
+}
+
+#[derive(Clone, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+struct ShardRef {
+    /// Top-level directory of the shard's files, empty for root files.
+    key: String,
+    /// File name inside [`SHARD_DIR`].
+    file: String,
+    /// Fingerprint of the shard's entries, see [`shard_digest`]. Also
+    /// names the shard file, unless the entries are `file` plus `delta`.
+    digest: [u8; 32],
+    /// [`ShardDelta`] file inside [`SHARD_DIR`] to apply on top of `file`.
+    delta: Option<String>,
+}
+
+/// Changes to a shard's entries since its base file was written, so a
+/// small edit rewrites a few entries rather than the whole shard.
+#[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+struct ShardDelta {
+    /// Entries added or changed since the base file.
+    upserts: HashMap<String, FileEntry>,
+    /// Paths of base entries since removed.
+    removed: Vec<String>,
+}
+
+/// Where one entry is in a shard file.
+///
+/// A shard file is the little-endian length of its table of slots, the
+/// encoded table, sorted by path, and then each entry encoded on its own,
+/// so a query can read the entries it needs and skip the rest.
+#[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+struct EntrySlot {
+    path: String,
+    /// Offset of the encoded entry from the end of the table.
+    offset: u64,
+    len: u64,
+}
+
+impl ShardDelta {
+    fn apply(self, entries: &mut HashMap<String, FileEntry>) {
+        for path in &self.removed {
+            entries.remove(path);
+        }
+        entries.extend(self.upserts);
+    }
+}
+
+/// Outcome of saving a sharded index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SaveReport {
+    pub shards: usize,
+    /// Shards written because no save, on any branch, had stored them yet.
+    pub written: usize,
+    /// Of `written`, shards saved as a delta on an earlier shard file.
+    pub deltas: usize,
+}
+
+/// Files a save would change, see [`crate::plan_save_repo`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SavePlan {
+    pub created: Vec<PlannedFile>,
+    pub overwritten: Vec<PlannedFile>,
+    pub deleted: Vec<PlannedFile>,
+}
+
+impl SavePlan {
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty() && self.overwritten.is_empty() && self.deleted.is_empty()
+    }
+}
+
+/// A file in a [`SavePlan`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedFile {
+    pub path: PathBuf,
+    /// Size once written, or before deletion.
+    pub bytes: u64,
+}
+
+impl PlannedFile {
+    /// `path` at its current size, if it exists.
+    pub(crate) fn existing(path: PathBuf) -> Option<Self> {
+        let bytes = fs::metadata(&path).ok()?.len();
+        Some(Self { path, bytes })
+    }
+}
+
+/// A sharded index whose file entries load on first access.
+///
+/// Opening one reads only the manifest, and looking up an entry reads only
+/// its shard's table and the entry itself, so queries that look at a
+/// handful of files never read the entries of the rest of the repository.
+pub struct ShardedIndex {
+    dir: PathBuf,
+    branch: Option<String>,
+    /// Corpus-wide data. `files` is only populated for legacy single-file
+    /// indexes, which have no shards.
+    index: DeepIndex,
+    shards: HashMap<String, Shard>,
+    embeddings: Option<Segment>,
+}
+
+struct Segment {
+    file: String,
+    /// None once the segment turned out to be unreadable.
+    segment: OnceLock<Option<EmbeddingSegment>>,
+}
+
+struct Shard {
+    file: String,
+    delta: Option<String>,
+    /// None once the shard turned out to be unreadable.
+    table: OnceLock<Option<EntryTable>>,
+}
+
+/// The entries of a shard, each loaded on first access.
+struct EntryTable {
+    /// Offset of the first entry in the shard file.
+    start: u64,
+    slots: HashMap<String, Slot>,
+}
+
+struct Slot {
+    /// Offset from [`EntryTable::start`] and length of the encoded entry.
+    /// Entries from a delta are loaded with the table and have neither.
+    offset: u64,
+    len: u64,
+    /// None once the entry turned out to be unreadable.
+    entry: OnceLock<Option<FileEntry>>,
+}
+
+impl ShardedIndex {
+    /// Open the sharded index in `dir`, as last saved on `branch` if it
+    /// was, otherwise as last saved. Returns None if there is none, or if
+    /// its manifest is unreadable or outdated.
+    pub(crate) fn open(dir: &Path, branch: Option<&str>) -> Result<Option<Self>, TopoError> {
+        let branch_manifest = branch
+            .map(|branch| dir.join(BRANCH_DIR).join(branch_file(branch)))
+            .and_then(|path| fs::read(path).ok())
+            .and_then(|bytes| decode_manifest(&bytes));
+        let manifest = match branch_manifest {
+            Some(manifest) => manifest,
+            None => {
+                let path = dir.join(MANIFEST_FILE);
+                if !path.exists() {
+                    return Ok(None);
+                }
+                let bytes = fs::read(&path).map_err(|e| TopoError::io(&path, e))?;
+                crypt::check_readable(&path, &bytes)?;
+                let Some(manifest) = decode_manifest(&bytes) else {
+                    return Ok(None);
+                };
+                manifest
+            }
+        };
+        let shards = manifest
+            .shards
+            .into_iter()
+            .map(|shard| {
+                let table = OnceLock::new();
+                let (file, delta) = (shard.file, shard.delta);
+                (shard.key, Shard { file, delta, table })
+            })
+            .collect();
+        let embeddings = manifest.embeddings.map(|file| Segment {
+            file,
+            segment: OnceLock::new(),
+        });
+        Ok(Some(Self {
+            dir: dir.to_path_buf(),
+            branch: manifest.branch,
+            index: manifest.index,
+            shards,
+            embeddings,
+        }))
+    }
+
+    /// Wrap a legacy single-file index, which is already fully loaded.
+    pub(crate) fn whole(index: DeepIndex) -> Self {
+        Self {
+            dir: PathBuf::new(),
+            branch: None,
+            index,
+            shards: HashMap::new(),
+            embeddings: None,
+        }
+    }
+
+    /// Corpus statistics, PageRank, tombstones, dependencies, and trigrams.
+    /// Use [`ShardedIndex::entry`] for file entries.
+    pub fn corpus(&self) -> &DeepIndex {
+        &self.index
+    }
+
+    /// The entry for `path`, reading it from its shard if needed. Files
+    /// whose shard or entry can't be read are treated as unindexed.
+    pub fn entry(&self, path: &str) -> Option<&FileEntry> {
+        if let Some(entry) = self.index.files.get(path) {
+            return Some(entry);
+        }
+        let shard = self.shards.get(shard_key(path))?;
+        let table = self.table(shard)?;
+        let slot = table.slots.get(path)?;
+        slot.entry
+            .get_or_init(|| read_entry(&self.dir.join(&shard.file), table.start, slot))
+            .as_ref()
+    }
+
+    /// Content hash of every indexed file, by path. Reads all shards.
+    pub fn hashes(&self) -> HashMap<&str, [u8; 32]> {
+        let mut hashes = tombstone::hashes(&self.index.files);
+        for shard in self.shards.values() {
+            hashes.extend(
+                self.entries(shard)
+                    .map(|(path, entry)| (path, entry.sha256)),
+            );
+        }
+        hashes
+    }
+
+    /// Number of file entries read from shards so far.
+    pub fn loaded_entries(&self) -> usize {
+        self.shards
+            .values()
+            .filter_map(|shard| shard.table.get()?.as_ref())
+            .flat_map(|table| table.slots.values())
+            .filter(|slot| slot.entry.get().is_some_and(Option::is_some))
+            .count()
+    }
+
+    /// Branch the index was built on, if known.
+    pub fn branch(&self) -> Option<&str> {
+        self.branch.as_deref()
+    }
+
+    /// Number of shards, 0 for legacy single-file indexes.
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Stored embedding vectors, loading their segment on first access.
+    /// None if there are none or the segment can't be read.
+    pub fn embeddings(&self) -> Option<&EmbeddingSegment> {
+        if let Some(segment) = &self.index.embeddings {
+            return Some(segment);
+        }
+        let embeddings = self.embeddings.as_ref()?;
+        embeddings
+            .segment
+            .get_or_init(|| read_segment(&self.dir.join(&embeddings.file)))
+            .as_ref()
+    }
+
+    fn table<'a>(&'a self, shard: &'a Shard) -> Option<&'a EntryTable> {
+        shard
+            .table
+            .get_or_init(|| read_table(&self.dir, &shard.file, shard.delta.as_deref()))
+            .as_ref()
+    }
+
+    /// Every readable entry of `shard`. Entries not loaded yet are read
+    /// together, in one pass over the shard file.
+    fn entries<'a>(&'a self, shard: &'a Shard) -> impl Iterator<Item = (&'a str, &'a FileEntry)> {
+        let table = self.table(shard);
+        if let Some(table) = table
+            && table.slots.values().any(|slot| slot.entry.get().is_none())
+        {
+            let bytes = fs::read(self.dir.join(&shard.file)).ok();
+            for slot in table.slots.values() {
+                slot.entry.get_or_init(|| {
+                    let start = usize::try_from(table.start + slot.offset).ok()?;
+                    let end = start.checked_add(usize::try_from(slot.len).ok()?)?;
+                    decode_entry(bytes.as_ref()?.get(start..end)?)
+                });
+            }
+        }
+        table
+            .into_iter()
+            .flat_map(|table| &table.slots)
+            .filter_map(|(path, slot)| Some((path.as_str(), slot.entry.get()?.as_ref()?)))
+    }
+
+    /// Load every shard into a complete index. Returns None if any shard
+    /// is missing or unreadable, so the index gets rebuilt. An unreadable
+    /// embedding segment is only left out.
+    pub fn into_index(self) -> Option<DeepIndex> {
+        let mut index = self.index;
+        if let Some(embeddings) = self.embeddings {
+            index.embeddings = match embeddings.segment.into_inner() {
+                Some(segment) => segment,
+                None => read_segment(&self.dir.join(&embeddings.file)),
+            };
+        }
+        for shard in self.shards.into_values() {
+            index.files.extend(read_entries(
+                &self.dir,
+                &shard.file,
+                shard.delta.as_deref(),
+            )?);
+        }
+        Some(index)
+    }
+}
+
+/// Write `index` into `dir` as one shard per top-level directory plus a
+/// manifest, and a copy of the manifest for `branch` when set.
+///
+/// Shard files are named by their contents, so only shards whose entries
+/// changed are written, and branches share the shards they have in
+/// common. A shard with a few changed entries is written as a delta on its
+/// last saved file, see [`ShardDelta`]. Shards no manifest references any
+/// more are removed.
+pub(crate) fn save(
+    index: &DeepIndex,
+    dir: &Path,
+    branch: Option<&str>,
+    level: i32,
+) -> Result<SaveReport, TopoError> {
+    let _lock = IndexLock::acquire(dir, LOCK_TIMEOUT)?;
+    let staged = stage(index, dir, branch, level)?;
+    // Manifests are staged last, so they never list shards not yet written
+    for (path, bytes) in &staged.writes {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| TopoError::io(parent, e))?;
+        }
+        write_atomic(path, bytes)?;
+    }
+    for path in &staged.removals {
+        let _ = fs::remove_file(path);
+    }
+    Ok(staged.report)
+}
+
+/// Write `bytes` beside `path` and rename it into place, so readers and a
+/// crash mid-write never leave a partial file at `path`.
+pub(crate) fn write_atomic(path: &Path, bytes: &[u8]) -> Result<(), TopoError> {
+    let mut staged = path.as_os_str().to_owned();
+    staged.push(STAGED_SUFFIX);
+    let staged = PathBuf::from(staged);
+    fs::write(&staged, bytes).map_err(|e| TopoError::io(&staged, e))?;
+    fs::rename(&staged, path).map_err(|e| TopoError::io(path, e))
+}
+
+/// The files [`save`] would create, overwrite, and delete, without
+/// writing anything.
+pub(crate) fn plan(
+    index: &DeepIndex,
+    dir: &Path,
+    branch: Option<&str>,
+    level: i32,
+) -> Result<SavePlan, TopoError> {
+    let staged = stage(index, dir, branch, level)?;
+    let mut plan = SavePlan::default();
+    for (path, bytes) in staged.writes {
+        let file = PlannedFile {
+            bytes: bytes.len() as u64,
+            path,
+        };
+        if file.path.exists() {
+            plan.overwritten.push(file);
+        } else {
+            plan.created.push(file);
+        }
+    }
+    plan.deleted = staged
+        .removals
+        .into_iter()
+        .filter_map(PlannedFile::existing)
+        .collect();
+    Ok(plan)
+}
+
+/// What a save changes in a sharded index directory.
+struct Staged {
+    /// Files to write with their encoded contents, in order.
+    writes: Vec<(PathBuf, Vec<u8>)>,
+    /// Files to remove once everything is written.
+    removals: Vec<PathBuf>,
+    report: SaveReport,
+}
+
+/// Work out what saving `index` into `dir` changes, reading but never
+/// writing.
+fn stage(
+    index: &DeepIndex,
+    dir: &Path,
+    branch: Option<&str>,
+    level: i32,
+) -> Result<Staged, TopoError> {
+    let mut grouped: BTreeMap<&str, Vec<(&String, &FileEntry)>> = BTreeMap::new();
+    for (path, entry) in &index.files {
+        grouped
+            .entry(shard_key(path))
+            .or_default()
+            .push((path, entry));
+    }
+
+    // Deltas build on the shards of the previous save at the same level
+    let previous = previous_manifest(dir, branch).filter(|manifest| manifest.level == level);
+    let previous: HashMap<&str, &ShardRef> = previous
+        .iter()
+        .flat_map(|manifest| &manifest.shards)
+        .map(|shard| (shard.key.as_str(), shard))
+        .collect();
+
+    let mut writes = Vec::new();
+    let mut shards = Vec::with_capacity(grouped.len());
+    let mut deltas = 0;
+    for (key, mut entries) in grouped {
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        let digest = shard_digest(&entries, level);
+        let file = shard_file(&digest);
+        let path = dir.join(&file);
+        let prior = previous.get(key).copied();
+        if let Some(prior) = prior.filter(|prior| prior.digest == digest) {
+            shards.push(prior.clone());
+            continue;
+        }
+        if let Some(prior) = prior.filter(|_| !path.exists())
+            && let Some((delta, bytes)) = stage_delta(dir, prior, &entries, &digest, level)?
+        {
+            if let Some(bytes) = bytes {
+                writes.push((dir.join(&delta), bytes));
+                deltas += 1;
+            }
+            shards.push(ShardRef {
+                key: key.to_string(),
+                file: prior.file.clone(),
+                digest,
+                delta: Some(delta),
+            });
+            continue;
+        }
+        if !path.exists() {
+            writes.push((path.clone(), encode_shard(&path, &entries, level)?));
+        }
+        shards.push(ShardRef {
+            key: key.to_string(),
+            file,
+            digest,
+            delta: None,
+        });
+    }
+    let written = writes.len();
+
+    let embeddings = match &index.embeddings {
+        Some(segment) => {
+            let (file, bytes) = stage_segment(segment, dir, level)?;
+            writes.extend(bytes.map(|bytes| (dir.join(&file), bytes)));
+            Some(file)
+        }
+        None => None,
+    };
+
+    let manifest = Manifest {
+        index: DeepIndex {
+            version: index.version,
+            files: HashMap::new(),
+            avg_doc_length: index.avg_doc_length,
+            total_docs: index.total_docs,
+            doc_frequencies: index.doc_frequencies.clone(),
+            pagerank_scores: index.pagerank_scores.clone(),
+            tombstones: index.tombstones.clone(),
+            dependencies: index.dependencies.clone(),
+            trigrams: index.trigrams.clone(),
+            chunk_postings: index.chunk_postings,
+            stemmed: index.stemmed,
+            embeddings: None,
+            git_activity: index.git_activity.clone(),
+            meta: index.meta.clone(),
+            duplicate_chunks: index.duplicate_chunks.clone(),
+        },
+        branch: branch.map(str::to_string),
+        shards,
+        embeddings,
+        level,
+    };
+    let manifest_path = dir.join(MANIFEST_FILE);
+    let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&manifest)
+        .map_err(|e| store::serialize_error(&manifest_path, e))?;
+    let encoded = store::encode(&manifest_path, &bytes, level)?;
+    let branch_path = branch.map(|branch| dir.join(BRANCH_DIR).join(branch_file(branch)));
+    if let Some(path) = &branch_path {
+        writes.push((path.clone(), encoded.clone()));
+    }
+    writes.push((manifest_path, encoded));
+
+    // The branch's own manifest is about to be the newest one
+    let keep = MAX_BRANCHES - usize::from(branch.is_some());
+    let (others, mut removals) = branch_manifests(dir, branch_path.as_deref(), keep);
+    let mut live: HashSet<String> = HashSet::new();
+    for shard in &manifest.shards {
+        live.insert(shard.file.clone());
+        live.extend(shard.delta.clone());
+    }
+    live.extend(manifest.embeddings.clone());
+    for other in others {
+        for shard in other.shards {
+            live.insert(shard.file);
+            live.extend(shard.delta);
+        }
+        live.extend(other.embeddings);
+    }
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let stale = name != MANIFEST_FILE && name.ends_with(".bin") && !live.contains(&name);
+            // Left behind by a writer that crashed before renaming it
+            if stale || name.ends_with(STAGED_SUFFIX) {
+                removals.push(entry.path());
+            }
+        }
+    }
+    if let Ok(segments) = fs::read_dir(dir.join(EMBEDDING_DIR)) {
+        for entry in segments.flatten() {
+            let name = format!("{EMBEDDING_DIR}/{}", entry.file_name().to_string_lossy());
+            if !live.contains(&name) {
+                removals.push(entry.path());
+            }
+        }
+    }
+
+    Ok(Staged {
+        writes,
+        removals,
+        report: SaveReport {
+            shards: manifest.shards.len(),
+            written,
+            deltas,
+        },
+    })
+}
+
+/// The file in `dir` holding `segment`, relative to `dir`, and its encoded
+/// contents unless an identical segment is already there.
+///
+/// Files are named by model, dimension, and contents, so segments from
+/// different models never mix and branches share identical ones.
+fn stage_segment(
+    segment: &EmbeddingSegment,
+    dir: &Path,
+    level: i32,
+) -> Result<(String, Option<Vec<u8>>), TopoError> {
+    let segments = dir.join(EMBEDDING_DIR);
+    let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(segment)
+        .map_err(|e| store::serialize_error(&segments, e))?;
+    let mut hasher = Sha256::new();
+    hasher.update(INDEX_VERSION.to_le_bytes());
+    hasher.update(level.to_le_bytes());
+    hasher.update(&bytes);
+    let digest: [u8; 32] = hasher.finalize().into();
+
+    let model: String = segment
+        .model
+        .chars()
+        .take(40)
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let file = format!(
+        "{EMBEDDING_DIR}/{model}-{}-{}.bin",
+        segment.dimension,
+        hex(&digest[..8])
+    );
+    let path = dir.join(&file);
+    if path.exists() {
+        return Ok((file, None));
+    }
+    let encoded = store::encode(&path, &bytes, level)?;
+    Ok((file, Some(encoded)))
+}
+
+/// The manifest last saved on `branch` in `dir`, or else the last one saved.
+fn previous_manifest(dir: &Path, branch: Option<&str>) -> Option<Manifest> {
+    branch
+        .map(|branch| dir.join(BRANCH_DIR).join(branch_file(branch)))
+        .into_iter()
+        .chain([dir.join(MANIFEST_FILE)])
+        .find_map(|path| decode_manifest(&fs::read(path).ok()?))
+}
+
+/// File name of a staged delta and its encoded contents, if not yet written.
+type StagedDelta = (String, Option<Vec<u8>>);
+
+/// A delta turning the entries of `prior`'s base file into `entries`,
+/// relative to `dir`, and its encoded contents unless an identical delta is
+/// already there. None if the base file is unreadable or the delta would
+/// be too large to be worth it, so the shard is compacted.
+fn stage_delta(
+    dir: &Path,
+    prior: &ShardRef,
+    entries: &[(&String, &FileEntry)],
+    digest: &[u8; 32],
+    level: i32,
+) -> Result<Option<StagedDelta>, TopoError> {
+    let Some(base) = read_shard(&dir.join(&prior.file)) else {
+        return Ok(None);
+    };
+    let upserts: HashMap<String, FileEntry> = entries
+        .iter()
+        .filter(|(path, entry)| base.get(path.as_str()) != Some(*entry))
+        .map(|(path, entry)| ((*path).clone(), (*entry).clone()))
+        .collect();
+    let current: HashSet<&str> = entries.iter().map(|(path, _)| path.as_str()).collect();
+    let mut removed: Vec<String> = base
+        .keys()
+        .filter(|path| !current.contains(path.as_str()))
+        .cloned()
+        .collect();
+    if (upserts.len() + removed.len()) * MAX_DELTA_SHARE > base.len() {
+        return Ok(None);
+    }
+    removed.sort_unstable();
+
+    let mut hasher = Sha256::new();
+    hasher.update(digest);
+    hasher.update(prior.file.as_bytes());
+    let file = format!("{}-delta.bin", hex(&hasher.finalize()[..16]));
+    let path = dir.join(&file);
+    if path.exists() {
+        return Ok(Some((file, None)));
+    }
+    let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&ShardDelta { upserts, removed })
+        .map_err(|e| store::serialize_error(&path, e))?;
+    let encoded = store::encode(&path, &bytes, level)?;
+    Ok(Some((file, Some(encoded))))
+}
+
+/// Branch manifests in `dir` other than `skip`, the `keep` most recently
+/// saved, and the files of the rest and of unreadable ones.
+fn branch_manifests(dir: &Path, skip: Option<&Path>, keep: usize) -> (Vec<Manifest>, Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir.join(BRANCH_DIR)) else {
+        return (Vec::new(), Vec::new());
+    };
+    let mut files: Vec<(SystemTime, PathBuf)> = entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            Some((modified, entry.path()))
+        })
+        .filter(|(_, path)| Some(path.as_path()) != skip)
+        .collect();
+    files.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+
+    let mut manifests = Vec::new();
+    let mut stale = Vec::new();
+    for (i, (_, path)) in files.into_iter().enumerate() {
+        let manifest = (i < keep)
+            .then(|| fs::read(&path).ok())
+            .flatten()
+            .and_then(|bytes| decode_manifest(&bytes));
+        match manifest {
+            Some(manifest) => manifests.push(manifest),
+            None => stale.push(path),
+        }
+    }
+    (manifests, stale)
+}
+
+/// Assemble a complete index from a sharded layout, reading manifest and
+/// shard files by name with `read`. Returns None if anything is missing,
+/// unreadable, or outdated.
+pub(crate) fn assemble(read: impl Fn(&str) -> Option<Vec<u8>>) -> Option<DeepIndex> {
+    let manifest = decode_manifest(&read(MANIFEST_FILE)?)?;
+    let mut index = manifest.index;
+    for shard in &manifest.shards {
+        let mut entries = decode_shard(&read(&shard.file)?)?;
+        if let Some(delta) = &shard.delta {
+            decode_delta(&read(delta)?)?.apply(&mut entries);
+        }
+        index.files.extend(entries);
+    }
+    index.embeddings = manifest
+        .embeddings
+        .and_then(|file| decode_segment(&read(&file)?));
+    Some(index)
+}
+
+/// The shard holding `path`: its top-level directory, or empty for files
+/// at the repository root.
+pub(crate) fn shard_key(path: &str) -> &str {
+    path.split_once('/').map_or("", |(dir, _)| dir)
+}
+
+/// Shard file name, derived from the shard's digest so identical shards
+/// are stored once.
+fn shard_file(digest: &[u8; 32]) -> String {
+    format!("{}.bin", hex(&digest[..16]))
+}
+
+/// Branch manifest file name, hashed so any branch name is a safe file name.
+fn branch_file(branch: &str) -> String {
+    format!("{}.bin", hex(&Sha256::digest(branch.as_bytes())[..8]))
+}
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Fingerprint of a shard's sorted entries.
+///
+/// Everything in an entry is hashed: the same content indexed with other
+/// settings, like chunk postings or another chunker, gives other entries.
+/// The index version and compression level are mixed in so changing either
+/// rewrites every shard.
+fn shard_digest(entries: &[(&String, &FileEntry)], level: i32) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(INDEX_VERSION.to_le_bytes());
+    hasher.update(level.to_le_bytes());
+    for (path, entry) in entries {
+        hasher.update(path.as_bytes());
+        hasher.update([0]);
+        hasher.update(entry.sha256);
+        hasher.update(entry.doc_length.to_le_bytes());
+        for (key, value) in &entry.metadata {
+            hasher.update(key.as_bytes());
+            hasher.update([0]);
+            hasher.update(value.as_bytes());
+            hasher.update([0]);
+        }
+        hasher.update([0xfe]);
+        for chunk in &entry.chunks {
+            hasher.update(chunk.kind.as_str().as_bytes());
+            hasher.update([0]);
+            hasher.update(chunk.name.as_bytes());
+            hasher.update([0]);
+            hasher.update(chunk.start_line.to_le_bytes());
+            hasher.update(chunk.end_line.to_le_bytes());
+            hasher.update(chunk.content.as_bytes());
+            hasher.update([0]);
+            for field in [&chunk.target, &chunk.doc, &chunk.parent, &chunk.signature] {
+                hash_optional(&mut hasher, field.as_deref());
+            }
+            hasher.update(chunk.tokens.to_le_bytes());
+            hasher.update([u8::from(chunk.exported)]);
+        }
+        hash_terms(&mut hasher, &entry.term_frequencies);
+        for chunk in entry.chunk_terms.iter().flatten() {
+            hasher.update(chunk.length.to_le_bytes());
+            hasher.update(chunk.start_line.to_le_bytes());
+            hasher.update(chunk.end_line.to_le_bytes());
+            hasher.update(chunk.hash.to_le_bytes());
+            hash_terms(&mut hasher, &chunk.term_frequencies);
+        }
+        for export in &entry.exports {
+            hasher.update(export.as_bytes());
+            hasher.update([0]);
+        }
+        hasher.update([0xfe]);
+        for hash in &entry.chunk_hashes {
+            hasher.update(hash.to_le_bytes());
+        }
+        hasher.update([0xff]);
+    }
+    hasher.finalize().into()
+}
+
+/// Hash a field that may be absent, telling absent apart from empty.
+fn hash_optional(hasher: &mut Sha256, value: Option<&str>) {
+    match value {
+        Some(value) => {
+            hasher.update([1]);
+            hasher.update(value.as_bytes());
+            hasher.update([0]);
+        }
+        None => hasher.update([0]),
+    }
+}
+
+/// Hash term frequencies in term order, so map order doesn't matter.
+fn hash_terms(hasher: &mut Sha256, terms: &HashMap<String, TermFreqs>) {
+    let mut sorted: Vec<(&String, &TermFreqs)> = terms.iter().collect();
+    sorted.sort_unstable_by_key(|(term, _)| *term);
+    for (term, freqs) in sorted {
+        hasher.update(term.as_bytes());
+        hasher.update([0]);
+        for count in [freqs.filename, freqs.symbols, freqs.body, freqs.docs] {
+            hasher.update(count.to_le_bytes());
+        }
+    }
+    hasher.update([0xfd]);
+}
+
+/// Encode sorted `entries` as the shard file at `path`, see [`EntrySlot`].
+fn encode_shard(
+    path: &Path,
+    entries: &[(&String, &FileEntry)],
+    level: i32,
+) -> Result<Vec<u8>, TopoError> {
+    let mut slots = Vec::with_capacity(entries.len());
+    let mut body = Vec::new();
+    for (file, entry) in entries {
+        let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(*entry)
+            .map_err(|e| store::serialize_error(path, e))?;
+        let encoded = store::encode(path, &bytes, level)?;
+        slots.push(EntrySlot {
+            path: (*file).clone(),
+            offset: body.len() as u64,
+            len: encoded.len() as u64,
+        });
+        body.extend_from_slice(&encoded);
+    }
+    let table = rkyv::to_bytes::<rkyv::rancor::Error>(&slots)
+        .map_err(|e| store::serialize_error(path, e))?;
+    let table = store::encode(path, &table, level)?;
+    let mut bytes = Vec::with_capacity(8 + table.len() + body.len());
+    bytes.extend_from_slice(&(table.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(&table);
+    bytes.extend_from_slice(&body);
+    Ok(bytes)
+}
+
+/// The slots of the shard file starting with `bytes`, and the offset of
+/// its first entry. `bytes` must hold at least the table.
+fn decode_slots(bytes: &[u8]) -> Option<(Vec<EntrySlot>, u64)> {
+    let len = u64::from_le_bytes(bytes.get(..8)?.try_into().ok()?);
+    let end = 8usize.checked_add(usize::try_from(len).ok()?)?;
+    let raw = store::decompress(bytes.get(8..end)?)?;
+    let slots = rkyv::from_bytes::<Vec<EntrySlot>, rkyv::rancor::Error>(&raw).ok()?;
+    Some((slots, end as u64))
+}
+
+fn decode_entry(bytes: &[u8]) -> Option<FileEntry> {
+    let raw = store::decompress(bytes)?;
+    rkyv::from_bytes::<FileEntry, rkyv::rancor::Error>(&raw).ok()
+}
+
+/// The table of shard `file` in `dir`, with `delta` applied if set,
+/// reading no entries but the delta's.
+fn read_table(dir: &Path, file: &str, delta: Option<&str>) -> Option<EntryTable> {
+    let mut reader = fs::File::open(dir.join(file)).ok()?;
+    let mut len = [0; 8];
+    reader.read_exact(&mut len).ok()?;
+    let mut bytes = len.to_vec();
+    reader
+        .take(u64::from_le_bytes(len))
+        .read_to_end(&mut bytes)
+        .ok()?;
+    let (slots, start) = decode_slots(&bytes)?;
+    let mut slots: HashMap<String, Slot> = slots
+        .into_iter()
+        .map(|slot| {
+            let entry = OnceLock::new();
+            let (offset, len) = (slot.offset, slot.len);
+            (slot.path, Slot { offset, len, entry })
+        })
+        .collect();
+    if let Some(delta) = delta {
+        let delta = decode_delta(&fs::read(dir.join(delta)).ok()?)?;
+        for path in &delta.removed {
+            slots.remove(path);
+        }
+        for (path, entry) in delta.upserts {
+            let slot = Slot {
+                offset: 0,
+                len: 0,
+                entry: OnceLock::from(Some(entry)),
+            };
+            slots.insert(path, slot);
+        }
+    }
+    Some(EntryTable { start, slots })
+}
+
+/// The entry in `slot` of the shard file at `path`.
+fn read_entry(path: &Path, start: u64, slot: &Slot) -> Option<FileEntry> {
+    let mut reader = fs::File::open(path).ok()?;
+    reader.seek(SeekFrom::Start(start + slot.offset)).ok()?;
+    let mut bytes = vec![0; usize::try_from(slot.len).ok()?];
+    reader.read_exact(&mut bytes).ok()?;
+    decode_entry(&bytes)
+}
+
+fn read_shard(path: &Path) -> Option<HashMap<String, FileEntry>> {
+    decode_shard(&fs::read(path).ok()?)
+}
+
+/// The entries of shard `file` in `dir`, with `delta` applied if set.
+fn read_entries(dir: &Path, file: &str, delta: Option<&str>) -> Option<HashMap<String, FileEntry>> {
+    let mut entries = read_shard(&dir.join(file))?;
+    if let Some(delta) = delta {
+        decode_delta(&fs::read(dir.join(delta)).ok()?)?.apply(&mut entries);
+    }
+    Some(entries)
+}
+
+fn decode_delta(bytes: &[u8]) -> Option<ShardDelta> {
+    let raw = store::decompress(bytes)?;
+    rkyv::from_bytes::<ShardDelta, rkyv::rancor::Error>(&raw).ok()
+}
+
+fn decode_manifest(bytes: &[u8]) -> Option<Manifest> {
+    let raw = store::decompress(bytes)?;
+    rkyv::from_bytes::<Manifest, rkyv::rancor::Error>(&raw)
+        .ok()
+        .filter(|manifest| manifest.index.version >= INDEX_VERSION)
+}
+
+fn decode_shard(bytes: &[u8]) -> Option<HashMap<String, FileEntry>> {
+    let (slots, start) = decode_slots(bytes)?;
+    let body = bytes.get(usize::try_from(start).ok()?..)?;
+    slots
+        .into_iter()
+        .map(|slot| {
+            let offset = usize::try_from(slot.offset).ok()?;
+            let end = offset.checked_add(usize::try_from(slot.len).ok()?)?;
+            Some((slot.path, decode_entry(body.get(offset..end)?)?))
+        })
+        .collect()
+}
+
+fn read_segment(path: &Path) -> Option<EmbeddingSegment> {
+    decode_segment(&fs::read(path).ok()?)
+}
+
+fn decode_segment(bytes: &[u8]) -> Option<EmbeddingSegment> {
+    let raw = store::decompress(bytes)?;
+    rkyv::from_bytes::<EmbeddingSegment, rkyv::rancor::Error>(&raw).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IndexBuilder;
+    use crate::test_support::file_info;
+    use topo_core::FileInfo;
+
+    fn build(root: &Path, files: &[(&str, &str)]) -> DeepIndex {
+        let infos: Vec<FileInfo> = files
+            .iter()
+            .map(|(path, content)| {
+                let full = root.join(path);
+                fs::create_dir_all(full.parent().unwrap()).unwrap();
+                fs::write(&full, content).unwrap();
+                file_info(path, content)
+            })
+            .collect();
+        IndexBuilder::new(root).build(&infos, None).unwrap().0
+    }
+
+    /// File of the shard holding `key` in the last saved manifest.
+    fn file_of(dir: &Path, key: &str) -> PathBuf {
+        let sharded = ShardedIndex::open(dir, None).unwrap().unwrap();
+        dir.join(&sharded.shards[key].file)
+    }
+
+    fn shard_files(dir: &Path) -> usize {
+        fs::read_dir(dir)
+            .unwrap()
+            .filter(|e| {
+                let name = e.as_ref().unwrap().file_name();
+                name != MANIFEST_FILE && name.to_string_lossy().ends_with(".bin")
+            })
+            .count()
+    }
+
+    #[test]
+    fn shard_keys_are_top_level_directories() {
+        assert_eq!(shard_key("src/auth/token.rs"), "src");
+        assert_eq!(shard_key("docs/guide.md"), "docs");
+        assert_eq!(shard_key("README.md"), "");
+        assert_ne!(branch_file("main"), branch_file("feature/auth"));
+    }
+
+    #[test]
+    fn concurrent_saves_leave_a_loadable_index() {
+        let repo = tempfile::tempdir().unwrap();
+        let dir = repo.path().join("shards");
+        let first = build(repo.path(), &[("src/auth.rs", "fn authenticate() {}\n")]);
+        let second = build(
+            repo.path(),
+            &[
+                ("src/auth.rs", "fn authorize() {}\n"),
+                ("docs/guide.md", "# Guide\n"),
+            ],
+        );
+
+        std::thread::scope(|scope| {
+            for index in [&first, &second, &first, &second] {
+                let dir = &dir;
+                scope.spawn(move || save(index, dir, Some("main"), 3).unwrap());
+            }
+        });
+
+        let loaded = ShardedIndex::open(&dir, None).unwrap().unwrap();
+        let shards = loaded.shards.len();
+        let index = loaded.into_index().unwrap();
+        assert!(index.total_docs == 1 || index.total_docs == 2);
+        assert_eq!(shards, index.total_docs as usize);
+        assert_eq!(shard_files(&dir), shards);
+
+        // Files left half-written by a crashed writer are cleaned up
+        fs::write(dir.join(format!("abc.bin{STAGED_SUFFIX}")), "partial").unwrap();
+        save(&first, &dir, None, 3).unwrap();
+        assert!(!dir.join(format!("abc.bin{STAGED_SUFFIX}")).exists());
+    }
+
+    #[test]
+    fn deltas_keep_entries_rebuilt_from_unchanged_content() {
+        let repo = tempfile::tempdir().unwrap();
+        let dir = repo.path().join("shards");
+        let names: Vec<String> = (0..8).map(|i| format!("src/m{i}.rs")).collect();
+        let files: Vec<(&str, &str)> = names.iter().map(|n| (n.as_str(), "fn f() {}\n")).collect();
+        let mut index = build(repo.path(), &files);
+        save(&index, &dir, None, 3).unwrap();
+
+        // Same content, different chunks, as after `--force` with another chunker
+        index.files.get_mut("src/m0.rs").unwrap().chunks[0].end_line = 5;
+        let report = save(&index, &dir, None, 3).unwrap();
+        assert_eq!(report.deltas, 1);
+        let loaded = ShardedIndex::open(&dir, None).unwrap().unwrap();
+        assert_eq!(loaded.entry("src/m0.rs").unwrap().chunks[0].end_line, 5);
+    }
+
+    #[test]
+    fn small_changes_are_saved_as_deltas_then_compacted() {
+        let repo = tempfile::tempdir().unwrap();
+        let dir = repo.path().join("shards");
+        let names: Vec<String> = (0..8).map(|i| format!("src/m{i}.rs")).collect();
+        let mut files: Vec<(&str, String)> = names
+            .iter()
+            .map(|name| (name.as_str(), format!("fn f() {{ {} }}\n", name.len())))
+            .collect();
+        let build_files = |files: &[(&str, String)]| {
+            let files: Vec<(&str, &str)> = files.iter().map(|(p, c)| (*p, c.as_str())).collect();
+            build(repo.path(), &files)
+        };
+        let delta_files = || {
+            fs::read_dir(&dir)
+                .unwrap()
+                .filter(|e| {
+                    let name = e.as_ref().unwrap().file_name();
+                    name.to_string_lossy().ends_with("-delta.bin")
+                })
+                .count()
+        };
+
+        save(&build_files(&files), &dir, None, 3).unwrap();
+        let base = file_of(&dir, "src");
+
+        // One edit is saved as a delta on the untouched shard file
+        files[0].1 = "fn edited() {}\n".to_string();
+        let index = build_files(&files);
+        let report = save(&index, &dir, None, 3).unwrap();
+        assert_eq!((report.written, report.deltas), (1, 1));
+        assert_eq!(file_of(&dir, "src"), base);
+        assert_eq!(delta_files(), 1);
+        let sharded = ShardedIndex::open(&dir, None).unwrap().unwrap();
+        assert_eq!(
+            sharded.entry("src/m0.rs").unwrap().sha256,
+            index.files["src/m0.rs"].sha256
+        );
+        let loaded = sharded.into_index().unwrap();
+        assert_eq!(loaded.files.len(), 8);
+        let read = |name: &str| fs::read(dir.join(name)).ok();
+        assert!(assemble(read).unwrap().files["src/m0.rs"].chunks[0].name == "edited");
+
+        // Deltas accumulate against the same base and replace each other
+        files.pop();
+        let index = build_files(&files);
+        assert_eq!(save(&index, &dir, None, 3).unwrap().deltas, 1);
+        assert_eq!(file_of(&dir, "src"), base);
+        assert_eq!(delta_files(), 1);
+        let loaded = ShardedIndex::open(&dir, None)
+            .unwrap()
+            .unwrap()
+            .into_index()
+            .unwrap();
+        assert_eq!(loaded.files.len(), 7);
+        assert!(!loaded.files.contains_key("src/m7.rs"));
+
+        // Saving again without changes writes nothing
+        assert_eq!(save(&index, &dir, None, 3).unwrap().written, 0);
+
+        // A delta grown past a quarter of the shard is compacted
+        files[1].1 = "fn one() {}\n".to_string();
+        files[2].1 = "fn two() {}\n".to_string();
+        let report = save(&build_files(&files), &dir, None, 3).unwrap();
+        assert_eq!((report.written, report.deltas), (1, 0));
+        assert_ne!(file_of(&dir, "src"), base);
+        assert!(!base.exists());
+        assert_eq!(delta_files(), 0);
+        let loaded = ShardedIndex::open(&dir, None)
+            .unwrap()
+            .unwrap()
+            .into_index()
+            .unwrap();
+        assert_eq!(loaded.files["src/m2.rs"].chunks[0].name, "two");
+    }
+
+    #[test]
+    fn saves_only_dirty_shards() {
+        let repo = tempfile::tempdir().unwrap();
+        let dir = repo.path().join("shards");
+        let mut files = vec![
+            ("src/auth.rs", "fn authenticate() {}\n"),
+            ("docs/guide.md", "# Guide\n"),
+            ("README.md", "# Demo\n"),
+        ];
+        let index = build(repo.path(), &files);
+        assert_eq!(
+            save(&index, &dir, None, 3).unwrap(),
+            SaveReport {
+                shards: 3,
+                written: 3,
+                deltas: 0
+            }
+        );
+        assert_eq!(save(&index, &dir, None, 3).unwrap().written, 0);
+
+        files[0].1 = "fn authorize() {}\n";
+        let changed = build(repo.path(), &files);
+        assert_eq!(save(&changed, &dir, None, 3).unwrap().written, 1);
+        assert_eq!(shard_files(&dir), 3);
+        // A new compression level rewrites everything
+        assert_eq!(save(&changed, &dir, None, 5).unwrap().written, 3);
+
+        // Emptied shards are removed
+        let docs_only = build(repo.path(), &files[1..2]);
+        assert_eq!(save(&docs_only, &dir, None, 5).unwrap().shards, 1);
+        assert_eq!(shard_files(&dir), 1);
+        assert!(file_of(&dir, "docs").exists());
+    }
+
+    #[test]
+    fn chunk_targets_are_part_of_the_shard_digest() {
+        let repo = tempfile::tempdir().unwrap();
+        let dir = repo.path().join("shards");
+        let mut index = build(repo.path(), &[("src/app.rs", "fn run() {}\n")]);
+        save(&index, &dir, None, 3).unwrap();
+
+        // Resolving an import changes no content, only the chunk's target
+        let entry = index.files.get_mut("src/app.rs").unwrap();
+        entry.chunks[0].target = Some("lib/util.rs".to_string());
+        assert_eq!(save(&index, &dir, None, 3).unwrap().written, 1);
+
+        let loaded = ShardedIndex::open(&dir, None).unwrap().unwrap();
+        let entry = loaded.entry("src/app.rs").unwrap();
+        assert_eq!(entry.chunks[0].target.as_deref(), Some("lib/util.rs"));
+    }
+
+    #[test]
+    fn plan_lists_changes_without_writing() {
+        let repo = tempfile::tempdir().unwrap();
+        let dir = repo.path().join("shards");
+        let mut files = vec![
+            ("src/auth.rs", "fn authenticate() {}\n"),
+            ("docs/guide.md", "# Guide\n"),
+        ];
+        let index = build(repo.path(), &files);
+        let first = plan(&index, &dir, Some("main"), 3).unwrap();
+        assert!(!dir.exists());
+        // Two shards, the manifest, and the branch manifest
+        assert_eq!(first.created.len(), 4);
+        assert!(first.overwritten.is_empty() && first.deleted.is_empty());
+        save(&index, &dir, Some("main"), 3).unwrap();
+        for file in &first.created {
+            assert_eq!(fs::metadata(&file.path).unwrap().len(), file.bytes);
+        }
+
+        files[0].1 = "fn authorize() {}\n";
+        let changed = build(repo.path(), &files);
+        let old_shard = file_of(&dir, "src");
+        let second = plan(&changed, &dir, Some("main"), 3).unwrap();
+        assert_eq!(second.created.len(), 1);
+        assert_eq!(second.overwritten.len(), 2);
+        assert_eq!(second.deleted.len(), 1);
+        assert_eq!(second.deleted[0].path, old_shard);
+        assert!(old_shard.exists());
+    }
+
+    #[test]
+    fn entries_load_one_shard_at_a_time() {
+        let repo = tempfile::tempdir().unwrap();
+        let dir = repo.path().join("shards");
+        let index = build(
+            repo.path(),
+            &[
+                ("src/auth.rs", "fn authenticate() {}\n"),
+                ("src/token.rs", "fn verify() {}\n"),
+                ("docs/guide.md", "# Guide\n"),
+            ],
+        );
+        save(&index, &dir, None, 3).unwrap();
+
+        let sharded = ShardedIndex::open(&dir, None).unwrap().unwrap();
+        assert_eq!(sharded.shard_count(), 2);
+        assert_eq!(sharded.corpus().total_docs, 3);
+        assert!(sharded.corpus().files.is_empty());
+        let loaded = || {
+            sharded
+                .shards
+                .values()
+                .filter(|s| s.table.get().is_some())
+                .count()
+        };
+        assert_eq!(loaded(), 0);
+
+        assert!(sharded.entry("src/auth.rs").is_some());
+        assert!(sharded.entry("src/missing.rs").is_none());
+        assert_eq!(loaded(), 1);
+        // Only the entry looked up is read, not the rest of its shard
+        assert_eq!(sharded.loaded_entries(), 1);
+        assert_eq!(sharded.hashes().len(), 3);
+        assert_eq!(sharded.loaded_entries(), 3);
+
+        let whole = sharded.into_index().unwrap();
+        assert_eq!(whole.files.len(), 3);
+    }
+
+    #[test]
+    fn missing_shard_forces_rebuild() {
+        let repo = tempfile::tempdir().unwrap();
+        let dir = repo.path().join("shards");
+        let index = build(
+            repo.path(),
+            &[("src/a.rs", "fn a() {}\n"), ("lib/b.rs", "fn b() {}\n")],
+        );
+        save(&index, &dir, None, 0).unwrap();
+        fs::remove_file(file_of(&dir, "lib")).unwrap();
+
+        let read = |name: &str| fs::read(dir.join(name)).ok();
+        assert!(assemble(read).is_none());
+        let sharded = ShardedIndex::open(&dir, None).unwrap().unwrap();
+        assert!(sharded.entry("lib/b.rs").is_none());
+        assert!(sharded.entry("src/a.rs").is_some());
+        assert!(sharded.into_index().is_none());
+    }
+
+    #[test]
+    fn branches_share_unchanged_shards() {
+        let repo = tempfile::tempdir().unwrap();
+        let dir = repo.path().join("shards");
+        let mut files = vec![
+            ("src/auth.rs", "fn authenticate() {}\n"),
+            ("docs/guide.md", "# Guide\n"),
+        ];
+        let main = build(repo.path(), &files);
+        save(&main, &dir, Some("main"), 3).unwrap();
+
+        files[0].1 = "fn authorize() {}\n";
+        let feature = build(repo.path(), &files);
+        // Only the changed directory is new on the feature branch
+        assert_eq!(
+            save(&feature, &dir, Some("feature/auth"), 3)
+                .unwrap()
+                .written,
+            1
+        );
+        assert_eq!(shard_files(&dir), 3);
+
+        // Switching back finds main's index without writing anything
+        let sharded = ShardedIndex::open(&dir, Some("main")).unwrap().unwrap();
+        assert_eq!(sharded.branch(), Some("main"));
+        assert_eq!(
+            sharded.entry("src/auth.rs").unwrap().sha256,
+            main.files["src/auth.rs"].sha256
+        );
+        assert_eq!(save(&main, &dir, Some("main"), 3).unwrap().written, 0);
+
+        // The last save wins for unknown branches and plain opens
+        let latest = ShardedIndex::open(&dir, Some("other")).unwrap().unwrap();
+        assert_eq!(latest.branch(), Some("main"));
+    }
+
+    #[test]
+    fn embeddings_live_in_their_own_segment() {
+        use topo_core::FileEmbeddings;
+        let repo = tempfile::tempdir().unwrap();
+        let dir = repo.path().join("shards");
+        let mut index = build(repo.path(), &[("src/a.rs", "fn a() {}\n")]);
+        save(&index, &dir, None, 3).unwrap();
+        assert!(!dir.join(EMBEDDING_DIR).exists());
+
+        let mut segment = EmbeddingSegment::new("org/mini-lm", 2);
+        let embeddings = FileEmbeddings {
+            sha256: index.files["src/a.rs"].sha256,
+            file: vec![1.0, 0.0],
+            chunks: vec![vec![0.0, 1.0]],
+        };
+        segment.insert("src/a.rs", embeddings).unwrap();
+        index.embeddings = Some(segment.clone());
+        // Only the segment is new; shards are unchanged
+        assert_eq!(save(&index, &dir, None, 3).unwrap().written, 0);
+        let segments: Vec<String> = fs::read_dir(dir.join(EMBEDDING_DIR))
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(segments.len(), 1);
+        assert!(segments[0].starts_with("org_mini-lm-2-"), "{segments:?}");
+
+        let sharded = ShardedIndex::open(&dir, None).unwrap().unwrap();
+        assert!(sharded.corpus().embeddings.is_none());
+        assert_eq!(sharded.embeddings(), Some(&segment));
+        assert_eq!(sharded.into_index().unwrap().embeddings, Some(segment));
+        let read = |name: &str| fs::read(dir.join(name)).ok();
+        assert!(assemble(read).unwrap().embeddings.is_some());
+
+        // A corrupt segment only loses the vectors
+        let path = dir.join(EMBEDDING_DIR).join(&segments[0]);
+        fs::write(&path, "garbage").unwrap();
+        let sharded = ShardedIndex::open(&dir, None).unwrap().unwrap();
+        assert!(sharded.embeddings().is_none());
+        assert_eq!(sharded.into_index().unwrap().files.len(), 1);
+
+        // Dropping the vectors removes the segment
+        index.embeddings = None;
+        save(&index, &dir, None, 3).unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn old_branches_are_pruned() {
+        let repo = tempfile::tempdir().unwrap();
+        let dir = repo.path().join("shards");
+        let mut content = String::new();
+        for i in 0..=MAX_BRANCHES {
+            content.push_str("fn f() {}\n");
+            let index = build(repo.path(), &[("src/lib.rs", content.as_str())]);
+            save(&index, &dir, Some(&format!("branch-{i}")), 3).unwrap();
+            // Keep modification times apart on coarse-grained filesystems
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(
+            fs::read_dir(dir.join(BRANCH_DIR)).unwrap().count(),
+            MAX_BRANCHES
+        );
+        assert!(!dir.join(BRANCH_DIR).join(branch_file("branch-0")).exists());
+        assert_eq!(shard_files(&dir), MAX_BRANCHES);
+    }
+}
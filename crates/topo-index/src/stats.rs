@@ -0,0 +1,181 @@
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use topo_core::{DeepIndex, Language};
+
+/// Statistics of a deep index, as `topo inspect` reports them.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IndexStats {
+    pub version: u32,
+    pub files: u32,
+    pub chunks: usize,
+    /// Distinct terms across all files.
+    pub unique_terms: usize,
+    /// File-level postings: the sum over files of their distinct terms.
+    pub file_terms: usize,
+    /// Chunk-level postings, when the index has them.
+    pub chunk_terms: Option<usize>,
+    pub avg_doc_length: f64,
+    pub dependency_edges: usize,
+    /// Terms in the trigram index, when one was built.
+    pub trigram_terms: Option<usize>,
+    /// Files with enrichment metadata.
+    pub enriched_files: usize,
+    /// Files per language, most first.
+    pub languages: Vec<FileCount>,
+    /// Files per extension, most first; `(none)` for files without one.
+    pub extensions: Vec<FileCount>,
+    /// Bytes the index takes on disk, when measured.
+    pub size_on_disk: Option<u64>,
+}
+
+/// Number of indexed files with some property, like a language.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileCount {
+    pub name: String,
+    pub files: usize,
+}
+
+impl IndexStats {
+    /// Statistics of `index`. Languages come from the build metadata when
+    /// recorded, since configuration can override them, and from file
+    /// extensions otherwise.
+    pub fn compute(index: &DeepIndex) -> Self {
+        let mut chunks = 0;
+        let mut file_terms = 0;
+        let mut chunk_terms = 0;
+        let mut enriched_files = 0;
+        let mut extensions: BTreeMap<String, usize> = BTreeMap::new();
+        let mut languages: BTreeMap<String, usize> = BTreeMap::new();
+        for (path, entry) in &index.files {
+            chunks += entry.chunks.len();
+            file_terms += entry.term_frequencies.len();
+            for chunk in entry.chunk_terms.iter().flatten() {
+                chunk_terms += chunk.term_frequencies.len();
+            }
+            if !entry.metadata.is_empty() {
+                enriched_files += 1;
+            }
+            let path = Path::new(path);
+            let extension = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or("(none)");
+            *extensions.entry(extension.to_string()).or_default() += 1;
+            if index.meta.is_none() {
+                *languages
+                    .entry(Language::from_path(path).to_string())
+                    .or_default() += 1;
+            }
+        }
+        if let Some(meta) = &index.meta {
+            languages = meta
+                .languages
+                .iter()
+                .map(|(language, &count)| (language.clone(), count as usize))
+                .collect();
+        }
+
+        Self {
+            version: index.version,
+            files: index.total_docs,
+            chunks,
+            unique_terms: index.doc_frequencies.len(),
+            file_terms,
+            chunk_terms: index.chunk_postings.then_some(chunk_terms),
+            avg_doc_length: index.avg_doc_length,
+            dependency_edges: index.dependencies.values().map(Vec::len).sum(),
+            trigram_terms: index.trigrams.as_ref().map(|t| t.term_count()),
+            enriched_files,
+            languages: by_count(languages),
+            extensions: by_count(extensions),
+            size_on_disk: None,
+        }
+    }
+
+    /// Record the size of the index directory `dir`, counting every file
+    /// under it.
+    pub fn with_disk_size(mut self, dir: &Path) -> Self {
+        self.size_on_disk = Some(disk_size(dir));
+        self
+    }
+}
+
+/// Counts sorted most first, ties by name.
+fn by_count(counts: BTreeMap<String, usize>) -> Vec<FileCount> {
+    let mut counts: Vec<FileCount> = counts
+        .into_iter()
+        .map(|(name, files)| FileCount { name, files })
+        .collect();
+    // Stable, so ties stay in name order
+    counts.sort_by_key(|count| Reverse(count.files));
+    counts
+}
+
+fn disk_size(dir: &Path) -> u64 {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| match entry.file_type() {
+            Ok(kind) if kind.is_dir() => disk_size(&entry.path()),
+            Ok(kind) if kind.is_file() => entry.metadata().map_or(0, |m| m.len()),
+            _ => 0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IndexBuilder;
+    use crate::store;
+    use crate::test_support::file_info;
+    use topo_core::FileInfo;
+
+    #[test]
+    fn counts_files_chunks_terms_and_languages() {
+        let dir = tempfile::tempdir().unwrap();
+        let sources = [
+            ("src/auth.rs", "pub fn login() {}\npub fn logout() {}\n"),
+            ("src/token.rs", "pub struct Token;\n"),
+            ("scripts/deploy.py", "def deploy():\n    pass\n"),
+            ("Makefile", "all:\n\tcargo build\n"),
+        ];
+        let files: Vec<FileInfo> = sources
+            .iter()
+            .map(|(path, content)| {
+                let full = dir.path().join(path);
+                fs::create_dir_all(full.parent().unwrap()).unwrap();
+                fs::write(full, content).unwrap();
+                file_info(path, content)
+            })
+            .collect();
+        let mut index = IndexBuilder::new(dir.path()).build(&files, None).unwrap().0;
+        index.meta = None;
+
+        let stats = IndexStats::compute(&index);
+        assert_eq!(stats.files, 4);
+        assert!(stats.chunks >= 4);
+        assert_eq!(stats.unique_terms, index.doc_frequencies.len());
+        assert_eq!(stats.chunk_terms, None);
+        assert_eq!(stats.size_on_disk, None);
+        let rust = FileCount {
+            name: "rs".to_string(),
+            files: 2,
+        };
+        assert_eq!(stats.extensions[0], rust);
+        assert_eq!(stats.extensions.len(), 3);
+        assert_eq!(stats.extensions[1].name, "(none)");
+        assert_eq!(stats.languages[0].name, "rust");
+        assert_eq!(stats.languages[0].files, 2);
+
+        store::save(&index, dir.path()).unwrap();
+        let stats = stats.with_disk_size(&store::index_path(dir.path()));
+        assert!(stats.size_on_disk.unwrap() > 0);
+        let json = serde_json::to_value(&stats).unwrap();
+        assert_eq!(json["extensions"][0]["files"], 2);
+    }
+}
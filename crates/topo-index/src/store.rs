@@ -1,103 +1,372 @@
-use std::collections::HashMap;
+use crate::builder::{INDEX_VERSION, retarget_entry};
+use crate::crypt;
+use crate::duplicates::duplicate_groups;
+use crate::gc;
+use crate::lock::LOCK_EXTENSION;
+use crate::shard::{self, PlannedFile, SHARD_DIR, SavePlan, SaveReport, ShardedIndex};
+use crate::tombstone;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::Path;
-use topo_core::DeepIndex;
+use std::path::{Path, PathBuf};
+use topo_core::{DEFAULT_INDEX_COMPRESSION, DeepIndex, FileEntry, TopoError};
 
 /// Default index file location relative to repo root.
-const INDEX_DIR: &str = ".topo";
-const INDEX_FILE: &str = "index.bin";
-
-/// Save a DeepIndex to disk using rkyv binary serialization.
-pub fn save(index: &DeepIndex, repo_root: &Path) -> anyhow::Result<()> {
-    let dir = repo_root.join(INDEX_DIR);
-    fs::create_dir_all(&dir)?;
+pub(crate) const INDEX_DIR: &str = ".topo";
+/// Single-file index written by older versions, still loaded if no
+/// sharded index exists.
+pub(crate) const INDEX_FILE: &str = "index.bin";
+/// Directory under [`INDEX_DIR`] holding nested repositories' indexes.
+const NESTED_INDEX_DIR: &str = "repos";
+/// Socket under [`INDEX_DIR`] that `topo daemon` listens on.
+pub(crate) const DAEMON_SOCKET: &str = "daemon.sock";
+/// Directory under [`INDEX_DIR`] local embedding models are downloaded to.
+pub(crate) const MODEL_DIR: &str = "models";
+
+/// Prefix of a zstd-compressed index file. Files without it are raw rkyv.
+const COMPRESSED_MAGIC: &[u8; 8] = b"TOPOZST1";
+
+/// Save a DeepIndex to disk using rkyv binary serialization, compressed at
+/// the default zstd level.
+pub fn save(index: &DeepIndex, repo_root: &Path) -> Result<SaveReport, TopoError> {
+    save_with_level(index, repo_root, DEFAULT_INDEX_COMPRESSION)
+}
 
-    let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(index)
-        .map_err(|e| anyhow::anyhow!("rkyv serialize: {e}"))?;
-    fs::write(dir.join(INDEX_FILE), &bytes)?;
+/// Save a DeepIndex compressed at zstd `level`; 0 writes it uncompressed.
+pub fn save_with_level(
+    index: &DeepIndex,
+    repo_root: &Path,
+    level: i32,
+) -> Result<SaveReport, TopoError> {
+    save_repo(index, repo_root, "", None, level)
+}
 
-    // Remove legacy JSON index if present
+/// Save the index of the repository nested at `prefix` below `repo_root`
+/// (empty for the root itself), built on `branch` if known. See
+/// [`repo_index_path`].
+///
+/// The index is sharded by top-level directory, and only shards whose
+/// files changed since the last save are rewritten. Each branch's latest
+/// index is kept, so switching back to a branch reuses it.
+pub fn save_repo(
+    index: &DeepIndex,
+    repo_root: &Path,
+    prefix: &str,
+    branch: Option<&str>,
+    level: i32,
+) -> Result<SaveReport, TopoError> {
+    let dir = repo_dir(repo_root, prefix);
+    let report = shard::save(index, &dir.join(SHARD_DIR), branch, level)?;
+
+    // Remove the legacy single-file and JSON indexes if present
+    let _ = fs::remove_file(dir.join(INDEX_FILE));
     let legacy = dir.join("index.json");
-    if legacy.exists() {
+    if prefix.is_empty() && legacy.exists() {
         let _ = fs::remove_file(legacy);
     }
 
-    Ok(())
+    Ok(report)
+}
+
+/// The files [`save_repo`] would create, overwrite, and delete, without
+/// writing anything.
+pub fn plan_save_repo(
+    index: &DeepIndex,
+    repo_root: &Path,
+    prefix: &str,
+    branch: Option<&str>,
+    level: i32,
+) -> Result<SavePlan, TopoError> {
+    let dir = repo_dir(repo_root, prefix);
+    let mut plan = shard::plan(index, &dir.join(SHARD_DIR), branch, level)?;
+    plan.deleted
+        .extend(PlannedFile::existing(dir.join(INDEX_FILE)));
+    if prefix.is_empty() {
+        plan.deleted
+            .extend(PlannedFile::existing(dir.join("index.json")));
+    }
+    Ok(plan)
 }
 
 /// Load a DeepIndex from disk. Returns None if the index file doesn't exist.
-pub fn load(repo_root: &Path) -> anyhow::Result<Option<DeepIndex>> {
-    let path = repo_root.join(INDEX_DIR).join(INDEX_FILE);
+pub fn load(repo_root: &Path) -> Result<Option<DeepIndex>, TopoError> {
+    load_repo(repo_root, "", None)
+}
+
+/// Load the whole index of the repository nested at `prefix` below
+/// `repo_root` (empty for the root itself), as last saved on `branch` if
+/// it was, otherwise as last saved.
+pub fn load_repo(
+    repo_root: &Path,
+    prefix: &str,
+    branch: Option<&str>,
+) -> Result<Option<DeepIndex>, TopoError> {
+    // Missing shards, old versions, or deserialization failures force a rebuild
+    Ok(open(repo_root, prefix, branch)?.and_then(ShardedIndex::into_index))
+}
+
+/// Open the last saved index of the repository nested at `prefix` below
+/// `repo_root` without reading its file entries; shards load as entries
+/// are looked up.
+pub fn open_repo(repo_root: &Path, prefix: &str) -> Result<Option<ShardedIndex>, TopoError> {
+    open(repo_root, prefix, None)
+}
+
+fn open(
+    repo_root: &Path,
+    prefix: &str,
+    branch: Option<&str>,
+) -> Result<Option<ShardedIndex>, TopoError> {
+    let dir = repo_dir(repo_root, prefix);
+    if let Some(index) = ShardedIndex::open(&dir.join(SHARD_DIR), branch)? {
+        return Ok(Some(index));
+    }
+
+    let path = dir.join(INDEX_FILE);
     if !path.exists() {
         return Ok(None);
     }
+    let bytes = fs::read(&path).map_err(|e| TopoError::io(&path, e))?;
+    crypt::check_readable(&path, &bytes)?;
+    Ok(decode(&bytes).map(ShardedIndex::whole))
+}
 
-    let bytes = fs::read(&path)?;
-    let index = match rkyv::from_bytes::<DeepIndex, rkyv::rancor::Error>(&bytes) {
-        Ok(idx) if idx.version >= 2 => idx,
-        // Old version or deserialization failure — force rebuild
-        _ => return Ok(None),
-    };
-    Ok(Some(index))
+/// Deserialize single-file index bytes, returning None for unreadable or
+/// outdated indexes.
+///
+/// Accepts both compressed and uncompressed index files.
+fn decode(bytes: &[u8]) -> Option<DeepIndex> {
+    let raw = decompress(bytes)?;
+    rkyv::from_bytes::<DeepIndex, rkyv::rancor::Error>(&raw)
+        .ok()
+        .filter(|idx| idx.version >= INDEX_VERSION)
 }
 
-/// Get the path to the index file.
-pub fn index_path(repo_root: &Path) -> std::path::PathBuf {
-    repo_root.join(INDEX_DIR).join(INDEX_FILE)
+/// Serialized index data as stored on disk: compressed at zstd `level`
+/// unless it is 0. `path` is only used in errors.
+pub(crate) fn encode(path: &Path, bytes: &[u8], level: i32) -> Result<Vec<u8>, TopoError> {
+    if level <= 0 {
+        return crypt::seal(path, bytes.to_vec());
+    }
+    let mut out = COMPRESSED_MAGIC.to_vec();
+    zstd::stream::copy_encode(bytes, &mut out, level).map_err(|e| TopoError::Index {
+        path: Some(path.to_path_buf()),
+        message: "failed to compress index".to_string(),
+        source: Some(Box::new(e)),
+    })?;
+    crypt::seal(path, out)
+}
+
+/// Undo [`encode`]'s encryption and compression, if any. Returns None for
+/// corrupt data, or encrypted data without the key.
+pub(crate) fn decompress(bytes: &[u8]) -> Option<Cow<'_, [u8]>> {
+    match crypt::open(bytes)? {
+        Cow::Borrowed(bytes) => match bytes.strip_prefix(COMPRESSED_MAGIC) {
+            Some(compressed) => zstd::decode_all(compressed).ok().map(Cow::Owned),
+            None => Some(Cow::Borrowed(bytes)),
+        },
+        Cow::Owned(bytes) => match bytes.strip_prefix(COMPRESSED_MAGIC) {
+            Some(compressed) => zstd::decode_all(compressed).ok().map(Cow::Owned),
+            None => Some(Cow::Owned(bytes)),
+        },
+    }
+}
+
+pub(crate) fn serialize_error(path: &Path, e: rkyv::rancor::Error) -> TopoError {
+    TopoError::Index {
+        path: Some(path.to_path_buf()),
+        message: "failed to serialize index".to_string(),
+        source: Some(Box::new(e)),
+    }
+}
+
+/// Get the path to the sharded index directory.
+pub fn index_path(repo_root: &Path) -> PathBuf {
+    repo_index_path(repo_root, "")
+}
+
+/// Path to the sharded index of the repository nested at `prefix` below
+/// `repo_root`. Nested repositories keep their indexes under the root's
+/// `.topo/repos/`, so their working trees stay untouched.
+pub fn repo_index_path(repo_root: &Path, prefix: &str) -> PathBuf {
+    repo_dir(repo_root, prefix).join(SHARD_DIR)
+}
+
+/// Where local embedding models are downloaded to for the repository at
+/// `repo_root`.
+pub fn model_cache_path(repo_root: &Path) -> PathBuf {
+    repo_root.join(INDEX_DIR).join(MODEL_DIR)
+}
+
+/// Where `topo daemon` listens for the repository at `repo_root`.
+pub fn daemon_socket_path(repo_root: &Path) -> PathBuf {
+    repo_root.join(INDEX_DIR).join(DAEMON_SOCKET)
+}
+
+/// Files of every index saved below `repo_root`, nested repositories'
+/// included, sorted by path. Usage statistics are left out.
+pub fn index_files(repo_root: &Path) -> Result<Vec<PlannedFile>, TopoError> {
+    let dir = repo_root.join(INDEX_DIR);
+    let mut files = Vec::new();
+    for name in [SHARD_DIR, NESTED_INDEX_DIR, INDEX_FILE, "index.json"] {
+        collect_files(&dir.join(name), &mut files)?;
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// Delete every index saved below `repo_root`, see [`index_files`].
+/// Returns the files removed.
+pub fn remove_indexes(repo_root: &Path) -> Result<Vec<PlannedFile>, TopoError> {
+    let files = index_files(repo_root)?;
+    let dir = repo_root.join(INDEX_DIR);
+    for name in [SHARD_DIR, NESTED_INDEX_DIR] {
+        let path = dir.join(name);
+        if path.is_dir() {
+            fs::remove_dir_all(&path).map_err(|e| TopoError::io(&path, e))?;
+        }
+    }
+    for name in [INDEX_FILE, "index.json"] {
+        let path = dir.join(name);
+        if path.is_file() {
+            fs::remove_file(&path).map_err(|e| TopoError::io(&path, e))?;
+        }
+    }
+    Ok(files)
+}
+
+/// Add `path` to `files` if it is a file, or every file below it if it is a
+/// directory. Write locks are left out.
+fn collect_files(path: &Path, files: &mut Vec<PlannedFile>) -> Result<(), TopoError> {
+    if path.extension().is_some_and(|ext| ext == LOCK_EXTENSION) {
+        return Ok(());
+    }
+    if path.is_file() {
+        files.extend(PlannedFile::existing(path.to_path_buf()));
+    } else if path.is_dir() {
+        for entry in fs::read_dir(path).map_err(|e| TopoError::io(path, e))? {
+            let entry = entry.map_err(|e| TopoError::io(path, e))?;
+            collect_files(&entry.path(), files)?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether an index was saved for the repository at `prefix`, readable or
+/// not.
+pub(crate) fn index_exists(repo_root: &Path, prefix: &str) -> bool {
+    let dir = repo_dir(repo_root, prefix);
+    dir.join(SHARD_DIR).join(shard::MANIFEST_FILE).exists() || dir.join(INDEX_FILE).exists()
+}
+
+/// Directory holding the index files of the repository at `prefix`.
+fn repo_dir(repo_root: &Path, prefix: &str) -> PathBuf {
+    let dir = repo_root.join(INDEX_DIR);
+    if prefix.is_empty() {
+        dir
+    } else {
+        dir.join(NESTED_INDEX_DIR).join(prefix)
+    }
 }
 
 /// Perform an incremental update: merge new index data with an existing index.
 ///
 /// Files whose SHA-256 hasn't changed keep their existing entries.
-/// New or changed files get entries from the fresh index.
+/// Files that moved with identical content keep their existing entries,
+/// re-keyed to the new path. New or changed files get entries from the
+/// fresh index. Files that disappeared become tombstones.
 pub fn merge_incremental(existing: &DeepIndex, fresh: &DeepIndex) -> DeepIndex {
     let mut merged_files = HashMap::new();
 
+    // Old entries whose path disappeared are rename sources, keyed by content
+    let mut moved: HashMap<[u8; 32], Vec<&FileEntry>> = HashMap::new();
+    for (path, entry) in &existing.files {
+        if !fresh.files.contains_key(path) {
+            moved.entry(entry.sha256).or_default().push(entry);
+        }
+    }
+
     // Start with all fresh entries
     for (path, entry) in &fresh.files {
+        // Old entries without chunk terms can't join an index that has
+        // them, nor entries with other term stems
+        let reusable = |old: &FileEntry| {
+            (!fresh.chunk_postings || old.chunk_terms.is_some())
+                && existing.stemmed == fresh.stemmed
+        };
         // Check if the file exists in the old index with the same hash
         if let Some(old_entry) = existing.files.get(path)
             && old_entry.sha256 == entry.sha256
+            && reusable(old_entry)
         {
             // File unchanged — keep existing entry
             merged_files.insert(path.clone(), old_entry.clone());
             continue;
         }
+        // File renamed without content changes — carry the old entry over
+        if !existing.files.contains_key(path)
+            && let Some(old_entry) = moved.get_mut(&entry.sha256).and_then(|v| v.pop())
+            && reusable(old_entry)
+        {
+            merged_files.insert(path.clone(), retarget_entry(old_entry, path));
+            continue;
+        }
         // File is new or changed — use fresh entry
         merged_files.insert(path.clone(), entry.clone());
     }
 
-    // Recompute corpus stats from merged data
-    let total_docs = merged_files.len() as u32;
-    let total_length: u32 = merged_files.values().map(|e| e.doc_length).sum();
-    let avg_doc_length = if total_docs > 0 {
-        total_length as f64 / total_docs as f64
-    } else {
-        1.0
-    };
-
-    let mut doc_frequencies: HashMap<String, u32> = HashMap::new();
-    for entry in merged_files.values() {
-        for term in entry.term_frequencies.keys() {
-            *doc_frequencies.entry(term.clone()).or_default() += 1;
-        }
+    let current = fresh.files.iter().map(|(p, e)| (p.as_str(), e.sha256));
+    let removed = tombstone::removed_paths(&tombstone::hashes(&existing.files), current);
+    let present: HashSet<&str> = fresh.files.keys().map(String::as_str).collect();
+    let tombstones = tombstone::update(
+        &existing.tombstones,
+        removed,
+        &present,
+        tombstone::now_secs(),
+    );
+
+    // Vectors of both, fresh ones winning, minus those of deleted or
+    // changed files
+    let embeddings = match (&existing.embeddings, &fresh.embeddings) {
+        (Some(old), Some(new)) => Some(old.updated_with(new)),
+        (old, new) => new.as_ref().or(old.as_ref()).cloned(),
     }
+    .map(|segment| segment.carried_over(merged_files.iter().map(|(p, e)| (p.as_str(), e.sha256))));
 
-    DeepIndex {
+    let mut merged = DeepIndex {
         version: fresh.version,
         files: merged_files,
-        avg_doc_length,
-        total_docs,
-        doc_frequencies,
+        avg_doc_length: 1.0,
+        total_docs: 0,
+        doc_frequencies: HashMap::new(),
         // PageRank is recomputed globally, always take from fresh index
         pagerank_scores: fresh.pagerank_scores.clone(),
-    }
+        tombstones,
+        // The dependency graph is rebuilt from every file's imports, like PageRank
+        dependencies: fresh.dependencies.clone(),
+        trigrams: fresh.trigrams.clone(),
+        chunk_postings: fresh.chunk_postings,
+        stemmed: fresh.stemmed,
+        embeddings,
+        git_activity: fresh
+            .git_activity
+            .clone()
+            .or_else(|| existing.git_activity.clone()),
+        meta: fresh.meta.clone().or_else(|| existing.meta.clone()),
+        duplicate_chunks: Vec::new(),
+    };
+    // Copies may be in files of either index, like the corpus stats
+    merged.duplicate_chunks = duplicate_groups(&merged.files);
+    // Recompute corpus stats from merged data
+    gc::recompute_corpus_stats(&mut merged);
+    merged
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::builder::IndexBuilder;
+    use crate::shard::MANIFEST_FILE;
     use topo_core::{ChunkKind, FileInfo, Language};
 
     fn make_file_info(path: &str, content: &str) -> FileInfo {
@@ -112,9 +381,33 @@ mod tests {
             language: Language::from_path(Path::new(path)),
             role: topo_core::FileRole::from_path(Path::new(path)),
             sha256: hash,
+            package: None,
         }
     }
 
+    #[test]
+    fn remove_indexes_keeps_usage_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = "fn main() {}\n";
+        fs::write(dir.path().join("main.rs"), content).unwrap();
+        let files = vec![make_file_info("main.rs", content)];
+        let index = IndexBuilder::new(dir.path()).build(&files, None).unwrap().0;
+        save(&index, dir.path()).unwrap();
+        save_repo(&index, dir.path(), "vendor/lib", None, 3).unwrap();
+        fs::write(dir.path().join(".topo/stats.json"), "{}").unwrap();
+
+        let listed = index_files(dir.path()).unwrap();
+        assert_eq!(listed.len(), 4);
+        assert!(listed.iter().all(|f| f.bytes > 0));
+        assert!(listed.iter().any(|f| f.path.ends_with("manifest.bin")));
+        assert!(dir.path().join(".topo/index").exists());
+
+        assert_eq!(remove_indexes(dir.path()).unwrap(), listed);
+        assert!(index_files(dir.path()).unwrap().is_empty());
+        assert!(!index_exists(dir.path(), ""));
+        assert!(dir.path().join(".topo/stats.json").exists());
+    }
+
     #[test]
     fn save_and_load_roundtrip() {
         let dir = tempfile::tempdir().unwrap();
@@ -128,7 +421,7 @@ mod tests {
         save(&index, dir.path()).unwrap();
         let loaded = load(dir.path()).unwrap().unwrap();
 
-        assert_eq!(loaded.version, 2);
+        assert_eq!(loaded.version, INDEX_VERSION);
         assert_eq!(loaded.total_docs, index.total_docs);
         assert!(loaded.files.contains_key("main.rs"));
         assert_eq!(
@@ -137,6 +430,99 @@ mod tests {
         );
     }
 
+    #[test]
+    fn compression_level_controls_file_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = "pub fn authenticate(token: &str) -> bool {\n    !token.is_empty()\n}\n";
+        fs::write(dir.path().join("auth.rs"), content).unwrap();
+        let files = vec![make_file_info("auth.rs", content)];
+        let index = IndexBuilder::new(dir.path()).build(&files, None).unwrap().0;
+
+        let manifest = index_path(dir.path()).join(MANIFEST_FILE);
+        save_with_level(&index, dir.path(), 0).unwrap();
+        let raw = fs::read(&manifest).unwrap();
+        assert!(!raw.starts_with(COMPRESSED_MAGIC));
+        // Uncompressed indexes from older versions still load
+        assert!(
+            load(dir.path())
+                .unwrap()
+                .unwrap()
+                .files
+                .contains_key("auth.rs")
+        );
+
+        save_with_level(&index, dir.path(), 19).unwrap();
+        let compressed = fs::read(&manifest).unwrap();
+        assert!(compressed.starts_with(COMPRESSED_MAGIC));
+        assert!(compressed.len() < raw.len());
+        let loaded = load(dir.path()).unwrap().unwrap();
+        assert_eq!(
+            loaded.files["auth.rs"].sha256,
+            index.files["auth.rs"].sha256
+        );
+    }
+
+    #[test]
+    fn nested_repo_indexes_are_kept_apart() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = "fn main() {}\n";
+        fs::write(dir.path().join("main.rs"), content).unwrap();
+        let files = vec![make_file_info("main.rs", content)];
+        let index = IndexBuilder::new(dir.path()).build(&files, None).unwrap().0;
+
+        save_repo(&index, dir.path(), "vendor/lib", None, 3).unwrap();
+        assert_eq!(
+            repo_index_path(dir.path(), "vendor/lib"),
+            dir.path().join(".topo/repos/vendor/lib/index")
+        );
+        assert!(repo_index_path(dir.path(), "vendor/lib").exists());
+        assert!(load(dir.path()).unwrap().is_none());
+        assert!(load_repo(dir.path(), "other", None).unwrap().is_none());
+        let loaded = load_repo(dir.path(), "vendor/lib", None).unwrap().unwrap();
+        assert!(loaded.files.contains_key("main.rs"));
+        assert_eq!(repo_index_path(dir.path(), ""), index_path(dir.path()));
+    }
+
+    #[test]
+    fn corrupt_compressed_index_forces_rebuild() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(INDEX_DIR)).unwrap();
+        let mut bytes = COMPRESSED_MAGIC.to_vec();
+        bytes.extend_from_slice(b"not zstd");
+        fs::write(dir.path().join(INDEX_DIR).join(INDEX_FILE), bytes).unwrap();
+        assert!(load(dir.path()).unwrap().is_none());
+    }
+
+    #[test]
+    fn loads_legacy_single_file_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = "fn main() {}\n";
+        fs::write(dir.path().join("main.rs"), content).unwrap();
+        let files = vec![make_file_info("main.rs", content)];
+        let index = IndexBuilder::new(dir.path()).build(&files, None).unwrap().0;
+
+        let legacy = dir.path().join(INDEX_DIR).join(INDEX_FILE);
+        fs::create_dir_all(legacy.parent().unwrap()).unwrap();
+        let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&index).unwrap();
+        fs::write(&legacy, encode(&legacy, &bytes, 3).unwrap()).unwrap();
+
+        let opened = open_repo(dir.path(), "").unwrap().unwrap();
+        assert_eq!(opened.shard_count(), 0);
+        assert!(opened.entry("main.rs").is_some());
+        assert!(
+            load(dir.path())
+                .unwrap()
+                .unwrap()
+                .files
+                .contains_key("main.rs")
+        );
+
+        // Saving replaces it with the sharded layout
+        save(&index, dir.path()).unwrap();
+        assert!(!legacy.exists());
+        assert_eq!(open_repo(dir.path(), "").unwrap().unwrap().shard_count(), 1);
+    }
+
     #[test]
     fn load_nonexistent_returns_none() {
         let dir = tempfile::tempdir().unwrap();
@@ -148,17 +534,26 @@ mod tests {
     fn save_creates_topo_dir() {
         let dir = tempfile::tempdir().unwrap();
         let index = DeepIndex {
-            version: 2,
+            version: INDEX_VERSION,
             files: HashMap::new(),
             avg_doc_length: 0.0,
             total_docs: 0,
             doc_frequencies: HashMap::new(),
             pagerank_scores: HashMap::new(),
+            tombstones: Vec::new(),
+            dependencies: HashMap::new(),
+            trigrams: None,
+            chunk_postings: false,
+            stemmed: false,
+            embeddings: None,
+            git_activity: None,
+            meta: None,
+            duplicate_chunks: Vec::new(),
         };
 
         save(&index, dir.path()).unwrap();
         assert!(dir.path().join(".topo").exists());
-        assert!(dir.path().join(".topo/index.bin").exists());
+        assert!(dir.path().join(".topo/index/manifest.bin").exists());
     }
 
     #[test]
@@ -228,6 +623,131 @@ mod tests {
         assert_eq!(merged.files["a.rs"].sha256, fresh.files["a.rs"].sha256);
     }
 
+    #[test]
+    fn merge_incremental_carries_over_renamed_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = "fn authenticate() {}\n";
+        fs::create_dir_all(dir.path().join("src/auth")).unwrap();
+        fs::write(dir.path().join("src/login.rs"), content).unwrap();
+        fs::write(dir.path().join("src/auth/session.rs"), content).unwrap();
+
+        let builder = IndexBuilder::new(dir.path());
+        let mut existing = builder
+            .build(&[make_file_info("src/login.rs", content)], None)
+            .unwrap()
+            .0;
+        // Mark the old entry so we can tell it apart from a fresh build
+        existing
+            .files
+            .get_mut("src/login.rs")
+            .unwrap()
+            .chunks
+            .push(topo_core::Chunk {
+                kind: ChunkKind::Other,
+                name: "marker".to_string(),
+                start_line: 1,
+                end_line: 1,
+                content: String::new(),
+                target: None,
+                doc: None,
+                parent: None,
+                signature: None,
+                tokens: 0,
+                exported: false,
+            });
+
+        let fresh = builder
+            .build(&[make_file_info("src/auth/session.rs", content)], None)
+            .unwrap()
+            .0;
+        let merged = merge_incremental(&existing, &fresh);
+
+        assert_eq!(merged.total_docs, 1);
+        let entry = &merged.files["src/auth/session.rs"];
+        assert!(entry.chunks.iter().any(|c| c.name == "marker"));
+        // Filename terms follow the new path
+        assert!(entry.term_frequencies["session"].filename > 0);
+        assert!(!entry.term_frequencies.contains_key("login"));
+        // Body terms carried over
+        assert!(entry.term_frequencies["authenticate"].body > 0);
+    }
+
+    #[test]
+    fn merge_incremental_keeps_vectors_of_unchanged_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |path: &str, content: &str| {
+            fs::write(dir.path().join(path), content).unwrap();
+            make_file_info(path, content)
+        };
+        let vectors = |info: &FileInfo, value: f32| topo_core::FileEmbeddings {
+            sha256: info.sha256,
+            file: vec![value, 0.0],
+            chunks: vec![vec![value, 1.0]],
+        };
+        let a = write("a.rs", "fn a() {}\n");
+        let b = write("b.rs", "fn b() {}\n");
+        let gone = write("gone.rs", "fn gone() {}\n");
+
+        let builder = IndexBuilder::new(dir.path());
+        let mut existing = builder
+            .build(&[a.clone(), b.clone(), gone.clone()], None)
+            .unwrap()
+            .0;
+        let mut segment = topo_core::EmbeddingSegment::new("mini-lm", 2);
+        for (info, value) in [(&a, 1.0), (&b, 2.0), (&gone, 3.0)] {
+            segment.insert(&info.path, vectors(info, value)).unwrap();
+        }
+        existing.embeddings = Some(segment);
+
+        // Only the changed and new files were embedded this time
+        let b2 = write("b.rs", "fn b2() {}\n");
+        let c = write("c.rs", "fn c() {}\n");
+        let mut fresh = builder
+            .build(&[a.clone(), b2.clone(), c.clone()], None)
+            .unwrap()
+            .0;
+        let mut segment = topo_core::EmbeddingSegment::new("mini-lm", 2);
+        for (info, value) in [(&b2, 4.0), (&c, 5.0)] {
+            segment.insert(&info.path, vectors(info, value)).unwrap();
+        }
+        fresh.embeddings = Some(segment);
+
+        let merged = merge_incremental(&existing, &fresh);
+        let segment = merged.embeddings.unwrap();
+        assert_eq!(segment.len(), 3);
+        assert_eq!(segment.get("a.rs", &a.sha256).unwrap().file[0], 1.0);
+        assert_eq!(segment.get("b.rs", &b2.sha256).unwrap().file[0], 4.0);
+        assert_eq!(segment.get("c.rs", &c.sha256).unwrap().file[0], 5.0);
+        assert!(segment.get("gone.rs", &gone.sha256).is_none());
+    }
+
+    #[test]
+    fn merge_incremental_records_tombstones() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.rs"), "fn a() {}\n").unwrap();
+        fs::write(dir.path().join("old_auth.rs"), "fn auth() {}\n").unwrap();
+
+        let builder = IndexBuilder::new(dir.path());
+        let existing = builder
+            .build(
+                &[
+                    make_file_info("a.rs", "fn a() {}\n"),
+                    make_file_info("old_auth.rs", "fn auth() {}\n"),
+                ],
+                None,
+            )
+            .unwrap()
+            .0;
+        let fresh = builder
+            .build(&[make_file_info("a.rs", "fn a() {}\n")], None)
+            .unwrap()
+            .0;
+
+        let merged = merge_incremental(&existing, &fresh);
+        let paths: Vec<&str> = merged.tombstones.iter().map(|t| t.path.as_str()).collect();
+        assert_eq!(paths, vec!["old_auth.rs"]);
+    }
+
     #[test]
     fn removes_legacy_json_index() {
         let dir = tempfile::tempdir().unwrap();
@@ -236,16 +756,25 @@ mod tests {
         fs::write(topo_dir.join("index.json"), b"{}").unwrap();
 
         let index = DeepIndex {
-            version: 2,
+            version: INDEX_VERSION,
             files: HashMap::new(),
             avg_doc_length: 0.0,
             total_docs: 0,
             doc_frequencies: HashMap::new(),
             pagerank_scores: HashMap::new(),
+            tombstones: Vec::new(),
+            dependencies: HashMap::new(),
+            trigrams: None,
+            chunk_postings: false,
+            stemmed: false,
+            embeddings: None,
+            git_activity: None,
+            meta: None,
+            duplicate_chunks: Vec::new(),
         };
 
         save(&index, dir.path()).unwrap();
         assert!(!topo_dir.join("index.json").exists());
-        assert!(topo_dir.join("index.bin").exists());
+        assert!(topo_dir.join("index/manifest.bin").exists());
     }
 }
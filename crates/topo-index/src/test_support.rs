@@ -0,0 +1,24 @@
+//! Fixtures shared by the tests of several modules.
+
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+use topo_core::{FileInfo, FileRole, Language};
+
+/// `path` with `content`, described as a scan would.
+pub(crate) fn file_info(path: &str, content: &str) -> FileInfo {
+    FileInfo {
+        path: path.to_string(),
+        size: content.len() as u64,
+        language: Language::from_path(Path::new(path)),
+        role: FileRole::from_path(Path::new(path)),
+        sha256: Sha256::digest(content.as_bytes()).into(),
+        package: None,
+    }
+}
+
+/// Write `content` to `path` under `root`, describing it as a scan would.
+pub(crate) fn write_file(root: &Path, path: &str, content: &str) -> FileInfo {
+    fs::write(root.join(path), content).unwrap();
+    file_info(path, content)
+}
@@ -0,0 +1,172 @@
+use crate::shard::ShardedIndex;
+use std::collections::{HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+use topo_core::{FileEntry, FileInfo, Tombstone};
+
+/// Tombstones older than this are dropped on the next build.
+pub const TOMBSTONE_TTL_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// Upper bound on retained tombstones; the oldest are dropped first.
+pub const MAX_TOMBSTONES: usize = 100;
+
+/// Files deleted since earlier builds, plus any indexed files missing from
+/// the current scan (deleted since the last build), most recent first.
+///
+/// Reads every shard of `index`, since any indexed file may be gone.
+pub fn recently_deleted(index: &ShardedIndex, files: &[FileInfo]) -> Vec<Tombstone> {
+    let current = files.iter().map(|f| (f.path.as_str(), f.sha256));
+    let removed = removed_paths(&index.hashes(), current);
+    let present: HashSet<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    update(&index.corpus().tombstones, removed, &present, now_secs())
+}
+
+/// Content hash of each indexed file, by path.
+pub(crate) fn hashes(files: &HashMap<String, FileEntry>) -> HashMap<&str, [u8; 32]> {
+    files
+        .iter()
+        .map(|(path, entry)| (path.as_str(), entry.sha256))
+        .collect()
+}
+
+/// Paths in `old` (path to content hash) that are gone from `current`.
+///
+/// Files that moved with identical content are renames, not deletions.
+pub(crate) fn removed_paths<'a>(
+    old: &HashMap<&str, [u8; 32]>,
+    current: impl Iterator<Item = (&'a str, [u8; 32])>,
+) -> Vec<String> {
+    let mut present = HashSet::new();
+    let mut arrived = HashSet::new();
+    for (path, sha) in current {
+        present.insert(path);
+        if !old.contains_key(path) {
+            arrived.insert(sha);
+        }
+    }
+    let mut removed: Vec<String> = old
+        .iter()
+        .filter(|(path, sha)| !present.contains(*path) && !arrived.contains(*sha))
+        .map(|(path, _)| path.to_string())
+        .collect();
+    removed.sort();
+    removed
+}
+
+/// Merge newly removed paths into the previous tombstones.
+///
+/// Paths that exist again are resurrected (dropped), expired tombstones are
+/// pruned, and the list is capped at [`MAX_TOMBSTONES`].
+pub(crate) fn update(
+    previous: &[Tombstone],
+    removed: Vec<String>,
+    present: &HashSet<&str>,
+    now: u64,
+) -> Vec<Tombstone> {
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut tombstones: Vec<Tombstone> = Vec::new();
+    for path in removed {
+        if seen.insert(path.clone()) {
+            tombstones.push(Tombstone {
+                path,
+                deleted_at: now,
+            });
+        }
+    }
+    for old in previous {
+        if present.contains(old.path.as_str())
+            || now.saturating_sub(old.deleted_at) > TOMBSTONE_TTL_SECS
+            || !seen.insert(old.path.clone())
+        {
+            continue;
+        }
+        tombstones.push(old.clone());
+    }
+    tombstones.sort_by(|a, b| {
+        b.deleted_at
+            .cmp(&a.deleted_at)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    tombstones.truncate(MAX_TOMBSTONES);
+    tombstones
+}
+
+pub(crate) fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(sha: u8) -> FileEntry {
+        FileEntry {
+            sha256: [sha; 32],
+            size: 0,
+            chunks: Vec::new(),
+            term_frequencies: HashMap::new(),
+            doc_length: 0,
+            metadata: Default::default(),
+            chunk_terms: None,
+            exports: Vec::new(),
+            chunk_hashes: Vec::new(),
+        }
+    }
+
+    fn tombstone(path: &str, deleted_at: u64) -> Tombstone {
+        Tombstone {
+            path: path.to_string(),
+            deleted_at,
+        }
+    }
+
+    #[test]
+    fn removed_paths_skips_exact_renames() {
+        let mut old = HashMap::new();
+        old.insert("src/old_auth.rs".to_string(), entry(1));
+        old.insert("src/moved.rs".to_string(), entry(2));
+        old.insert("src/kept.rs".to_string(), entry(3));
+
+        let current = [("src/kept.rs", [3; 32]), ("src/new/moved.rs", [2; 32])];
+        let removed = removed_paths(&hashes(&old), current.into_iter());
+        assert_eq!(removed, vec!["src/old_auth.rs"]);
+    }
+
+    #[test]
+    fn update_adds_new_and_keeps_previous() {
+        let previous = vec![tombstone("a.rs", 100)];
+        let tombstones = update(&previous, vec!["b.rs".to_string()], &HashSet::new(), 200);
+        assert_eq!(
+            tombstones,
+            vec![tombstone("b.rs", 200), tombstone("a.rs", 100)]
+        );
+    }
+
+    #[test]
+    fn update_drops_resurrected_and_expired() {
+        let previous = vec![tombstone("back.rs", 100), tombstone("old.rs", 0)];
+        let present: HashSet<&str> = ["back.rs"].into_iter().collect();
+        let now = TOMBSTONE_TTL_SECS + 1;
+        assert!(update(&previous, Vec::new(), &present, now).is_empty());
+    }
+
+    #[test]
+    fn update_refreshes_repeated_deletion() {
+        let previous = vec![tombstone("a.rs", 100)];
+        let tombstones = update(&previous, vec!["a.rs".to_string()], &HashSet::new(), 200);
+        assert_eq!(tombstones, vec![tombstone("a.rs", 200)]);
+    }
+
+    #[test]
+    fn update_caps_list() {
+        let removed: Vec<String> = (0..MAX_TOMBSTONES + 10)
+            .map(|i| format!("f{i}.rs"))
+            .collect();
+        assert_eq!(
+            update(&[], removed, &HashSet::new(), 1).len(),
+            MAX_TOMBSTONES
+        );
+    }
+}
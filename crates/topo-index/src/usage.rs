@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use topo_core::TopoError;
+
+/// Latency samples kept for percentiles; the oldest are dropped first.
+pub const MAX_LATENCY_SAMPLES: usize = 1000;
+
+/// Local usage statistics, kept in `.topo/stats.json`.
+///
+/// Counts and timings only: no query text, paths, or identities are
+/// recorded, and nothing leaves the machine.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UsageStats {
+    pub queries: u64,
+    /// Latencies of the most recent queries in milliseconds, oldest first.
+    pub query_latencies_ms: Vec<u64>,
+    /// Queries that needed a deep index and found one on disk.
+    pub index_hits: u64,
+    /// Queries that needed a deep index and found none.
+    pub index_misses: u64,
+    pub index_runs: u64,
+    /// Files whose index entries were reused from the previous build.
+    pub files_reused: u64,
+    pub files_reindexed: u64,
+}
+
+impl UsageStats {
+    /// Record a query that took `latency`. `index_hit` is `None` when the
+    /// query didn't need a deep index.
+    pub fn record_query(&mut self, latency: Duration, index_hit: Option<bool>) {
+        self.queries += 1;
+        self.query_latencies_ms
+            .push(u64::try_from(latency.as_millis()).unwrap_or(u64::MAX));
+        let excess = self
+            .query_latencies_ms
+            .len()
+            .saturating_sub(MAX_LATENCY_SAMPLES);
+        self.query_latencies_ms.drain(..excess);
+        match index_hit {
+            Some(true) => self.index_hits += 1,
+            Some(false) => self.index_misses += 1,
+            None => {}
+        }
+    }
+
+    /// Record an index build that reused `reused` files and reindexed
+    /// `reindexed`.
+    pub fn record_index(&mut self, reused: usize, reindexed: usize) {
+        self.index_runs += 1;
+        self.files_reused += reused as u64;
+        self.files_reindexed += reindexed as u64;
+    }
+
+    /// Nearest-rank `percentile` (0–100) of recent query latencies, in
+    /// milliseconds.
+    pub fn latency_percentile(&self, percentile: f64) -> Option<u64> {
+        if self.query_latencies_ms.is_empty() {
+            return None;
+        }
+        let mut sorted = self.query_latencies_ms.clone();
+        sorted.sort_unstable();
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * sorted.len() as f64).ceil() as usize;
+        Some(sorted[rank.saturating_sub(1)])
+    }
+
+    /// Share of index-backed queries that found an index.
+    pub fn index_hit_rate(&self) -> Option<f64> {
+        rate(self.index_hits, self.index_misses)
+    }
+
+    /// Share of files that index builds reused rather than reindexed.
+    pub fn reuse_rate(&self) -> Option<f64> {
+        rate(self.files_reused, self.files_reindexed)
+    }
+}
+
+fn rate(hits: u64, misses: u64) -> Option<f64> {
+    let total = hits + misses;
+    (total > 0).then(|| hits as f64 / total as f64)
+}
+
+/// Where usage statistics for the repository at `root` are kept.
+pub fn usage_path(root: &Path) -> PathBuf {
+    root.join(".topo").join("stats.json")
+}
+
+/// Read the usage statistics of the repository at `root`, empty if none
+/// have been recorded.
+pub fn load_usage(root: &Path) -> Result<UsageStats, TopoError> {
+    let path = usage_path(root);
+    let bytes = match fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(UsageStats::default()),
+        Err(e) => return Err(TopoError::io(&path, e)),
+    };
+    serde_json::from_slice(&bytes).map_err(|e| TopoError::Index {
+        path: Some(path),
+        message: "invalid usage stats".to_string(),
+        source: Some(Box::new(e)),
+    })
+}
+
+/// Update the usage statistics of the repository at `root`.
+///
+/// Only repositories that already have a `.topo` directory are tracked. An
+/// unreadable stats file is started over.
+pub fn record_usage(root: &Path, update: impl FnOnce(&mut UsageStats)) -> Result<(), TopoError> {
+    let path = usage_path(root);
+    if !root.join(".topo").is_dir() {
+        return Ok(());
+    }
+    let mut stats = load_usage(root).unwrap_or_default();
+    update(&mut stats);
+
+    let json = serde_json::to_vec_pretty(&stats).map_err(|e| TopoError::Index {
+        path: Some(path.clone()),
+        message: "failed to serialize usage stats".to_string(),
+        source: Some(Box::new(e)),
+    })?;
+    // Write then rename so a concurrent reader never sees a partial file
+    let staged = path.with_extension("json.tmp");
+    fs::write(&staged, json).map_err(|e| TopoError::io(&staged, e))?;
+    fs::rename(&staged, &path).map_err(|e| TopoError::io(&path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_use_nearest_rank() {
+        let mut stats = UsageStats::default();
+        assert_eq!(stats.latency_percentile(50.0), None);
+        for ms in [40, 10, 30, 20, 100] {
+            stats.record_query(Duration::from_millis(ms), None);
+        }
+        assert_eq!(stats.latency_percentile(50.0), Some(30));
+        assert_eq!(stats.latency_percentile(90.0), Some(100));
+        assert_eq!(stats.latency_percentile(0.0), Some(10));
+        assert_eq!(stats.index_hit_rate(), None);
+    }
+
+    #[test]
+    fn latency_samples_are_capped() {
+        let mut stats = UsageStats::default();
+        for ms in 0..(MAX_LATENCY_SAMPLES as u64 + 5) {
+            stats.record_query(Duration::from_millis(ms), Some(ms % 2 == 0));
+        }
+        assert_eq!(stats.queries, MAX_LATENCY_SAMPLES as u64 + 5);
+        assert_eq!(stats.query_latencies_ms.len(), MAX_LATENCY_SAMPLES);
+        assert_eq!(stats.query_latencies_ms[0], 5);
+        assert!((stats.index_hit_rate().unwrap() - 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn records_into_existing_topo_dir_only() {
+        let dir = tempfile::tempdir().unwrap();
+        record_usage(dir.path(), |s| s.record_index(0, 3)).unwrap();
+        assert!(!usage_path(dir.path()).exists());
+
+        fs::create_dir(dir.path().join(".topo")).unwrap();
+        record_usage(dir.path(), |s| s.record_index(0, 3)).unwrap();
+        record_usage(dir.path(), |s| s.record_index(3, 1)).unwrap();
+        let stats = load_usage(dir.path()).unwrap();
+        assert_eq!(stats.index_runs, 2);
+        assert_eq!(stats.reuse_rate(), Some(3.0 / 7.0));
+    }
+
+    #[test]
+    fn corrupt_stats_are_an_error_to_read_and_reset_on_write() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join(".topo")).unwrap();
+        fs::write(usage_path(dir.path()), "not json").unwrap();
+        assert!(matches!(
+            load_usage(dir.path()),
+            Err(TopoError::Index { .. })
+        ));
+        record_usage(dir.path(), |s| {
+            s.record_query(Duration::from_millis(5), Some(true))
+        })
+        .unwrap();
+        assert_eq!(load_usage(dir.path()).unwrap().queries, 1);
+    }
+}
@@ -0,0 +1,271 @@
+use crate::gc;
+use crate::store;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use topo_core::{DeepIndex, FileEntry, TopoError};
+
+/// A problem found by [`verify`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IndexIssue {
+    /// The index exists but can't be loaded: a shard is missing, data is
+    /// corrupt, or it was written by an older version.
+    Unreadable,
+    /// A corpus-wide field disagrees with the file entries.
+    Inconsistent { field: &'static str },
+    /// An indexed file is gone from disk.
+    Missing { path: String },
+    /// An indexed file's content changed since it was indexed.
+    Stale { path: String },
+    /// A chunk's line span falls outside its file.
+    ChunkOutOfRange { path: String, chunk: String },
+}
+
+impl IndexIssue {
+    /// The file whose entry is bad, for issues confined to one entry.
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            Self::Missing { path } | Self::Stale { path } | Self::ChunkOutOfRange { path, .. } => {
+                Some(path)
+            }
+            Self::Unreadable | Self::Inconsistent { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for IndexIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unreadable => f.write_str("index is unreadable or outdated"),
+            Self::Inconsistent { field } => {
+                write!(f, "{field} disagrees with the file entries")
+            }
+            Self::Missing { path } => write!(f, "{path}: file no longer exists"),
+            Self::Stale { path } => write!(f, "{path}: content changed since indexing"),
+            Self::ChunkOutOfRange { path, chunk } => {
+                write!(f, "{path}: chunk `{chunk}` spans lines outside the file")
+            }
+        }
+    }
+}
+
+/// Outcome of [`verify`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// File entries checked.
+    pub files: usize,
+    pub issues: Vec<IndexIssue>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Files whose entries must be rebuilt, sorted.
+    pub fn bad_paths(&self) -> Vec<&str> {
+        let mut paths: Vec<&str> = self.issues.iter().filter_map(IndexIssue::path).collect();
+        paths.dedup();
+        paths
+    }
+}
+
+/// Check the saved index of the repository nested at `prefix` below
+/// `repo_root` against the files under `files_root`. Returns None if no
+/// index was saved.
+pub fn verify_repo(
+    repo_root: &Path,
+    prefix: &str,
+    branch: Option<&str>,
+    files_root: &Path,
+) -> Result<Option<VerifyReport>, TopoError> {
+    if !store::index_exists(repo_root, prefix) {
+        return Ok(None);
+    }
+    Ok(Some(match store::load_repo(repo_root, prefix, branch)? {
+        Some(index) => verify(&index, files_root),
+        None => VerifyReport {
+            files: 0,
+            issues: vec![IndexIssue::Unreadable],
+        },
+    }))
+}
+
+/// Check the structural invariants of `index` and its entries against the
+/// files under `root`.
+///
+/// Corpus statistics and the dependency graph must match the entries, each
+/// entry's file must exist with the content it was indexed from, and chunks
+/// must lie within their file.
+pub fn verify(index: &DeepIndex, root: &Path) -> VerifyReport {
+    let mut issues = Vec::new();
+
+    let mut expected = index.clone();
+    gc::recompute_corpus_stats(&mut expected);
+    if expected.total_docs != index.total_docs {
+        issues.push(IndexIssue::Inconsistent {
+            field: "total_docs",
+        });
+    }
+    if (expected.avg_doc_length - index.avg_doc_length).abs() > 1e-6 {
+        issues.push(IndexIssue::Inconsistent {
+            field: "avg_doc_length",
+        });
+    }
+    if expected.doc_frequencies != index.doc_frequencies {
+        issues.push(IndexIssue::Inconsistent {
+            field: "doc_frequencies",
+        });
+    }
+    let indexed: HashSet<&str> = index.files.keys().map(String::as_str).collect();
+    let dangling = index.dependencies.iter().any(|(from, deps)| {
+        !indexed.contains(from.as_str()) || deps.iter().any(|to| !indexed.contains(to.as_str()))
+    });
+    if dangling {
+        issues.push(IndexIssue::Inconsistent {
+            field: "dependencies",
+        });
+    }
+
+    let mut paths: Vec<&String> = index.files.keys().collect();
+    paths.sort();
+    for path in paths {
+        issues.extend(check_entry(path, &index.files[path], root));
+    }
+
+    VerifyReport {
+        files: index.files.len(),
+        issues,
+    }
+}
+
+fn check_entry(path: &str, entry: &FileEntry, root: &Path) -> Option<IndexIssue> {
+    let Ok(bytes) = fs::read(root.join(path)) else {
+        return Some(IndexIssue::Missing {
+            path: path.to_string(),
+        });
+    };
+    let sha256: [u8; 32] = Sha256::digest(&bytes).into();
+    if sha256 != entry.sha256 {
+        return Some(IndexIssue::Stale {
+            path: path.to_string(),
+        });
+    }
+    // Lines as tree-sitter counts them: a trailing newline starts one more
+    let lines = bytes.iter().filter(|&&b| b == b'\n').count() as u32 + 1;
+    entry
+        .chunks
+        .iter()
+        .find(|c| c.start_line == 0 || c.end_line < c.start_line || c.end_line > lines)
+        .map(|chunk| IndexIssue::ChunkOutOfRange {
+            path: path.to_string(),
+            chunk: chunk.name.clone(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::IndexBuilder;
+    use crate::test_support::write_file;
+
+    fn indexed_repo(root: &Path) -> DeepIndex {
+        let files = vec![
+            write_file(root, "a.rs", "fn alpha() {\n    1;\n}\n"),
+            write_file(root, "b.rs", "fn beta() {}\n"),
+            write_file(root, "c.rs", "fn gamma() {}\n"),
+        ];
+        IndexBuilder::new(root).build(&files, None).unwrap().0
+    }
+
+    #[test]
+    fn fresh_index_verifies_clean() {
+        let dir = tempfile::tempdir().unwrap();
+        let index = indexed_repo(dir.path());
+        let report = verify(&index, dir.path());
+        assert!(report.is_ok(), "{:?}", report.issues);
+        assert_eq!(report.files, 3);
+    }
+
+    #[test]
+    fn finds_stale_missing_and_out_of_range_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut index = indexed_repo(dir.path());
+        fs::write(dir.path().join("b.rs"), "fn beta() { 2 }\n").unwrap();
+        fs::remove_file(dir.path().join("c.rs")).unwrap();
+        index.files.get_mut("a.rs").unwrap().chunks[0].end_line = 40;
+
+        let report = verify(&index, dir.path());
+        assert_eq!(
+            report.issues,
+            [
+                IndexIssue::ChunkOutOfRange {
+                    path: "a.rs".to_string(),
+                    chunk: "alpha".to_string()
+                },
+                IndexIssue::Stale {
+                    path: "b.rs".to_string()
+                },
+                IndexIssue::Missing {
+                    path: "c.rs".to_string()
+                },
+            ]
+        );
+        assert_eq!(report.bad_paths(), ["a.rs", "b.rs", "c.rs"]);
+    }
+
+    #[test]
+    fn finds_inconsistent_corpus_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut index = indexed_repo(dir.path());
+        index.total_docs += 1;
+        *index.doc_frequencies.get_mut("alpha").unwrap() += 1;
+        index
+            .dependencies
+            .insert("a.rs".to_string(), vec!["gone.rs".to_string()]);
+
+        let report = verify(&index, dir.path());
+        let fields: Vec<String> = report.issues.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            fields,
+            [
+                "total_docs disagrees with the file entries",
+                "doc_frequencies disagrees with the file entries",
+                "dependencies disagrees with the file entries",
+            ]
+        );
+        assert!(report.bad_paths().is_empty());
+    }
+
+    #[test]
+    fn unreadable_saved_index_is_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(
+            verify_repo(dir.path(), "", None, dir.path())
+                .unwrap()
+                .is_none()
+        );
+
+        let index = indexed_repo(dir.path());
+        store::save(&index, dir.path()).unwrap();
+        let report = verify_repo(dir.path(), "", None, dir.path())
+            .unwrap()
+            .unwrap();
+        assert!(report.is_ok());
+
+        // Drop a shard, which makes the whole index unloadable
+        let index_dir = store::index_path(dir.path());
+        let shard = fs::read_dir(&index_dir)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .find(|p| p.file_name().is_some_and(|n| n != "manifest.bin") && p.is_file())
+            .unwrap();
+        fs::remove_file(shard).unwrap();
+        let report = verify_repo(dir.path(), "", None, dir.path())
+            .unwrap()
+            .unwrap();
+        assert_eq!(report.issues, [IndexIssue::Unreadable]);
+    }
+}
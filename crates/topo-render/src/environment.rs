@@ -0,0 +1,133 @@
+use serde::Serialize;
+
+/// Tool state that produced a selection, recorded in every output header.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Environment {
+    /// Topo version.
+    pub version: String,
+    /// Deep index format version, when a deep index contributed to scoring.
+    pub index_version: Option<u32>,
+    /// Repository fingerprint of the scanned file set.
+    pub fingerprint: String,
+    pub preset: String,
+    /// Token estimator used for budgets.
+    pub tokenizer: String,
+    /// Hash of the effective `.topo.toml`, or `"default"`.
+    pub config_hash: String,
+    /// Capabilities the selection would have used but that were missing,
+    /// like `deep_index` or `git`, see `topo capabilities`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub degraded: Vec<String>,
+}
+
+/// PascalCase view of [`Environment`] for JSONL headers.
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct EnvironmentHeader<'a> {
+    version: &'a str,
+    index_version: Option<u32>,
+    fingerprint: &'a str,
+    preset: &'a str,
+    tokenizer: &'a str,
+    config_hash: &'a str,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    degraded: &'a [String],
+}
+
+impl Environment {
+    pub(crate) fn header(&self) -> EnvironmentHeader<'_> {
+        EnvironmentHeader {
+            version: &self.version,
+            index_version: self.index_version,
+            fingerprint: &self.fingerprint,
+            preset: &self.preset,
+            tokenizer: &self.tokenizer,
+            config_hash: &self.config_hash,
+            degraded: &self.degraded,
+        }
+    }
+
+    /// One-line summary with abbreviated hashes, for text formats.
+    pub fn summary(&self) -> String {
+        let index = self
+            .index_version
+            .map(|v| format!("v{v}"))
+            .unwrap_or_else(|| "none".to_string());
+        let mut summary = format!(
+            "topo {} preset={} index={index} fingerprint={} tokenizer={} config={}",
+            self.version,
+            self.preset,
+            short(&self.fingerprint),
+            self.tokenizer,
+            short(&self.config_hash),
+        );
+        if !self.degraded.is_empty() {
+            summary.push_str(&format!(" degraded={}", self.degraded.join(",")));
+        }
+        summary
+    }
+}
+
+fn short(hash: &str) -> &str {
+    hash.get(..12).unwrap_or(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Environment {
+        Environment {
+            version: "0.1.0".to_string(),
+            index_version: Some(2),
+            fingerprint: "a".repeat(64),
+            preset: "balanced".to_string(),
+            tokenizer: "bytes/4".to_string(),
+            config_hash: "default".to_string(),
+            degraded: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn summary_abbreviates_hashes() {
+        assert_eq!(
+            sample().summary(),
+            "topo 0.1.0 preset=balanced index=v2 fingerprint=aaaaaaaaaaaa tokenizer=bytes/4 config=default"
+        );
+    }
+
+    #[test]
+    fn summary_without_index() {
+        let env = Environment {
+            index_version: None,
+            ..sample()
+        };
+        assert!(env.summary().contains("index=none"));
+        assert!(!env.summary().contains("degraded"));
+    }
+
+    #[test]
+    fn summary_and_header_list_degraded_capabilities() {
+        let env = Environment {
+            degraded: vec!["deep_index".to_string(), "git".to_string()],
+            ..sample()
+        };
+        assert!(env.summary().ends_with(" degraded=deep_index,git"));
+        let value = serde_json::to_value(env.header()).unwrap();
+        assert_eq!(value["Degraded"][1], "git");
+        assert!(
+            serde_json::to_value(sample().header())
+                .unwrap()
+                .get("Degraded")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn header_uses_pascal_case() {
+        let env = sample();
+        let value = serde_json::to_value(env.header()).unwrap();
+        assert_eq!(value["IndexVersion"], 2);
+        assert_eq!(value["ConfigHash"], "default");
+    }
+}
@@ -0,0 +1,551 @@
+use crate::environment::Environment;
+use crate::{pinned_first, render_error};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::io::Write;
+use topo_core::{ChunkKind, ContextWindow, Language, ScoredFile, TopoError, display_path};
+use topo_treesit::{Literal, literal_ranges};
+
+/// Maximum number of content bytes embedded per file.
+const MAX_CONTENT_BYTES: usize = 200_000;
+
+/// Writes scored files as a self-contained HTML report.
+///
+/// The report has no external assets: a sortable file table, per-file
+/// score breakdown bars, and collapsible file contents with lightweight
+/// syntax highlighting, all inlined into a single document.
+pub struct HtmlWriter {
+    query: String,
+    preset: String,
+    contents: HashMap<String, String>,
+    context: HashMap<String, Vec<ContextWindow>>,
+    environment: Option<Environment>,
+    highlight: HashSet<String>,
+}
+
+impl HtmlWriter {
+    pub fn new(query: &str, preset: &str) -> Self {
+        Self {
+            query: query.to_string(),
+            preset: preset.to_string(),
+            contents: HashMap::new(),
+            context: HashMap::new(),
+            environment: None,
+            highlight: HashSet::new(),
+        }
+    }
+
+    /// Provide file contents (keyed by path) to embed in the report.
+    pub fn contents(mut self, contents: HashMap<String, String>) -> Self {
+        self.contents = contents;
+        self
+    }
+
+    /// Embed only the lines around query matches (keyed by path) instead
+    /// of whole contents for these files.
+    pub fn context(mut self, context: HashMap<String, Vec<ContextWindow>>) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Record the producing tool state in the report header.
+    pub fn environment(mut self, environment: Environment) -> Self {
+        self.environment = Some(environment);
+        self
+    }
+
+    /// Mark these words wherever they appear in embedded contents.
+    pub fn highlight(mut self, words: HashSet<String>) -> Self {
+        self.highlight = words;
+        self
+    }
+
+    /// Render the report as a string.
+    pub fn render(&self, files: &[ScoredFile], scanned_count: usize) -> Result<String, TopoError> {
+        let mut buf = Vec::new();
+        self.write_to(&mut buf, files, scanned_count)?;
+        String::from_utf8(buf).map_err(|e| render_error("output is not valid UTF-8", e))
+    }
+
+    /// Write the report to a writer.
+    pub fn write_to(
+        &self,
+        writer: &mut dyn Write,
+        files: &[ScoredFile],
+        scanned_count: usize,
+    ) -> Result<(), TopoError> {
+        let files = &*pinned_first(files);
+        let total_tokens: u64 = files.iter().map(|f| f.tokens).sum();
+        let max_score = files.iter().map(|f| f.score).fold(0.0f64, f64::max);
+
+        let mut rows = String::new();
+        let mut details = String::new();
+        for (rank, file) in files.iter().enumerate() {
+            let path = escape_html(&display_path(&file.path));
+            let _ = writeln!(
+                rows,
+                "<tr><td>{}</td><td><a href=\"#f{rank}\">{path}</a></td>\
+                 <td data-v=\"{score}\">{score:.4}</td><td data-v=\"{tokens}\">{tokens}</td>\
+                 <td>{lang}</td><td>{role}</td><td>{bars}</td></tr>",
+                rank + 1,
+                score = file.score,
+                tokens = file.tokens,
+                lang = file.language.as_str(),
+                role = if file.signals.pinned {
+                    format!("{}, pinned", file.role.as_str())
+                } else {
+                    file.role.as_str().to_string()
+                },
+                bars = signal_bars(file, max_score),
+            );
+
+            let body = match (self.context.get(&file.path), self.contents.get(&file.path)) {
+                (Some(windows), _) => windows
+                    .iter()
+                    .map(|w| {
+                        format!(
+                            "<p class=\"note\">lines {}&ndash;{}{}</p>\
+                             <pre><code class=\"lang-{}\">{}</code></pre>",
+                            w.start_line,
+                            w.end_line,
+                            window_note(w),
+                            file.language.as_str(),
+                            highlight_code(&w.text, file.language, &self.highlight)
+                        )
+                    })
+                    .collect(),
+                (None, Some(text)) => {
+                    let (text, truncated) = truncate_utf8(text, MAX_CONTENT_BYTES);
+                    let mut body = format!(
+                        "<pre><code class=\"lang-{}\">{}</code></pre>",
+                        file.language.as_str(),
+                        highlight_code(text, file.language, &self.highlight)
+                    );
+                    if truncated {
+                        body.push_str("<p class=\"note\">(truncated)</p>");
+                    }
+                    body
+                }
+                (None, None) => "<p class=\"note\">(content not available)</p>".to_string(),
+            };
+            let _ = writeln!(
+                details,
+                "<details id=\"f{rank}\"><summary>{path} \
+                 <span class=\"note\">{:.4} · {} tokens</span></summary>{body}</details>",
+                file.score, file.tokens,
+            );
+        }
+
+        let env_line = match &self.environment {
+            Some(env) => format!(
+                "<p class=\"note\" id=\"environment\">{}</p>\n",
+                escape_html(&env.summary())
+            ),
+            None => String::new(),
+        };
+
+        write!(
+            writer,
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>topo report: {title}</title>\n<style>{CSS}</style>\n</head>\n<body>\n\
+             <h1>topo report</h1>\n\
+             <p><b>Query:</b> {title} &middot; <b>Preset:</b> {preset} &middot; \
+             <b>Selected:</b> {count} of {scanned_count} files &middot; \
+             <b>Tokens:</b> {total_tokens}</p>\n{env_line}\
+             <table id=\"files\">\n<thead><tr><th>#</th><th>Path</th><th>Score</th>\
+             <th>Tokens</th><th>Lang</th><th>Role</th><th>Signals</th></tr></thead>\n\
+             <tbody>\n{rows}</tbody>\n</table>\n\
+             <p class=\"legend\"><span class=\"bar bm25f\"></span>BM25F \
+             <span class=\"bar heuristic\"></span>heuristic \
+             <span class=\"bar pagerank\"></span>PageRank \
+             <span class=\"bar recency\"></span>git recency</p>\n\
+             <h2>Contents</h2>\n{details}<script>{JS}</script>\n</body>\n</html>\n",
+            title = escape_html(&self.query),
+            preset = escape_html(&self.preset),
+            count = files.len(),
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Inline horizontal bars for each available signal.
+fn signal_bars(file: &ScoredFile, max_score: f64) -> String {
+    let mut out = String::new();
+    let signals = [
+        ("bm25f", Some(file.signals.bm25f)),
+        ("heuristic", Some(file.signals.heuristic)),
+        ("pagerank", file.signals.pagerank),
+        ("recency", file.signals.git_recency),
+        ("exports", file.signals.exports),
+        ("phrase", file.signals.phrase),
+        ("coupling", file.signals.coupling),
+        ("author", file.signals.author),
+        ("centrality", file.signals.centrality),
+    ];
+    // BM25F is unbounded; scale it against the best score in the report.
+    let bm25f_scale = if max_score > 1.0 { max_score } else { 1.0 };
+    for (name, value) in signals {
+        let Some(value) = value else { continue };
+        let scale = if name == "bm25f" { bm25f_scale } else { 1.0 };
+        let width = ((value / scale).clamp(0.0, 1.0) * 100.0).round();
+        let _ = write!(
+            out,
+            "<span class=\"bar {name}\" style=\"width:{width}px\" title=\"{name}: {value:.4}\"></span>"
+        );
+    }
+    out
+}
+
+/// Escape text for safe inclusion in HTML element content and attributes.
+pub(crate) fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// The kinds of the chunks in a context window and the files holding
+/// copies of them, after its line range.
+fn window_note(window: &ContextWindow) -> String {
+    let mut note = String::new();
+    if !window.kinds.is_empty() {
+        let kinds: Vec<&str> = window.kinds.iter().map(ChunkKind::as_str).collect();
+        note = format!(" &middot; {}", kinds.join(", "));
+    }
+    if let Some(files) = window.duplicated_in {
+        note.push_str(&format!(" &middot; duplicated in {files} files"));
+    }
+    note
+}
+
+/// Words highlighted as keywords in the code of languages that have them.
+const KEYWORDS: &[&str] = &[
+    "fn",
+    "let",
+    "mut",
+    "pub",
+    "use",
+    "mod",
+    "struct",
+    "enum",
+    "impl",
+    "trait",
+    "match",
+    "if",
+    "else",
+    "for",
+    "while",
+    "loop",
+    "return",
+    "def",
+    "class",
+    "import",
+    "from",
+    "func",
+    "package",
+    "var",
+    "const",
+    "function",
+    "export",
+    "interface",
+    "type",
+    "async",
+    "await",
+    "public",
+    "private",
+    "static",
+    "void",
+    "new",
+    "self",
+    "this",
+];
+
+/// Escape code like [`escape_html`], wrapping each word in `words` in
+/// `<mark>`, and keywords, comments, and strings in classed spans as
+/// `language` writes them. Words are runs of alphanumerics and
+/// underscores.
+fn highlight_code(s: &str, language: Language, words: &HashSet<String>) -> String {
+    let keywords = !matches!(
+        language,
+        Language::Markdown
+            | Language::Html
+            | Language::Json
+            | Language::Yaml
+            | Language::Toml
+            | Language::Other
+    );
+    let mut out = String::with_capacity(s.len());
+    let mut at = 0;
+    for (range, literal) in literal_ranges(s, language) {
+        push_words(&mut out, &s[at..range.start], words, keywords);
+        out.push_str(match literal {
+            Literal::Comment => "<span class=\"com\">",
+            Literal::String => "<span class=\"str\">",
+        });
+        push_words(&mut out, &s[range.clone()], words, false);
+        out.push_str("</span>");
+        at = range.end;
+    }
+    push_words(&mut out, &s[at..], words, keywords);
+    out
+}
+
+/// Append `s` to `out` escaped, wrapping each word in `words` in `<mark>`
+/// and, if `keywords`, each of [`KEYWORDS`] in a span.
+fn push_words(out: &mut String, s: &str, words: &HashSet<String>, keywords: bool) {
+    let mut last = 0;
+    for word in s.split(|c: char| !(c.is_alphanumeric() || c == '_')) {
+        let (open, close) = if words.contains(word) {
+            ("<mark>", "</mark>")
+        } else if keywords && KEYWORDS.contains(&word) {
+            ("<span class=\"kw\">", "</span>")
+        } else {
+            continue;
+        };
+        let start = word.as_ptr() as usize - s.as_ptr() as usize;
+        out.push_str(&escape_html(&s[last..start]));
+        out.push_str(open);
+        out.push_str(word);
+        out.push_str(close);
+        last = start + word.len();
+    }
+    out.push_str(&escape_html(&s[last..]));
+}
+
+/// Truncate to at most `max` bytes on a char boundary.
+fn truncate_utf8(s: &str, max: usize) -> (&str, bool) {
+    if s.len() <= max {
+        return (s, false);
+    }
+    let mut end = max;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    (&s[..end], true)
+}
+
+const CSS: &str = "\
+body{font-family:system-ui,sans-serif;margin:2rem;color:#222}\
+table{border-collapse:collapse;width:100%}\
+th,td{padding:4px 8px;border-bottom:1px solid #ddd;text-align:left;font-size:14px}\
+th{cursor:pointer;background:#f4f4f4;user-select:none}\
+.bar{display:inline-block;height:8px;margin-right:2px;vertical-align:middle}\
+.legend .bar{width:12px}\
+.bm25f{background:#4c78a8}.heuristic{background:#f58518}\
+.pagerank{background:#54a24b}.recency{background:#b279a2}\
+.note{color:#888;font-size:12px}\
+details{margin:4px 0}summary{cursor:pointer;font-family:monospace}\
+pre{background:#f8f8f8;padding:8px;overflow:auto;font-size:12px}\
+mark{background:#fff3a3}\
+.kw{color:#a626a4}.str{color:#50a14f}.com{color:#a0a1a7;font-style:italic}";
+
+const JS: &str = r#"
+document.querySelectorAll('#files th').forEach(function(th, col) {
+  th.addEventListener('click', function() {
+    var body = th.closest('table').tBodies[0];
+    var asc = th.dataset.asc !== '1';
+    th.dataset.asc = asc ? '1' : '0';
+    var rows = Array.prototype.slice.call(body.rows);
+    rows.sort(function(a, b) {
+      var x = a.cells[col], y = b.cells[col];
+      var nx = parseFloat(x.dataset.v || x.textContent), ny = parseFloat(y.dataset.v || y.textContent);
+      var r = (!isNaN(nx) && !isNaN(ny)) ? nx - ny : x.textContent.localeCompare(y.textContent);
+      return asc ? r : -r;
+    });
+    rows.forEach(function(r) { body.appendChild(r); });
+  });
+});
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use topo_core::{FileRole, Language, SignalBreakdown};
+
+    fn sample_files() -> Vec<ScoredFile> {
+        vec![
+            ScoredFile {
+                path: "src/auth.rs".to_string(),
+                score: 0.9,
+                signals: SignalBreakdown {
+                    bm25f: 0.8,
+                    heuristic: 0.6,
+                    pagerank: Some(0.5),
+                    ..Default::default()
+                },
+                tokens: 120,
+                language: Language::Rust,
+                role: FileRole::Implementation,
+                preview: None,
+            },
+            ScoredFile {
+                path: "docs/<weird>.md".to_string(),
+                score: 0.4,
+                signals: SignalBreakdown::default(),
+                tokens: 30,
+                language: Language::Markdown,
+                role: FileRole::Documentation,
+                preview: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn html_is_self_contained_document() {
+        let output = HtmlWriter::new("auth", "balanced")
+            .render(&sample_files(), 10)
+            .unwrap();
+        assert!(output.starts_with("<!DOCTYPE html>"));
+        assert!(output.contains("<style>"));
+        assert!(output.contains("<script>"));
+        assert!(!output.contains("src=\"http"));
+        assert!(output.trim_end().ends_with("</html>"));
+    }
+
+    #[test]
+    fn html_has_one_row_and_section_per_file() {
+        let output = HtmlWriter::new("auth", "balanced")
+            .render(&sample_files(), 10)
+            .unwrap();
+        assert_eq!(output.matches("<tr><td>").count(), 2);
+        assert_eq!(output.matches("<details").count(), 2);
+        assert!(output.contains("2 of 10 files"));
+    }
+
+    #[test]
+    fn html_escapes_paths_and_query() {
+        let output = HtmlWriter::new("<script>alert(1)</script>", "balanced")
+            .render(&sample_files(), 10)
+            .unwrap();
+        assert!(output.contains("docs/&lt;weird&gt;.md"));
+        assert!(!output.contains("<script>alert(1)"));
+    }
+
+    #[test]
+    fn html_embeds_escaped_contents() {
+        let mut contents = HashMap::new();
+        contents.insert(
+            "src/auth.rs".to_string(),
+            "fn a() -> Vec<u8> {}".to_string(),
+        );
+        let output = HtmlWriter::new("auth", "balanced")
+            .contents(contents)
+            .render(&sample_files(), 10)
+            .unwrap();
+        assert!(output.contains("<span class=\"kw\">fn</span> a() -&gt; Vec&lt;u8&gt; {}"));
+        assert!(output.contains("(content not available)"));
+    }
+
+    #[test]
+    fn html_marks_highlighted_words() {
+        let mut contents = HashMap::new();
+        contents.insert(
+            "src/auth.rs".to_string(),
+            "fn check_auth(a: &Auth) -> authz {}".to_string(),
+        );
+        let output = HtmlWriter::new("auth", "balanced")
+            .contents(contents)
+            .highlight(["check_auth".to_string(), "Auth".to_string()].into())
+            .render(&sample_files(), 10)
+            .unwrap();
+        assert!(output.contains(
+            "<span class=\"kw\">fn</span> <mark>check_auth</mark>(a: &amp;<mark>Auth</mark>) -&gt; authz {}"
+        ));
+    }
+
+    #[test]
+    fn html_highlights_comments_and_strings_by_language() {
+        let files = sample_files();
+        let rust = "#[derive(Debug)]\nlet url = \"https://auth\"; // auth check\n";
+        let output = HtmlWriter::new("auth", "balanced")
+            .contents([(files[0].path.clone(), rust.to_string())].into())
+            .highlight(["auth".to_string()].into())
+            .render(&files[..1], 1)
+            .unwrap();
+        assert!(
+            output.contains("#[derive(Debug)]\n"),
+            "attributes aren't comments"
+        );
+        assert!(
+            output.contains("<span class=\"str\">&quot;https://<mark>auth</mark>&quot;</span>")
+        );
+        assert!(output.contains("<span class=\"com\">// <mark>auth</mark> check</span>"));
+
+        let css = ScoredFile {
+            path: "site.css".to_string(),
+            language: Language::Css,
+            ..files[0].clone()
+        };
+        let output = HtmlWriter::new("auth", "balanced")
+            .contents([(css.path.clone(), "a { color: #fff; }".to_string())].into())
+            .render(&[css], 1)
+            .unwrap();
+        assert!(output.contains("a { color: #fff; }"));
+    }
+
+    #[test]
+    fn html_embeds_context_windows_over_contents() {
+        let files = sample_files();
+        let window = ContextWindow {
+            start_line: 10,
+            end_line: 12,
+            text: "let token = auth();".to_string(),
+            kinds: vec![ChunkKind::Function, ChunkKind::Method],
+            duplicated_in: Some(2),
+        };
+        let output = HtmlWriter::new("auth", "fast")
+            .contents([(files[0].path.clone(), "whole file".to_string())].into())
+            .context([(files[0].path.clone(), vec![window])].into())
+            .render(&files, 2)
+            .unwrap();
+        assert!(output.contains(
+            "lines 10&ndash;12 &middot; function, method &middot; duplicated in 2 files"
+        ));
+        assert!(output.contains("<span class=\"kw\">let</span> token = auth();"));
+        assert!(!output.contains("whole file"));
+    }
+
+    #[test]
+    fn html_includes_environment_when_set() {
+        let env = Environment {
+            version: "0.1.0".to_string(),
+            preset: "balanced".to_string(),
+            ..Default::default()
+        };
+        let output = HtmlWriter::new("auth", "balanced")
+            .environment(env)
+            .render(&sample_files(), 10)
+            .unwrap();
+        assert!(output.contains("id=\"environment\">topo 0.1.0 preset=balanced"));
+
+        let bare = HtmlWriter::new("auth", "balanced")
+            .render(&sample_files(), 10)
+            .unwrap();
+        assert!(!bare.contains("id=\"environment\""));
+    }
+
+    #[test]
+    fn html_signal_bars_only_for_present_signals() {
+        let files = sample_files();
+        let bars = signal_bars(&files[0], 0.9);
+        assert!(bars.contains("pagerank"));
+        assert!(!bars.contains("recency"));
+    }
+
+    #[test]
+    fn truncate_utf8_respects_char_boundaries() {
+        let (s, truncated) = truncate_utf8("héllo", 2);
+        assert_eq!(s, "h");
+        assert!(truncated);
+        assert_eq!(truncate_utf8("abc", 10), ("abc", false));
+    }
+}
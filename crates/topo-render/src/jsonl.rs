@@ -1,23 +1,37 @@
+use crate::environment::{Environment, EnvironmentHeader};
+use crate::{pinned_first, render_error};
 use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
-use topo_core::ScoredFile;
+use topo_core::{ChunkKind, ContextWindow, Ownership, ScoredFile, Tombstone, TopoError};
 
-/// Writes scored files in JSONL v0.3 format.
+/// Writes scored files in JSONL v0.4 format.
 pub struct JsonlWriter {
     query: String,
     preset: String,
     max_bytes: Option<u64>,
     min_score: f64,
+    environment: Option<Environment>,
+    recently_deleted: Vec<Tombstone>,
+    ownership: HashMap<String, Ownership>,
+    matched_lines: HashMap<String, Vec<u32>>,
+    context: HashMap<String, Vec<ContextWindow>>,
+    orientation: HashSet<String>,
+    tree: Option<String>,
 }
 
 #[derive(Serialize)]
 #[serde(rename_all = "PascalCase")]
-struct Header {
+struct Header<'a> {
     version: String,
     query: String,
     preset: String,
     budget: Budget,
     min_score: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    environment: Option<EnvironmentHeader<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tree: Option<&'a str>,
 }
 
 #[derive(Serialize)]
@@ -29,12 +43,48 @@ struct Budget {
 
 #[derive(Serialize)]
 #[serde(rename_all = "PascalCase")]
-struct FileEntry {
+struct FileEntry<'a> {
     path: String,
     score: f64,
     tokens: u64,
     language: String,
     role: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    preview: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    owner: Option<OwnerEntry<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    matched_lines: Option<&'a [u32]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    context: Option<Vec<WindowEntry<'a>>>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    orientation: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pinned: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    raw_score: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duplicate_of: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct OwnerEntry<'a> {
+    author: &'a str,
+    share: f64,
+    recent_share: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct WindowEntry<'a> {
+    start_line: u32,
+    end_line: u32,
+    text: &'a str,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    kinds: &'a [ChunkKind],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duplicated_in: Option<u32>,
 }
 
 #[derive(Serialize)]
@@ -43,6 +93,15 @@ struct Footer {
     total_files: usize,
     total_tokens: u64,
     scanned_files: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    recently_deleted: Vec<DeletedEntry>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct DeletedEntry {
+    path: String,
+    deleted_at: u64,
 }
 
 impl JsonlWriter {
@@ -52,6 +111,13 @@ impl JsonlWriter {
             preset: preset.to_string(),
             max_bytes: None,
             min_score: 0.0,
+            environment: None,
+            recently_deleted: Vec::new(),
+            ownership: HashMap::new(),
+            matched_lines: HashMap::new(),
+            context: HashMap::new(),
+            orientation: HashSet::new(),
+            tree: None,
         }
     }
 
@@ -65,44 +131,113 @@ impl JsonlWriter {
         self
     }
 
-    /// Render scored files as JSONL v0.3 string.
-    pub fn render(&self, files: &[ScoredFile], scanned_count: usize) -> anyhow::Result<String> {
+    /// Record the producing tool state in the header.
+    pub fn environment(mut self, environment: Environment) -> Self {
+        self.environment = Some(environment);
+        self
+    }
+
+    /// List recently deleted files in the footer.
+    pub fn recently_deleted(mut self, tombstones: Vec<Tombstone>) -> Self {
+        self.recently_deleted = tombstones;
+        self
+    }
+
+    /// Annotate entries with blame ownership, keyed by path.
+    pub fn ownership(mut self, ownership: HashMap<String, Ownership>) -> Self {
+        self.ownership = ownership;
+        self
+    }
+
+    /// List the lines matching the query in each entry, keyed by path.
+    pub fn matched_lines(mut self, matched_lines: HashMap<String, Vec<u32>>) -> Self {
+        self.matched_lines = matched_lines;
+        self
+    }
+
+    /// Include the lines around query matches in each entry, keyed by path.
+    pub fn context(mut self, context: HashMap<String, Vec<ContextWindow>>) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Flag entries picked to orient the reader rather than by rank.
+    pub fn orientation(mut self, paths: HashSet<String>) -> Self {
+        self.orientation = paths;
+        self
+    }
+
+    /// Put a directory outline of the repository in the header.
+    pub fn tree(mut self, tree: Option<String>) -> Self {
+        self.tree = tree;
+        self
+    }
+
+    /// Render scored files as JSONL v0.4 string.
+    pub fn render(&self, files: &[ScoredFile], scanned_count: usize) -> Result<String, TopoError> {
         let mut buf = Vec::new();
         self.write_to(&mut buf, files, scanned_count)?;
-        Ok(String::from_utf8(buf)?)
+        String::from_utf8(buf).map_err(|e| render_error("output is not valid UTF-8", e))
     }
 
-    /// Write JSONL v0.3 output to a writer.
+    /// Write JSONL v0.4 output to a writer.
     pub fn write_to(
         &self,
         writer: &mut dyn Write,
         files: &[ScoredFile],
         scanned_count: usize,
-    ) -> anyhow::Result<()> {
+    ) -> Result<(), TopoError> {
         // Header
         let header = Header {
-            version: "0.3".to_string(),
+            version: "0.4".to_string(),
             query: self.query.clone(),
             preset: self.preset.clone(),
             budget: Budget {
                 max_bytes: self.max_bytes,
             },
             min_score: self.min_score,
+            environment: self.environment.as_ref().map(Environment::header),
+            tree: self.tree.as_deref(),
         };
-        serde_json::to_writer(&mut *writer, &header)?;
+        serde_json::to_writer(&mut *writer, &header)
+            .map_err(|e| render_error("failed to serialize header", e))?;
         writeln!(writer)?;
 
         // File entries
         let mut total_tokens = 0u64;
-        for file in files {
+        for file in pinned_first(files).iter() {
             let entry = FileEntry {
                 path: file.path.clone(),
                 score: file.score,
                 tokens: file.tokens,
                 language: file.language.as_str().to_string(),
                 role: file.role.as_str().to_string(),
+                preview: file.preview.clone(),
+                owner: self.ownership.get(&file.path).map(|o| OwnerEntry {
+                    author: &o.top_author,
+                    share: o.top_share,
+                    recent_share: o.recent_share,
+                }),
+                matched_lines: self.matched_lines.get(&file.path).map(Vec::as_slice),
+                context: self.context.get(&file.path).map(|windows| {
+                    windows
+                        .iter()
+                        .map(|w| WindowEntry {
+                            start_line: w.start_line,
+                            end_line: w.end_line,
+                            text: &w.text,
+                            kinds: &w.kinds,
+                            duplicated_in: w.duplicated_in,
+                        })
+                        .collect()
+                }),
+                orientation: self.orientation.contains(&file.path),
+                pinned: file.signals.pinned,
+                raw_score: file.signals.raw,
+                duplicate_of: file.signals.duplicate_of.as_deref(),
             };
-            serde_json::to_writer(&mut *writer, &entry)?;
+            serde_json::to_writer(&mut *writer, &entry)
+                .map_err(|e| render_error("failed to serialize entry", e))?;
             writeln!(writer)?;
             total_tokens += file.tokens;
         }
@@ -112,8 +247,17 @@ impl JsonlWriter {
             total_files: files.len(),
             total_tokens,
             scanned_files: scanned_count,
+            recently_deleted: self
+                .recently_deleted
+                .iter()
+                .map(|t| DeletedEntry {
+                    path: t.path.clone(),
+                    deleted_at: t.deleted_at,
+                })
+                .collect(),
         };
-        serde_json::to_writer(&mut *writer, &footer)?;
+        serde_json::to_writer(&mut *writer, &footer)
+            .map_err(|e| render_error("failed to serialize footer", e))?;
         writeln!(writer)?;
 
         Ok(())
@@ -1,15 +1,46 @@
-//! JSONL v0.3, JSON, compact, and human-readable output rendering.
+//! JSONL v0.4, JSON, compact, HTML, SARIF, and human-readable output rendering.
 
 mod compact;
+mod environment;
+mod html;
 mod jsonl;
+mod sarif;
 
 pub use compact::CompactWriter;
+pub use environment::Environment;
+pub use html::HtmlWriter;
 pub use jsonl::JsonlWriter;
+pub use sarif::SarifWriter;
+
+use std::borrow::Cow;
+use topo_core::{ScoredFile, TopoError};
+
+/// `files` with the pinned ones (see [`topo_core::Pins`]) first, both
+/// groups keeping their order. Every writer lists files in this order.
+pub fn pinned_first(files: &[ScoredFile]) -> Cow<'_, [ScoredFile]> {
+    if files.is_sorted_by_key(|f| !f.signals.pinned) {
+        return Cow::Borrowed(files);
+    }
+    let mut ordered = files.to_vec();
+    ordered.sort_by_key(|f| !f.signals.pinned);
+    Cow::Owned(ordered)
+}
+
+/// Wrap a serialization failure as a render error.
+pub(crate) fn render_error(
+    message: &str,
+    source: impl std::error::Error + Send + Sync + 'static,
+) -> TopoError {
+    TopoError::Render {
+        message: message.to_string(),
+        source: Some(Box::new(source)),
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use topo_core::{FileRole, Language, ScoredFile, SignalBreakdown};
+    use topo_core::{ChunkKind, FileRole, Language, ScoredFile, SignalBreakdown};
 
     fn sample_files() -> Vec<ScoredFile> {
         vec![
@@ -24,6 +55,7 @@ mod tests {
                 tokens: 1200,
                 language: Language::Rust,
                 role: FileRole::Implementation,
+                preview: None,
             },
             ScoredFile {
                 path: "src/auth/handler.rs".to_string(),
@@ -36,10 +68,28 @@ mod tests {
                 tokens: 800,
                 language: Language::Rust,
                 role: FileRole::Implementation,
+                preview: None,
             },
         ]
     }
 
+    #[test]
+    fn pinned_files_render_first() {
+        let mut files = sample_files();
+        files[1].signals.pinned = true;
+        let output = JsonlWriter::new("auth", "balanced")
+            .render(&files, 2)
+            .unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[1].contains("src/auth/handler.rs"));
+        assert!(lines[1].contains("\"Pinned\":true"));
+        assert!(!lines[2].contains("Pinned"));
+
+        let compact = CompactWriter::new().render(&files);
+        assert!(compact.starts_with("src/auth/handler.rs (impl, 800tok, 0.72, pinned)\n"));
+        assert!(matches!(pinned_first(&sample_files()), Cow::Borrowed(_)));
+    }
+
     #[test]
     fn jsonl_output_has_three_lines() {
         let files = sample_files();
@@ -62,7 +112,7 @@ mod tests {
 
         let first_line = output.lines().next().unwrap();
         let header: serde_json::Value = serde_json::from_str(first_line).unwrap();
-        assert_eq!(header["Version"], "0.3");
+        assert_eq!(header["Version"], "0.4");
     }
 
     #[test]
@@ -94,6 +144,125 @@ mod tests {
         assert!(file_entry["Role"].is_string());
     }
 
+    #[test]
+    fn jsonl_file_entries_include_preview_when_set() {
+        let mut files = sample_files();
+        files[0].preview = Some("pub fn auth_middleware(req: Request)".to_string());
+        let output = JsonlWriter::new("test", "balanced")
+            .render(&files, 100)
+            .unwrap();
+
+        let lines: Vec<&str> = output.trim().lines().collect();
+        let first: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+        let second: serde_json::Value = serde_json::from_str(lines[2]).unwrap();
+        assert_eq!(first["Preview"], "pub fn auth_middleware(req: Request)");
+        assert!(second.get("Preview").is_none());
+    }
+
+    #[test]
+    fn jsonl_file_entries_include_owner_when_known() {
+        let files = sample_files();
+        let owner = topo_core::Ownership {
+            top_author: "dev@example.com".to_string(),
+            top_share: 0.8,
+            own_share: 0.0,
+            recent_share: 0.25,
+        };
+        let output = JsonlWriter::new("test", "deep")
+            .ownership([(files[0].path.clone(), owner)].into())
+            .render(&files, 100)
+            .unwrap();
+
+        let lines: Vec<&str> = output.trim().lines().collect();
+        let first: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+        let second: serde_json::Value = serde_json::from_str(lines[2]).unwrap();
+        assert_eq!(first["Owner"]["Author"], "dev@example.com");
+        assert_eq!(first["Owner"]["Share"], 0.8);
+        assert_eq!(first["Owner"]["RecentShare"], 0.25);
+        assert!(second.get("Owner").is_none());
+    }
+
+    #[test]
+    fn jsonl_file_entries_include_matched_lines_when_set() {
+        let files = sample_files();
+        let output = JsonlWriter::new("test", "balanced")
+            .matched_lines([(files[0].path.clone(), vec![3, 17])].into())
+            .render(&files, 100)
+            .unwrap();
+
+        let lines: Vec<&str> = output.trim().lines().collect();
+        let first: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+        let second: serde_json::Value = serde_json::from_str(lines[2]).unwrap();
+        assert_eq!(first["MatchedLines"], serde_json::json!([3, 17]));
+        assert!(second.get("MatchedLines").is_none());
+    }
+
+    #[test]
+    fn jsonl_file_entries_include_context_when_set() {
+        let files = sample_files();
+        let window = topo_core::ContextWindow {
+            start_line: 4,
+            end_line: 6,
+            text: "a\nb\nc".to_string(),
+            kinds: Vec::new(),
+            duplicated_in: None,
+        };
+        let output = JsonlWriter::new("test", "balanced")
+            .context([(files[1].path.clone(), vec![window])].into())
+            .render(&files, 100)
+            .unwrap();
+
+        let lines: Vec<&str> = output.trim().lines().collect();
+        let first: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+        let second: serde_json::Value = serde_json::from_str(lines[2]).unwrap();
+        assert!(first.get("Context").is_none());
+        assert_eq!(
+            second["Context"],
+            serde_json::json!([{"StartLine": 4, "EndLine": 6, "Text": "a\nb\nc"}])
+        );
+    }
+
+    #[test]
+    fn jsonl_context_windows_carry_chunk_kinds() {
+        let files = sample_files();
+        let window = topo_core::ContextWindow {
+            start_line: 1,
+            end_line: 9,
+            text: "fn a() {}".to_string(),
+            kinds: vec![ChunkKind::Function, ChunkKind::TestCase],
+            duplicated_in: Some(3),
+        };
+        let output = JsonlWriter::new("test", "balanced")
+            .context([(files[0].path.clone(), vec![window])].into())
+            .render(&files, 100)
+            .unwrap();
+        let line = output.trim().lines().nth(1).unwrap();
+        let entry: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert_eq!(
+            entry["Context"][0]["Kinds"],
+            serde_json::json!(["function", "test"])
+        );
+        assert_eq!(entry["Context"][0]["DuplicatedIn"], 3);
+    }
+
+    #[test]
+    fn jsonl_marks_orientation_material() {
+        let files = sample_files();
+        let output = JsonlWriter::new("test", "balanced")
+            .orientation([files[1].path.clone()].into())
+            .tree(Some("./ (2 files)".to_string()))
+            .render(&files, 100)
+            .unwrap();
+
+        let lines: Vec<&str> = output.trim().lines().collect();
+        let header: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        let first: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+        let second: serde_json::Value = serde_json::from_str(lines[2]).unwrap();
+        assert_eq!(header["Tree"], "./ (2 files)");
+        assert!(first.get("Orientation").is_none());
+        assert_eq!(second["Orientation"], true);
+    }
+
     #[test]
     fn jsonl_footer_has_totals() {
         let files = sample_files();
@@ -142,6 +311,62 @@ mod tests {
         assert_eq!(header["Budget"]["MaxBytes"], 50_000);
     }
 
+    #[test]
+    fn jsonl_environment_in_header() {
+        let env = Environment {
+            version: "0.1.0".to_string(),
+            index_version: Some(2),
+            fingerprint: "abc".to_string(),
+            preset: "deep".to_string(),
+            tokenizer: "bytes/4".to_string(),
+            config_hash: "default".to_string(),
+            degraded: Vec::new(),
+        };
+        let output = JsonlWriter::new("test", "deep")
+            .environment(env)
+            .render(&[], 0)
+            .unwrap();
+
+        let first_line = output.lines().next().unwrap();
+        let header: serde_json::Value = serde_json::from_str(first_line).unwrap();
+        assert_eq!(header["Environment"]["Version"], "0.1.0");
+        assert_eq!(header["Environment"]["IndexVersion"], 2);
+        assert_eq!(header["Environment"]["Fingerprint"], "abc");
+        assert_eq!(header["Environment"]["Tokenizer"], "bytes/4");
+        assert_eq!(header["Environment"]["ConfigHash"], "default");
+    }
+
+    #[test]
+    fn jsonl_header_omits_environment_when_unset() {
+        let output = JsonlWriter::new("test", "deep").render(&[], 0).unwrap();
+        let header: serde_json::Value =
+            serde_json::from_str(output.lines().next().unwrap()).unwrap();
+        assert!(header.get("Environment").is_none());
+    }
+
+    #[test]
+    fn jsonl_footer_lists_recently_deleted() {
+        let output = JsonlWriter::new("test", "balanced")
+            .recently_deleted(vec![topo_core::Tombstone {
+                path: "src/old_auth.rs".to_string(),
+                deleted_at: 1_700_000_000,
+            }])
+            .render(&[], 0)
+            .unwrap();
+        let footer: serde_json::Value =
+            serde_json::from_str(output.trim().lines().last().unwrap()).unwrap();
+        assert_eq!(footer["RecentlyDeleted"][0]["Path"], "src/old_auth.rs");
+        assert_eq!(footer["RecentlyDeleted"][0]["DeletedAt"], 1_700_000_000);
+    }
+
+    #[test]
+    fn jsonl_footer_omits_recently_deleted_when_empty() {
+        let output = JsonlWriter::new("test", "balanced").render(&[], 0).unwrap();
+        let footer: serde_json::Value =
+            serde_json::from_str(output.trim().lines().last().unwrap()).unwrap();
+        assert!(footer.get("RecentlyDeleted").is_none());
+    }
+
     #[test]
     fn jsonl_preset_in_header() {
         let output = JsonlWriter::new("test", "deep").render(&[], 0).unwrap();
@@ -0,0 +1,285 @@
+use crate::environment::Environment;
+use crate::render_error;
+use serde::Serialize;
+use std::io::Write;
+use topo_core::{CONFIG_FILE, PolicyRule, PolicyViolation, Severity, TopoError};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+
+/// Writes policy violations as a SARIF 2.1.0 log.
+///
+/// Every result carries a location so code-scanning dashboards accept it:
+/// per-file violations point at the offending file, selection-wide ones
+/// (budgets) point at the policy file.
+pub struct SarifWriter {
+    tool_version: String,
+    environment: Option<Environment>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Log<'a> {
+    #[serde(rename = "$schema")]
+    schema: &'a str,
+    version: &'a str,
+    runs: Vec<Run<'a>>,
+}
+
+#[derive(Serialize)]
+struct Run<'a> {
+    tool: Tool<'a>,
+    results: Vec<SarifResult<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    properties: Option<RunProperties<'a>>,
+}
+
+/// SARIF property bag carrying the producing tool state.
+#[derive(Serialize)]
+struct RunProperties<'a> {
+    topo: &'a Environment,
+}
+
+#[derive(Serialize)]
+struct Tool<'a> {
+    driver: Driver<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Driver<'a> {
+    name: &'a str,
+    version: &'a str,
+    information_uri: &'a str,
+    rules: Vec<Rule<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Rule<'a> {
+    id: &'a str,
+    short_description: Text<'a>,
+    default_configuration: Configuration<'a>,
+}
+
+#[derive(Serialize)]
+struct Configuration<'a> {
+    level: &'a str,
+}
+
+#[derive(Serialize)]
+struct Text<'a> {
+    text: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult<'a> {
+    rule_id: &'a str,
+    rule_index: usize,
+    level: &'a str,
+    message: Text<'a>,
+    locations: Vec<Location<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location<'a> {
+    physical_location: PhysicalLocation<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation<'a> {
+    artifact_location: ArtifactLocation<'a>,
+}
+
+#[derive(Serialize)]
+struct ArtifactLocation<'a> {
+    uri: &'a str,
+}
+
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+    }
+}
+
+impl SarifWriter {
+    pub fn new(tool_version: &str) -> Self {
+        Self {
+            tool_version: tool_version.to_string(),
+            environment: None,
+        }
+    }
+
+    /// Record the producing tool state in the run's property bag.
+    pub fn environment(mut self, environment: Environment) -> Self {
+        self.environment = Some(environment);
+        self
+    }
+
+    /// Render violations as a pretty-printed SARIF document.
+    pub fn render(&self, violations: &[PolicyViolation]) -> Result<String, TopoError> {
+        let mut buf = Vec::new();
+        self.write_to(&mut buf, violations)?;
+        String::from_utf8(buf).map_err(|e| render_error("output is not valid UTF-8", e))
+    }
+
+    /// Write the SARIF document to a writer.
+    pub fn write_to(
+        &self,
+        writer: &mut dyn Write,
+        violations: &[PolicyViolation],
+    ) -> Result<(), TopoError> {
+        let rules = PolicyRule::ALL
+            .iter()
+            .map(|rule| Rule {
+                id: rule.id(),
+                short_description: Text {
+                    text: rule.description(),
+                },
+                default_configuration: Configuration {
+                    level: level(rule.severity()),
+                },
+            })
+            .collect();
+
+        let results = violations
+            .iter()
+            .map(|v| SarifResult {
+                rule_id: v.rule.id(),
+                rule_index: PolicyRule::ALL
+                    .iter()
+                    .position(|r| *r == v.rule)
+                    .unwrap_or_default(),
+                level: level(v.rule.severity()),
+                message: Text { text: &v.message },
+                locations: vec![Location {
+                    physical_location: PhysicalLocation {
+                        artifact_location: ArtifactLocation {
+                            uri: v.path.as_deref().unwrap_or(CONFIG_FILE),
+                        },
+                    },
+                }],
+            })
+            .collect();
+
+        let log = Log {
+            schema: SARIF_SCHEMA,
+            version: SARIF_VERSION,
+            runs: vec![Run {
+                tool: Tool {
+                    driver: Driver {
+                        name: "topo",
+                        version: &self.tool_version,
+                        information_uri: "https://github.com/demwunz/topo",
+                        rules,
+                    },
+                },
+                results,
+                properties: self.environment.as_ref().map(|topo| RunProperties { topo }),
+            }],
+        };
+
+        serde_json::to_writer_pretty(&mut *writer, &log)
+            .map_err(|e| render_error("failed to serialize log", e))?;
+        writeln!(writer)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn violations() -> Vec<PolicyViolation> {
+        vec![
+            PolicyViolation {
+                rule: PolicyRule::SecretFile,
+                message: ".env matches a secret file pattern".to_string(),
+                path: Some(".env".to_string()),
+            },
+            PolicyViolation {
+                rule: PolicyRule::TokenBudget,
+                message: "selection has 200 tokens (limit 100)".to_string(),
+                path: None,
+            },
+        ]
+    }
+
+    fn parse(output: &str) -> serde_json::Value {
+        serde_json::from_str(output).unwrap()
+    }
+
+    #[test]
+    fn sarif_has_schema_and_version() {
+        let log = parse(&SarifWriter::new("0.1.0").render(&[]).unwrap());
+        assert_eq!(log["version"], "2.1.0");
+        assert!(log["$schema"].as_str().unwrap().contains("sarif-2.1.0"));
+        assert_eq!(log["runs"][0]["tool"]["driver"]["name"], "topo");
+        assert_eq!(log["runs"][0]["tool"]["driver"]["version"], "0.1.0");
+    }
+
+    #[test]
+    fn sarif_run_properties_carry_environment() {
+        let bare = parse(&SarifWriter::new("0.1.0").render(&[]).unwrap());
+        assert!(bare["runs"][0].get("properties").is_none());
+
+        let env = Environment {
+            fingerprint: "abc".to_string(),
+            ..Default::default()
+        };
+        let log = parse(
+            &SarifWriter::new("0.1.0")
+                .environment(env)
+                .render(&[])
+                .unwrap(),
+        );
+        assert_eq!(log["runs"][0]["properties"]["topo"]["fingerprint"], "abc");
+    }
+
+    #[test]
+    fn sarif_declares_all_rules() {
+        let log = parse(&SarifWriter::new("0.1.0").render(&[]).unwrap());
+        let rules = log["runs"][0]["tool"]["driver"]["rules"]
+            .as_array()
+            .unwrap();
+        assert_eq!(rules.len(), PolicyRule::ALL.len());
+        assert_eq!(rules[0]["id"], PolicyRule::ALL[0].id());
+    }
+
+    #[test]
+    fn sarif_empty_results_for_clean_selection() {
+        let log = parse(&SarifWriter::new("0.1.0").render(&[]).unwrap());
+        assert_eq!(log["runs"][0]["results"].as_array().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn sarif_results_reference_rules_and_files() {
+        let log = parse(&SarifWriter::new("0.1.0").render(&violations()).unwrap());
+        let results = log["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+
+        assert_eq!(results[0]["ruleId"], "topo/secret-file");
+        assert_eq!(results[0]["level"], "error");
+        let rule_index = results[0]["ruleIndex"].as_u64().unwrap() as usize;
+        assert_eq!(PolicyRule::ALL[rule_index], PolicyRule::SecretFile);
+        assert_eq!(
+            results[0]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            ".env"
+        );
+    }
+
+    #[test]
+    fn sarif_selection_wide_results_point_at_config() {
+        let log = parse(&SarifWriter::new("0.1.0").render(&violations()).unwrap());
+        let result = &log["runs"][0]["results"][1];
+        assert_eq!(result["ruleId"], "topo/token-budget");
+        assert_eq!(
+            result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            CONFIG_FILE
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::fs;
+use std::path::Path;
+use topo_core::FileInfo;
+
+/// Attributes assigned by `.gitattributes` files.
+///
+/// Follows git's precedence: files in deeper directories override those
+/// above them, and later lines override earlier ones.
+#[derive(Debug, Default)]
+pub struct GitAttributes {
+    /// In precedence order, lowest first.
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug)]
+struct Rule {
+    /// Directory of the `.gitattributes` file, `/`-terminated, empty at
+    /// the root.
+    dir: String,
+    pattern: Gitignore,
+    /// Attribute name and value; `None` unspecifies it (`!attr`).
+    attrs: Vec<(String, Option<String>)>,
+}
+
+impl GitAttributes {
+    /// Read every `.gitattributes` among the scanned `files`.
+    pub fn load(root: &Path, files: &[FileInfo]) -> Self {
+        let mut sources: Vec<&str> = files
+            .iter()
+            .map(|f| f.path.as_str())
+            .filter(|path| path.rsplit('/').next() == Some(".gitattributes"))
+            .collect();
+        sources.sort_by_key(|path| (path.matches('/').count(), *path));
+
+        let mut attributes = Self::default();
+        for path in sources {
+            if let Ok(contents) = fs::read_to_string(root.join(path)) {
+                let dir = &path[..path.len() - ".gitattributes".len()];
+                attributes.add(dir, &contents);
+            }
+        }
+        attributes
+    }
+
+    /// Add the rules of a `.gitattributes` file in `dir` (`/`-terminated,
+    /// empty for the root).
+    pub fn add(&mut self, dir: &str, contents: &str) {
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let Some(glob) = fields.next() else {
+                continue;
+            };
+            // Negative patterns are forbidden in .gitattributes
+            if glob.starts_with('!') {
+                continue;
+            }
+            let mut builder = GitignoreBuilder::new("");
+            let Ok(pattern) = builder.add_line(None, glob).and_then(|b| b.build()) else {
+                continue;
+            };
+            let attrs = fields.map(parse_attr).collect();
+            self.rules.push(Rule {
+                dir: dir.to_string(),
+                pattern,
+                attrs,
+            });
+        }
+    }
+
+    /// Value of `attr` for `path`: `"true"` when set, `"false"` when unset
+    /// (`-attr`), the value for `attr=value`, or `None` when unspecified.
+    pub fn get(&self, path: &str, attr: &str) -> Option<&str> {
+        let mut value = None;
+        for rule in &self.rules {
+            let Some(relative) = path.strip_prefix(rule.dir.as_str()) else {
+                continue;
+            };
+            let Some((_, assigned)) = rule.attrs.iter().rev().find(|(name, _)| name == attr) else {
+                continue;
+            };
+            if rule.pattern.matched(relative, false).is_ignore() {
+                value = assigned.as_deref();
+            }
+        }
+        value
+    }
+
+    /// Whether `attr` is explicitly true or false for `path`.
+    pub fn flag(&self, path: &str, attr: &str) -> Option<bool> {
+        match self.get(path, attr)? {
+            "true" => Some(true),
+            "false" => Some(false),
+            _ => None,
+        }
+    }
+}
+
+fn parse_attr(field: &str) -> (String, Option<String>) {
+    if let Some(name) = field.strip_prefix('-') {
+        (name.to_string(), Some("false".to_string()))
+    } else if let Some(name) = field.strip_prefix('!') {
+        (name.to_string(), None)
+    } else if let Some((name, value)) = field.split_once('=') {
+        (name.to_string(), Some(value.to_string()))
+    } else {
+        (field.to_string(), Some("true".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_set_unset_and_values() {
+        let mut attributes = GitAttributes::default();
+        attributes.add(
+            "",
+            "# comment\n*.rs linguist-language=Rust\nvendor/** linguist-vendored\n\
+             vendor/ours/** -linguist-vendored\n",
+        );
+        assert_eq!(
+            attributes.get("src/a.rs", "linguist-language"),
+            Some("Rust")
+        );
+        assert_eq!(
+            attributes.flag("vendor/lib/a.js", "linguist-vendored"),
+            Some(true)
+        );
+        assert_eq!(
+            attributes.flag("vendor/ours/a.js", "linguist-vendored"),
+            Some(false)
+        );
+        assert_eq!(attributes.get("src/a.js", "linguist-vendored"), None);
+    }
+
+    #[test]
+    fn nested_files_override_and_stay_scoped() {
+        let mut attributes = GitAttributes::default();
+        attributes.add("", "*.js linguist-generated\n");
+        attributes.add(
+            "web/",
+            "*.js !linguist-generated\nbuild/* linguist-vendored\n",
+        );
+        assert_eq!(
+            attributes.flag("lib/a.js", "linguist-generated"),
+            Some(true)
+        );
+        assert_eq!(attributes.get("web/a.js", "linguist-generated"), None);
+        assert_eq!(
+            attributes.flag("web/build/out.js", "linguist-vendored"),
+            Some(true)
+        );
+        // Patterns with a slash are relative to their file's directory
+        assert_eq!(attributes.get("build/out.js", "linguist-vendored"), None);
+    }
+}
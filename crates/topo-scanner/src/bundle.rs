@@ -1,29 +1,162 @@
 use crate::fingerprint;
+use crate::hash::HashCache;
+use crate::packages;
 use crate::scanner::Scanner;
 use std::path::Path;
 use std::time::SystemTime;
-use topo_core::Bundle;
+use topo_core::{Bundle, Config, IgnoreRules, LanguageDetector, RoleClassifier, TopoError};
 
 /// Orchestrates scan -> hash -> fingerprint -> Bundle.
 pub struct BundleBuilder<'a> {
     root: &'a Path,
+    config: Option<&'a Config>,
+    skip_nested_repos: bool,
+    hashes: Option<&'a HashCache>,
+}
+
+/// One repository's bundle within a federated scan.
+#[derive(Debug, Clone)]
+pub struct RepoBundle {
+    /// Path of the repository relative to the top-level root; empty for
+    /// the root itself.
+    pub prefix: String,
+    /// Files of this repository only, with paths relative to its own root.
+    pub bundle: Bundle,
+}
+
+impl RepoBundle {
+    /// Path of `path` (relative to this repository) from the top-level root.
+    pub fn qualify(&self, path: &str) -> String {
+        if self.prefix.is_empty() {
+            path.to_string()
+        } else {
+            format!("{}/{path}", self.prefix)
+        }
+    }
+
+    /// The repository in `repos` owning `path` (relative to the top-level
+    /// root), with the path relative to that repository.
+    pub fn locate<'r, 'p>(
+        repos: &'r [RepoBundle],
+        path: &'p str,
+    ) -> Option<(&'r RepoBundle, &'p str)> {
+        repos
+            .iter()
+            .filter_map(|repo| {
+                if repo.prefix.is_empty() {
+                    return Some((repo, path));
+                }
+                let rel = path.strip_prefix(repo.prefix.as_str())?.strip_prefix('/')?;
+                Some((repo, rel))
+            })
+            // The innermost repository wins
+            .max_by_key(|(repo, _)| repo.prefix.len())
+    }
 }
 
 impl<'a> BundleBuilder<'a> {
     pub fn new(root: &'a Path) -> Self {
-        Self { root }
+        Self {
+            root,
+            config: None,
+            skip_nested_repos: false,
+            hashes: None,
+        }
+    }
+
+    /// Use an already-loaded configuration instead of reading `.topo.toml`.
+    pub fn config(mut self, config: &'a Config) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Leave files of nested repositories out of the bundle.
+    pub fn skip_nested_repos(mut self, skip: bool) -> Self {
+        self.skip_nested_repos = skip;
+        self
+    }
+
+    /// Reuse the hashes `cache` remembers for files unchanged since, see
+    /// [`Scanner::hash_cache`].
+    pub fn hash_cache(mut self, cache: &'a HashCache) -> Self {
+        self.hashes = Some(cache);
+        self
+    }
+
+    /// Scan the root and every repository nested below it (submodules,
+    /// nested checkouts) into separate bundles, root first.
+    ///
+    /// Each bundle holds only its own repository's files, so fingerprints
+    /// and incremental index updates stay per repository. Without nested
+    /// repositories this is a single bundle equal to [`Self::build`].
+    pub fn build_federated(&self) -> Result<Vec<RepoBundle>, TopoError> {
+        let loaded;
+        let config = match self.config {
+            Some(c) => c,
+            None => {
+                loaded = Config::load(self.root)?;
+                &loaded
+            }
+        };
+
+        let mut repos = Vec::new();
+        let mut pending = vec![(String::new(), self.root.to_path_buf())];
+        while let Some((prefix, dir)) = pending.pop() {
+            let builder = BundleBuilder {
+                root: &dir,
+                config: Some(config),
+                skip_nested_repos: true,
+                hashes: self.hashes,
+            };
+            for nested in builder.scanner(config)?.nested_repos() {
+                let child = if prefix.is_empty() {
+                    nested.clone()
+                } else {
+                    format!("{prefix}/{nested}")
+                };
+                pending.push((child, dir.join(&nested)));
+            }
+            repos.push(RepoBundle {
+                prefix,
+                bundle: builder.build()?,
+            });
+        }
+        repos.sort_by(|a, b| a.prefix.cmp(&b.prefix));
+        Ok(repos)
+    }
+
+    fn scanner(&self, config: &Config) -> Result<Scanner<'a>, TopoError> {
+        let classifier = RoleClassifier::new(&config.roles)?;
+        let scanner = Scanner::new(self.root)
+            .classifier(classifier)
+            .languages(LanguageDetector::new(&config.languages))
+            .ignore(IgnoreRules::new(&config.scan.ignore)?)
+            .skip_nested_repos(self.skip_nested_repos);
+        Ok(match self.hashes {
+            Some(cache) => scanner.hash_cache(cache),
+            None => scanner,
+        })
     }
 
     /// Build a complete Bundle from the repository root.
-    pub fn build(&self) -> anyhow::Result<Bundle> {
-        let scanner = Scanner::new(self.root);
-        let files = scanner.scan()?;
+    pub fn build(&self) -> Result<Bundle, TopoError> {
+        let loaded;
+        let config = match self.config {
+            Some(c) => c,
+            None => {
+                loaded = Config::load(self.root)?;
+                &loaded
+            }
+        };
+        let mut files = self.scanner(config)?.scan()?;
+        let packages = packages::assign(self.root, &mut files);
         let fp = fingerprint::generate(&files);
 
         Ok(Bundle {
             fingerprint: fp,
             root: self.root.to_path_buf(),
             files,
+            packages,
             scanned_at: SystemTime::now(),
         })
     }
@@ -91,6 +224,92 @@ mod tests {
         assert_ne!(file.sha256, [0u8; 32]);
     }
 
+    #[test]
+    fn bundle_builder_applies_role_rules_from_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("qa")).unwrap();
+        fs::write(dir.path().join("qa/login.py"), "def check(): pass").unwrap();
+        fs::write(
+            dir.path().join(".topo.toml"),
+            "[[roles]]\npattern = \"qa/**\"\nrole = \"test\"\n",
+        )
+        .unwrap();
+
+        let bundle = BundleBuilder::new(dir.path()).build().unwrap();
+        let file = bundle
+            .files
+            .iter()
+            .find(|f| f.path == "qa/login.py")
+            .unwrap();
+        assert_eq!(file.role, topo_core::FileRole::Test);
+    }
+
+    #[test]
+    fn bundle_builder_applies_language_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("page.tpl"), "package main").unwrap();
+        fs::write(dir.path().join("BUILD.bazel"), "cc_library(name = \"x\")").unwrap();
+        fs::write(dir.path().join("main.zig"), "pub fn main() void {}").unwrap();
+        fs::write(
+            dir.path().join(".topo.toml"),
+            "[languages]\ntpl = \"go\"\nbazel = \"starlark\"\n",
+        )
+        .unwrap();
+
+        let bundle = BundleBuilder::new(dir.path()).build().unwrap();
+        let find = |p: &str| bundle.files.iter().find(|f| f.path == p).unwrap();
+
+        assert_eq!(find("page.tpl").language, topo_core::Language::Go);
+        assert_eq!(find("page.tpl").role, topo_core::FileRole::Implementation);
+        assert_eq!(find("BUILD.bazel").language, topo_core::Language::Starlark);
+        assert_eq!(find("main.zig").language, topo_core::Language::Zig);
+    }
+
+    #[test]
+    fn bundle_builder_explicit_config_overrides_file() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("contracts")).unwrap();
+        fs::write(dir.path().join("contracts/user.rs"), "struct User;").unwrap();
+
+        let config =
+            Config::parse("[[roles]]\npattern = \"contracts/**\"\nrole = \"config\"\n").unwrap();
+        let bundle = BundleBuilder::new(dir.path())
+            .config(&config)
+            .build()
+            .unwrap();
+        assert_eq!(bundle.files[0].role, topo_core::FileRole::Config);
+    }
+
+    #[test]
+    fn bundle_builder_rejects_invalid_config() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(".topo.toml"), "roles = 3").unwrap();
+        assert!(BundleBuilder::new(dir.path()).build().is_err());
+    }
+
+    #[test]
+    fn bundle_builder_detects_packages() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("packages/ui/src")).unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{"name": "monorepo", "workspaces": ["packages/*"]}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("packages/ui/package.json"),
+            r#"{"name": "@acme/ui"}"#,
+        )
+        .unwrap();
+        fs::write(dir.path().join("packages/ui/src/button.ts"), "export {}").unwrap();
+        fs::write(dir.path().join("tsconfig.json"), "{}").unwrap();
+
+        let bundle = BundleBuilder::new(dir.path()).build().unwrap();
+        assert_eq!(bundle.packages.len(), 2);
+        assert_eq!(bundle.package_files("@acme/ui").count(), 2);
+        assert_eq!(bundle.package_files("monorepo").count(), 2);
+    }
+
     #[test]
     fn bundle_builder_token_count() {
         let dir = tempfile::tempdir().unwrap();
@@ -101,4 +320,81 @@ mod tests {
         let bundle = BundleBuilder::new(dir.path()).build().unwrap();
         assert_eq!(bundle.total_tokens(), 100);
     }
+
+    fn nested_repo_tree() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+        // A submodule checkout has a `.git` file pointing at the parent's modules
+        fs::create_dir_all(root.join("vendor/lib/src")).unwrap();
+        fs::write(
+            root.join("vendor/lib/.git"),
+            "gitdir: ../../.git/modules/lib",
+        )
+        .unwrap();
+        fs::write(root.join("vendor/lib/src/lib.rs"), "pub fn lib() {}").unwrap();
+        fs::create_dir_all(root.join("vendor/lib/deps/inner/.git")).unwrap();
+        fs::write(root.join("vendor/lib/deps/inner/inner.rs"), "fn inner() {}").unwrap();
+        dir
+    }
+
+    #[test]
+    fn bundle_builder_includes_nested_repos_by_default() {
+        let dir = nested_repo_tree();
+        let bundle = BundleBuilder::new(dir.path()).build().unwrap();
+        assert_eq!(bundle.file_count(), 3);
+    }
+
+    #[test]
+    fn federated_build_splits_nested_repos() {
+        let dir = nested_repo_tree();
+        let repos = BundleBuilder::new(dir.path()).build_federated().unwrap();
+
+        let prefixes: Vec<&str> = repos.iter().map(|r| r.prefix.as_str()).collect();
+        assert_eq!(prefixes, vec!["", "vendor/lib", "vendor/lib/deps/inner"]);
+        let paths = |i: usize| -> Vec<&str> {
+            repos[i]
+                .bundle
+                .files
+                .iter()
+                .map(|f| f.path.as_str())
+                .collect()
+        };
+        assert_eq!(paths(0), vec!["src/main.rs"]);
+        assert_eq!(paths(1), vec!["src/lib.rs"]);
+        assert_eq!(paths(2), vec!["inner.rs"]);
+        assert_eq!(repos[1].bundle.root, dir.path().join("vendor/lib"));
+        // Fingerprints are per repository
+        assert_ne!(repos[0].bundle.fingerprint, repos[1].bundle.fingerprint);
+    }
+
+    #[test]
+    fn federated_paths_qualify_and_locate() {
+        let dir = nested_repo_tree();
+        let repos = BundleBuilder::new(dir.path()).build_federated().unwrap();
+
+        assert_eq!(repos[1].qualify("src/lib.rs"), "vendor/lib/src/lib.rs");
+        assert_eq!(repos[0].qualify("src/main.rs"), "src/main.rs");
+
+        let (repo, rel) = RepoBundle::locate(&repos, "vendor/lib/deps/inner/inner.rs").unwrap();
+        assert_eq!(
+            (repo.prefix.as_str(), rel),
+            ("vendor/lib/deps/inner", "inner.rs")
+        );
+        let (repo, rel) = RepoBundle::locate(&repos, "vendor/library.rs").unwrap();
+        assert_eq!((repo.prefix.as_str(), rel), ("", "vendor/library.rs"));
+    }
+
+    #[test]
+    fn federated_build_without_nested_repos_is_single_bundle() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("main.rs"), "fn main() {}").unwrap();
+        let repos = BundleBuilder::new(dir.path()).build_federated().unwrap();
+        let bundle = BundleBuilder::new(dir.path()).build().unwrap();
+        assert_eq!(repos.len(), 1);
+        assert_eq!(repos[0].prefix, "");
+        assert_eq!(repos[0].bundle.fingerprint, bundle.fingerprint);
+    }
 }
@@ -0,0 +1,297 @@
+use std::collections::{HashMap, HashSet};
+use topo_core::FileInfo;
+
+/// Minimum size ratio (smaller / larger) for a near-match rename.
+const NEAR_MATCH_MIN_SIZE_RATIO: f64 = 0.8;
+
+/// Module index filenames: `src/auth.rs` → `src/auth/mod.rs` is a rename.
+const MODULE_INDEX_STEMS: &[&str] = &["mod", "index", "__init__", "main", "lib"];
+
+/// A file that moved between two scans.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rename {
+    pub from: String,
+    pub to: String,
+    /// 1.0 for identical content, lower for near matches.
+    pub similarity: f64,
+}
+
+/// Differences between two scans of the same repository.
+///
+/// Renames are detected first by identical SHA-256, then by a near-match
+/// heuristic (same name or module-index move, same language, similar size),
+/// so moved files aren't reported as a delete plus an add.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BundleDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub modified: Vec<String>,
+    pub renamed: Vec<Rename>,
+}
+
+impl BundleDiff {
+    /// Compute the diff from `old` to `new`.
+    pub fn new(old: &[FileInfo], new: &[FileInfo]) -> Self {
+        let old_by_path: HashMap<&str, &FileInfo> =
+            old.iter().map(|f| (f.path.as_str(), f)).collect();
+        let new_paths: HashSet<&str> = new.iter().map(|f| f.path.as_str()).collect();
+
+        let mut diff = Self::default();
+        let mut added: Vec<&FileInfo> = Vec::new();
+        for file in new {
+            match old_by_path.get(file.path.as_str()) {
+                Some(prev) if prev.sha256 != file.sha256 => diff.modified.push(file.path.clone()),
+                Some(_) => {}
+                None => added.push(file),
+            }
+        }
+        let mut removed: Vec<&FileInfo> = old
+            .iter()
+            .filter(|f| !new_paths.contains(f.path.as_str()))
+            .collect();
+
+        // Pass 1: identical content
+        let mut removed_by_sha: HashMap<[u8; 32], Vec<usize>> = HashMap::new();
+        for (i, f) in removed.iter().enumerate() {
+            removed_by_sha.entry(f.sha256).or_default().push(i);
+        }
+        let mut taken = vec![false; removed.len()];
+        let mut unmatched_added = Vec::new();
+        for file in added {
+            let candidate = removed_by_sha
+                .get(&file.sha256)
+                .and_then(|idxs| idxs.iter().copied().find(|&i| !taken[i]));
+            match candidate {
+                Some(i) => {
+                    taken[i] = true;
+                    diff.renamed.push(Rename {
+                        from: removed[i].path.clone(),
+                        to: file.path.clone(),
+                        similarity: 1.0,
+                    });
+                }
+                None => unmatched_added.push(file),
+            }
+        }
+
+        // Pass 2: near matches, best candidate first
+        for file in unmatched_added {
+            let best = removed
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !taken[*i])
+                .filter_map(|(i, prev)| near_match(prev, file).map(|s| (i, s)))
+                .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+            match best {
+                Some((i, similarity)) => {
+                    taken[i] = true;
+                    diff.renamed.push(Rename {
+                        from: removed[i].path.clone(),
+                        to: file.path.clone(),
+                        similarity,
+                    });
+                }
+                None => diff.added.push(file.path.clone()),
+            }
+        }
+
+        let mut i = 0;
+        removed.retain(|_| {
+            let keep = !taken[i];
+            i += 1;
+            keep
+        });
+        diff.removed = removed.into_iter().map(|f| f.path.clone()).collect();
+
+        diff.added.sort();
+        diff.removed.sort();
+        diff.modified.sort();
+        diff.renamed.sort_by(|a, b| a.to.cmp(&b.to));
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.modified.is_empty()
+            && self.renamed.is_empty()
+    }
+
+    /// Move per-path values from renamed files' old paths to their new paths.
+    ///
+    /// Existing values at the new path are left untouched.
+    pub fn carry_over<V>(&self, values: &mut HashMap<String, V>) {
+        for rename in &self.renamed {
+            if values.contains_key(&rename.to) {
+                continue;
+            }
+            if let Some(v) = values.remove(&rename.from) {
+                values.insert(rename.to.clone(), v);
+            }
+        }
+    }
+}
+
+/// Similarity score if `new` looks like a moved-and-edited `old`.
+fn near_match(old: &FileInfo, new: &FileInfo) -> Option<f64> {
+    if old.language != new.language {
+        return None;
+    }
+    let (old_dir, old_name) = split_path(&old.path);
+    let (new_dir, new_name) = split_path(&new.path);
+
+    let same_name = old_name == new_name;
+    // `dir/auth.rs` → `dir/auth/mod.rs`, and the reverse
+    let into_module = new_dir == join_path(old_dir, stem(old_name))
+        && MODULE_INDEX_STEMS.contains(&stem(new_name));
+    let out_of_module = old_dir == join_path(new_dir, stem(new_name))
+        && MODULE_INDEX_STEMS.contains(&stem(old_name));
+    if !(same_name || into_module || out_of_module) {
+        return None;
+    }
+
+    let (small, large) = if old.size <= new.size {
+        (old.size, new.size)
+    } else {
+        (new.size, old.size)
+    };
+    let ratio = if large == 0 {
+        1.0
+    } else {
+        small as f64 / large as f64
+    };
+    (ratio >= NEAR_MATCH_MIN_SIZE_RATIO).then_some(ratio)
+}
+
+fn split_path(path: &str) -> (&str, &str) {
+    match path.rsplit_once('/') {
+        Some((dir, name)) => (dir, name),
+        None => ("", path),
+    }
+}
+
+fn stem(name: &str) -> &str {
+    name.split_once('.').map(|(s, _)| s).unwrap_or(name)
+}
+
+fn join_path(dir: &str, name: &str) -> String {
+    if dir.is_empty() {
+        name.to_string()
+    } else {
+        format!("{dir}/{name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use topo_core::{FileRole, Language};
+
+    fn file(path: &str, size: u64, content_id: u8) -> FileInfo {
+        FileInfo {
+            path: path.to_string(),
+            size,
+            language: Language::from_path(Path::new(path)),
+            role: FileRole::from_path(Path::new(path)),
+            sha256: [content_id; 32],
+            package: None,
+        }
+    }
+
+    #[test]
+    fn identical_scans_have_empty_diff() {
+        let files = vec![file("a.rs", 10, 1), file("b.rs", 20, 2)];
+        assert!(BundleDiff::new(&files, &files).is_empty());
+    }
+
+    #[test]
+    fn detects_added_removed_modified() {
+        let old = vec![file("a.rs", 10, 1), file("b.rs", 20, 2)];
+        let new = vec![file("a.rs", 12, 3), file("c.py", 30, 4)];
+        let diff = BundleDiff::new(&old, &new);
+        assert_eq!(diff.added, vec!["c.py"]);
+        assert_eq!(diff.removed, vec!["b.rs"]);
+        assert_eq!(diff.modified, vec!["a.rs"]);
+        assert!(diff.renamed.is_empty());
+    }
+
+    #[test]
+    fn exact_rename_by_sha() {
+        let old = vec![file("src/auth.rs", 100, 7)];
+        let new = vec![file("src/security/login.rs", 100, 7)];
+        let diff = BundleDiff::new(&old, &new);
+        assert!(diff.added.is_empty());
+        assert!(diff.removed.is_empty());
+        assert_eq!(
+            diff.renamed,
+            vec![Rename {
+                from: "src/auth.rs".to_string(),
+                to: "src/security/login.rs".to_string(),
+                similarity: 1.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn near_match_into_module_directory() {
+        let old = vec![file("src/auth.rs", 100, 1)];
+        let new = vec![file("src/auth/mod.rs", 95, 2)];
+        let diff = BundleDiff::new(&old, &new);
+        assert_eq!(diff.renamed.len(), 1);
+        assert_eq!(diff.renamed[0].from, "src/auth.rs");
+        assert_eq!(diff.renamed[0].to, "src/auth/mod.rs");
+        assert!((diff.renamed[0].similarity - 0.95).abs() < 1e-9);
+    }
+
+    #[test]
+    fn near_match_same_name_new_directory() {
+        let old = vec![file("lib/utils.py", 200, 1)];
+        let new = vec![file("pkg/utils.py", 190, 2)];
+        let diff = BundleDiff::new(&old, &new);
+        assert_eq!(diff.renamed.len(), 1);
+    }
+
+    #[test]
+    fn near_match_rejects_large_size_change() {
+        let old = vec![file("lib/utils.py", 200, 1)];
+        let new = vec![file("pkg/utils.py", 50, 2)];
+        let diff = BundleDiff::new(&old, &new);
+        assert!(diff.renamed.is_empty());
+        assert_eq!(diff.added, vec!["pkg/utils.py"]);
+        assert_eq!(diff.removed, vec!["lib/utils.py"]);
+    }
+
+    #[test]
+    fn near_match_requires_related_names() {
+        let old = vec![file("src/auth.rs", 100, 1)];
+        let new = vec![file("src/billing.rs", 100, 2)];
+        assert!(BundleDiff::new(&old, &new).renamed.is_empty());
+    }
+
+    #[test]
+    fn duplicate_content_pairs_each_source_once() {
+        let old = vec![file("a/x.rs", 10, 9), file("b/x.rs", 10, 9)];
+        let new = vec![file("c/x.rs", 10, 9), file("d/x.rs", 10, 9)];
+        let diff = BundleDiff::new(&old, &new);
+        assert_eq!(diff.renamed.len(), 2);
+        let from: HashSet<&str> = diff.renamed.iter().map(|r| r.from.as_str()).collect();
+        assert_eq!(from.len(), 2);
+    }
+
+    #[test]
+    fn carry_over_moves_values_to_new_paths() {
+        let old = vec![file("src/auth.rs", 100, 7), file("src/keep.rs", 10, 1)];
+        let new = vec![file("src/auth/mod.rs", 100, 7), file("src/keep.rs", 10, 1)];
+        let diff = BundleDiff::new(&old, &new);
+
+        let mut scores = HashMap::new();
+        scores.insert("src/auth.rs".to_string(), 0.8);
+        scores.insert("src/keep.rs".to_string(), 0.1);
+        diff.carry_over(&mut scores);
+
+        assert_eq!(scores.get("src/auth/mod.rs"), Some(&0.8));
+        assert!(!scores.contains_key("src/auth.rs"));
+        assert_eq!(scores.get("src/keep.rs"), Some(&0.1));
+    }
+}
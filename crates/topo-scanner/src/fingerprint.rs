@@ -34,6 +34,7 @@ mod tests {
             language: Language::Other,
             role: FileRole::Other,
             sha256: [0u8; 32],
+            package: None,
         }
     }
 
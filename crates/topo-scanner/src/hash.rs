@@ -1,10 +1,16 @@
+use crate::long_path::long_path;
 use sha2::{Digest, Sha256};
-use std::fs;
-use std::path::Path;
+use std::collections::HashMap;
+use std::fs::{self, Metadata};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, SystemTime};
+use topo_core::TopoError;
 
-/// Compute SHA-256 hash of a file's contents.
-pub fn sha256_file(path: &Path) -> anyhow::Result<[u8; 32]> {
-    let contents = fs::read(path)?;
+/// Compute SHA-256 hash of a file's contents. Reads past `MAX_PATH` on Windows.
+pub fn sha256_file(path: &Path) -> Result<[u8; 32], TopoError> {
+    let contents = fs::read(long_path(path)).map_err(|e| TopoError::io(path, e))?;
     Ok(sha256_bytes(&contents))
 }
 
@@ -14,3 +20,72 @@ pub fn sha256_bytes(data: &[u8]) -> [u8; 32] {
     hasher.update(data);
     hasher.finalize().into()
 }
+
+/// SHA-256 hashes of files remembered with their size and modification
+/// time, so that rescans of a repository only rehash the files that
+/// changed since. Shared by the scans of a long-running process, such as
+/// `topo daemon`.
+#[derive(Debug, Default)]
+pub struct HashCache {
+    hashes: Mutex<HashMap<PathBuf, Stamped>>,
+    hashed_bytes: AtomicU64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Stamped {
+    size: u64,
+    modified: SystemTime,
+    /// When the file was hashed; a file modified within [`RACY_WINDOW`] of
+    /// it may have changed again without its modification time moving.
+    hashed_at: SystemTime,
+    sha256: [u8; 32],
+}
+
+/// Granularity below which file systems may not tell two writes apart.
+const RACY_WINDOW: Duration = Duration::from_secs(2);
+
+impl HashCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// SHA-256 of the file at `path` with `metadata`, reusing the
+    /// remembered hash when its size and modification time are unchanged.
+    pub fn sha256_file(&self, path: &Path, metadata: &Metadata) -> Result<[u8; 32], TopoError> {
+        let modified = metadata.modified().ok();
+        if let Some(modified) = modified {
+            let hashes = self.hashes.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Some(stamped) = hashes.get(path)
+                && stamped.size == metadata.len()
+                && stamped.modified == modified
+                && stamped.hashed_at >= modified + RACY_WINDOW
+            {
+                return Ok(stamped.sha256);
+            }
+        }
+
+        let hashed_at = SystemTime::now();
+        let sha256 = sha256_file(path)?;
+        self.hashed_bytes
+            .fetch_add(metadata.len(), Ordering::Relaxed);
+        if let Some(modified) = modified {
+            let stamped = Stamped {
+                size: metadata.len(),
+                modified,
+                hashed_at,
+                sha256,
+            };
+            self.hashes
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(path.to_path_buf(), stamped);
+        }
+        Ok(sha256)
+    }
+
+    /// Bytes read for hashing so far, leaving out the files whose hash was
+    /// reused.
+    pub fn hashed_bytes(&self) -> u64 {
+        self.hashed_bytes.load(Ordering::Relaxed)
+    }
+}
@@ -1,11 +1,23 @@
 //! File walking with gitignore support and content hashing.
 
+mod attributes;
 mod bundle;
+mod diff;
 pub(crate) mod fingerprint;
 pub(crate) mod hash;
+mod linguist;
+mod long_path;
+mod orientation;
+mod packages;
 mod scanner;
 
-pub use bundle::BundleBuilder;
+pub use attributes::GitAttributes;
+pub use bundle::{BundleBuilder, RepoBundle};
+pub use diff::{BundleDiff, Rename};
+pub use hash::HashCache;
+pub use linguist::{LanguageBreakdown, LanguageShare, language_breakdown};
+pub use long_path::long_path;
+pub use orientation::{TREE_DEPTH, orientation_files, tree_summary};
 pub use scanner::Scanner;
 
 #[cfg(test)]
@@ -13,6 +25,7 @@ mod tests {
     use super::*;
     use std::fs;
     use std::path::Path;
+    use topo_core::IgnoreRules;
 
     fn create_test_dir() -> tempfile::TempDir {
         let dir = tempfile::tempdir().unwrap();
@@ -75,6 +88,18 @@ mod tests {
         assert!(!paths.iter().any(|p| p.ends_with(".tmp")));
     }
 
+    #[test]
+    fn scanner_leaves_out_ignore_rules() {
+        let dir = create_test_dir();
+        let rules = IgnoreRules::new(&["tests/**".to_string(), "*.md".to_string()]).unwrap();
+        let files = Scanner::new(dir.path()).ignore(rules).scan().unwrap();
+
+        let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        assert!(paths.contains(&"src/main.rs"));
+        assert!(!paths.contains(&"tests/integration.rs"));
+        assert!(!paths.contains(&"README.md"));
+    }
+
     #[test]
     fn scanner_detects_languages() {
         let dir = create_test_dir();
@@ -156,6 +181,38 @@ mod tests {
         assert_ne!(a.sha256, b.sha256);
     }
 
+    #[test]
+    fn hash_cache_rehashes_only_changed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = std::time::SystemTime::now() - std::time::Duration::from_secs(60);
+        for (name, content) in [("a.rs", "content a"), ("b.rs", "content b")] {
+            fs::write(dir.path().join(name), content).unwrap();
+            let file = fs::File::options()
+                .write(true)
+                .open(dir.path().join(name))
+                .unwrap();
+            file.set_modified(old).unwrap();
+        }
+
+        let cache = HashCache::new();
+        let scan = || Scanner::new(dir.path()).hash_cache(&cache).scan().unwrap();
+        let hashes = |files: Vec<topo_core::FileInfo>| -> Vec<[u8; 32]> {
+            files.into_iter().map(|f| f.sha256).collect()
+        };
+        assert_eq!(
+            hashes(scan()),
+            hashes(Scanner::new(dir.path()).scan().unwrap())
+        );
+        assert_eq!(cache.hashed_bytes(), 18);
+        scan();
+        assert_eq!(cache.hashed_bytes(), 18);
+
+        fs::write(dir.path().join("b.rs"), "content bb").unwrap();
+        let files = scan();
+        assert_eq!(cache.hashed_bytes(), 28);
+        assert_eq!(files[1].sha256, hash::sha256_bytes(b"content bb"));
+    }
+
     #[test]
     fn scanner_empty_directory() {
         let dir = tempfile::tempdir().unwrap();
@@ -178,6 +235,27 @@ mod tests {
         assert_ne!(hash1, hash2);
     }
 
+    #[test]
+    fn scanner_finds_files_beyond_max_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested: String = (0..12)
+            .map(|i| format!("directory_{i:02}_padding/"))
+            .collect();
+        let rel = format!("{nested}deep.rs");
+        assert!(dir.path().join(&rel).as_os_str().len() > 260);
+        fs::create_dir_all(long_path(&dir.path().join(&nested))).unwrap();
+        fs::write(long_path(&dir.path().join(&rel)), "fn deep() {}").unwrap();
+
+        let files = Scanner::new(dir.path()).scan().unwrap();
+        let deep = files.iter().find(|f| f.path == rel).unwrap();
+        assert_eq!(deep.size, "fn deep() {}".len() as u64);
+        assert_eq!(deep.sha256, hash::sha256_bytes(b"fn deep() {}"));
+        assert_eq!(
+            hash::sha256_file(&dir.path().join(&rel)).unwrap(),
+            deep.sha256
+        );
+    }
+
     #[test]
     fn scanner_nonexistent_path() {
         let scanner = Scanner::new(Path::new("/nonexistent/path/that/does/not/exist"));
@@ -0,0 +1,172 @@
+use crate::attributes::GitAttributes;
+use std::collections::HashMap;
+use topo_core::{FileInfo, FileRole, Language};
+
+/// One language's share of a [`LanguageBreakdown`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LanguageShare {
+    /// Linguist name, as GitHub shows it.
+    pub name: String,
+    pub bytes: u64,
+    /// Share of counted bytes, 0–100.
+    pub percentage: f64,
+    pub files: usize,
+}
+
+/// Bytes per language, counted the way github-linguist does.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LanguageBreakdown {
+    /// Largest first.
+    pub languages: Vec<LanguageShare>,
+    pub total_bytes: u64,
+    /// Files left out as vendored, generated, or documentation.
+    pub excluded: usize,
+}
+
+/// Break `files` down by language.
+///
+/// Vendored and generated files (role [`FileRole::Generated`]) and
+/// documentation are left out unless `.gitattributes` says otherwise
+/// through `linguist-vendored`, `linguist-generated`, or
+/// `linguist-documentation`. `linguist-language` overrides the detected
+/// language and `linguist-detectable` decides whether data and prose
+/// languages count.
+pub fn language_breakdown(files: &[FileInfo], attributes: &GitAttributes) -> LanguageBreakdown {
+    let mut counts: HashMap<String, (u64, usize)> = HashMap::new();
+    let mut excluded = 0;
+
+    for file in files {
+        if is_excluded(file, attributes) {
+            excluded += 1;
+            continue;
+        }
+        let (name, detectable) = match attributes.get(&file.path, "linguist-language") {
+            Some(name) => match Language::from_linguist_name(name) {
+                Some(language) => (
+                    language.linguist_name().to_string(),
+                    language.is_linguist_detectable(),
+                ),
+                None => (name.to_string(), true),
+            },
+            None if file.language == Language::Other => continue,
+            None => (
+                file.language.linguist_name().to_string(),
+                file.language.is_linguist_detectable(),
+            ),
+        };
+        if !attributes
+            .flag(&file.path, "linguist-detectable")
+            .unwrap_or(detectable)
+        {
+            continue;
+        }
+        let entry = counts.entry(name).or_default();
+        entry.0 += file.size;
+        entry.1 += 1;
+    }
+
+    let total_bytes: u64 = counts.values().map(|(bytes, _)| bytes).sum();
+    let mut languages: Vec<LanguageShare> = counts
+        .into_iter()
+        .map(|(name, (bytes, files))| LanguageShare {
+            name,
+            bytes,
+            percentage: if total_bytes == 0 {
+                0.0
+            } else {
+                bytes as f64 * 100.0 / total_bytes as f64
+            },
+            files,
+        })
+        .collect();
+    languages.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.name.cmp(&b.name)));
+
+    LanguageBreakdown {
+        languages,
+        total_bytes,
+        excluded,
+    }
+}
+
+fn is_excluded(file: &FileInfo, attributes: &GitAttributes) -> bool {
+    let vendored = attributes.flag(&file.path, "linguist-vendored");
+    let generated = attributes.flag(&file.path, "linguist-generated");
+    let documentation = attributes.flag(&file.path, "linguist-documentation");
+    if [vendored, generated, documentation].contains(&Some(true)) {
+        return true;
+    }
+    match file.role {
+        FileRole::Generated => vendored.is_none() && generated.is_none(),
+        FileRole::Documentation => documentation.is_none(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn file(path: &str, size: u64) -> FileInfo {
+        FileInfo {
+            path: path.to_string(),
+            size,
+            language: Language::from_path(Path::new(path)),
+            role: FileRole::from_path(Path::new(path)),
+            sha256: [0; 32],
+            package: None,
+        }
+    }
+
+    #[test]
+    fn counts_bytes_per_linguist_language() {
+        let files = [
+            file("src/main.rs", 300),
+            file("src/lib.rs", 100),
+            file("web/app.ts", 100),
+            file("config.yaml", 500),
+            file("README.md", 900),
+            file("vendor/lib.go", 1000),
+            file("data.bin", 50),
+        ];
+        let breakdown = language_breakdown(&files, &GitAttributes::default());
+        let names: Vec<&str> = breakdown
+            .languages
+            .iter()
+            .map(|l| l.name.as_str())
+            .collect();
+        assert_eq!(names, ["Rust", "TypeScript"]);
+        assert_eq!(breakdown.total_bytes, 500);
+        assert_eq!(breakdown.languages[0].files, 2);
+        assert!((breakdown.languages[0].percentage - 80.0).abs() < 1e-9);
+        assert_eq!(breakdown.excluded, 2);
+    }
+
+    #[test]
+    fn gitattributes_override_defaults() {
+        let files = [
+            file("src/main.rs", 100),
+            file("src/gen.rs", 400),
+            file("vendor/ours/lib.go", 200),
+            file("config.yaml", 50),
+            file("templates/page.tmpl", 80),
+        ];
+        let mut attributes = GitAttributes::default();
+        attributes.add(
+            "",
+            "src/gen.rs linguist-generated\nvendor/ours/** -linguist-vendored\n\
+             *.yaml linguist-detectable\n*.tmpl linguist-language=html\n",
+        );
+        let breakdown = language_breakdown(&files, &attributes);
+        let shares: Vec<(&str, u64)> = breakdown
+            .languages
+            .iter()
+            .map(|l| (l.name.as_str(), l.bytes))
+            .collect();
+        assert_eq!(
+            shares,
+            [("Go", 200), ("Rust", 100), ("HTML", 80), ("YAML", 50)]
+        );
+        assert_eq!(breakdown.excluded, 1);
+    }
+}
@@ -0,0 +1,122 @@
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+/// `path` in a form the filesystem accepts at any length.
+///
+/// On Windows, paths longer than `MAX_PATH` (260 characters) can only be
+/// opened in their extended-length form: absolute, backslash-separated, and
+/// prefixed with `\\?\` (`\\?\UNC\` for network shares). Elsewhere, and for
+/// paths that are already extended or can't be made absolute, `path` is
+/// returned as is.
+pub fn long_path(path: &Path) -> Cow<'_, Path> {
+    if !cfg!(windows) {
+        return Cow::Borrowed(path);
+    }
+    std::path::absolute(path)
+        .ok()
+        .and_then(|absolute| absolute.to_str().and_then(extended))
+        .map_or(Cow::Borrowed(path), |extended| {
+            Cow::Owned(PathBuf::from(extended))
+        })
+}
+
+/// The extended-length form of the absolute Windows path `path`, or None if
+/// it already has one, names a device, or isn't absolute.
+///
+/// Extended-length paths bypass the normalization Windows otherwise does,
+/// so forward slashes, `.`, and `..` are resolved here.
+fn extended(path: &str) -> Option<String> {
+    let path = path.replace('/', "\\");
+    if path.starts_with(r"\\?\") || path.starts_with(r"\\.\") {
+        return None;
+    }
+    let (prefix, rest) = if let Some(unc) = path.strip_prefix(r"\\") {
+        // `\\server\share` keeps both components out of `..` resolution
+        let mut parts = unc.splitn(3, '\\');
+        let server = parts.next().filter(|s| !s.is_empty())?;
+        let share = parts.next().filter(|s| !s.is_empty())?;
+        (
+            format!(r"\\?\UNC\{server}\{share}"),
+            parts.next().unwrap_or(""),
+        )
+    } else {
+        let bytes = path.as_bytes();
+        if bytes.len() < 3 || !bytes[0].is_ascii_alphabetic() || &bytes[1..3] != b":\\" {
+            return None;
+        }
+        (format!(r"\\?\{}", &path[..2]), &path[3..])
+    };
+
+    let mut components: Vec<&str> = Vec::new();
+    for component in rest.split('\\') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop();
+            }
+            name => components.push(name),
+        }
+    }
+    let mut out = prefix;
+    out.push('\\');
+    out.push_str(&components.join("\\"));
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extended_prefixes_drive_paths() {
+        assert_eq!(
+            extended(r"C:\repo\src\main.rs").as_deref(),
+            Some(r"\\?\C:\repo\src\main.rs")
+        );
+        assert_eq!(extended(r"d:\").as_deref(), Some(r"\\?\d:\"));
+        // Joined relative paths use forward slashes
+        assert_eq!(
+            extended(r"C:\repo/src/./lib/../main.rs").as_deref(),
+            Some(r"\\?\C:\repo\src\main.rs")
+        );
+        assert_eq!(extended(r"C:\..\repo").as_deref(), Some(r"\\?\C:\repo"));
+    }
+
+    #[test]
+    fn extended_rewrites_unc_shares() {
+        assert_eq!(
+            extended(r"\\server\share\repo\a.rs").as_deref(),
+            Some(r"\\?\UNC\server\share\repo\a.rs")
+        );
+        assert_eq!(
+            extended("//server/share").as_deref(),
+            Some(r"\\?\UNC\server\share\")
+        );
+        // `..` stops at the share
+        assert_eq!(
+            extended(r"\\server\share\..\..\repo").as_deref(),
+            Some(r"\\?\UNC\server\share\repo")
+        );
+        assert_eq!(extended(r"\\server"), None);
+    }
+
+    #[test]
+    fn extended_leaves_other_paths_alone() {
+        assert_eq!(extended(r"\\?\C:\repo"), None);
+        assert_eq!(extended(r"\\?\UNC\server\share"), None);
+        assert_eq!(extended(r"\\.\pipe\topo"), None);
+        assert_eq!(extended(r"repo\src"), None);
+        assert_eq!(extended(r"C:repo"), None);
+        assert_eq!(extended("/home/repo"), None);
+    }
+
+    #[test]
+    fn long_path_is_unchanged_off_windows() {
+        let path = Path::new("repo/src/main.rs");
+        if cfg!(windows) {
+            assert!(long_path(path).to_str().unwrap().starts_with(r"\\?\"));
+        } else {
+            assert_eq!(long_path(path), path);
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::BTreeMap;
+use topo_core::{display_path, is_entry_point};
+
+/// Directory levels shown by [`tree_summary`].
+pub const TREE_DEPTH: usize = 2;
+
+/// Lines kept in a [`tree_summary`]; the rest are counted in a final line.
+const MAX_TREE_LINES: usize = 60;
+
+/// Paths among `paths` that orient a reader in the repository, best
+/// first: the top-level README, then entry points like `src/main.rs` and
+/// `cmd/app/main.go`, then READMEs one level down. Shallower paths come
+/// first within each group.
+pub fn orientation_files<'a>(paths: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut ranked: Vec<(u8, usize, &str)> = paths
+        .into_iter()
+        .filter_map(|path| {
+            let depth = path.matches('/').count();
+            let name = path.rsplit('/').next().unwrap_or(path);
+            let readme = name.to_lowercase().starts_with("readme");
+            let group = match depth {
+                0 if readme => 0,
+                _ if is_entry_point(path) => 1,
+                1 if readme => 2,
+                _ => return None,
+            };
+            Some((group, depth, path))
+        })
+        .collect();
+    ranked.sort_unstable();
+    ranked.into_iter().map(|(_, _, path)| path).collect()
+}
+
+/// Outline of the directories holding `paths`, [`TREE_DEPTH`] levels deep,
+/// with the number of files below each.
+pub fn tree_summary<'a>(paths: impl IntoIterator<Item = &'a str>) -> String {
+    let mut total = 0;
+    // Keyed by components so a directory's children follow it directly
+    let mut dirs: BTreeMap<Vec<&str>, usize> = BTreeMap::new();
+    for path in paths {
+        total += 1;
+        let components: Vec<&str> = path.split('/').collect();
+        let depth = (components.len() - 1).min(TREE_DEPTH);
+        for end in 1..=depth {
+            *dirs.entry(components[..end].to_vec()).or_default() += 1;
+        }
+    }
+
+    let mut lines = vec![format!("./ ({})", files(total))];
+    for (dir, count) in &dirs {
+        let indent = "  ".repeat(dir.len());
+        lines.push(format!(
+            "{indent}{}/ ({})",
+            display_path(dir[dir.len() - 1]),
+            files(*count)
+        ));
+    }
+    if lines.len() > MAX_TREE_LINES {
+        let hidden = lines.len() - MAX_TREE_LINES;
+        lines.truncate(MAX_TREE_LINES);
+        lines.push(format!("  ... {hidden} more directories"));
+    }
+    lines.join("\n")
+}
+
+fn files(count: usize) -> String {
+    if count == 1 {
+        "1 file".to_string()
+    } else {
+        format!("{count} files")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orientation_files_rank_readme_then_entry_points() {
+        let paths = [
+            "src/auth.rs",
+            "crates/cli/src/main.rs",
+            "docs/README.md",
+            "src/lib.rs",
+            "README.md",
+            "a/b/c/d/main.go",
+            "docs/guide/README.md",
+        ];
+        assert_eq!(
+            orientation_files(paths),
+            [
+                "README.md",
+                "src/lib.rs",
+                "crates/cli/src/main.rs",
+                "docs/README.md"
+            ]
+        );
+    }
+
+    #[test]
+    fn tree_summary_counts_files_per_directory() {
+        let paths = [
+            "README.md",
+            "src-gen/x.rs",
+            "src/main.rs",
+            "src/auth/login.rs",
+            "src/auth/deep/token.rs",
+            "tests/it.rs",
+        ];
+        assert_eq!(
+            tree_summary(paths),
+            "./ (6 files)\n  src/ (3 files)\n    auth/ (2 files)\n  src-gen/ (1 file)\n  tests/ (1 file)"
+        );
+        assert_eq!(
+            tree_summary(["a\nb/x.rs"]),
+            "./ (1 file)\n  a\\u{a}b/ (1 file)"
+        );
+    }
+}
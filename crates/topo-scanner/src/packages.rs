@@ -0,0 +1,101 @@
+use std::fs;
+use std::path::Path;
+use topo_core::{FileInfo, FileRole, Package, PackageKind, PackageMap, is_package_manifest};
+
+/// Detect workspace packages from scanned manifests and tag each file with
+/// the innermost package containing it.
+///
+/// Manifests under generated/vendored directories are ignored. Returns the
+/// detected packages, innermost first.
+pub(crate) fn assign(root: &Path, files: &mut [FileInfo]) -> Vec<Package> {
+    let mut packages: Vec<Package> = files
+        .iter()
+        .filter(|f| f.role != FileRole::Generated)
+        .filter(|f| is_package_manifest(f.path.rsplit('/').next().unwrap_or(&f.path)))
+        .filter_map(|f| {
+            let contents = fs::read_to_string(root.join(&f.path)).ok()?;
+            Package::from_manifest(&f.path, &contents)
+        })
+        .collect();
+    // An Nx project name is what the workspace refers to, so it beats a
+    // package.json in the same directory.
+    packages.sort_by_key(|p| p.kind != PackageKind::Nx);
+
+    let map = PackageMap::new(packages);
+    for file in files.iter_mut() {
+        file.package = map.find(&file.path).map(|p| p.name.clone());
+    }
+    map.packages().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Scanner;
+
+    #[test]
+    fn tags_files_in_cargo_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\n",
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("crates/core/src")).unwrap();
+        fs::write(
+            root.join("crates/core/Cargo.toml"),
+            "[package]\nname = \"demo-core\"\n",
+        )
+        .unwrap();
+        fs::write(root.join("crates/core/src/lib.rs"), "pub fn x() {}").unwrap();
+        fs::write(root.join("README.md"), "# Demo").unwrap();
+
+        let mut files = Scanner::new(root).scan().unwrap();
+        let packages = assign(root, &mut files);
+
+        assert_eq!(packages.len(), 1);
+        let find = |p: &str| files.iter().find(|f| f.path == p).unwrap();
+        assert_eq!(
+            find("crates/core/src/lib.rs").package.as_deref(),
+            Some("demo-core")
+        );
+        assert_eq!(find("README.md").package, None);
+    }
+
+    #[test]
+    fn nx_project_beats_package_json_in_same_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("apps/web")).unwrap();
+        fs::write(
+            root.join("apps/web/package.json"),
+            r#"{"name": "@acme/web"}"#,
+        )
+        .unwrap();
+        fs::write(root.join("apps/web/project.json"), r#"{"name": "web"}"#).unwrap();
+        fs::write(root.join("apps/web/index.ts"), "export {}").unwrap();
+
+        let mut files = Scanner::new(root).scan().unwrap();
+        assign(root, &mut files);
+
+        let index = files
+            .iter()
+            .find(|f| f.path == "apps/web/index.ts")
+            .unwrap();
+        assert_eq!(index.package.as_deref(), Some("web"));
+    }
+
+    #[test]
+    fn ignores_vendored_manifests() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("vendor/lib")).unwrap();
+        fs::write(root.join("vendor/lib/package.json"), r#"{"name": "lib"}"#).unwrap();
+        fs::write(root.join("vendor/lib/index.js"), "module.exports = {}").unwrap();
+
+        let mut files = Scanner::new(root).scan().unwrap();
+        assert!(assign(root, &mut files).is_empty());
+        assert!(files.iter().all(|f| f.package.is_none()));
+    }
+}
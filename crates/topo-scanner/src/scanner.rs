@@ -1,16 +1,63 @@
-use crate::hash;
+use crate::hash::{self, HashCache};
+use crate::long_path::long_path;
 use ignore::WalkBuilder;
 use std::path::Path;
-use topo_core::{FileInfo, FileRole, Language};
+use std::sync::{Arc, Mutex};
+use topo_core::{FileInfo, FileRole, IgnoreRules, LanguageDetector, RoleClassifier, TopoError};
 
 /// Walks a directory tree, respecting .gitignore rules, and produces `FileInfo` entries.
 pub struct Scanner<'a> {
     root: &'a Path,
+    classifier: RoleClassifier,
+    languages: LanguageDetector,
+    ignore: IgnoreRules,
+    skip_nested_repos: bool,
+    hashes: Option<&'a HashCache>,
 }
 
 impl<'a> Scanner<'a> {
     pub fn new(root: &'a Path) -> Self {
-        Self { root }
+        Self {
+            root,
+            classifier: RoleClassifier::default(),
+            languages: LanguageDetector::default(),
+            ignore: IgnoreRules::default(),
+            skip_nested_repos: false,
+            hashes: None,
+        }
+    }
+
+    /// Use a rule-based classifier for file roles instead of the built-in heuristics alone.
+    pub fn classifier(mut self, classifier: RoleClassifier) -> Self {
+        self.classifier = classifier;
+        self
+    }
+
+    /// Use a language detector with user extension overrides.
+    pub fn languages(mut self, languages: LanguageDetector) -> Self {
+        self.languages = languages;
+        self
+    }
+
+    /// Leave out files matching `[scan] ignore` globs, as well as
+    /// gitignored ones.
+    pub fn ignore(mut self, ignore: IgnoreRules) -> Self {
+        self.ignore = ignore;
+        self
+    }
+
+    /// Stop at nested repositories (submodules, nested checkouts) instead of
+    /// scanning their files as part of this one.
+    pub fn skip_nested_repos(mut self, skip: bool) -> Self {
+        self.skip_nested_repos = skip;
+        self
+    }
+
+    /// Reuse the hashes `cache` remembers for files unchanged since, and
+    /// remember the new ones.
+    pub fn hash_cache(mut self, cache: &'a HashCache) -> Self {
+        self.hashes = Some(cache);
+        self
     }
 
     /// Directories that are always excluded from scanning, regardless of .gitignore.
@@ -27,26 +74,76 @@ impl<'a> Scanner<'a> {
         ".hg",
     ];
 
-    /// Scan the directory tree and return metadata for all non-ignored files.
-    pub fn scan(&self) -> anyhow::Result<Vec<FileInfo>> {
-        let mut files = Vec::new();
-
-        let walker = WalkBuilder::new(self.root)
+    /// Gitignore-aware walker that skips [`Self::ALWAYS_SKIP_DIRS`] and,
+    /// when `skip_nested_repos` is set, nested repositories. Every skipped
+    /// nested repository is passed to `on_nested`.
+    ///
+    /// The walk starts from [`long_path`] of the root, so on Windows files
+    /// nested deeper than `MAX_PATH` and on network shares are found too.
+    fn walker<F>(&self, root: &Path, skip_nested_repos: bool, on_nested: F) -> ignore::Walk
+    where
+        F: Fn(&Path) + Send + Sync + 'static,
+    {
+        let walk_root = root.to_path_buf();
+        WalkBuilder::new(root)
             .hidden(false) // don't skip dotfiles by default
             .git_ignore(true)
             .git_global(true)
             .git_exclude(true)
-            .filter_entry(|entry| {
+            .filter_entry(move |entry| {
+                if !entry.file_type().is_some_and(|ft| ft.is_dir()) {
+                    // Worktrees and submodules have a `.git` file instead
+                    return entry.file_name() != ".git";
+                }
                 // Skip directories that should always be excluded
-                if entry.file_type().is_some_and(|ft| ft.is_dir())
-                    && let Some(name) = entry.file_name().to_str()
+                if let Some(name) = entry.file_name().to_str()
                     && Self::ALWAYS_SKIP_DIRS.contains(&name)
                 {
                     return false;
                 }
+                if skip_nested_repos && is_nested_repo(&walk_root, entry.path()) {
+                    on_nested(entry.path());
+                    return false;
+                }
                 true
             })
-            .build();
+            .build()
+    }
+
+    /// Repositories nested below the root, as sorted relative paths.
+    ///
+    /// Repositories nested inside those are not included; scan each one to
+    /// find its own.
+    pub fn nested_repos(&self) -> Vec<String> {
+        let root = long_path(self.root);
+        let found = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&found);
+        let walker = self.walker(&root, true, move |dir| {
+            // A panic elsewhere in the walk leaves the list itself intact
+            sink.lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(dir.to_path_buf());
+        });
+        // Drive the walk; only the skipped directories matter
+        walker.for_each(drop);
+
+        let mut repos: Vec<String> = found
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter_map(|dir| dir.strip_prefix(&root).ok())
+            .map(|rel| rel.to_string_lossy().replace('\\', "/"))
+            .collect();
+        repos.sort();
+        repos
+    }
+
+    /// Scan the directory tree and return metadata for all non-ignored files.
+    pub fn scan(&self) -> Result<Vec<FileInfo>, TopoError> {
+        let mut files = Vec::new();
+
+        let root = long_path(self.root);
+        let walker = self.walker(&root, self.skip_nested_repos, |_| {});
 
         for entry in walker {
             let entry = match entry {
@@ -62,7 +159,7 @@ impl<'a> Scanner<'a> {
             let path = entry.path();
 
             // Get relative path from root
-            let rel_path = match path.strip_prefix(self.root) {
+            let rel_path = match path.strip_prefix(&root) {
                 Ok(p) => p,
                 Err(_) => continue,
             };
@@ -74,6 +171,9 @@ impl<'a> Scanner<'a> {
 
             // Always use forward slashes for consistent cross-platform paths
             let rel_str = rel_path.to_string_lossy().replace('\\', "/");
+            if self.ignore.is_ignored(Path::new(&rel_str)) {
+                continue;
+            }
 
             // Get file metadata
             let metadata = match path.metadata() {
@@ -87,10 +187,19 @@ impl<'a> Scanner<'a> {
             }
 
             let size = metadata.len();
-            let language = Language::from_path(rel_path);
-            let role = FileRole::from_path(rel_path);
+            let language = self.languages.detect(rel_path);
+            let mut role = self.classifier.classify(rel_path);
+            // Extensions mapped to code by an override are implementation,
+            // even though the built-in heuristics don't recognise them.
+            if role == FileRole::Other && language.is_programming_language() {
+                role = FileRole::Implementation;
+            }
 
-            let sha256 = match hash::sha256_file(path) {
+            let hashed = match self.hashes {
+                Some(cache) => cache.sha256_file(path, &metadata),
+                None => hash::sha256_file(path),
+            };
+            let sha256 = match hashed {
                 Ok(h) => h,
                 Err(_) => continue,
             };
@@ -101,6 +210,7 @@ impl<'a> Scanner<'a> {
                 language,
                 role,
                 sha256,
+                package: None,
             });
         }
 
@@ -109,3 +219,8 @@ impl<'a> Scanner<'a> {
         Ok(files)
     }
 }
+
+/// Whether `dir` is the root of a repository other than `root`.
+fn is_nested_repo(root: &Path, dir: &Path) -> bool {
+    dir != root && dir.is_dir() && dir.join(".git").exists()
+}
@@ -1,4 +1,6 @@
-use crate::tokenizer::Tokenizer;
+use crate::fuzzy::near_misses;
+use crate::synonyms::{EXPANSION_WEIGHT, Synonyms};
+use crate::tokenizer::{Tokenizer, stem};
 use std::collections::HashMap;
 use topo_core::TermFreqs;
 
@@ -6,12 +8,16 @@ use topo_core::TermFreqs;
 const W_FILENAME: f64 = 5.0;
 const W_SYMBOLS: f64 = 3.0;
 const W_BODY: f64 = 1.0;
+/// Default weight of the docs field. Doc text also counts as body, so a
+/// documented term weighs `W_BODY + DEFAULT_DOCS_WEIGHT` per occurrence.
+pub const DEFAULT_DOCS_WEIGHT: f64 = 2.0;
 
 /// BM25F parameters.
 const K1: f64 = 1.2;
 const B: f64 = 0.75;
 
 /// Precomputed corpus statistics needed for IDF calculation.
+#[derive(Debug, Clone)]
 pub struct CorpusStats {
     pub total_docs: usize,
     pub avg_doc_length: f64,
@@ -56,11 +62,22 @@ impl CorpusStats {
     /// for the filename field only. This enables BM25F scoring before the deep index
     /// is built.
     pub fn from_paths(paths: &[&str]) -> Self {
+        Self::path_stats(paths, false)
+    }
+
+    /// [`CorpusStats::from_paths`] with every path term stemmed, to match
+    /// a [`Bm25fScorer`] with [`Bm25fScorer::stemming`].
+    pub fn from_stemmed_paths(paths: &[&str]) -> Self {
+        Self::path_stats(paths, true)
+    }
+
+    /// [`CorpusStats::from_paths`], stemmed when `stemming` is set.
+    pub fn path_stats(paths: &[&str], stemming: bool) -> Self {
         let mut doc_frequencies: HashMap<String, usize> = HashMap::new();
         let mut total_length = 0u64;
 
         for path in paths {
-            let tokens = Tokenizer::tokenize(path);
+            let tokens = Tokenizer::terms(path, stemming);
             let unique: std::collections::HashSet<&String> = tokens.iter().collect();
             for token in &unique {
                 *doc_frequencies.entry((*token).clone()).or_default() += 1;
@@ -82,26 +99,160 @@ impl CorpusStats {
     }
 }
 
+/// Per-field weights of a [`Bm25fScorer`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FieldWeights {
+    pub filename: f64,
+    pub symbols: f64,
+    pub body: f64,
+    pub docs: f64,
+}
+
+impl Default for FieldWeights {
+    fn default() -> Self {
+        Self {
+            filename: W_FILENAME,
+            symbols: W_SYMBOLS,
+            body: W_BODY,
+            docs: DEFAULT_DOCS_WEIGHT,
+        }
+    }
+}
+
 /// BM25F scorer using field-weighted term frequencies.
 ///
-/// Field weights: filename=5.0, symbols=3.0, body=1.0.
+/// Default field weights: filename=5.0, symbols=3.0, body=1.0, docs=2.0.
 /// Parameters: k1=1.2, b=0.75.
 pub struct Bm25fScorer {
+    /// Query terms as tokenized.
     query_tokens: Vec<String>,
+    /// Weights of query terms other than 1.0, by term as tokenized.
+    query_weights: HashMap<String, f64>,
+    /// Terms scored alongside the query, with their weights.
+    extra: Vec<(String, f64)>,
+    /// Synonyms of the query terms.
+    expansions: Vec<String>,
+    /// Terms scored, stemmed if asked, with their weights: the query's,
+    /// extra terms, synonyms at [`EXPANSION_WEIGHT`], then near misses of
+    /// query terms missing from the corpus at their similarity.
+    terms: Vec<(String, f64)>,
     stats: CorpusStats,
+    weights: FieldWeights,
+    stemming: bool,
+    fuzzy: bool,
 }
 
 impl Bm25fScorer {
     pub fn new(query: &str, stats: CorpusStats) -> Self {
-        Self {
+        let mut scorer = Self {
             query_tokens: Tokenizer::tokenize(query),
+            query_weights: HashMap::new(),
+            extra: Vec::new(),
+            expansions: Vec::new(),
+            terms: Vec::new(),
             stats,
+            weights: FieldWeights::default(),
+            stemming: false,
+            fuzzy: false,
+        };
+        scorer.weigh_terms();
+        scorer
+    }
+
+    /// Stem query terms, and path terms in [`Bm25fScorer::score_path`],
+    /// for term frequencies and stats from an index built with stemming.
+    pub fn stemming(mut self, enabled: bool) -> Self {
+        self.stemming = enabled;
+        self.weigh_terms();
+        self
+    }
+
+    /// Also score the synonyms of query terms, at [`EXPANSION_WEIGHT`].
+    pub fn synonyms(mut self, synonyms: &Synonyms) -> Self {
+        self.expansions = synonyms.expand(&self.query_tokens);
+        self.weigh_terms();
+        self
+    }
+
+    /// Weigh these query terms at their weights rather than 1.0, e.g. the
+    /// terms of a [`crate::TermVector`].
+    pub fn query_weights(mut self, weights: &[(String, f64)]) -> Self {
+        self.query_weights = weights.iter().cloned().collect();
+        self.weigh_terms();
+        self
+    }
+
+    /// Also score these terms, each word at its weight, e.g. fuzzy
+    /// candidates at their similarity.
+    pub fn expand(mut self, terms: impl IntoIterator<Item = (String, f64)>) -> Self {
+        for (term, weight) in terms {
+            let tokens = Tokenizer::tokenize(&term);
+            self.extra
+                .extend(tokens.into_iter().map(|token| (token, weight)));
+        }
+        self.weigh_terms();
+        self
+    }
+
+    /// Also score near misses of query terms missing from the corpus, like
+    /// `middleware` for `middlware`, at their similarity, see
+    /// [`crate::near_miss`].
+    pub fn fuzzy(mut self, enabled: bool) -> Self {
+        self.fuzzy = enabled;
+        self.weigh_terms();
+        self
+    }
+
+    fn weigh_terms(&mut self) {
+        let term = |token: &String| {
+            if self.stemming {
+                stem(token)
+            } else {
+                token.clone()
+            }
+        };
+        let mut terms: Vec<(String, f64)> = self
+            .query_tokens
+            .iter()
+            .map(|t| (term(t), self.query_weights.get(t).copied().unwrap_or(1.0)))
+            .collect();
+        let queried = terms.len();
+        let extra = self.extra.iter().map(|(t, weight)| (term(t), *weight));
+        let synonyms = self.expansions.iter().map(|t| (term(t), EXPANSION_WEIGHT));
+        for (added, weight) in extra.chain(synonyms) {
+            if !terms.iter().any(|(t, _)| *t == added) {
+                terms.push((added, weight));
+            }
         }
+        if self.fuzzy {
+            let vocabulary = &self.stats.doc_frequencies;
+            let missing: Vec<String> = terms[..queried]
+                .iter()
+                .map(|(t, _)| t.clone())
+                .filter(|t| !vocabulary.contains_key(t))
+                .collect();
+            for query_term in missing {
+                for (near, similarity) in
+                    near_misses(&query_term, vocabulary.keys().map(String::as_str))
+                {
+                    if !terms.iter().any(|(t, _)| t == near) {
+                        terms.push((near.to_string(), similarity));
+                    }
+                }
+            }
+        }
+        self.terms = terms;
+    }
+
+    /// Use custom field weights instead of the defaults.
+    pub fn field_weights(mut self, weights: FieldWeights) -> Self {
+        self.weights = weights;
+        self
     }
 
     /// Compute BM25F score for a document given its term frequencies and doc length.
     pub fn score(&self, term_freqs: &HashMap<String, TermFreqs>, doc_length: u32) -> f64 {
-        if self.query_tokens.is_empty() || self.stats.total_docs == 0 {
+        if self.terms.is_empty() || self.stats.total_docs == 0 {
             return 0.0;
         }
 
@@ -113,7 +264,7 @@ impl Bm25fScorer {
         let length_norm = 1.0 - B + B * (dl / avgdl);
 
         let mut score = 0.0;
-        for token in &self.query_tokens {
+        for (token, weight) in &self.terms {
             let df = self.stats.doc_frequencies.get(token).copied().unwrap_or(0) as f64;
 
             // IDF: log((N - df + 0.5) / (df + 0.5) + 1)
@@ -123,15 +274,17 @@ impl Bm25fScorer {
             let tf = term_freqs
                 .get(token)
                 .map(|f| {
-                    W_FILENAME * f.filename as f64
-                        + W_SYMBOLS * f.symbols as f64
-                        + W_BODY * f.body as f64
+                    let w = &self.weights;
+                    w.filename * f.filename as f64
+                        + w.symbols * f.symbols as f64
+                        + w.body * f.body as f64
+                        + w.docs * f.docs as f64
                 })
                 .unwrap_or(0.0);
 
             // BM25F formula: IDF * tf_weighted / (tf_weighted + k1 * length_norm)
             if tf > 0.0 {
-                score += idf * tf / (tf + K1 * length_norm);
+                score += weight * idf * tf / (tf + K1 * length_norm);
             }
         }
 
@@ -142,7 +295,7 @@ impl Bm25fScorer {
     ///
     /// Tokenizes the path and puts all term frequencies into the filename field.
     pub fn score_path(&self, path: &str) -> f64 {
-        let tokens = Tokenizer::tokenize(path);
+        let tokens = Tokenizer::terms(path, self.stemming);
         let mut term_freqs: HashMap<String, TermFreqs> = HashMap::new();
         for token in &tokens {
             term_freqs.entry(token.clone()).or_default().filename += 1;
@@ -192,6 +345,21 @@ mod tests {
         assert_eq!(score, 0.0);
     }
 
+    #[test]
+    fn bm25f_query_weights_scale_terms() {
+        let score = |weights: &[(String, f64)], path: &str| {
+            Bm25fScorer::new("auth handler", CorpusStats::from_paths(&sample_paths()))
+                .query_weights(weights)
+                .score_path(path)
+        };
+        let half = [("handler".to_string(), 0.5)];
+        assert!(score(&half, "src/auth/handler.rs") < score(&[], "src/auth/handler.rs"));
+        assert_eq!(
+            score(&half, "src/auth/middleware.rs"),
+            score(&[], "src/auth/middleware.rs")
+        );
+    }
+
     #[test]
     fn bm25f_rarer_terms_score_higher() {
         let paths = sample_paths();
@@ -221,6 +389,7 @@ mod tests {
                 filename: 2,
                 symbols: 3,
                 body: 5,
+                docs: 0,
             },
         );
 
@@ -242,6 +411,7 @@ mod tests {
                 filename: 3,
                 symbols: 0,
                 body: 0,
+                docs: 0,
             },
         );
 
@@ -252,6 +422,7 @@ mod tests {
                 filename: 0,
                 symbols: 0,
                 body: 3,
+                docs: 0,
             },
         );
 
@@ -262,6 +433,42 @@ mod tests {
         assert!(filename_score > body_score);
     }
 
+    #[test]
+    fn bm25f_docs_weight_is_configurable() {
+        let paths = sample_paths();
+        let mut documented = HashMap::new();
+        documented.insert(
+            "auth".to_string(),
+            TermFreqs {
+                body: 2,
+                docs: 2,
+                ..Default::default()
+            },
+        );
+        let mut undocumented = HashMap::new();
+        undocumented.insert(
+            "auth".to_string(),
+            TermFreqs {
+                body: 2,
+                ..Default::default()
+            },
+        );
+
+        let scorer = Bm25fScorer::new("auth", CorpusStats::from_paths(&paths));
+        assert!(scorer.score(&documented, 10) > scorer.score(&undocumented, 10));
+
+        let ignore_docs = FieldWeights {
+            docs: 0.0,
+            ..Default::default()
+        };
+        let scorer =
+            Bm25fScorer::new("auth", CorpusStats::from_paths(&paths)).field_weights(ignore_docs);
+        assert_eq!(
+            scorer.score(&documented, 10),
+            scorer.score(&undocumented, 10)
+        );
+    }
+
     #[test]
     fn bm25f_multi_term_query() {
         let paths = sample_paths();
@@ -275,6 +482,19 @@ mod tests {
         assert!(auth_handler > auth_only);
     }
 
+    #[test]
+    fn bm25f_synonyms_score_below_the_term() {
+        let paths = ["src/db/database.rs", "src/db/pool.rs", "src/main.rs"];
+        let stats = || CorpusStats::from_paths(&paths);
+        let direct = Bm25fScorer::new("database", stats());
+        let expanded = Bm25fScorer::new("db", stats()).synonyms(&Synonyms::builtin());
+
+        let path = "src/database.rs";
+        assert!(expanded.score_path(path) > 0.0);
+        assert!(expanded.score_path(path) < direct.score_path(path));
+        assert_eq!(Bm25fScorer::new("db", stats()).score_path(path), 0.0);
+    }
+
     #[test]
     fn bm25f_corpus_stats_from_paths() {
         let paths = sample_paths();
@@ -0,0 +1,94 @@
+//! Entry points and hub files: where a reader new to a codebase starts,
+//! whatever the query.
+
+use std::collections::HashMap;
+use topo_core::{ScoredFile, is_entry_point};
+
+/// Boost of the most central file, relative to its score, unless
+/// configured otherwise.
+pub const DEFAULT_CENTRALITY_WEIGHT: f64 = 0.15;
+
+/// How central each file is, from 0.0 to 1.0: 1.0 for conventional entry
+/// points like `src/main.rs` or `index.ts`, and for the rest, how many
+/// files import them, log-scaled against the most imported one.
+#[derive(Debug, Clone, Default)]
+pub struct Centrality {
+    /// Files importing each file.
+    in_degree: HashMap<String, usize>,
+    most: usize,
+}
+
+impl Centrality {
+    /// Centrality from `dependencies`, each path → the paths it imports,
+    /// as recorded in the deep index; empty without one, leaving entry
+    /// points alone central.
+    pub fn new(dependencies: &HashMap<String, Vec<String>>) -> Self {
+        let mut in_degree: HashMap<String, usize> = HashMap::new();
+        for (from, imports) in dependencies {
+            for to in imports.iter().filter(|to| *to != from) {
+                *in_degree.entry(to.clone()).or_default() += 1;
+            }
+        }
+        let most = in_degree.values().copied().max().unwrap_or(0);
+        Self { in_degree, most }
+    }
+
+    /// Centrality of the file at `path`.
+    pub fn score(&self, path: &str) -> f64 {
+        if is_entry_point(path) {
+            return 1.0;
+        }
+        match self.in_degree.get(path) {
+            Some(&count) if self.most > 0 => {
+                (1.0 + count as f64).ln() / (1.0 + self.most as f64).ln()
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Raise each file's score by up to `weight` of it by its centrality.
+    /// The order is left to the caller to restore.
+    pub fn apply(&self, weight: f64, scored: &mut [ScoredFile]) {
+        for file in scored.iter_mut() {
+            let centrality = self.score(&file.path);
+            if centrality > 0.0 {
+                file.signals.centrality = Some(centrality);
+                file.score *= 1.0 + weight * centrality;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dependencies(edges: &[(&str, &[&str])]) -> HashMap<String, Vec<String>> {
+        edges
+            .iter()
+            .map(|(from, to)| (from.to_string(), to.iter().map(|t| t.to_string()).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn entry_points_and_imported_files_are_central() {
+        let centrality = Centrality::new(&dependencies(&[
+            ("src/a.rs", &["src/util.rs", "src/db.rs"]),
+            ("src/b.rs", &["src/util.rs"]),
+            ("src/c.rs", &["src/util.rs", "src/c.rs"]),
+        ]));
+        assert_eq!(centrality.score("src/util.rs"), 1.0);
+        assert_eq!(centrality.score("src/db.rs"), 2f64.ln() / 4f64.ln());
+        assert_eq!(
+            centrality.score("src/c.rs"),
+            0.0,
+            "self-imports don't count"
+        );
+        assert_eq!(centrality.score("src/main.rs"), 1.0);
+        assert_eq!(centrality.score("a/b/c/d/main.rs"), 0.0, "too deep");
+
+        let paths_only = Centrality::default();
+        assert_eq!(paths_only.score("index.ts"), 1.0);
+        assert_eq!(paths_only.score("src/util.rs"), 0.0);
+    }
+}
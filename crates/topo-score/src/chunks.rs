@@ -0,0 +1,210 @@
+use crate::bm25f::{Bm25fScorer, CorpusStats, FieldWeights};
+use crate::tokenizer::Tokenizer;
+use std::collections::HashMap;
+use topo_core::ChunkTerms;
+
+/// Postings from terms to the chunks containing them, over the chunk term
+/// frequencies of a set of files.
+///
+/// Chunks are the documents: [`ChunkPostings::rank`] scores them with
+/// BM25F against each other, not against whole files.
+pub struct ChunkPostings<'a> {
+    /// `(path, position in the file's chunks, terms)` of every chunk.
+    chunks: Vec<(&'a str, usize, &'a ChunkTerms)>,
+    /// Term → indices into `chunks`, ascending.
+    postings: HashMap<&'a str, Vec<usize>>,
+    total_length: u64,
+    /// Files holding a copy of each of `chunks`, when given.
+    copies: Vec<usize>,
+    stemming: bool,
+}
+
+/// A chunk ranked by [`ChunkPostings::rank`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoredChunk<'a> {
+    pub path: &'a str,
+    /// Position in the file's chunk list.
+    pub chunk: usize,
+    pub score: f64,
+}
+
+impl<'a> ChunkPostings<'a> {
+    /// Postings over `files`, given as `(path, chunk terms)` pairs.
+    pub fn build(files: impl IntoIterator<Item = (&'a str, &'a [ChunkTerms])>) -> Self {
+        let mut chunks = Vec::new();
+        let mut postings: HashMap<&'a str, Vec<usize>> = HashMap::new();
+        let mut total_length = 0u64;
+        for (path, terms) in files {
+            for (position, chunk) in terms.iter().enumerate() {
+                for term in chunk.term_frequencies.keys() {
+                    postings.entry(term).or_default().push(chunks.len());
+                }
+                total_length += u64::from(chunk.length);
+                chunks.push((path, position, chunk));
+            }
+        }
+        Self {
+            chunks,
+            postings,
+            total_length,
+            copies: Vec::new(),
+            stemming: false,
+        }
+    }
+
+    /// Stem query terms, for chunk terms from an index built with
+    /// stemming.
+    pub fn stemming(mut self, enabled: bool) -> Self {
+        self.stemming = enabled;
+        self
+    }
+
+    /// Damp the scores of chunks with copies in other files, by
+    /// [`duplicate_damping`], so vendored or copy-pasted code doesn't
+    /// crowd out the rest. `copies(path, chunk)` is the number of files
+    /// holding the chunk at that position, see
+    /// [`topo_core::DeepIndex::duplicate_files`].
+    pub fn copies(mut self, copies: impl Fn(&str, usize) -> usize) -> Self {
+        self.copies = self
+            .chunks
+            .iter()
+            .map(|&(path, chunk, _)| copies(path, chunk))
+            .collect();
+        self
+    }
+
+    /// Number of chunks.
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Chunks containing any term of `query`, best first. Ties keep path
+    /// and chunk order.
+    pub fn rank(&self, query: &str, weights: FieldWeights) -> Vec<ScoredChunk<'a>> {
+        let tokens = Tokenizer::terms(query, self.stemming);
+        let mut candidates: Vec<usize> = tokens
+            .iter()
+            .filter_map(|token| self.postings.get(token.as_str()))
+            .flatten()
+            .copied()
+            .collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        let stats = CorpusStats {
+            total_docs: self.chunks.len(),
+            avg_doc_length: if self.chunks.is_empty() {
+                1.0
+            } else {
+                (self.total_length as f64 / self.chunks.len() as f64).max(1.0)
+            },
+            doc_frequencies: tokens
+                .iter()
+                .filter_map(|token| Some((token.clone(), self.postings.get(token.as_str())?.len())))
+                .collect(),
+        };
+        let scorer = Bm25fScorer::new(query, stats)
+            .field_weights(weights)
+            .stemming(self.stemming);
+
+        let mut ranked: Vec<ScoredChunk<'a>> = candidates
+            .into_iter()
+            .map(|i| {
+                let (path, chunk, terms) = self.chunks[i];
+                let copies = self.copies.get(i).copied().unwrap_or(1);
+                ScoredChunk {
+                    path,
+                    chunk,
+                    score: scorer.score(&terms.term_frequencies, terms.length)
+                        * duplicate_damping(copies),
+                }
+            })
+            .filter(|c| c.score > 0.0)
+            .collect();
+        // Stable, so equal scores stay in build order
+        ranked.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        ranked
+    }
+}
+
+/// Score multiplier of a chunk found in `files` files: 1 for a single
+/// file, shrinking logarithmically with each copy.
+pub fn duplicate_damping(files: usize) -> f64 {
+    1.0 / (1.0 + (files.max(1) as f64).ln())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use topo_core::TermFreqs;
+
+    fn chunk(terms: &[(&str, u32, u32)]) -> ChunkTerms {
+        ChunkTerms {
+            term_frequencies: terms
+                .iter()
+                .map(|&(term, symbols, body)| {
+                    let tf = TermFreqs {
+                        symbols,
+                        body,
+                        ..Default::default()
+                    };
+                    (term.to_string(), tf)
+                })
+                .collect(),
+            length: terms.iter().map(|t| t.2).sum(),
+            start_line: 1,
+            end_line: 1,
+            hash: 0,
+        }
+    }
+
+    #[test]
+    fn rank_scores_chunks_against_each_other() {
+        let auth = [
+            chunk(&[("login", 1, 3), ("session", 0, 2)]),
+            chunk(&[("logout", 1, 1), ("session", 0, 1)]),
+        ];
+        let db = [chunk(&[("connect", 1, 2)]), chunk(&[("session", 0, 1)])];
+        let postings = ChunkPostings::build([("auth.rs", &auth[..]), ("db.rs", &db[..])]);
+        assert_eq!(postings.len(), 4);
+
+        let ranked = postings.rank("login session", FieldWeights::default());
+        assert_eq!(ranked.len(), 3);
+        assert_eq!((ranked[0].path, ranked[0].chunk), ("auth.rs", 0));
+        assert!(ranked.iter().all(|c| (c.path, c.chunk) != ("db.rs", 0)));
+
+        assert!(postings.rank("zebra", FieldWeights::default()).is_empty());
+        assert!(
+            ChunkPostings::build([])
+                .rank("login", FieldWeights::default())
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn copies_in_other_files_are_damped() {
+        let auth = [chunk(&[("login", 1, 2)])];
+        let vendored = [chunk(&[("login", 1, 2)])];
+        let build =
+            || ChunkPostings::build([("auth.rs", &auth[..]), ("vendor/auth.rs", &vendored[..])]);
+        let plain = build().rank("login", FieldWeights::default());
+        assert_eq!(plain[0].score, plain[1].score);
+
+        let damped = build()
+            .copies(|path, _| if path == "auth.rs" { 1 } else { 3 })
+            .rank("login", FieldWeights::default());
+        assert_eq!(damped[0].path, "auth.rs");
+        assert_eq!(damped[0].score, plain[0].score);
+        assert!((damped[1].score - plain[1].score * duplicate_damping(3)).abs() < 1e-12);
+        assert!(duplicate_damping(3) < duplicate_damping(2));
+        assert_eq!(duplicate_damping(1), 1.0);
+    }
+}
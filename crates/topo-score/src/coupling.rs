@@ -0,0 +1,272 @@
+//! Coupling of tests to the implementation files they cover, so a query
+//! matching one can bring in the other.
+
+use std::collections::HashMap;
+use topo_core::{FileInfo, FileRole, ScoredFile};
+
+/// Boost of a file coupled to one matching the query, relative to that
+/// file's score and the strength of the coupling.
+pub const COUPLING_WEIGHT: f64 = 0.5;
+
+/// Strength of a coupling through a directory named like the test's
+/// subject, as `tests/auth_test.rs` has with `src/auth/middleware.rs`.
+const DIRECTORY_COUPLING: f64 = 0.5;
+
+/// Test subjects too generic to say what a test covers.
+const GENERIC_SUBJECTS: &[&str] = &[
+    "", "mod", "lib", "index", "main", "init", "__init__", "conftest", "setup", "test", "tests",
+    "spec", "helpers", "utils", "common",
+];
+
+/// Which files get a boost from a coupled file matching the query.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Coupling {
+    /// Every file is scored on its own.
+    #[default]
+    Off,
+    /// Tests of implementation files matching the query.
+    Tests,
+    /// Tests of matching implementation files, and implementation files of
+    /// matching tests.
+    Both,
+}
+
+/// Tests coupled to implementation files, each pair with its strength from
+/// 0.0 to 1.0.
+#[derive(Debug, Clone, Default)]
+pub struct TestPairs {
+    /// Positions of the test and the implementation file among the files
+    /// detected from, and the strength.
+    pairs: Vec<(usize, usize, f64)>,
+}
+
+impl TestPairs {
+    /// Couple each test among `files` to the implementation files it
+    /// imports, per `imports` (path → imported paths), and those named
+    /// like it without its test affix (`auth_test.rs`, `test_auth.py`,
+    /// `auth.spec.ts`, and `AuthTest.java` all cover `auth`). Files under
+    /// a directory of that name are coupled at half strength.
+    pub fn detect(files: &[&FileInfo], imports: &HashMap<String, Vec<String>>) -> Self {
+        let mut by_path: HashMap<&str, usize> = HashMap::new();
+        let mut by_stem: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut by_directory: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, file) in files.iter().enumerate() {
+            if file.role != FileRole::Implementation {
+                continue;
+            }
+            by_path.insert(&file.path, i);
+            let (directories, name) = split(&file.path);
+            by_stem
+                .entry(stem(name).to_lowercase())
+                .or_default()
+                .push(i);
+            for directory in directories {
+                by_directory
+                    .entry(directory.to_lowercase())
+                    .or_default()
+                    .push(i);
+            }
+        }
+
+        let mut strengths: HashMap<(usize, usize), f64> = HashMap::new();
+        let mut couple = |test: usize, implementation: usize, strength: f64| {
+            let entry = strengths.entry((test, implementation)).or_default();
+            *entry = entry.max(strength);
+        };
+        for (test, file) in files.iter().enumerate() {
+            if file.role != FileRole::Test {
+                continue;
+            }
+            for imported in imports.get(&file.path).into_iter().flatten() {
+                if let Some(&implementation) = by_path.get(imported.as_str()) {
+                    couple(test, implementation, 1.0);
+                }
+            }
+            let Some(subject) = subject(&file.path) else {
+                continue;
+            };
+            for &implementation in by_stem.get(&subject).into_iter().flatten() {
+                couple(test, implementation, 1.0);
+            }
+            for &implementation in by_directory.get(&subject).into_iter().flatten() {
+                couple(test, implementation, DIRECTORY_COUPLING);
+            }
+        }
+
+        let mut pairs: Vec<(usize, usize, f64)> = strengths
+            .into_iter()
+            .map(|((test, implementation), strength)| (test, implementation, strength))
+            .collect();
+        pairs.sort_by_key(|&(test, implementation, _)| (test, implementation));
+        Self { pairs }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// Boost files of `scored`, in the order of the files the pairs were
+    /// detected from, that are coupled to a file matching the query, as
+    /// `coupling` allows. A file matches when its BM25F score is positive;
+    /// the boost is [`COUPLING_WEIGHT`] of the best matching partner's
+    /// score times the coupling's strength, so boosts don't chain.
+    pub fn apply(&self, coupling: Coupling, scored: &mut [ScoredFile]) {
+        if coupling == Coupling::Off {
+            return;
+        }
+        let partner = |i: usize, strength: f64| {
+            let file = &scored[i];
+            (file.signals.bm25f > 0.0).then_some(strength * file.score)
+        };
+        let mut best = vec![0.0_f64; scored.len()];
+        for &(test, implementation, strength) in &self.pairs {
+            if let Some(boost) = partner(implementation, strength) {
+                best[test] = best[test].max(boost);
+            }
+            if coupling == Coupling::Both
+                && let Some(boost) = partner(test, strength)
+            {
+                best[implementation] = best[implementation].max(boost);
+            }
+        }
+        for (file, boost) in scored.iter_mut().zip(best) {
+            if boost > 0.0 {
+                file.signals.coupling = Some(boost);
+                file.score += COUPLING_WEIGHT * boost;
+            }
+        }
+    }
+}
+
+/// Directories of `path` and its file name.
+fn split(path: &str) -> (Vec<&str>, &str) {
+    let mut parts: Vec<&str> = path.split(['/', '\\']).collect();
+    let name = parts.pop().unwrap_or_default();
+    (parts, name)
+}
+
+/// File name up to its first dot: `auth` for `auth.spec.ts`.
+fn stem(name: &str) -> &str {
+    name.split('.').next().unwrap_or_default()
+}
+
+/// What the test at `path` covers, lowercased: its name without extension
+/// and test affix, unless that is too generic to tell.
+fn subject(path: &str) -> Option<String> {
+    let stem = stem(split(path).1);
+    // Case matters for `AuthTest`, not for `auth_test` or `Auth_Test`
+    let stem = ["Tests", "Test", "Spec"]
+        .iter()
+        .find_map(|suffix| stem.strip_suffix(suffix).filter(|s| !s.is_empty()))
+        .unwrap_or(stem)
+        .to_lowercase();
+    let subject = ["_tests", "_test", "_spec", "-test", "-spec"]
+        .iter()
+        .find_map(|suffix| stem.strip_suffix(suffix))
+        .or_else(|| stem.strip_prefix("test_"))
+        .unwrap_or(&stem);
+    (!GENERIC_SUBJECTS.contains(&subject)).then(|| subject.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use topo_core::{Language, SignalBreakdown};
+
+    fn file(path: &str, role: FileRole) -> FileInfo {
+        FileInfo {
+            path: path.to_string(),
+            size: 100,
+            language: Language::Rust,
+            role,
+            sha256: [0; 32],
+            package: None,
+        }
+    }
+
+    fn scored(file: &FileInfo, score: f64) -> ScoredFile {
+        ScoredFile {
+            path: file.path.clone(),
+            score,
+            signals: SignalBreakdown {
+                bm25f: score,
+                ..Default::default()
+            },
+            tokens: 0,
+            language: file.language,
+            role: file.role,
+            preview: None,
+        }
+    }
+
+    #[test]
+    fn subjects_drop_test_affixes() {
+        assert_eq!(subject("tests/auth_test.rs").as_deref(), Some("auth"));
+        assert_eq!(subject("tests/test_auth.py").as_deref(), Some("auth"));
+        assert_eq!(subject("src/auth.spec.ts").as_deref(), Some("auth"));
+        assert_eq!(
+            subject("src/test/java/AuthServiceTest.java").as_deref(),
+            Some("authservice")
+        );
+        assert_eq!(subject("tests/parser.rs").as_deref(), Some("parser"));
+        assert_eq!(subject("tests/common/mod.rs"), None);
+        assert_eq!(subject("tests/Test.java"), None);
+    }
+
+    #[test]
+    fn tests_couple_by_name_directory_and_import() {
+        let files = [
+            file("src/auth/middleware.rs", FileRole::Implementation),
+            file("src/auth.rs", FileRole::Implementation),
+            file("src/db.rs", FileRole::Implementation),
+            file("tests/auth_test.rs", FileRole::Test),
+            file("tests/integration.rs", FileRole::Test),
+        ];
+        let refs: Vec<&FileInfo> = files.iter().collect();
+        let imports = HashMap::from([(
+            "tests/integration.rs".to_string(),
+            vec!["src/db.rs".to_string()],
+        )]);
+        let pairs = TestPairs::detect(&refs, &imports);
+        assert_eq!(
+            pairs.pairs,
+            [(3, 0, DIRECTORY_COUPLING), (3, 1, 1.0), (4, 2, 1.0)]
+        );
+        assert!(TestPairs::detect(&refs[..3], &imports).is_empty());
+    }
+
+    #[test]
+    fn matching_files_boost_their_counterparts() {
+        let files = [
+            file("src/auth/middleware.rs", FileRole::Implementation),
+            file("tests/middleware_test.rs", FileRole::Test),
+            file("src/db.rs", FileRole::Implementation),
+            file("tests/db_test.rs", FileRole::Test),
+        ];
+        let refs: Vec<&FileInfo> = files.iter().collect();
+        let pairs = TestPairs::detect(&refs, &HashMap::new());
+        let fresh = || {
+            vec![
+                scored(&files[0], 0.8),
+                scored(&files[1], 0.0),
+                scored(&files[2], 0.0),
+                scored(&files[3], 0.4),
+            ]
+        };
+
+        let mut off = fresh();
+        pairs.apply(Coupling::Off, &mut off);
+        assert!(off.iter().all(|f| f.signals.coupling.is_none()));
+
+        let mut tests = fresh();
+        pairs.apply(Coupling::Tests, &mut tests);
+        assert_eq!(tests[1].signals.coupling, Some(0.8));
+        assert_eq!(tests[1].score, COUPLING_WEIGHT * 0.8);
+        assert_eq!(tests[2].signals.coupling, None, "one way only");
+
+        let mut both = fresh();
+        pairs.apply(Coupling::Both, &mut both);
+        assert_eq!(both[2].signals.coupling, Some(0.4));
+        assert_eq!(both[0].signals.coupling, None, "its test doesn't match");
+    }
+}
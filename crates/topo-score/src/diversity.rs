@@ -0,0 +1,185 @@
+//! Maximal Marginal Relevance: re-ranking results so that near-identical
+//! files don't crowd out the rest of the codebase.
+
+use std::collections::HashSet;
+use topo_core::ScoredFile;
+
+/// Leading results re-ranked by [`Mmr::rerank`] unless set otherwise.
+pub const DEFAULT_MMR_CANDIDATES: usize = 100;
+
+/// Maximal Marginal Relevance re-ranking.
+///
+/// Results are picked greedily by `lambda * relevance - (1 - lambda) *
+/// similarity`, where relevance is a file's score relative to the best and
+/// similarity its highest to any file picked before it. A lambda of 1.0
+/// keeps the ranking as it is; lower values trade relevance for covering
+/// more distinct areas.
+#[derive(Debug, Clone, Copy)]
+pub struct Mmr {
+    lambda: f64,
+    candidates: usize,
+}
+
+impl Mmr {
+    /// Re-ranking with `lambda` (0.0–1.0) weighing relevance against
+    /// diversity.
+    pub fn new(lambda: f64) -> Self {
+        Self {
+            lambda: lambda.clamp(0.0, 1.0),
+            candidates: DEFAULT_MMR_CANDIDATES,
+        }
+    }
+
+    /// Re-rank only the leading `n` results, leaving the rest in order.
+    pub fn candidates(mut self, n: usize) -> Self {
+        self.candidates = n;
+        self
+    }
+
+    /// Reorder the leading results of `scored`, sorted by score, for
+    /// diversity. Scores stay as they are, so the result is no longer
+    /// sorted by them. `similarity` of two files is from 0.0 to 1.0, see
+    /// [`path_similarity`] and [`term_similarity`].
+    pub fn rerank(
+        &self,
+        scored: &mut [ScoredFile],
+        similarity: impl Fn(&ScoredFile, &ScoredFile) -> f64,
+    ) {
+        let count = self.candidates.min(scored.len());
+        if self.lambda >= 1.0 || count < 3 {
+            return;
+        }
+        let best = scored[..count]
+            .iter()
+            .map(|file| file.score)
+            .fold(f64::MIN_POSITIVE, f64::max);
+        let mut remaining: Vec<usize> = (0..count).collect();
+        // Highest similarity of each remaining file to any picked one
+        let mut closest = vec![0.0_f64; count];
+        let mut order: Vec<usize> = Vec::with_capacity(count);
+        while let Some((position, &picked)) =
+            remaining.iter().enumerate().max_by(|(_, a), (_, b)| {
+                let value = |i: usize| {
+                    self.lambda * scored[i].score / best - (1.0 - self.lambda) * closest[i]
+                };
+                // Ties go to the better ranked file
+                value(**a).total_cmp(&value(**b)).then(b.cmp(a))
+            })
+        {
+            remaining.remove(position);
+            order.push(picked);
+            for &i in &remaining {
+                closest[i] = closest[i].max(similarity(&scored[picked], &scored[i]));
+            }
+        }
+        let reordered: Vec<ScoredFile> = order.iter().map(|&i| scored[i].clone()).collect();
+        scored[..count].clone_from_slice(&reordered);
+    }
+}
+
+/// Share of directories two paths have in common, from the top: 1.0 for
+/// files in the same directory, 0.0 for files with different top-level
+/// directories or both at the root.
+pub fn path_similarity(a: &str, b: &str) -> f64 {
+    fn dirs(path: &str) -> Vec<&str> {
+        let mut parts: Vec<&str> = path.split(['/', '\\']).collect();
+        parts.pop();
+        parts
+    }
+    let (a, b) = (dirs(a), dirs(b));
+    let deepest = a.len().max(b.len());
+    if deepest == 0 {
+        return 0.0;
+    }
+    let shared = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    shared as f64 / deepest as f64
+}
+
+/// Jaccard similarity of two files' terms: shared terms over all terms.
+pub fn term_similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.len() + b.len() - a.intersection(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use topo_core::{FileRole, Language};
+
+    fn scored(path: &str, score: f64) -> ScoredFile {
+        ScoredFile {
+            path: path.to_string(),
+            score,
+            signals: Default::default(),
+            tokens: 0,
+            language: Language::Rust,
+            role: FileRole::Implementation,
+            preview: None,
+        }
+    }
+
+    fn paths(files: &[ScoredFile]) -> Vec<&str> {
+        files.iter().map(|f| f.path.as_str()).collect()
+    }
+
+    #[test]
+    fn similar_files_make_way_for_other_areas() {
+        let mut files = vec![
+            scored("src/handlers/users.rs", 1.0),
+            scored("src/handlers/orders.rs", 0.95),
+            scored("src/handlers/items.rs", 0.9),
+            scored("src/db/schema.rs", 0.8),
+            scored("docs/api.md", 0.5),
+        ];
+        let similarity = |a: &ScoredFile, b: &ScoredFile| path_similarity(&a.path, &b.path);
+
+        let mut unchanged = files.clone();
+        Mmr::new(1.0).rerank(&mut unchanged, similarity);
+        assert_eq!(paths(&unchanged), paths(&files));
+
+        Mmr::new(0.5).rerank(&mut files, similarity);
+        assert_eq!(
+            paths(&files),
+            [
+                "src/handlers/users.rs",
+                "docs/api.md",
+                "src/db/schema.rs",
+                "src/handlers/orders.rs",
+                "src/handlers/items.rs",
+            ]
+        );
+        assert_eq!(files[1].score, 0.5, "scores are kept");
+    }
+
+    #[test]
+    fn only_leading_candidates_move() {
+        let mut files = vec![
+            scored("a/x.rs", 1.0),
+            scored("a/y.rs", 0.9),
+            scored("b/z.rs", 0.8),
+            scored("c/w.rs", 0.7),
+        ];
+        Mmr::new(0.0)
+            .candidates(3)
+            .rerank(&mut files, |a, b| path_similarity(&a.path, &b.path));
+        assert_eq!(paths(&files), ["a/x.rs", "b/z.rs", "a/y.rs", "c/w.rs"]);
+    }
+
+    #[test]
+    fn similarities() {
+        assert_eq!(path_similarity("src/a/x.rs", "src/a/y.rs"), 1.0);
+        assert_eq!(path_similarity("src/a/x.rs", "src/b/y.rs"), 0.5);
+        assert_eq!(path_similarity("src/x.rs", "docs/y.md"), 0.0);
+        assert_eq!(path_similarity("x.rs", "y.rs"), 0.0);
+
+        let terms = |words: &[&str]| words.iter().map(|w| w.to_string()).collect();
+        assert_eq!(
+            term_similarity(&terms(&["a", "b"]), &terms(&["b", "c"])),
+            1.0 / 3.0
+        );
+        assert_eq!(term_similarity(&terms(&[]), &terms(&[])), 0.0);
+    }
+}
@@ -0,0 +1,128 @@
+//! Files with identical contents, like vendored copies, which would
+//! otherwise each take their full share of the budget.
+
+use std::collections::HashMap;
+use topo_core::{FileInfo, ScoredFile};
+
+/// Share of its score a copy of a better scoring file keeps.
+pub const DUPLICATE_WEIGHT: f64 = 0.1;
+
+/// Files sharing their sha256 with another file.
+#[derive(Debug, Clone, Default)]
+pub struct Duplicates {
+    hashes: HashMap<String, [u8; 32]>,
+}
+
+impl Duplicates {
+    /// The duplicated files among `files`. Empty files, and files without
+    /// a hash, are never duplicates.
+    pub fn new<'f>(files: impl IntoIterator<Item = &'f FileInfo>) -> Self {
+        let mut groups: HashMap<[u8; 32], Vec<&str>> = HashMap::new();
+        for file in files {
+            if file.size > 0 && file.sha256 != [0; 32] {
+                groups.entry(file.sha256).or_default().push(&file.path);
+            }
+        }
+        let hashes = groups
+            .into_iter()
+            .filter(|(_, paths)| paths.len() > 1)
+            .flat_map(|(hash, paths)| paths.into_iter().map(move |path| (path.to_string(), hash)))
+            .collect();
+        Self { hashes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    /// Keep the best scoring file of each group of copies in `scored`, the
+    /// first given on a tie, and scale the others' scores by
+    /// [`DUPLICATE_WEIGHT`], marking whose copies they are. The order is
+    /// left to the caller to restore.
+    pub fn apply(&self, scored: &mut [ScoredFile]) {
+        if self.hashes.is_empty() {
+            return;
+        }
+        let mut best: HashMap<[u8; 32], usize> = HashMap::new();
+        for (i, file) in scored.iter().enumerate() {
+            if let Some(&hash) = self.hashes.get(&file.path) {
+                let leader = best.entry(hash).or_insert(i);
+                if file.score > scored[*leader].score {
+                    *leader = i;
+                }
+            }
+        }
+        for i in 0..scored.len() {
+            let leader = match self.hashes.get(&scored[i].path) {
+                Some(hash) => best[hash],
+                None => continue,
+            };
+            if leader != i {
+                let original = scored[leader].path.clone();
+                let file = &mut scored[i];
+                file.score *= DUPLICATE_WEIGHT;
+                file.signals.duplicate_of = Some(original);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use topo_core::{FileRole, Language};
+
+    fn file(path: &str, hash: u8, size: u64) -> FileInfo {
+        FileInfo {
+            path: path.to_string(),
+            size,
+            language: Language::Rust,
+            role: FileRole::Implementation,
+            sha256: [hash; 32],
+            package: None,
+        }
+    }
+
+    fn scored(path: &str, score: f64) -> ScoredFile {
+        ScoredFile {
+            path: path.to_string(),
+            score,
+            signals: Default::default(),
+            tokens: 0,
+            language: Language::Rust,
+            role: FileRole::Implementation,
+            preview: None,
+        }
+    }
+
+    #[test]
+    fn copies_of_the_best_scoring_file_are_demoted() {
+        let files = [
+            file("vendor/json/parse.rs", 1, 100),
+            file("src/parse.rs", 1, 100),
+            file("src/lib.rs", 2, 100),
+            file("src/a/mod.rs", 3, 0),
+            file("src/b/mod.rs", 3, 0),
+        ];
+        let duplicates = Duplicates::new(&files);
+        let mut results = vec![
+            scored("vendor/json/parse.rs", 0.5),
+            scored("src/parse.rs", 0.8),
+            scored("src/lib.rs", 0.6),
+            scored("src/a/mod.rs", 0.2),
+            scored("src/b/mod.rs", 0.2),
+        ];
+        duplicates.apply(&mut results);
+
+        assert_eq!(results[1].score, 0.8);
+        assert_eq!(results[1].signals.duplicate_of, None);
+        assert_eq!(results[0].score, 0.5 * DUPLICATE_WEIGHT);
+        assert_eq!(
+            results[0].signals.duplicate_of.as_deref(),
+            Some("src/parse.rs")
+        );
+        assert_eq!(results[2].score, 0.6);
+        assert_eq!(results[4].score, 0.2, "empty files aren't copies");
+        assert!(Duplicates::new(&files[2..]).is_empty());
+    }
+}
@@ -0,0 +1,287 @@
+use crate::fusion::RrfFusion;
+use topo_core::{Embedder, FileEntry, ScoredFile, TopoError, cosine_similarity};
+
+/// Leading files compared with the query by default.
+const DEFAULT_CANDIDATES: usize = 500;
+
+/// Texts sent to the embedder per call by default.
+const DEFAULT_BATCH_SIZE: usize = 32;
+
+/// Declarations named in a file's summary, at most this many.
+const MAX_SUMMARY_SYMBOLS: usize = 20;
+
+/// Characters of a file's summary; the rest is left out.
+const MAX_SUMMARY_CHARS: usize = 2_000;
+
+/// Semantic scoring: the cosine similarity between an embedding of the
+/// query and one of each file's summary (its path, leading symbols, and
+/// doc comments), fused with the ranking by RRF.
+///
+/// Conceptual queries like "rate limiting logic" then find files that
+/// never use those words, such as a `Throttle` documented as capping
+/// requests per second. Bring any [`Embedder`], e.g. a local ONNX model.
+pub struct EmbeddingScorer<'a> {
+    embedder: &'a dyn Embedder,
+    candidates: usize,
+    batch_size: usize,
+}
+
+impl<'a> EmbeddingScorer<'a> {
+    pub fn new(embedder: &'a dyn Embedder) -> Self {
+        Self {
+            embedder,
+            candidates: DEFAULT_CANDIDATES,
+            batch_size: DEFAULT_BATCH_SIZE,
+        }
+    }
+
+    /// Compare only the leading `count` files with the query, since every
+    /// summary is embedded at query time.
+    pub fn candidates(mut self, count: usize) -> Self {
+        self.candidates = count;
+        self
+    }
+
+    /// Send the embedder at most `size` texts per call.
+    pub fn batch_size(mut self, size: usize) -> Self {
+        self.batch_size = size.max(1);
+        self
+    }
+
+    /// The text embedded for the file at `path`: the path, then the names
+    /// of its exports and other declarations, then the first line of each
+    /// of their doc comments.
+    pub fn summary(path: &str, entry: Option<&FileEntry>) -> String {
+        let mut summary = path.to_string();
+        let Some(entry) = entry else {
+            return summary;
+        };
+        let mut declarations: Vec<_> = entry
+            .chunks
+            .iter()
+            .filter(|chunk| chunk.kind.is_declaration())
+            .collect();
+        // Exports first, keeping file order otherwise
+        declarations.sort_by_key(|chunk| !chunk.exported);
+        declarations.truncate(MAX_SUMMARY_SYMBOLS);
+
+        let symbols: Vec<String> = declarations
+            .iter()
+            .map(|chunk| match &chunk.parent {
+                Some(parent) => format!("{parent}::{}", chunk.name),
+                None => chunk.name.clone(),
+            })
+            .collect();
+        if !symbols.is_empty() {
+            summary.push('\n');
+            summary.push_str(&symbols.join(", "));
+        }
+        let docs = declarations
+            .iter()
+            .filter_map(|chunk| chunk.doc.as_deref()?.lines().next());
+        for doc in docs {
+            summary.push('\n');
+            summary.push_str(doc.trim());
+        }
+        match summary.char_indices().nth(MAX_SUMMARY_CHARS) {
+            Some((end, _)) => summary[..end].to_string(),
+            None => summary,
+        }
+    }
+
+    /// Cosine similarity between `query` and each of `texts`, in order.
+    pub fn similarities(&self, query: &str, texts: &[String]) -> Result<Vec<f64>, TopoError> {
+        let query = self.embed(&[query])?.pop().unwrap_or_default();
+        let mut similarities = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(self.batch_size) {
+            let batch: Vec<&str> = batch.iter().map(String::as_str).collect();
+            let vectors = self.embed(&batch)?;
+            similarities.extend(vectors.iter().map(|v| cosine_similarity(&query, v)));
+        }
+        Ok(similarities)
+    }
+
+    /// Fill [`SignalBreakdown::embedding`] of the leading candidates in
+    /// `scored`, which must be sorted, and fuse their ranking by similarity
+    /// into it with RRF, keeping `scored` sorted. `entry` looks up a file's
+    /// index entry for its summary.
+    ///
+    /// [`SignalBreakdown::embedding`]: topo_core::SignalBreakdown::embedding
+    pub fn apply<'e>(
+        &self,
+        query: &str,
+        scored: &mut [ScoredFile],
+        entry: impl Fn(&str) -> Option<&'e FileEntry>,
+    ) -> Result<(), TopoError> {
+        let count = self.candidates.min(scored.len());
+        if count == 0 {
+            return Ok(());
+        }
+        let summaries: Vec<String> = scored[..count]
+            .iter()
+            .map(|file| Self::summary(&file.path, entry(&file.path)))
+            .collect();
+        let similarities = self.similarities(query, &summaries)?;
+        for (file, &similarity) in scored.iter_mut().zip(&similarities) {
+            file.signals.embedding = Some(similarity);
+        }
+
+        let mut ranked: Vec<(&str, f64)> = scored[..count]
+            .iter()
+            .zip(similarities)
+            .filter(|(_, similarity)| *similarity > 0.0)
+            .map(|(file, similarity)| (file.path.as_str(), similarity))
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(b.0)));
+        let ranking: Vec<String> = ranked.iter().map(|(path, _)| path.to_string()).collect();
+        if !ranking.is_empty() {
+            let ranking: Vec<&str> = ranking.iter().map(String::as_str).collect();
+            RrfFusion::new().fuse_scored(scored, &[ranking]);
+        }
+        Ok(())
+    }
+
+    /// One vector per text, failing if the embedder returns too few.
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, TopoError> {
+        let model = self.embedder.model();
+        let vectors = self.embedder.embed(texts).map_err(|e| TopoError::Score {
+            message: format!("embedding with {model} failed: {e}"),
+        })?;
+        if vectors.len() != texts.len() {
+            return Err(TopoError::Score {
+                message: format!(
+                    "{model} returned {} vectors for {} texts",
+                    vectors.len(),
+                    texts.len()
+                ),
+            });
+        }
+        Ok(vectors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use topo_core::{Chunk, ChunkKind, EmbedError, FileRole, Language, SignalBreakdown};
+
+    /// Embeds texts by the concepts their words stand for, so synonyms
+    /// land on the same axis.
+    struct Concepts;
+
+    impl Embedder for Concepts {
+        fn model(&self) -> &str {
+            "concepts"
+        }
+
+        fn dimension(&self) -> u32 {
+            3
+        }
+
+        fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, EmbedError> {
+            let axis = |word: &str| match word {
+                "rate" | "limiting" | "throttle" | "requests" => Some(0),
+                "login" | "session" => Some(1),
+                "render" | "html" => Some(2),
+                _ => None,
+            };
+            Ok(texts
+                .iter()
+                .map(|text| {
+                    let mut vector = vec![0.0; 3];
+                    let words = text.split(|c: char| !c.is_alphanumeric());
+                    for i in words.filter_map(|w| axis(&w.to_lowercase())) {
+                        vector[i] += 1.0;
+                    }
+                    vector
+                })
+                .collect())
+        }
+    }
+
+    fn scored(path: &str, score: f64) -> ScoredFile {
+        ScoredFile {
+            path: path.to_string(),
+            score,
+            signals: SignalBreakdown::default(),
+            tokens: 100,
+            language: Language::Rust,
+            role: FileRole::Implementation,
+            preview: None,
+        }
+    }
+
+    fn declaration(name: &str, doc: &str, exported: bool) -> Chunk {
+        Chunk {
+            kind: ChunkKind::Function,
+            name: name.to_string(),
+            start_line: 1,
+            end_line: 1,
+            content: String::new(),
+            target: None,
+            doc: Some(doc.to_string()),
+            parent: None,
+            signature: None,
+            tokens: 0,
+            exported,
+        }
+    }
+
+    fn entry(chunks: Vec<Chunk>) -> FileEntry {
+        FileEntry {
+            sha256: [0; 32],
+            size: 0,
+            chunks,
+            term_frequencies: Default::default(),
+            doc_length: 0,
+            metadata: Default::default(),
+            chunk_terms: None,
+            exports: Vec::new(),
+            chunk_hashes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn summaries_name_exports_first_with_their_docs() {
+        let entry = entry(vec![
+            declaration("helper", "Internal.\nMore.", false),
+            declaration("throttle", "Caps requests per second.", true),
+        ]);
+        assert_eq!(
+            EmbeddingScorer::summary("src/gate.rs", Some(&entry)),
+            "src/gate.rs\nthrottle, helper\nCaps requests per second.\nInternal."
+        );
+        assert_eq!(EmbeddingScorer::summary("README.md", None), "README.md");
+    }
+
+    #[test]
+    fn conceptual_matches_are_fused_into_the_ranking() {
+        let gate = entry(vec![declaration("throttle", "Caps requests.", true)]);
+        let mut files = vec![
+            scored("src/logic.rs", 3.0),
+            scored("src/view.rs", 2.0),
+            scored("src/gate.rs", 1.0),
+        ];
+        let lookup = |path: &str| (path == "src/gate.rs").then_some(&gate);
+        EmbeddingScorer::new(&Concepts)
+            .batch_size(2)
+            .apply("rate limiting logic", &mut files, lookup)
+            .unwrap();
+
+        assert_eq!(files[0].path, "src/gate.rs");
+        assert!(files[0].signals.embedding.unwrap() > 0.9);
+        let view = files.iter().find(|f| f.path == "src/view.rs").unwrap();
+        assert_eq!(view.signals.embedding, Some(0.0));
+    }
+
+    #[test]
+    fn only_leading_candidates_are_embedded() {
+        let mut files = vec![scored("src/session.rs", 2.0), scored("src/login.rs", 1.0)];
+        EmbeddingScorer::new(&Concepts)
+            .candidates(1)
+            .apply("login", &mut files, |_| None)
+            .unwrap();
+        assert_eq!(files[0].signals.embedding, Some(1.0));
+        assert_eq!(files[1].signals.embedding, None);
+    }
+}
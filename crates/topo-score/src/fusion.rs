@@ -28,11 +28,21 @@ impl RrfFusion {
     /// Each input is a ranked list of `ScoredFile`s (already sorted by their signal score).
     /// The output is a merged list sorted by the fused RRF score.
     pub fn fuse(&self, rankings: &[Vec<&ScoredFile>]) -> Vec<RrfResult> {
+        let rankings: Vec<Vec<&str>> = rankings
+            .iter()
+            .map(|ranking| ranking.iter().map(|file| file.path.as_str()).collect())
+            .collect();
+        self.fuse_paths(&rankings)
+    }
+
+    /// Combine ranked lists of paths, best first, into a single ranking
+    /// using RRF.
+    pub fn fuse_paths(&self, rankings: &[Vec<&str>]) -> Vec<RrfResult> {
         let mut rrf_scores: HashMap<&str, f64> = HashMap::new();
 
         for ranking in rankings {
-            for (rank, file) in ranking.iter().enumerate() {
-                *rrf_scores.entry(&file.path).or_default() += 1.0 / (self.k + rank as f64 + 1.0);
+            for (rank, path) in ranking.iter().enumerate() {
+                *rrf_scores.entry(path).or_default() += 1.0 / (self.k + rank as f64 + 1.0);
             }
         }
 
@@ -57,6 +67,21 @@ impl RrfFusion {
     ///
     /// Takes ownership of a base scored list and applies RRF from additional signal rankings.
     pub fn fuse_scored(&self, base: &mut [ScoredFile], additional_rankings: &[Vec<&str>]) {
+        let weighted: Vec<(Vec<&str>, f64)> = additional_rankings
+            .iter()
+            .map(|ranking| (ranking.clone(), 1.0))
+            .collect();
+        self.fuse_scored_weighted(base, &weighted);
+    }
+
+    /// Like [`RrfFusion::fuse_scored`], with each additional ranking's
+    /// contributions scaled by its weight, relative to the base ranking's
+    /// 1.0.
+    pub fn fuse_scored_weighted(
+        &self,
+        base: &mut [ScoredFile],
+        additional_rankings: &[(Vec<&str>, f64)],
+    ) {
         if additional_rankings.is_empty() {
             return;
         }
@@ -65,16 +90,17 @@ impl RrfFusion {
         let base_ranking: Vec<String> = base.iter().map(|f| f.path.clone()).collect();
 
         // All rankings including the base
-        let mut all_rankings: Vec<Vec<String>> = vec![base_ranking];
-        for ranking in additional_rankings {
-            all_rankings.push(ranking.iter().map(|s| s.to_string()).collect());
+        let mut all_rankings: Vec<(Vec<String>, f64)> = vec![(base_ranking, 1.0)];
+        for (ranking, weight) in additional_rankings {
+            all_rankings.push((ranking.iter().map(|s| s.to_string()).collect(), *weight));
         }
 
         // Compute RRF scores
         let mut rrf_scores: HashMap<String, f64> = HashMap::new();
-        for ranking in &all_rankings {
+        for (ranking, weight) in &all_rankings {
             for (rank, path) in ranking.iter().enumerate() {
-                *rrf_scores.entry(path.clone()).or_default() += 1.0 / (self.k + rank as f64 + 1.0);
+                *rrf_scores.entry(path.clone()).or_default() +=
+                    weight / (self.k + rank as f64 + 1.0);
             }
         }
 
@@ -120,6 +146,7 @@ mod tests {
             tokens: 100,
             language: Language::Rust,
             role: FileRole::Implementation,
+            preview: None,
         }
     }
 
@@ -239,6 +266,26 @@ mod tests {
         assert_eq!(base[1].score, 2.0);
     }
 
+    #[test]
+    fn rrf_weighted_rankings_shift_the_order() {
+        let base = || vec![make_scored("a.rs", 2.0), make_scored("b.rs", 1.0)];
+        let reversed = vec!["b.rs", "a.rs"];
+
+        let mut light = base();
+        RrfFusion::new().fuse_scored_weighted(&mut light, &[(reversed.clone(), 0.5)]);
+        assert_eq!(light[0].path, "a.rs");
+
+        let mut heavy = base();
+        RrfFusion::new().fuse_scored_weighted(&mut heavy, &[(reversed.clone(), 2.0)]);
+        assert_eq!(heavy[0].path, "b.rs");
+
+        let mut plain = base();
+        let mut unit = base();
+        RrfFusion::new().fuse_scored(&mut plain, std::slice::from_ref(&reversed));
+        RrfFusion::new().fuse_scored_weighted(&mut unit, &[(reversed, 1.0)]);
+        assert_eq!(plain[0].score, unit[0].score);
+    }
+
     #[test]
     fn rrf_file_in_one_ranking_only() {
         let files1 = [make_scored("a.rs", 2.0), make_scored("b.rs", 1.0)];
@@ -0,0 +1,104 @@
+//! Near-miss matching of query terms within a few edits, for typos like
+//! `middlware`.
+
+/// Shortest query term matched fuzzily; shorter ones are a typo away from
+/// too many others.
+pub const MIN_FUZZY_LEN: usize = 4;
+
+/// Near misses kept per query term.
+const MAX_NEAR_MISSES: usize = 3;
+
+/// Edits allowed to a term of `len` characters.
+fn max_edits(len: usize) -> usize {
+    match len {
+        0..MIN_FUZZY_LEN => 0,
+        MIN_FUZZY_LEN..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// Optimal string alignment distance between `a` and `b`: insertions,
+/// deletions, substitutions, and swaps of adjacent characters count one
+/// edit each.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // Rows for the prefixes of `a` two, one, and zero characters shorter
+    let mut before: Vec<usize> = Vec::new();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = (previous[j] + 1)
+                .min(row[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(before[j - 2] + 1);
+            }
+        }
+        before = std::mem::replace(&mut previous, row);
+    }
+    previous[b.len()]
+}
+
+/// How similar `candidate` is to `term` when it is a near miss of it, from
+/// 1.0 less one edit per character of the longer of the two; `None` when
+/// they are equal, `term` is too short to match fuzzily, or it takes more
+/// edits than its length allows (one up to seven characters, two beyond).
+pub fn near_miss(term: &str, candidate: &str) -> Option<f64> {
+    let len = term.chars().count();
+    let other = candidate.chars().count();
+    let allowed = max_edits(len);
+    if allowed == 0 || term == candidate || len.abs_diff(other) > allowed {
+        return None;
+    }
+    let edits = edit_distance(term, candidate);
+    (edits <= allowed).then(|| 1.0 - edits as f64 / len.max(other) as f64)
+}
+
+/// The closest near misses of `term` in `vocabulary` with their
+/// similarity, most similar first, at most three.
+pub fn near_misses<'v>(
+    term: &str,
+    vocabulary: impl IntoIterator<Item = &'v str>,
+) -> Vec<(&'v str, f64)> {
+    let mut found: Vec<(&str, f64)> = vocabulary
+        .into_iter()
+        .filter_map(|candidate| Some((candidate, near_miss(term, candidate)?)))
+        .collect();
+    found.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    found.truncate(MAX_NEAR_MISSES);
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_distance_counts_swaps_once() {
+        assert_eq!(edit_distance("middlware", "middleware"), 1);
+        assert_eq!(edit_distance("confgi", "config"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("same", "same"), 0);
+    }
+
+    #[test]
+    fn near_misses_scale_with_length() {
+        assert_eq!(near_miss("middlware", "middleware"), Some(0.9));
+        assert!(near_miss("parsre", "parser").is_some());
+        assert_eq!(near_miss("cfg", "cgf"), None, "too short");
+        assert_eq!(near_miss("route", "router"), near_miss("router", "route"));
+        assert_eq!(near_miss("route", "rout"), Some(0.8));
+        assert_eq!(near_miss("route", "ru"), None);
+        assert_eq!(near_miss("handler", "handler"), None);
+
+        let vocabulary = ["middleware", "middle", "ware", "widdleware"];
+        assert_eq!(
+            near_misses("middlware", vocabulary),
+            [("middleware", 0.9), ("widdleware", 0.8)]
+        );
+    }
+}
@@ -1,63 +1,331 @@
 use std::collections::HashMap;
 use std::path::Path;
-use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+use topo_core::{FileActivity, FileCommit, GitActivity, TopoError};
+use topo_vcs::{ChangeStatus, Commit, LogOptions, Vcs};
 
-/// Number of days to look back for git activity.
-const LOOKBACK_DAYS: u32 = 90;
+/// Days of history read for git activity unless configured otherwise.
+pub const DEFAULT_LOOKBACK_DAYS: u32 = 90;
+
+/// Days after which a commit counts half as much, unless configured
+/// otherwise.
+pub const DEFAULT_HALF_LIFE_DAYS: f64 = 30.0;
+
+/// Share of the recency signal from decayed commit counts, unless
+/// configured otherwise; the rest comes from how long ago the file was
+/// last touched.
+pub const DEFAULT_COMMIT_WEIGHT: f64 = 0.5;
+
+const DAY_SECS: f64 = 24.0 * 60.0 * 60.0;
+
+/// How recent history makes a file recent: an exponential decay of each
+/// commit's weight with its age, blending how often a file changed with
+/// how long ago it last did.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecencyModel {
+    /// Days after which a commit weighs half as much.
+    pub half_life_days: f64,
+    /// Commits older than this many days are ignored.
+    pub lookback_days: u32,
+    /// Share (0.0–1.0) of the score from decayed commit counts; the rest is
+    /// the decayed age of the newest commit.
+    pub commit_weight: f64,
+}
+
+impl Default for RecencyModel {
+    fn default() -> Self {
+        Self {
+            half_life_days: DEFAULT_HALF_LIFE_DAYS,
+            lookback_days: DEFAULT_LOOKBACK_DAYS,
+            commit_weight: DEFAULT_COMMIT_WEIGHT,
+        }
+    }
+}
+
+impl RecencyModel {
+    /// Weight of a commit made `age` seconds ago: 1.0 now, 0.5 after a
+    /// half-life.
+    fn decay(&self, age: u64) -> f64 {
+        0.5_f64.powf(age as f64 / DAY_SECS / self.half_life_days)
+    }
+
+    /// Recency from 0.0 to 1.0 of each file with commits in `activity`
+    /// within the lookback before `now`.
+    ///
+    /// The decayed commit counts are log-normalized against the busiest
+    /// file, and blended by `commit_weight` with the decayed age of each
+    /// file's newest commit, which isn't normalized: a repository nobody
+    /// touched for a while has no file that is very recent.
+    pub fn scores(&self, activity: &GitActivity, now: u64) -> HashMap<String, f64> {
+        let cutoff = now.saturating_sub(u64::from(self.lookback_days) * 24 * 60 * 60);
+        let decayed: HashMap<&str, (f64, f64)> = activity
+            .files
+            .iter()
+            .filter_map(|(path, file)| {
+                let ages: Vec<u64> = file
+                    .commits
+                    .iter()
+                    .filter(|c| c.timestamp >= cutoff)
+                    .map(|c| now.saturating_sub(c.timestamp))
+                    .collect();
+                let newest = ages.iter().min()?;
+                let count = ages.iter().map(|&age| self.decay(age)).sum();
+                Some((path.as_str(), (count, self.decay(*newest))))
+            })
+            .collect();
+        let busiest = decayed
+            .values()
+            .map(|&(count, _)| count)
+            .fold(0.0_f64, f64::max);
+        let weight = self.commit_weight.clamp(0.0, 1.0);
+        decayed
+            .into_iter()
+            .map(|(path, (count, newest))| {
+                let frequency = if busiest > 0.0 {
+                    (1.0 + count).ln() / (1.0 + busiest).ln()
+                } else {
+                    0.0
+                };
+                let score = weight * frequency + (1.0 - weight) * newest;
+                (path.to_string(), score)
+            })
+            .collect()
+    }
+
+    /// How much each file in `activity` is the recent work of the author
+    /// with `email`, from 0.0 to 1.0, as of `now`: their commits to it
+    /// within the lookback, each decayed by its age, relative to the file
+    /// they worked on most. Files they didn't commit to are left out.
+    pub fn affinity(&self, activity: &GitActivity, email: &str, now: u64) -> HashMap<String, f64> {
+        let Some(author) = activity.author_id(email) else {
+            return HashMap::new();
+        };
+        let cutoff = now.saturating_sub(u64::from(self.lookback_days) * 24 * 60 * 60);
+        let decayed: Vec<(&str, f64)> = activity
+            .files
+            .iter()
+            .filter_map(|(path, file)| {
+                let own: f64 = file
+                    .commits
+                    .iter()
+                    .filter(|c| c.author == author && c.timestamp >= cutoff)
+                    .map(|c| self.decay(now.saturating_sub(c.timestamp)))
+                    .sum();
+                (own > 0.0).then_some((path.as_str(), own))
+            })
+            .collect();
+        let most = decayed.iter().map(|&(_, own)| own).fold(0.0_f64, f64::max);
+        decayed
+            .into_iter()
+            .map(|(path, own)| (path.to_string(), own / most))
+            .collect()
+    }
+}
 
 /// Compute git recency scores for files in a repository.
 ///
-/// Runs `git log` to count commits per file in the last N days.
+/// Counts commits per file in the last N days.
 /// Returns normalized scores in [0.0, 1.0] where 1.0 = most recently active.
-pub fn git_recency_scores(repo_root: &Path) -> anyhow::Result<HashMap<String, f64>> {
-    let commit_counts = git_commit_counts(repo_root, LOOKBACK_DAYS)?;
+pub fn git_recency_scores(repo_root: &Path) -> Result<HashMap<String, f64>, TopoError> {
+    match topo_vcs::detect(repo_root) {
+        Some(vcs) => recency_scores(vcs.as_ref()),
+        None => Ok(HashMap::new()),
+    }
+}
+
+/// Recency scores from any [`Vcs`] backend.
+pub fn recency_scores(vcs: &dyn Vcs) -> Result<HashMap<String, f64>, TopoError> {
+    let commits = match vcs.log(&LogOptions::new().since_days(DEFAULT_LOOKBACK_DAYS)) {
+        Ok(commits) => commits,
+        // No commits yet or the repo is unreadable — no signal
+        Err(TopoError::Vcs { .. }) => return Ok(HashMap::new()),
+        Err(e) => return Err(e),
+    };
+    Ok(normalize(count_commits(&commits)))
+}
+
+/// Recency scores from history recorded in the index, without reading it
+/// again, by `model` as of now.
+pub fn activity_recency(activity: &GitActivity, model: &RecencyModel) -> HashMap<String, f64> {
+    model.scores(activity, now_secs())
+}
 
-    if commit_counts.is_empty() {
-        return Ok(HashMap::new());
+/// [`RecencyModel::affinity`] of the author with `email` for history
+/// recorded in the index, as of now.
+pub fn author_affinity(
+    activity: &GitActivity,
+    email: &str,
+    model: &RecencyModel,
+) -> HashMap<String, f64> {
+    model.affinity(activity, email, now_secs())
+}
+
+/// Read the last `lookback_days` of history of the repository at
+/// `repo_root` for the index, or `None` outside a repository or without
+/// recent commits.
+///
+/// With `previous`, only commits after its head are read; older ones are
+/// carried over while they stay in the window. If the head is gone, e.g.
+/// after a rebase, the whole window is read again.
+pub fn git_activity(
+    repo_root: &Path,
+    previous: Option<&GitActivity>,
+    lookback_days: u32,
+) -> Result<Option<GitActivity>, TopoError> {
+    match topo_vcs::detect(repo_root) {
+        Some(vcs) => read_activity(vcs.as_ref(), previous, now_secs(), lookback_days),
+        None => Ok(None),
     }
+}
 
-    let max_count = commit_counts.values().copied().max().unwrap_or(1) as f64;
+/// [`git_activity`] from any [`Vcs`] backend, with the window ending at `now`.
+pub fn read_activity(
+    vcs: &dyn Vcs,
+    previous: Option<&GitActivity>,
+    now: u64,
+    lookback_days: u32,
+) -> Result<Option<GitActivity>, TopoError> {
+    let options = LogOptions::new()
+        .since_days(lookback_days)
+        .line_counts(true);
+    let newer = previous.map(|p| vcs.log(&options.clone().since_commit(&p.head)));
+    let (commits, previous) = match newer {
+        Some(Ok(commits)) => (commits, previous),
+        Some(Err(TopoError::Vcs { .. })) | None => match vcs.log(&options) {
+            Ok(commits) => (commits, None),
+            // No commits yet or the repo is unreadable — no history
+            Err(TopoError::Vcs { .. }) => return Ok(None),
+            Err(e) => return Err(e),
+        },
+        Some(Err(e)) => return Err(e),
+    };
+
+    let Some(head) = commits
+        .first()
+        .map(|c| c.id.clone())
+        .or_else(|| previous.map(|p| p.head.clone()))
+    else {
+        return Ok(None);
+    };
+    let cutoff = now.saturating_sub(u64::from(lookback_days) * 24 * 60 * 60);
+    Ok(Some(collect_activity(head, &commits, previous, cutoff)))
+}
 
-    let scores = commit_counts
+/// Log-scale normalization: log(1 + count) / log(1 + max_count).
+fn normalize(commit_counts: HashMap<String, u32>) -> HashMap<String, f64> {
+    let max_count = commit_counts.values().copied().max().unwrap_or(1) as f64;
+    commit_counts
         .into_iter()
         .map(|(path, count)| {
-            // Log-scale normalization: log(1 + count) / log(1 + max_count)
             let score = (1.0 + count as f64).ln() / (1.0 + max_count).ln();
             (path, score)
         })
-        .collect();
+        .collect()
+}
 
-    Ok(scores)
+/// Tally commits (newest first) per current path.
+///
+/// Renames are followed, so commits made under a file's old path count
+/// towards its current path.
+fn count_commits(commits: &[Commit]) -> HashMap<String, u32> {
+    collect_activity(String::new(), commits, None, 0)
+        .files
+        .into_iter()
+        .map(|(path, activity)| (path, activity.commit_count()))
+        .collect()
 }
 
-/// Count commits per file in the last N days using git log.
-fn git_commit_counts(repo_root: &Path, days: u32) -> anyhow::Result<HashMap<String, u32>> {
-    let output = Command::new("git")
-        .args([
-            "log",
-            "--format=",
-            "--name-only",
-            &format!("--since={days}.days"),
-        ])
-        .current_dir(repo_root)
-        .output()?;
-
-    if !output.status.success() {
-        // Not a git repo or git not available — return empty
-        return Ok(HashMap::new());
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut counts: HashMap<String, u32> = HashMap::new();
-
-    for line in stdout.lines() {
-        let trimmed = line.trim();
-        if !trimmed.is_empty() {
-            *counts.entry(trimmed.to_string()).or_default() += 1;
+/// Activity as of `head`: commits (newest first) per current path,
+/// followed by the commits in `previous` made at or after `cutoff`.
+///
+/// Renames are followed, including renames of paths in `previous`.
+fn collect_activity(
+    head: String,
+    commits: &[Commit],
+    previous: Option<&GitActivity>,
+    cutoff: u64,
+) -> GitActivity {
+    let mut files: HashMap<String, FileActivity> = HashMap::new();
+    let mut authors: Vec<String> = Vec::new();
+    let mut author_ids: HashMap<String, u32> = HashMap::new();
+    let mut author_id = |email: &str| -> u32 {
+        let email = email.to_lowercase();
+        *author_ids.entry(email).or_insert_with_key(|email| {
+            authors.push(email.clone());
+            authors.len() as u32 - 1
+        })
+    };
+    // Old path → path it was renamed to in a newer commit
+    let mut renamed_to: HashMap<String, String> = HashMap::new();
+
+    let resolve = |renamed_to: &HashMap<String, String>, path: &str| -> String {
+        let mut current = path;
+        // Bounded walk guards against pathological cycles
+        for _ in 0..renamed_to.len() {
+            match renamed_to.get(current) {
+                Some(next) => current = next,
+                None => break,
+            }
+        }
+        current.to_string()
+    };
+
+    for commit in commits {
+        for change in &commit.changes {
+            let path = resolve(&renamed_to, &change.path);
+            if change.status == ChangeStatus::Renamed
+                && let Some(from) = &change.old_path
+            {
+                renamed_to.insert(from.clone(), path.clone());
+            }
+            files.entry(path).or_default().commits.push(FileCommit {
+                timestamp: commit.timestamp,
+                churn: change
+                    .lines
+                    .map_or(0, |lines| lines.added.saturating_add(lines.removed)),
+                author: author_id(&commit.author_email),
+            });
+        }
+    }
+
+    // Older history is keyed by the paths at the previous head, and its
+    // authors numbered as they were then
+    if let Some(previous) = previous {
+        for (path, activity) in &previous.files {
+            let kept: Vec<FileCommit> = activity
+                .commits
+                .iter()
+                .filter(|c| c.timestamp >= cutoff)
+                .map(|c| FileCommit {
+                    author: author_id(
+                        previous
+                            .authors
+                            .get(c.author as usize)
+                            .map_or("", String::as_str),
+                    ),
+                    ..*c
+                })
+                .collect();
+            files
+                .entry(resolve(&renamed_to, path))
+                .or_default()
+                .commits
+                .extend(kept);
         }
     }
+    files.retain(|_, activity| !activity.commits.is_empty());
+    GitActivity {
+        head,
+        files,
+        authors,
+    }
+}
 
-    Ok(counts)
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
 }
 
 /// Score a single file's recency given the full recency map.
@@ -70,6 +338,8 @@ pub fn file_recency(scores: &HashMap<String, f64>, path: &str) -> f64 {
 mod tests {
     use super::*;
     use std::fs;
+    use std::process::Command;
+    use topo_vcs::FileChange;
 
     fn init_git_repo(dir: &Path) {
         Command::new("git")
@@ -167,6 +437,301 @@ mod tests {
         assert!(active_score > once_score);
     }
 
+    fn commit(changes: Vec<FileChange>) -> Commit {
+        Commit {
+            id: String::new(),
+            author_email: String::new(),
+            timestamp: 0,
+            changes,
+        }
+    }
+
+    #[test]
+    fn count_commits_follows_renames() {
+        // Newest commit first: a.rs was renamed to b.rs, then b.rs to c.rs
+        let commits = vec![
+            commit(vec![FileChange::new(ChangeStatus::Modified, "c.rs")]),
+            commit(vec![FileChange::moved(
+                ChangeStatus::Renamed,
+                "b.rs",
+                "c.rs",
+            )]),
+            commit(vec![FileChange::new(ChangeStatus::Modified, "b.rs")]),
+            commit(vec![FileChange::moved(
+                ChangeStatus::Renamed,
+                "a.rs",
+                "b.rs",
+            )]),
+            commit(vec![
+                FileChange::new(ChangeStatus::Added, "a.rs"),
+                FileChange::new(ChangeStatus::Added, "other.rs"),
+            ]),
+        ];
+        let counts = count_commits(&commits);
+        assert_eq!(counts.get("c.rs"), Some(&5));
+        assert_eq!(counts.get("other.rs"), Some(&1));
+        assert!(!counts.contains_key("a.rs"));
+        assert!(!counts.contains_key("b.rs"));
+    }
+
+    #[test]
+    fn count_commits_reused_path_after_rename() {
+        // a.rs renamed to b.rs, then a new a.rs created
+        let commits = vec![
+            commit(vec![FileChange::new(ChangeStatus::Added, "a.rs")]),
+            commit(vec![FileChange::moved(
+                ChangeStatus::Renamed,
+                "a.rs",
+                "b.rs",
+            )]),
+            commit(vec![FileChange::new(ChangeStatus::Added, "a.rs")]),
+        ];
+        let counts = count_commits(&commits);
+        assert_eq!(counts.get("a.rs"), Some(&1));
+        assert_eq!(counts.get("b.rs"), Some(&2));
+    }
+
+    #[test]
+    fn copies_do_not_redirect_history() {
+        let commits = vec![
+            commit(vec![FileChange::moved(
+                ChangeStatus::Copied,
+                "a.rs",
+                "b.rs",
+            )]),
+            commit(vec![FileChange::new(ChangeStatus::Added, "a.rs")]),
+        ];
+        let counts = count_commits(&commits);
+        assert_eq!(counts.get("a.rs"), Some(&1));
+        assert_eq!(counts.get("b.rs"), Some(&1));
+    }
+
+    #[test]
+    fn collect_activity_extends_previous_history() {
+        let previous = GitActivity {
+            head: "old".to_string(),
+            files: HashMap::from([
+                (
+                    "a.rs".to_string(),
+                    FileActivity {
+                        commits: vec![
+                            FileCommit {
+                                timestamp: 500,
+                                churn: 2,
+                                author: 0,
+                            },
+                            // Outside the window by now
+                            FileCommit {
+                                timestamp: 50,
+                                churn: 9,
+                                author: 1,
+                            },
+                        ],
+                    },
+                ),
+                (
+                    "stale.rs".to_string(),
+                    FileActivity {
+                        commits: vec![FileCommit {
+                            timestamp: 10,
+                            churn: 1,
+                            author: 1,
+                        }],
+                    },
+                ),
+            ]),
+            authors: vec!["old@x.com".to_string(), "gone@x.com".to_string()],
+        };
+        let mut renamed = commit(vec![FileChange::moved(
+            ChangeStatus::Renamed,
+            "a.rs",
+            "b.rs",
+        )]);
+        renamed.timestamp = 900;
+        renamed.author_email = "New@X.com".to_string();
+        renamed.changes[0].lines = Some(topo_vcs::LineCounts {
+            added: 3,
+            removed: 1,
+        });
+
+        let activity = collect_activity("new".to_string(), &[renamed], Some(&previous), 100);
+        assert_eq!(activity.files.len(), 1);
+        let moved = &activity.files["b.rs"];
+        assert_eq!(moved.commit_count(), 2);
+        assert_eq!(moved.last_modified(), Some(900));
+        assert_eq!(moved.churn(), 6);
+        // Authors are renumbered, and those of dropped commits left out
+        assert_eq!(activity.authors, ["new@x.com", "old@x.com"]);
+        let authors: Vec<u32> = moved.commits.iter().map(|c| c.author).collect();
+        assert_eq!(authors, [0, 1]);
+        assert_eq!(activity.author_id("OLD@x.com"), Some(1));
+    }
+
+    #[test]
+    fn git_activity_reads_only_new_commits() {
+        let dir = tempfile::tempdir().unwrap();
+        init_git_repo(dir.path());
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .args(args)
+                .current_dir(dir.path())
+                .output()
+                .unwrap();
+        };
+        fs::write(dir.path().join("a.rs"), "fn a() {}\n").unwrap();
+        git(&["add", "a.rs"]);
+        git(&["commit", "-m", "add a"]);
+
+        let first = git_activity(dir.path(), None, DEFAULT_LOOKBACK_DAYS)
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.files["a.rs"].commit_count(), 1);
+        assert_eq!(first.files["a.rs"].churn(), 1);
+        // Nothing new: the same history comes back
+        assert_eq!(
+            git_activity(dir.path(), Some(&first), DEFAULT_LOOKBACK_DAYS)
+                .unwrap()
+                .as_ref(),
+            Some(&first)
+        );
+
+        fs::write(dir.path().join("a.rs"), "fn a() {}\nfn b() {}\n").unwrap();
+        git(&["commit", "-am", "add b"]);
+        let second = git_activity(dir.path(), Some(&first), DEFAULT_LOOKBACK_DAYS)
+            .unwrap()
+            .unwrap();
+        assert_ne!(second.head, first.head);
+        assert_eq!(second.files["a.rs"].commit_count(), 2);
+        assert_eq!(second.files["a.rs"].churn(), 2);
+        assert!(activity_recency(&second, &RecencyModel::default())["a.rs"] > 0.99);
+
+        // An unknown head falls back to reading the whole window
+        let unknown = GitActivity {
+            head: "0".repeat(40),
+            ..first
+        };
+        let reread = git_activity(dir.path(), Some(&unknown), DEFAULT_LOOKBACK_DAYS)
+            .unwrap()
+            .unwrap();
+        assert_eq!(reread, second);
+    }
+
+    #[test]
+    fn recency_decays_with_age() {
+        const DAY: u64 = 24 * 60 * 60;
+        let now = 1000 * DAY;
+        let file = |ages: &[u64]| FileActivity {
+            commits: ages
+                .iter()
+                .map(|age| FileCommit {
+                    timestamp: now - age * DAY,
+                    churn: 1,
+                    author: 0,
+                })
+                .collect(),
+        };
+        let activity = GitActivity {
+            head: "head".to_string(),
+            files: HashMap::from([
+                ("fresh.rs".to_string(), file(&[0])),
+                ("busy.rs".to_string(), file(&[30, 30, 30, 30])),
+                ("stale.rs".to_string(), file(&[200])),
+            ]),
+            authors: vec!["me@x.com".to_string()],
+        };
+
+        let by_age = RecencyModel {
+            commit_weight: 0.0,
+            ..Default::default()
+        };
+        let scores = by_age.scores(&activity, now);
+        assert_eq!(scores["fresh.rs"], 1.0);
+        assert!((scores["busy.rs"] - 0.5).abs() < 1e-9, "one half-life old");
+        assert!(!scores.contains_key("stale.rs"), "outside the lookback");
+
+        // Four commits a half-life ago weigh more than one today
+        let by_count = RecencyModel {
+            commit_weight: 1.0,
+            ..Default::default()
+        };
+        let scores = by_count.scores(&activity, now);
+        assert_eq!(scores["busy.rs"], 1.0);
+        assert!(scores["fresh.rs"] < 1.0);
+
+        // A longer half-life and lookback keep older history relevant
+        let patient = RecencyModel {
+            half_life_days: 200.0,
+            lookback_days: 365,
+            commit_weight: 0.0,
+        };
+        assert!((patient.scores(&activity, now)["stale.rs"] - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn affinity_follows_the_authors_recent_commits() {
+        const DAY: u64 = 24 * 60 * 60;
+        let now = 1000 * DAY;
+        let commit = |age: u64, author: u32| FileCommit {
+            timestamp: now - age * DAY,
+            churn: 1,
+            author,
+        };
+        let file = |commits: Vec<FileCommit>| FileActivity { commits };
+        let activity = GitActivity {
+            head: "head".to_string(),
+            files: HashMap::from([
+                (
+                    "mine.rs".to_string(),
+                    file(vec![commit(0, 0), commit(1, 0)]),
+                ),
+                (
+                    "older.rs".to_string(),
+                    file(vec![commit(30, 0), commit(0, 1)]),
+                ),
+                ("theirs.rs".to_string(), file(vec![commit(0, 1)])),
+            ]),
+            authors: vec!["me@x.com".to_string(), "you@x.com".to_string()],
+        };
+        let model = RecencyModel::default();
+
+        let mine = model.affinity(&activity, "Me@X.com", now);
+        assert_eq!(mine["mine.rs"], 1.0);
+        assert!(mine["older.rs"] > 0.2 && mine["older.rs"] < 0.3);
+        assert!(!mine.contains_key("theirs.rs"));
+        assert!(model.affinity(&activity, "nobody@x.com", now).is_empty());
+    }
+
+    #[test]
+    fn recency_follows_git_mv() {
+        let dir = tempfile::tempdir().unwrap();
+        init_git_repo(dir.path());
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .args(args)
+                .current_dir(dir.path())
+                .output()
+                .unwrap();
+        };
+
+        for i in 0..3 {
+            fs::write(dir.path().join("auth.rs"), format!("fn v{i}() {{}}\n")).unwrap();
+            git(&["add", "auth.rs"]);
+            git(&["commit", "-m", &format!("auth v{i}")]);
+        }
+        fs::write(dir.path().join("other.rs"), "fn other() {}\n").unwrap();
+        git(&["add", "other.rs"]);
+        git(&["commit", "-m", "other"]);
+        fs::create_dir_all(dir.path().join("auth")).unwrap();
+        git(&["mv", "auth.rs", "auth/mod.rs"]);
+        git(&["commit", "-m", "move auth"]);
+
+        let scores = git_recency_scores(dir.path()).unwrap();
+        assert!(!scores.contains_key("auth.rs"));
+        let moved = scores.get("auth/mod.rs").copied().unwrap_or(0.0);
+        let other = scores.get("other.rs").copied().unwrap_or(0.0);
+        assert!(moved > other, "moved={moved} other={other}");
+    }
+
     #[test]
     fn file_recency_missing_file() {
         let scores = HashMap::new();
@@ -1,4 +1,6 @@
-use crate::tokenizer::Tokenizer;
+use crate::fuzzy::near_miss;
+use crate::synonyms::{EXPANSION_WEIGHT, Synonyms};
+use crate::tokenizer::{Tokenizer, stem};
 use topo_core::FileRole;
 
 /// Path-based heuristic scorer.
@@ -10,14 +12,69 @@ use topo_core::FileRole;
 /// - Size penalty (very large files penalized)
 /// - Well-known path bonus (src/, lib/, cmd/ get boost)
 pub struct HeuristicScorer {
+    /// Query terms as tokenized.
     query_tokens: Vec<String>,
+    /// Synonyms of each query term.
+    expansions: Vec<Vec<String>>,
+    /// Each query term and its synonyms, stemmed if asked.
+    terms: Vec<(String, Vec<String>)>,
+    stemming: bool,
+    fuzzy: bool,
 }
 
 impl HeuristicScorer {
     pub fn new(query: &str) -> Self {
-        Self {
-            query_tokens: Tokenizer::tokenize(query),
-        }
+        let query_tokens = Tokenizer::tokenize(query);
+        let mut scorer = Self {
+            expansions: vec![Vec::new(); query_tokens.len()],
+            query_tokens,
+            terms: Vec::new(),
+            stemming: false,
+            fuzzy: false,
+        };
+        scorer.match_terms();
+        scorer
+    }
+
+    /// Match query and path terms by their stems.
+    pub fn stemming(mut self, enabled: bool) -> Self {
+        self.stemming = enabled;
+        self.match_terms();
+        self
+    }
+
+    /// Also match the synonyms of query terms, at [`EXPANSION_WEIGHT`].
+    pub fn synonyms(mut self, synonyms: &Synonyms) -> Self {
+        self.expansions = self
+            .query_tokens
+            .iter()
+            .map(|token| synonyms.of(token).to_vec())
+            .collect();
+        self.match_terms();
+        self
+    }
+
+    /// Also match path terms a few edits from query terms, at their
+    /// similarity, see [`crate::near_miss`].
+    pub fn fuzzy(mut self, enabled: bool) -> Self {
+        self.fuzzy = enabled;
+        self
+    }
+
+    fn match_terms(&mut self) {
+        let term = |token: &String| {
+            if self.stemming {
+                stem(token)
+            } else {
+                token.clone()
+            }
+        };
+        self.terms = self
+            .query_tokens
+            .iter()
+            .zip(&self.expansions)
+            .map(|(token, expansions)| (term(token), expansions.iter().map(term).collect()))
+            .collect();
     }
 
     /// Score a file path. Returns a value in [0.0, 1.0].
@@ -25,7 +82,7 @@ impl HeuristicScorer {
         let mut score = 0.0;
 
         // 1. Keyword match bonus (0.0 - 0.4)
-        score += self.keyword_score(path) * 0.4;
+        score += self.keyword_bonus(path);
 
         // 2. File role bonus (0.0 - 0.25)
         score += role_score(role) * 0.25;
@@ -42,20 +99,43 @@ impl HeuristicScorer {
         score.clamp(0.0, 1.0)
     }
 
-    /// Fraction of query tokens found in the path.
+    /// The part of [`HeuristicScorer::score`] for query terms in `path`,
+    /// from 0.0 to 0.4.
+    pub fn keyword_bonus(&self, path: &str) -> f64 {
+        self.keyword_score(path) * 0.4
+    }
+
+    /// Fraction of query tokens found in the path, those found only by a
+    /// synonym counting [`EXPANSION_WEIGHT`], and only by a near miss its
+    /// similarity.
     fn keyword_score(&self, path: &str) -> f64 {
-        if self.query_tokens.is_empty() {
+        if self.terms.is_empty() {
             return 0.0;
         }
 
-        let path_tokens = Tokenizer::tokenize(path);
-        let matches = self
-            .query_tokens
+        let path_tokens = Tokenizer::terms(path, self.stemming);
+        let found = |term: &String| path_tokens.contains(term);
+        let matches: f64 = self
+            .terms
             .iter()
-            .filter(|qt| path_tokens.iter().any(|pt| pt == *qt))
-            .count();
-
-        matches as f64 / self.query_tokens.len() as f64
+            .map(|(term, expansions)| {
+                if found(term) {
+                    return 1.0;
+                }
+                let synonym = if expansions.iter().any(found) {
+                    EXPANSION_WEIGHT
+                } else {
+                    0.0
+                };
+                path_tokens
+                    .iter()
+                    .filter(|_| self.fuzzy)
+                    .filter_map(|token| near_miss(term, token))
+                    .fold(synonym, f64::max)
+            })
+            .sum();
+
+        matches / self.terms.len() as f64
     }
 }
 
@@ -1,27 +1,250 @@
-use crate::bm25f::{Bm25fScorer, CorpusStats};
+use crate::bm25f::{Bm25fScorer, CorpusStats, FieldWeights};
+use crate::coupling::{Coupling, TestPairs};
+use crate::duplicates::Duplicates;
 use crate::heuristic::HeuristicScorer;
+use crate::preset::ScoringPreset;
+use crate::preview::Previewer;
+use crate::query::{ParsedQuery, QueryFilters};
+use crate::synonyms::Synonyms;
+use globset::{Glob, GlobMatcher};
+use rayon::prelude::*;
 use std::collections::HashMap;
 use topo_core::{FileInfo, ScoredFile, SignalBreakdown};
 
-/// Default weight for BM25F in hybrid scoring.
-const DEFAULT_BM25F_WEIGHT: f64 = 0.6;
-/// Default weight for heuristic in hybrid scoring.
-const DEFAULT_HEURISTIC_WEIGHT: f64 = 0.4;
+/// Fewest files scored by one rayon task, so small candidate sets aren't
+/// spread over threads for nothing.
+const MIN_FILES_PER_TASK: usize = 256;
+
+/// Scorers of a query's `-term` words, whose matches count against files.
+type Penalties = Option<(Bm25fScorer, HeuristicScorer)>;
+
+/// Globs restricting the files a query scores, any of which may match.
+/// Empty scopes hold every file.
+#[derive(Debug, Clone, Default)]
+pub struct PathScope {
+    globs: Vec<GlobMatcher>,
+    /// Directory the globs' paths are relative to, when it isn't the
+    /// files' own root.
+    prefix: String,
+}
+
+impl PathScope {
+    pub fn new(globs: &[Glob]) -> Self {
+        Self {
+            globs: globs.iter().map(Glob::compile_matcher).collect(),
+            prefix: String::new(),
+        }
+    }
+
+    /// Match paths as if they were under `prefix`, for files of a
+    /// repository nested there in the one the globs were written for.
+    pub fn under(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.globs.is_empty()
+    }
+
+    /// Whether the file at `path` is in scope.
+    pub fn contains(&self, path: &str) -> bool {
+        if self.globs.is_empty() {
+            return true;
+        }
+        if self.prefix.is_empty() {
+            return self.globs.iter().any(|g| g.is_match(path));
+        }
+        let path = format!("{}/{path}", self.prefix);
+        self.globs.iter().any(|g| g.is_match(&path))
+    }
+}
 
 /// Hybrid scorer combining BM25F (content relevance) and heuristic (path-based) signals.
+///
+/// Weighed as [`ScoringPreset::BALANCED`] unless set otherwise.
 pub struct HybridScorer {
     bm25f_weight: f64,
     heuristic_weight: f64,
+    field_weights: FieldWeights,
     query: String,
+    /// Extra terms (e.g. fuzzy matches) scored alongside the query, with
+    /// their weights.
+    expansions: Vec<(String, f64)>,
+    stemming: bool,
+    synonyms: Synonyms,
+    fuzzy: bool,
+    /// Field filters of the query, restricting the files scored.
+    filters: QueryFilters,
+    /// Terms whose matches lower a file's score, from `-term` words.
+    penalties: Vec<String>,
+    /// Globs restricting the files scored.
+    scope: PathScope,
+    /// Path corpus statistics of all the files scored, computed earlier.
+    corpus_stats: Option<CorpusStats>,
+    /// Weights of query terms other than 1.0.
+    query_weights: Vec<(String, f64)>,
+    coupling: Coupling,
+    /// Import edges (path → imported paths) coupling tests to the files
+    /// they cover.
+    imports: HashMap<String, Vec<String>>,
 }
 
 impl HybridScorer {
+    /// Scorer for `query`, whose field filters like `lang:rust` restrict
+    /// the files scored and whose `-term` words penalize, see
+    /// [`ParsedQuery::parse`].
     pub fn new(query: &str) -> Self {
+        let ParsedQuery {
+            text,
+            filters,
+            penalties,
+        } = ParsedQuery::parse(query);
         Self {
-            bm25f_weight: DEFAULT_BM25F_WEIGHT,
-            heuristic_weight: DEFAULT_HEURISTIC_WEIGHT,
-            query: query.to_string(),
+            bm25f_weight: ScoringPreset::BALANCED.bm25f_weight,
+            heuristic_weight: ScoringPreset::BALANCED.heuristic_weight,
+            field_weights: FieldWeights::default(),
+            query: text,
+            expansions: Vec::new(),
+            stemming: false,
+            synonyms: Synonyms::none(),
+            fuzzy: false,
+            filters,
+            penalties,
+            scope: PathScope::default(),
+            corpus_stats: None,
+            query_weights: Vec::new(),
+            coupling: Coupling::Off,
+            imports: HashMap::new(),
+        }
+    }
+
+    /// The files passing the query's filters and scope.
+    fn candidates<'f>(&self, files: &'f [FileInfo]) -> Vec<&'f FileInfo> {
+        files
+            .iter()
+            .filter(|f| self.filters.matches(&f.path, f))
+            .filter(|f| self.scope.contains(&f.path))
+            .collect()
+    }
+
+    /// Score only files matching any of `globs`, like `src/payments/**`.
+    /// Corpus statistics are computed over these files alone, so a term
+    /// common elsewhere in a monorepo still tells files in scope apart.
+    pub fn scoped_to(self, globs: &[Glob]) -> Self {
+        self.scope(PathScope::new(globs))
+    }
+
+    /// Score only files in `scope`, as [`Self::scoped_to`] does, e.g. with
+    /// globs written for the repository the files are nested in.
+    pub fn scope(mut self, scope: PathScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Use `stats`, the [`CorpusStats::path_stats`] of every file given to
+    /// [`Self::score`], rather than computing them again, e.g. when they
+    /// are cached between queries. They are ignored when filters or a
+    /// scope leave some files out.
+    pub fn corpus_stats(mut self, stats: CorpusStats) -> Self {
+        self.corpus_stats = Some(stats);
+        self
+    }
+
+    /// Lower the scores of files matching these terms by what they would
+    /// add as query terms, as `-term` words in the query do, e.g. for
+    /// penalties parsed out of the query beforehand.
+    pub fn penalize(mut self, terms: impl IntoIterator<Item = String>) -> Self {
+        self.penalties.extend(terms);
+        self
+    }
+
+    /// Scorers of the penalized terms, if any.
+    fn penalties(&self, stats: &CorpusStats) -> Penalties {
+        if self.penalties.is_empty() {
+            return None;
         }
+        let terms = self.penalties.join(" ");
+        Some((
+            Bm25fScorer::new(&terms, stats.clone())
+                .field_weights(self.field_weights)
+                .stemming(self.stemming),
+            HeuristicScorer::new(&terms).stemming(self.stemming),
+        ))
+    }
+
+    /// Weigh these query terms at their weights in BM25F rather than 1.0,
+    /// e.g. the terms of a [`crate::TermVector`].
+    pub fn query_weights(mut self, weights: Vec<(String, f64)>) -> Self {
+        self.query_weights = weights;
+        self
+    }
+
+    /// Match query terms by their stems, in paths and in term frequencies
+    /// from an index built with stemming.
+    pub fn stemming(mut self, enabled: bool) -> Self {
+        self.stemming = enabled;
+        self
+    }
+
+    /// Expand query terms with these abbreviations and synonyms, scored at
+    /// [`crate::EXPANSION_WEIGHT`].
+    pub fn synonyms(mut self, synonyms: Synonyms) -> Self {
+        self.synonyms = synonyms;
+        self
+    }
+
+    /// Score these terms alongside the query, e.g. fuzzy candidates for
+    /// query terms that don't appear in the corpus.
+    pub fn expand(self, terms: impl IntoIterator<Item = String>) -> Self {
+        self.expand_weighted(terms.into_iter().map(|term| (term, 1.0)))
+    }
+
+    /// Score these terms alongside the query at their weights, e.g. fuzzy
+    /// candidates at their similarity to the query terms they stand for.
+    pub fn expand_weighted(mut self, terms: impl IntoIterator<Item = (String, f64)>) -> Self {
+        self.expansions.extend(terms);
+        self
+    }
+
+    /// Match query terms to near misses a few edits away, for typos, at
+    /// their similarity. Path-only [`HybridScorer::score`] looks for them
+    /// among path terms; [`HybridScorer::score_with_index`] only in paths
+    /// for its heuristic, and takes indexed terms from
+    /// [`HybridScorer::expand_weighted`].
+    pub fn fuzzy(mut self, enabled: bool) -> Self {
+        self.fuzzy = enabled;
+        self
+    }
+
+    /// Boost tests coupled to implementation files matching the query, or
+    /// both ways, see [`TestPairs::detect`].
+    pub fn coupling(mut self, coupling: Coupling) -> Self {
+        self.coupling = coupling;
+        self
+    }
+
+    /// Couple tests to the files they import, with the file-level
+    /// dependency graph of a deep index.
+    pub fn imports(mut self, imports: HashMap<String, Vec<String>>) -> Self {
+        self.imports = imports;
+        self
+    }
+
+    /// Apply the coupling boosts to `scored`, in the order of `files`.
+    fn couple(&self, files: &[&FileInfo], scored: &mut [ScoredFile]) {
+        if self.coupling != Coupling::Off {
+            TestPairs::detect(files, &self.imports).apply(self.coupling, scored);
+        }
+    }
+
+    /// The query plus any expansion terms.
+    fn scoring_query(&self) -> String {
+        if self.expansions.is_empty() {
+            return self.query.clone();
+        }
+        let terms: Vec<&str> = self.expansions.iter().map(|(t, _)| t.as_str()).collect();
+        format!("{} {}", self.query, terms.join(" "))
     }
 
     /// Set custom weights. They will be normalized to sum to 1.0.
@@ -34,101 +257,153 @@ impl HybridScorer {
         self
     }
 
+    /// Weigh BM25F and the heuristic as `preset` does.
+    pub fn preset(self, preset: &ScoringPreset) -> Self {
+        self.weights(preset.bm25f_weight, preset.heuristic_weight)
+    }
+
+    /// Set the BM25F field weights used for indexed term frequencies.
+    pub fn field_weights(mut self, weights: FieldWeights) -> Self {
+        self.field_weights = weights;
+        self
+    }
+
+    /// Previewer for the same query, to explain matches in selected files.
+    pub fn previewer(&self) -> Previewer {
+        Previewer::new(&self.scoring_query())
+    }
+
+    /// `f` with its BM25F and heuristic scores weighed together, less
+    /// `penalty`.
+    fn scored(&self, f: &FileInfo, bm25f: f64, heuristic: f64, penalty: f64) -> ScoredFile {
+        let combined = self.bm25f_weight * bm25f + self.heuristic_weight * heuristic;
+        ScoredFile {
+            path: f.path.clone(),
+            score: (combined - penalty).max(0.0),
+            signals: SignalBreakdown {
+                bm25f,
+                heuristic,
+                penalty: (penalty > 0.0).then_some(penalty),
+                ..SignalBreakdown::default()
+            },
+            tokens: f.estimated_tokens(),
+            language: f.language,
+            role: f.role,
+            preview: None,
+        }
+    }
+
     /// Score a set of files and return them sorted by score (descending).
-    pub fn score(&self, files: &[FileInfo]) -> Vec<ScoredFile> {
+    pub fn score(&self, all: &[FileInfo]) -> Vec<ScoredFile> {
+        let files = self.candidates(all);
         if files.is_empty() {
             return Vec::new();
         }
 
         // Build BM25F corpus stats from file paths (shallow mode)
-        let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
-        let stats = CorpusStats::from_paths(&paths);
-        let bm25f = Bm25fScorer::new(&self.query, stats);
-        let heuristic = HeuristicScorer::new(&self.query);
+        let stats = match &self.corpus_stats {
+            Some(stats) if files.len() == all.len() => stats.clone(),
+            _ => {
+                let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+                CorpusStats::path_stats(&paths, self.stemming)
+            }
+        };
+        let penalties = self.penalties(&stats);
+        let bm25f = Bm25fScorer::new(&self.query, stats)
+            .field_weights(self.field_weights)
+            .expand(self.expansions.iter().cloned())
+            .stemming(self.stemming)
+            .synonyms(&self.synonyms)
+            .query_weights(&self.query_weights)
+            .fuzzy(self.fuzzy);
+        let heuristic = HeuristicScorer::new(&self.scoring_query())
+            .stemming(self.stemming)
+            .synonyms(&self.synonyms)
+            .fuzzy(self.fuzzy);
 
         let mut scored: Vec<ScoredFile> = files
-            .iter()
+            .par_iter()
+            .with_min_len(MIN_FILES_PER_TASK)
             .map(|f| {
                 let bm25f_score = bm25f.score_path(&f.path);
                 let heuristic_score = heuristic.score(&f.path, f.role, f.size);
+                let penalty = penalties.as_ref().map_or(0.0, |(bm25f, heuristic)| {
+                    self.bm25f_weight * bm25f.score_path(&f.path)
+                        + self.heuristic_weight * heuristic.keyword_bonus(&f.path)
+                });
 
-                let combined =
-                    self.bm25f_weight * bm25f_score + self.heuristic_weight * heuristic_score;
-
-                ScoredFile {
-                    path: f.path.clone(),
-                    score: combined,
-                    signals: SignalBreakdown {
-                        bm25f: bm25f_score,
-                        heuristic: heuristic_score,
-                        pagerank: None,
-                        git_recency: None,
-                        embedding: None,
-                    },
-                    tokens: f.estimated_tokens(),
-                    language: f.language,
-                    role: f.role,
-                }
+                self.scored(f, bm25f_score, heuristic_score, penalty)
             })
             .collect();
+        self.couple(&files, &mut scored);
+        Duplicates::new(files.iter().copied()).apply(&mut scored);
 
-        scored.sort_by(|a, b| {
-            b.score
-                .partial_cmp(&a.score)
-                .unwrap_or(std::cmp::Ordering::Equal)
-        });
+        // Stable, so ties keep the order files were given in
+        scored.sort_by(|a, b| b.score.total_cmp(&a.score));
         scored
     }
 
-    /// Score files with full term frequencies from the deep index.
+    /// Score files with full term frequencies from the deep index. With a
+    /// scope, `stats` should cover only the files in it, see
+    /// [`CorpusStats::from_documents`].
     pub fn score_with_index(
         &self,
         files: &[FileInfo],
         term_freqs: &HashMap<String, (HashMap<String, topo_core::TermFreqs>, u32)>,
         stats: CorpusStats,
     ) -> Vec<ScoredFile> {
+        let mut files = self.candidates(files);
+        // `!term` words leave out files with the term anywhere indexed,
+        // not only in their path
+        files.retain(|f| {
+            term_freqs
+                .get(&f.path)
+                .is_none_or(|(tf, _)| !self.filters.excludes_terms(tf, self.stemming))
+        });
         if files.is_empty() {
             return Vec::new();
         }
 
-        let bm25f = Bm25fScorer::new(&self.query, stats);
-        let heuristic = HeuristicScorer::new(&self.query);
+        let penalties = self.penalties(&stats);
+        let bm25f = Bm25fScorer::new(&self.query, stats)
+            .field_weights(self.field_weights)
+            .expand(self.expansions.iter().cloned())
+            .stemming(self.stemming)
+            .synonyms(&self.synonyms)
+            .query_weights(&self.query_weights);
+        let heuristic = HeuristicScorer::new(&self.scoring_query())
+            .stemming(self.stemming)
+            .synonyms(&self.synonyms)
+            .fuzzy(self.fuzzy);
 
         let mut scored: Vec<ScoredFile> = files
-            .iter()
+            .par_iter()
+            .with_min_len(MIN_FILES_PER_TASK)
             .map(|f| {
-                let bm25f_score = if let Some((tf, dl)) = term_freqs.get(&f.path) {
+                let indexed = term_freqs.get(&f.path);
+                let bm25f_score = if let Some((tf, dl)) = indexed {
                     bm25f.score(tf, *dl)
                 } else {
                     bm25f.score_path(&f.path)
                 };
                 let heuristic_score = heuristic.score(&f.path, f.role, f.size);
-
-                let combined =
-                    self.bm25f_weight * bm25f_score + self.heuristic_weight * heuristic_score;
-
-                ScoredFile {
-                    path: f.path.clone(),
-                    score: combined,
-                    signals: SignalBreakdown {
-                        bm25f: bm25f_score,
-                        heuristic: heuristic_score,
-                        pagerank: None,
-                        git_recency: None,
-                        embedding: None,
-                    },
-                    tokens: f.estimated_tokens(),
-                    language: f.language,
-                    role: f.role,
-                }
+                let penalty = penalties.as_ref().map_or(0.0, |(bm25f, heuristic)| {
+                    let matched = match indexed {
+                        Some((tf, dl)) => bm25f.score(tf, *dl),
+                        None => bm25f.score_path(&f.path),
+                    };
+                    self.bm25f_weight * matched
+                        + self.heuristic_weight * heuristic.keyword_bonus(&f.path)
+                });
+
+                self.scored(f, bm25f_score, heuristic_score, penalty)
             })
             .collect();
+        self.couple(&files, &mut scored);
+        Duplicates::new(files.iter().copied()).apply(&mut scored);
 
-        scored.sort_by(|a, b| {
-            b.score
-                .partial_cmp(&a.score)
-                .unwrap_or(std::cmp::Ordering::Equal)
-        });
+        // Stable, so ties keep the order files were given in
+        scored.sort_by(|a, b| b.score.total_cmp(&a.score));
         scored
     }
 }
@@ -136,7 +411,7 @@ impl HybridScorer {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use topo_core::{FileRole, Language};
+    use topo_core::{FileRole, Language, TermFreqs};
 
     fn sample_files() -> Vec<FileInfo> {
         vec![
@@ -146,6 +421,7 @@ mod tests {
                 language: Language::Rust,
                 role: FileRole::Implementation,
                 sha256: [0u8; 32],
+                package: None,
             },
             FileInfo {
                 path: "src/auth/middleware.rs".to_string(),
@@ -153,6 +429,7 @@ mod tests {
                 language: Language::Rust,
                 role: FileRole::Implementation,
                 sha256: [0u8; 32],
+                package: None,
             },
             FileInfo {
                 path: "src/db/connection.rs".to_string(),
@@ -160,6 +437,7 @@ mod tests {
                 language: Language::Rust,
                 role: FileRole::Implementation,
                 sha256: [0u8; 32],
+                package: None,
             },
             FileInfo {
                 path: "tests/auth_test.rs".to_string(),
@@ -167,6 +445,7 @@ mod tests {
                 language: Language::Rust,
                 role: FileRole::Test,
                 sha256: [0u8; 32],
+                package: None,
             },
             FileInfo {
                 path: "README.md".to_string(),
@@ -174,6 +453,7 @@ mod tests {
                 language: Language::Markdown,
                 role: FileRole::Documentation,
                 sha256: [0u8; 32],
+                package: None,
             },
         ]
     }
@@ -190,6 +470,215 @@ mod tests {
         }
     }
 
+    #[test]
+    fn stemming_matches_other_word_forms() {
+        let plain = HybridScorer::new("connecting").score(&sample_files());
+        assert!(plain.iter().all(|f| f.signals.bm25f == 0.0));
+
+        let stemmed = HybridScorer::new("connecting")
+            .stemming(true)
+            .score(&sample_files());
+        assert_eq!(stemmed[0].path, "src/db/connection.rs");
+        assert!(stemmed[0].signals.bm25f > 0.0);
+    }
+
+    #[test]
+    fn synonyms_match_abbreviations() {
+        let plain = HybridScorer::new("mw").score(&sample_files());
+        assert!(plain.iter().all(|f| f.signals.bm25f == 0.0));
+
+        let expanded = HybridScorer::new("mw")
+            .synonyms(Synonyms::builtin())
+            .score(&sample_files());
+        assert_eq!(expanded[0].path, "src/auth/middleware.rs");
+        assert!(expanded[0].signals.bm25f > 0.0);
+    }
+
+    #[test]
+    fn query_filters_restrict_the_candidates() {
+        let results = HybridScorer::new("lang:rust path:src auth").score(&sample_files());
+        let paths: Vec<&str> = results.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths.len(), 3);
+        assert!(paths.iter().all(|p| p.starts_with("src/")));
+        assert!(results[0].path.starts_with("src/auth/"));
+
+        let tests = HybridScorer::new("role:test auth").score(&sample_files());
+        assert_eq!(tests.len(), 1);
+        assert_eq!(tests[0].path, "tests/auth_test.rs");
+        assert!(tests[0].signals.bm25f > 0.0);
+    }
+
+    #[test]
+    fn scope_restricts_the_corpus() {
+        let everywhere = HybridScorer::new("auth handler").score(&sample_files());
+        let scoped = HybridScorer::new("auth handler")
+            .scoped_to(&[Glob::new("src/auth/**").unwrap()])
+            .score(&sample_files());
+        let paths: Vec<&str> = scoped.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["src/auth/handler.rs", "src/auth/middleware.rs"]);
+
+        // `auth` is in every file in scope, so it no longer tells them apart
+        let bm25f = |results: &[ScoredFile], path: &str| {
+            results
+                .iter()
+                .find(|f| f.path == path)
+                .unwrap()
+                .signals
+                .bm25f
+        };
+        assert!(bm25f(&everywhere, "src/auth/middleware.rs") > 0.0);
+        assert!(
+            bm25f(&scoped, "src/auth/middleware.rs") < bm25f(&everywhere, "src/auth/middleware.rs")
+        );
+
+        // Globs of the repository the files are nested in
+        let nested = HybridScorer::new("auth handler")
+            .scope(
+                PathScope::new(&[Glob::new("vendor/lib/src/auth/**").unwrap()]).under("vendor/lib"),
+            )
+            .score(&sample_files());
+        assert_eq!(nested.len(), 2);
+    }
+
+    #[test]
+    fn large_candidate_sets_rank_deterministically() {
+        let files: Vec<FileInfo> = (0..2000)
+            .map(|i| FileInfo {
+                path: format!("src/mod{}/file{i}.rs", i % 7),
+                ..sample_files()[0].clone()
+            })
+            .collect();
+        let results = HybridScorer::new("mod3 file").score(&files);
+        assert_eq!(results.len(), files.len());
+        assert!(results.windows(2).all(|w| w[0].score >= w[1].score));
+        let paths = |results: &[ScoredFile]| -> Vec<String> {
+            results.iter().map(|f| f.path.clone()).collect()
+        };
+        assert_eq!(
+            paths(&results),
+            paths(&HybridScorer::new("mod3 file").score(&files))
+        );
+
+        // Ties keep the order the files were given in
+        let tied: Vec<usize> = results
+            .iter()
+            .take_while(|f| f.score == results[0].score)
+            .map(|f| files.iter().position(|g| g.path == f.path).unwrap())
+            .collect();
+        assert!(tied.len() > 1);
+        assert!(tied.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn precomputed_stats_apply_to_all_files_only() {
+        let files = sample_files();
+        let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        let computed = HybridScorer::new("auth").score(&files);
+        let given = HybridScorer::new("auth")
+            .corpus_stats(CorpusStats::path_stats(&paths, false))
+            .score(&files);
+        assert_eq!(given[0].signals.bm25f, computed[0].signals.bm25f);
+
+        // Stats of the whole repository don't fit a scoped query
+        let scoped = |scorer: HybridScorer| {
+            scorer
+                .scoped_to(&[Glob::new("src/auth/**").unwrap()])
+                .score(&files)[0]
+                .signals
+                .bm25f
+        };
+        let stats = CorpusStats::path_stats(&paths, false);
+        assert_eq!(
+            scoped(HybridScorer::new("auth").corpus_stats(stats)),
+            scoped(HybridScorer::new("auth"))
+        );
+    }
+
+    #[test]
+    fn negated_terms_lower_or_exclude_files() {
+        let score = |query: &str, path: &str| {
+            HybridScorer::new(query)
+                .score(&sample_files())
+                .into_iter()
+                .find(|f| f.path == path)
+        };
+        let plain = score("auth", "src/auth/middleware.rs").unwrap();
+        let penalized = score("auth -middleware", "src/auth/middleware.rs").unwrap();
+        assert!(penalized.score < plain.score);
+        assert!(penalized.signals.penalty.is_some());
+        let untouched = score("auth -middleware", "src/auth/handler.rs").unwrap();
+        assert_eq!(untouched.signals.penalty, None);
+        assert_eq!(
+            untouched.score,
+            score("auth", "src/auth/handler.rs").unwrap().score
+        );
+        assert!(score("auth !middleware", "src/auth/middleware.rs").is_none());
+
+        // Penalties parsed out of the query beforehand weigh the same
+        let given = HybridScorer::new("auth")
+            .penalize(["middleware".to_string()])
+            .score(&sample_files());
+        let given = given.iter().find(|f| f.path == "src/auth/middleware.rs");
+        assert_eq!(given.unwrap().score, penalized.score);
+    }
+
+    #[test]
+    fn excluded_terms_drop_files_by_their_content() {
+        let files = sample_files();
+        let body = |terms: &[&str]| {
+            let tf: HashMap<String, TermFreqs> = terms
+                .iter()
+                .map(|t| {
+                    let freqs = TermFreqs {
+                        body: 1,
+                        ..TermFreqs::default()
+                    };
+                    (t.to_string(), freqs)
+                })
+                .collect();
+            (tf, terms.len() as u32)
+        };
+        let term_freqs = HashMap::from([
+            ("src/auth/handler.rs".to_string(), body(&["auth", "legacy"])),
+            ("src/auth/middleware.rs".to_string(), body(&["auth"])),
+        ]);
+        let stats = CorpusStats::from_documents(
+            term_freqs
+                .iter()
+                .map(|(path, (tf, len))| (path.as_str(), tf, *len)),
+        );
+        let paths = |query: &str| -> Vec<String> {
+            HybridScorer::new(query)
+                .score_with_index(&files, &term_freqs, stats.clone())
+                .into_iter()
+                .map(|f| f.path)
+                .collect()
+        };
+
+        assert!(paths("auth").contains(&"src/auth/handler.rs".to_string()));
+        let excluded = paths("auth !legacy");
+        assert!(!excluded.contains(&"src/auth/handler.rs".to_string()));
+        assert!(excluded.contains(&"src/auth/middleware.rs".to_string()));
+    }
+
+    #[test]
+    fn coupled_tests_follow_matching_files() {
+        let rank = |results: &[ScoredFile]| {
+            results
+                .iter()
+                .position(|f| f.path == "tests/auth_test.rs")
+                .unwrap()
+        };
+        let plain = HybridScorer::new("middleware").score(&sample_files());
+        let coupled = HybridScorer::new("middleware")
+            .coupling(Coupling::Tests)
+            .score(&sample_files());
+        assert!(plain[rank(&plain)].signals.coupling.is_none());
+        assert!(coupled[rank(&coupled)].signals.coupling.is_some());
+        assert!(rank(&coupled) < rank(&plain));
+        assert_eq!(coupled[0].path, "src/auth/middleware.rs");
+    }
+
     #[test]
     fn hybrid_relevant_files_rank_higher() {
         let scorer = HybridScorer::new("auth");
@@ -201,6 +690,44 @@ mod tests {
         assert!(top_paths.contains(&"src/auth/middleware.rs"));
     }
 
+    #[test]
+    fn hybrid_expansion_terms_are_scored() {
+        let plain = HybridScorer::new("middlware").score(&sample_files());
+        assert!(plain.iter().all(|f| f.signals.bm25f == 0.0));
+
+        let expanded = HybridScorer::new("middlware")
+            .expand(["middleware".to_string()])
+            .score(&sample_files());
+        assert_eq!(expanded[0].path, "src/auth/middleware.rs");
+        assert!(expanded[0].signals.bm25f > 0.0);
+    }
+
+    #[test]
+    fn fuzzy_matches_typos_at_reduced_weight() {
+        let fuzzy = HybridScorer::new("middlware")
+            .fuzzy(true)
+            .score(&sample_files());
+        assert_eq!(fuzzy[0].path, "src/auth/middleware.rs");
+        assert!(fuzzy[0].signals.bm25f > 0.0);
+
+        let exact = HybridScorer::new("middleware").score(&sample_files());
+        assert_eq!(exact[0].path, "src/auth/middleware.rs");
+        assert!(fuzzy[0].signals.bm25f < exact[0].signals.bm25f);
+        assert!(fuzzy[0].signals.heuristic < exact[0].signals.heuristic);
+    }
+
+    #[test]
+    fn weighted_expansions_scale_scores() {
+        let full = HybridScorer::new("middlware")
+            .expand(["middleware".to_string()])
+            .score(&sample_files());
+        let scaled = HybridScorer::new("middlware")
+            .expand_weighted([("middleware".to_string(), 0.5)])
+            .score(&sample_files());
+        assert_eq!(scaled[0].path, "src/auth/middleware.rs");
+        assert!((scaled[0].signals.bm25f - full[0].signals.bm25f / 2.0).abs() < 1e-9);
+    }
+
     #[test]
     fn hybrid_signals_populated() {
         let scorer = HybridScorer::new("auth");
@@ -237,6 +764,19 @@ mod tests {
         assert_eq!(heuristic_only[0].signals.heuristic, heuristic_only[0].score);
     }
 
+    #[test]
+    fn presets_set_the_weights() {
+        let fast = HybridScorer::new("auth")
+            .preset(&ScoringPreset::FAST)
+            .score(&sample_files());
+        assert!(fast.iter().all(|f| f.score == f.signals.heuristic));
+        let balanced = HybridScorer::new("auth")
+            .preset(&ScoringPreset::BALANCED)
+            .score(&sample_files());
+        let default = HybridScorer::new("auth").score(&sample_files());
+        assert_eq!(balanced[0].score, default[0].score);
+    }
+
     #[test]
     fn hybrid_empty_files() {
         let scorer = HybridScorer::new("auth");
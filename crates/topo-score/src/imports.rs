@@ -0,0 +1,624 @@
+use std::collections::{HashMap, HashSet};
+use topo_core::Language;
+
+/// Extensions of the files C and C++ code includes.
+const C_INCLUDE_EXTENSIONS: &[&str] = &[
+    "h", "hh", "hpp", "hxx", "h++", "inc", "inl", "ipp", "tpp", "def", "c", "cc", "cpp", "cxx",
+];
+
+/// Resolves import statements to the repository files they load.
+///
+/// Best effort, from paths alone: Rust `mod` and `use` (`crate::`,
+/// `self::`, `super::`, and workspace crates), relative JavaScript and
+/// TypeScript imports, Python modules (absolute and relative), Go
+/// packages, matched by the trailing components of their import path, and
+/// quoted C and C++ includes, see [`c_include`]. Standard library and
+/// third-party imports don't resolve.
+pub struct ImportResolver {
+    files: HashSet<String>,
+    /// Directories holding Go files.
+    go_packages: HashSet<String>,
+    /// File name → files C and C++ code can include by that name.
+    c_includes: HashMap<String, Vec<String>>,
+}
+
+impl ImportResolver {
+    /// Resolver for a repository holding `paths`.
+    pub fn new<'a>(paths: impl IntoIterator<Item = &'a str>) -> Self {
+        let files: HashSet<String> = paths.into_iter().map(str::to_string).collect();
+        let go_packages = files
+            .iter()
+            .filter(|path| path.ends_with(".go"))
+            .map(|path| parent(path).to_string())
+            .collect();
+        let mut c_includes: HashMap<String, Vec<String>> = HashMap::new();
+        for path in &files {
+            let name = file_name(path);
+            let includable = name
+                .rsplit_once('.')
+                .is_some_and(|(_, ext)| C_INCLUDE_EXTENSIONS.contains(&ext));
+            if includable {
+                c_includes
+                    .entry(name.to_string())
+                    .or_default()
+                    .push(path.clone());
+            }
+        }
+        Self {
+            files,
+            go_packages,
+            c_includes,
+        }
+    }
+
+    /// Repository-relative path of the file `statement`, an import in
+    /// `importing_file`, loads; the package directory for Go. None for
+    /// imports from outside the repository and ones that can't be resolved.
+    pub fn resolve(
+        &self,
+        statement: &str,
+        importing_file: &str,
+        language: Language,
+    ) -> Option<String> {
+        let target = match language {
+            Language::Rust => self.resolve_rust(statement, importing_file),
+            Language::JavaScript | Language::TypeScript => {
+                self.resolve_js(statement, importing_file)
+            }
+            Language::Python => self.resolve_python(statement, importing_file),
+            Language::Go => self.resolve_go(statement),
+            Language::C | Language::Cpp => self.resolve_c(statement, importing_file),
+            _ => None,
+        }?;
+        (target != importing_file).then_some(target)
+    }
+
+    fn has(&self, path: &str) -> bool {
+        self.files.contains(path)
+    }
+
+    /// The first of `candidates` that is a file of the repository.
+    fn first(&self, candidates: impl IntoIterator<Item = String>) -> Option<String> {
+        candidates.into_iter().find(|c| self.has(c))
+    }
+
+    // ── Rust ───────────────────────────────────────────────────────
+
+    fn resolve_rust(&self, statement: &str, importing_file: &str) -> Option<String> {
+        let statement = strip_visibility(statement.trim().trim_end_matches(';').trim());
+        let module_dir = rust_module_dir(importing_file);
+        if let Some(name) = statement.strip_prefix("mod ") {
+            return self.rust_module(&module_dir, &[name.trim()]);
+        }
+        let path = statement.strip_prefix("use ")?.trim_start_matches("::");
+        // `a::b::{c, d}`, `a::b::*`, and `a::b as c` all import from `a::b`
+        let path = path
+            .split(['{', '*', ' '])
+            .next()
+            .unwrap_or("")
+            .trim_end_matches("::");
+        let segments: Vec<&str> = path.split("::").filter(|s| !s.is_empty()).collect();
+        let (first, rest) = segments.split_first()?;
+
+        let (base, rest) = match *first {
+            "crate" => (self.rust_crate_root(importing_file)?, rest),
+            "self" => (module_dir, rest),
+            "super" => {
+                let mut base = parent(&module_dir).to_string();
+                let mut rest = rest;
+                while let Some(("super", tail)) = rest.split_first().map(|(s, t)| (*s, t)) {
+                    base = parent(&base).to_string();
+                    rest = tail;
+                }
+                (base, rest)
+            }
+            // A child module, since the 2018 edition
+            name if self.rust_module(&module_dir, &[name]).is_some() => (module_dir, &segments[..]),
+            name => (self.rust_workspace_crate(name)?, rest),
+        };
+        // Later segments may name items rather than modules
+        (1..=rest.len())
+            .rev()
+            .find_map(|len| self.rust_module(&base, &rest[..len]))
+            .or_else(|| self.rust_module_file(&base))
+    }
+
+    /// File of the module `segments` below the module directory `base`.
+    fn rust_module(&self, base: &str, segments: &[&str]) -> Option<String> {
+        let path = join(base, &segments.join("/"));
+        self.first([format!("{path}.rs"), format!("{path}/mod.rs")])
+    }
+
+    /// File declaring the module whose directory is `dir`.
+    fn rust_module_file(&self, dir: &str) -> Option<String> {
+        self.first([
+            join(dir, "lib.rs"),
+            join(dir, "main.rs"),
+            join(dir, "mod.rs"),
+            format!("{dir}.rs"),
+        ])
+    }
+
+    /// Source directory of the crate `importing_file` belongs to: the
+    /// nearest directory above it with a `lib.rs` or `main.rs`.
+    fn rust_crate_root(&self, importing_file: &str) -> Option<String> {
+        let mut dir = parent(importing_file);
+        loop {
+            if self.has(&join(dir, "lib.rs")) || self.has(&join(dir, "main.rs")) {
+                return Some(dir.to_string());
+            }
+            if dir.is_empty() {
+                return None;
+            }
+            dir = parent(dir);
+        }
+    }
+
+    /// Source directory of the workspace crate imported as `name`.
+    fn rust_workspace_crate(&self, name: &str) -> Option<String> {
+        let dashed = name.replace('_', "-");
+        let mut roots: Vec<&str> = self
+            .files
+            .iter()
+            .filter_map(|path| path.strip_suffix("/src/lib.rs"))
+            .filter(|dir| {
+                let crate_dir = dir.rsplit('/').next().unwrap_or(dir);
+                crate_dir == name || crate_dir == dashed
+            })
+            .collect();
+        roots.sort_unstable();
+        roots.first().map(|dir| format!("{dir}/src"))
+    }
+
+    // ── JavaScript / TypeScript ────────────────────────────────────
+
+    fn resolve_js(&self, statement: &str, importing_file: &str) -> Option<String> {
+        let specifier = quoted(statement)?;
+        // Bare specifiers name packages
+        if !specifier.starts_with("./") && !specifier.starts_with("../") {
+            return None;
+        }
+        let path = normalize(&join(parent(importing_file), specifier))?;
+        let mut candidates = vec![path.clone()];
+        // TypeScript imports compiled names: `./auth.js` is `./auth.ts`
+        if let Some(stem) = [".js", ".jsx", ".mjs", ".cjs"]
+            .iter()
+            .find_map(|ext| path.strip_suffix(ext))
+        {
+            candidates.extend([".ts", ".tsx", ".mts", ".cts"].map(|ext| format!("{stem}{ext}")));
+        }
+        for ext in [".ts", ".tsx", ".js", ".jsx", ".mjs", ".cjs"] {
+            candidates.push(format!("{path}{ext}"));
+        }
+        for ext in [".ts", ".tsx", ".js", ".jsx"] {
+            candidates.push(format!("{path}/index{ext}"));
+        }
+        self.first(candidates)
+    }
+
+    // ── Python ─────────────────────────────────────────────────────
+
+    fn resolve_python(&self, statement: &str, importing_file: &str) -> Option<String> {
+        let statement = statement.trim();
+        let (module, names) = if let Some(rest) = statement.strip_prefix("from ") {
+            let (module, names) = rest.split_once(" import ")?;
+            (module.trim(), names.trim())
+        } else {
+            // `import a.b as c, d` resolves `a.b`
+            let rest = statement.strip_prefix("import ")?;
+            let first = rest.split(',').next()?.trim();
+            (first.split(" as ").next()?.trim(), "")
+        };
+
+        let dots = module.chars().take_while(|&c| c == '.').count();
+        let segments: Vec<&str> = module[dots..]
+            .split('.')
+            .filter(|s| !s.is_empty())
+            .collect();
+        let roots: Vec<String> = if dots > 0 {
+            let mut dir = parent(importing_file);
+            for _ in 1..dots {
+                dir = parent(dir);
+            }
+            vec![dir.to_string()]
+        } else {
+            // The repository root, a `src` layout, and each directory
+            // above the importing file, outermost first
+            let mut roots = vec![String::new(), "src".to_string()];
+            let mut ancestors = Vec::new();
+            let mut dir = parent(importing_file);
+            while !dir.is_empty() {
+                ancestors.push(dir.to_string());
+                dir = parent(dir);
+            }
+            roots.extend(ancestors.into_iter().rev());
+            roots
+        };
+
+        // `from pkg import module` imports a module, `from pkg import name`
+        // something defined in the package
+        let imported = names
+            .trim_start_matches('(')
+            .split(',')
+            .next()
+            .and_then(|name| name.split_whitespace().next())
+            .filter(|name| *name != "*");
+        for root in &roots {
+            if let Some(name) = imported {
+                let mut with_name = segments.clone();
+                with_name.push(name);
+                if let Some(found) = self.python_module(root, &with_name) {
+                    return Some(found);
+                }
+            }
+            if let Some(found) = (1..=segments.len())
+                .rev()
+                .find_map(|len| self.python_module(root, &segments[..len]))
+            {
+                return Some(found);
+            }
+            if segments.is_empty() {
+                return self.first([join(root, "__init__.py")]);
+            }
+        }
+        None
+    }
+
+    /// File of the module `segments` below `root`.
+    fn python_module(&self, root: &str, segments: &[&str]) -> Option<String> {
+        if segments.is_empty() {
+            return None;
+        }
+        let path = join(root, &segments.join("/"));
+        self.first([format!("{path}.py"), format!("{path}/__init__.py")])
+    }
+
+    // ── Go ─────────────────────────────────────────────────────────
+
+    fn resolve_go(&self, statement: &str) -> Option<String> {
+        let import_path = quoted(statement)?;
+        // The longest package directory the import path ends with
+        self.go_packages
+            .iter()
+            .filter(|dir| {
+                !dir.is_empty()
+                    && (import_path == dir.as_str()
+                        || import_path
+                            .strip_suffix(dir.as_str())
+                            .is_some_and(|prefix| prefix.ends_with('/')))
+            })
+            .max_by(|a, b| a.len().cmp(&b.len()).then_with(|| b.cmp(a)))
+            .cloned()
+    }
+}
+
+impl ImportResolver {
+    // ── C / C++ ────────────────────────────────────────────────────
+
+    fn resolve_c(&self, statement: &str, importing_file: &str) -> Option<String> {
+        let include = statement
+            .trim_start()
+            .strip_prefix('#')?
+            .trim_start()
+            .strip_prefix("include")?
+            .trim_start();
+        // `<...>` includes are the system's and third-party libraries'
+        let include = include.strip_prefix('"')?.split('"').next()?;
+        let named = self
+            .c_includes
+            .get(file_name(include))
+            .into_iter()
+            .flatten()
+            .map(String::as_str);
+        c_include(include, importing_file, |path| self.has(path), named)
+    }
+}
+
+/// Repository file the quoted `#include "include"` in `importing_file`
+/// names: the path relative to the including file's directory, or to one
+/// of the directories above it or their `include/` directories, nearest
+/// first. Failing those, as for headers found through `-I` flags, the file
+/// whose path ends with the include, nearest the including file.
+///
+/// `exists` tells whether a path is a repository file; `named` lists the
+/// repository files with the include's file name.
+pub(crate) fn c_include<'a>(
+    include: &str,
+    importing_file: &str,
+    exists: impl Fn(&str) -> bool,
+    named: impl IntoIterator<Item = &'a str>,
+) -> Option<String> {
+    let mut dir = parent(importing_file);
+    loop {
+        for base in [dir.to_string(), join(dir, "include")] {
+            let path = normalize(&join(&base, include)).filter(|path| exists(path));
+            if path.is_some() {
+                return path;
+            }
+        }
+        if dir.is_empty() {
+            break;
+        }
+        dir = parent(dir);
+    }
+
+    if include.split('/').any(|c| c == "..") {
+        return None;
+    }
+    let include = include.trim_start_matches("./");
+    named
+        .into_iter()
+        .filter(|path| {
+            path.strip_suffix(include)
+                .is_some_and(|prefix| prefix.is_empty() || prefix.ends_with('/'))
+        })
+        .max_by(|a, b| {
+            let shared = |path: &str| {
+                path.split('/')
+                    .zip(importing_file.split('/'))
+                    .take_while(|(x, y)| x == y)
+                    .count()
+            };
+            shared(a).cmp(&shared(b)).then_with(|| b.cmp(a))
+        })
+        .map(str::to_string)
+}
+
+/// `statement` without a leading `pub`, `pub(crate)`, and the like.
+fn strip_visibility(statement: &str) -> &str {
+    match statement.strip_prefix("pub") {
+        Some(rest) if rest.starts_with('(') => rest
+            .split_once(')')
+            .map_or(statement, |(_, rest)| rest.trim_start()),
+        Some(rest) if rest.starts_with(' ') => rest.trim_start(),
+        _ => statement,
+    }
+}
+
+/// Directory holding the child modules of the Rust file `path`:
+/// `src/auth.rs` → `src/auth`, `src/auth/mod.rs` → `src/auth`.
+fn rust_module_dir(path: &str) -> String {
+    let dir = parent(path);
+    let file = path.rsplit('/').next().unwrap_or(path);
+    match file.strip_suffix(".rs") {
+        Some("lib" | "main" | "mod") | None => dir.to_string(),
+        Some(stem) => join(dir, stem),
+    }
+}
+
+/// The first quoted string in `statement`.
+fn quoted(statement: &str) -> Option<&str> {
+    let start = statement.find(['\'', '"', '`'])?;
+    let quote = statement[start..].chars().next()?;
+    let rest = &statement[start + 1..];
+    rest.find(quote).map(|end| &rest[..end])
+}
+
+/// Last component of the repository-relative `path`.
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// Directory of the repository-relative `path`; empty at the root.
+fn parent(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(dir, _)| dir)
+}
+
+fn join(dir: &str, name: &str) -> String {
+    if dir.is_empty() {
+        name.to_string()
+    } else {
+        format!("{dir}/{name}")
+    }
+}
+
+/// `path` with `.` and `..` components resolved; None if it leaves the
+/// repository.
+fn normalize(path: &str) -> Option<String> {
+    let mut components: Vec<&str> = Vec::new();
+    for component in path.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop()?;
+            }
+            name => components.push(name),
+        }
+    }
+    Some(components.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolver(paths: &[&str]) -> ImportResolver {
+        ImportResolver::new(paths.iter().copied())
+    }
+
+    #[test]
+    fn resolves_rust_mod_and_use() {
+        let r = resolver(&[
+            "crates/app/src/lib.rs",
+            "crates/app/src/auth.rs",
+            "crates/app/src/auth/token.rs",
+            "crates/app/src/db/mod.rs",
+            "crates/app-core/src/lib.rs",
+            "crates/app-core/src/config.rs",
+        ]);
+        let resolve = |statement, file| r.resolve(statement, file, Language::Rust);
+        let lib = "crates/app/src/lib.rs";
+        let auth = "crates/app/src/auth.rs";
+
+        assert_eq!(resolve("pub mod auth;", lib).as_deref(), Some(auth));
+        assert_eq!(
+            resolve("mod db;", lib).as_deref(),
+            Some("crates/app/src/db/mod.rs")
+        );
+        assert_eq!(
+            resolve("mod token;", auth).as_deref(),
+            Some("crates/app/src/auth/token.rs")
+        );
+        assert_eq!(
+            resolve("use crate::auth::token::{Token, verify};", lib).as_deref(),
+            Some("crates/app/src/auth/token.rs")
+        );
+        // Items resolve to the module defining them
+        assert_eq!(
+            resolve("use crate::auth::Session;", lib).as_deref(),
+            Some(auth)
+        );
+        assert_eq!(
+            resolve("use super::Session;", "crates/app/src/auth/token.rs").as_deref(),
+            Some(auth)
+        );
+        assert_eq!(
+            resolve("use self::token::Token;", auth).as_deref(),
+            Some("crates/app/src/auth/token.rs")
+        );
+        assert_eq!(
+            resolve("use app_core::config::Config;", lib).as_deref(),
+            Some("crates/app-core/src/config.rs")
+        );
+        assert_eq!(resolve("use std::collections::HashMap;", lib), None);
+        assert_eq!(resolve("use serde::Deserialize;", lib), None);
+    }
+
+    #[test]
+    fn resolves_relative_js_imports() {
+        let r = resolver(&[
+            "web/src/app.ts",
+            "web/src/auth.ts",
+            "web/src/components/index.tsx",
+            "web/lib/util.js",
+        ]);
+        let resolve = |statement| r.resolve(statement, "web/src/app.ts", Language::TypeScript);
+
+        assert_eq!(
+            resolve("import { login } from './auth';").as_deref(),
+            Some("web/src/auth.ts")
+        );
+        assert_eq!(
+            resolve("import { login } from './auth.js';").as_deref(),
+            Some("web/src/auth.ts")
+        );
+        assert_eq!(
+            resolve("import { Button } from \"./components\"").as_deref(),
+            Some("web/src/components/index.tsx")
+        );
+        assert_eq!(
+            resolve("import util from '../lib/util'").as_deref(),
+            Some("web/lib/util.js")
+        );
+        assert_eq!(resolve("import React from 'react';"), None);
+        assert_eq!(resolve("import x from '../../../outside'"), None);
+    }
+
+    #[test]
+    fn resolves_python_modules() {
+        let r = resolver(&[
+            "app/__init__.py",
+            "app/models.py",
+            "app/api/__init__.py",
+            "app/api/views.py",
+            "src/tools/cli.py",
+        ]);
+        let resolve = |statement| r.resolve(statement, "app/api/views.py", Language::Python);
+
+        assert_eq!(
+            resolve("from app.models import User").as_deref(),
+            Some("app/models.py")
+        );
+        assert_eq!(
+            resolve("import app.models as m").as_deref(),
+            Some("app/models.py")
+        );
+        assert_eq!(
+            resolve("from ..models import User").as_deref(),
+            Some("app/models.py")
+        );
+        assert_eq!(
+            resolve("from . import views").as_deref(),
+            None,
+            "a file doesn't import itself"
+        );
+        assert_eq!(
+            resolve("from .. import models").as_deref(),
+            Some("app/models.py")
+        );
+        assert_eq!(
+            resolve("from app import api").as_deref(),
+            Some("app/api/__init__.py")
+        );
+        assert_eq!(
+            resolve("import tools.cli").as_deref(),
+            Some("src/tools/cli.py")
+        );
+        assert_eq!(resolve("import os"), None);
+    }
+
+    #[test]
+    fn resolves_go_packages_by_path_suffix() {
+        let r = resolver(&[
+            "main.go",
+            "internal/auth/auth.go",
+            "pkg/auth/client.go",
+            "internal/db/db.go",
+        ]);
+        let resolve = |statement| r.resolve(statement, "main.go", Language::Go);
+
+        assert_eq!(
+            resolve("import \"github.com/acme/app/internal/auth\"").as_deref(),
+            Some("internal/auth")
+        );
+        assert_eq!(
+            resolve("import db \"github.com/acme/app/internal/db\"").as_deref(),
+            Some("internal/db")
+        );
+        assert_eq!(resolve("import \"net/http\""), None);
+        assert_eq!(resolve("import \"github.com/acme/app/auth\""), None);
+    }
+
+    #[test]
+    fn resolves_quoted_c_includes() {
+        let r = resolver(&[
+            "src/net/socket.c",
+            "src/net/socket.h",
+            "src/util.h",
+            "src/util.c",
+            "include/app/config.h",
+            "libs/json/include/json/json.h",
+            "libs/yaml/include/json/json.h",
+            "libs/yaml/src/parse.c",
+        ]);
+        let resolve = |statement, file| r.resolve(statement, file, Language::C);
+
+        assert_eq!(
+            resolve("#include \"socket.h\"", "src/net/socket.c").as_deref(),
+            Some("src/net/socket.h")
+        );
+        assert_eq!(
+            resolve("#include \"../util.h\"", "src/net/socket.c").as_deref(),
+            Some("src/util.h")
+        );
+        // A directory above the including file
+        assert_eq!(
+            resolve("#include \"util.h\"", "src/net/socket.c").as_deref(),
+            Some("src/util.h")
+        );
+        // An `include/` directory above it
+        assert_eq!(
+            resolve("#  include \"app/config.h\"", "src/util.c").as_deref(),
+            Some("include/app/config.h")
+        );
+        // An include path elsewhere, the nearest one winning
+        assert_eq!(
+            resolve("#include \"json/json.h\"", "libs/yaml/src/parse.c").as_deref(),
+            Some("libs/yaml/include/json/json.h")
+        );
+        assert_eq!(resolve("#include <stdio.h>", "src/util.c"), None);
+        assert_eq!(resolve("#include \"missing.h\"", "src/util.c"), None);
+        // Same stem, different file
+        assert_eq!(resolve("#include \"util.hpp\"", "src/net/socket.c"), None);
+    }
+}
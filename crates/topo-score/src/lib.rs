@@ -1,23 +1,74 @@
 //! BM25F, heuristic, structural, and RRF fusion scoring.
 
 mod bm25f;
+mod centrality;
+mod chunks;
+mod coupling;
+mod diversity;
+mod duplicates;
+mod embedding;
 mod fusion;
+mod fuzzy;
 mod git_recency;
 mod heuristic;
+mod imports;
+#[cfg(feature = "embedding")]
+mod local_embedder;
+mod long_query;
+mod normalize;
+mod ownership;
 mod pagerank;
+mod phrase;
+mod pins;
+mod preset;
+mod preview;
+mod query;
 mod resolve;
+mod retrieval;
+mod synonyms;
 mod tokenizer;
+mod training;
 
 pub mod hybrid;
 
-pub use bm25f::{Bm25fScorer, CorpusStats};
+pub use bm25f::{Bm25fScorer, CorpusStats, DEFAULT_DOCS_WEIGHT, FieldWeights};
+pub use centrality::{Centrality, DEFAULT_CENTRALITY_WEIGHT};
+pub use chunks::{ChunkPostings, ScoredChunk, duplicate_damping};
+pub use coupling::{COUPLING_WEIGHT, Coupling, TestPairs};
+pub use diversity::{DEFAULT_MMR_CANDIDATES, Mmr, path_similarity, term_similarity};
+pub use duplicates::{DUPLICATE_WEIGHT, Duplicates};
+pub use embedding::EmbeddingScorer;
 pub use fusion::{RrfFusion, RrfResult};
-pub use git_recency::{file_recency, git_recency_scores};
+pub use fuzzy::{MIN_FUZZY_LEN, edit_distance, near_miss, near_misses};
+pub use git_recency::{
+    DEFAULT_COMMIT_WEIGHT, DEFAULT_HALF_LIFE_DAYS, DEFAULT_LOOKBACK_DAYS, RecencyModel,
+    activity_recency, author_affinity, file_recency, git_activity, git_recency_scores,
+    read_activity, recency_scores,
+};
 pub use heuristic::HeuristicScorer;
-pub use hybrid::HybridScorer;
+pub use hybrid::{HybridScorer, PathScope};
+pub use imports::ImportResolver;
+#[cfg(feature = "embedding")]
+pub use local_embedder::LocalEmbedder;
+pub use long_query::{MAX_DOCUMENT_TERMS, MAX_TERM_FREQUENCY, TermVector};
+pub use normalize::{Normalization, SOFTMAX_TEMPERATURE};
+pub use ownership::{
+    blame_summaries, blame_summary, git_ownership_scores, ownership, ownership_scores,
+    summarized_ownership, summary_ownership,
+};
 pub use pagerank::{ImportGraph, extract_imports};
+pub use phrase::{PHRASE_WEIGHT, Phrases};
+pub use pins::{PIN_BOOST, apply_pins};
+pub use preset::ScoringPreset;
+pub use preview::{
+    HIGHLIGHT_CLOSE, HIGHLIGHT_OPEN, MAX_MATCHED_LINES, MAX_PREVIEW_READ_BYTES, Matches, Previewer,
+};
+pub use query::{ParsedQuery, QueryFilters};
 pub use resolve::build_import_graph;
-pub use tokenizer::Tokenizer;
+pub use retrieval::{RankedPath, RetrievalComparison};
+pub use synonyms::{EXPANSION_WEIGHT, Synonyms};
+pub use tokenizer::{Tokenizer, stem};
+pub use training::{NDCG_DEPTH, TrainingQuery, fit_weights};
 
 #[cfg(test)]
 mod tests {
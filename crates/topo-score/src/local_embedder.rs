@@ -0,0 +1,71 @@
+use fastembed::{EmbeddingModel, TextEmbedding, TextInitOptions};
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+use topo_core::{EmbedError, Embedder, TopoError};
+
+/// An [`Embedder`] running a local ONNX model with fastembed, such as
+/// `AllMiniLML6V2`. The model is downloaded into a cache directory on
+/// first use, then runs offline.
+pub struct LocalEmbedder {
+    model: Mutex<TextEmbedding>,
+    name: String,
+    dimension: u32,
+}
+
+impl LocalEmbedder {
+    /// Load the fastembed model called `name` (case-insensitive), kept in
+    /// `cache_dir`.
+    pub fn new(name: &str, cache_dir: &Path) -> Result<Self, TopoError> {
+        let model: EmbeddingModel = name
+            .parse()
+            .map_err(|message| TopoError::Score { message })?;
+        let dimension = TextEmbedding::get_model_info(&model)
+            .map_err(|e| TopoError::Score {
+                message: format!("embedding model {name}: {e}"),
+            })?
+            .dim;
+        let options = TextInitOptions::new(model)
+            .with_cache_dir(cache_dir.to_path_buf())
+            .with_show_download_progress(false);
+        let model = TextEmbedding::try_new(options).map_err(|e| TopoError::Score {
+            message: format!("failed to load embedding model {name}: {e}"),
+        })?;
+        Ok(Self {
+            model: Mutex::new(model),
+            name: name.to_string(),
+            dimension: dimension as u32,
+        })
+    }
+}
+
+impl Embedder for LocalEmbedder {
+    fn model(&self) -> &str {
+        &self.name
+    }
+
+    fn dimension(&self) -> u32 {
+        self.dimension
+    }
+
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, EmbedError> {
+        // Running the model needs it exclusively
+        let mut model = self.model.lock().unwrap_or_else(PoisonError::into_inner);
+        model
+            .embed(texts, None)
+            .map_err(|e| EmbedError::Fatal(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_unknown_models() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = LocalEmbedder::new("NoSuchModel", dir.path()).err().unwrap();
+        assert!(err.to_string().contains("NoSuchModel"));
+        // Nothing is downloaded for a name that isn't a model
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+}
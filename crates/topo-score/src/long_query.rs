@@ -0,0 +1,195 @@
+//! Whole documents as queries: a bug report or a diff in place of a few
+//! keywords.
+
+use crate::tokenizer::Tokenizer;
+use std::collections::HashMap;
+
+/// Occurrences of a term that count towards its weight; a word repeated
+/// all through a document weighs no more than one used this often.
+pub const MAX_TERM_FREQUENCY: usize = 3;
+
+/// Terms of a document kept as query terms, the most used first.
+pub const MAX_DOCUMENT_TERMS: usize = 32;
+
+/// Query terms of a document, each weighted from 0.0 to 1.0 by how often
+/// the document uses it, up to [`MAX_TERM_FREQUENCY`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TermVector {
+    terms: Vec<(String, f64)>,
+}
+
+impl TermVector {
+    /// Terms of the prose or code in `text`. Stop words, numbers, and
+    /// hashes are left out.
+    pub fn from_text(text: &str) -> Self {
+        let mut counts = Counts::default();
+        counts.add(text, 1);
+        counts.vector()
+    }
+
+    /// Terms of the unified diff `diff`, as `git diff` prints it: the
+    /// paths of the files changed count fully, and the lines added and
+    /// removed as text. Unchanged context lines are left out.
+    pub fn from_diff(diff: &str) -> Self {
+        let mut counts = Counts::default();
+        for line in diff.lines() {
+            if let Some(path) = line
+                .strip_prefix("+++ ")
+                .or_else(|| line.strip_prefix("--- "))
+            {
+                if path != "/dev/null" {
+                    let path = path
+                        .strip_prefix("a/")
+                        .or_else(|| path.strip_prefix("b/"))
+                        .unwrap_or(path);
+                    counts.add(path, MAX_TERM_FREQUENCY);
+                }
+            } else if let Some(hunk) = line.strip_prefix("@@") {
+                // The enclosing function git names after the line ranges
+                if let Some((_, function)) = hunk.split_once("@@") {
+                    counts.add(function, 1);
+                }
+            } else if let Some(changed) = line.strip_prefix(['+', '-']) {
+                counts.add(changed, 1);
+            }
+        }
+        counts.vector()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// The terms with their weights, the heaviest first.
+    pub fn terms(&self) -> &[(String, f64)] {
+        &self.terms
+    }
+
+    /// The terms as query text, to match wherever the query is tokenized.
+    pub fn query(&self) -> String {
+        let terms: Vec<&str> = self.terms.iter().map(|(t, _)| t.as_str()).collect();
+        terms.join(" ")
+    }
+
+    /// Leave out `terms`, e.g. those of a query typed alongside the
+    /// document, which keep their full weight.
+    pub fn without(mut self, terms: &[String]) -> Self {
+        self.terms.retain(|(term, _)| !terms.contains(term));
+        self
+    }
+}
+
+/// Term counts of a document, in order of first use.
+#[derive(Default)]
+struct Counts {
+    counts: HashMap<String, usize>,
+    order: Vec<String>,
+}
+
+impl Counts {
+    /// Count each term of `text` `times` times.
+    fn add(&mut self, text: &str, times: usize) {
+        // Prose and code punctuate more than queries do
+        let words: String = text
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '_' {
+                    c
+                } else {
+                    ' '
+                }
+            })
+            .collect();
+        for term in Tokenizer::tokenize(&words) {
+            if is_noise(&term) {
+                continue;
+            }
+            let count = self.counts.entry(term.clone()).or_insert_with(|| {
+                self.order.push(term);
+                0
+            });
+            *count += times;
+        }
+    }
+
+    /// The [`MAX_DOCUMENT_TERMS`] most used terms, ties going to the first
+    /// used, weighted by their capped counts.
+    fn vector(self) -> TermVector {
+        let capped = |term: &String| self.counts[term].min(MAX_TERM_FREQUENCY);
+        let mut order = self.order.clone();
+        order.sort_by_key(|term| std::cmp::Reverse(capped(term)));
+        let terms = order
+            .into_iter()
+            .take(MAX_DOCUMENT_TERMS)
+            .map(|term| {
+                let weight = capped(&term) as f64 / MAX_TERM_FREQUENCY as f64;
+                (term, weight)
+            })
+            .collect();
+        TermVector { terms }
+    }
+}
+
+/// Numbers, and hex strings like commit and blob ids, which say nothing
+/// about what a document is about.
+fn is_noise(term: &str) -> bool {
+    term.bytes().all(|b| b.is_ascii_digit())
+        || (term.len() >= 7
+            && term.bytes().all(|b| b.is_ascii_hexdigit())
+            && term.bytes().any(|b| b.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_terms_are_capped() {
+        let vector = TermVector::from_text(
+            "Checkout crashes when the cart is empty. The cart total is null, \
+             cart cart cart. Seen in build 4512, commit 3fa9c01e.",
+        );
+        assert_eq!(
+            vector.terms()[..2],
+            [
+                ("cart".to_string(), 1.0),
+                ("checkout".to_string(), 1.0 / 3.0)
+            ]
+        );
+        let query = vector.query();
+        assert!(query.contains("crashes") && query.contains("null"));
+        assert!(!query.contains("4512") && !query.contains("3fa9c01e"));
+        assert!(!query.contains("the"));
+    }
+
+    #[test]
+    fn diffs_count_paths_and_changed_lines() {
+        let diff = "\
+diff --git a/src/payments/refund.rs b/src/payments/refund.rs
+index 83db48f..bf269f4 100644
+--- a/src/payments/refund.rs
++++ b/src/payments/refund.rs
+@@ -10,7 +10,7 @@ fn issue_refund(order: &Order) {
+     let ledger = Ledger::open();
+-    let amount = order.total;
++    let amount = order.total - order.fees;
+";
+        let vector = TermVector::from_diff(diff);
+        let weight = |term: &str| {
+            vector
+                .terms()
+                .iter()
+                .find(|(t, _)| t == term)
+                .map(|(_, w)| *w)
+        };
+        assert_eq!(weight("refund"), Some(1.0));
+        assert_eq!(weight("payments"), Some(1.0));
+        assert_eq!(weight("fees"), Some(1.0 / 3.0));
+        assert_eq!(weight("issue"), Some(1.0 / 3.0));
+        assert_eq!(weight("ledger"), None, "context lines are left out");
+        assert_eq!(weight("83db48f"), None);
+
+        let typed = vector.without(&["refund".to_string()]);
+        assert_eq!(typed.terms()[0].0, "src");
+    }
+}
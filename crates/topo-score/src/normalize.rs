@@ -0,0 +1,161 @@
+//! Rescaling of scores to 0.0–1.0, so thresholds like `--min-score` mean
+//! the same from one query or repository to the next.
+
+use topo_core::ScoredFile;
+
+/// Temperature of [`Normalization::Softmax`], on scores relative to the
+/// best: a file a tenth of the best score behind is about e times less
+/// likely.
+pub const SOFTMAX_TEMPERATURE: f64 = 0.1;
+
+/// How the scores of a query's results are scaled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Normalization {
+    /// Scores as the signals add up, unbounded.
+    #[default]
+    Raw,
+    /// The best result scores 1.0 and the worst 0.0, the rest in between
+    /// in proportion.
+    MinMax,
+    /// The leading candidates share a total of 1.0 by softmax; later
+    /// results score 0.0.
+    Softmax,
+}
+
+impl Normalization {
+    /// The normalization called `name`: one of [`topo_core::NORMALIZATIONS`].
+    pub fn named(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "raw" => Some(Self::Raw),
+            "minmax" => Some(Self::MinMax),
+            "softmax" => Some(Self::Softmax),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Raw => "raw",
+            Self::MinMax => "minmax",
+            Self::Softmax => "softmax",
+        }
+    }
+
+    /// Rescale `scored`, sorted by score, keeping each file's raw score
+    /// in its signals. Softmax spreads over the leading `candidates`.
+    /// Order is kept.
+    pub fn apply(&self, scored: &mut [ScoredFile], candidates: usize) {
+        if *self == Self::Raw || scored.is_empty() {
+            return;
+        }
+        for file in scored.iter_mut() {
+            file.signals.raw = Some(file.score);
+        }
+        let (best, worst) = scored
+            .iter()
+            .map(|file| file.score)
+            .fold((f64::MIN, f64::MAX), |(best, worst), score| {
+                (best.max(score), worst.min(score))
+            });
+        match self {
+            Self::Raw => {}
+            Self::MinMax => {
+                let range = best - worst;
+                for file in scored.iter_mut() {
+                    file.score = if range > 0.0 {
+                        (file.score - worst) / range
+                    } else if best > 0.0 {
+                        1.0
+                    } else {
+                        0.0
+                    };
+                }
+            }
+            Self::Softmax => {
+                let count = candidates.min(scored.len());
+                let (leading, rest) = scored.split_at_mut(count);
+                let top = leading
+                    .iter()
+                    .map(|file| file.score)
+                    .fold(0.0_f64, f64::max);
+                if top > 0.0 {
+                    let weights: Vec<f64> = leading
+                        .iter()
+                        .map(|file| ((file.score / top - 1.0) / SOFTMAX_TEMPERATURE).exp())
+                        .collect();
+                    let total: f64 = weights.iter().sum();
+                    for (file, weight) in leading.iter_mut().zip(weights) {
+                        file.score = weight / total;
+                    }
+                } else {
+                    leading.iter_mut().for_each(|file| file.score = 0.0);
+                }
+                rest.iter_mut().for_each(|file| file.score = 0.0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use topo_core::{FileRole, Language};
+
+    fn scored(scores: &[f64]) -> Vec<ScoredFile> {
+        scores
+            .iter()
+            .enumerate()
+            .map(|(i, &score)| ScoredFile {
+                path: format!("src/{i}.rs"),
+                score,
+                signals: Default::default(),
+                tokens: 0,
+                language: Language::Rust,
+                role: FileRole::Implementation,
+                preview: None,
+            })
+            .collect()
+    }
+
+    fn scores(files: &[ScoredFile]) -> Vec<f64> {
+        files.iter().map(|f| f.score).collect()
+    }
+
+    #[test]
+    fn min_max_spans_zero_to_one() {
+        let mut files = scored(&[2.5, 1.5, 0.5]);
+        Normalization::MinMax.apply(&mut files, 50);
+        assert_eq!(scores(&files), [1.0, 0.5, 0.0]);
+        assert_eq!(files[1].signals.raw, Some(1.5));
+
+        // The same ranking at another scale normalizes the same
+        let mut scaled = scored(&[25.0, 15.0, 5.0]);
+        Normalization::MinMax.apply(&mut scaled, 50);
+        assert_eq!(scores(&scaled), scores(&files));
+
+        let mut tied = scored(&[0.3, 0.3]);
+        Normalization::MinMax.apply(&mut tied, 50);
+        assert_eq!(scores(&tied), [1.0, 1.0]);
+    }
+
+    #[test]
+    fn softmax_shares_one_among_candidates() {
+        let mut files = scored(&[1.0, 0.9, 0.5, 0.4]);
+        Normalization::Softmax.apply(&mut files, 3);
+        let total: f64 = files[..3].iter().map(|f| f.score).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        assert!(files[0].score > files[1].score && files[1].score > files[2].score);
+        assert_eq!(files[3].score, 0.0);
+        assert_eq!(files[3].signals.raw, Some(0.4));
+    }
+
+    #[test]
+    fn raw_leaves_scores_alone() {
+        let mut files = scored(&[2.0, 1.0]);
+        Normalization::Raw.apply(&mut files, 50);
+        assert_eq!(scores(&files), [2.0, 1.0]);
+        assert_eq!(files[0].signals.raw, None);
+        assert_eq!(Normalization::named("MinMax"), Some(Normalization::MinMax));
+        assert_eq!(Normalization::named("zscore"), None);
+    }
+}
@@ -0,0 +1,258 @@
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use topo_core::{BlameSummary, Ownership, TopoError};
+use topo_vcs::{BlameLine, Vcs};
+
+/// Lines changed within this many days count as recent.
+const RECENT_DAYS: u64 = 30;
+
+/// Summarize blame ownership for `paths` in a repository.
+///
+/// Returns an empty map outside version control.
+pub fn git_ownership_scores(
+    repo_root: &Path,
+    paths: &[&str],
+) -> Result<HashMap<String, Ownership>, TopoError> {
+    match topo_vcs::detect(repo_root) {
+        Some(vcs) => ownership_scores(vcs.as_ref(), paths),
+        None => Ok(HashMap::new()),
+    }
+}
+
+/// Blame each of `paths` and summarize who owns its lines.
+///
+/// The querying author is the backend's configured user. Files blame can't
+/// attribute (untracked, outside the repo) are left out.
+pub fn ownership_scores(
+    vcs: &dyn Vcs,
+    paths: &[&str],
+) -> Result<HashMap<String, Ownership>, TopoError> {
+    summarized_ownership(vcs, &blame_summaries(vcs, paths)?)
+}
+
+/// Blame each of `paths`, several at a time, and reduce it to a
+/// [`BlameSummary`]. Files blame can't attribute are left out.
+pub fn blame_summaries(
+    vcs: &dyn Vcs,
+    paths: &[&str],
+) -> Result<HashMap<String, BlameSummary>, TopoError> {
+    let blamed: Vec<Option<(String, BlameSummary)>> = paths
+        .par_iter()
+        .map(|&path| match vcs.blame(path) {
+            Ok(lines) => Ok(Some((path.to_string(), blame_summary(&lines)))),
+            Err(TopoError::Vcs { .. }) => Ok(None),
+            Err(e) => Err(e),
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(blamed.into_iter().flatten().collect())
+}
+
+/// Who owns the lines of each file in `summaries`, as seen by the
+/// backend's configured user now.
+pub fn summarized_ownership(
+    vcs: &dyn Vcs,
+    summaries: &HashMap<String, BlameSummary>,
+) -> Result<HashMap<String, Ownership>, TopoError> {
+    let me = match vcs.user_email() {
+        Ok(email) => email,
+        Err(TopoError::Vcs { .. }) => None,
+        Err(e) => return Err(e),
+    };
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Ok(summaries
+        .iter()
+        .filter_map(|(path, summary)| {
+            let owner = summary_ownership(summary, me.as_deref(), now)?;
+            Some((path.clone(), owner))
+        })
+        .collect())
+}
+
+/// Reduce the blame `lines` of one file to a [`BlameSummary`].
+pub fn blame_summary(lines: &[BlameLine]) -> BlameSummary {
+    let mut counts: BTreeMap<(String, u64), usize> = BTreeMap::new();
+    for line in lines {
+        *counts
+            .entry((line.author_email.to_lowercase(), line.timestamp))
+            .or_default() += 1;
+    }
+    BlameSummary {
+        lines: counts
+            .into_iter()
+            .map(|((author, time), count)| (author, time, count))
+            .collect(),
+        // Blame attributes uncommitted lines to the all-zero commit
+        uncommitted: lines.iter().any(|l| l.commit.bytes().all(|b| b == b'0')),
+    }
+}
+
+/// Summarize blame for one file, as seen by `me` at time `now`.
+///
+/// Returns `None` for files without lines.
+pub fn ownership(lines: &[BlameLine], me: Option<&str>, now: u64) -> Option<Ownership> {
+    summary_ownership(&blame_summary(lines), me, now)
+}
+
+/// Summarize the blame `summary` of one file, as seen by `me` at time
+/// `now`.
+///
+/// Returns `None` for files without lines.
+pub fn summary_ownership(summary: &BlameSummary, me: Option<&str>, now: u64) -> Option<Ownership> {
+    let total: usize = summary.lines.iter().map(|(_, _, count)| count).sum();
+    if total == 0 {
+        return None;
+    }
+
+    let mut per_author: HashMap<&str, usize> = HashMap::new();
+    for (author, _, count) in &summary.lines {
+        *per_author.entry(author).or_default() += count;
+    }
+    // Most lines wins; ties go to the alphabetically first author
+    let (top_author, top_lines) = per_author
+        .iter()
+        .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+        .map(|(author, count)| (author.to_string(), *count))?;
+
+    let own_lines = me
+        .and_then(|me| per_author.get(me.to_lowercase().as_str()))
+        .copied()
+        .unwrap_or(0);
+    let cutoff = now.saturating_sub(RECENT_DAYS * 24 * 60 * 60);
+    let recent_lines: usize = summary
+        .lines
+        .iter()
+        .filter(|(_, time, _)| *time >= cutoff)
+        .map(|(_, _, count)| count)
+        .sum();
+
+    let total = total as f64;
+    Some(Ownership {
+        top_author,
+        top_share: top_lines as f64 / total,
+        own_share: own_lines as f64 / total,
+        recent_share: recent_lines as f64 / total,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::process::Command;
+
+    const DAY: u64 = 24 * 60 * 60;
+    const NOW: u64 = 1_000 * DAY;
+
+    fn line(line: usize, author: &str, age_days: u64) -> BlameLine {
+        BlameLine {
+            line,
+            commit: format!("c{line}"),
+            author_email: author.to_string(),
+            timestamp: NOW - age_days * DAY,
+        }
+    }
+
+    #[test]
+    fn empty_file_has_no_owner() {
+        assert!(ownership(&[], Some("a@x.com"), NOW).is_none());
+    }
+
+    #[test]
+    fn summarizes_authors_and_recent_lines() {
+        let lines = [
+            line(1, "alice@x.com", 200),
+            line(2, "alice@x.com", 200),
+            line(3, "Bob@x.com", 5),
+            line(4, "alice@x.com", 1),
+        ];
+        let owner = ownership(&lines, Some("bob@x.com"), NOW).unwrap();
+        assert_eq!(owner.top_author, "alice@x.com");
+        assert!((owner.top_share - 0.75).abs() < 1e-9);
+        // Email match ignores case
+        assert!((owner.own_share - 0.25).abs() < 1e-9);
+        assert!((owner.recent_share - 0.5).abs() < 1e-9);
+        assert!((owner.score() - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn own_share_dominates_stale_file() {
+        let lines = [line(1, "me@x.com", 400), line(2, "me@x.com", 400)];
+        let mine = ownership(&lines, Some("me@x.com"), NOW).unwrap();
+        let theirs = ownership(&lines, Some("other@x.com"), NOW).unwrap();
+        assert!((mine.score() - 1.0).abs() < 1e-9);
+        assert_eq!(theirs.score(), 0.0);
+    }
+
+    #[test]
+    fn tied_authors_pick_first_alphabetically() {
+        let lines = [line(1, "zed@x.com", 1), line(2, "amy@x.com", 1)];
+        let owner = ownership(&lines, None, NOW).unwrap();
+        assert_eq!(owner.top_author, "amy@x.com");
+        assert_eq!(owner.own_share, 0.0);
+    }
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    #[test]
+    fn ownership_from_real_blame() {
+        let dir = tempfile::tempdir().unwrap();
+        git(dir.path(), &["init", "-q"]);
+        git(dir.path(), &["config", "user.email", "me@example.com"]);
+        git(dir.path(), &["config", "user.name", "Me"]);
+        fs::write(dir.path().join("a.rs"), "fn a() {}\nfn b() {}\n").unwrap();
+        git(dir.path(), &["add", "a.rs"]);
+        git(dir.path(), &["commit", "-q", "-m", "add a"]);
+        fs::write(dir.path().join("untracked.rs"), "fn u() {}\n").unwrap();
+
+        let owners = git_ownership_scores(dir.path(), &["a.rs", "untracked.rs"]).unwrap();
+        assert_eq!(owners.len(), 1);
+        let owner = &owners["a.rs"];
+        assert_eq!(owner.top_author, "me@example.com");
+        assert_eq!(owner.own_share, 1.0);
+        assert_eq!(owner.recent_share, 1.0);
+
+        fs::write(dir.path().join("a.rs"), "fn a() {}\nfn c() {}\n").unwrap();
+        let vcs = topo_vcs::detect(dir.path()).unwrap();
+        let summaries = blame_summaries(vcs.as_ref(), &["a.rs"]).unwrap();
+        assert!(summaries["a.rs"].uncommitted);
+        assert_eq!(
+            summaries["a.rs"].lines.iter().map(|l| l.2).sum::<usize>(),
+            2
+        );
+    }
+
+    #[test]
+    fn summaries_group_lines_by_author_and_time() {
+        let lines = [
+            line(1, "Alice@x.com", 3),
+            line(2, "alice@x.com", 3),
+            line(3, "bob@x.com", 3),
+        ];
+        let summary = blame_summary(&lines);
+        assert_eq!(
+            summary.lines,
+            [
+                ("alice@x.com".to_string(), NOW - 3 * DAY, 2),
+                ("bob@x.com".to_string(), NOW - 3 * DAY, 1)
+            ]
+        );
+        assert!(!summary.uncommitted);
+        assert_eq!(
+            summary_ownership(&summary, None, NOW),
+            ownership(&lines, None, NOW)
+        );
+    }
+}
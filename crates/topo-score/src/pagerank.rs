@@ -135,6 +135,21 @@ impl ImportGraph {
         scores.into_iter().map(|(k, v)| (k, v / max)).collect()
     }
 
+    /// Outgoing edges per file, sorted and deduplicated, without self-loops.
+    ///
+    /// Files with no outgoing edges are omitted.
+    pub fn adjacency(&self) -> HashMap<String, Vec<String>> {
+        self.edges
+            .iter()
+            .filter_map(|(from, to)| {
+                let mut deps: Vec<String> = to.iter().filter(|t| *t != from).cloned().collect();
+                deps.sort();
+                deps.dedup();
+                (!deps.is_empty()).then(|| (from.clone(), deps))
+            })
+            .collect()
+    }
+
     pub fn node_count(&self) -> usize {
         self.nodes.len()
     }
@@ -157,9 +172,10 @@ pub fn extract_imports(content: &str, language: topo_core::Language) -> Vec<Stri
     match language {
         topo_core::Language::Rust => extract_rust_imports(content),
         topo_core::Language::Python => extract_python_imports(content),
-        topo_core::Language::JavaScript | topo_core::Language::TypeScript => {
-            extract_js_imports(content)
-        }
+        topo_core::Language::JavaScript
+        | topo_core::Language::TypeScript
+        | topo_core::Language::Vue
+        | topo_core::Language::Svelte => extract_js_imports(content),
         topo_core::Language::Go => extract_go_imports(content),
         topo_core::Language::Java | topo_core::Language::Kotlin => extract_java_imports(content),
         topo_core::Language::C | topo_core::Language::Cpp => extract_c_includes(content),
@@ -497,6 +513,20 @@ fn extract_go_imports(content: &str) -> Vec<String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn adjacency_sorts_dedups_and_drops_self_loops() {
+        let mut graph = ImportGraph::new();
+        graph.add_edge("a.rs", "c.rs");
+        graph.add_edge("a.rs", "b.rs");
+        graph.add_edge("a.rs", "c.rs");
+        graph.add_edge("b.rs", "b.rs");
+        graph.add_node("d.rs");
+
+        let adjacency = graph.adjacency();
+        assert_eq!(adjacency.len(), 1);
+        assert_eq!(adjacency["a.rs"], vec!["b.rs", "c.rs"]);
+    }
+
     #[test]
     fn pagerank_empty_graph() {
         let graph = ImportGraph::new();
@@ -0,0 +1,106 @@
+use crate::tokenizer::Tokenizer;
+use topo_core::ChunkTerms;
+
+/// Boost of a file confirming every quoted phrase of a query, relative to
+/// its score; confirming some of them earns a share of it.
+pub const PHRASE_WEIGHT: f64 = 0.5;
+
+/// Quoted phrases of a query, like `"connection pool"`, whose words should
+/// appear together rather than anywhere in a file.
+///
+/// The index keeps no term positions, so a phrase counts as confirmed
+/// where its words are adjacent in a file's path (`connection_pool.rs`) or
+/// all found in one of its indexed chunks.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Phrases {
+    /// Terms of each phrase of two or more, stemmed if asked.
+    phrases: Vec<Vec<String>>,
+    stemming: bool,
+}
+
+impl Phrases {
+    /// Phrases between pairs of double quotes in `query`, with terms
+    /// stemmed when `stemming` is set, to match an index built with it. An
+    /// unpaired quote and phrases of a single term are ignored; the words
+    /// are still scored as plain query terms.
+    pub fn parse(query: &str, stemming: bool) -> Self {
+        let phrases = query
+            .split('"')
+            .enumerate()
+            // Odd pieces are quoted, unless the last quote is unpaired
+            .filter(|&(i, _)| i % 2 == 1 && i < query.matches('"').count())
+            .map(|(_, quoted)| Tokenizer::terms(quoted, stemming))
+            .filter(|terms| terms.len() > 1)
+            .collect();
+        Self { phrases, stemming }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.phrases.is_empty()
+    }
+
+    /// Share of the phrases confirmed for the file at `path` with these
+    /// indexed `chunks`, from 0.0 to 1.0.
+    pub fn confirmed(&self, path: &str, chunks: &[ChunkTerms]) -> f64 {
+        if self.phrases.is_empty() {
+            return 0.0;
+        }
+        let path_terms = Tokenizer::terms(path, self.stemming);
+        let confirmed = self
+            .phrases
+            .iter()
+            .filter(|phrase| {
+                path_terms
+                    .windows(phrase.len())
+                    .any(|window| window == phrase.as_slice())
+                    || chunks.iter().any(|chunk| {
+                        phrase
+                            .iter()
+                            .all(|term| chunk.term_frequencies.contains_key(term))
+                    })
+            })
+            .count();
+        confirmed as f64 / self.phrases.len() as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use topo_core::TermFreqs;
+
+    fn chunk(terms: &[&str]) -> ChunkTerms {
+        ChunkTerms {
+            term_frequencies: terms
+                .iter()
+                .map(|term| (term.to_string(), TermFreqs::default()))
+                .collect::<HashMap<_, _>>(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parses_quoted_phrases() {
+        let phrases = Phrases::parse(r#"fix "connection pool" leak in "Pool" "#, false);
+        assert_eq!(phrases.phrases, [["connection", "pool"]]);
+        assert!(Phrases::parse(r#"unpaired "connection pool"#, false).is_empty());
+        assert!(Phrases::parse("connection pool", false).is_empty());
+        assert_eq!(
+            Phrases::parse(r#""pooled connections""#, true).phrases,
+            [["pool", "connect"]]
+        );
+    }
+
+    #[test]
+    fn phrases_need_adjacent_path_terms_or_one_chunk() {
+        let phrases = Phrases::parse(r#""connection pool" "retry policy""#, false);
+        assert_eq!(phrases.confirmed("src/db/connection_pool.rs", &[]), 0.5);
+        assert_eq!(phrases.confirmed("src/pool/connection.rs", &[]), 0.0);
+
+        let apart = [chunk(&["connection", "open"]), chunk(&["pool", "size"])];
+        assert_eq!(phrases.confirmed("src/db.rs", &apart), 0.0);
+        let together = [chunk(&["connection", "pool", "retry", "policy"])];
+        assert_eq!(phrases.confirmed("src/db.rs", &together), 1.0);
+    }
+}
@@ -0,0 +1,62 @@
+use topo_core::{Pins, ScoredFile};
+
+/// Added to the score of every pinned file: more than any combination of
+/// signals gives, so pinned files rank above all others.
+pub const PIN_BOOST: f64 = 10.0;
+
+/// Mark and boost the files in `scored` that `pins` lists, then sort by
+/// score so they lead.
+pub fn apply_pins(scored: &mut [ScoredFile], pins: &Pins) {
+    if pins.is_empty() {
+        return;
+    }
+    for file in scored.iter_mut() {
+        if pins.contains(&file.path) {
+            file.signals.pinned = true;
+            file.score += PIN_BOOST;
+        }
+    }
+    scored.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use topo_core::{FileRole, Language, SignalBreakdown};
+
+    fn scored(path: &str, score: f64) -> ScoredFile {
+        ScoredFile {
+            path: path.to_string(),
+            score,
+            signals: SignalBreakdown::default(),
+            tokens: 10,
+            language: Language::Rust,
+            role: FileRole::Implementation,
+            preview: None,
+        }
+    }
+
+    #[test]
+    fn pinned_files_lead_whatever_their_score() {
+        let mut files = vec![
+            scored("src/auth.rs", 0.9),
+            scored("src/main.rs", 0.4),
+            scored("docs/ARCHITECTURE.md", 0.0),
+        ];
+        let pins = Pins::parse("docs/ARCHITECTURE.md\nsrc/main.rs\nsrc/gone.rs\n");
+        apply_pins(&mut files, &pins);
+
+        let order: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            order,
+            ["src/main.rs", "docs/ARCHITECTURE.md", "src/auth.rs"]
+        );
+        assert!(files[0].signals.pinned && files[1].signals.pinned);
+        assert!(!files[2].signals.pinned);
+        assert_eq!(files[1].score, PIN_BOOST);
+    }
+}
@@ -0,0 +1,113 @@
+//! Scoring presets: which signals a query runs, how they are weighed, and
+//! how far down the ranking the boosts look.
+
+/// What a preset does when scoring a query.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScoringPreset {
+    /// Name, as given to `--preset`.
+    pub name: &'static str,
+    /// Weight of BM25F in the hybrid score.
+    pub bm25f_weight: f64,
+    /// Weight of the path heuristic in the hybrid score.
+    pub heuristic_weight: f64,
+    /// Whether queries need a deep index, built first when missing.
+    pub deep_index: bool,
+    /// Whether structural signals run: PageRank, git recency, public API,
+    /// and ownership.
+    pub structural: bool,
+    /// Leading files checked for the public API and phrase boosts.
+    pub candidates: usize,
+    /// Leading files blamed for the ownership boost, with `structural`.
+    pub blame_candidates: usize,
+    /// Budget in bytes unless given.
+    pub max_bytes: u64,
+    /// Minimum score of a selected file unless given.
+    pub min_score: f64,
+}
+
+impl ScoringPreset {
+    /// Path heuristic alone, no index needed.
+    pub const FAST: Self = Self {
+        name: "fast",
+        bm25f_weight: 0.0,
+        heuristic_weight: 1.0,
+        deep_index: false,
+        structural: false,
+        candidates: 20,
+        blame_candidates: 0,
+        max_bytes: 50_000,
+        min_score: 0.05,
+    };
+
+    /// BM25F and the path heuristic, with a cached deep index.
+    pub const BALANCED: Self = Self {
+        name: "balanced",
+        bm25f_weight: 0.6,
+        heuristic_weight: 0.4,
+        deep_index: true,
+        structural: false,
+        candidates: 50,
+        blame_candidates: 0,
+        max_bytes: 100_000,
+        min_score: 0.01,
+    };
+
+    /// Balanced scoring plus the structural signals.
+    pub const DEEP: Self = Self {
+        name: "deep",
+        structural: true,
+        blame_candidates: 25,
+        max_bytes: 200_000,
+        min_score: 0.005,
+        ..Self::BALANCED
+    };
+
+    /// Deep scoring with the boosts looking twice as far down the ranking.
+    pub const THOROUGH: Self = Self {
+        name: "thorough",
+        candidates: 100,
+        blame_candidates: 50,
+        max_bytes: 500_000,
+        min_score: 0.001,
+        ..Self::DEEP
+    };
+
+    /// Every preset, fastest first.
+    pub const ALL: [Self; 4] = [Self::FAST, Self::BALANCED, Self::DEEP, Self::THOROUGH];
+
+    /// The preset called `name`, ignoring case.
+    pub fn named(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.name.eq_ignore_ascii_case(name))
+    }
+}
+
+impl Default for ScoringPreset {
+    fn default() -> Self {
+        Self::BALANCED
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_grow_in_scope() {
+        assert_eq!(ScoringPreset::named("Deep"), Some(ScoringPreset::DEEP));
+        assert_eq!(ScoringPreset::named("slow"), None);
+        assert_eq!(ScoringPreset::default().name, "balanced");
+        for pair in ScoringPreset::ALL.windows(2) {
+            let (smaller, larger) = (&pair[0], &pair[1]);
+            assert!(smaller.max_bytes < larger.max_bytes);
+            assert!(smaller.min_score > larger.min_score);
+            assert!(smaller.candidates <= larger.candidates);
+            assert!(smaller.blame_candidates <= larger.blame_candidates);
+        }
+        let all = ScoringPreset::ALL;
+        assert_eq!(all.map(|p| p.deep_index), [false, true, true, true]);
+        assert_eq!(all.map(|p| p.structural), [false, false, true, true]);
+        assert_eq!(ScoringPreset::FAST.bm25f_weight, 0.0);
+    }
+}
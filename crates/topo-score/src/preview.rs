@@ -0,0 +1,314 @@
+use crate::tokenizer::Tokenizer;
+use std::collections::{HashMap, HashSet};
+use topo_core::{Chunk, ScoredFile};
+
+/// Maximum preview length in characters.
+const MAX_PREVIEW_CHARS: usize = 160;
+
+/// Maximum matched line numbers listed per file.
+pub const MAX_MATCHED_LINES: usize = 100;
+
+/// Files larger than this are best previewed from their chunks alone.
+pub const MAX_PREVIEW_READ_BYTES: u64 = 1_000_000;
+
+/// Markers wrapped around matched words by [`Previewer::highlight`].
+pub const HIGHLIGHT_OPEN: &str = "«";
+pub const HIGHLIGHT_CLOSE: &str = "»";
+
+/// Where a query matched in the files given to [`Previewer::annotate`].
+#[derive(Debug, Default)]
+pub struct Matches {
+    /// 1-based numbers of matching lines, by path.
+    pub lines: HashMap<String, Vec<u32>>,
+    /// Words containing a query term, across all the files.
+    pub words: HashSet<String>,
+}
+
+/// Builds one-line previews showing why a file matched a query.
+pub struct Previewer {
+    terms: HashSet<String>,
+}
+
+impl Previewer {
+    pub fn new(query: &str) -> Self {
+        Self {
+            terms: Tokenizer::tokenize(query).into_iter().collect(),
+        }
+    }
+
+    /// Preview for one file.
+    ///
+    /// Prefers the first content line containing a query term, then the
+    /// signature of the best-matching chunk, then the first definition.
+    pub fn preview(&self, content: Option<&str>, chunks: &[Chunk]) -> Option<String> {
+        content
+            .and_then(|c| self.matching_line(c))
+            .or_else(|| self.chunk_signature(chunks))
+            .map(one_line)
+    }
+
+    /// Fill in the preview of each of `files`.
+    ///
+    /// `content` reads a file by path, `None` where it can't be read or is
+    /// larger than [`MAX_PREVIEW_READ_BYTES`]; `chunks` looks up its
+    /// indexed chunks. With `highlight`, query terms in previews are
+    /// marked and the matching lines and words of each file are returned.
+    pub fn annotate<'c>(
+        &self,
+        files: &mut [ScoredFile],
+        content: impl Fn(&str) -> Option<String>,
+        chunks: impl Fn(&str) -> &'c [Chunk],
+        highlight: bool,
+    ) -> Matches {
+        let mut matches = Matches::default();
+        for file in files {
+            let content = content(&file.path);
+            file.preview = self.preview(content.as_deref(), chunks(&file.path));
+            if !highlight {
+                continue;
+            }
+            file.preview = file.preview.as_deref().map(|p| self.highlight(p));
+            if let Some(content) = &content {
+                let lines = self.matched_lines(content);
+                if !lines.is_empty() {
+                    matches.lines.insert(file.path.clone(), lines);
+                }
+                matches
+                    .words
+                    .extend(self.matched_words(content).into_iter().map(str::to_string));
+            }
+        }
+        matches
+    }
+
+    fn matching_line<'a>(&self, content: &'a str) -> Option<&'a str> {
+        if self.terms.is_empty() {
+            return None;
+        }
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .find(|line| self.overlap(line) > 0)
+    }
+
+    fn chunk_signature<'a>(&self, chunks: &'a [Chunk]) -> Option<&'a str> {
+        let definitions = chunks.iter().filter(|c| c.kind.is_declaration());
+        // max_by_key keeps the last maximum; reverse so ties go to the first chunk
+        let best = definitions
+            .clone()
+            .rev()
+            .max_by_key(|c| self.overlap(&c.qualified_name()))
+            .filter(|c| self.overlap(&c.qualified_name()) > 0)
+            .or_else(|| definitions.clone().next())?;
+        best.content.lines().find(|line| !line.trim().is_empty())
+    }
+
+    /// Whether `word` contains a query term, e.g. `RateLimiter` for
+    /// "limiter".
+    pub fn matches_word(&self, word: &str) -> bool {
+        Tokenizer::tokenize(word)
+            .iter()
+            .any(|t| self.terms.contains(t))
+    }
+
+    /// Wrap each word of `text` that contains a query term in
+    /// [`HIGHLIGHT_OPEN`] and [`HIGHLIGHT_CLOSE`].
+    pub fn highlight(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut last = 0;
+        for (start, word) in words(text) {
+            if self.matches_word(word) {
+                out.push_str(&text[last..start]);
+                out.push_str(HIGHLIGHT_OPEN);
+                out.push_str(word);
+                out.push_str(HIGHLIGHT_CLOSE);
+                last = start + word.len();
+            }
+        }
+        out.push_str(&text[last..]);
+        out
+    }
+
+    /// 1-based numbers of the lines of `content` containing a query term,
+    /// at most [`MAX_MATCHED_LINES`].
+    pub fn matched_lines(&self, content: &str) -> Vec<u32> {
+        if self.terms.is_empty() {
+            return Vec::new();
+        }
+        content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| words(line).any(|(_, word)| self.matches_word(word)))
+            .map(|(i, _)| i as u32 + 1)
+            .take(MAX_MATCHED_LINES)
+            .collect()
+    }
+
+    /// Distinct words of `content` containing a query term.
+    pub fn matched_words<'a>(&self, content: &'a str) -> HashSet<&'a str> {
+        words(content)
+            .map(|(_, word)| word)
+            .filter(|word| self.matches_word(word))
+            .collect()
+    }
+
+    /// Number of distinct query terms in `text`.
+    fn overlap(&self, text: &str) -> usize {
+        Tokenizer::tokenize(text)
+            .into_iter()
+            .filter(|t| self.terms.contains(t))
+            .collect::<HashSet<_>>()
+            .len()
+    }
+}
+
+/// Identifier-like words of `text` (runs of alphanumerics and
+/// underscores) with their byte offsets.
+fn words(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| !word.is_empty())
+        .map(move |word| (word.as_ptr() as usize - text.as_ptr() as usize, word))
+}
+
+/// Collapse whitespace and truncate to [`MAX_PREVIEW_CHARS`].
+fn one_line(line: &str) -> String {
+    let collapsed = line.split_whitespace().collect::<Vec<_>>().join(" ");
+    if collapsed.chars().count() <= MAX_PREVIEW_CHARS {
+        return collapsed;
+    }
+    let mut truncated: String = collapsed.chars().take(MAX_PREVIEW_CHARS - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use topo_core::ChunkKind;
+
+    fn chunk(kind: ChunkKind, name: &str, content: &str) -> Chunk {
+        Chunk {
+            kind,
+            name: name.to_string(),
+            start_line: 1,
+            end_line: 1,
+            content: content.to_string(),
+            target: None,
+            doc: None,
+            parent: None,
+            signature: None,
+            tokens: 0,
+            exported: false,
+        }
+    }
+
+    #[test]
+    fn first_matching_line_wins() {
+        let previewer = Previewer::new("rate limiter");
+        let content = "use std::time;\n\n    pub struct   RateLimiter {\n// limiter config\n";
+        assert_eq!(
+            previewer.preview(Some(content), &[]).as_deref(),
+            Some("pub struct RateLimiter {")
+        );
+    }
+
+    #[test]
+    fn falls_back_to_best_chunk_signature() {
+        let previewer = Previewer::new("authenticate token");
+        let chunks = vec![
+            chunk(ChunkKind::Function, "parse", "fn parse() {}"),
+            chunk(
+                ChunkKind::Function,
+                "authenticate",
+                "\npub fn authenticate(token: &str) -> bool {\n    true\n}",
+            ),
+        ];
+        assert_eq!(
+            previewer
+                .preview(Some("nothing relevant"), &chunks)
+                .as_deref(),
+            Some("pub fn authenticate(token: &str) -> bool {")
+        );
+    }
+
+    #[test]
+    fn falls_back_to_first_definition() {
+        let previewer = Previewer::new("billing");
+        let chunks = vec![
+            chunk(ChunkKind::Import, "std", "use std::io;"),
+            chunk(ChunkKind::Function, "main", "fn main() {"),
+            chunk(ChunkKind::Function, "run", "fn run() {"),
+        ];
+        assert_eq!(
+            previewer.preview(None, &chunks).as_deref(),
+            Some("fn main() {")
+        );
+    }
+
+    #[test]
+    fn no_content_or_chunks_has_no_preview() {
+        assert!(Previewer::new("auth").preview(None, &[]).is_none());
+    }
+
+    #[test]
+    fn highlights_words_containing_query_terms() {
+        let previewer = Previewer::new("rate limiter");
+        assert_eq!(
+            previewer.highlight("pub struct RateLimiter { rate: u32 }"),
+            "pub struct «RateLimiter» { «rate»: u32 }"
+        );
+        assert_eq!(previewer.highlight("no match"), "no match");
+    }
+
+    #[test]
+    fn lists_matched_lines_and_words() {
+        let previewer = Previewer::new("auth token");
+        let content = "use std::io;
+fn check_auth() {}
+
+let token = 1; // auth
+";
+        assert_eq!(previewer.matched_lines(content), [2, 4]);
+        let mut words: Vec<&str> = previewer.matched_words(content).into_iter().collect();
+        words.sort();
+        assert_eq!(words, ["auth", "check_auth", "token"]);
+        assert!(Previewer::new("").matched_lines(content).is_empty());
+    }
+
+    #[test]
+    fn annotates_files_with_previews_and_matches() {
+        let scored = |path: &str| ScoredFile {
+            path: path.to_string(),
+            score: 1.0,
+            signals: Default::default(),
+            tokens: 0,
+            language: topo_core::Language::Rust,
+            role: topo_core::FileRole::Implementation,
+            preview: None,
+        };
+        let mut files = [scored("src/auth.rs"), scored("src/big.rs")];
+        let chunks = [chunk(ChunkKind::Function, "run", "fn run() {")];
+        let previewer = Previewer::new("auth");
+        let content = |path: &str| (path == "src/auth.rs").then(|| "\nmod auth {}".to_string());
+
+        let matches = previewer.annotate(&mut files, content, |_| &chunks[..], false);
+        assert_eq!(files[0].preview.as_deref(), Some("mod auth {}"));
+        assert_eq!(files[1].preview.as_deref(), Some("fn run() {"));
+        assert!(matches.lines.is_empty());
+
+        let matches = previewer.annotate(&mut files, content, |_| &[], true);
+        assert_eq!(files[0].preview.as_deref(), Some("mod «auth» {}"));
+        assert_eq!(files[1].preview, None);
+        assert_eq!(matches.lines["src/auth.rs"], [2]);
+        assert!(matches.words.contains("auth"));
+    }
+
+    #[test]
+    fn long_lines_are_truncated() {
+        let previewer = Previewer::new("auth");
+        let line = format!("auth {}", "x".repeat(500));
+        let preview = previewer.preview(Some(&line), &[]).unwrap();
+        assert_eq!(preview.chars().count(), MAX_PREVIEW_CHARS);
+        assert!(preview.ends_with('…'));
+    }
+}
@@ -0,0 +1,292 @@
+//! Field filters written into query text, like `lang:rust role:impl
+//! path:src/auth tokens`, and terms to avoid, like `-legacy !vendor`.
+
+use crate::tokenizer::{Tokenizer, stem};
+use globset::{Glob, GlobMatcher};
+use std::collections::HashMap;
+use topo_core::{FileInfo, FileRole, Language};
+
+/// Query text split into the terms to score, the filters restricting
+/// which files are scored at all, and the terms penalizing files.
+#[derive(Debug, Clone, Default)]
+pub struct ParsedQuery {
+    /// The query without its filters and negated terms.
+    pub text: String,
+    pub filters: QueryFilters,
+    /// Terms of `-term` words: files matching them score lower.
+    pub penalties: Vec<String>,
+}
+
+impl ParsedQuery {
+    /// Split `query` into terms and filters.
+    ///
+    /// A filter is a word `field:value` outside double quotes, with field
+    /// `path`, `lang`, `role`, or `pkg`; the value may list alternatives
+    /// separated by commas. Words whose field is unknown, or whose value
+    /// names no known language or role, stay in the text, so `role:admin`
+    /// can still be searched for.
+    ///
+    /// A word `-term` penalizes files matching it, and `!term` leaves out
+    /// files whose path or indexed terms have it, both outside double
+    /// quotes and starting with a letter, so `-1` and `--force` stay in the
+    /// text.
+    pub fn parse(query: &str) -> Self {
+        let mut filters = QueryFilters::default();
+        let mut penalties: Vec<String> = Vec::new();
+        let mut words: Vec<&str> = Vec::new();
+        let mut quoted = false;
+        for word in query.split_whitespace() {
+            let in_quotes = quoted || word.contains('"');
+            quoted ^= word.matches('"').count() % 2 == 1;
+            if in_quotes {
+                words.push(word);
+            } else if let Some(term) = negated(word, '-') {
+                penalties.extend(Tokenizer::tokenize(term));
+            } else if let Some(term) = negated(word, '!') {
+                filters.exclude(term);
+            } else if !filters.add(word) {
+                words.push(word);
+            }
+        }
+        Self {
+            text: words.join(" "),
+            filters,
+            penalties,
+        }
+    }
+}
+
+/// The term of `word` if it is `prefix` followed by a letter.
+fn negated(word: &str, prefix: char) -> Option<&str> {
+    word.strip_prefix(prefix)
+        .filter(|term| term.starts_with(char::is_alphabetic))
+}
+
+/// Restrictions of a query to files by path, language, role, and package.
+///
+/// A file matches when it matches any value of every field given: `lang:rust
+/// lang:go role:impl` keeps Rust and Go implementation files.
+#[derive(Debug, Clone, Default)]
+pub struct QueryFilters {
+    paths: Vec<PathFilter>,
+    languages: Vec<Language>,
+    roles: Vec<FileRole>,
+    packages: Vec<String>,
+    /// Stemmed terms of each `!term` word.
+    excluded: Vec<Vec<String>>,
+}
+
+/// A `path:` value: a glob if it has glob characters, otherwise a file or
+/// a directory the file is under.
+#[derive(Debug, Clone)]
+enum PathFilter {
+    Under(String),
+    Glob(GlobMatcher),
+}
+
+impl PathFilter {
+    fn parse(value: &str) -> Option<Self> {
+        if value.contains(['*', '?', '[', '{']) {
+            let glob = Glob::new(value).ok()?;
+            return Some(Self::Glob(glob.compile_matcher()));
+        }
+        let value = value.trim_start_matches("./").trim_end_matches('/');
+        (!value.is_empty()).then(|| Self::Under(value.to_string()))
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        match self {
+            Self::Under(prefix) => path
+                .strip_prefix(prefix.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/')),
+            Self::Glob(glob) => glob.is_match(path),
+        }
+    }
+}
+
+impl QueryFilters {
+    /// Add the filter `word` spells, returning whether it is one.
+    fn add(&mut self, word: &str) -> bool {
+        let Some((field, values)) = word.split_once(':') else {
+            return false;
+        };
+        let values: Vec<&str> = values.split(',').filter(|v| !v.is_empty()).collect();
+        if values.is_empty() {
+            return false;
+        }
+        let added = match field.to_ascii_lowercase().as_str() {
+            "path" => parse_all(&values, PathFilter::parse).map(|v| self.paths.extend(v)),
+            "lang" => {
+                parse_all(&values, Language::from_linguist_name).map(|v| self.languages.extend(v))
+            }
+            "role" => parse_all(&values, FileRole::from_name).map(|v| self.roles.extend(v)),
+            "pkg" => parse_all(&values, |v| Some(v.to_string())).map(|v| self.packages.extend(v)),
+            _ => None,
+        };
+        added.is_some()
+    }
+
+    /// Leave out files whose path has every term of `word`, matched by
+    /// their stems so `!test` also leaves out `tests/`.
+    fn exclude(&mut self, word: &str) {
+        let terms = Tokenizer::tokenize_stemmed(word);
+        if !terms.is_empty() {
+            self.excluded.push(terms);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+            && self.languages.is_empty()
+            && self.roles.is_empty()
+            && self.packages.is_empty()
+            && self.excluded.is_empty()
+    }
+
+    /// Whether the query has `!term` words, which also leave out files by
+    /// their indexed terms, see [`Self::excludes_terms`].
+    pub fn has_exclusions(&self) -> bool {
+        !self.excluded.is_empty()
+    }
+
+    /// Whether `path` has the terms of a `!term` word.
+    fn excludes(&self, path: &str) -> bool {
+        if self.excluded.is_empty() {
+            return false;
+        }
+        let path_terms: Vec<String> = Tokenizer::tokenize(path).iter().map(|t| stem(t)).collect();
+        self.excluded
+            .iter()
+            .any(|terms| terms.iter().all(|term| path_terms.contains(term)))
+    }
+
+    /// Whether a file whose index has `terms`, stemmed if `stemmed` is set,
+    /// has the terms of a `!term` word in its content, symbols, or docs.
+    pub fn excludes_terms<V>(&self, terms: &HashMap<String, V>, stemmed: bool) -> bool {
+        if self.excluded.is_empty() {
+            return false;
+        }
+        let has = |term: &String| {
+            if stemmed {
+                terms.contains_key(term)
+            } else {
+                terms.keys().any(|indexed| stem(indexed) == *term)
+            }
+        };
+        self.excluded.iter().any(|words| words.iter().all(has))
+    }
+
+    /// Whether `file` passes the filters, with `path` its path as written
+    /// in queries: `file.path`, or in a federation, qualified by its
+    /// repository.
+    pub fn matches(&self, path: &str, file: &FileInfo) -> bool {
+        (self.paths.is_empty() || self.paths.iter().any(|filter| filter.matches(path)))
+            && (self.languages.is_empty() || self.languages.contains(&file.language))
+            && (self.roles.is_empty() || self.roles.contains(&file.role))
+            && (self.packages.is_empty()
+                || file
+                    .package
+                    .as_ref()
+                    .is_some_and(|package| self.packages.contains(package)))
+            && !self.excludes(path)
+    }
+}
+
+/// Each of `values` parsed, or `None` if any of them isn't valid.
+fn parse_all<T>(values: &[&str], parse: impl Fn(&str) -> Option<T>) -> Option<Vec<T>> {
+    values.iter().map(|value| parse(value)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, language: Language, role: FileRole, package: Option<&str>) -> FileInfo {
+        FileInfo {
+            path: path.to_string(),
+            size: 100,
+            language,
+            role,
+            sha256: [0; 32],
+            package: package.map(String::from),
+        }
+    }
+
+    #[test]
+    fn filters_are_split_from_the_text() {
+        let query = ParsedQuery::parse("lang:rust role:impl path:src/auth/ refresh tokens");
+        assert_eq!(query.text, "refresh tokens");
+        assert!(!query.filters.is_empty());
+
+        // Unknown fields and values, and quoted words, are text
+        let query = ParsedQuery::parse(r#"role:admin std::fmt lang:klingon "path:src x" http://a"#);
+        assert_eq!(
+            query.text,
+            r#"role:admin std::fmt lang:klingon "path:src x" http://a"#
+        );
+        assert!(query.filters.is_empty());
+    }
+
+    #[test]
+    fn negated_terms_penalize_or_exclude() {
+        let query =
+            ParsedQuery::parse(r#"auth -legacy -DeprecatedApi !vendor "-quoted" -1 --force"#);
+        assert_eq!(query.text, r#"auth "-quoted" -1 --force"#);
+        assert_eq!(query.penalties, ["legacy", "deprecated", "api"]);
+
+        let file = |path: &str| file(path, Language::Rust, FileRole::Implementation, None);
+        let kept =
+            |query: &str, path: &str| ParsedQuery::parse(query).filters.matches(path, &file(path));
+        assert!(!kept("auth !vendor", "vendor/auth/token.rs"));
+        assert!(kept("auth !vendor", "src/auth/token.rs"));
+        assert!(!kept("!test", "tests/auth.rs"), "matched by stem");
+        assert!(!kept("!legacy_auth", "src/legacy/auth.rs"));
+        assert!(
+            kept("!legacy_auth", "src/legacy/db.rs"),
+            "every term must match"
+        );
+    }
+
+    #[test]
+    fn files_must_match_every_field() {
+        let rust = file(
+            "src/auth/token.rs",
+            Language::Rust,
+            FileRole::Implementation,
+            Some("auth"),
+        );
+        let test = file("src/auth/token_test.go", Language::Go, FileRole::Test, None);
+        let other = file(
+            "src/authz.rs",
+            Language::Rust,
+            FileRole::Implementation,
+            None,
+        );
+        let filters = |query: &str| ParsedQuery::parse(query).filters;
+        let matching = |query: &str| -> Vec<&str> {
+            let filters = filters(query);
+            [&rust, &test, &other]
+                .into_iter()
+                .filter(|f| filters.matches(&f.path, f))
+                .map(|f| f.path.as_str())
+                .collect()
+        };
+
+        assert_eq!(
+            matching("path:src/auth"),
+            ["src/auth/token.rs", "src/auth/token_test.go"]
+        );
+        assert_eq!(matching("path:src/auth lang:rust"), ["src/auth/token.rs"]);
+        assert_eq!(
+            matching("lang:rust,go role:test"),
+            ["src/auth/token_test.go"]
+        );
+        assert_eq!(
+            matching("role:impl lang:Rust"),
+            ["src/auth/token.rs", "src/authz.rs"]
+        );
+        assert_eq!(matching("pkg:auth"), ["src/auth/token.rs"]);
+        assert_eq!(matching("path:**/*_test.go"), ["src/auth/token_test.go"]);
+        assert_eq!(matching("anything").len(), 3);
+    }
+}
@@ -317,36 +317,32 @@ fn resolve_java(import_path: &str, file_index: &HashMap<String, Vec<String>>) ->
 
 /// C/C++: resolve `#include "header.h"` paths.
 ///
-/// Quoted includes are project-local. Resolve relative to the importing file's
-/// directory, then fall back to stem matching.
+/// Quoted includes are project-local, resolved like chunk targets: see
+/// [`crate::imports::c_include`].
 fn resolve_c_include(
     include_path: &str,
     importing_file: &str,
     file_index: &HashMap<String, Vec<String>>,
 ) -> Vec<String> {
-    // Try resolving relative to the importing file's directory
-    let base = Path::new(importing_file).parent().unwrap_or(Path::new(""));
-    let resolved = base.join(include_path);
-    let resolved_str = resolved.to_string_lossy();
-
-    // Check if the resolved path matches any known file exactly
-    for files in file_index.values() {
-        for f in files {
-            if f == resolved_str.as_ref() {
-                return vec![f.clone()];
-            }
-        }
-    }
-
-    // Fall back to stem matching
-    let stem = Path::new(include_path)
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("");
-    file_index
-        .get(&stem.to_lowercase())
-        .cloned()
-        .unwrap_or_default()
+    let stemmed = |path: &str| {
+        let stem = Path::new(path)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("");
+        file_index
+            .get(&stem.to_lowercase())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    };
+    let name = include_path.rsplit('/').next().unwrap_or(include_path);
+    let named = stemmed(include_path)
+        .iter()
+        .map(String::as_str)
+        .filter(|path| path.rsplit('/').next() == Some(name));
+    let exists = |path: &str| stemmed(path).iter().any(|f| f == path);
+    crate::imports::c_include(include_path, importing_file, exists, named)
+        .into_iter()
+        .collect()
 }
 
 /// Ruby: resolve `require` and `require_relative`.
@@ -739,6 +735,17 @@ mod tests {
         assert!(result.contains(&"include/myclass.hpp".to_string()));
     }
 
+    #[test]
+    fn resolve_c_include_ignores_same_stem_files() {
+        let paths = vec!["src/auth.h", "src/auth.c", "lib/auth.h", "src/net/main.c"];
+        let idx = build_file_index(&paths);
+
+        let result = resolve_import("../auth.h", "src/net/main.c", Language::C, &idx);
+        assert_eq!(result, ["src/auth.h"]);
+        let result = resolve_import("auth.h", "src/net/main.c", Language::C, &idx);
+        assert_eq!(result, ["src/auth.h"]);
+    }
+
     #[test]
     fn resolve_ruby_require() {
         let paths = vec!["lib/auth.rb", "lib/handler.rb"];
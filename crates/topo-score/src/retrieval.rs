@@ -0,0 +1,172 @@
+use crate::fusion::RrfFusion;
+use std::collections::HashSet;
+use topo_core::{ScoredFile, VectorMatch};
+
+/// A file in one of the rankings of a [`RetrievalComparison`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankedPath {
+    pub path: String,
+    /// BM25F score, cosine similarity, or RRF score, depending on the
+    /// ranking.
+    pub score: f64,
+}
+
+/// The same query ranked by sparse retrieval (BM25F alone), dense
+/// retrieval (embedding similarity alone), and the RRF fusion of both.
+///
+/// Side by side, they show whether embeddings find files that BM25F
+/// misses in a repository, and so whether they are worth their cost.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RetrievalComparison {
+    pub bm25f: Vec<RankedPath>,
+    pub embedding: Vec<RankedPath>,
+    pub fused: Vec<RankedPath>,
+}
+
+impl RetrievalComparison {
+    /// Compare `scored` files, ranked on their BM25F signal only, with the
+    /// `dense` nearest neighbours of the query's embedding, keeping the
+    /// `top` files of each ranking.
+    ///
+    /// Files that BM25F doesn't match at all are left out of its ranking.
+    /// Both full rankings are fused before truncating, so a file ranked
+    /// just below `top` by both can still make the fused ranking.
+    pub fn new(scored: &[ScoredFile], dense: &[VectorMatch], top: usize) -> Self {
+        let mut sparse: Vec<RankedPath> = scored
+            .iter()
+            .filter(|file| file.signals.bm25f > 0.0)
+            .map(|file| RankedPath {
+                path: file.path.clone(),
+                score: file.signals.bm25f,
+            })
+            .collect();
+        sparse.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.path.cmp(&b.path)));
+
+        let mut embedding: Vec<RankedPath> = dense
+            .iter()
+            .map(|m| RankedPath {
+                path: m.path.clone(),
+                score: m.score,
+            })
+            .collect();
+        embedding.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.path.cmp(&b.path)));
+
+        let rankings: Vec<Vec<&str>> = [&sparse, &embedding]
+            .iter()
+            .map(|ranking| ranking.iter().map(|r| r.path.as_str()).collect())
+            .collect();
+        let mut fused: Vec<RankedPath> = RrfFusion::new()
+            .fuse_paths(&rankings)
+            .into_iter()
+            .map(|r| RankedPath {
+                path: r.path,
+                score: r.rrf_score,
+            })
+            .collect();
+        fused.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.path.cmp(&b.path)));
+
+        sparse.truncate(top);
+        embedding.truncate(top);
+        fused.truncate(top);
+        Self {
+            bm25f: sparse,
+            embedding,
+            fused,
+        }
+    }
+
+    /// Share of the BM25F ranking that the embedding ranking also found,
+    /// from 0.0 (nothing in common) to 1.0. Low overlap means embeddings
+    /// surface different files; whether those are better is for the user
+    /// to judge.
+    pub fn overlap(&self) -> f64 {
+        let dense: HashSet<&str> = self.embedding.iter().map(|r| r.path.as_str()).collect();
+        let shared = self
+            .bm25f
+            .iter()
+            .filter(|r| dense.contains(r.path.as_str()))
+            .count();
+        let size = self.bm25f.len().max(self.embedding.len());
+        if size == 0 {
+            return 0.0;
+        }
+        shared as f64 / size as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use topo_core::{FileRole, Language, SignalBreakdown};
+
+    fn scored(path: &str, bm25f: f64) -> ScoredFile {
+        ScoredFile {
+            path: path.to_string(),
+            score: bm25f,
+            signals: SignalBreakdown {
+                bm25f,
+                ..SignalBreakdown::default()
+            },
+            tokens: 100,
+            language: Language::Rust,
+            role: FileRole::Implementation,
+            preview: None,
+        }
+    }
+
+    fn nearest(path: &str, score: f64) -> VectorMatch {
+        VectorMatch {
+            path: path.to_string(),
+            score,
+        }
+    }
+
+    fn paths(ranking: &[RankedPath]) -> Vec<&str> {
+        ranking.iter().map(|r| r.path.as_str()).collect()
+    }
+
+    #[test]
+    fn ranks_each_method_and_their_fusion() {
+        let sparse = [
+            scored("src/auth.rs", 4.0),
+            scored("src/token.rs", 2.0),
+            scored("src/unrelated.rs", 0.0),
+            scored("src/session.rs", 3.0),
+        ];
+        let dense = [
+            nearest("src/login.rs", 0.9),
+            nearest("src/session.rs", 0.8),
+            nearest("src/auth.rs", 0.7),
+        ];
+
+        let comparison = RetrievalComparison::new(&sparse, &dense, 3);
+        assert_eq!(
+            paths(&comparison.bm25f),
+            ["src/auth.rs", "src/session.rs", "src/token.rs"]
+        );
+        assert_eq!(
+            paths(&comparison.embedding),
+            ["src/login.rs", "src/session.rs", "src/auth.rs"]
+        );
+        // Found by both methods, so fused ahead of files only one found
+        assert_eq!(
+            paths(&comparison.fused)[..2],
+            ["src/auth.rs", "src/session.rs"]
+        );
+        assert_eq!(comparison.fused.len(), 3);
+        assert!((comparison.overlap() - 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn without_dense_results_fusion_follows_bm25f() {
+        let sparse = [scored("b.rs", 1.0), scored("a.rs", 2.0)];
+        let comparison = RetrievalComparison::new(&sparse, &[], 10);
+        assert!(comparison.embedding.is_empty());
+        assert_eq!(paths(&comparison.fused), paths(&comparison.bm25f));
+        assert_eq!(comparison.overlap(), 0.0);
+        assert_eq!(
+            RetrievalComparison::new(&[], &[], 10),
+            RetrievalComparison::default()
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use crate::tokenizer::Tokenizer;
+use std::collections::HashMap;
+
+/// Weight of a query term's synonym relative to the term itself.
+pub const EXPANSION_WEIGHT: f64 = 0.5;
+
+/// Developer abbreviations and what they stand for, as tokens.
+const ABBREVIATIONS: &[(&str, &[&str])] = &[
+    ("a11y", &["accessibility"]),
+    ("admin", &["administrator"]),
+    ("arg", &["argument"]),
+    ("args", &["arguments"]),
+    ("async", &["asynchronous"]),
+    ("auth", &["authentication", "authorization"]),
+    ("authn", &["authentication"]),
+    ("authz", &["authorization"]),
+    ("btn", &["button"]),
+    ("cfg", &["config", "configuration"]),
+    ("cmd", &["command"]),
+    ("config", &["configuration"]),
+    ("conn", &["connection"]),
+    ("ctx", &["context"]),
+    ("db", &["database"]),
+    ("dep", &["dependency"]),
+    ("deps", &["dependencies"]),
+    ("dev", &["development"]),
+    ("dir", &["directory"]),
+    ("doc", &["documentation"]),
+    ("docs", &["documentation"]),
+    ("env", &["environment"]),
+    ("err", &["error"]),
+    ("fn", &["function"]),
+    ("func", &["function"]),
+    ("i18n", &["internationalization"]),
+    ("id", &["identifier"]),
+    ("img", &["image"]),
+    ("impl", &["implementation"]),
+    ("init", &["initialize", "initialization"]),
+    ("k8s", &["kubernetes"]),
+    ("l10n", &["localization"]),
+    ("lib", &["library"]),
+    ("mgr", &["manager"]),
+    ("msg", &["message"]),
+    ("mw", &["middleware"]),
+    ("num", &["number"]),
+    ("obj", &["object"]),
+    ("perf", &["performance"]),
+    ("pkg", &["package"]),
+    ("prod", &["production"]),
+    ("pwd", &["password"]),
+    ("repo", &["repository"]),
+    ("req", &["request"]),
+    ("res", &["response"]),
+    ("resp", &["response"]),
+    ("spec", &["specification"]),
+    ("str", &["string"]),
+    ("svc", &["service"]),
+    ("sync", &["synchronize", "synchronization"]),
+    ("tmp", &["temporary"]),
+    ("util", &["utility"]),
+    ("utils", &["utilities"]),
+    ("val", &["value"]),
+    ("var", &["variable"]),
+    ("ws", &["websocket"]),
+];
+
+/// Query expansion with abbreviations and synonyms, so "db" finds
+/// `database.rs` and "kubernetes" finds `k8s/`.
+///
+/// Expansion works both ways: an abbreviation stands for its long forms
+/// and each long form for the abbreviation, but long forms don't stand for
+/// each other ("authentication" doesn't find "authorization"). Expanded
+/// terms score at [`EXPANSION_WEIGHT`].
+#[derive(Debug, Clone, Default)]
+pub struct Synonyms {
+    /// Term → terms it stands for, both ways.
+    expansions: HashMap<String, Vec<String>>,
+}
+
+impl Synonyms {
+    /// No expansions at all.
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// The built-in developer abbreviations, like `auth`, `cfg`, and
+    /// `k8s`.
+    pub fn builtin() -> Self {
+        let mut synonyms = Self::none();
+        for (term, expansions) in ABBREVIATIONS {
+            synonyms.add(term, expansions.iter().copied());
+        }
+        synonyms
+    }
+
+    /// These synonyms with `term` also standing for `synonyms`, e.g. from
+    /// the `[synonyms]` section of `.topo.toml`. Synonyms may be several
+    /// words or an identifier, each word of which is an expansion.
+    pub fn with<'s>(mut self, term: &str, synonyms: impl IntoIterator<Item = &'s str>) -> Self {
+        self.add(term, synonyms);
+        self
+    }
+
+    fn add<'s>(&mut self, term: &str, synonyms: impl IntoIterator<Item = &'s str>) {
+        let term = term.to_lowercase();
+        for synonym in synonyms.into_iter().flat_map(Tokenizer::tokenize) {
+            if synonym == term {
+                continue;
+            }
+            for (from, to) in [(&term, &synonym), (&synonym, &term)] {
+                let expansions = self.expansions.entry(from.clone()).or_default();
+                if !expansions.contains(to) {
+                    expansions.push(to.clone());
+                }
+            }
+        }
+    }
+
+    /// What `term` stands for, if anything.
+    pub fn of(&self, term: &str) -> &[String] {
+        self.expansions.get(term).map_or(&[], Vec::as_slice)
+    }
+
+    /// The expansions of `tokens` that aren't among them, in order.
+    pub fn expand(&self, tokens: &[String]) -> Vec<String> {
+        let mut expanded: Vec<String> = Vec::new();
+        for expansion in tokens.iter().flat_map(|token| self.of(token)) {
+            if !tokens.contains(expansion) && !expanded.contains(expansion) {
+                expanded.push(expansion.clone());
+            }
+        }
+        expanded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(query: &str) -> Vec<String> {
+        Tokenizer::tokenize(query)
+    }
+
+    #[test]
+    fn abbreviations_expand_both_ways() {
+        let synonyms = Synonyms::builtin();
+        assert_eq!(synonyms.expand(&tokens("db pool")), ["database"]);
+        assert_eq!(synonyms.expand(&tokens("kubernetes")), ["k8s"]);
+        assert_eq!(
+            synonyms.expand(&tokens("auth")),
+            ["authentication", "authorization"]
+        );
+        // Long forms stand for the abbreviation, not for each other
+        assert_eq!(
+            synonyms.expand(&tokens("authentication")),
+            ["auth", "authn"]
+        );
+        assert!(synonyms.expand(&tokens("db database")).is_empty());
+        assert!(Synonyms::none().expand(&tokens("db")).is_empty());
+    }
+
+    #[test]
+    fn user_synonyms_extend_the_builtins() {
+        let synonyms = Synonyms::builtin()
+            .with("tf", ["terraform"])
+            .with("DB", ["dataStore", "db"]);
+        assert_eq!(synonyms.expand(&tokens("tf")), ["terraform"]);
+        assert_eq!(
+            synonyms.expand(&tokens("db")),
+            ["database", "data", "store"]
+        );
+        assert_eq!(synonyms.of("store"), ["db"]);
+    }
+}
@@ -41,6 +41,22 @@ impl Tokenizer {
 
         tokens
     }
+
+    /// [`Tokenizer::tokenize`], then [`stem`] each term, for indexes built
+    /// with stemming.
+    pub fn tokenize_stemmed(input: &str) -> Vec<String> {
+        Self::tokenize(input).iter().map(|t| stem(t)).collect()
+    }
+
+    /// [`Tokenizer::tokenize_stemmed`] when `stemming`, else
+    /// [`Tokenizer::tokenize`].
+    pub fn terms(input: &str, stemming: bool) -> Vec<String> {
+        if stemming {
+            Self::tokenize_stemmed(input)
+        } else {
+            Self::tokenize(input)
+        }
+    }
 }
 
 /// Split a string on camelCase / PascalCase boundaries.
@@ -90,6 +106,223 @@ fn is_stop_word(word: &str) -> bool {
     STOP_WORDS.binary_search(&word).is_ok()
 }
 
+/// Porter stem of a lowercase `word`, so that "authenticate",
+/// "authenticated", and "authentication" all become "authent". Words with
+/// anything but ASCII lowercase letters, like `utf8`, are left alone.
+pub fn stem(word: &str) -> String {
+    if word.len() <= 2 || !word.bytes().all(|b| b.is_ascii_lowercase()) {
+        return word.to_string();
+    }
+    let mut stemmer = Stemmer {
+        word: word.as_bytes().to_vec(),
+        stem: 0,
+    };
+    stemmer.step1ab();
+    stemmer.step1c();
+    stemmer.step2();
+    stemmer.step3();
+    stemmer.step4();
+    stemmer.step5();
+    // Only ASCII letters went in
+    String::from_utf8(stemmer.word).unwrap_or_else(|_| word.to_string())
+}
+
+/// The Porter stemmer's state: the word so far, and the length of the
+/// stem before the suffix last matched by [`Stemmer::ends`].
+struct Stemmer {
+    word: Vec<u8>,
+    stem: usize,
+}
+
+impl Stemmer {
+    /// Whether the letter at `i` is a consonant; `y` is one after a vowel.
+    fn consonant(&self, i: usize) -> bool {
+        match self.word[i] {
+            b'a' | b'e' | b'i' | b'o' | b'u' => false,
+            b'y' => i == 0 || !self.consonant(i - 1),
+            _ => true,
+        }
+    }
+
+    /// The number of vowel-consonant sequences in the stem.
+    fn measure(&self) -> usize {
+        let mut count = 0;
+        let mut vowel = false;
+        for i in 0..self.stem {
+            if !self.consonant(i) {
+                vowel = true;
+            } else if vowel {
+                count += 1;
+                vowel = false;
+            }
+        }
+        count
+    }
+
+    fn vowel_in_stem(&self) -> bool {
+        (0..self.stem).any(|i| !self.consonant(i))
+    }
+
+    /// Whether the first `len` letters end in a double consonant.
+    fn double_consonant(&self, len: usize) -> bool {
+        len >= 2 && self.word[len - 1] == self.word[len - 2] && self.consonant(len - 1)
+    }
+
+    /// Whether the first `len` letters end consonant-vowel-consonant, the
+    /// last not `w`, `x`, or `y`, as in "hop" but not "snow".
+    fn cvc(&self, len: usize) -> bool {
+        len >= 3
+            && self.consonant(len - 1)
+            && !self.consonant(len - 2)
+            && self.consonant(len - 3)
+            && !matches!(self.word[len - 1], b'w' | b'x' | b'y')
+    }
+
+    /// Whether the word ends with `suffix`, setting the stem before it.
+    fn ends(&mut self, suffix: &str) -> bool {
+        if !self.word.ends_with(suffix.as_bytes()) {
+            return false;
+        }
+        self.stem = self.word.len() - suffix.len();
+        true
+    }
+
+    /// Replace the suffix after the stem with `replacement`.
+    fn set_to(&mut self, replacement: &str) {
+        self.word.truncate(self.stem);
+        self.word.extend_from_slice(replacement.as_bytes());
+    }
+
+    /// Replace the first of `rules` whose suffix ends the word, if its
+    /// stem's measure is above `min`.
+    fn replace(&mut self, rules: &[(&str, &str)], min: usize) {
+        if let Some(&(_, replacement)) = rules.iter().find(|(suffix, _)| self.ends(suffix))
+            && self.measure() > min
+        {
+            self.set_to(replacement);
+        }
+    }
+
+    /// Plurals and -ed or -ing.
+    fn step1ab(&mut self) {
+        if self.word.ends_with(b"s") {
+            if self.ends("sses") {
+                self.set_to("ss");
+            } else if self.ends("ies") {
+                self.set_to("i");
+            } else if !self.word.ends_with(b"ss") {
+                self.word.pop();
+            }
+        }
+        if self.ends("eed") {
+            if self.measure() > 0 {
+                self.word.pop();
+            }
+        } else if (self.ends("ed") || self.ends("ing")) && self.vowel_in_stem() {
+            self.word.truncate(self.stem);
+            let len = self.word.len();
+            if self.ends("at") {
+                self.set_to("ate");
+            } else if self.ends("bl") {
+                self.set_to("ble");
+            } else if self.ends("iz") {
+                self.set_to("ize");
+            } else if self.double_consonant(len) {
+                if !matches!(self.word[len - 1], b'l' | b's' | b'z') {
+                    self.word.pop();
+                }
+            } else {
+                self.stem = len;
+                if self.measure() == 1 && self.cvc(len) {
+                    self.word.push(b'e');
+                }
+            }
+        }
+    }
+
+    /// A final `y` after a vowel becomes `i`.
+    fn step1c(&mut self) {
+        if self.ends("y") && self.vowel_in_stem() {
+            let last = self.word.len() - 1;
+            self.word[last] = b'i';
+        }
+    }
+
+    /// Double suffixes map to single ones.
+    fn step2(&mut self) {
+        const RULES: &[(&str, &str)] = &[
+            ("ational", "ate"),
+            ("tional", "tion"),
+            ("enci", "ence"),
+            ("anci", "ance"),
+            ("izer", "ize"),
+            ("bli", "ble"),
+            ("alli", "al"),
+            ("entli", "ent"),
+            ("eli", "e"),
+            ("ousli", "ous"),
+            ("ization", "ize"),
+            ("ation", "ate"),
+            ("ator", "ate"),
+            ("alism", "al"),
+            ("iveness", "ive"),
+            ("fulness", "ful"),
+            ("ousness", "ous"),
+            ("aliti", "al"),
+            ("iviti", "ive"),
+            ("biliti", "ble"),
+            ("logi", "log"),
+        ];
+        self.replace(RULES, 0);
+    }
+
+    /// -ic-, -full, -ness and the like.
+    fn step3(&mut self) {
+        const RULES: &[(&str, &str)] = &[
+            ("icate", "ic"),
+            ("ative", ""),
+            ("alize", "al"),
+            ("iciti", "ic"),
+            ("ical", "ic"),
+            ("ful", ""),
+            ("ness", ""),
+        ];
+        self.replace(RULES, 0);
+    }
+
+    /// -ant, -ence and the like, from stems with more than one syllable.
+    fn step4(&mut self) {
+        const SUFFIXES: &[&str] = &[
+            "al", "ance", "ence", "er", "ic", "able", "ible", "ant", "ement", "ment", "ent", "ion",
+            "ou", "ism", "ate", "iti", "ous", "ive", "ize",
+        ];
+        let found = SUFFIXES.iter().find(|suffix| {
+            self.ends(suffix)
+                && (**suffix != "ion"
+                    || self.stem > 0 && matches!(self.word[self.stem - 1], b's' | b't'))
+        });
+        if found.is_some() && self.measure() > 1 {
+            self.word.truncate(self.stem);
+        }
+    }
+
+    /// A final `-e`, and `-ll` to `-l`, in longer words.
+    fn step5(&mut self) {
+        self.stem = self.word.len();
+        if self.word.ends_with(b"e") {
+            let measure = self.measure();
+            let len = self.word.len();
+            if measure > 1 || measure == 1 && !self.cvc(len - 1) {
+                self.word.pop();
+            }
+        }
+        self.stem = self.word.len();
+        if self.word.ends_with(b"ll") && self.measure() > 1 {
+            self.word.pop();
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -116,4 +349,48 @@ mod tests {
         let windows = Tokenizer::tokenize(r"src\auth\middleware.rs");
         assert_eq!(unix, windows);
     }
+
+    #[test]
+    fn stems_follow_porter() {
+        let cases = [
+            ("caresses", "caress"),
+            ("ponies", "poni"),
+            ("cats", "cat"),
+            ("agreed", "agre"),
+            ("feed", "feed"),
+            ("motoring", "motor"),
+            ("conflated", "conflat"),
+            ("hopping", "hop"),
+            ("falling", "fall"),
+            ("filing", "file"),
+            ("happy", "happi"),
+            ("relational", "relat"),
+            ("conditional", "condit"),
+            ("generalization", "gener"),
+            ("hopeful", "hope"),
+            ("goodness", "good"),
+            ("adjustment", "adjust"),
+            ("effective", "effect"),
+            ("cease", "ceas"),
+            ("controll", "control"),
+        ];
+        for (word, expected) in cases {
+            assert_eq!(stem(word), expected, "{word}");
+        }
+        assert_eq!(stem("utf8"), "utf8");
+        assert_eq!(stem("is"), "is");
+    }
+
+    #[test]
+    fn word_forms_share_a_stem() {
+        assert_eq!(
+            Tokenizer::tokenize_stemmed("authenticate authenticated authentication"),
+            ["authent"; 3]
+        );
+        assert_eq!(Tokenizer::terms("parseConfigs", true), ["pars", "config"]);
+        assert_eq!(
+            Tokenizer::terms("parseConfigs", false),
+            ["parse", "configs"]
+        );
+    }
 }
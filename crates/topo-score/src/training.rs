@@ -0,0 +1,210 @@
+//! Fitting scoring weights to relevance judgments from `topo feedback`.
+
+use crate::fusion::RrfFusion;
+use crate::preset::ScoringPreset;
+use std::collections::HashSet;
+use topo_core::{ScoredFile, WeightProfile};
+
+/// Leading ranks counted by NDCG.
+pub const NDCG_DEPTH: usize = 10;
+
+/// Largest weight of the PageRank ranking tried.
+const MAX_PAGERANK_WEIGHT: f64 = 3.0;
+
+/// First step of coordinate ascent, halved whenever no step improves.
+const INITIAL_STEP: f64 = 0.2;
+
+/// Smallest step tried before stopping.
+const MIN_STEP: f64 = 0.01;
+
+/// A judged query to fit weights to.
+#[derive(Debug, Clone, Default)]
+pub struct TrainingQuery {
+    /// Files to rank with their signals from scoring the query: its leading
+    /// results and the judged files.
+    pub files: Vec<ScoredFile>,
+    /// Paths judged relevant.
+    pub good: HashSet<String>,
+    /// Paths judged not relevant.
+    pub bad: HashSet<String>,
+}
+
+impl TrainingQuery {
+    /// Graded gain of `path`: 3 judged good, 1 unjudged, 0 judged bad, so
+    /// a bad file ranked high costs the place of a file that might help.
+    fn gain(&self, path: &str) -> f64 {
+        if self.good.contains(path) {
+            3.0
+        } else if self.bad.contains(path) {
+            0.0
+        } else {
+            1.0
+        }
+    }
+
+    /// NDCG of the files ranked with `weights`, over the first
+    /// [`NDCG_DEPTH`] ranks: 1.0 when no order of the files would do
+    /// better.
+    pub fn ndcg(&self, weights: &WeightProfile) -> f64 {
+        let dcg = |gains: &mut dyn Iterator<Item = f64>| -> f64 {
+            gains
+                .take(NDCG_DEPTH)
+                .enumerate()
+                .map(|(i, gain)| gain / (i as f64 + 2.0).log2())
+                .sum()
+        };
+        let mut ideal: Vec<f64> = self.files.iter().map(|f| self.gain(&f.path)).collect();
+        ideal.sort_by(|a, b| b.total_cmp(a));
+        let best = dcg(&mut ideal.into_iter());
+        if best == 0.0 {
+            return 1.0;
+        }
+        let ranked = self.rank(weights);
+        dcg(&mut ranked.iter().map(|f| self.gain(&f.path))) / best
+    }
+
+    /// The files ranked as a query scored with `weights` would rank them:
+    /// by the weighted hybrid score, fused with their PageRank ranking.
+    fn rank(&self, weights: &WeightProfile) -> Vec<ScoredFile> {
+        let mut files = self.files.clone();
+        for file in &mut files {
+            file.score =
+                weights.bm25f * file.signals.bm25f + weights.heuristic * file.signals.heuristic;
+        }
+        files.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        let mut pagerank: Vec<(&str, f64)> = self
+            .files
+            .iter()
+            .filter_map(|f| Some((f.path.as_str(), f.signals.pagerank?)))
+            .collect();
+        pagerank.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        if !pagerank.is_empty() {
+            let ranking: Vec<&str> = pagerank.iter().map(|(path, _)| *path).collect();
+            RrfFusion::new().fuse_scored_weighted(&mut files, &[(ranking, weights.pagerank)]);
+        }
+        files
+    }
+}
+
+/// Fit the hybrid and PageRank fusion weights to `queries` by coordinate
+/// ascent on their mean NDCG, starting from the balanced preset's weights.
+///
+/// Each round tries moving the BM25F share of the hybrid score (the
+/// heuristic gets the rest) and the PageRank weight up and down by a step,
+/// keeping moves that raise the mean; the step halves when none does.
+pub fn fit_weights(queries: &[TrainingQuery]) -> WeightProfile {
+    let mean = |weights: &WeightProfile| {
+        if queries.is_empty() {
+            return 1.0;
+        }
+        queries.iter().map(|q| q.ndcg(weights)).sum::<f64>() / queries.len() as f64
+    };
+    let mut best = WeightProfile {
+        bm25f: ScoringPreset::BALANCED.bm25f_weight,
+        heuristic: ScoringPreset::BALANCED.heuristic_weight,
+        pagerank: 1.0,
+        judgments: queries.len(),
+        ndcg: 0.0,
+    };
+    best.ndcg = mean(&best);
+
+    let mut step = INITIAL_STEP;
+    while step >= MIN_STEP {
+        let mut improved = false;
+        for delta in [step, -step] {
+            let bm25f = (best.bm25f + delta).clamp(0.0, 1.0);
+            let pagerank = (best.pagerank + delta).clamp(0.0, MAX_PAGERANK_WEIGHT);
+            let moves = [
+                WeightProfile {
+                    bm25f,
+                    heuristic: 1.0 - bm25f,
+                    ..best
+                },
+                WeightProfile { pagerank, ..best },
+            ];
+            for mut candidate in moves {
+                candidate.ndcg = mean(&candidate);
+                if candidate.ndcg > best.ndcg + f64::EPSILON {
+                    best = candidate;
+                    improved = true;
+                }
+            }
+        }
+        if !improved {
+            step /= 2.0;
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use topo_core::{FileRole, Language, SignalBreakdown};
+
+    fn file(path: &str, bm25f: f64, heuristic: f64) -> ScoredFile {
+        ScoredFile {
+            path: path.to_string(),
+            score: 0.0,
+            signals: SignalBreakdown {
+                bm25f,
+                heuristic,
+                ..Default::default()
+            },
+            tokens: 0,
+            language: Language::Rust,
+            role: FileRole::Implementation,
+            preview: None,
+        }
+    }
+
+    fn query(good: &[&str], bad: &[&str]) -> TrainingQuery {
+        TrainingQuery {
+            files: vec![
+                // Strong on content, weak on path
+                file("src/auth/session.rs", 0.9, 0.1),
+                file("src/legacy/auth.rs", 0.05, 0.9),
+                file("src/util.rs", 0.3, 0.5),
+            ],
+            good: good.iter().map(|p| p.to_string()).collect(),
+            bad: bad.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn ndcg_rewards_good_files_first() {
+        let queries = query(&["src/auth/session.rs"], &["src/legacy/auth.rs"]);
+        let content = WeightProfile {
+            bm25f: 1.0,
+            heuristic: 0.0,
+            pagerank: 1.0,
+            judgments: 1,
+            ndcg: 0.0,
+        };
+        let paths = WeightProfile {
+            bm25f: 0.0,
+            heuristic: 1.0,
+            ..content
+        };
+        assert_eq!(queries.ndcg(&content), 1.0);
+        assert!(queries.ndcg(&paths) < 1.0);
+        assert_eq!(query(&[], &[]).ndcg(&paths), 1.0, "nothing judged");
+    }
+
+    #[test]
+    fn fitting_moves_weights_toward_the_judgments() {
+        let content = fit_weights(&[query(&["src/auth/session.rs"], &["src/legacy/auth.rs"])]);
+        assert_eq!(content.ndcg, 1.0);
+        assert!(content.bm25f > content.heuristic);
+
+        let paths = fit_weights(&[query(&["src/legacy/auth.rs"], &["src/auth/session.rs"])]);
+        assert_eq!(paths.ndcg, 1.0);
+        assert!(paths.heuristic > paths.bm25f);
+        assert!((paths.bm25f + paths.heuristic - 1.0).abs() < 1e-9);
+        assert_eq!(paths.judgments, 1);
+    }
+}
@@ -0,0 +1,405 @@
+//! Comment stripping: split source files into code and comments.
+//!
+//! A small lexer per language family, aware of string literals so that
+//! `"http://"` or `'#'` isn't taken for a comment, but without parsing.
+
+use std::ops::Range;
+use topo_core::Language;
+
+/// A file split by [`strip_comments`]. Both halves keep every line break
+/// of the content, so line numbers mean the same in either.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StrippedComments {
+    /// The content with its comments blanked out.
+    pub code: String,
+    /// The comments, markers included, with the code blanked out.
+    pub comments: String,
+}
+
+/// What a range found by [`literal_ranges`] holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Literal {
+    Comment,
+    String,
+}
+
+/// How a language writes comments and the strings they can't start in.
+struct Syntax {
+    /// Line comment markers.
+    line: &'static [&'static str],
+    /// Block comment delimiters, tried before line markers.
+    blocks: &'static [(&'static str, &'static str)],
+    /// Whether block comments nest, like `/* /* */ */` in Rust.
+    nested: bool,
+    /// String delimiters.
+    quotes: &'static [u8],
+    /// Delimiters whose strings can span lines; others end at the line.
+    multiline: &'static [u8],
+    /// Whether a tripled quote opens a string closed by the same triple.
+    triple: bool,
+}
+
+const C_BLOCK: &[(&str, &str)] = &[("/*", "*/")];
+
+const C_LIKE: Syntax = Syntax {
+    line: &["//"],
+    blocks: C_BLOCK,
+    nested: false,
+    quotes: b"\"'",
+    multiline: b"",
+    triple: false,
+};
+
+const HASH: Syntax = Syntax {
+    line: &["#"],
+    blocks: &[],
+    nested: false,
+    quotes: b"\"'",
+    multiline: b"",
+    triple: false,
+};
+
+/// The comment syntax of `language`, or `None` where it has no comments
+/// worth stripping, like JSON.
+fn syntax(language: Language) -> Option<Syntax> {
+    let syntax = match language {
+        Language::Rust => Syntax {
+            nested: true,
+            multiline: b"\"",
+            ..C_LIKE
+        },
+        Language::C
+        | Language::Cpp
+        | Language::CSharp
+        | Language::Solidity
+        | Language::Protobuf
+        | Language::Java => C_LIKE,
+        Language::Go | Language::JavaScript | Language::TypeScript => Syntax {
+            quotes: b"\"'`",
+            multiline: b"`",
+            ..C_LIKE
+        },
+        Language::Kotlin | Language::Swift | Language::Scala => Syntax {
+            nested: true,
+            triple: true,
+            ..C_LIKE
+        },
+        Language::Dart => Syntax {
+            triple: true,
+            ..C_LIKE
+        },
+        Language::Zig => Syntax {
+            blocks: &[],
+            ..C_LIKE
+        },
+        Language::Php => Syntax {
+            line: &["//", "#"],
+            ..C_LIKE
+        },
+        Language::Vue | Language::Svelte => Syntax {
+            blocks: &[("<!--", "-->"), ("/*", "*/")],
+            quotes: b"\"'`",
+            multiline: b"`",
+            ..C_LIKE
+        },
+        Language::Css => Syntax {
+            line: &[],
+            ..C_LIKE
+        },
+        Language::Python | Language::Elixir => Syntax {
+            triple: true,
+            ..HASH
+        },
+        Language::Ruby
+        | Language::Shell
+        | Language::R
+        | Language::Yaml
+        | Language::Toml
+        | Language::Starlark
+        | Language::GraphQl => HASH,
+        Language::Nix => Syntax {
+            blocks: C_BLOCK,
+            ..HASH
+        },
+        Language::Terraform => Syntax {
+            line: &["#", "//"],
+            blocks: C_BLOCK,
+            ..HASH
+        },
+        Language::Sql => Syntax {
+            line: &["--"],
+            blocks: C_BLOCK,
+            ..HASH
+        },
+        Language::Lua => Syntax {
+            line: &["--"],
+            blocks: &[("--[[", "]]")],
+            ..HASH
+        },
+        Language::Haskell => Syntax {
+            line: &["--"],
+            blocks: &[("{-", "-}")],
+            nested: true,
+            // `'` also ends names, like `x'`
+            quotes: b"\"",
+            ..HASH
+        },
+        Language::Html | Language::Markdown => Syntax {
+            line: &[],
+            blocks: &[("<!--", "-->")],
+            // Prose is full of apostrophes
+            quotes: b"",
+            ..HASH
+        },
+        Language::Json | Language::Other => return None,
+    };
+    Some(syntax)
+}
+
+/// Split `content` into its code and its comments. String literals are
+/// code, so Python docstrings stay in it. Languages without comment
+/// syntax are all code.
+pub fn strip_comments(content: &str, language: Language) -> StrippedComments {
+    let mut code = String::with_capacity(content.len());
+    let mut comments = String::new();
+    let mut at = 0;
+    for (range, literal) in literal_ranges(content, language) {
+        if literal != Literal::Comment {
+            continue;
+        }
+        code.push_str(&content[at..range.start]);
+        blank(&mut comments, &content[at..range.start]);
+        blank(&mut code, &content[range.clone()]);
+        comments.push_str(&content[range.clone()]);
+        at = range.end;
+    }
+    code.push_str(&content[at..]);
+    blank(&mut comments, &content[at..]);
+    StrippedComments { code, comments }
+}
+
+/// Append the line breaks of `text` to `out`.
+fn blank(out: &mut String, text: &str) {
+    out.extend(text.chars().filter(|&c| c == '\n'));
+}
+
+/// Byte ranges of the comments and string literals in `content`, in
+/// order, e.g. to highlight them. Markers and quotes are all ASCII, so
+/// every range starts and ends on a character boundary. Languages without
+/// comment syntax have none.
+pub fn literal_ranges(content: &str, language: Language) -> Vec<(Range<usize>, Literal)> {
+    let Some(syntax) = syntax(language) else {
+        return Vec::new();
+    };
+    let syntax = &syntax;
+    let bytes = content.as_bytes();
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+        if let Some(&(open, close)) = syntax
+            .blocks
+            .iter()
+            .find(|(open, _)| rest.starts_with(open.as_bytes()))
+        {
+            let end = block_end(bytes, i + open.len(), open, close, syntax.nested);
+            ranges.push((i..end, Literal::Comment));
+            i = end;
+        } else if syntax
+            .line
+            .iter()
+            .any(|marker| rest.starts_with(marker.as_bytes()))
+            && opens_line_comment(bytes, i, language)
+        {
+            let end = rest
+                .iter()
+                .position(|&b| b == b'\n')
+                .map_or(bytes.len(), |n| i + n);
+            ranges.push((i..end, Literal::Comment));
+            i = end;
+        } else if syntax.quotes.contains(&bytes[i]) {
+            let end = string_end(content, i, language, syntax);
+            // A lone quote, like a Rust lifetime's, opens no string
+            if end > i + 1 {
+                ranges.push((i..end, Literal::String));
+            }
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+    ranges
+}
+
+/// Whether the line comment marker at `i` starts a comment: PHP's `#[`
+/// opens an attribute, and a shell `#` must start a word, unlike `$#`.
+fn opens_line_comment(bytes: &[u8], i: usize, language: Language) -> bool {
+    match language {
+        Language::Php => !bytes[i..].starts_with(b"#["),
+        Language::Shell => i == 0 || bytes[i - 1].is_ascii_whitespace(),
+        _ => true,
+    }
+}
+
+/// End (exclusive) of the block comment whose body starts at `from`, or
+/// the end of `bytes` when it is never closed.
+fn block_end(bytes: &[u8], from: usize, open: &str, close: &str, nested: bool) -> usize {
+    let mut depth = 1;
+    let mut i = from;
+    while i < bytes.len() {
+        if bytes[i..].starts_with(close.as_bytes()) {
+            depth -= 1;
+            i += close.len();
+            if depth == 0 {
+                return i;
+            }
+        } else if nested && bytes[i..].starts_with(open.as_bytes()) {
+            depth += 1;
+            i += open.len();
+        } else {
+            i += 1;
+        }
+    }
+    bytes.len()
+}
+
+/// End (exclusive) of the string literal opened by the quote at `start`.
+/// Strings that are never closed end at the line, or for multi-line
+/// strings at the end of `content`.
+fn string_end(content: &str, start: usize, language: Language, syntax: &Syntax) -> usize {
+    let bytes = content.as_bytes();
+    let quote = bytes[start];
+    // A Rust `'` opens a character literal only if one follows, not a lifetime
+    if language == Language::Rust && quote == b'\'' {
+        let rest = &content[start + 1..];
+        let char_len = rest.chars().next().map_or(0, char::len_utf8);
+        let literal = rest.starts_with('\\') || rest[char_len..].starts_with('\'');
+        if !literal {
+            return start + 1;
+        }
+    }
+    let triple = [quote; 3];
+    if syntax.triple && bytes[start..].starts_with(&triple) {
+        return bytes[start + 3..]
+            .windows(3)
+            .position(|w| w == triple)
+            .map_or(bytes.len(), |n| start + 3 + n + 3);
+    }
+
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b if b == quote => return i + 1,
+            b'\n' if !syntax.multiline.contains(&quote) => return i,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code(content: &str, language: Language) -> String {
+        strip_comments(content, language).code
+    }
+
+    #[test]
+    fn c_family_line_and_block_comments() {
+        let src = "// Copyright Example\nfn check(url: &str) -> bool { /* old: fn legacy() */\n    url != \"http://x\" // why\n}\n";
+        let stripped = strip_comments(src, Language::Rust);
+        assert_eq!(
+            stripped.code,
+            "\nfn check(url: &str) -> bool { \n    url != \"http://x\" \n}\n"
+        );
+        assert_eq!(
+            stripped.comments,
+            "// Copyright Example\n/* old: fn legacy() */\n// why\n\n"
+        );
+        assert_eq!(stripped.code.lines().count(), src.lines().count());
+    }
+
+    #[test]
+    fn multi_line_and_nested_blocks_keep_line_breaks() {
+        let src = "/* outer\n /* inner */ still */\nlet a = '/';\nlet b = 'x'; // c\n";
+        assert_eq!(
+            code(src, Language::Rust),
+            "\n\nlet a = '/';\nlet b = 'x'; \n"
+        );
+        assert_eq!(
+            code("/* one\n/* two */ x\n", Language::C),
+            "\n x\n",
+            "C blocks don't nest"
+        );
+    }
+
+    #[test]
+    fn rust_lifetimes_are_not_strings() {
+        let src = "fn f<'a>(x: &'a str) -> &'a str { x } // done\n";
+        assert_eq!(
+            code(src, Language::Rust),
+            "fn f<'a>(x: &'a str) -> &'a str { x } \n"
+        );
+    }
+
+    #[test]
+    fn hash_comments_and_docstrings() {
+        let src = "# licence\ndef f():\n    \"\"\"Docs # kept.\"\"\"\n    return '#'  # trailing\n";
+        assert_eq!(
+            code(src, Language::Python),
+            "\ndef f():\n    \"\"\"Docs # kept.\"\"\"\n    return '#'  \n"
+        );
+        assert_eq!(code("echo $# # count\n", Language::Shell), "echo $# \n");
+        assert_eq!(
+            code("#[Route('/')] # note\n", Language::Php),
+            "#[Route('/')] \n"
+        );
+    }
+
+    #[test]
+    fn other_comment_styles() {
+        assert_eq!(
+            code("SELECT 1; -- one\n/* two */ SELECT '--';\n", Language::Sql),
+            "SELECT 1; \n SELECT '--';\n"
+        );
+        assert_eq!(
+            code("--[[ block\n]] local x = 1 -- y\n", Language::Lua),
+            "\n local x = 1 \n"
+        );
+        assert_eq!(
+            code("f x' = x' {- a {- b -} -} -- c\n", Language::Haskell),
+            "f x' = x'  \n"
+        );
+        assert_eq!(
+            code("const s = `a\n// b`;\n", Language::JavaScript),
+            "const s = `a\n// b`;\n"
+        );
+        assert_eq!(
+            code("Don't <!-- hidden --> see\n", Language::Markdown),
+            "Don't  see\n"
+        );
+        assert_eq!(code("{\"a\": \"//\"}", Language::Json), "{\"a\": \"//\"}");
+    }
+
+    #[test]
+    fn literal_ranges_include_strings() {
+        let src = "#[derive(Debug)]\nlet url = \"https://x\"; // note\nfn f<'a>() {}\n";
+        let literals: Vec<_> = literal_ranges(src, Language::Rust)
+            .into_iter()
+            .map(|(range, literal)| (&src[range], literal))
+            .collect();
+        assert_eq!(
+            literals,
+            [
+                ("\"https://x\"", Literal::String),
+                ("// note", Literal::Comment)
+            ]
+        );
+        assert_eq!(
+            literal_ranges("color: #fff; /* a */", Language::Css).len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,242 @@
+//! Structured chunker for YAML, JSON, and TOML config files.
+//!
+//! Emits a [`ChunkKind::Key`] chunk for each top-level key, and each
+//! table header in TOML, spanning the lines up to the next one, so config
+//! files can be found by what they configure rather than their filename
+//! alone. Works line by line like the regex chunker; nothing is parsed.
+
+use topo_core::{Chunk, ChunkKind, Language};
+
+use crate::Chunker;
+
+/// Chunker for the config languages, see [`ConfigChunker::handles`].
+pub struct ConfigChunker;
+
+impl ConfigChunker {
+    /// Whether `language` is a config language this chunker understands.
+    pub fn handles(language: Language) -> bool {
+        matches!(language, Language::Yaml | Language::Json | Language::Toml)
+    }
+}
+
+impl Chunker for ConfigChunker {
+    fn chunk(&self, content: &str, language: Language) -> Vec<Chunk> {
+        let keys = match language {
+            Language::Yaml => yaml_keys(content),
+            Language::Json => json_keys(content),
+            Language::Toml => toml_keys(content),
+            _ => return Vec::new(),
+        };
+        let last = content.lines().count() as u32;
+        let mut chunks: Vec<Chunk> = Vec::with_capacity(keys.len());
+        for (i, (line, name)) in keys.iter().enumerate() {
+            let end_line = keys.get(i + 1).map_or(last, |(next, _)| next - 1);
+            chunks.push(Chunk {
+                kind: ChunkKind::Key,
+                name: name.clone(),
+                start_line: *line,
+                end_line: end_line.max(*line),
+                content: String::new(),
+                target: None,
+                doc: None,
+                parent: None,
+                signature: None,
+                tokens: 0,
+                exported: false,
+            });
+        }
+        chunks
+    }
+}
+
+/// 1-based lines and names of the unindented `key:` lines of a YAML file.
+fn yaml_keys(content: &str) -> Vec<(u32, String)> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.starts_with([' ', '\t', '-', '#', '%']))
+        .filter_map(|(i, line)| {
+            let key = line.split_once(':')?.0.trim();
+            let key = key.trim_matches(|c| c == '"' || c == '\'');
+            (!key.is_empty() && !key.starts_with(['{', '[', '&', '*', '!']))
+                .then(|| ((i + 1) as u32, key.to_string()))
+        })
+        .collect()
+}
+
+/// 1-based lines and names of the keys of a JSON file's top-level object.
+fn json_keys(content: &str) -> Vec<(u32, String)> {
+    let mut keys = Vec::new();
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    // A string at depth 1 that may turn out to be a key
+    let mut string = String::new();
+    let mut candidate: Option<String> = None;
+    for (i, line) in content.lines().enumerate() {
+        for c in line.chars() {
+            if in_string {
+                match c {
+                    _ if escaped => {
+                        escaped = false;
+                        string.push(c);
+                    }
+                    '\\' => escaped = true,
+                    '"' => {
+                        in_string = false;
+                        if depth == 1 {
+                            candidate = Some(std::mem::take(&mut string));
+                        }
+                    }
+                    _ => string.push(c),
+                }
+                continue;
+            }
+            match c {
+                '"' => {
+                    in_string = true;
+                    string.clear();
+                }
+                ':' if depth == 1 => {
+                    if let Some(key) = candidate.take() {
+                        keys.push(((i + 1) as u32, key));
+                    }
+                }
+                '{' | '[' => depth += 1,
+                '}' | ']' => depth = depth.saturating_sub(1),
+                c if !c.is_whitespace() => candidate = None,
+                _ => {}
+            }
+        }
+    }
+    keys
+}
+
+/// 1-based lines and names of a TOML file's table headers, `[database.pool]`
+/// and `[[servers]]`, and of the keys before the first of them.
+fn toml_keys(content: &str) -> Vec<(u32, String)> {
+    let mut keys = Vec::new();
+    let mut in_table = false;
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        let line_num = (i + 1) as u32;
+        if line.starts_with('[') {
+            let header = line
+                .trim_start_matches('[')
+                .split(']')
+                .next()
+                .unwrap_or_default()
+                .trim();
+            if !header.is_empty() {
+                keys.push((line_num, header.to_string()));
+                in_table = true;
+            }
+            continue;
+        }
+        if in_table || line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some((key, _)) = line.split_once('=') {
+            let key = key.trim().trim_matches('"');
+            if !key.is_empty() {
+                keys.push((line_num, key.to_string()));
+            }
+        }
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(src: &str, language: Language) -> Vec<(String, u32, u32)> {
+        ConfigChunker
+            .chunk(src, language)
+            .into_iter()
+            .map(|c| {
+                assert_eq!(c.kind, ChunkKind::Key);
+                (c.name, c.start_line, c.end_line)
+            })
+            .collect()
+    }
+
+    fn key(name: &str, start: u32, end: u32) -> (String, u32, u32) {
+        (name.to_string(), start, end)
+    }
+
+    #[test]
+    fn yaml_top_level_keys() {
+        let src = "\
+# production settings
+---
+database:
+  pool:
+    max_connections: 20
+\"log_level\": info
+servers:
+  - web1
+  - web2
+";
+        assert_eq!(
+            keys(src, Language::Yaml),
+            [
+                key("database", 3, 5),
+                key("log_level", 6, 6),
+                key("servers", 7, 9)
+            ]
+        );
+    }
+
+    #[test]
+    fn json_top_level_keys() {
+        let src = r#"{
+  "name": "app",
+  "database": {
+    "pool": { "max": 20 },
+    "hosts": ["a:1", "b\"c"]
+  },
+  "scripts": {"build": "tsc"}
+}
+"#;
+        assert_eq!(
+            keys(src, Language::Json),
+            [
+                key("name", 2, 2),
+                key("database", 3, 6),
+                key("scripts", 7, 8)
+            ]
+        );
+        assert!(keys("[1, 2]", Language::Json).is_empty());
+    }
+
+    #[test]
+    fn toml_tables_and_leading_keys() {
+        let src = "\
+title = \"app\"
+
+[database.pool]
+max = 20
+
+[[servers]]
+name = \"web\"
+";
+        assert_eq!(
+            keys(src, Language::Toml),
+            [
+                key("title", 1, 2),
+                key("database.pool", 3, 5),
+                key("servers", 6, 7)
+            ]
+        );
+    }
+
+    #[test]
+    fn other_languages_give_nothing() {
+        assert!(
+            ConfigChunker
+                .chunk("fn main() {}", Language::Rust)
+                .is_empty()
+        );
+    }
+}
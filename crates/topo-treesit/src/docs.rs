@@ -0,0 +1,518 @@
+//! Doc comment and docstring extraction.
+//!
+//! Like the regex chunker, this works line by line without parsing, so it
+//! recognizes each language's documentation conventions rather than every
+//! comment.
+
+use topo_core::{Chunk, Language};
+
+/// Declarations a Go doc comment sits directly above.
+const GO_DECLARATIONS: &[&str] = &["package ", "func ", "type ", "var ", "const "];
+
+/// Declarations a Ruby doc comment sits directly above.
+const RUBY_DECLARATIONS: &[&str] = &["def ", "class ", "module "];
+
+/// Statements a SQL doc comment sits directly above.
+const SQL_DECLARATIONS: &[&str] = &["CREATE ", "create "];
+
+/// Declarations a Protobuf doc comment sits directly above.
+const PROTOBUF_DECLARATIONS: &[&str] = &["message ", "enum ", "service ", "rpc "];
+
+/// Lines a Python `def` or `class` header is looked for the colon ending
+/// it on.
+const MAX_HEADER_LINES: usize = 20;
+
+/// The text of the doc comments and docstrings in `content`, with comment
+/// markers left in place for the tokenizer to drop.
+///
+/// Recognizes `///`, `//!`, `/** */`, and `/*! */` in Rust; `/** */` (plus
+/// `///` where it is conventional) in C-family languages; comments directly
+/// above declarations in Go, Ruby, SQL, and Protobuf; Python and Elixir docstrings; and
+/// Haskell `-- |`, Lua `---`, and roxygen `#'` comments.
+pub fn doc_comments(content: &str, language: Language) -> Vec<&str> {
+    match language {
+        Language::Rust => {
+            let mut docs = line_docs(content, &["///", "//!"]);
+            docs.extend(block_docs(content, "/**", "*/"));
+            docs.extend(block_docs(content, "/*!", "*/"));
+            docs
+        }
+        Language::C
+        | Language::Cpp
+        | Language::Swift
+        | Language::Solidity
+        | Language::CSharp
+        | Language::Dart => {
+            let mut docs = line_docs(content, &["///"]);
+            docs.extend(block_docs(content, "/**", "*/"));
+            docs
+        }
+        Language::Java
+        | Language::JavaScript
+        | Language::TypeScript
+        | Language::Kotlin
+        | Language::Scala
+        | Language::Php
+        | Language::Vue
+        | Language::Svelte => block_docs(content, "/**", "*/"),
+        Language::Go => preceding_comments(content, "//", GO_DECLARATIONS),
+        Language::Ruby => preceding_comments(content, "#", RUBY_DECLARATIONS),
+        Language::Sql => preceding_comments(content, "--", SQL_DECLARATIONS),
+        Language::Protobuf => preceding_comments(content, "//", PROTOBUF_DECLARATIONS),
+        Language::Python => python_docstrings(content),
+        Language::Elixir => elixir_docs(content),
+        Language::Haskell => line_docs(content, &["-- |", "-- ^"]),
+        Language::Lua => line_docs(content, &["---"]),
+        Language::R => line_docs(content, &["#'"]),
+        _ => Vec::new(),
+    }
+}
+
+/// Attach to each function, method, type, and impl chunk the doc comment
+/// or docstring of its declaration, see [`Chunk::doc`].
+pub fn attach_docs(content: &str, chunks: &mut [Chunk], language: Language) {
+    let lines: Vec<&str> = content.lines().collect();
+    for chunk in chunks.iter_mut().filter(|c| documentable(c)) {
+        chunk.doc = declaration_doc(&lines, chunk.start_line, language).map(|doc| doc.text);
+    }
+}
+
+/// 1-based lines summarizing `chunk`: its first line and the doc comment
+/// or docstring of its declaration, which are contiguous.
+pub fn summary_span(lines: &[&str], chunk: &Chunk, language: Language) -> (u32, u32) {
+    let start = chunk.start_line;
+    match declaration_doc(lines, start, language).filter(|_| documentable(chunk)) {
+        Some(doc) => (doc.first.min(start), doc.last.max(start)),
+        None => (start, start),
+    }
+}
+
+fn documentable(chunk: &Chunk) -> bool {
+    chunk.kind.is_declaration()
+}
+
+/// A declaration's doc comment or docstring.
+struct DeclarationDoc {
+    /// Without comment markers, quotes, or indentation.
+    text: String,
+    /// 1-based lines it spans.
+    first: u32,
+    last: u32,
+}
+
+/// The doc comment or docstring of the declaration on 1-based `line`:
+/// the doc comment directly above it (past attributes and decorators),
+/// or for Python the docstring opening its body.
+fn declaration_doc(lines: &[&str], line: u32, language: Language) -> Option<DeclarationDoc> {
+    let at = (line as usize)
+        .checked_sub(1)
+        .filter(|&i| i < lines.len())?;
+    let (prefixes, block): (&[&str], bool) = match language {
+        Language::Rust
+        | Language::C
+        | Language::Cpp
+        | Language::Swift
+        | Language::Solidity
+        | Language::CSharp
+        | Language::Dart => (&["///"], true),
+        Language::Java
+        | Language::JavaScript
+        | Language::TypeScript
+        | Language::Kotlin
+        | Language::Scala
+        | Language::Php
+        | Language::Vue
+        | Language::Svelte => (&[], true),
+        Language::Go | Language::Protobuf => (&["//"], false),
+        Language::Ruby => (&["#"], false),
+        Language::Sql => (&["--"], false),
+        Language::Haskell => (&["-- |"], false),
+        Language::Lua => (&["---"], false),
+        Language::R => (&["#'"], false),
+        Language::Python => return python_docstring(lines, at),
+        Language::Elixir => return elixir_doc(lines, at),
+        _ => return None,
+    };
+    // Attributes and decorators sit between a doc comment and its declaration
+    let mut end = at;
+    while end > 0 && is_attribute(lines[end - 1].trim_start(), language) {
+        end -= 1;
+    }
+
+    let strip = |line: &'_ str| -> Option<String> {
+        let line = line.trim_start();
+        let rest = prefixes
+            .iter()
+            .find_map(|prefix| line.strip_prefix(prefix))?;
+        // `////` is a plain comment in Rust
+        (language != Language::Rust || !rest.starts_with('/')).then(|| rest.to_string())
+    };
+    let mut start = end;
+    while start > 0 && strip(lines[start - 1]).is_some() {
+        start -= 1;
+    }
+    if start < end {
+        let text: Vec<String> = lines[start..end].iter().filter_map(|l| strip(l)).collect();
+        return doc(&text, start, end - 1);
+    }
+
+    if !block || end == 0 || !lines[end - 1].trim_end().ends_with("*/") {
+        return None;
+    }
+    let open = (0..end).rev().find(|&i| lines[i].contains("/*"))?;
+    let first = lines[open].trim_start();
+    if !first.starts_with("/**") || first.starts_with("/**/") {
+        return None;
+    }
+    let text: Vec<String> = lines[open..end]
+        .iter()
+        .map(|line| {
+            let line = line.trim();
+            let line = line.strip_prefix("/**").unwrap_or(line);
+            let line = line.strip_suffix("*/").unwrap_or(line).trim();
+            line.strip_prefix('*').unwrap_or(line).to_string()
+        })
+        .collect();
+    doc(&text, open, end - 1)
+}
+
+/// Whether `line` is an attribute or decorator of the declaration below.
+fn is_attribute(line: &str, language: Language) -> bool {
+    match language {
+        Language::Rust => line.starts_with("#["),
+        Language::Go | Language::Ruby | Language::Haskell | Language::Lua | Language::R => false,
+        _ => line.starts_with('@'),
+    }
+}
+
+/// The docstring opening the body of the `def` or `class` whose header
+/// starts on 0-based `at`.
+fn python_docstring(lines: &[&str], at: usize) -> Option<DeclarationDoc> {
+    // Headers can run over several lines
+    let header_end = (at..lines.len().min(at + MAX_HEADER_LINES)).find(|&i| {
+        let code = lines[i].split('#').next().unwrap_or_default();
+        code.trim_end().ends_with(':')
+    })?;
+    let open = (header_end + 1..lines.len()).find(|&i| !lines[i].trim().is_empty())?;
+    let line = lines[open].trim().trim_start_matches(['r', 'u', 'R', 'U']);
+    let quote = ["\"\"\"", "'''"]
+        .into_iter()
+        .find(|q| line.starts_with(q))?;
+    let body = &line[quote.len()..];
+    if let Some(end) = body.find(quote) {
+        return doc(&[body[..end].to_string()], open, open);
+    }
+    let close = (open + 1..lines.len()).find(|&i| lines[i].contains(quote))?;
+    let mut text = vec![body.to_string()];
+    text.extend(lines[open + 1..close].iter().map(|l| l.to_string()));
+    text.push(
+        lines[close]
+            .split(quote)
+            .next()
+            .unwrap_or_default()
+            .to_string(),
+    );
+    doc(&text, open, close)
+}
+
+/// The `@doc` string or heredoc above the `def` on 0-based `at`, past
+/// `@spec` and `@impl` lines.
+fn elixir_doc(lines: &[&str], at: usize) -> Option<DeclarationDoc> {
+    let mut end = at;
+    while end > 0
+        && ["@spec", "@impl"]
+            .iter()
+            .any(|a| lines[end - 1].trim_start().starts_with(a))
+    {
+        end -= 1;
+    }
+    let above = lines[end.checked_sub(1)?].trim();
+    if let Some(rest) = above.strip_prefix("@doc \"") {
+        let text = rest.strip_suffix('"').unwrap_or(rest);
+        return doc(&[text.to_string()], end - 1, end - 1);
+    }
+    if above != "\"\"\"" {
+        return None;
+    }
+    let open = (0..end - 1)
+        .rev()
+        .find(|&i| lines[i].trim() == "@doc \"\"\"")?;
+    let text: Vec<String> = lines[open + 1..end - 1]
+        .iter()
+        .map(|l| l.to_string())
+        .collect();
+    doc(&text, open, end - 1)
+}
+
+/// A [`DeclarationDoc`] of `text` lines spanning 0-based `first..=last`;
+/// None if it is blank.
+fn doc(text: &[String], first: usize, last: usize) -> Option<DeclarationDoc> {
+    let text = text
+        .iter()
+        .map(|line| line.trim())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let text = text.trim();
+    (!text.is_empty()).then(|| DeclarationDoc {
+        text: text.to_string(),
+        first: first as u32 + 1,
+        last: last as u32 + 1,
+    })
+}
+
+/// Lines starting with any of `prefixes`, after indentation.
+fn line_docs<'a>(content: &'a str, prefixes: &[&str]) -> Vec<&'a str> {
+    content
+        .lines()
+        .map(str::trim_start)
+        .filter_map(|line| prefixes.iter().find_map(|prefix| line.strip_prefix(prefix)))
+        .collect()
+}
+
+/// Text between each `open` marker and the next `close`.
+fn block_docs<'a>(content: &'a str, open: &str, close: &str) -> Vec<&'a str> {
+    let mut docs = Vec::new();
+    let mut rest = content;
+    while let Some(start) = rest.find(open) {
+        let body = &rest[start + open.len()..];
+        // `/**/` is an empty plain comment, not a doc comment
+        if let Some(after) = body.strip_prefix('/') {
+            rest = after;
+            continue;
+        }
+        let end = body.find(close).unwrap_or(body.len());
+        docs.push(&body[..end]);
+        rest = &body[end..];
+    }
+    docs
+}
+
+/// Runs of `marker` comment lines directly above a line starting with one
+/// of `declarations`.
+fn preceding_comments<'a>(content: &'a str, marker: &str, declarations: &[&str]) -> Vec<&'a str> {
+    let mut docs = Vec::new();
+    let mut run: Vec<&str> = Vec::new();
+    for line in content.lines().map(str::trim_start) {
+        if let Some(comment) = line.strip_prefix(marker) {
+            run.push(comment);
+            continue;
+        }
+        if declarations.iter().any(|d| line.starts_with(d)) {
+            docs.append(&mut run);
+        }
+        run.clear();
+    }
+    docs
+}
+
+/// Triple-quoted strings opening a module or following a `def` or `class`
+/// header.
+fn python_docstrings(content: &str) -> Vec<&str> {
+    let mut docs = Vec::new();
+    // Whether the next statement can be a docstring
+    let mut expecting = true;
+    let mut rest = content;
+    while let Some(newline) = rest.find('\n').or((!rest.is_empty()).then_some(rest.len())) {
+        let line = rest[..newline].trim();
+        let quote = ["\"\"\"", "'''"]
+            .into_iter()
+            .find(|q| line.trim_start_matches(['r', 'u', 'R', 'U']).starts_with(q));
+        match quote {
+            Some(quote) if expecting => {
+                let start = rest.find(quote).unwrap_or(0) + quote.len();
+                let end = rest[start..].find(quote).map_or(rest.len(), |e| start + e);
+                docs.push(&rest[start..end]);
+                rest = &rest[(end + quote.len()).min(rest.len())..];
+                expecting = false;
+                continue;
+            }
+            _ => {
+                if !line.is_empty() && !line.starts_with('#') {
+                    let header = line.starts_with("def ")
+                        || line.starts_with("async def ")
+                        || line.starts_with("class ");
+                    expecting = header && line.ends_with(':');
+                }
+            }
+        }
+        rest = &rest[(newline + 1).min(rest.len())..];
+    }
+    docs
+}
+
+/// `@doc` and `@moduledoc` strings and heredocs.
+fn elixir_docs(content: &str) -> Vec<&str> {
+    let mut docs = Vec::new();
+    let mut rest = content;
+    while let Some(at) = ["@doc", "@moduledoc"]
+        .into_iter()
+        .filter_map(|attr| rest.find(attr))
+        .min()
+    {
+        let after = &rest[at..];
+        let Some(open) = after.find('"') else {
+            break;
+        };
+        let (quote, start) = if after[open..].starts_with("\"\"\"") {
+            ("\"\"\"", open + 3)
+        } else {
+            ("\"", open + 1)
+        };
+        let end = after[start..]
+            .find(quote)
+            .map_or(after.len(), |e| start + e);
+        docs.push(&after[start..end]);
+        rest = &after[(end + quote.len()).min(after.len())..];
+    }
+    docs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use topo_core::ChunkKind;
+
+    #[test]
+    fn rust_doc_comments() {
+        let src = "//! Crate docs\n/// Checks the token\nfn check() {\n    // not a doc\n}\n/** Block docs */\nstruct S; /**/\n";
+        assert_eq!(
+            doc_comments(src, Language::Rust),
+            [" Crate docs", " Checks the token", " Block docs "]
+        );
+    }
+
+    #[test]
+    fn python_docstrings_follow_headers() {
+        let src = "\"\"\"Module docs.\"\"\"\nimport os\n\ndef login(user):\n    \"\"\"Log a user in.\n\n    Returns a session.\n    \"\"\"\n    x = \"\"\"not a docstring\"\"\"\n\nclass Session:\n    '''Session state.'''\n";
+        assert_eq!(
+            doc_comments(src, Language::Python),
+            [
+                "Module docs.",
+                "Log a user in.\n\n    Returns a session.\n    ",
+                "Session state."
+            ]
+        );
+    }
+
+    #[test]
+    fn go_and_ruby_comments_above_declarations() {
+        let go = "// Package auth verifies tokens.\npackage auth\n\n// helper comment\nx := 1\n\n// Verify checks a token.\nfunc Verify() {}\n";
+        assert_eq!(
+            doc_comments(go, Language::Go),
+            [" Package auth verifies tokens.", " Verify checks a token."]
+        );
+        let ruby = "# Logs users in.\nclass Login\n  # Runs it.\n  def call; end\nend\n";
+        assert_eq!(
+            doc_comments(ruby, Language::Ruby),
+            [" Logs users in.", " Runs it."]
+        );
+    }
+
+    fn chunk(kind: ChunkKind, line: u32) -> Chunk {
+        Chunk {
+            kind,
+            name: String::new(),
+            start_line: line,
+            end_line: line,
+            content: String::new(),
+            target: None,
+            doc: None,
+            parent: None,
+            signature: None,
+            tokens: 0,
+            exported: false,
+        }
+    }
+
+    fn docs_of(src: &str, language: Language, lines: &[u32]) -> Vec<Option<String>> {
+        let mut chunks: Vec<Chunk> = lines
+            .iter()
+            .map(|&line| chunk(ChunkKind::Function, line))
+            .collect();
+        attach_docs(src, &mut chunks, language);
+        chunks.into_iter().map(|c| c.doc).collect()
+    }
+
+    #[test]
+    fn declarations_get_the_doc_comment_above_them() {
+        let rust = "//! Crate docs\n/// Checks the token.\n///\n/// Fails when expired.\n#[inline]\nfn check() {}\n\n// plain\nfn plain() {}\n//// banner\nfn banner() {}\n";
+        assert_eq!(
+            docs_of(rust, Language::Rust, &[6, 9, 11]),
+            [
+                Some("Checks the token.\n\nFails when expired.".to_string()),
+                None,
+                None
+            ]
+        );
+        let ts = "/**\n * Adds numbers.\n * @param a first\n */\n@memo\nexport function add(a) {}\n/* plain */\nfunction sub() {}\n";
+        assert_eq!(
+            docs_of(ts, Language::TypeScript, &[6, 8]),
+            [Some("Adds numbers.\n@param a first".to_string()), None]
+        );
+        let go = "// Verify checks a token.\nfunc Verify() {}\n";
+        assert_eq!(
+            docs_of(go, Language::Go, &[2]),
+            [Some("Verify checks a token.".to_string())]
+        );
+        let ex = "@doc \"\"\"\nLogs in.\n\"\"\"\n@spec login() :: :ok\ndef login, do: :ok\n";
+        assert_eq!(
+            docs_of(ex, Language::Elixir, &[5]),
+            [Some("Logs in.".to_string())]
+        );
+    }
+
+    #[test]
+    fn python_declarations_get_their_docstring() {
+        let src = "def login(\n    user,\n):\n    \"\"\"Log a user in.\n\n    Returns a session.\n    \"\"\"\n    return 1\n\nclass Session:\n    '''Session state.'''\n\ndef bare():\n    pass\n";
+        assert_eq!(
+            docs_of(src, Language::Python, &[1, 10, 13]),
+            [
+                Some("Log a user in.\n\nReturns a session.".to_string()),
+                Some("Session state.".to_string()),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn summaries_span_the_first_line_and_doc() {
+        let rust = "/// Checks the token.\nfn check() {\n    body();\n}\n";
+        let lines: Vec<&str> = rust.lines().collect();
+        let check = chunk(ChunkKind::Function, 2);
+        assert_eq!(summary_span(&lines, &check, Language::Rust), (1, 2));
+        // Imports aren't summarized by their doc
+        let import = chunk(ChunkKind::Import, 2);
+        assert_eq!(summary_span(&lines, &import, Language::Rust), (2, 2));
+
+        let python = "def login():\n    \"\"\"Log in.\n    Really.\"\"\"\n    return 1\n";
+        let lines: Vec<&str> = python.lines().collect();
+        let login = chunk(ChunkKind::Function, 1);
+        assert_eq!(summary_span(&lines, &login, Language::Python), (1, 3));
+    }
+
+    #[test]
+    fn other_languages() {
+        let js = "/**\n * Adds numbers.\n */\nfunction add() {}\n/* plain */\n";
+        assert_eq!(
+            doc_comments(js, Language::JavaScript),
+            ["\n * Adds numbers.\n "]
+        );
+        let ex = "@moduledoc \"\"\"\nAuth helpers.\n\"\"\"\n@doc \"Logs in.\"\n";
+        assert_eq!(
+            doc_comments(ex, Language::Elixir),
+            ["\nAuth helpers.\n", "Logs in."]
+        );
+        assert!(doc_comments("# Title\n", Language::Markdown).is_empty());
+        let sql = "-- Registered accounts.\nCREATE TABLE users (\n    id INT -- key\n);\n";
+        assert_eq!(doc_comments(sql, Language::Sql), [" Registered accounts."]);
+        assert_eq!(
+            docs_of(sql, Language::Sql, &[2]),
+            [Some("Registered accounts.".to_string())]
+        );
+        let proto = "service Auth {\n  // Exchanges a password for a token.\n  rpc Login(Req) returns (Resp);\n}\n";
+        assert_eq!(
+            docs_of(proto, Language::Protobuf, &[3]),
+            [Some("Exchanges a password for a token.".to_string())]
+        );
+    }
+}
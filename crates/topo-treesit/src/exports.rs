@@ -0,0 +1,235 @@
+//! Public API detection.
+//!
+//! Like the regex chunker, this reads declaration lines rather than
+//! parsing them, so it recognizes each language's visibility conventions:
+//! `pub` in Rust, `export` in JavaScript and TypeScript, a capitalized name
+//! in Go, `__all__` (or no leading underscore) in Python, and `public` in
+//! Java and C#. Other languages export nothing.
+
+use topo_core::{Chunk, ChunkKind, Language};
+
+/// Mark each of `chunks` declared public in `content`, see
+/// [`Chunk::exported`], and return the file's public API: the qualified
+/// names of the exported chunks, and the names re-exported by Rust
+/// `pub use`, JavaScript `export { … }`, and Python `__all__`.
+pub fn mark_exports(content: &str, chunks: &mut [Chunk], language: Language) -> Vec<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let python_all = (language == Language::Python)
+        .then(|| python_all(content))
+        .flatten();
+    // Python and JavaScript methods are public when their class is
+    let mut exported_types: Vec<String> = Vec::new();
+    let mut exports: Vec<String> = Vec::new();
+    for chunk in chunks.iter_mut() {
+        // Impls and tests are never part of an API
+        if !chunk.kind.is_declaration()
+            || matches!(chunk.kind, ChunkKind::Impl | ChunkKind::TestCase)
+        {
+            continue;
+        }
+        let Some(line) = (chunk.start_line as usize)
+            .checked_sub(1)
+            .and_then(|i| lines.get(i))
+        else {
+            continue;
+        };
+        let line = line.trim_start();
+        let parent_exported = chunk
+            .parent
+            .as_ref()
+            .is_some_and(|parent| exported_types.contains(parent));
+        chunk.exported = match language {
+            Language::Rust => line.starts_with("pub "),
+            Language::Go => chunk.name.starts_with(char::is_uppercase),
+            Language::JavaScript | Language::TypeScript => match chunk.parent {
+                Some(_) => {
+                    parent_exported
+                        && !["#", "private ", "protected "]
+                            .iter()
+                            .any(|modifier| line.starts_with(modifier))
+                }
+                None => line.starts_with("export "),
+            },
+            Language::Python => match (&chunk.parent, &python_all) {
+                (Some(_), _) => parent_exported && !chunk.name.starts_with('_'),
+                (None, Some(all)) => all.contains(&chunk.name),
+                (None, None) => !chunk.name.starts_with('_'),
+            },
+            Language::Java | Language::CSharp => line
+                .split_whitespace()
+                .take_while(|word| !word.contains(chunk.name.as_str()))
+                .any(|word| word == "public"),
+            _ => false,
+        };
+        if chunk.exported {
+            if chunk.kind.is_type() {
+                exported_types.push(chunk.name.clone());
+            }
+            exports.push(chunk.qualified_name());
+        }
+    }
+
+    let reexported = match language {
+        Language::Rust => reexports(&lines, "pub use "),
+        Language::JavaScript | Language::TypeScript => reexports(&lines, "export {"),
+        Language::Python => python_all.unwrap_or_default(),
+        _ => Vec::new(),
+    };
+    for name in reexported {
+        if !exports.contains(&name) {
+            exports.push(name);
+        }
+    }
+    exports
+}
+
+/// Names re-exported by the lines starting with `prefix`: the last segment
+/// or alias of each path in `pub use a::{B, c::D as E};`, and each name or
+/// alias in `export { a, b as c } from './x';`.
+fn reexports(lines: &[&str], prefix: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    for line in lines.iter().map(|line| line.trim_start()) {
+        let Some(rest) = line.strip_prefix(prefix) else {
+            continue;
+        };
+        // A braced list, or its remainder when the prefix opened it
+        let list = match (rest.find('{'), rest.find('}')) {
+            (Some(open), Some(close)) if open < close => &rest[open + 1..close],
+            (None, Some(close)) => &rest[..close],
+            _ => rest.split(';').next().unwrap_or_default(),
+        };
+        for item in list.split(',') {
+            let item = item.trim();
+            let name = match item.rsplit_once(" as ") {
+                Some((_, alias)) => alias,
+                None => item.rsplit("::").next().unwrap_or(item),
+            };
+            let name = name.trim();
+            if !name.is_empty() && !matches!(name, "*" | "self" | "_") {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names
+}
+
+/// The names listed in a Python module's `__all__`, if it has one.
+fn python_all(content: &str) -> Option<Vec<String>> {
+    let start = match content.strip_prefix("__all__") {
+        Some(_) => 0,
+        None => content.find("\n__all__")? + 1,
+    };
+    let rest = &content[start..];
+    let open = rest.find(['[', '('])?;
+    let close = open + rest[open..].find([']', ')'])?;
+    let names = rest[open + 1..close]
+        .split(',')
+        .map(|item| item.trim().trim_matches(|c| c == '"' || c == '\''))
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect();
+    Some(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Chunker, RegexChunker};
+
+    fn exports(src: &str, language: Language) -> (Vec<String>, Vec<String>) {
+        let mut chunks = RegexChunker.chunk(src, language);
+        let exports = mark_exports(src, &mut chunks, language);
+        let marked = chunks
+            .iter()
+            .filter(|c| c.exported)
+            .map(Chunk::qualified_name)
+            .collect();
+        (marked, exports)
+    }
+
+    #[test]
+    fn rust_pub_items_and_reexports() {
+        let src = "\
+pub use crate::auth::{Token, session::Session as UserSession};
+pub struct Auth {}
+impl Auth {
+    pub fn check(&self) {}
+    fn hash(&self) {}
+}
+pub(crate) fn helper() {}
+fn private() {}
+";
+        let (marked, exports) = exports(src, Language::Rust);
+        assert_eq!(marked, ["Auth", "Auth::check"]);
+        assert_eq!(exports, ["Auth", "Auth::check", "Token", "UserSession"]);
+    }
+
+    #[test]
+    fn typescript_exports_and_public_methods() {
+        let src = "\
+export class Auth {
+  login(user: string) {}
+  private hash() {}
+}
+class Internal {
+  run() {}
+}
+export function logout() {}
+export { Internal as Helper, parse } from './parse';
+";
+        let (marked, exports) = exports(src, Language::TypeScript);
+        assert_eq!(marked, ["Auth", "Auth::login", "logout"]);
+        assert_eq!(
+            exports,
+            ["Auth", "Auth::login", "logout", "Helper", "parse"]
+        );
+    }
+
+    #[test]
+    fn go_capitalized_names() {
+        let src = "\
+type Auth struct{}
+func (a *Auth) Check() bool { return true }
+func (a *Auth) hash() {}
+func helper() {}
+";
+        let (marked, _) = exports(src, Language::Go);
+        assert_eq!(marked, ["Auth", "Auth::Check"]);
+    }
+
+    #[test]
+    fn python_all_or_underscores() {
+        let src = "\
+class Auth:
+    def check(self):
+        pass
+    def _hash(self):
+        pass
+def _helper():
+    pass
+";
+        let (marked, _) = exports(src, Language::Python);
+        assert_eq!(marked, ["Auth", "Auth::check"]);
+
+        let src = "__all__ = [\n    \"login\",\n    'Session',\n]\ndef login():\n    pass\ndef logout():\n    pass\n";
+        let (marked, exports) = exports(src, Language::Python);
+        assert_eq!(marked, ["login"]);
+        assert_eq!(exports, ["login", "Session"]);
+    }
+
+    #[test]
+    fn java_public_members() {
+        let src = "\
+public class AuthService {
+    public boolean check(String token) {
+        return true;
+    }
+    private void hash() {}
+}
+";
+        let (marked, _) = exports(src, Language::Java);
+        assert_eq!(marked, ["AuthService", "AuthService::check"]);
+        let (marked, exports) = exports("function f() {}\n", Language::Shell);
+        assert!(marked.is_empty() && exports.is_empty());
+    }
+}
@@ -1,15 +1,36 @@
 //! Code chunking: extract functions, types, and imports from source files.
 //!
-//! Uses tree-sitter for precise AST chunking when a grammar is available,
-//! with regex-based fallback for unsupported languages.
+//! With the `tree-sitter` feature, uses tree-sitter for precise AST
+//! chunking when a grammar is available, with regex-based fallback for
+//! unsupported languages. Without it, chunks with regexes alone.
 
+mod comments;
+mod config_chunker;
+mod docs;
+mod exports;
+mod lsp;
+#[cfg(feature = "tree-sitter")]
 mod queries;
 mod regex_chunker;
+mod registry;
+mod sfc_chunker;
+#[cfg(feature = "tree-sitter")]
 mod ts_chunker;
+mod window_chunker;
 
+pub use comments::{Literal, StrippedComments, literal_ranges, strip_comments};
+pub use config_chunker::ConfigChunker;
+pub use docs::{attach_docs, doc_comments, summary_span};
+pub use exports::mark_exports;
+pub use lsp::LspChunker;
 pub use regex_chunker::RegexChunker;
+pub use registry::ChunkerRegistry;
+pub use sfc_chunker::SfcChunker;
+#[cfg(feature = "tree-sitter")]
 pub use ts_chunker::TreeSitterChunker;
+#[cfg(feature = "tree-sitter")]
 pub use ts_chunker::ts_language_for;
+pub use window_chunker::{WINDOW_LINES, WINDOW_OVERLAP, WindowChunker};
 
 use topo_core::{Chunk, Language};
 
@@ -17,11 +38,21 @@ use topo_core::{Chunk, Language};
 pub trait Chunker {
     /// Extract code chunks from file content.
     fn chunk(&self, content: &str, language: Language) -> Vec<Chunk>;
+
+    /// Extract code chunks from the file at `path`, relative to the
+    /// repository root. Chunkers that need the file's identity override
+    /// this; the rest chunk the content alone.
+    fn chunk_file(&self, path: &str, content: &str, language: Language) -> Vec<Chunk> {
+        let _ = path;
+        self.chunk(content, language)
+    }
 }
 
 /// Composite chunker: tries tree-sitter first, falls back to regex.
+#[cfg(feature = "tree-sitter")]
 pub struct CompositeChunker;
 
+#[cfg(feature = "tree-sitter")]
 impl Chunker for CompositeChunker {
     fn chunk(&self, content: &str, language: Language) -> Vec<Chunk> {
         let ts_chunks = TreeSitterChunker.chunk(content, language);
@@ -32,12 +63,23 @@ impl Chunker for CompositeChunker {
     }
 }
 
-/// Create the default chunker (regex-based, fast indexing).
-///
-/// Tree-sitter chunkers (`TreeSitterChunker`, `CompositeChunker`) remain
-/// available for on-demand enrichment of selected files.
-pub fn default_chunker() -> RegexChunker {
-    RegexChunker
+/// The chunker indexing uses unless told otherwise: [`CompositeChunker`]
+/// with the `tree-sitter` feature, so nested functions and methods get
+/// their full spans, and the faster [`RegexChunker`] without it.
+#[cfg(feature = "tree-sitter")]
+pub type DefaultChunker = CompositeChunker;
+
+/// The chunker indexing uses unless told otherwise: [`CompositeChunker`]
+/// with the `tree-sitter` feature, and the faster [`RegexChunker`] without it.
+#[cfg(not(feature = "tree-sitter"))]
+pub type DefaultChunker = RegexChunker;
+
+/// Whether this build chunks with tree-sitter grammars.
+pub const TREE_SITTER: bool = cfg!(feature = "tree-sitter");
+
+/// Create the default chunker, see [`DefaultChunker`].
+pub fn default_chunker() -> DefaultChunker {
+    DefaultChunker {}
 }
 
 #[cfg(test)]
@@ -61,6 +103,17 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "tree-sitter")]
+    fn default_chunker_uses_tree_sitter() {
+        // A regex pass only sees the signature line
+        let src = "impl Config {\n    fn load(&self) -> bool {\n        true\n    }\n}\n";
+        let chunks = default_chunker().chunk(src, Language::Rust);
+        let load = chunks.iter().find(|c| c.name == "load").unwrap();
+        assert_eq!((load.start_line, load.end_line), (2, 4));
+    }
+
+    #[test]
+    #[cfg(feature = "tree-sitter")]
     fn composite_prefers_tree_sitter() {
         let chunker = CompositeChunker;
         let src = "pub fn authenticate(token: &str) -> bool {\n    !token.is_empty()\n}\n";
@@ -78,6 +131,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "tree-sitter")]
     fn composite_falls_back_to_regex() {
         let chunker = CompositeChunker;
         // Markdown has no tree-sitter query — should get empty from both
@@ -0,0 +1,527 @@
+//! Symbols from a running language server.
+//!
+//! Speaks just enough of the Language Server Protocol over stdio to open a
+//! file and ask for its `textDocument/documentSymbol`, which gives
+//! compiler-accurate symbols with full spans where the regex chunker only
+//! sees declaration lines.
+
+use crate::{Chunker, default_chunker};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use topo_core::{Chunk, ChunkKind, Language};
+
+/// Chunker that asks a language server for each file's symbols, falling
+/// back to the [`crate::DefaultChunker`] for languages without a server, and when a
+/// server can't be started, stops answering, or finds nothing.
+///
+/// Servers start on the first file of their language and are shut down
+/// when the chunker is dropped. Requests to one server are serialized.
+pub struct LspChunker {
+    root: PathBuf,
+    timeout: Duration,
+    servers: HashMap<Language, Mutex<Server>>,
+}
+
+enum Server {
+    Idle(Vec<String>),
+    Running(Session),
+    Failed(String),
+}
+
+impl LspChunker {
+    /// A chunker for files under `root`, waiting up to `timeout` for each
+    /// server response.
+    pub fn new(root: impl Into<PathBuf>, timeout: Duration) -> Self {
+        Self {
+            root: root.into(),
+            timeout,
+            servers: HashMap::new(),
+        }
+    }
+
+    /// Run `command` (a program and its arguments) as the language server
+    /// for `language`.
+    pub fn server(mut self, language: Language, command: Vec<String>) -> Self {
+        self.servers
+            .insert(language, Mutex::new(Server::Idle(command)));
+        self
+    }
+
+    /// Languages whose server couldn't be used, with the reason, sorted.
+    pub fn failures(&self) -> Vec<(Language, String)> {
+        let mut failures: Vec<(Language, String)> = self
+            .servers
+            .iter()
+            .filter_map(|(language, server)| match &*lock(server) {
+                Server::Failed(reason) => Some((*language, reason.clone())),
+                _ => None,
+            })
+            .collect();
+        failures.sort_by_key(|(language, _)| language.as_str());
+        failures
+    }
+
+    fn symbols(
+        &self,
+        server: &Mutex<Server>,
+        path: &str,
+        content: &str,
+        language: Language,
+    ) -> Option<Vec<Chunk>> {
+        let mut server = lock(server);
+        if let Server::Idle(command) = &*server {
+            *server = match Session::start(command, &self.root, self.timeout) {
+                Ok(session) => Server::Running(session),
+                Err(e) => Server::Failed(format!("`{}` failed to start: {e}", command.join(" "))),
+            };
+        }
+        let Server::Running(session) = &mut *server else {
+            return None;
+        };
+        match session.document_symbols(&self.root.join(path), content, language) {
+            Ok(symbols) => Some(symbols),
+            Err(e) => {
+                *server = Server::Failed(e.to_string());
+                None
+            }
+        }
+    }
+}
+
+impl Chunker for LspChunker {
+    fn chunk(&self, content: &str, language: Language) -> Vec<Chunk> {
+        default_chunker().chunk(content, language)
+    }
+
+    fn chunk_file(&self, path: &str, content: &str, language: Language) -> Vec<Chunk> {
+        let fallback = default_chunker().chunk(content, language);
+        let Some(server) = self.servers.get(&language) else {
+            return fallback;
+        };
+        let mut chunks = match self.symbols(server, path, content, language) {
+            Some(symbols) if !symbols.is_empty() => symbols,
+            _ => return fallback,
+        };
+        // Servers don't report imports; keep the ones the fallback found
+        chunks.extend(fallback.into_iter().filter(|c| c.kind == ChunkKind::Import));
+        chunks.sort_by_key(|c| (c.start_line, std::cmp::Reverse(c.end_line)));
+        chunks
+    }
+}
+
+/// A running language server.
+struct Session {
+    child: Child,
+    stdin: ChildStdin,
+    messages: Receiver<Value>,
+    next_id: u64,
+    timeout: Duration,
+}
+
+impl Session {
+    fn start(command: &[String], root: &Path, timeout: Duration) -> io::Result<Self> {
+        let (program, args) = command
+            .split_first()
+            .ok_or_else(|| io::Error::other("empty server command"))?;
+        let mut child = Command::new(program)
+            .args(args)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| io::Error::other("no stdin"))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| io::Error::other("no stdout"))?;
+
+        // Read on a thread of its own so requests can time out
+        let (sender, messages) = mpsc::channel();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(stdout);
+            while let Ok(Some(message)) = read_message(&mut reader) {
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut session = Self {
+            child,
+            stdin,
+            messages,
+            next_id: 0,
+            timeout,
+        };
+        let root_uri = file_uri(root);
+        session.request(
+            "initialize",
+            json!({
+                "processId": std::process::id(),
+                "rootUri": root_uri,
+                "workspaceFolders": [{ "uri": root_uri, "name": "root" }],
+                "capabilities": {
+                    "textDocument": {
+                        "documentSymbol": { "hierarchicalDocumentSymbolSupport": true }
+                    }
+                },
+            }),
+        )?;
+        session.notify("initialized", json!({}))?;
+        Ok(session)
+    }
+
+    /// Chunks for the symbols of the file at `path` holding `content`.
+    fn document_symbols(
+        &mut self,
+        path: &Path,
+        content: &str,
+        language: Language,
+    ) -> io::Result<Vec<Chunk>> {
+        let uri = file_uri(path);
+        self.notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": {
+                    "uri": uri,
+                    "languageId": language_id(language),
+                    "version": 1,
+                    "text": content,
+                }
+            }),
+        )?;
+        let result = self.request(
+            "textDocument/documentSymbol",
+            json!({ "textDocument": { "uri": uri } }),
+        );
+        self.notify(
+            "textDocument/didClose",
+            json!({ "textDocument": { "uri": uri } }),
+        )?;
+        let mut chunks = Vec::new();
+        symbol_chunks(&result?, None, &mut chunks);
+        Ok(chunks)
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> io::Result<Value> {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(&json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))?;
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let wait = deadline.saturating_duration_since(Instant::now());
+            let mut message = self.messages.recv_timeout(wait).map_err(|e| match e {
+                RecvTimeoutError::Timeout => io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!(
+                        "no answer to {method} within {}ms",
+                        self.timeout.as_millis()
+                    ),
+                ),
+                RecvTimeoutError::Disconnected => {
+                    io::Error::new(io::ErrorKind::UnexpectedEof, "the language server exited")
+                }
+            })?;
+            match (message.get("id").cloned(), message.get("method")) {
+                (Some(reply_to), None) if reply_to == id => {
+                    if let Some(error) = message.get("error") {
+                        return Err(io::Error::other(format!(
+                            "{method} failed: {}",
+                            error["message"].as_str().unwrap_or("unknown error")
+                        )));
+                    }
+                    return Ok(message["result"].take());
+                }
+                // Servers ask the client things too; answer so they don't stall
+                (Some(request_id), Some(request)) => {
+                    let result = match request.as_str() {
+                        Some("workspace/configuration") => {
+                            let items = message["params"]["items"].as_array().map_or(0, Vec::len);
+                            Value::Array(vec![Value::Null; items])
+                        }
+                        _ => Value::Null,
+                    };
+                    self.send(&json!({ "jsonrpc": "2.0", "id": request_id, "result": result }))?;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn notify(&mut self, method: &str, params: Value) -> io::Result<()> {
+        self.send(&json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+    }
+
+    fn send(&mut self, message: &Value) -> io::Result<()> {
+        let body = message.to_string();
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+        self.stdin.flush()
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.timeout = self.timeout.min(Duration::from_secs(1));
+        if self.request("shutdown", Value::Null).is_ok() {
+            let _ = self.notify("exit", Value::Null);
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Read one `Content-Length`-framed message; None at end of stream.
+fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.eq_ignore_ascii_case("content-length")
+        {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+    let length = length.ok_or_else(|| io::Error::other("message without Content-Length"))?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(io::Error::other)
+}
+
+/// Append chunks for the symbols in a `documentSymbol` result, which is
+/// either nested `DocumentSymbol`s or flat `SymbolInformation`s. Functions
+/// nested in `parent`, or whose `containerName` names one, are its methods.
+fn symbol_chunks(result: &Value, parent: Option<&str>, chunks: &mut Vec<Chunk>) {
+    for symbol in result.as_array().into_iter().flatten() {
+        let range = symbol
+            .get("range")
+            .or_else(|| symbol.pointer("/location/range"));
+        let kind = symbol["kind"].as_u64().and_then(chunk_kind);
+        let name = symbol["name"].as_str();
+        if let (Some(kind), Some(name), Some(range)) = (kind, name, range) {
+            let line = |pointer: &str| range.pointer(pointer).and_then(Value::as_u64);
+            let parent = parent
+                .or_else(|| symbol["containerName"].as_str())
+                .filter(|_| kind == ChunkKind::Function)
+                .filter(|parent| !parent.is_empty());
+            if let (Some(start), Some(end)) = (line("/start/line"), line("/end/line")) {
+                chunks.push(Chunk {
+                    kind: if parent.is_some() {
+                        ChunkKind::Method
+                    } else {
+                        kind
+                    },
+                    name: name.to_string(),
+                    start_line: start as u32 + 1,
+                    end_line: end.max(start) as u32 + 1,
+                    content: String::new(),
+                    target: None,
+                    doc: None,
+                    parent: parent.map(str::to_string),
+                    signature: None,
+                    tokens: 0,
+                    exported: false,
+                });
+            }
+        }
+        if let Some(children) = symbol.get("children") {
+            let container = match kind {
+                Some(kind) if kind.is_type() || kind == ChunkKind::Impl => name.map(impl_type),
+                _ => None,
+            };
+            symbol_chunks(children, container, chunks);
+        }
+    }
+}
+
+/// The type an impl symbol is for: rust-analyzer names impl blocks
+/// `impl Auth` and `impl Display for Auth`. Other names are unchanged.
+fn impl_type(name: &str) -> &str {
+    let Some(rest) = name.strip_prefix("impl") else {
+        return name;
+    };
+    let ty = rest.rsplit(" for ").next().unwrap_or(rest).trim();
+    let ty = ty.split('<').next().unwrap_or(ty);
+    ty.rsplit("::").next().unwrap_or(ty)
+}
+
+/// The chunk kind of an LSP `SymbolKind`, if it's one worth a chunk.
+fn chunk_kind(kind: u64) -> Option<ChunkKind> {
+    match kind {
+        // Method, Constructor, Function
+        6 | 9 | 12 => Some(ChunkKind::Function),
+        // Class, Struct
+        5 | 23 => Some(ChunkKind::Type),
+        10 => Some(ChunkKind::Enum),
+        11 => Some(ChunkKind::Trait),
+        14 => Some(ChunkKind::Constant),
+        // Object, which rust-analyzer reports impl blocks as
+        19 => Some(ChunkKind::Impl),
+        _ => None,
+    }
+}
+
+/// The LSP `languageId` of `language`.
+fn language_id(language: Language) -> &'static str {
+    match language {
+        Language::Shell => "shellscript",
+        other => other.as_str(),
+    }
+}
+
+/// `path` as a `file://` URI, percent-encoding what URIs don't allow.
+fn file_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' | b':' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    uri
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_framed_messages() {
+        let body = r#"{"jsonrpc":"2.0","id":1,"result":null}"#;
+        let stream = format!(
+            "Content-Length: {}\r\nContent-Type: application/vscode-jsonrpc\r\n\r\n{body}",
+            body.len()
+        );
+        let mut reader = BufReader::new(stream.as_bytes());
+        let message = read_message(&mut reader).unwrap().unwrap();
+        assert_eq!(message["id"], 1);
+        assert!(read_message(&mut reader).unwrap().is_none());
+    }
+
+    #[test]
+    fn maps_nested_and_flat_symbols() {
+        let nested = json!([{
+            "name": "Auth", "kind": 23,
+            "range": { "start": { "line": 2 }, "end": { "line": 5 } },
+            "children": [
+                { "name": "token", "kind": 8,
+                  "range": { "start": { "line": 3 }, "end": { "line": 3 } } },
+                { "name": "check", "kind": 6,
+                  "range": { "start": { "line": 4 }, "end": { "line": 4 } } }
+            ]
+        }]);
+        let mut chunks = Vec::new();
+        symbol_chunks(&nested, None, &mut chunks);
+        let found: Vec<(ChunkKind, &str, u32, u32)> = chunks
+            .iter()
+            .map(|c| (c.kind, c.name.as_str(), c.start_line, c.end_line))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (ChunkKind::Type, "Auth", 3, 6),
+                (ChunkKind::Method, "check", 5, 5)
+            ]
+        );
+        assert_eq!(chunks[1].parent.as_deref(), Some("Auth"));
+
+        let flat = json!([{
+            "name": "login", "kind": 12,
+            "location": { "uri": "file:///a.go",
+                          "range": { "start": { "line": 0 }, "end": { "line": 9 } } }
+        }]);
+        let mut chunks = Vec::new();
+        symbol_chunks(&flat, None, &mut chunks);
+        assert_eq!((chunks[0].start_line, chunks[0].end_line), (1, 10));
+        assert_eq!(chunks[0].kind, ChunkKind::Function);
+
+        let methods = json!([
+            { "name": "impl Display for store::Auth<T>", "kind": 19,
+              "range": { "start": { "line": 0 }, "end": { "line": 4 } },
+              "children": [{ "name": "fmt", "kind": 6,
+                  "range": { "start": { "line": 1 }, "end": { "line": 3 } },
+                  "children": [{ "name": "helper", "kind": 12,
+                      "range": { "start": { "line": 2 }, "end": { "line": 2 } } }] }] },
+            { "name": "Handle", "kind": 6, "containerName": "Server",
+              "location": { "uri": "file:///a.go",
+                            "range": { "start": { "line": 6 }, "end": { "line": 8 } } } }
+        ]);
+        let mut chunks = Vec::new();
+        symbol_chunks(&methods, None, &mut chunks);
+        let names: Vec<(ChunkKind, String)> = chunks
+            .iter()
+            .map(|c| (c.kind, c.qualified_name()))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                (
+                    ChunkKind::Impl,
+                    "impl Display for store::Auth<T>".to_string()
+                ),
+                (ChunkKind::Method, "Auth::fmt".to_string()),
+                (ChunkKind::Function, "helper".to_string()),
+                (ChunkKind::Method, "Server::Handle".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn falls_back_to_regex_without_a_server() {
+        let source = "use std::io;\nfn login() {}\n";
+        let chunker = LspChunker::new("/repo", Duration::from_millis(100)).server(
+            Language::Rust,
+            vec!["topo-no-such-language-server".to_string()],
+        );
+        let chunks = chunker.chunk_file("src/auth.rs", source, Language::Rust);
+        let names: Vec<&str> = chunks.iter().map(|c| c.name.as_str()).collect();
+        let regex = default_chunker().chunk(source, Language::Rust);
+        let expected: Vec<&str> = regex.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, expected);
+
+        let failures = chunker.failures();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, Language::Rust);
+        // Unconfigured languages never start a server
+        chunker.chunk_file("a.py", "def f():\n    pass\n", Language::Python);
+        assert_eq!(chunker.failures().len(), 1);
+    }
+
+    #[test]
+    fn encodes_file_uris() {
+        assert_eq!(
+            file_uri(Path::new("/repo/my src/a+b.rs")),
+            "file:///repo/my%20src/a%2Bb.rs"
+        );
+    }
+}
@@ -2,9 +2,14 @@
 //!
 //! Each language gets a query string with standardized capture names:
 //! - `@function` — function/method outer node
-//! - `@type` — struct/class/enum/trait/interface outer node
+//! - `@type` — struct/class/enum/trait/interface outer node; traits and
+//!   enums are told apart by their declaration line
 //! - `@impl` — impl block outer node
-//! - `@import` — use/import statement outer node
+//! - `@constant` — constant or static outer node
+//! - `@macro` — macro definition outer node
+//! - `@test` — a call declaring a test, named by its description
+//! - `@import` — use/import statement outer node, named by its text unless
+//!   it has a `@name`
 //! - `@name` — identifier node inside the above (for symbol name extraction)
 
 use topo_core::Language;
@@ -37,6 +42,19 @@ pub fn query_for(language: Language) -> Option<&'static str> {
         | Language::Html
         | Language::Css
         | Language::Other => None,
+        // Recognised languages without a bundled grammar
+        Language::Zig
+        | Language::Nix
+        | Language::Terraform
+        | Language::Starlark
+        | Language::Solidity
+        | Language::Sql
+        | Language::Protobuf
+        | Language::GraphQl
+        | Language::Vue
+        | Language::Svelte
+        | Language::CSharp
+        | Language::Dart => None,
     }
 }
 
@@ -47,19 +65,26 @@ const RUST: &str = r#"
 (trait_item name: (type_identifier) @name) @type
 (type_item name: (type_identifier) @name) @type
 (impl_item) @impl
+(const_item name: (identifier) @name) @constant
+(static_item name: (identifier) @name) @constant
+(macro_definition name: (identifier) @name) @macro
 (use_declaration) @import
+(mod_item !body) @import
 "#;
 
 const GO: &str = r#"
 (function_declaration name: (identifier) @name) @function
 (method_declaration name: (field_identifier) @name) @function
 (type_declaration (type_spec name: (type_identifier) @name)) @type
-(import_declaration) @import
+(const_spec name: (identifier) @name) @constant
+(import_spec) @import
 "#;
 
 const PYTHON: &str = r#"
 (function_definition name: (identifier) @name) @function
 (class_definition name: (identifier) @name) @type
+(module (expression_statement (assignment left: (identifier) @name)) @constant
+  (#match? @name "^[A-Z][A-Z0-9_]*$"))
 (import_statement) @import
 (import_from_statement) @import
 "#;
@@ -68,6 +93,10 @@ const JAVASCRIPT: &str = r#"
 (function_declaration name: (identifier) @name) @function
 (class_declaration name: (identifier) @name) @type
 (method_definition name: (property_identifier) @name) @function
+((lexical_declaration (variable_declarator name: (identifier) @name)) @constant
+  (#match? @name "^[A-Z][A-Z0-9_]*$"))
+((call_expression function: (identifier) @_call arguments: (arguments . (string) @name)) @test
+  (#match? @_call "^(it|test)$"))
 (import_statement) @import
 "#;
 
@@ -78,6 +107,10 @@ const TYPESCRIPT: &str = r#"
 (interface_declaration name: (type_identifier) @name) @type
 (type_alias_declaration name: (type_identifier) @name) @type
 (enum_declaration name: (identifier) @name) @type
+((lexical_declaration (variable_declarator name: (identifier) @name)) @constant
+  (#match? @name "^[A-Z][A-Z0-9_]*$"))
+((call_expression function: (identifier) @_call arguments: (arguments . (string) @name)) @test
+  (#match? @_call "^(it|test)$"))
 (import_statement) @import
 "#;
 
@@ -87,6 +120,8 @@ const JAVA: &str = r#"
 (enum_declaration name: (identifier) @name) @type
 (method_declaration name: (identifier) @name) @function
 (constructor_declaration name: (identifier) @name) @function
+((field_declaration declarator: (variable_declarator name: (identifier) @name)) @constant
+  (#match? @name "^[A-Z][A-Z0-9_]*$"))
 (import_declaration) @import
 "#;
 
@@ -95,6 +130,7 @@ const RUBY: &str = r#"
 (singleton_method name: (identifier) @name) @function
 (class name: (constant) @name) @type
 (module name: (constant) @name) @type
+(assignment left: (constant) @name) @constant
 (call method: (identifier) @name) @import
 "#;
 
@@ -104,6 +140,8 @@ const C: &str = r#"
 (enum_specifier name: (type_identifier) @name) @type
 (union_specifier name: (type_identifier) @name) @type
 (type_definition declarator: (type_identifier) @name) @type
+(preproc_def name: (identifier) @name) @macro
+(preproc_function_def name: (identifier) @name) @macro
 (preproc_include) @import
 "#;
 
@@ -113,7 +151,9 @@ const CPP: &str = r#"
 (class_specifier name: (type_identifier) @name) @type
 (struct_specifier name: (type_identifier) @name) @type
 (enum_specifier name: (type_identifier) @name) @type
-(namespace_definition name: (identifier) @name) @type
+(namespace_definition name: (namespace_identifier) @name) @type
+(preproc_def name: (identifier) @name) @macro
+(preproc_function_def name: (identifier) @name) @macro
 (preproc_include) @import
 "#;
 
@@ -171,6 +211,7 @@ const PHP: &str = r#"
 (interface_declaration name: (name) @name) @type
 (trait_declaration name: (name) @name) @type
 (enum_declaration name: (name) @name) @type
+(const_declaration (const_element (name) @name)) @constant
 (namespace_use_declaration) @import
 "#;
 
@@ -1,65 +1,473 @@
 //! Regex-based code chunker for all target languages.
 //!
-//! Extracts function, type, impl, and import declarations using
+//! Extracts function, method, type, impl, and import declarations using
 //! line-by-line pattern matching. This is the default backend;
 //! tree-sitter can be added behind a feature flag for AST precision.
 
 use topo_core::{Chunk, ChunkKind, Language};
 
-use crate::Chunker;
+use crate::{Chunker, ConfigChunker, SfcChunker};
 
 /// Regex-free, pattern-matching chunker that works for all target languages.
 pub struct RegexChunker;
 
 impl Chunker for RegexChunker {
     fn chunk(&self, content: &str, language: Language) -> Vec<Chunk> {
+        if ConfigChunker::handles(language) {
+            return ConfigChunker.chunk(content, language);
+        }
+        if SfcChunker::handles(language) {
+            return SfcChunker.chunk(content, language);
+        }
         let mut chunks = Vec::new();
-
-        for (i, line) in content.lines().enumerate() {
+        // Multi-line imports: inside a Go `import ( ... )` block, and a JS
+        // import whose module comes on a later line
+        let mut go_import_block = false;
+        let mut js_import: Option<Chunk> = None;
+        // Inside a GraphQL `"""` description, whose text isn't schema
+        let mut graphql_description = false;
+        let mut scopes = Scopes::new(language);
+        let lines: Vec<&str> = content.lines().collect();
+
+        for (i, &line) in lines.iter().enumerate() {
             let trimmed = line.trim();
             if trimmed.is_empty() || trimmed.starts_with("//") {
                 continue;
             }
+            let line_num = (i + 1) as u32;
+
+            if go_import_block {
+                if trimmed.starts_with(')') {
+                    go_import_block = false;
+                } else {
+                    chunks.push(import_chunk(format!("import {trimmed}"), line_num));
+                }
+                continue;
+            }
+            if let Some(mut chunk) = js_import.take() {
+                chunk.name.push(' ');
+                chunk.name.push_str(trimmed);
+                chunk.end_line = line_num;
+                if trimmed.contains(['\'', '"', '`']) {
+                    chunks.push(chunk);
+                } else {
+                    js_import = Some(chunk);
+                }
+                continue;
+            }
             // '#' is a comment in Python/Ruby/Shell, but not C/C++ (#include, #define)
             if trimmed.starts_with('#') && !matches!(language, Language::C | Language::Cpp) {
                 continue;
             }
+            if language == Language::GraphQl && (graphql_description || trimmed.starts_with('"')) {
+                if trimmed.matches("\"\"\"").count() % 2 == 1 {
+                    graphql_description = !graphql_description;
+                }
+                continue;
+            }
+            scopes.enter_line(line);
+
+            let result = extract(trimmed, language);
+            let js = matches!(language, Language::JavaScript | Language::TypeScript);
+            let result = match result {
+                None if js && scopes.in_type_body() => {
+                    js_method(trimmed).map(|name| (ChunkKind::Function, name))
+                }
+                None if language == Language::GraphQl
+                    && scopes.in_type_body()
+                    && scopes
+                        .owner()
+                        .is_some_and(|owner| GRAPHQL_ROOT_TYPES.contains(&owner)) =>
+                {
+                    graphql_field(trimmed).map(|name| (ChunkKind::Function, name))
+                }
+                result => result,
+            };
+            // Imports open no scopes, and a multi-line import's closing
+            // brace is consumed with the rest of it
+            let import = matches!(result, Some((ChunkKind::Import, _)));
+
+            match result {
+                Some((ChunkKind::Import, name))
+                    if language == Language::Go && name == "import (" =>
+                {
+                    go_import_block = true;
+                }
+                Some((ChunkKind::Import, name))
+                    if matches!(language, Language::JavaScript | Language::TypeScript)
+                        && !name.contains(['\'', '"', '`']) =>
+                {
+                    js_import = Some(import_chunk(name, line_num));
+                }
+                Some((ChunkKind::Import, name)) => chunks.push(import_chunk(name, line_num)),
+                Some((kind, name)) => {
+                    let (mut kind, name, parent) = place(kind, name, trimmed, language, &scopes);
+                    if matches!(kind, ChunkKind::Function | ChunkKind::Method)
+                        && is_test(&name, trimmed, &lines[..i], language)
+                    {
+                        kind = ChunkKind::TestCase;
+                    }
+                    if let Some(scope) = scope_of(kind, &name, trimmed, language) {
+                        scopes.declare(scope);
+                    }
+                    chunks.push(Chunk {
+                        kind,
+                        name,
+                        start_line: line_num,
+                        end_line: line_num,
+                        content: String::new(),
+                        target: None,
+                        doc: None,
+                        parent,
+                        signature: None,
+                        tokens: 0,
+                        exported: false,
+                    });
+                }
+                None => {}
+            }
+            if !import {
+                scopes.leave_line(trimmed);
+            }
+        }
 
-            let line_num = (i + 1) as u32;
+        chunks
+    }
+}
 
-            let result = match language {
-                Language::Rust => extract_rust(trimmed),
-                Language::Go => extract_go(trimmed),
-                Language::Python => extract_python(trimmed),
-                Language::JavaScript | Language::TypeScript => extract_js_ts(trimmed),
-                Language::Java => extract_java(trimmed),
-                Language::Ruby => extract_ruby(trimmed),
-                Language::C | Language::Cpp => extract_c_cpp(trimmed),
-                _ => None,
-            };
+/// Declarations enclosing the current line, innermost last, for telling
+/// methods from free functions.
+///
+/// Python, Ruby, and Elixir scopes end at the next line indented no
+/// deeper than their declaration; the others at the brace closing their body. Go has
+/// no nesting: its methods name their receiver instead.
+struct Scopes {
+    stack: Vec<Scope>,
+    /// Whether scopes follow indentation rather than braces.
+    indented: bool,
+    /// Brace depth after the previous line.
+    depth: usize,
+    /// Indentation of the current line.
+    indent: usize,
+}
+
+struct Scope {
+    /// Type or impl whose functions are its methods; none for functions
+    /// and namespaces.
+    name: Option<String>,
+    /// Brace depth, or indentation, of the declaration.
+    level: usize,
+    /// Whether the body has been entered: a braced declaration waits for
+    /// its `{`, and is dropped at a `;` that ends it first, or a `}`
+    /// closing the block it is in: an expression body has no scope.
+    open: bool,
+}
+
+impl Scopes {
+    fn new(language: Language) -> Self {
+        Self {
+            stack: Vec::new(),
+            indented: matches!(
+                language,
+                Language::Python | Language::Ruby | Language::Elixir
+            ),
+            depth: 0,
+            indent: 0,
+        }
+    }
+
+    /// Close the indented scopes that `line` is outside of.
+    fn enter_line(&mut self, line: &str) {
+        if !self.indented {
+            return;
+        }
+        self.indent = line.len() - line.trim_start().len();
+        while self
+            .stack
+            .last()
+            .is_some_and(|scope| scope.level >= self.indent)
+        {
+            self.stack.pop();
+        }
+    }
+
+    /// Open a scope for the declaration on the current line, replacing one
+    /// still waiting for its body: a signature holds no declarations.
+    fn declare(&mut self, name: Option<String>) {
+        if self.indented {
+            self.stack.push(Scope {
+                name,
+                level: self.indent,
+                open: true,
+            });
+            return;
+        }
+        if self.stack.last().is_some_and(|scope| !scope.open) {
+            self.stack.pop();
+        }
+        self.stack.push(Scope {
+            name,
+            level: self.depth,
+            open: false,
+        });
+    }
 
-            if let Some((kind, name)) = result {
-                chunks.push(Chunk {
-                    kind,
-                    name,
-                    start_line: line_num,
-                    end_line: line_num,
-                    content: String::new(),
-                });
+    /// Follow the braces and semicolons of `line`.
+    fn leave_line(&mut self, line: &str) {
+        if self.indented {
+            return;
+        }
+        for c in line.chars() {
+            match c {
+                '{' => {
+                    if let Some(scope) = self.stack.last_mut() {
+                        scope.open = true;
+                    }
+                    self.depth += 1;
+                }
+                '}' => {
+                    self.depth = self.depth.saturating_sub(1);
+                    while self
+                        .stack
+                        .last()
+                        .is_some_and(|scope| !scope.open || scope.level >= self.depth)
+                    {
+                        self.stack.pop();
+                    }
+                }
+                ';' if self.stack.last().is_some_and(|scope| !scope.open) => {
+                    self.stack.pop();
+                }
+                _ => {}
             }
         }
+    }
 
-        chunks
+    /// The innermost scope whose body the current line is in.
+    fn innermost(&self) -> Option<&Scope> {
+        self.stack.iter().rev().find(|scope| scope.open)
+    }
+
+    /// The type or impl the current line is directly inside, if any.
+    fn owner(&self) -> Option<&str> {
+        self.innermost()?.name.as_deref()
+    }
+
+    /// Whether the current line is directly in the body of a type, not in
+    /// one of its methods or a nested block.
+    fn in_type_body(&self) -> bool {
+        self.innermost()
+            .is_some_and(|scope| scope.name.is_some() && self.depth == scope.level + 1)
+    }
+}
+
+/// The kind, name, and parent of a declaration: a function declared in a
+/// type or impl, or naming one as `Type::name` or a Go receiver, is a
+/// method of it.
+fn place(
+    kind: ChunkKind,
+    name: String,
+    line: &str,
+    language: Language,
+    scopes: &Scopes,
+) -> (ChunkKind, String, Option<String>) {
+    if kind != ChunkKind::Function {
+        return (kind, name, None);
+    }
+    if let Some((parent, method)) = name.rsplit_once("::") {
+        return (
+            ChunkKind::Method,
+            method.to_string(),
+            Some(parent.to_string()),
+        );
+    }
+    let parent = match language {
+        Language::Go => go_receiver(line),
+        _ => scopes.owner().map(str::to_string),
+    };
+    match parent {
+        Some(parent) => (ChunkKind::Method, name, Some(parent)),
+        None => (kind, name, None),
+    }
+}
+
+/// The scope a declaration opens, if it has a body: named for types,
+/// traits, enums, and impls, whose functions are methods. Go, Lua, and SQL scopes aren't
+/// tracked, and GraphQL fields have no body.
+fn scope_of(kind: ChunkKind, name: &str, line: &str, language: Language) -> Option<Option<String>> {
+    if matches!(language, Language::Go | Language::Lua | Language::Sql) {
+        return None;
+    }
+    match kind {
+        ChunkKind::Method if language == Language::GraphQl => None,
+        ChunkKind::Function | ChunkKind::Method | ChunkKind::TestCase => Some(None),
+        ChunkKind::Impl if language == Language::Rust => Some(Some(rust_impl_type(line, name))),
+        ChunkKind::Type
+            if matches!(language, Language::Cpp | Language::CSharp)
+                && line.contains("namespace ") =>
+        {
+            Some(None)
+        }
+        ChunkKind::Type | ChunkKind::Trait | ChunkKind::Enum | ChunkKind::Impl => {
+            Some(Some(name.to_string()))
+        }
+        ChunkKind::Import
+        | ChunkKind::Constant
+        | ChunkKind::Macro
+        | ChunkKind::Key
+        | ChunkKind::Section
+        | ChunkKind::Other => None,
+    }
+}
+
+/// The kind of the type declared on `line`: [`ChunkKind::Trait`] or
+/// [`ChunkKind::Enum`] where the line declares one, else
+/// [`ChunkKind::Type`]. For chunkers that find types without telling them
+/// apart.
+#[cfg(feature = "tree-sitter")]
+pub(crate) fn type_kind(line: &str, language: Language) -> ChunkKind {
+    match extract(line.trim(), language) {
+        Some((kind, _)) if kind.is_type() => kind,
+        _ => ChunkKind::Type,
+    }
+}
+
+/// Whether the function `name`, declared on `line` below the lines `above`,
+/// is a test: one with a test attribute or annotation, on its line or among
+/// those directly above it, or named like a test where tests are found by
+/// name (`TestLogin(t *testing.T)` in Go, `test_login` and `testLogin` in
+/// Python, Ruby, PHP, and Swift).
+pub(crate) fn is_test(name: &str, line: &str, above: &[&str], language: Language) -> bool {
+    let attributed = test_attribute(line, language)
+        || above
+            .iter()
+            .rev()
+            .map(|line| line.trim())
+            .take_while(|line| attribute_line(line, language))
+            .any(|line| test_attribute(line, language));
+    attributed
+        || match language {
+            Language::Go => name.starts_with("Test") && line.contains("*testing.T"),
+            Language::Python | Language::Ruby | Language::Php | Language::Swift => name
+                .strip_prefix("test")
+                .is_some_and(|rest| rest.starts_with('_') || rest.starts_with(char::is_uppercase)),
+            _ => false,
+        }
+}
+
+/// Whether the trimmed `line` is an attribute, annotation, or decorator,
+/// and not a declaration following one on the same line, like
+/// `@Test public void logsOut() {}`.
+fn attribute_line(line: &str, language: Language) -> bool {
+    match language {
+        Language::Rust | Language::Php => line.starts_with("#["),
+        Language::CSharp => line.starts_with('['),
+        _ => line.starts_with('@') && (!line.contains(' ') || line.ends_with(')')),
+    }
+}
+
+/// Whether the trimmed `line` starts with a test attribute: `#[test]`,
+/// `#[tokio::test]`, `@Test`, `@ParameterizedTest`, or xUnit's `[Fact]`.
+fn test_attribute(line: &str, language: Language) -> bool {
+    let marker = match language {
+        Language::Rust | Language::Php => "#[",
+        Language::CSharp => "[",
+        _ => "@",
+    };
+    if !line.starts_with(marker) {
+        return false;
+    }
+    let rest = line.trim_start_matches(['#', '[', '@']);
+    let path = rest
+        .split(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | ':' | '.')))
+        .next()
+        .unwrap_or_default();
+    let name = path.rsplit([':', '.']).next().unwrap_or(path);
+    matches!(
+        name,
+        "test" | "rstest" | "Fact" | "Theory" | "TestMethod" | "TestCase"
+    ) || (name.ends_with("Test") && !name.starts_with("Before") && !name.starts_with("After"))
+}
+
+/// The description of a test declared by calling one of `callers` on
+/// `line`: `logs in` for `it("logs in", () => {`.
+fn test_call(line: &str, callers: &[&str]) -> Option<String> {
+    let rest = callers
+        .iter()
+        .find_map(|caller| line.strip_prefix(caller))?
+        .trim_start();
+    let quote = rest
+        .chars()
+        .next()
+        .filter(|c| matches!(c, '"' | '\'' | '`'))?;
+    let description = &rest[1..];
+    let end = description.find(quote)?;
+    Some(description[..end].to_string()).filter(|description| !description.is_empty())
+}
+
+/// Chunk of the import statement `statement`, starting on `line`.
+fn import_chunk(statement: String, line: u32) -> Chunk {
+    Chunk {
+        kind: ChunkKind::Import,
+        name: statement,
+        start_line: line,
+        end_line: line,
+        content: String::new(),
+        target: None,
+        doc: None,
+        parent: None,
+        signature: None,
+        tokens: 0,
+        exported: false,
+    }
+}
+
+/// The kind and name of the declaration or import on the trimmed `line`.
+fn extract(line: &str, language: Language) -> Option<(ChunkKind, String)> {
+    match language {
+        Language::Rust => extract_rust(line),
+        Language::Go => extract_go(line),
+        Language::Python => extract_python(line),
+        Language::JavaScript | Language::TypeScript => extract_js_ts(line),
+        Language::Java => extract_java(line),
+        Language::Ruby => extract_ruby(line),
+        Language::C | Language::Cpp => extract_c_cpp(line),
+        Language::Kotlin => extract_kotlin(line),
+        Language::Swift => extract_swift(line),
+        Language::Scala => extract_scala(line),
+        Language::Elixir => extract_elixir(line),
+        Language::Php => extract_php(line),
+        Language::CSharp => extract_csharp(line),
+        Language::Dart => extract_dart(line),
+        Language::Lua => extract_lua(line),
+        Language::Sql => extract_sql(line),
+        Language::Protobuf => extract_protobuf(line),
+        Language::GraphQl => extract_graphql(line),
+        _ => None,
     }
 }
 
 // ── Rust ───────────────────────────────────────────────────────────
 
 fn extract_rust(line: &str) -> Option<(ChunkKind, String)> {
-    let stripped = line
+    let visible = line
         .trim_start_matches("pub ")
         .trim_start_matches("pub(crate) ")
-        .trim_start_matches("pub(super) ")
+        .trim_start_matches("pub(super) ");
+    // `const MAX: usize = 8;` and `static mut COUNT: u32`, not `const fn`
+    if let Some(rest) = visible
+        .strip_prefix("const ")
+        .or_else(|| visible.strip_prefix("static "))
+        && let Some(name) = ident(rest.trim_start_matches("mut "), &[':'])
+        && name != "_"
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+    {
+        return Some((ChunkKind::Constant, name));
+    }
+    if let Some(rest) = visible.strip_prefix("macro_rules!") {
+        return ident(rest.trim_start(), &[' ', '{', '(']).map(|n| (ChunkKind::Macro, n));
+    }
+    let stripped = visible
         .trim_start_matches("async ")
         .trim_start_matches("unsafe ")
         .trim_start_matches("const ");
@@ -71,23 +479,66 @@ fn extract_rust(line: &str) -> Option<(ChunkKind, String)> {
         return ident(rest, &[' ', '{', '<', '(']).map(|n| (ChunkKind::Type, n));
     }
     if let Some(rest) = stripped.strip_prefix("enum ") {
-        return ident(rest, &[' ', '{', '<']).map(|n| (ChunkKind::Type, n));
+        return ident(rest, &[' ', '{', '<']).map(|n| (ChunkKind::Enum, n));
     }
     if let Some(rest) = stripped.strip_prefix("trait ") {
-        return ident(rest, &[' ', '{', '<', ':']).map(|n| (ChunkKind::Type, n));
+        return ident(rest, &[' ', '{', '<', ':']).map(|n| (ChunkKind::Trait, n));
     }
     if let Some(rest) = stripped.strip_prefix("type ") {
         return ident(rest, &[' ', '=', '<', ';']).map(|n| (ChunkKind::Type, n));
     }
-    if let Some(rest) = stripped.strip_prefix("impl ") {
+    if let Some(rest) = stripped
+        .strip_prefix("impl ")
+        .or_else(|| stripped.strip_prefix("impl").filter(|r| r.starts_with('<')))
+    {
+        let rest = skip_generics(rest.trim_start());
         return ident(rest, &[' ', '{', '<']).map(|n| (ChunkKind::Impl, n));
     }
     if stripped.starts_with("use ") {
         return Some((ChunkKind::Import, stripped.to_string()));
     }
+    // `mod name;` loads a file; `mod name { ... }` is inline
+    if stripped.starts_with("mod ") && stripped.ends_with(';') {
+        return Some((ChunkKind::Import, stripped.to_string()));
+    }
     None
 }
 
+/// The type a Rust impl is for: `Auth` in `impl Auth`, `impl<T> Auth<T>`,
+/// and `impl Display for Auth`, whose chunk is named after the trait.
+fn rust_impl_type(line: &str, name: &str) -> String {
+    let Some((_, rest)) = line.split_once(" for ") else {
+        return name.to_string();
+    };
+    let rest = rest.trim_start_matches('&');
+    let path = ident(rest, &[' ', '{', '<']).unwrap_or_else(|| name.to_string());
+    match path.rsplit_once("::") {
+        Some((_, last)) => last.to_string(),
+        None => path,
+    }
+}
+
+/// `rest` after its generic parameters, if it starts with any.
+fn skip_generics(rest: &str) -> &str {
+    if !rest.starts_with('<') {
+        return rest;
+    }
+    let mut depth = 0;
+    for (i, c) in rest.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return rest[i + 1..].trim_start();
+                }
+            }
+            _ => {}
+        }
+    }
+    rest
+}
+
 // ── Go ─────────────────────────────────────────────────────────────
 
 fn extract_go(line: &str) -> Option<(ChunkKind, String)> {
@@ -101,7 +552,16 @@ fn extract_go(line: &str) -> Option<(ChunkKind, String)> {
         return ident(rest, &['(', ' ']).map(|n| (ChunkKind::Function, n));
     }
     if let Some(rest) = line.strip_prefix("type ") {
-        return ident(rest, &[' ']).map(|n| (ChunkKind::Type, n));
+        let kind = if rest.contains(" interface") {
+            ChunkKind::Trait
+        } else {
+            ChunkKind::Type
+        };
+        return ident(rest, &[' ']).map(|n| (kind, n));
+    }
+    // A `const ( ... )` block's constants aren't tracked
+    if let Some(rest) = line.strip_prefix("const ") {
+        return ident(rest, &[' ', '=', '(']).map(|n| (ChunkKind::Constant, n));
     }
     if line.starts_with("import ") || line == "import (" {
         return Some((ChunkKind::Import, line.to_string()));
@@ -109,6 +569,14 @@ fn extract_go(line: &str) -> Option<(ChunkKind, String)> {
     None
 }
 
+/// The receiver type of a Go method: `Server` in `func (s *Server) Handle()`
+/// and `func (s Server[T]) Handle()`.
+fn go_receiver(line: &str) -> Option<String> {
+    let receiver = line.strip_prefix("func (")?.split(')').next()?;
+    let ty = receiver.split_whitespace().last()?.trim_start_matches('*');
+    ident(ty, &['['])
+}
+
 // ── Python ─────────────────────────────────────────────────────────
 
 fn extract_python(line: &str) -> Option<(ChunkKind, String)> {
@@ -117,12 +585,21 @@ fn extract_python(line: &str) -> Option<(ChunkKind, String)> {
         return ident(rest, &['(']).map(|n| (ChunkKind::Function, n));
     }
     if let Some(rest) = stripped.strip_prefix("class ") {
-        return ident(rest, &['(', ':']).map(|n| (ChunkKind::Type, n));
+        let bases = rest.split_once('(').map_or("", |(_, bases)| bases);
+        let kind = if bases.contains("Enum") {
+            ChunkKind::Enum
+        } else if bases.contains("Protocol") || bases.contains("ABC") {
+            ChunkKind::Trait
+        } else {
+            ChunkKind::Type
+        };
+        return ident(rest, &['(', ':']).map(|n| (kind, n));
     }
     if line.starts_with("import ") || line.starts_with("from ") {
         return Some((ChunkKind::Import, line.to_string()));
     }
-    None
+    // `MAX_RETRIES = 3`, `TIMEOUT: float = 1.5`
+    screaming_assignment(line).map(|n| (ChunkKind::Constant, n))
 }
 
 // ── JavaScript / TypeScript ────────────────────────────────────────
@@ -135,6 +612,9 @@ fn extract_js_ts(line: &str) -> Option<(ChunkKind, String)> {
         .trim_start_matches("abstract ")
         .trim_start_matches("declare ");
 
+    if let Some(description) = test_call(line, &["it(", "test("]) {
+        return Some((ChunkKind::TestCase, description));
+    }
     if let Some(rest) = stripped.strip_prefix("function ") {
         let name = ident(rest, &['(', '<', ' '])?;
         if name != "*" {
@@ -145,13 +625,16 @@ fn extract_js_ts(line: &str) -> Option<(ChunkKind, String)> {
         return ident(rest, &[' ', '{', '<']).map(|n| (ChunkKind::Type, n));
     }
     if let Some(rest) = stripped.strip_prefix("interface ") {
-        return ident(rest, &[' ', '{', '<']).map(|n| (ChunkKind::Type, n));
+        return ident(rest, &[' ', '{', '<']).map(|n| (ChunkKind::Trait, n));
     }
     if let Some(rest) = stripped.strip_prefix("type ") {
         return ident(rest, &[' ', '=', '<']).map(|n| (ChunkKind::Type, n));
     }
-    if let Some(rest) = stripped.strip_prefix("enum ") {
-        return ident(rest, &[' ', '{']).map(|n| (ChunkKind::Type, n));
+    if let Some(rest) = stripped
+        .strip_prefix("enum ")
+        .or_else(|| stripped.strip_prefix("const enum "))
+    {
+        return ident(rest, &[' ', '{']).map(|n| (ChunkKind::Enum, n));
     }
     // Arrow functions: const foo = (...) =>
     if let Some(rest) = stripped
@@ -161,25 +644,58 @@ fn extract_js_ts(line: &str) -> Option<(ChunkKind, String)> {
     {
         return ident(rest, &[' ', '=', ':']).map(|n| (ChunkKind::Function, n));
     }
+    if let Some(rest) = stripped.strip_prefix("const ")
+        && let Some(name) = screaming_assignment(rest)
+    {
+        return Some((ChunkKind::Constant, name));
+    }
     if line.starts_with("import ") {
         return Some((ChunkKind::Import, line.to_string()));
     }
     None
 }
 
+/// The name of a class method, `constructor() {` or `static async load(id) {`,
+/// for a line directly in a class body.
+fn js_method(line: &str) -> Option<String> {
+    const MODIFIERS: &[&str] = &[
+        "static ",
+        "async ",
+        "get ",
+        "set ",
+        "public ",
+        "private ",
+        "protected ",
+        "readonly ",
+        "override ",
+        "*",
+    ];
+    let mut rest = line;
+    while let Some(stripped) = MODIFIERS.iter().find_map(|m| rest.strip_prefix(m)) {
+        rest = stripped.trim_start();
+    }
+    let end = rest.find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '$' | '#')))?;
+    let (name, after) = rest.split_at(end);
+    let keyword = matches!(
+        name,
+        "if" | "for" | "while" | "switch" | "catch" | "return" | "function"
+    );
+    (!name.is_empty() && !keyword && after.starts_with(['(', '<'])).then(|| name.to_string())
+}
+
 // ── Java ───────────────────────────────────────────────────────────
 
 fn extract_java(line: &str) -> Option<(ChunkKind, String)> {
-    let stripped = strip_java_modifiers(line);
+    let stripped = strip_modifiers(line, JAVA_MODIFIERS);
 
     if let Some(rest) = stripped.strip_prefix("class ") {
         return ident(rest, &[' ', '{', '<']).map(|n| (ChunkKind::Type, n));
     }
     if let Some(rest) = stripped.strip_prefix("interface ") {
-        return ident(rest, &[' ', '{', '<']).map(|n| (ChunkKind::Type, n));
+        return ident(rest, &[' ', '{', '<']).map(|n| (ChunkKind::Trait, n));
     }
     if let Some(rest) = stripped.strip_prefix("enum ") {
-        return ident(rest, &[' ', '{', '<']).map(|n| (ChunkKind::Type, n));
+        return ident(rest, &[' ', '{', '<']).map(|n| (ChunkKind::Enum, n));
     }
     if let Some(rest) = stripped.strip_prefix("record ") {
         return ident(rest, &[' ', '(', '<']).map(|n| (ChunkKind::Type, n));
@@ -187,6 +703,13 @@ fn extract_java(line: &str) -> Option<(ChunkKind, String)> {
     if let Some(rest) = stripped.strip_prefix("@interface ") {
         return ident(rest, &[' ', '{']).map(|n| (ChunkKind::Type, n));
     }
+    // `static final int MAX_SIZE = 10;`
+    if line.contains("static ")
+        && line.contains("final ")
+        && let Some(name) = typed_constant(stripped).filter(|name| screaming_case(name))
+    {
+        return Some((ChunkKind::Constant, name));
+    }
 
     // Method: returnType name(...)  — look for '(' on lines with a return type
     if stripped.contains('(')
@@ -214,24 +737,27 @@ fn extract_java(line: &str) -> Option<(ChunkKind, String)> {
     None
 }
 
-fn strip_java_modifiers(line: &str) -> &str {
+const JAVA_MODIFIERS: &[&str] = &[
+    "public ",
+    "private ",
+    "protected ",
+    "static ",
+    "final ",
+    "abstract ",
+    "synchronized ",
+    "native ",
+    "default ",
+];
+
+/// `line` without its leading `modifiers` and same-line annotations
+/// (`@Override`, `@objc`).
+fn strip_modifiers<'a>(line: &'a str, modifiers: &[&str]) -> &'a str {
     let mut s = line;
-    let modifiers = [
-        "public ",
-        "private ",
-        "protected ",
-        "static ",
-        "final ",
-        "abstract ",
-        "synchronized ",
-        "native ",
-        "default ",
-    ];
     loop {
         let before = s;
-        for m in &modifiers {
+        for m in modifiers {
             if let Some(rest) = s.strip_prefix(m) {
-                s = rest;
+                s = rest.trim_start();
             }
         }
         // Also strip annotations on the same line: @Override etc.
@@ -267,7 +793,9 @@ fn extract_java_method_name(stripped: &str) -> Option<String> {
 // ── Ruby ───────────────────────────────────────────────────────────
 
 fn extract_ruby(line: &str) -> Option<(ChunkKind, String)> {
-    if let Some(rest) = line.strip_prefix("def ") {
+    // `private def helper`
+    let stripped = strip_modifiers(line, &["private ", "protected ", "public "]);
+    if let Some(rest) = stripped.strip_prefix("def ") {
         // self.method_name or method_name
         let rest = rest.strip_prefix("self.").unwrap_or(rest);
         return ident(rest, &['(', ' ', ';']).map(|n| (ChunkKind::Function, n));
@@ -284,7 +812,8 @@ fn extract_ruby(line: &str) -> Option<(ChunkKind, String)> {
     if line.starts_with("include ") || line.starts_with("extend ") {
         return Some((ChunkKind::Import, line.to_string()));
     }
-    None
+    test_call(line, &["it ", "it(", "test ", "test(", "specify "])
+        .map(|description| (ChunkKind::TestCase, description))
 }
 
 // ── C / C++ ────────────────────────────────────────────────────────
@@ -297,10 +826,13 @@ fn extract_c_cpp(line: &str) -> Option<(ChunkKind, String)> {
         .trim_start_matches("virtual ")
         .trim_start_matches("explicit ");
 
-    // Preprocessor includes
+    // Preprocessor includes and macros
     if line.starts_with("#include") {
         return Some((ChunkKind::Import, line.to_string()));
     }
+    if let Some(rest) = line.strip_prefix("#define ") {
+        return ident(rest.trim_start(), &[' ', '\t', '(']).map(|n| (ChunkKind::Macro, n));
+    }
 
     // struct/class/enum/union/namespace
     if let Some(rest) = stripped.strip_prefix("struct ") {
@@ -311,7 +843,7 @@ fn extract_c_cpp(line: &str) -> Option<(ChunkKind, String)> {
     }
     if let Some(rest) = stripped.strip_prefix("enum ") {
         let rest = rest.strip_prefix("class ").unwrap_or(rest);
-        return ident(rest, &[' ', '{', ':', ';']).map(|n| (ChunkKind::Type, n));
+        return ident(rest, &[' ', '{', ':', ';']).map(|n| (ChunkKind::Enum, n));
     }
     if let Some(rest) = stripped.strip_prefix("union ") {
         return ident(rest, &[' ', '{', ';']).map(|n| (ChunkKind::Type, n));
@@ -377,8 +909,511 @@ fn extract_c_function_name(line: &str) -> Option<String> {
     }
 }
 
+// ── Kotlin ─────────────────────────────────────────────────────────
+
+const KOTLIN_MODIFIERS: &[&str] = &[
+    "public ",
+    "private ",
+    "protected ",
+    "internal ",
+    "open ",
+    "abstract ",
+    "final ",
+    "override ",
+    "suspend ",
+    "inline ",
+    "data ",
+    "sealed ",
+    "enum ",
+    "annotation ",
+    "inner ",
+    "value ",
+    "operator ",
+    "infix ",
+    "tailrec ",
+    "external ",
+    "companion ",
+    "expect ",
+    "actual ",
+];
+
+fn extract_kotlin(line: &str) -> Option<(ChunkKind, String)> {
+    let stripped = strip_modifiers(line, KOTLIN_MODIFIERS);
+    if let Some(rest) = stripped.strip_prefix("fun ") {
+        // `fun String.toSlug()` extends String
+        let name = ident(skip_generics(rest), &['(', ' '])?;
+        return Some((ChunkKind::Function, receiver_method(&name, ".")));
+    }
+    if let Some(rest) = stripped.strip_prefix("const val ") {
+        return ident(rest, &[' ', ':', '=']).map(|n| (ChunkKind::Constant, n));
+    }
+    for (keyword, kind) in [
+        ("class ", ChunkKind::Type),
+        ("interface ", ChunkKind::Trait),
+        ("object ", ChunkKind::Type),
+        ("typealias ", ChunkKind::Type),
+    ] {
+        if let Some(rest) = stripped.strip_prefix(keyword) {
+            // The modifiers took the `enum` of `enum class`
+            let kind = if line.contains("enum class ") {
+                ChunkKind::Enum
+            } else {
+                kind
+            };
+            return ident(rest, &[' ', '(', '{', '<', ':', '=']).map(|n| (kind, n));
+        }
+    }
+    if line.starts_with("import ") || line.starts_with("package ") {
+        return Some((ChunkKind::Import, line.to_string()));
+    }
+    None
+}
+
+/// A function name qualified by the type it extends, `String.toSlug`, as
+/// the `String::toSlug` that [`place`] makes a method.
+fn receiver_method(name: &str, separator: &str) -> String {
+    match name.rsplit_once(separator) {
+        Some((receiver, method)) if !receiver.is_empty() => format!("{receiver}::{method}"),
+        _ => name.to_string(),
+    }
+}
+
+// ── Swift ──────────────────────────────────────────────────────────
+
+const SWIFT_MODIFIERS: &[&str] = &[
+    "public ",
+    "private ",
+    "fileprivate ",
+    "internal ",
+    "open ",
+    "final ",
+    "static ",
+    "override ",
+    "mutating ",
+    "nonmutating ",
+    "convenience ",
+    "required ",
+    "indirect ",
+    "nonisolated ",
+];
+
+fn extract_swift(line: &str) -> Option<(ChunkKind, String)> {
+    let stripped = strip_modifiers(line, SWIFT_MODIFIERS);
+    // A `class func` is a type method, not a class
+    let stripped = stripped
+        .strip_prefix("class ")
+        .filter(|rest| rest.starts_with("func "))
+        .unwrap_or(stripped);
+    if let Some(rest) = stripped.strip_prefix("func ") {
+        return ident(rest, &['(', '<', ' ']).map(|n| (ChunkKind::Function, n));
+    }
+    if stripped.starts_with("init(") || stripped.starts_with("init?(") {
+        return Some((ChunkKind::Function, "init".to_string()));
+    }
+    for (keyword, kind) in [
+        ("class ", ChunkKind::Type),
+        ("struct ", ChunkKind::Type),
+        ("enum ", ChunkKind::Enum),
+        ("protocol ", ChunkKind::Trait),
+        ("actor ", ChunkKind::Type),
+        ("typealias ", ChunkKind::Type),
+    ] {
+        if let Some(rest) = stripped.strip_prefix(keyword) {
+            return ident(rest, &[' ', '{', '<', ':', '=']).map(|n| (kind, n));
+        }
+    }
+    if let Some(rest) = stripped.strip_prefix("extension ") {
+        return ident(rest, &[' ', '{', '<', ':']).map(|n| (ChunkKind::Impl, n));
+    }
+    if line.starts_with("import ") {
+        return Some((ChunkKind::Import, line.to_string()));
+    }
+    None
+}
+
+// ── Scala ──────────────────────────────────────────────────────────
+
+const SCALA_MODIFIERS: &[&str] = &[
+    "private ",
+    "protected ",
+    "final ",
+    "sealed ",
+    "abstract ",
+    "implicit ",
+    "override ",
+    "lazy ",
+    "case ",
+    "open ",
+    "inline ",
+    "opaque ",
+];
+
+fn extract_scala(line: &str) -> Option<(ChunkKind, String)> {
+    let stripped = strip_modifiers(line, SCALA_MODIFIERS);
+    if let Some(rest) = stripped.strip_prefix("def ") {
+        return ident(rest, &['(', '[', ':', ' ', '=']).map(|n| (ChunkKind::Function, n));
+    }
+    for (keyword, kind) in [
+        ("class ", ChunkKind::Type),
+        ("trait ", ChunkKind::Trait),
+        ("object ", ChunkKind::Type),
+        ("enum ", ChunkKind::Enum),
+        ("type ", ChunkKind::Type),
+    ] {
+        if let Some(rest) = stripped.strip_prefix(keyword) {
+            return ident(rest, &[' ', '(', '[', '{', ':', '=']).map(|n| (kind, n));
+        }
+    }
+    if line.starts_with("import ") || line.starts_with("package ") {
+        return Some((ChunkKind::Import, line.to_string()));
+    }
+    None
+}
+
+// ── Elixir ─────────────────────────────────────────────────────────
+
+fn extract_elixir(line: &str) -> Option<(ChunkKind, String)> {
+    for (keyword, kind) in [
+        ("def ", ChunkKind::Function),
+        ("defp ", ChunkKind::Function),
+        ("defmacro ", ChunkKind::Macro),
+        ("defmacrop ", ChunkKind::Macro),
+        ("defguard ", ChunkKind::Function),
+        ("defguardp ", ChunkKind::Function),
+    ] {
+        if let Some(rest) = line.strip_prefix(keyword) {
+            return ident(rest, &['(', ' ', ',']).map(|n| (kind, n));
+        }
+    }
+    for (keyword, kind) in [
+        ("defmodule ", ChunkKind::Type),
+        ("defprotocol ", ChunkKind::Trait),
+    ] {
+        if let Some(rest) = line.strip_prefix(keyword) {
+            return ident(rest, &[' ', ',']).map(|n| (kind, n));
+        }
+    }
+    if let Some(description) = test_call(line, &["test "]) {
+        return Some((ChunkKind::TestCase, description));
+    }
+    if let Some(rest) = line.strip_prefix("defimpl ") {
+        return ident(rest, &[' ', ',']).map(|n| (ChunkKind::Impl, n));
+    }
+    if ["import ", "alias ", "require ", "use "]
+        .iter()
+        .any(|keyword| line.starts_with(keyword))
+    {
+        return Some((ChunkKind::Import, line.to_string()));
+    }
+    None
+}
+
+// ── PHP ────────────────────────────────────────────────────────────
+
+const PHP_MODIFIERS: &[&str] = &[
+    "public ",
+    "private ",
+    "protected ",
+    "static ",
+    "abstract ",
+    "final ",
+    "readonly ",
+];
+
+fn extract_php(line: &str) -> Option<(ChunkKind, String)> {
+    let stripped = strip_modifiers(line, PHP_MODIFIERS);
+    if let Some(rest) = stripped.strip_prefix("function ") {
+        let rest = rest.trim_start_matches('&');
+        return ident(rest, &['(', ' ']).map(|n| (ChunkKind::Function, n));
+    }
+    for (keyword, kind) in [
+        ("class ", ChunkKind::Type),
+        ("interface ", ChunkKind::Trait),
+        ("trait ", ChunkKind::Trait),
+        ("enum ", ChunkKind::Enum),
+    ] {
+        if let Some(rest) = stripped.strip_prefix(keyword) {
+            return ident(rest, &[' ', '{', ':']).map(|n| (kind, n));
+        }
+    }
+    if let Some(rest) = stripped.strip_prefix("const ") {
+        return typed_constant(rest).map(|n| (ChunkKind::Constant, n));
+    }
+    if ["use ", "namespace ", "require", "include"]
+        .iter()
+        .any(|keyword| line.starts_with(keyword))
+    {
+        return Some((ChunkKind::Import, line.to_string()));
+    }
+    None
+}
+
+// ── C# ─────────────────────────────────────────────────────────────
+
+const CSHARP_MODIFIERS: &[&str] = &[
+    "public ",
+    "private ",
+    "protected ",
+    "internal ",
+    "static ",
+    "abstract ",
+    "sealed ",
+    "virtual ",
+    "override ",
+    "async ",
+    "partial ",
+    "readonly ",
+    "unsafe ",
+    "extern ",
+    "new ",
+    "file ",
+    "required ",
+];
+
+fn extract_csharp(line: &str) -> Option<(ChunkKind, String)> {
+    if line.starts_with("using ") && !line.contains('(') {
+        return Some((ChunkKind::Import, line.to_string()));
+    }
+    let stripped = strip_modifiers(line, CSHARP_MODIFIERS);
+    // `record Person(string Name)`, `record struct Point`
+    if let Some(rest) = stripped.strip_prefix("record ") {
+        let rest = rest
+            .strip_prefix("struct ")
+            .or_else(|| rest.strip_prefix("class "))
+            .unwrap_or(rest);
+        return ident(rest, &[' ', '(', '{', '<', ':', ';']).map(|n| (ChunkKind::Type, n));
+    }
+    for (keyword, kind) in [
+        ("class ", ChunkKind::Type),
+        ("struct ", ChunkKind::Type),
+        ("interface ", ChunkKind::Trait),
+        ("enum ", ChunkKind::Enum),
+        ("namespace ", ChunkKind::Type),
+    ] {
+        if let Some(rest) = stripped.strip_prefix(keyword) {
+            return ident(rest, &[' ', '{', '<', ':', ';']).map(|n| (kind, n));
+        }
+    }
+    if let Some(rest) = stripped.strip_prefix("const ") {
+        return typed_constant(rest).map(|n| (ChunkKind::Constant, n));
+    }
+    typed_function_name(stripped).map(|n| (ChunkKind::Function, n))
+}
+
+// ── Dart ───────────────────────────────────────────────────────────
+
+const DART_MODIFIERS: &[&str] = &[
+    "abstract ",
+    "base ",
+    "final ",
+    "sealed ",
+    "interface ",
+    "static ",
+    "external ",
+    "factory ",
+];
+
+fn extract_dart(line: &str) -> Option<(ChunkKind, String)> {
+    if ["import ", "export ", "part "]
+        .iter()
+        .any(|keyword| line.starts_with(keyword))
+    {
+        return Some((ChunkKind::Import, line.to_string()));
+    }
+    if let Some(description) = test_call(line, &["test(", "testWidgets("]) {
+        return Some((ChunkKind::TestCase, description));
+    }
+    let stripped = strip_modifiers(line, DART_MODIFIERS);
+    for (keyword, kind) in [
+        ("class ", ChunkKind::Type),
+        ("mixin ", ChunkKind::Trait),
+        ("enum ", ChunkKind::Enum),
+        ("extension ", ChunkKind::Type),
+        ("typedef ", ChunkKind::Type),
+    ] {
+        if let Some(rest) = stripped.strip_prefix(keyword) {
+            return ident(rest, &[' ', '{', '<', '=', '(']).map(|n| (kind, n));
+        }
+    }
+    if let Some(rest) = stripped.strip_prefix("const ") {
+        return typed_constant(rest).map(|n| (ChunkKind::Constant, n));
+    }
+    typed_function_name(stripped).map(|n| (ChunkKind::Function, n))
+}
+
+/// The name of the function or method a C#- or Dart-style `Type name(...)`
+/// declaration on `line` defines. Statements are told apart by their
+/// leading keyword, or by an assignment or call before the parenthesis.
+fn typed_function_name(line: &str) -> Option<String> {
+    const STATEMENTS: &[&str] = &[
+        "if", "else", "for", "foreach", "while", "switch", "catch", "return", "await", "throw",
+        "new", "yield", "using", "lock", "case", "var", "final", "const", "}",
+    ];
+    let before_paren = line[..line.find('(')?].trim();
+    let (return_type, name) = before_paren.rsplit_once(' ')?;
+    // `T First<T>(...)`
+    let name = name.split('<').next()?;
+    let first = return_type.split_whitespace().next()?;
+    let statement =
+        STATEMENTS.contains(&first) || STATEMENTS.contains(&name) || before_paren.contains('=');
+    let valid = name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+    (!statement && valid).then(|| name.to_string())
+}
+
+// ── Lua ────────────────────────────────────────────────────────────
+
+fn extract_lua(line: &str) -> Option<(ChunkKind, String)> {
+    let stripped = line.strip_prefix("local ").unwrap_or(line);
+    if let Some(rest) = stripped.strip_prefix("function ") {
+        // `function M.new(...)` and `function M:greet(...)` belong to M
+        let name = ident(rest, &['(', ' '])?;
+        let name = receiver_method(&name.replace(':', "."), ".");
+        return Some((ChunkKind::Function, name));
+    }
+    if line.contains("require(") || line.contains("require \"") || line.contains("require '") {
+        return Some((ChunkKind::Import, line.to_string()));
+    }
+    None
+}
+
+// ── SQL ────────────────────────────────────────────────────────────
+
+/// Schema objects by their `CREATE` keyword; functions and procedures are
+/// [`ChunkKind::Function`], the rest [`ChunkKind::Type`].
+const SQL_OBJECTS: &[(&str, ChunkKind)] = &[
+    ("table", ChunkKind::Type),
+    ("view", ChunkKind::Type),
+    ("index", ChunkKind::Type),
+    ("function", ChunkKind::Function),
+    ("procedure", ChunkKind::Function),
+];
+
+/// Words that may come between `CREATE` and the object, or between the
+/// object and its name.
+const SQL_MODIFIERS: &[&str] = &[
+    "or",
+    "replace",
+    "temp",
+    "temporary",
+    "unlogged",
+    "materialized",
+    "unique",
+    "clustered",
+    "nonclustered",
+    "concurrently",
+    "if",
+    "not",
+    "exists",
+];
+
+fn extract_sql(line: &str) -> Option<(ChunkKind, String)> {
+    let mut words = line.split_whitespace();
+    if !words.next()?.eq_ignore_ascii_case("create") {
+        return None;
+    }
+    let mut word = words.find(|w| !is_sql_modifier(w))?;
+    let &(_, kind) = SQL_OBJECTS
+        .iter()
+        .find(|(object, _)| word.eq_ignore_ascii_case(object))?;
+    word = words.find(|w| !is_sql_modifier(w))?;
+    // `public.users(`, `"Users"`, `[dbo].[Users]`: the unquoted last part
+    let name = word.split('(').next()?.rsplit('.').next()?;
+    let name = name.trim_matches(|c| matches!(c, '"' | '`' | '[' | ']' | ';'));
+    (!name.is_empty() && !name.eq_ignore_ascii_case("on")).then(|| (kind, name.to_string()))
+}
+
+fn is_sql_modifier(word: &str) -> bool {
+    SQL_MODIFIERS.iter().any(|m| word.eq_ignore_ascii_case(m))
+}
+
+// ── Protobuf ───────────────────────────────────────────────────────
+
+fn extract_protobuf(line: &str) -> Option<(ChunkKind, String)> {
+    for (keyword, kind) in [
+        ("message ", ChunkKind::Type),
+        ("enum ", ChunkKind::Enum),
+        ("service ", ChunkKind::Type),
+    ] {
+        if let Some(rest) = line.strip_prefix(keyword) {
+            return ident(rest, &[' ', '{']).map(|n| (kind, n));
+        }
+    }
+    if let Some(rest) = line.strip_prefix("rpc ") {
+        return ident(rest, &[' ', '(']).map(|n| (ChunkKind::Function, n));
+    }
+    if line.starts_with("import ") {
+        return Some((ChunkKind::Import, line.to_string()));
+    }
+    None
+}
+
+// ── GraphQL ────────────────────────────────────────────────────────
+
+/// Types whose fields are the schema's operations.
+const GRAPHQL_ROOT_TYPES: &[&str] = &["Query", "Mutation", "Subscription"];
+
+fn extract_graphql(line: &str) -> Option<(ChunkKind, String)> {
+    let line = line.strip_prefix("extend ").unwrap_or(line);
+    for (keyword, kind) in [
+        ("type ", ChunkKind::Type),
+        ("input ", ChunkKind::Type),
+        ("interface ", ChunkKind::Trait),
+        ("enum ", ChunkKind::Enum),
+        ("union ", ChunkKind::Type),
+        ("scalar ", ChunkKind::Type),
+    ] {
+        if let Some(rest) = line.strip_prefix(keyword) {
+            return ident(rest, &[' ', '{', '@', '=']).map(|n| (kind, n));
+        }
+    }
+    for keyword in ["query ", "mutation ", "subscription ", "fragment "] {
+        if let Some(rest) = line.strip_prefix(keyword) {
+            return ident(rest, &[' ', '{', '(', '@']).map(|n| (ChunkKind::Function, n));
+        }
+    }
+    None
+}
+
+/// The name of the field declared on `line` in the body of a root type:
+/// `user(id: ID!): User`.
+fn graphql_field(line: &str) -> Option<String> {
+    let end = line.find([':', '('])?;
+    let name = line[..end].trim();
+    (!name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_'))
+        .then(|| name.to_string())
+}
+
 // ── Helpers ────────────────────────────────────────────────────────
 
+/// The name assigned on `line` if it's a `SCREAMING_CASE` constant:
+/// `MAX_RETRIES = 3` or `TIMEOUT: float = 1.5`.
+fn screaming_assignment(line: &str) -> Option<String> {
+    let name = ident(line, &[' ', ':', '='])?;
+    let after = line[name.len()..].trim_start();
+    let assigned = (after.starts_with('=') && !after.starts_with("=="))
+        || (after.starts_with(':') && after.contains('='));
+    (assigned && screaming_case(&name)).then_some(name)
+}
+
+/// The name a typed constant declaration assigns: `MaxSize` in
+/// `int MaxSize = 10;`, or `LIMIT` in `LIMIT = 5;`.
+fn typed_constant(declaration: &str) -> Option<String> {
+    let (target, _) = declaration.split_once('=')?;
+    let name = target.split_whitespace().last()?;
+    (name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_'))
+    .then(|| name.to_string())
+}
+
+/// Whether `name` is written in `SCREAMING_CASE`, as constants are.
+fn screaming_case(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_uppercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
 /// Extract the first identifier token from `rest`, splitting on any char in `delims`.
 fn ident(rest: &str, delims: &[char]) -> Option<String> {
     let name = rest.split(delims).next()?.trim();
@@ -425,14 +1460,74 @@ impl Config<String> {
 ";
         let chunks = RegexChunker.chunk(src, Language::Rust);
         let kinds: Vec<_> = chunks.iter().map(|c| c.kind).collect();
-        assert!(kinds.contains(&ChunkKind::Type)); // struct, enum, trait
+        assert!(kinds.contains(&ChunkKind::Type));
+        assert!(kinds.contains(&ChunkKind::Enum));
+        assert!(kinds.contains(&ChunkKind::Trait));
         assert!(kinds.contains(&ChunkKind::Impl));
-        assert!(kinds.contains(&ChunkKind::Function)); // new, handle
+        assert!(!kinds.contains(&ChunkKind::Function));
+        let methods: Vec<String> = chunks
+            .iter()
+            .filter(|c| c.kind == ChunkKind::Method)
+            .map(Chunk::qualified_name)
+            .collect();
+        assert_eq!(methods, ["Handler::handle", "Config::new"]);
         assert!(chunks.iter().any(|c| c.name == "Config"));
         assert!(chunks.iter().any(|c| c.name == "Status"));
         assert!(chunks.iter().any(|c| c.name == "Handler"));
     }
 
+    #[test]
+    fn rust_methods_name_the_type_they_are_for() {
+        let src = "\
+impl<T: Clone> Cache<T> {
+    fn get(&self) -> T {
+        let key = || { 1 };
+        fn helper() {}
+        todo!()
+    }
+}
+
+impl fmt::Display for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Ok(())
+    }
+}
+
+trait Check {
+    fn check(&self)
+        -> bool;
+}
+
+fn free() {}
+
+mod tests {
+    fn case() {}
+}
+";
+        let chunks = RegexChunker.chunk(src, Language::Rust);
+        let functions: Vec<(ChunkKind, String)> = chunks
+            .iter()
+            .filter(|c| matches!(c.kind, ChunkKind::Function | ChunkKind::Method))
+            .map(|c| (c.kind, c.qualified_name()))
+            .collect();
+        assert_eq!(
+            functions,
+            [
+                (ChunkKind::Method, "Cache::get".to_string()),
+                (ChunkKind::Function, "helper".to_string()),
+                (ChunkKind::Method, "Auth::fmt".to_string()),
+                (ChunkKind::Method, "Check::check".to_string()),
+                (ChunkKind::Function, "free".to_string()),
+                (ChunkKind::Function, "case".to_string()),
+            ]
+        );
+        assert!(
+            chunks
+                .iter()
+                .any(|c| c.kind == ChunkKind::Impl && c.name == "Cache")
+        );
+    }
+
     #[test]
     fn rust_imports() {
         let src = "use std::collections::HashMap;\nuse crate::Foo;\n";
@@ -441,6 +1536,41 @@ impl Config<String> {
         assert!(chunks.iter().all(|c| c.kind == ChunkKind::Import));
     }
 
+    #[test]
+    fn rust_mod_declarations_are_imports() {
+        let src = "pub mod auth;\nmod db;\n\nmod tests {\n}\n";
+        let chunks = RegexChunker.chunk(src, Language::Rust);
+        let names: Vec<&str> = chunks.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["mod auth;", "mod db;"]);
+        assert!(chunks.iter().all(|c| c.kind == ChunkKind::Import));
+    }
+
+    #[test]
+    fn go_import_blocks_give_one_chunk_per_package() {
+        let src = "import (\n\t\"fmt\"\n\t// HTTP\n\tweb \"net/http\"\n)\n\nfunc main() {}\n";
+        let chunks = RegexChunker.chunk(src, Language::Go);
+        let imports: Vec<(&str, u32)> = chunks
+            .iter()
+            .filter(|c| c.kind == ChunkKind::Import)
+            .map(|c| (c.name.as_str(), c.start_line))
+            .collect();
+        assert_eq!(
+            imports,
+            [("import \"fmt\"", 2), ("import web \"net/http\"", 4)]
+        );
+        assert!(chunks.iter().any(|c| c.name == "main"));
+    }
+
+    #[test]
+    fn multiline_js_imports_span_to_their_module() {
+        let src = "import {\n  login,\n  logout,\n} from './auth';\n\nfunction app() {}\n";
+        let chunks = RegexChunker.chunk(src, Language::TypeScript);
+        let import = chunks.iter().find(|c| c.kind == ChunkKind::Import).unwrap();
+        assert_eq!(import.name, "import { login, logout, } from './auth';");
+        assert_eq!((import.start_line, import.end_line), (1, 4));
+        assert!(chunks.iter().any(|c| c.name == "app"));
+    }
+
     #[test]
     fn rust_type_alias() {
         let src = "pub type Result<T> = std::result::Result<T, Error>;\n";
@@ -464,7 +1594,7 @@ impl Config<String> {
         assert!(
             chunks
                 .iter()
-                .any(|c| c.name == "Handle" && c.kind == ChunkKind::Function)
+                .any(|c| c.qualified_name() == "Server::Handle" && c.kind == ChunkKind::Method)
         );
     }
 
@@ -504,7 +1634,8 @@ from pathlib import Path
         assert!(
             chunks
                 .iter()
-                .any(|c| c.name == "authenticate" && c.kind == ChunkKind::Function)
+                .any(|c| c.qualified_name() == "UserService::authenticate"
+                    && c.kind == ChunkKind::Method)
         );
         assert!(
             chunks
@@ -558,6 +1689,39 @@ import { useState } from 'react';
         assert!(chunks.iter().any(|c| c.kind == ChunkKind::Import));
     }
 
+    #[test]
+    fn js_class_methods() {
+        let src = "\
+export class Auth {
+    constructor(store) {
+        this.store = store;
+    }
+
+    static async load(id) {
+        if (id) {
+            return new Auth();
+        }
+    }
+}
+
+function check() {}
+";
+        let chunks = RegexChunker.chunk(src, Language::JavaScript);
+        let functions: Vec<(ChunkKind, String)> = chunks
+            .iter()
+            .filter(|c| matches!(c.kind, ChunkKind::Function | ChunkKind::Method))
+            .map(|c| (c.kind, c.qualified_name()))
+            .collect();
+        assert_eq!(
+            functions,
+            [
+                (ChunkKind::Method, "Auth::constructor".to_string()),
+                (ChunkKind::Method, "Auth::load".to_string()),
+                (ChunkKind::Function, "check".to_string()),
+            ]
+        );
+    }
+
     #[test]
     fn ts_interfaces_and_types() {
         let src = "\
@@ -576,7 +1740,7 @@ export enum Status {
         assert!(
             chunks
                 .iter()
-                .any(|c| c.name == "Config" && c.kind == ChunkKind::Type)
+                .any(|c| c.name == "Config" && c.kind == ChunkKind::Trait)
         );
         assert!(
             chunks
@@ -586,7 +1750,7 @@ export enum Status {
         assert!(
             chunks
                 .iter()
-                .any(|c| c.name == "Status" && c.kind == ChunkKind::Type)
+                .any(|c| c.name == "Status" && c.kind == ChunkKind::Enum)
         );
     }
 
@@ -614,12 +1778,13 @@ public class UserService {
         assert!(
             chunks
                 .iter()
-                .any(|c| c.name == "authenticate" && c.kind == ChunkKind::Function)
+                .any(|c| c.qualified_name() == "UserService::authenticate"
+                    && c.kind == ChunkKind::Method)
         );
         assert!(
-            chunks
-                .iter()
-                .any(|c| c.name == "isValid" && c.kind == ChunkKind::Function)
+            chunks.iter().any(
+                |c| c.qualified_name() == "UserService::isValid" && c.kind == ChunkKind::Method
+            )
         );
     }
 
@@ -640,12 +1805,12 @@ import java.util.List;
         assert!(
             chunks
                 .iter()
-                .any(|c| c.name == "Handler" && c.kind == ChunkKind::Type)
+                .any(|c| c.name == "Handler" && c.kind == ChunkKind::Trait)
         );
         assert!(
             chunks
                 .iter()
-                .any(|c| c.name == "Status" && c.kind == ChunkKind::Type)
+                .any(|c| c.name == "Status" && c.kind == ChunkKind::Enum)
         );
         assert!(chunks.iter().any(|c| c.kind == ChunkKind::Import));
     }
@@ -680,12 +1845,14 @@ require_relative 'helper'
         assert!(
             chunks
                 .iter()
-                .any(|c| c.name == "authenticate" && c.kind == ChunkKind::Function)
+                .any(|c| c.qualified_name() == "UserService::authenticate"
+                    && c.kind == ChunkKind::Method)
         );
         assert!(
             chunks
                 .iter()
-                .any(|c| c.name == "create" && c.kind == ChunkKind::Function)
+                .any(|c| c.qualified_name() == "UserService::create"
+                    && c.kind == ChunkKind::Method)
         );
         assert!(
             chunks
@@ -777,10 +1944,20 @@ enum class Status {
         assert!(
             chunks
                 .iter()
-                .any(|c| c.name == "Status" && c.kind == ChunkKind::Type)
+                .any(|c| c.name == "Status" && c.kind == ChunkKind::Enum)
         );
     }
 
+    #[test]
+    fn cpp_methods_defined_out_of_class() {
+        let src = "bool Auth::check(const char *token) {\n    return true;\n}\n";
+        let chunks = RegexChunker.chunk(src, Language::Cpp);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].kind, ChunkKind::Method);
+        assert_eq!(chunks[0].name, "check");
+        assert_eq!(chunks[0].parent.as_deref(), Some("Auth"));
+    }
+
     #[test]
     fn cpp_typedef() {
         let src = "typedef unsigned long size_t;\n";
@@ -790,6 +1967,595 @@ enum class Status {
         assert_eq!(chunks[0].name, "size_t");
     }
 
+    // ── SQL ────────────────────────────────────────────────────────
+
+    #[test]
+    fn sql_schema_objects() {
+        let src = "\
+-- users and their sessions
+CREATE TABLE IF NOT EXISTS public.users (
+    id SERIAL PRIMARY KEY,
+    email TEXT NOT NULL
+);
+create unique index idx_users_email on users (email);
+CREATE INDEX ON users (id);
+CREATE OR REPLACE VIEW \"ActiveUsers\" AS SELECT * FROM users;
+CREATE MATERIALIZED VIEW session_counts AS SELECT 1;
+CREATE OR REPLACE FUNCTION touch_user(id integer) RETURNS void AS $$
+BEGIN
+END;
+$$ LANGUAGE plpgsql;
+CREATE PROCEDURE [dbo].[PurgeSessions]
+";
+        let chunks = RegexChunker.chunk(src, Language::Sql);
+        let found: Vec<(ChunkKind, &str, u32)> = chunks
+            .iter()
+            .map(|c| (c.kind, c.name.as_str(), c.start_line))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (ChunkKind::Type, "users", 2),
+                (ChunkKind::Type, "idx_users_email", 6),
+                (ChunkKind::Type, "ActiveUsers", 8),
+                (ChunkKind::Type, "session_counts", 9),
+                (ChunkKind::Function, "touch_user", 10),
+                (ChunkKind::Function, "PurgeSessions", 14),
+            ]
+        );
+    }
+
+    // ── Kotlin, Swift, Scala, Elixir, PHP, C#, Dart, Lua ───────────
+
+    fn expect(found: &[(&str, ChunkKind, u32)]) -> Vec<(ChunkKind, String, u32)> {
+        found
+            .iter()
+            .map(|&(name, kind, line)| (kind, name.to_string(), line))
+            .collect()
+    }
+
+    #[test]
+    fn kotlin_declarations() {
+        let src = "\
+package app.auth
+import kotlinx.coroutines.flow.Flow
+
+data class Session(val token: String)
+sealed interface Result
+object Registry {
+    suspend fun lookup(id: Int): Session? = null
+}
+fun String.toSlug(): String = lowercase()
+private fun <T> cache(value: T): T = value
+";
+        let chunks = RegexChunker.chunk(src, Language::Kotlin);
+        assert_eq!(
+            placed(&chunks),
+            expect(&[
+                ("package app.auth", ChunkKind::Import, 1),
+                ("import kotlinx.coroutines.flow.Flow", ChunkKind::Import, 2),
+                ("Session", ChunkKind::Type, 4),
+                ("Result", ChunkKind::Trait, 5),
+                ("Registry", ChunkKind::Type, 6),
+                ("Registry::lookup", ChunkKind::Method, 7),
+                ("String::toSlug", ChunkKind::Method, 9),
+                ("cache", ChunkKind::Function, 10),
+            ])
+        );
+    }
+
+    #[test]
+    fn swift_declarations() {
+        let src = "\
+import Foundation
+
+public final class AuthService {
+    init(store: Store) {}
+    @objc public func login(user: String) -> Bool { true }
+    class func shared() -> AuthService { AuthService() }
+}
+protocol Store {}
+extension Store {
+    mutating func reset() {}
+}
+func main() {}
+";
+        let chunks = RegexChunker.chunk(src, Language::Swift);
+        assert_eq!(
+            placed(&chunks),
+            expect(&[
+                ("import Foundation", ChunkKind::Import, 1),
+                ("AuthService", ChunkKind::Type, 3),
+                ("AuthService::init", ChunkKind::Method, 4),
+                ("AuthService::login", ChunkKind::Method, 5),
+                ("AuthService::shared", ChunkKind::Method, 6),
+                ("Store", ChunkKind::Trait, 8),
+                ("Store", ChunkKind::Impl, 9),
+                ("Store::reset", ChunkKind::Method, 10),
+                ("main", ChunkKind::Function, 12),
+            ])
+        );
+    }
+
+    #[test]
+    fn scala_declarations() {
+        let src = "\
+package app
+import scala.util.Try
+
+sealed trait Shape
+final case class Circle(r: Double) extends Shape {
+  def area: Double = math.Pi * r * r
+}
+object Shapes {
+  private def parse[T](s: String): Try[T] = ???
+}
+";
+        let chunks = RegexChunker.chunk(src, Language::Scala);
+        assert_eq!(
+            placed(&chunks),
+            expect(&[
+                ("package app", ChunkKind::Import, 1),
+                ("import scala.util.Try", ChunkKind::Import, 2),
+                ("Shape", ChunkKind::Trait, 4),
+                ("Circle", ChunkKind::Type, 5),
+                ("Circle::area", ChunkKind::Method, 6),
+                ("Shapes", ChunkKind::Type, 8),
+                ("Shapes::parse", ChunkKind::Method, 9),
+            ])
+        );
+    }
+
+    #[test]
+    fn elixir_declarations() {
+        let src = "\
+defmodule MyApp.Auth do
+  alias MyApp.Repo
+
+  def login(user, password) do
+    :ok
+  end
+
+  defp hash(password), do: password
+end
+
+defimpl String.Chars, for: MyApp.User do
+  def to_string(user), do: user.name
+end
+";
+        let chunks = RegexChunker.chunk(src, Language::Elixir);
+        assert_eq!(
+            placed(&chunks),
+            expect(&[
+                ("MyApp.Auth", ChunkKind::Type, 1),
+                ("alias MyApp.Repo", ChunkKind::Import, 2),
+                ("MyApp.Auth::login", ChunkKind::Method, 4),
+                ("MyApp.Auth::hash", ChunkKind::Method, 8),
+                ("String.Chars", ChunkKind::Impl, 11),
+                ("String.Chars::to_string", ChunkKind::Method, 12),
+            ])
+        );
+    }
+
+    #[test]
+    fn ruby_visibility_modifiers() {
+        let src = "class Auth\n  private def digest(password)\n  end\nend\n";
+        let chunks = RegexChunker.chunk(src, Language::Ruby);
+        assert_eq!(
+            placed(&chunks),
+            expect(&[
+                ("Auth", ChunkKind::Type, 1),
+                ("Auth::digest", ChunkKind::Method, 2),
+            ])
+        );
+    }
+
+    #[test]
+    fn php_declarations() {
+        let src = "\
+<?php
+namespace App\\Auth;
+use App\\Models\\User;
+require_once 'helpers.php';
+
+final class LoginController
+{
+    public static function &handle(User $user): bool
+    {
+        return true;
+    }
+}
+function helper() {}
+";
+        let chunks = RegexChunker.chunk(src, Language::Php);
+        assert_eq!(
+            placed(&chunks),
+            expect(&[
+                ("namespace App\\Auth;", ChunkKind::Import, 2),
+                ("use App\\Models\\User;", ChunkKind::Import, 3),
+                ("require_once 'helpers.php';", ChunkKind::Import, 4),
+                ("LoginController", ChunkKind::Type, 6),
+                ("LoginController::handle", ChunkKind::Method, 8),
+                ("helper", ChunkKind::Function, 13),
+            ])
+        );
+    }
+
+    #[test]
+    fn csharp_declarations() {
+        let src = "\
+using System.Threading.Tasks;
+
+namespace App.Auth
+{
+    public record Credentials(string User, string Password);
+
+    public sealed class AuthService : IAuthService
+    {
+        public AuthService(IStore store) {}
+
+        [HttpPost(\"login\")]
+        public async Task<bool> LoginAsync(Credentials credentials)
+        {
+            var ok = await Verify(credentials);
+            if (ok) { Log(credentials); }
+            return ok;
+        }
+
+        private static T First<T>(List<T> items) => items[0];
+    }
+}
+";
+        let chunks = RegexChunker.chunk(src, Language::CSharp);
+        assert_eq!(
+            placed(&chunks),
+            expect(&[
+                ("using System.Threading.Tasks;", ChunkKind::Import, 1),
+                ("App.Auth", ChunkKind::Type, 3),
+                ("Credentials", ChunkKind::Type, 5),
+                ("AuthService", ChunkKind::Type, 7),
+                ("AuthService::LoginAsync", ChunkKind::Method, 12),
+                ("AuthService::First", ChunkKind::Method, 19),
+            ])
+        );
+    }
+
+    #[test]
+    fn dart_declarations() {
+        let src = "\
+import 'package:http/http.dart' as http;
+
+abstract class Repository {
+  Future<User> fetch(String id);
+}
+mixin Logging {}
+class UserRepository extends Repository {
+  @override
+  Future<User> fetch(String id) async {
+    final response = await http.get(uri(id));
+    return User.fromJson(response.body);
+  }
+}
+void main() {}
+";
+        let chunks = RegexChunker.chunk(src, Language::Dart);
+        assert_eq!(
+            placed(&chunks),
+            expect(&[
+                (
+                    "import 'package:http/http.dart' as http;",
+                    ChunkKind::Import,
+                    1
+                ),
+                ("Repository", ChunkKind::Type, 3),
+                ("Repository::fetch", ChunkKind::Method, 4),
+                ("Logging", ChunkKind::Trait, 6),
+                ("UserRepository", ChunkKind::Type, 7),
+                ("UserRepository::fetch", ChunkKind::Method, 9),
+                ("main", ChunkKind::Function, 14),
+            ])
+        );
+    }
+
+    #[test]
+    fn lua_declarations() {
+        let src = "\
+local json = require(\"json\")
+local M = {}
+
+function M.new(opts)
+  return setmetatable({}, M)
+end
+
+function M:greet(name)
+end
+
+local function helper() end
+";
+        let chunks = RegexChunker.chunk(src, Language::Lua);
+        assert_eq!(
+            placed(&chunks),
+            expect(&[
+                ("local json = require(\"json\")", ChunkKind::Import, 1),
+                ("M::new", ChunkKind::Method, 4),
+                ("M::greet", ChunkKind::Method, 8),
+                ("helper", ChunkKind::Function, 11),
+            ])
+        );
+    }
+
+    // ── Protobuf and GraphQL ───────────────────────────────────────
+
+    fn placed(chunks: &[Chunk]) -> Vec<(ChunkKind, String, u32)> {
+        chunks
+            .iter()
+            .map(|c| (c.kind, c.qualified_name(), c.start_line))
+            .collect()
+    }
+
+    #[test]
+    fn protobuf_messages_and_services() {
+        let src = "\
+syntax = \"proto3\";
+import \"google/protobuf/timestamp.proto\";
+
+message LoginRequest {
+  string user = 1;
+  enum Mode {
+    PASSWORD = 0;
+  }
+}
+
+service Auth {
+  // Exchanges credentials for a token.
+  rpc Login(LoginRequest) returns (LoginReply);
+  rpc Watch(stream Ping) returns (stream Pong) {}
+}
+";
+        let chunks = RegexChunker.chunk(src, Language::Protobuf);
+        let expected = [
+            (
+                ChunkKind::Import,
+                "import \"google/protobuf/timestamp.proto\";",
+                2,
+            ),
+            (ChunkKind::Type, "LoginRequest", 4),
+            (ChunkKind::Enum, "Mode", 6),
+            (ChunkKind::Type, "Auth", 11),
+            (ChunkKind::Method, "Auth::Login", 13),
+            (ChunkKind::Method, "Auth::Watch", 14),
+        ]
+        .map(|(kind, name, line)| (kind, name.to_string(), line));
+        assert_eq!(placed(&chunks), expected);
+    }
+
+    #[test]
+    fn graphql_types_and_operations() {
+        let src = "\
+\"\"\"
+A registered account. Note: emails are unique.
+\"\"\"
+type User implements Node @key(fields: \"id\") {
+  id: ID!
+  email: String
+}
+
+type Query {
+  \"Look up one account\"
+  user(id: ID!): User
+  users: [User!]!
+}
+
+extend type Mutation {
+  login(email: String!, password: String!): Token
+}
+
+input LoginInput { email: String! }
+union SearchResult = User | Post
+scalar DateTime
+
+query CurrentUser($id: ID!) {
+  user(id: $id) { email }
+}
+fragment UserFields on User { id }
+";
+        let chunks = RegexChunker.chunk(src, Language::GraphQl);
+        let expected = [
+            (ChunkKind::Type, "User", 4),
+            (ChunkKind::Type, "Query", 9),
+            (ChunkKind::Method, "Query::user", 11),
+            (ChunkKind::Method, "Query::users", 12),
+            (ChunkKind::Type, "Mutation", 15),
+            (ChunkKind::Method, "Mutation::login", 16),
+            (ChunkKind::Type, "LoginInput", 19),
+            (ChunkKind::Type, "SearchResult", 20),
+            (ChunkKind::Type, "DateTime", 21),
+            (ChunkKind::Function, "CurrentUser", 23),
+            (ChunkKind::Function, "UserFields", 26),
+        ]
+        .map(|(kind, name, line)| (kind, name.to_string(), line));
+        assert_eq!(placed(&chunks), expected);
+    }
+
+    // ── Traits, enums, constants, macros, and tests ────────────────
+
+    #[test]
+    fn rust_constants_macros_and_tests() {
+        let src = "\
+pub const MAX_TOKENS: usize = 8;
+static mut COUNT: u32 = 0;
+pub const fn limit() -> usize { 8 }
+macro_rules! ensure {
+    ($e:expr) => {};
+}
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn checks_token() {}
+    #[tokio::test]
+    async fn refreshes() {}
+    fn helper() {}
+}
+";
+        assert_eq!(
+            placed(&RegexChunker.chunk(src, Language::Rust)),
+            expect(&[
+                ("MAX_TOKENS", ChunkKind::Constant, 1),
+                ("COUNT", ChunkKind::Constant, 2),
+                ("limit", ChunkKind::Function, 3),
+                ("ensure", ChunkKind::Macro, 4),
+                ("checks_token", ChunkKind::TestCase, 10),
+                ("refreshes", ChunkKind::TestCase, 12),
+                ("helper", ChunkKind::Function, 13),
+            ])
+        );
+    }
+
+    #[test]
+    fn go_and_python_kinds() {
+        let src = "\
+const MaxRetries = 3
+type Store interface {
+\tGet(key string) string
+}
+func TestLogin(t *testing.T) {}
+func TestMain(m *testing.M) {}
+";
+        assert_eq!(
+            placed(&RegexChunker.chunk(src, Language::Go)),
+            expect(&[
+                ("MaxRetries", ChunkKind::Constant, 1),
+                ("Store", ChunkKind::Trait, 2),
+                ("TestLogin", ChunkKind::TestCase, 5),
+                ("TestMain", ChunkKind::Function, 6),
+            ])
+        );
+
+        let src = "\
+MAX_RETRIES = 3
+TIMEOUT: float = 1.5
+logger = get_logger()
+class Color(Enum):
+    RED = 1
+class Store(Protocol):
+    def get(self): ...
+class TestAuth(unittest.TestCase):
+    def test_login(self):
+        pass
+    def setUp(self):
+        pass
+def test_logout():
+    pass
+";
+        assert_eq!(
+            placed(&RegexChunker.chunk(src, Language::Python)),
+            expect(&[
+                ("MAX_RETRIES", ChunkKind::Constant, 1),
+                ("TIMEOUT", ChunkKind::Constant, 2),
+                ("Color", ChunkKind::Enum, 4),
+                ("RED", ChunkKind::Constant, 5),
+                ("Store", ChunkKind::Trait, 6),
+                ("Store::get", ChunkKind::Method, 7),
+                ("TestAuth", ChunkKind::Type, 8),
+                ("TestAuth::test_login", ChunkKind::TestCase, 9),
+                ("TestAuth::setUp", ChunkKind::Method, 11),
+                ("test_logout", ChunkKind::TestCase, 13),
+            ])
+        );
+    }
+
+    #[test]
+    fn js_constants_and_test_calls() {
+        let src = "\
+export const API_URL = 'https://example.com';
+const enum Direction { Up }
+describe('auth', () => {
+  it('logs in', async () => {
+  });
+  test(\"logs out\", () => {});
+});
+";
+        assert_eq!(
+            placed(&RegexChunker.chunk(src, Language::TypeScript)),
+            expect(&[
+                ("API_URL", ChunkKind::Constant, 1),
+                ("Direction", ChunkKind::Enum, 2),
+                ("logs in", ChunkKind::TestCase, 4),
+                ("logs out", ChunkKind::TestCase, 6),
+            ])
+        );
+    }
+
+    #[test]
+    fn annotated_tests_constants_and_macros() {
+        let src = "\
+public class AuthTest {
+    private static final int MAX_SIZE = 10;
+    @Test
+    public void logsIn() {}
+    @Test public void logsOut() {}
+    @BeforeEach
+    void setUp() {}
+}
+";
+        assert_eq!(
+            placed(&RegexChunker.chunk(src, Language::Java)),
+            expect(&[
+                ("AuthTest", ChunkKind::Type, 1),
+                ("MAX_SIZE", ChunkKind::Constant, 2),
+                ("AuthTest::logsIn", ChunkKind::TestCase, 4),
+                ("AuthTest::logsOut", ChunkKind::TestCase, 5),
+                ("AuthTest::setUp", ChunkKind::Method, 7),
+            ])
+        );
+
+        let src = "\
+public class AuthTests {
+    public const int MaxSize = 10;
+    [Fact]
+    public void LogsIn() {}
+}
+public interface IStore {}
+";
+        assert_eq!(
+            placed(&RegexChunker.chunk(src, Language::CSharp)),
+            expect(&[
+                ("AuthTests", ChunkKind::Type, 1),
+                ("MaxSize", ChunkKind::Constant, 2),
+                ("AuthTests::LogsIn", ChunkKind::TestCase, 4),
+                ("IStore", ChunkKind::Trait, 6),
+            ])
+        );
+
+        let src =
+            "#define MAX_LEN 64\n#define MIN(a, b) ((a) < (b) ? (a) : (b))\nenum Mode { FAST };\n";
+        assert_eq!(
+            placed(&RegexChunker.chunk(src, Language::C)),
+            expect(&[
+                ("MAX_LEN", ChunkKind::Macro, 1),
+                ("MIN", ChunkKind::Macro, 2),
+                ("Mode", ChunkKind::Enum, 3),
+            ])
+        );
+
+        let src = "\
+defmodule AuthTest do
+  defmacro assert_ok(expr) do
+  end
+  test \"logs in\" do
+  end
+end
+";
+        assert_eq!(
+            placed(&RegexChunker.chunk(src, Language::Elixir)),
+            expect(&[
+                ("AuthTest", ChunkKind::Type, 1),
+                ("assert_ok", ChunkKind::Macro, 2),
+                ("logs in", ChunkKind::TestCase, 4),
+            ])
+        );
+    }
+
     // ── Edge cases ─────────────────────────────────────────────────
 
     #[test]
@@ -0,0 +1,191 @@
+//! Per-language and per-path chunker overrides.
+//!
+//! Library users register their own [`Chunker`] for a language, or for
+//! paths matching a glob, so formats the built-in chunkers don't know, such
+//! as in-house DSLs, are chunked properly. Everything else goes to the
+//! fallback, the [`crate::DefaultChunker`] unless replaced.
+
+use std::collections::HashMap;
+
+use globset::{Glob, GlobMatcher};
+use topo_core::{Chunk, Language, TopoError};
+
+use crate::{Chunker, default_chunker};
+
+type BoxedChunker = Box<dyn Chunker + Send + Sync>;
+
+/// Chunker that dispatches each file to the chunker registered for it.
+///
+/// Glob rules are checked first, in registration order, and the first
+/// match wins; then the chunker registered for the file's language; then
+/// the fallback. Pass the registry to `IndexBuilder::chunker` to index
+/// with it.
+pub struct ChunkerRegistry {
+    globs: Vec<(GlobMatcher, BoxedChunker)>,
+    languages: HashMap<Language, BoxedChunker>,
+    fallback: BoxedChunker,
+}
+
+impl ChunkerRegistry {
+    /// An empty registry, chunking every file with the
+    /// [`crate::DefaultChunker`].
+    pub fn new() -> Self {
+        Self {
+            globs: Vec::new(),
+            languages: HashMap::new(),
+            fallback: Box::new(default_chunker()),
+        }
+    }
+
+    /// Chunk files of `language` with `chunker`, replacing any chunker
+    /// registered for it before.
+    pub fn language(
+        mut self,
+        language: Language,
+        chunker: impl Chunker + Send + Sync + 'static,
+    ) -> Self {
+        self.languages.insert(language, Box::new(chunker));
+        self
+    }
+
+    /// Chunk files whose repo-relative path matches `pattern` with
+    /// `chunker`, whatever their language.
+    pub fn glob(
+        mut self,
+        pattern: &str,
+        chunker: impl Chunker + Send + Sync + 'static,
+    ) -> Result<Self, TopoError> {
+        let glob = Glob::new(pattern).map_err(|source| TopoError::Pattern {
+            pattern: pattern.to_string(),
+            source,
+        })?;
+        self.globs.push((glob.compile_matcher(), Box::new(chunker)));
+        Ok(self)
+    }
+
+    /// Chunk files no rule matches with `chunker` instead of the
+    /// [`crate::DefaultChunker`].
+    pub fn fallback(mut self, chunker: impl Chunker + Send + Sync + 'static) -> Self {
+        self.fallback = Box::new(chunker);
+        self
+    }
+
+    /// The chunker for the file at `path`, or for any file of `language`
+    /// when there's no path.
+    fn resolve(&self, path: Option<&str>, language: Language) -> &dyn Chunker {
+        let by_glob = path.and_then(|path| {
+            self.globs
+                .iter()
+                .find(|(matcher, _)| matcher.is_match(path))
+        });
+        match by_glob {
+            Some((_, chunker)) => chunker.as_ref(),
+            None => self
+                .languages
+                .get(&language)
+                .unwrap_or(&self.fallback)
+                .as_ref(),
+        }
+    }
+}
+
+impl Default for ChunkerRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Chunker for ChunkerRegistry {
+    fn chunk(&self, content: &str, language: Language) -> Vec<Chunk> {
+        self.resolve(None, language).chunk(content, language)
+    }
+
+    fn chunk_file(&self, path: &str, content: &str, language: Language) -> Vec<Chunk> {
+        self.resolve(Some(path), language)
+            .chunk_file(path, content, language)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use topo_core::ChunkKind;
+
+    /// A chunker that finds one chunk named `name` per file.
+    struct Named(&'static str);
+
+    impl Chunker for Named {
+        fn chunk(&self, _: &str, _: Language) -> Vec<Chunk> {
+            vec![Chunk {
+                kind: ChunkKind::Other,
+                name: self.0.to_string(),
+                start_line: 1,
+                end_line: 1,
+                content: String::new(),
+                target: None,
+                doc: None,
+                parent: None,
+                signature: None,
+                tokens: 0,
+                exported: false,
+            }]
+        }
+    }
+
+    fn names(registry: &ChunkerRegistry, path: &str, language: Language) -> Vec<String> {
+        registry
+            .chunk_file(path, "fn main() {}\n", language)
+            .into_iter()
+            .map(|c| c.name)
+            .collect()
+    }
+
+    #[test]
+    fn globs_then_languages_then_fallback() {
+        let registry = ChunkerRegistry::new()
+            .language(Language::Rust, Named("rust"))
+            .language(Language::Other, Named("other"))
+            .glob("rules/**/*.dsl", Named("dsl"))
+            .unwrap()
+            .glob("rules/**", Named("rules"))
+            .unwrap();
+        assert_eq!(
+            names(&registry, "rules/auth/login.dsl", Language::Other),
+            ["dsl"]
+        );
+        assert_eq!(names(&registry, "rules/main.rs", Language::Rust), ["rules"]);
+        assert_eq!(names(&registry, "src/main.rs", Language::Rust), ["rust"]);
+        assert_eq!(names(&registry, "notes.dsl", Language::Other), ["other"]);
+        // Without a path, only languages apply
+        let chunks = registry.chunk("", Language::Other);
+        assert_eq!(chunks[0].name, "other");
+        // Unregistered languages use the default chunker
+        assert_eq!(
+            names(&registry, "src/main.go", Language::Go),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            names(&ChunkerRegistry::new(), "main.rs", Language::Rust),
+            ["main"]
+        );
+    }
+
+    #[test]
+    fn later_language_replaces_earlier_and_fallback_is_configurable() {
+        let registry = ChunkerRegistry::default()
+            .language(Language::Rust, Named("first"))
+            .language(Language::Rust, Named("second"))
+            .fallback(Named("fallback"));
+        assert_eq!(names(&registry, "lib.rs", Language::Rust), ["second"]);
+        assert_eq!(names(&registry, "main.py", Language::Python), ["fallback"]);
+    }
+
+    #[test]
+    fn invalid_glob_is_an_error() {
+        let err = ChunkerRegistry::new()
+            .glob("rules/[", Named("x"))
+            .err()
+            .unwrap();
+        assert!(matches!(err, TopoError::Pattern { pattern, .. } if pattern == "rules/["));
+    }
+}
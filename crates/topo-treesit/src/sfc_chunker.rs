@@ -0,0 +1,185 @@
+//! Section chunker for Vue and Svelte single-file components.
+//!
+//! Emits a [`ChunkKind::Section`] chunk for each top-level `<template>`,
+//! `<script>`, and `<style>` block, and chunks the script blocks as
+//! JavaScript or TypeScript, so a component's functions are found apart
+//! from its markup and styles. Like the other chunkers it works line by
+//! line: blocks open and close on lines of their own at column 0, as SFC
+//! formatters write them, which tells them from nested `<template>` tags.
+
+use topo_core::{Chunk, ChunkKind, Language};
+
+use crate::{Chunker, RegexChunker};
+
+/// The top-level blocks of a single-file component.
+const SECTIONS: &[&str] = &["template", "script", "style"];
+
+/// Chunker for Vue and Svelte components, see [`SfcChunker::handles`].
+pub struct SfcChunker;
+
+impl SfcChunker {
+    /// Whether `language` is a single-file component language.
+    pub fn handles(language: Language) -> bool {
+        matches!(language, Language::Vue | Language::Svelte)
+    }
+}
+
+impl Chunker for SfcChunker {
+    fn chunk(&self, content: &str, language: Language) -> Vec<Chunk> {
+        if !Self::handles(language) {
+            return Vec::new();
+        }
+        let lines: Vec<&str> = content.lines().collect();
+        let mut chunks = Vec::new();
+        let mut i = 0;
+        while i < lines.len() {
+            let Some((name, open_tag)) = section_start(lines[i]) else {
+                i += 1;
+                continue;
+            };
+            let close = format!("</{name}>");
+            let end = if lines[i].trim_end().ends_with(&close) {
+                i
+            } else {
+                (i + 1..lines.len())
+                    .find(|&j| lines[j].starts_with(&close))
+                    .unwrap_or(lines.len() - 1)
+            };
+            chunks.push(section_chunk(name, (i + 1) as u32, (end + 1) as u32));
+            if name == "script" && end > i + 1 {
+                let script = lines[i + 1..end].join("\n");
+                let offset = (i + 1) as u32;
+                chunks.extend(
+                    RegexChunker
+                        .chunk(&script, script_language(open_tag))
+                        .into_iter()
+                        .map(|mut chunk| {
+                            chunk.start_line += offset;
+                            chunk.end_line += offset;
+                            chunk
+                        }),
+                );
+            }
+            i = end + 1;
+        }
+        chunks
+    }
+}
+
+/// The section `line` opens, and its opening tag.
+fn section_start(line: &str) -> Option<(&'static str, &str)> {
+    let tag = line.strip_prefix('<')?;
+    let tag = &tag[..tag.find('>')?];
+    SECTIONS.iter().copied().find_map(|name| {
+        let attrs = tag.strip_prefix(name)?;
+        (attrs.is_empty() || attrs.starts_with(char::is_whitespace)).then_some((name, tag))
+    })
+}
+
+/// The language of a script block, from its opening tag's `lang`.
+fn script_language(open_tag: &str) -> Language {
+    let typescript = [
+        "lang=\"ts\"",
+        "lang='ts'",
+        "lang=\"typescript\"",
+        "lang='typescript'",
+    ];
+    if typescript.iter().any(|lang| open_tag.contains(lang)) {
+        Language::TypeScript
+    } else {
+        Language::JavaScript
+    }
+}
+
+fn section_chunk(name: &str, start_line: u32, end_line: u32) -> Chunk {
+    Chunk {
+        kind: ChunkKind::Section,
+        name: name.to_string(),
+        start_line,
+        end_line,
+        content: String::new(),
+        target: None,
+        doc: None,
+        parent: None,
+        signature: None,
+        tokens: 0,
+        exported: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(src: &str, language: Language) -> Vec<(ChunkKind, String, u32, u32)> {
+        SfcChunker
+            .chunk(src, language)
+            .into_iter()
+            .map(|c| (c.kind, c.name, c.start_line, c.end_line))
+            .collect()
+    }
+
+    fn chunk(kind: ChunkKind, name: &str, start: u32, end: u32) -> (ChunkKind, String, u32, u32) {
+        (kind, name.to_string(), start, end)
+    }
+
+    #[test]
+    fn vue_sections_and_script() {
+        let src = "\
+<template>
+  <template v-if=\"user\">
+    <p>{{ user.name }}</p>
+  </template>
+</template>
+
+<script setup lang=\"ts\">
+import { ref } from 'vue'
+interface User { name: string }
+function login(user: User) {}
+</script>
+
+<style scoped>
+.login { color: red; }
+</style>
+";
+        assert_eq!(
+            found(src, Language::Vue),
+            [
+                chunk(ChunkKind::Section, "template", 1, 5),
+                chunk(ChunkKind::Section, "script", 7, 11),
+                chunk(ChunkKind::Import, "import { ref } from 'vue'", 8, 8),
+                chunk(ChunkKind::Trait, "User", 9, 9),
+                chunk(ChunkKind::Function, "login", 10, 10),
+                chunk(ChunkKind::Section, "style", 13, 15),
+            ]
+        );
+    }
+
+    #[test]
+    fn svelte_script_and_style() {
+        let src = "\
+<script>
+  export function logout() {}
+</script>
+
+<button on:click={logout}>Log out</button>
+<style>button { margin: 0; }</style>
+";
+        assert_eq!(
+            found(src, Language::Svelte),
+            [
+                chunk(ChunkKind::Section, "script", 1, 3),
+                chunk(ChunkKind::Function, "logout", 2, 2),
+                chunk(ChunkKind::Section, "style", 6, 6),
+            ]
+        );
+    }
+
+    #[test]
+    fn script_language_from_lang() {
+        assert_eq!(script_language("script lang='ts'"), Language::TypeScript);
+        assert_eq!(script_language("script setup"), Language::JavaScript);
+        assert!(section_start("<scripts>").is_none());
+        assert!(SfcChunker.chunk("<script>", Language::Html).is_empty());
+    }
+}
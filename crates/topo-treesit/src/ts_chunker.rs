@@ -11,6 +11,7 @@ use tree_sitter::{Parser, Query, QueryCursor, StreamingIterator};
 
 use crate::Chunker;
 use crate::queries;
+use crate::regex_chunker::{is_test, type_kind};
 
 struct GrammarEntry {
     language: tree_sitter::Language,
@@ -19,6 +20,9 @@ struct GrammarEntry {
     type_idx: Option<u32>,
     impl_idx: Option<u32>,
     import_idx: Option<u32>,
+    constant_idx: Option<u32>,
+    macro_idx: Option<u32>,
+    test_idx: Option<u32>,
     name_idx: Option<u32>,
 }
 
@@ -47,6 +51,7 @@ impl Chunker for TreeSitterChunker {
         let mut cursor = QueryCursor::new();
         let mut matches = cursor.matches(&entry.query, tree.root_node(), content.as_bytes());
         let mut chunks = Vec::new();
+        let lines: Vec<&str> = content.lines().collect();
 
         while let Some(m) = matches.next() {
             let mut outer_node = None;
@@ -68,6 +73,15 @@ impl Chunker for TreeSitterChunker {
                 } else if entry.import_idx == Some(capture.index) {
                     outer_node = Some(capture.node);
                     kind = ChunkKind::Import;
+                } else if entry.constant_idx == Some(capture.index) {
+                    outer_node = Some(capture.node);
+                    kind = ChunkKind::Constant;
+                } else if entry.macro_idx == Some(capture.index) {
+                    outer_node = Some(capture.node);
+                    kind = ChunkKind::Macro;
+                } else if entry.test_idx == Some(capture.index) {
+                    outer_node = Some(capture.node);
+                    kind = ChunkKind::TestCase;
                 }
             }
 
@@ -76,10 +90,32 @@ impl Chunker for TreeSitterChunker {
                 None => continue,
             };
 
-            let name = name_node
-                .and_then(|n| n.utf8_text(content.as_bytes()).ok())
-                .unwrap_or("")
-                .to_string();
+            let name = match name_node {
+                Some(n) => n.utf8_text(content.as_bytes()).unwrap_or("").to_string(),
+                None if kind == ChunkKind::Import => {
+                    import_statement(node.utf8_text(content.as_bytes()).unwrap_or(""), language)
+                }
+                None => String::new(),
+            };
+            // The line declaring the name, below any attributes
+            let row = name_node.map_or(node.start_position().row, |n| n.start_position().row);
+            let line = lines.get(row).copied().unwrap_or_default();
+            let (kind, name, parent) = match kind {
+                ChunkKind::Function => {
+                    let (kind, name, parent) = place_function(node, name, content);
+                    if is_test(&name, line, &lines[..row.min(lines.len())], language) {
+                        (ChunkKind::TestCase, name, parent)
+                    } else {
+                        (kind, name, parent)
+                    }
+                }
+                ChunkKind::Type => (type_kind(line, language), name, None),
+                ChunkKind::TestCase => {
+                    let description = name.trim_matches(|c| matches!(c, '"' | '\'' | '`'));
+                    (kind, description.to_string(), None)
+                }
+                kind => (kind, name, None),
+            };
 
             let start_line = node.start_position().row as u32 + 1;
             let end_line = node.end_position().row as u32 + 1;
@@ -93,6 +129,12 @@ impl Chunker for TreeSitterChunker {
                 start_line,
                 end_line,
                 content: node_content,
+                target: None,
+                doc: None,
+                parent,
+                signature: None,
+                tokens: 0,
+                exported: false,
             });
         }
 
@@ -100,6 +142,119 @@ impl Chunker for TreeSitterChunker {
     }
 }
 
+/// An import node's text on one line. Go import specs inside an
+/// `import ( ... )` block get their own `import` keyword.
+fn import_statement(text: &str, language: Language) -> String {
+    let statement = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if language == Language::Go && !statement.starts_with("import") {
+        format!("import {statement}")
+    } else {
+        statement
+    }
+}
+
+/// Nodes whose functions are methods of them.
+const CONTAINER_KINDS: &[&str] = &[
+    "impl_item",
+    "trait_item",
+    "class_declaration",
+    "class_definition",
+    "class_specifier",
+    "struct_specifier",
+    "struct_declaration",
+    "interface_declaration",
+    "trait_declaration",
+    "trait_definition",
+    "protocol_declaration",
+    "object_declaration",
+    "object_definition",
+    "enum_declaration",
+    "class",
+    "module",
+];
+
+/// Nodes whose nested functions are local to them, not methods.
+const FUNCTION_KINDS: &[&str] = &[
+    "function_item",
+    "function_definition",
+    "function_declaration",
+    "method_declaration",
+    "method_definition",
+    "constructor_declaration",
+    "method",
+    "singleton_method",
+    "arrow_function",
+    "closure_expression",
+    "lambda",
+];
+
+/// The kind, name, and parent of a function: one declared directly in a
+/// type, trait, or impl, defined as `Type::name`, or with a Go receiver is
+/// a method of it.
+fn place_function(
+    node: tree_sitter::Node,
+    name: String,
+    content: &str,
+) -> (ChunkKind, String, Option<String>) {
+    if let Some((parent, method)) = name.rsplit_once("::") {
+        return (
+            ChunkKind::Method,
+            method.to_string(),
+            Some(parent.to_string()),
+        );
+    }
+    let parent = match node.child_by_field_name("receiver") {
+        Some(receiver) => go_receiver(text(receiver, content)),
+        None => enclosing_type(node, content),
+    };
+    match parent {
+        Some(parent) => (ChunkKind::Method, name, Some(parent)),
+        None => (ChunkKind::Function, name, None),
+    }
+}
+
+/// Name of the type, trait, or impl `node` is declared in, unless a
+/// function comes between them.
+fn enclosing_type(node: tree_sitter::Node, content: &str) -> Option<String> {
+    let mut ancestor = node.parent();
+    while let Some(current) = ancestor {
+        let kind = current.kind();
+        if FUNCTION_KINDS.contains(&kind) {
+            return None;
+        }
+        if CONTAINER_KINDS.contains(&kind) {
+            // An impl is named by the type it is for
+            let field = if kind == "impl_item" { "type" } else { "name" };
+            let name = current.child_by_field_name(field).or_else(|| {
+                let mut cursor = current.walk();
+                current
+                    .named_children(&mut cursor)
+                    .find(|child| child.kind().ends_with("identifier"))
+            })?;
+            return Some(type_name(text(name, content)));
+        }
+        ancestor = current.parent();
+    }
+    None
+}
+
+/// The receiver type of a Go method: `Server` for `(s *Server)`.
+fn go_receiver(receiver: &str) -> Option<String> {
+    let receiver = receiver.trim_matches(|c| c == '(' || c == ')');
+    let ty = receiver.split_whitespace().last()?.trim_start_matches('*');
+    Some(type_name(ty)).filter(|name| !name.is_empty())
+}
+
+/// A type's bare name: `Cache` for `crate::store::Cache<T>` or `Cache[T]`.
+fn type_name(ty: &str) -> String {
+    let ty = ty.split(['<', '[']).next().unwrap_or(ty).trim();
+    ty.rsplit("::").next().unwrap_or(ty).to_string()
+}
+
+fn text<'a>(node: tree_sitter::Node, content: &'a str) -> &'a str {
+    node.utf8_text(content.as_bytes()).unwrap_or("")
+}
+
 /// Initialize all grammar entries.
 fn init_grammars() -> HashMap<Language, GrammarEntry> {
     let mut map = HashMap::new();
@@ -147,6 +302,9 @@ fn init_grammars() -> HashMap<Language, GrammarEntry> {
         let type_idx = capture_index(&query, "type");
         let impl_idx = capture_index(&query, "impl");
         let import_idx = capture_index(&query, "import");
+        let constant_idx = capture_index(&query, "constant");
+        let macro_idx = capture_index(&query, "macro");
+        let test_idx = capture_index(&query, "test");
         let name_idx = capture_index(&query, "name");
 
         map.insert(
@@ -158,6 +316,9 @@ fn init_grammars() -> HashMap<Language, GrammarEntry> {
                 type_idx,
                 impl_idx,
                 import_idx,
+                constant_idx,
+                macro_idx,
+                test_idx,
                 name_idx,
             },
         );
@@ -215,7 +376,10 @@ use std::collections::HashMap;
             .collect();
         assert!(!fn_chunks.is_empty(), "should find functions");
         assert!(fn_chunks.iter().any(|c| c.name == "authenticate"));
-        assert!(fn_chunks.iter().any(|c| c.name == "new"));
+        assert!(!fn_chunks.iter().any(|c| c.name == "new"));
+        let new = chunks.iter().find(|c| c.name == "new").unwrap();
+        assert_eq!(new.kind, ChunkKind::Method);
+        assert_eq!(new.qualified_name(), "Config::new");
 
         // Functions should have multi-line spans
         let auth = fn_chunks.iter().find(|c| c.name == "authenticate").unwrap();
@@ -231,7 +395,11 @@ use std::collections::HashMap;
             .filter(|c| c.kind == ChunkKind::Type)
             .collect();
         assert!(type_chunks.iter().any(|c| c.name == "Config"));
-        assert!(type_chunks.iter().any(|c| c.name == "Status"));
+        assert!(
+            chunks
+                .iter()
+                .any(|c| c.name == "Status" && c.kind == ChunkKind::Enum)
+        );
 
         assert!(chunks.iter().any(|c| c.kind == ChunkKind::Impl));
         assert!(chunks.iter().any(|c| c.kind == ChunkKind::Import));
@@ -260,7 +428,8 @@ from pathlib import Path
         assert!(
             chunks
                 .iter()
-                .any(|c| c.name == "authenticate" && c.kind == ChunkKind::Function)
+                .any(|c| c.qualified_name() == "UserService::authenticate"
+                    && c.kind == ChunkKind::Method)
         );
         assert!(
             chunks
@@ -333,6 +502,149 @@ import { useState } from 'react';
         assert!(chunks.iter().any(|c| c.kind == ChunkKind::Import));
     }
 
+    #[test]
+    fn imports_are_named_by_their_statement() {
+        let imports = |src: &str, language| -> Vec<String> {
+            TreeSitterChunker
+                .chunk(src, language)
+                .into_iter()
+                .filter(|c| c.kind == ChunkKind::Import)
+                .map(|c| c.name)
+                .collect()
+        };
+        assert_eq!(
+            imports(
+                "mod auth;\nmod tests {}\nuse crate::auth::{\n    Token,\n};\n",
+                Language::Rust
+            ),
+            ["mod auth;", "use crate::auth::{ Token, };"]
+        );
+        assert_eq!(
+            imports(
+                "package main\n\nimport (\n\t\"fmt\"\n\tdb \"example.com/app/db\"\n)\n",
+                Language::Go
+            ),
+            ["import \"fmt\"", "import db \"example.com/app/db\""]
+        );
+    }
+
+    #[test]
+    fn methods_name_their_parent() {
+        let functions = |src: &str, language| -> Vec<(ChunkKind, String)> {
+            TreeSitterChunker
+                .chunk(src, language)
+                .into_iter()
+                .filter(|c| matches!(c.kind, ChunkKind::Function | ChunkKind::Method))
+                .map(|c| (c.kind, c.qualified_name()))
+                .collect()
+        };
+        let method = |name: &str| (ChunkKind::Method, name.to_string());
+        let function = |name: &str| (ChunkKind::Function, name.to_string());
+
+        assert_eq!(
+            functions(
+                "impl<T> fmt::Display for store::Cache<T> {\n    fn fmt(&self) {\n        fn helper() {}\n    }\n}\nfn free() {}\n",
+                Language::Rust
+            ),
+            [method("Cache::fmt"), function("helper"), function("free")]
+        );
+        assert_eq!(
+            functions(
+                "package main\n\nfunc (s *Server) Handle() {}\n\nfunc main() {}\n",
+                Language::Go
+            ),
+            [method("Server::Handle"), function("main")]
+        );
+        assert_eq!(
+            functions(
+                "class Auth {\n    check(token) {\n        return true;\n    }\n}\n",
+                Language::JavaScript
+            ),
+            [method("Auth::check")]
+        );
+        assert_eq!(
+            functions(
+                "bool Auth::check() {\n    return true;\n}\nint f() { return 0; }\n",
+                Language::Cpp
+            ),
+            [method("Auth::check"), function("f")]
+        );
+    }
+
+    #[test]
+    fn traits_enums_constants_macros_and_tests() {
+        let found = |src: &str, language: Language| -> Vec<(ChunkKind, String)> {
+            TreeSitterChunker
+                .chunk(src, language)
+                .into_iter()
+                .map(|c| (c.kind, c.qualified_name()))
+                .collect()
+        };
+        let kind = |kind: ChunkKind, name: &str| (kind, name.to_string());
+
+        let src = "\
+pub trait Store {}
+pub const LIMIT: usize = 8;
+macro_rules! ensure { () => {}; }
+#[test]
+fn checks_limit() {}
+";
+        assert_eq!(
+            found(src, Language::Rust),
+            [
+                kind(ChunkKind::Trait, "Store"),
+                kind(ChunkKind::Constant, "LIMIT"),
+                kind(ChunkKind::Macro, "ensure"),
+                kind(ChunkKind::TestCase, "checks_limit"),
+            ]
+        );
+
+        let src = "\
+export const API_URL = 'https://example.com';
+export enum Mode { Fast }
+export interface Session {}
+it('logs in', () => {});
+";
+        assert_eq!(
+            found(src, Language::TypeScript),
+            [
+                kind(ChunkKind::Constant, "API_URL"),
+                kind(ChunkKind::Enum, "Mode"),
+                kind(ChunkKind::Trait, "Session"),
+                kind(ChunkKind::TestCase, "logs in"),
+            ]
+        );
+
+        let src = "MAX_RETRIES = 3\nretries = 0\ndef test_login():\n    pass\n";
+        assert_eq!(
+            found(src, Language::Python),
+            [
+                kind(ChunkKind::Constant, "MAX_RETRIES"),
+                kind(ChunkKind::TestCase, "test_login"),
+            ]
+        );
+
+        let src = "#define MAX_LEN 64\nenum mode { FAST };\n";
+        assert_eq!(
+            found(src, Language::C),
+            [
+                kind(ChunkKind::Macro, "MAX_LEN"),
+                kind(ChunkKind::Enum, "mode"),
+            ]
+        );
+
+        // Adding captures mustn't break any grammar's query
+        for language in [
+            Language::Go,
+            Language::Java,
+            Language::Ruby,
+            Language::Cpp,
+            Language::Php,
+        ] {
+            assert!(GRAMMARS.contains_key(&language), "{language:?}");
+        }
+    }
+
     #[test]
     fn unsupported_language_returns_empty() {
         let chunks = TreeSitterChunker.chunk("# heading\nsome text", Language::Markdown);
@@ -0,0 +1,112 @@
+//! Sliding-window fallback chunker.
+//!
+//! Splits content into fixed-size, overlapping windows of lines, each a
+//! [`ChunkKind::Other`] chunk named by its line range, so files no other
+//! chunker understands still get chunk-level granularity.
+
+use topo_core::{Chunk, ChunkKind, Language};
+
+use crate::Chunker;
+
+/// Lines per window by default.
+pub const WINDOW_LINES: usize = 50;
+
+/// Lines each window shares with the next by default.
+pub const WINDOW_OVERLAP: usize = 10;
+
+/// Chunker of overlapping line windows, for any language.
+pub struct WindowChunker {
+    lines: usize,
+    overlap: usize,
+}
+
+impl WindowChunker {
+    /// Windows of `lines` lines, each sharing `overlap` lines with the
+    /// next. The overlap is capped below the window size.
+    pub fn new(lines: usize, overlap: usize) -> Self {
+        let lines = lines.max(1);
+        Self {
+            lines,
+            overlap: overlap.min(lines - 1),
+        }
+    }
+}
+
+impl Default for WindowChunker {
+    fn default() -> Self {
+        Self::new(WINDOW_LINES, WINDOW_OVERLAP)
+    }
+}
+
+impl Chunker for WindowChunker {
+    fn chunk(&self, content: &str, _language: Language) -> Vec<Chunk> {
+        let total = content.lines().count();
+        let step = self.lines - self.overlap;
+        let mut chunks = Vec::new();
+        let mut start = 1;
+        while start <= total {
+            let end = (start + self.lines - 1).min(total);
+            chunks.push(Chunk {
+                kind: ChunkKind::Other,
+                name: format!("lines {start}-{end}"),
+                start_line: start as u32,
+                end_line: end as u32,
+                content: String::new(),
+                target: None,
+                doc: None,
+                parent: None,
+                signature: None,
+                tokens: 0,
+                exported: false,
+            });
+            if end == total {
+                break;
+            }
+            start += step;
+        }
+        chunks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(chunker: &WindowChunker, lines: usize) -> Vec<(String, u32, u32)> {
+        let content: String = (1..=lines).map(|i| format!("line {i}\n")).collect();
+        chunker
+            .chunk(&content, Language::Other)
+            .into_iter()
+            .map(|c| (c.name, c.start_line, c.end_line))
+            .collect()
+    }
+
+    fn span(start: u32, end: u32) -> (String, u32, u32) {
+        (format!("lines {start}-{end}"), start, end)
+    }
+
+    #[test]
+    fn overlapping_windows_cover_every_line() {
+        let chunker = WindowChunker::new(10, 3);
+        assert_eq!(
+            spans(&chunker, 24),
+            [span(1, 10), span(8, 17), span(15, 24)]
+        );
+        assert_eq!(
+            spans(&chunker, 25),
+            [span(1, 10), span(8, 17), span(15, 24), span(22, 25)]
+        );
+    }
+
+    #[test]
+    fn short_and_empty_content() {
+        let chunker = WindowChunker::default();
+        assert_eq!(spans(&chunker, 3), [span(1, 3)]);
+        assert!(spans(&chunker, 0).is_empty());
+        // An overlap as large as the window still advances
+        assert_eq!(
+            spans(&WindowChunker::new(2, 5), 3),
+            [span(1, 2), span(2, 3)]
+        );
+    }
+}
@@ -0,0 +1,504 @@
+use crate::vcs::{BlameLine, ChangeStatus, Commit, FileChange, LineCounts, LogOptions, Vcs};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use topo_core::TopoError;
+
+/// Record separator between commits in `git log` output.
+const RECORD: char = '\x1e';
+/// Field separator within a commit header.
+const FIELD: char = '\x1f';
+
+/// Git backend that shells out to the `git` executable.
+pub struct GitCli {
+    root: PathBuf,
+}
+
+impl GitCli {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Run git in the working tree and return stdout.
+    ///
+    /// Failing to spawn git is an I/O error; a non-zero exit (not a repo,
+    /// no commits yet, unknown revision) is a [`TopoError::Vcs`].
+    fn run(&self, args: &[&str]) -> Result<Vec<u8>, TopoError> {
+        let output = Command::new("git")
+            .args(["-c", "core.quotePath=false"])
+            .args(args)
+            .current_dir(&self.root)
+            .output()
+            .map_err(|e| match e.kind() {
+                // Without a git binary the repository has no readable
+                // history, which callers already handle
+                std::io::ErrorKind::NotFound => TopoError::Vcs {
+                    path: Some(self.root.clone()),
+                    message: "git is not installed".to_string(),
+                },
+                _ => TopoError::io(&self.root, e),
+            })?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(TopoError::Vcs {
+                path: Some(self.root.clone()),
+                message: format!(
+                    "git {} failed: {}",
+                    args.first().unwrap_or(&""),
+                    stderr.lines().next().unwrap_or("").trim()
+                ),
+            });
+        }
+        Ok(output.stdout)
+    }
+}
+
+impl Vcs for GitCli {
+    fn name(&self) -> &'static str {
+        "git"
+    }
+
+    fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn log(&self, options: &LogOptions) -> Result<Vec<Commit>, TopoError> {
+        let format = format!("--format={RECORD}%H{FIELD}%ae{FIELD}%at");
+        let mut args = vec!["log".to_string(), format, "-M".to_string()];
+        if options.line_counts {
+            // `--name-status` and `--numstat` don't combine, `--raw` does
+            args.extend(["--raw", "--numstat", "--no-abbrev"].map(String::from));
+        } else {
+            args.push("--name-status".to_string());
+        }
+        if let Some(days) = options.since_days {
+            args.push(format!("--since={days}.days"));
+        }
+        if let Some(max) = options.max_commits {
+            args.push(format!("--max-count={max}"));
+        }
+        if let Some(id) = &options.since_commit {
+            args.push(format!("{id}..HEAD"));
+        }
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let stdout = self.run(&args)?;
+        Ok(parse_log(&String::from_utf8_lossy(&stdout)))
+    }
+
+    fn diff(&self, base: &str) -> Result<Vec<FileChange>, TopoError> {
+        let stdout = self.run(&["diff", "--name-status", "-M", base, "--"])?;
+        Ok(String::from_utf8_lossy(&stdout)
+            .lines()
+            .filter_map(parse_name_status)
+            .collect())
+    }
+
+    fn status(&self) -> Result<Vec<FileChange>, TopoError> {
+        let stdout = self.run(&["status", "--porcelain=v1", "-z", "--untracked-files=all"])?;
+        Ok(parse_status(&String::from_utf8_lossy(&stdout)))
+    }
+
+    fn blame(&self, path: &str) -> Result<Vec<BlameLine>, TopoError> {
+        let stdout = self.run(&["blame", "--line-porcelain", "--", path])?;
+        Ok(parse_blame(&String::from_utf8_lossy(&stdout)))
+    }
+
+    fn read_object(&self, rev: &str, path: &str) -> Result<Option<Vec<u8>>, TopoError> {
+        match self.run(&["cat-file", "blob", &format!("{rev}:{path}")]) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(TopoError::Vcs { .. }) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn user_email(&self) -> Result<Option<String>, TopoError> {
+        // `git config` exits non-zero when the key is unset
+        match self.run(&["config", "user.email"]) {
+            Ok(bytes) => Ok(Some(String::from_utf8_lossy(&bytes).trim().to_string())
+                .filter(|email| !email.is_empty())),
+            Err(TopoError::Vcs { .. }) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn branch(&self) -> Result<Option<String>, TopoError> {
+        // `symbolic-ref --quiet` exits non-zero on a detached HEAD
+        match self.run(&["symbolic-ref", "--quiet", "--short", "HEAD"]) {
+            Ok(bytes) => Ok(Some(String::from_utf8_lossy(&bytes).trim().to_string())
+                .filter(|branch| !branch.is_empty())),
+            Err(TopoError::Vcs { .. }) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn sparse_paths(&self) -> Result<Option<Vec<String>>, TopoError> {
+        // `git config` exits non-zero when the key is unset
+        match self.run(&["config", "--bool", "core.sparseCheckout"]) {
+            Ok(bytes) if String::from_utf8_lossy(&bytes).trim() == "true" => {}
+            Ok(_) | Err(TopoError::Vcs { .. }) => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        let stdout = self.run(&["ls-files", "-t", "-z"])?;
+        Ok(Some(parse_skip_worktree(&String::from_utf8_lossy(&stdout))))
+    }
+}
+
+/// Paths of skip-worktree entries (tag `S`) in `git ls-files -t -z` output.
+fn parse_skip_worktree(files: &str) -> Vec<String> {
+    files
+        .split('\0')
+        .filter_map(|entry| entry.strip_prefix("S "))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Parse `git log --name-status` output produced with the record format
+/// `RECORD %H FIELD %ae FIELD %at`.
+fn parse_log(log: &str) -> Vec<Commit> {
+    log.split(RECORD)
+        .filter_map(|record| {
+            let mut lines = record.lines();
+            let mut header = lines.next()?.split(FIELD);
+            let id = header.next().filter(|id| !id.is_empty())?.to_string();
+            let author_email = header.next().unwrap_or("").to_string();
+            let timestamp = header.next().and_then(|t| t.parse().ok()).unwrap_or(0);
+            let mut changes = Vec::new();
+            let mut counts = Vec::new();
+            for line in lines {
+                if let Some(raw) = line.strip_prefix(':') {
+                    changes.extend(parse_raw(raw));
+                } else if line.starts_with(|c: char| c.is_ascii_digit() || c == '-') {
+                    counts.push(parse_numstat(line));
+                } else {
+                    changes.extend(parse_name_status(line));
+                }
+            }
+            // `--numstat` lists the same files in the same order as `--raw`
+            for (change, lines) in changes.iter_mut().zip(counts) {
+                change.lines = lines;
+            }
+            Some(Commit {
+                id,
+                author_email,
+                timestamp,
+                changes,
+            })
+        })
+        .collect()
+}
+
+/// Parse one `--raw` line without its leading `:`, e.g.
+/// `100644 100644 <old> <new> M\tsrc/a.rs`.
+fn parse_raw(line: &str) -> Option<FileChange> {
+    let (meta, paths) = line.split_once('\t')?;
+    let status = meta.rsplit(' ').next()?;
+    parse_name_status(&format!("{status}\t{paths}"))
+}
+
+/// Parse one `--numstat` line, e.g. `3\t1\tsrc/a.rs`. Binary files
+/// (`-\t-`) have no line counts.
+fn parse_numstat(line: &str) -> Option<LineCounts> {
+    let mut fields = line.split('\t');
+    Some(LineCounts {
+        added: fields.next()?.parse().ok()?,
+        removed: fields.next()?.parse().ok()?,
+    })
+}
+
+/// Parse one `--name-status` line, e.g. `M\tsrc/a.rs` or `R095\told\tnew`.
+fn parse_name_status(line: &str) -> Option<FileChange> {
+    let mut fields = line.trim_end().split('\t');
+    let status = fields.next().filter(|s| !s.is_empty())?;
+    let change = match status.as_bytes()[0] {
+        b'R' | b'C' => {
+            let (from, to) = (fields.next()?, fields.next()?);
+            let kind = if status.starts_with('R') {
+                ChangeStatus::Renamed
+            } else {
+                ChangeStatus::Copied
+            };
+            FileChange::moved(kind, from, to)
+        }
+        b'A' => FileChange::new(ChangeStatus::Added, fields.next()?),
+        b'D' => FileChange::new(ChangeStatus::Deleted, fields.next()?),
+        // M, T (type change), U (unmerged) and anything newer
+        _ => FileChange::new(ChangeStatus::Modified, fields.next()?),
+    };
+    Some(change)
+}
+
+/// Parse `git status --porcelain=v1 -z` output.
+fn parse_status(status: &str) -> Vec<FileChange> {
+    let mut changes = Vec::new();
+    let mut entries = status.split('\0').filter(|e| !e.is_empty());
+    while let Some(entry) = entries.next() {
+        let Some((xy, path)) = entry.split_at_checked(2) else {
+            continue;
+        };
+        let path = path.trim_start();
+        let change = if xy == "??" {
+            FileChange::new(ChangeStatus::Untracked, path)
+        } else if xy.contains('R') || xy.contains('C') {
+            // Renames and copies are followed by the source path
+            let Some(from) = entries.next() else {
+                continue;
+            };
+            let kind = if xy.contains('R') {
+                ChangeStatus::Renamed
+            } else {
+                ChangeStatus::Copied
+            };
+            FileChange::moved(kind, from, path)
+        } else if xy.contains('D') {
+            FileChange::new(ChangeStatus::Deleted, path)
+        } else if xy.contains('A') {
+            FileChange::new(ChangeStatus::Added, path)
+        } else {
+            FileChange::new(ChangeStatus::Modified, path)
+        };
+        changes.push(change);
+    }
+    changes
+}
+
+/// Parse `git blame --line-porcelain` output.
+fn parse_blame(blame: &str) -> Vec<BlameLine> {
+    let mut lines = Vec::new();
+    let mut current: Option<BlameLine> = None;
+    for line in blame.lines() {
+        if line.starts_with('\t') {
+            // Line content ends the record
+            lines.extend(current.take());
+        } else if let Some(email) = line.strip_prefix("author-mail ") {
+            if let Some(blame) = current.as_mut() {
+                blame.author_email = email.trim_matches(|c| c == '<' || c == '>').to_string();
+            }
+        } else if let Some(time) = line.strip_prefix("author-time ") {
+            if let Some(blame) = current.as_mut() {
+                blame.timestamp = time.trim().parse().unwrap_or(0);
+            }
+        } else if current.is_none() {
+            // Header: <sha> <original line> <final line> [<group size>]
+            let mut fields = line.split(' ');
+            let (Some(commit), _, Some(final_line)) = (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            let Ok(final_line) = final_line.parse() else {
+                continue;
+            };
+            current = Some(BlameLine {
+                line: final_line,
+                commit: commit.to_string(),
+                author_email: String::new(),
+                timestamp: 0,
+            });
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    fn init_repo(dir: &Path) {
+        git(dir, &["init", "-q"]);
+        git(dir, &["config", "user.email", "dev@example.com"]);
+        git(dir, &["config", "user.name", "Dev"]);
+    }
+
+    #[test]
+    fn parses_log_records() {
+        let log = format!(
+            "{RECORD}abc{FIELD}a@x.com{FIELD}1700000000\n\nM\tsrc/a.rs\nR095\told.rs\tnew.rs\n\
+             {RECORD}def{FIELD}b@x.com{FIELD}1600000000\n\nA\tsrc/a.rs\n"
+        );
+        let commits = parse_log(&log);
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].id, "abc");
+        assert_eq!(commits[0].author_email, "a@x.com");
+        assert_eq!(commits[0].timestamp, 1_700_000_000);
+        assert_eq!(
+            commits[0].changes,
+            vec![
+                FileChange::new(ChangeStatus::Modified, "src/a.rs"),
+                FileChange::moved(ChangeStatus::Renamed, "old.rs", "new.rs"),
+            ]
+        );
+        assert_eq!(
+            commits[1].changes,
+            vec![FileChange::new(ChangeStatus::Added, "src/a.rs")]
+        );
+    }
+
+    #[test]
+    fn parses_log_records_with_line_counts() {
+        let sha = "0".repeat(40);
+        let log = format!(
+            "{RECORD}abc{FIELD}a@x.com{FIELD}1700000000\n\n\
+             :100644 100644 {sha} {sha} M\tsrc/a.rs\n\
+             :100644 100644 {sha} {sha} R090\told.rs\tnew.rs\n\
+             :000000 100644 {sha} {sha} A\tlogo.png\n\
+             3\t1\tsrc/a.rs\n1\t1\told.rs => new.rs\n-\t-\tlogo.png\n"
+        );
+        let commits = parse_log(&log);
+        assert_eq!(commits.len(), 1);
+        let changes = &commits[0].changes;
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes[0].path, "src/a.rs");
+        assert_eq!(
+            changes[0].lines,
+            Some(LineCounts {
+                added: 3,
+                removed: 1
+            })
+        );
+        assert_eq!(changes[1].status, ChangeStatus::Renamed);
+        assert_eq!(changes[1].old_path.as_deref(), Some("old.rs"));
+        assert_eq!(changes[1].lines.map(|l| l.added), Some(1));
+        assert_eq!(changes[2].status, ChangeStatus::Added);
+        assert!(changes[2].lines.is_none());
+    }
+
+    #[test]
+    fn parses_porcelain_status() {
+        let status = " M src/a.rs\0R  new.rs\0old.rs\0?? notes.txt\0D  gone.rs\0A  added.rs\0";
+        assert_eq!(
+            parse_status(status),
+            vec![
+                FileChange::new(ChangeStatus::Modified, "src/a.rs"),
+                FileChange::moved(ChangeStatus::Renamed, "old.rs", "new.rs"),
+                FileChange::new(ChangeStatus::Untracked, "notes.txt"),
+                FileChange::new(ChangeStatus::Deleted, "gone.rs"),
+                FileChange::new(ChangeStatus::Added, "added.rs"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_line_porcelain_blame() {
+        let blame = "aaaa 1 1 2\nauthor Dev\nauthor-mail <dev@example.com>\nauthor-time 100\nfilename a.rs\n\tfn a() {}\n\
+                     aaaa 2 2\nauthor Dev\nauthor-mail <dev@example.com>\nauthor-time 100\nfilename a.rs\n\t}\n";
+        let lines = parse_blame(blame);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].line, 2);
+        assert_eq!(lines[1].commit, "aaaa");
+        assert_eq!(lines[1].author_email, "dev@example.com");
+        assert_eq!(lines[1].timestamp, 100);
+    }
+
+    #[test]
+    fn parses_skip_worktree_entries() {
+        let files = "H src/a.rs\0S docs/guide.md\0S docs/with space.md\0";
+        assert_eq!(
+            parse_skip_worktree(files),
+            ["docs/guide.md", "docs/with space.md"]
+        );
+    }
+
+    #[test]
+    fn reports_paths_outside_a_sparse_checkout() {
+        let dir = tempfile::tempdir().unwrap();
+        init_repo(dir.path());
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::create_dir_all(dir.path().join("docs")).unwrap();
+        fs::write(dir.path().join("src/a.rs"), "fn a() {}\n").unwrap();
+        fs::write(dir.path().join("docs/guide.md"), "# Guide\n").unwrap();
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "-q", "-m", "init"]);
+
+        let vcs = GitCli::new(dir.path());
+        assert_eq!(vcs.sparse_paths().unwrap(), None);
+
+        git(dir.path(), &["sparse-checkout", "set", "src"]);
+        assert!(!dir.path().join("docs/guide.md").exists());
+        assert_eq!(
+            vcs.sparse_paths().unwrap(),
+            Some(vec!["docs/guide.md".to_string()])
+        );
+        assert_eq!(
+            vcs.read_object("HEAD", "docs/guide.md").unwrap().as_deref(),
+            Some(b"# Guide\n".as_slice())
+        );
+    }
+
+    #[test]
+    fn git_backend_against_real_repo() {
+        let dir = tempfile::tempdir().unwrap();
+        init_repo(dir.path());
+        fs::write(dir.path().join("a.rs"), "fn a() {}\n").unwrap();
+        git(dir.path(), &["add", "a.rs"]);
+        git(dir.path(), &["commit", "-q", "-m", "add a"]);
+        git(dir.path(), &["mv", "a.rs", "b.rs"]);
+        git(dir.path(), &["commit", "-q", "-m", "move a"]);
+        fs::write(dir.path().join("new.rs"), "fn n() {}\n").unwrap();
+
+        let vcs = GitCli::new(dir.path());
+        let commits = vcs.log(&LogOptions::new().since_days(90)).unwrap();
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].author_email, "dev@example.com");
+        assert_eq!(
+            commits[0].changes,
+            vec![FileChange::moved(ChangeStatus::Renamed, "a.rs", "b.rs")]
+        );
+        assert_eq!(vcs.log(&LogOptions::new().max_commits(1)).unwrap().len(), 1);
+        let since = LogOptions::new().since_commit(&commits[1].id);
+        assert_eq!(vcs.log(&since).unwrap(), commits[..1]);
+        let counted = vcs.log(&LogOptions::new().line_counts(true)).unwrap();
+        assert_eq!(
+            counted[1].changes[0].lines,
+            Some(LineCounts {
+                added: 1,
+                removed: 0
+            })
+        );
+
+        assert_eq!(
+            vcs.status().unwrap(),
+            vec![FileChange::new(ChangeStatus::Untracked, "new.rs")]
+        );
+        assert_eq!(
+            vcs.diff("HEAD~1").unwrap(),
+            vec![FileChange::moved(ChangeStatus::Renamed, "a.rs", "b.rs")]
+        );
+
+        let blame = vcs.blame("b.rs").unwrap();
+        assert_eq!(blame.len(), 1);
+        assert_eq!(blame[0].commit, commits[1].id);
+
+        assert_eq!(
+            vcs.read_object("HEAD~1", "a.rs").unwrap().as_deref(),
+            Some(b"fn a() {}\n".as_slice())
+        );
+        assert!(vcs.read_object("HEAD", "a.rs").unwrap().is_none());
+        assert_eq!(
+            vcs.user_email().unwrap().as_deref(),
+            Some("dev@example.com")
+        );
+
+        git(dir.path(), &["checkout", "-q", "-b", "feature/auth"]);
+        assert_eq!(vcs.branch().unwrap().as_deref(), Some("feature/auth"));
+        git(dir.path(), &["checkout", "-q", "--detach"]);
+        assert!(vcs.branch().unwrap().is_none());
+    }
+
+    #[test]
+    fn empty_repo_log_is_a_vcs_error() {
+        let dir = tempfile::tempdir().unwrap();
+        init_repo(dir.path());
+        let err = GitCli::new(dir.path()).log(&LogOptions::new()).unwrap_err();
+        assert!(matches!(err, TopoError::Vcs { .. }));
+    }
+}
@@ -0,0 +1,394 @@
+use crate::git::GitCli;
+use crate::vcs::{BlameLine, ChangeStatus, Commit, FileChange, LineCounts, LogOptions, Vcs};
+use gix::object::tree::diff::ChangeDetached;
+use gix::prelude::TreeDiffChangeExt;
+use gix::revision::walk::Sorting;
+use gix::traverse::commit::simple::CommitTimeOrder;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use topo_core::TopoError;
+
+/// In-process git backend built on gix.
+///
+/// History and object reads run without spawning processes. Diff, status,
+/// blame, and sparse checkouts still go through [`GitCli`].
+pub struct Gix {
+    repo: gix::ThreadSafeRepository,
+    root: PathBuf,
+    cli: GitCli,
+}
+
+impl Gix {
+    /// Open the repository containing `root`.
+    pub fn discover(root: impl Into<PathBuf>) -> Result<Self, TopoError> {
+        let root = root.into();
+        let repo = gix::ThreadSafeRepository::discover(&root).map_err(|e| vcs_error(&root, e))?;
+        Ok(Self {
+            repo,
+            cli: GitCli::new(&root),
+            root,
+        })
+    }
+
+    fn error(&self, e: impl std::fmt::Display) -> TopoError {
+        vcs_error(&self.root, e)
+    }
+}
+
+impl Vcs for Gix {
+    fn name(&self) -> &'static str {
+        "gix"
+    }
+
+    fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn log(&self, options: &LogOptions) -> Result<Vec<Commit>, TopoError> {
+        let repo = self.repo.to_thread_local();
+        let head = repo.head_id().map_err(|e| self.error(e))?;
+
+        let order = CommitTimeOrder::NewestFirst;
+        let sorting = match options.since_days {
+            Some(days) => Sorting::ByCommitTimeCutoff {
+                order,
+                seconds: now_secs() - i64::from(days) * 24 * 60 * 60,
+            },
+            None => Sorting::ByCommitTime(order),
+        };
+        let mut walk = repo.rev_walk([head.detach()]).sorting(sorting);
+        if let Some(id) = &options.since_commit {
+            let id = gix::ObjectId::from_hex(id.as_bytes()).map_err(|e| self.error(e))?;
+            walk = walk.with_hidden([id]);
+        }
+        let walk = walk.all().map_err(|e| self.error(e))?;
+
+        let diff_options = gix::diff::Options::default().with_rewrites(Some(Default::default()));
+        let mut line_cache = if options.line_counts {
+            Some(
+                repo.diff_resource_cache_for_tree_diff()
+                    .map_err(|e| self.error(e))?,
+            )
+        } else {
+            None
+        };
+        let mut commits = Vec::new();
+        for info in walk {
+            if options.max_commits.is_some_and(|max| commits.len() >= max) {
+                break;
+            }
+            let info = info.map_err(|e| self.error(e))?;
+            let commit = info.object().map_err(|e| self.error(e))?;
+            let author = commit.author().map_err(|e| self.error(e))?;
+
+            // Like `git log`, merges are listed without a diff
+            let parents: Vec<_> = info.parent_ids().collect();
+            let changes = if parents.len() > 1 {
+                Vec::new()
+            } else {
+                let tree = commit.tree().map_err(|e| self.error(e))?;
+                let parent_tree = match parents.first() {
+                    Some(parent) => parent
+                        .object()
+                        .map_err(|e| self.error(e))?
+                        .peel_to_tree()
+                        .map_err(|e| self.error(e))?,
+                    None => repo.empty_tree(),
+                };
+                repo.diff_tree_to_tree(&parent_tree, &tree, diff_options)
+                    .map_err(|e| self.error(e))?
+                    .into_iter()
+                    .filter_map(|change| {
+                        let lines = line_cache
+                            .as_mut()
+                            .and_then(|cache| line_counts(&repo, &change, cache));
+                        let mut file_change = file_change(change)?;
+                        file_change.lines = lines;
+                        Some(file_change)
+                    })
+                    .collect()
+            };
+
+            commits.push(Commit {
+                id: info.id.to_string(),
+                author_email: author.email.to_string(),
+                timestamp: u64::try_from(author.seconds()).unwrap_or(0),
+                changes,
+            });
+        }
+        Ok(commits)
+    }
+
+    fn diff(&self, base: &str) -> Result<Vec<FileChange>, TopoError> {
+        self.cli.diff(base)
+    }
+
+    fn status(&self) -> Result<Vec<FileChange>, TopoError> {
+        self.cli.status()
+    }
+
+    fn blame(&self, path: &str) -> Result<Vec<BlameLine>, TopoError> {
+        self.cli.blame(path)
+    }
+
+    fn sparse_paths(&self) -> Result<Option<Vec<String>>, TopoError> {
+        self.cli.sparse_paths()
+    }
+
+    fn read_object(&self, rev: &str, path: &str) -> Result<Option<Vec<u8>>, TopoError> {
+        let repo = self.repo.to_thread_local();
+        let Ok(id) = repo.rev_parse_single(format!("{rev}:{path}").as_str()) else {
+            return Ok(None);
+        };
+        let object = id.object().map_err(|e| self.error(e))?;
+        if object.kind != gix::object::Kind::Blob {
+            return Ok(None);
+        }
+        Ok(Some(object.detach().data))
+    }
+
+    fn user_email(&self) -> Result<Option<String>, TopoError> {
+        let repo = self.repo.to_thread_local();
+        Ok(repo
+            .config_snapshot()
+            .string("user.email")
+            .map(|email| email.to_string())
+            .filter(|email| !email.is_empty()))
+    }
+
+    fn branch(&self) -> Result<Option<String>, TopoError> {
+        let repo = self.repo.to_thread_local();
+        let head = repo.head_name().map_err(|e| self.error(e))?;
+        Ok(head.map(|name| name.shorten().to_string()))
+    }
+}
+
+/// Convert a tree change to a [`FileChange`], skipping directories.
+fn file_change(change: ChangeDetached) -> Option<FileChange> {
+    if change.entry_mode().is_tree() {
+        return None;
+    }
+    let change = match change {
+        ChangeDetached::Addition { location, .. } => {
+            FileChange::new(ChangeStatus::Added, location.to_string())
+        }
+        ChangeDetached::Deletion { location, .. } => {
+            FileChange::new(ChangeStatus::Deleted, location.to_string())
+        }
+        ChangeDetached::Modification { location, .. } => {
+            FileChange::new(ChangeStatus::Modified, location.to_string())
+        }
+        ChangeDetached::Rewrite {
+            source_location,
+            location,
+            copy,
+            ..
+        } => {
+            let status = if copy {
+                ChangeStatus::Copied
+            } else {
+                ChangeStatus::Renamed
+            };
+            FileChange::moved(status, source_location.to_string(), location.to_string())
+        }
+    };
+    Some(change)
+}
+
+/// Lines added and removed by `change`, or `None` for binary files.
+fn line_counts(
+    repo: &gix::Repository,
+    change: &ChangeDetached,
+    cache: &mut gix::diff::blob::Platform,
+) -> Option<LineCounts> {
+    let counter = change
+        .attach(repo, repo)
+        .diff(cache)
+        .ok()?
+        .line_counts()
+        .ok()
+        .flatten();
+    // The cache only grows, and each blob is diffed once
+    cache.clear_resource_cache_keep_allocation();
+    counter.map(|counter| LineCounts {
+        added: counter.insertions,
+        removed: counter.removals,
+    })
+}
+
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn vcs_error(root: &Path, e: impl std::fmt::Display) -> TopoError {
+    TopoError::Vcs {
+        path: Some(root.to_path_buf()),
+        message: e.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::process::Command;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    fn repo_with_history(dir: &Path) {
+        git(dir, &["init", "-q"]);
+        git(dir, &["config", "user.email", "dev@example.com"]);
+        git(dir, &["config", "user.name", "Dev"]);
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(
+            dir.join("src/auth.rs"),
+            "pub fn check() -> bool {\n    true\n}\n",
+        )
+        .unwrap();
+        fs::write(dir.join("README.md"), "# demo\n").unwrap();
+        git(dir, &["add", "."]);
+        git(dir, &["commit", "-q", "-m", "init"]);
+        fs::write(dir.join("README.md"), "# demo\n\nMore.\n").unwrap();
+        git(dir, &["commit", "-q", "-am", "docs"]);
+        git(dir, &["mv", "src/auth.rs", "src/login.rs"]);
+        git(dir, &["rm", "-q", "README.md"]);
+        git(dir, &["commit", "-q", "-m", "move"]);
+    }
+
+    #[test]
+    fn log_matches_git_cli() {
+        let dir = tempfile::tempdir().unwrap();
+        repo_with_history(dir.path());
+
+        let options = LogOptions::new().since_days(90);
+        let gix = Gix::discover(dir.path()).unwrap().log(&options).unwrap();
+        let cli = GitCli::new(dir.path()).log(&options).unwrap();
+        assert_eq!(gix.len(), 3);
+        for (a, b) in gix.iter().zip(&cli) {
+            let mut a_changes = a.changes.clone();
+            let mut b_changes = b.changes.clone();
+            a_changes.sort_by(|x, y| x.path.cmp(&y.path));
+            b_changes.sort_by(|x, y| x.path.cmp(&y.path));
+            assert_eq!(a.id, b.id);
+            assert_eq!(a.author_email, b.author_email);
+            assert_eq!(a.timestamp, b.timestamp);
+            assert_eq!(a_changes, b_changes);
+        }
+        assert!(gix[0].changes.contains(&FileChange::moved(
+            ChangeStatus::Renamed,
+            "src/auth.rs",
+            "src/login.rs"
+        )));
+    }
+
+    #[test]
+    fn line_counts_match_git_cli() {
+        let dir = tempfile::tempdir().unwrap();
+        repo_with_history(dir.path());
+
+        let options = LogOptions::new().line_counts(true);
+        let gix = Gix::discover(dir.path()).unwrap().log(&options).unwrap();
+        let cli = GitCli::new(dir.path()).log(&options).unwrap();
+        for (a, b) in gix.iter().zip(&cli) {
+            let mut a_changes = a.changes.clone();
+            let mut b_changes = b.changes.clone();
+            a_changes.sort_by(|x, y| x.path.cmp(&y.path));
+            b_changes.sort_by(|x, y| x.path.cmp(&y.path));
+            assert_eq!(a_changes, b_changes);
+        }
+        let docs = &gix[1].changes[0];
+        assert_eq!(docs.path, "README.md");
+        assert_eq!(
+            docs.lines,
+            Some(LineCounts {
+                added: 2,
+                removed: 0
+            })
+        );
+
+        let since = LogOptions::new().since_commit(&gix[2].id);
+        let newer = Gix::discover(dir.path()).unwrap().log(&since).unwrap();
+        assert_eq!(newer.len(), 2);
+        assert_eq!(newer[1].id, gix[1].id);
+    }
+
+    #[test]
+    fn log_respects_max_commits() {
+        let dir = tempfile::tempdir().unwrap();
+        repo_with_history(dir.path());
+        let gix = Gix::discover(dir.path()).unwrap();
+        assert_eq!(gix.log(&LogOptions::new().max_commits(2)).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn reads_objects_at_revisions() {
+        let dir = tempfile::tempdir().unwrap();
+        repo_with_history(dir.path());
+        let gix = Gix::discover(dir.path()).unwrap();
+        assert_eq!(
+            gix.read_object("HEAD~1", "README.md").unwrap().as_deref(),
+            Some(b"# demo\n\nMore.\n".as_slice())
+        );
+        assert!(gix.read_object("HEAD", "README.md").unwrap().is_none());
+        assert!(gix.read_object("HEAD", "src").unwrap().is_none());
+    }
+
+    #[test]
+    fn reads_user_email_from_config() {
+        let dir = tempfile::tempdir().unwrap();
+        repo_with_history(dir.path());
+        let gix = Gix::discover(dir.path()).unwrap();
+        assert_eq!(
+            gix.user_email().unwrap(),
+            GitCli::new(dir.path()).user_email().unwrap()
+        );
+        assert_eq!(
+            gix.user_email().unwrap().as_deref(),
+            Some("dev@example.com")
+        );
+    }
+
+    #[test]
+    fn reads_current_branch() {
+        let dir = tempfile::tempdir().unwrap();
+        repo_with_history(dir.path());
+        git(dir.path(), &["checkout", "-q", "-b", "feature/auth"]);
+        let gix = Gix::discover(dir.path()).unwrap();
+        assert_eq!(gix.branch().unwrap().as_deref(), Some("feature/auth"));
+        assert_eq!(
+            gix.branch().unwrap(),
+            GitCli::new(dir.path()).branch().unwrap()
+        );
+
+        git(dir.path(), &["checkout", "-q", "--detach"]);
+        assert!(
+            Gix::discover(dir.path())
+                .unwrap()
+                .branch()
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn empty_repo_log_is_a_vcs_error() {
+        let dir = tempfile::tempdir().unwrap();
+        git(dir.path(), &["init", "-q"]);
+        let err = Gix::discover(dir.path())
+            .unwrap()
+            .log(&LogOptions::new())
+            .unwrap_err();
+        assert!(matches!(err, TopoError::Vcs { .. }));
+    }
+}
@@ -0,0 +1,64 @@
+//! Version control abstraction for history-derived signals and diff modes.
+
+mod git;
+mod gix;
+mod vcs;
+
+pub use git::GitCli;
+pub use gix::Gix;
+pub use vcs::{BlameLine, ChangeStatus, Commit, FileChange, LineCounts, LogOptions, Vcs};
+
+use std::path::Path;
+
+/// Detect the version control system managing `root`.
+///
+/// Prefers the in-process [`Gix`] backend, falling back to [`GitCli`] when
+/// a `.git` directory or file (worktrees, submodules) exists in `root` or
+/// an ancestor but gix can't open it. Returns `None` outside a repository.
+pub fn detect(root: &Path) -> Option<Box<dyn Vcs>> {
+    if let Ok(gix) = Gix::discover(root) {
+        return Some(Box::new(gix));
+    }
+    root.ancestors()
+        .any(|dir| dir.join(".git").exists())
+        .then(|| Box::new(GitCli::new(root)) as Box<dyn Vcs>)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn detects_git_from_subdirectory() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".git")).unwrap();
+        fs::create_dir_all(dir.path().join("src/nested")).unwrap();
+
+        let vcs = detect(&dir.path().join("src/nested")).unwrap();
+        assert_eq!(vcs.name(), "git");
+        assert_eq!(vcs.root(), dir.path().join("src/nested"));
+    }
+
+    #[test]
+    fn no_vcs_outside_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        // tempdir may live under a checkout; only assert when it doesn't
+        if !dir.path().ancestors().any(|d| d.join(".git").exists()) {
+            assert!(detect(dir.path()).is_none());
+        }
+    }
+
+    #[test]
+    fn log_options_builder() {
+        let options = LogOptions::new()
+            .since_days(30)
+            .max_commits(10)
+            .since_commit("abc123")
+            .line_counts(true);
+        assert_eq!(options.since_days, Some(30));
+        assert_eq!(options.max_commits, Some(10));
+        assert_eq!(options.since_commit.as_deref(), Some("abc123"));
+        assert!(options.line_counts);
+    }
+}
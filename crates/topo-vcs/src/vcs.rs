@@ -0,0 +1,154 @@
+use std::path::Path;
+use topo_core::TopoError;
+
+/// A version control backend.
+///
+/// All history-derived signals and diff modes go through this trait, so a
+/// backend only has to answer these questions once. Paths are repo-relative
+/// with `/` separators.
+pub trait Vcs: Send + Sync {
+    /// Short backend name, e.g. `"git"`.
+    fn name(&self) -> &'static str;
+
+    /// Working tree root.
+    fn root(&self) -> &Path;
+
+    /// Commits with their file changes, newest first.
+    ///
+    /// Renames are detected, so a moved file shows up as
+    /// [`ChangeStatus::Renamed`] with its `old_path`.
+    fn log(&self, options: &LogOptions) -> Result<Vec<Commit>, TopoError>;
+
+    /// Files changed between `base` and the working tree.
+    fn diff(&self, base: &str) -> Result<Vec<FileChange>, TopoError>;
+
+    /// Uncommitted changes in the working tree, including untracked files.
+    fn status(&self) -> Result<Vec<FileChange>, TopoError>;
+
+    /// Last commit to touch each line of `path` in the working tree.
+    fn blame(&self, path: &str) -> Result<Vec<BlameLine>, TopoError>;
+
+    /// Contents of `path` at revision `rev`, or `None` if it doesn't exist there.
+    fn read_object(&self, rev: &str, path: &str) -> Result<Option<Vec<u8>>, TopoError>;
+
+    /// Configured author email of the local user, if set.
+    fn user_email(&self) -> Result<Option<String>, TopoError>;
+
+    /// Short name of the checked-out branch, or `None` on a detached HEAD.
+    fn branch(&self) -> Result<Option<String>, TopoError>;
+
+    /// Tracked files a sparse checkout leaves out of the working tree, or
+    /// `None` if the working tree isn't sparse. Their contents are still
+    /// readable with [`Vcs::read_object`].
+    fn sparse_paths(&self) -> Result<Option<Vec<String>>, TopoError>;
+}
+
+/// Filters for [`Vcs::log`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogOptions {
+    /// Only commits from the last N days.
+    pub since_days: Option<u32>,
+    /// At most this many commits.
+    pub max_commits: Option<usize>,
+    /// Only commits after this one, i.e. not reachable from it.
+    pub since_commit: Option<String>,
+    /// Count added and removed lines, see [`FileChange::lines`].
+    pub line_counts: bool,
+}
+
+impl LogOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn since_days(mut self, days: u32) -> Self {
+        self.since_days = Some(days);
+        self
+    }
+
+    pub fn max_commits(mut self, max: usize) -> Self {
+        self.max_commits = Some(max);
+        self
+    }
+
+    pub fn since_commit(mut self, id: impl Into<String>) -> Self {
+        self.since_commit = Some(id.into());
+        self
+    }
+
+    pub fn line_counts(mut self, enabled: bool) -> Self {
+        self.line_counts = enabled;
+        self
+    }
+}
+
+/// One commit from [`Vcs::log`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Commit {
+    pub id: String,
+    pub author_email: String,
+    /// Author time, seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub changes: Vec<FileChange>,
+}
+
+/// How a file changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChangeStatus {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+    Copied,
+    Untracked,
+}
+
+/// A changed file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChange {
+    pub status: ChangeStatus,
+    pub path: String,
+    /// Source path for renames and copies.
+    pub old_path: Option<String>,
+    /// Lines added and removed, for text files in a log read with
+    /// [`LogOptions::line_counts`].
+    pub lines: Option<LineCounts>,
+}
+
+/// Lines added and removed by a [`FileChange`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct LineCounts {
+    pub added: u32,
+    pub removed: u32,
+}
+
+impl FileChange {
+    pub fn new(status: ChangeStatus, path: impl Into<String>) -> Self {
+        Self {
+            status,
+            path: path.into(),
+            old_path: None,
+            lines: None,
+        }
+    }
+
+    pub fn moved(status: ChangeStatus, from: impl Into<String>, to: impl Into<String>) -> Self {
+        Self {
+            status,
+            path: to.into(),
+            old_path: Some(from.into()),
+            lines: None,
+        }
+    }
+}
+
+/// Attribution for one line from [`Vcs::blame`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlameLine {
+    /// 1-based line number in the working tree file.
+    pub line: usize,
+    pub commit: String,
+    pub author_email: String,
+    /// Author time, seconds since the Unix epoch.
+    pub timestamp: u64,
+}